@@ -28,7 +28,13 @@ const sidebars: SidebarsConfig = {
         'configuration/deployments',
         'configuration/statefulsets',
         'configuration/daemonsets',
+        'configuration/cronjobs',
         'configuration/helmreleases',
+        'configuration/argocd',
+        'configuration/kustomizations',
+        'configuration/flux-image-policies',
+        'configuration/plain-helm',
+        'configuration/gitops',
         'configuration/event-sources',
         'configuration/approval-workflow',
         'configuration/notifications',
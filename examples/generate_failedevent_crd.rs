@@ -0,0 +1,6 @@
+use headwind::models::FailedEvent;
+use kube::CustomResourceExt;
+
+fn main() {
+    print!("{}", serde_yaml::to_string(&FailedEvent::crd()).unwrap());
+}
@@ -0,0 +1,9 @@
+use headwind::models::HeadwindSettings;
+use kube::CustomResourceExt;
+
+fn main() {
+    print!(
+        "{}",
+        serde_yaml::to_string(&HeadwindSettings::crd()).unwrap()
+    );
+}
@@ -0,0 +1,6 @@
+use headwind::models::UpdateAudit;
+use kube::CustomResourceExt;
+
+fn main() {
+    print!("{}", serde_yaml::to_string(&UpdateAudit::crd()).unwrap());
+}
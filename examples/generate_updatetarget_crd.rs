@@ -0,0 +1,6 @@
+use headwind::models::UpdateTarget;
+use kube::CustomResourceExt;
+
+fn main() {
+    print!("{}", serde_yaml::to_string(&UpdateTarget::crd()).unwrap());
+}
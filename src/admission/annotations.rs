@@ -0,0 +1,487 @@
+//! Validating admission webhook for `headwind.sh/*` annotations on workloads.
+//!
+//! The API server sends Deployments, StatefulSets, DaemonSets, CronJobs and HelmReleases to
+//! `POST /admission/workloads` when they are created or updated. Annotation values Headwind
+//! can't use (unknown policies, malformed intervals, patterns that don't compile) are denied
+//! instead of being skipped with a debug log at reconcile time. Unknown `headwind.sh/` keys
+//! only produce a warning, as they may come from a newer Headwind.
+//!
+//! On updates only annotations that were added or changed are denied, so a workload that
+//! already carries an invalid value can still be scaled or rolled out (including by
+//! Headwind); its problems are returned as warnings.
+
+use crate::metrics::ANNOTATION_REVIEWS;
+use crate::models::policy::{
+    CONTAINER_POLICY_KEYS, annotations, container_annotations, parse_container_key,
+    parse_container_overrides, parse_duration_secs,
+};
+use crate::models::{EventSource, UpdateMode, UpdatePolicy};
+use crate::ordering::parse_dependencies;
+use crate::platforms::PlatformCheck;
+use crate::policy::version_ceiling;
+use crate::rollback::HISTORY_ANNOTATION;
+use crate::schedule::Schedule;
+use axum::Json;
+use kube::core::DynamicObject;
+use kube::core::admission::{AdmissionRequest, AdmissionResponse, AdmissionReview, Operation};
+use regex::Regex;
+use schemars::JsonSchema;
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+/// Annotation keys Headwind reads or writes
+pub const KNOWN_ANNOTATIONS: &[&str] = &[
+    annotations::POLICY,
+    annotations::PATTERN,
+    annotations::REQUIRE_APPROVAL,
+    annotations::MIN_UPDATE_INTERVAL,
+    annotations::IMAGES,
+    annotations::IGNORE_PATTERN,
+    annotations::ALLOW_PRERELEASE,
+    annotations::PRERELEASE_CHANNEL,
+    annotations::PIN_DIGEST,
+    annotations::CHANNEL_TAG,
+    annotations::UPDATE_MODE,
+    annotations::SCHEDULE,
+    annotations::APPROVAL_TTL,
+    annotations::REQUIRED_APPROVALS,
+    annotations::DRY_RUN,
+    annotations::UPDATE_GROUP,
+    annotations::ROLLOUT_AFTER,
+    annotations::MIN_TAG_AGE,
+    annotations::MAX_VERSION,
+    annotations::LAST_UPDATE,
+    annotations::EVENT_SOURCE,
+    annotations::POLLING_INTERVAL,
+    annotations::AUTO_ROLLBACK,
+    annotations::ROLLBACK_TIMEOUT,
+    annotations::HEALTH_CHECK_RETRIES,
+    annotations::ERROR_RATE_QUERY,
+    annotations::MAX_ERROR_RATE,
+    annotations::LATENCY_QUERY,
+    annotations::MAX_LATENCY,
+    annotations::CANARY,
+    annotations::CANARY_PERCENT,
+    annotations::CANARY_TIMEOUT,
+    annotations::ARGOCD_APPLICATION,
+    annotations::ARGOCD_IMAGE_PARAMETER,
+    annotations::FLUX_KUSTOMIZATION,
+    annotations::GITOPS_REPOSITORY,
+    annotations::GITOPS_PATH,
+    annotations::GITOPS_FORMAT,
+    annotations::GITOPS_BRANCH,
+    annotations::GITOPS_VALUE_PATH,
+    annotations::GITOPS_PULL_REQUEST,
+    annotations::GITOPS_PROVIDER,
+    annotations::PRE_UPDATE_HOOK,
+    annotations::POST_UPDATE_HOOK,
+    annotations::MAX_CRITICAL_CVES,
+    annotations::PLATFORM_CHECK,
+    annotations::NOTIFY_ROUTE,
+    annotations::NOTIFY_CHANNEL,
+    annotations::NOTIFY_DETECTED,
+    annotations::VALUES_IMAGE_PATHS,
+    annotations::BUILD_METADATA,
+    annotations::CORRELATION_ID,
+    annotations::TRACEPARENT,
+    HISTORY_ANNOTATION,
+    "headwind.sh/helm-release",
+];
+
+/// How serious an annotation problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Headwind can't use the value
+    Error,
+    /// The value is accepted but probably not what was meant
+    Warning,
+}
+
+/// A problem with one annotation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Finding {
+    /// Annotation key
+    pub annotation: String,
+    pub severity: Severity,
+    /// What is wrong with it
+    pub message: String,
+}
+
+impl Finding {
+    pub(crate) fn error(annotation: &str, message: impl Into<String>) -> Self {
+        Self {
+            annotation: annotation.to_string(),
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn warning(annotation: &str, message: impl Into<String>) -> Self {
+        Self {
+            annotation: annotation.to_string(),
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.annotation, self.message)
+    }
+}
+
+/// Check the `headwind.sh/*` annotations of a resource in `namespace`. Empty when they are
+/// all valid.
+pub fn validate_annotations(
+    annotations: &BTreeMap<String, String>,
+    namespace: &str,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for (key, value) in annotations {
+        if !key.starts_with("headwind.sh/") {
+            continue;
+        }
+        let value = value.as_str();
+        // Per-container overrides are checked like the setting they override
+        let setting = match parse_container_key(key) {
+            Some((_, setting)) if !CONTAINER_POLICY_KEYS.contains(&setting.as_str()) => {
+                findings.push(Finding::warning(
+                    key,
+                    "not a per-container setting, Headwind ignores it",
+                ));
+                continue;
+            },
+            Some((_, setting)) => setting,
+            None => key.clone(),
+        };
+        let result = match setting.as_str() {
+            annotations::POLICY => parse_as::<UpdatePolicy>(value),
+            annotations::EVENT_SOURCE => parse_as::<EventSource>(value),
+            annotations::UPDATE_MODE => parse_as::<UpdateMode>(value),
+            annotations::PLATFORM_CHECK => parse_as::<PlatformCheck>(value),
+            annotations::SCHEDULE => parse_as::<Schedule>(value),
+            annotations::REQUIRE_APPROVAL
+            | annotations::ALLOW_PRERELEASE
+            | annotations::PIN_DIGEST
+            | annotations::DRY_RUN
+            | annotations::NOTIFY_DETECTED
+            | annotations::AUTO_ROLLBACK
+            | annotations::CANARY
+            | annotations::GITOPS_PULL_REQUEST => parse_as::<bool>(value),
+            annotations::MIN_UPDATE_INTERVAL
+            | annotations::POLLING_INTERVAL
+            | annotations::ROLLBACK_TIMEOUT
+            | annotations::CANARY_TIMEOUT
+            | annotations::HEALTH_CHECK_RETRIES => seconds(value),
+            annotations::REQUIRED_APPROVALS | annotations::MAX_CRITICAL_CVES => {
+                parse_as::<u32>(value)
+            },
+            annotations::MAX_ERROR_RATE | annotations::MAX_LATENCY => parse_as::<f64>(value),
+            annotations::APPROVAL_TTL | annotations::MIN_TAG_AGE => {
+                parse_duration_secs(value).map(|_| ()).ok_or_else(|| {
+                    format!(
+                        "invalid duration {:?}, expected seconds or a number with s, m, h or d",
+                        value
+                    )
+                })
+            },
+            annotations::MAX_VERSION => version_ceiling(value)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            annotations::CANARY_PERCENT => canary_percent(value),
+            annotations::ROLLOUT_AFTER => parse_dependencies(annotations, namespace)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            annotations::GITOPS_FORMAT => one_of(value, &["manifest", "yaml", "kustomize", "helm"]),
+            annotations::PRE_UPDATE_HOOK | annotations::POST_UPDATE_HOOK => http_url(value),
+            _ if !KNOWN_ANNOTATIONS.contains(&key.as_str()) => {
+                findings.push(Finding::warning(
+                    key,
+                    "unknown annotation, Headwind ignores it",
+                ));
+                continue;
+            },
+            _ => continue,
+        };
+        if let Err(message) = result {
+            findings.push(Finding::error(key, message));
+        }
+    }
+
+    findings.extend(check_pattern(annotations));
+    for container in parse_container_overrides(annotations).into_keys() {
+        if let Some(mut finding) = check_pattern(&container_annotations(annotations, &container)) {
+            finding.annotation = format!("{}{}.pattern", annotations::CONTAINER_PREFIX, container);
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+/// The pattern of the glob, regex and range policies
+fn check_pattern(annotations: &BTreeMap<String, String>) -> Option<Finding> {
+    let policy = annotations.get(annotations::POLICY)?.parse().ok()?;
+    let pattern = annotations
+        .get(annotations::PATTERN)
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty());
+
+    match (policy, pattern) {
+        (UpdatePolicy::Glob | UpdatePolicy::Regex | UpdatePolicy::Range, None) => {
+            Some(Finding::error(
+                annotations::PATTERN,
+                format!("required by the {} policy", policy),
+            ))
+        },
+        (UpdatePolicy::Regex, Some(pattern)) => Regex::new(pattern)
+            .err()
+            .map(|e| Finding::error(annotations::PATTERN, format!("invalid regex: {}", e))),
+        (UpdatePolicy::Range, Some(pattern)) => VersionReq::parse(pattern).err().map(|e| {
+            Finding::error(
+                annotations::PATTERN,
+                format!("invalid version range: {}", e),
+            )
+        }),
+        (UpdatePolicy::Glob, Some(_)) => None,
+        (_, Some(_)) => Some(Finding::warning(
+            annotations::PATTERN,
+            format!("ignored by the {} policy", policy),
+        )),
+        (_, None) => None,
+    }
+}
+
+fn parse_as<T>(value: &str) -> Result<(), String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse::<T>()
+        .map(|_| ())
+        .map_err(|e| format!("invalid value {:?}: {}", value, e))
+}
+
+fn seconds(value: &str) -> Result<(), String> {
+    value
+        .parse::<u64>()
+        .map(|_| ())
+        .map_err(|_| format!("invalid value {:?}, expected a whole number", value))
+}
+
+fn one_of(value: &str, allowed: &[&str]) -> Result<(), String> {
+    if allowed.contains(&value.trim().to_lowercase().as_str()) {
+        return Ok(());
+    }
+    Err(format!(
+        "invalid value {:?}, expected one of {}",
+        value,
+        allowed.join(", ")
+    ))
+}
+
+fn canary_percent(value: &str) -> Result<(), String> {
+    match value.trim().trim_end_matches('%').parse::<u32>() {
+        Ok(percent) if (1..=100).contains(&percent) => Ok(()),
+        _ => Err(format!(
+            "invalid value {:?}, expected a percentage from 1 to 100",
+            value
+        )),
+    }
+}
+
+fn http_url(value: &str) -> Result<(), String> {
+    match reqwest::Url::parse(value.trim()) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+        _ => Err(format!(
+            "invalid value {:?}, expected an http(s) URL",
+            value
+        )),
+    }
+}
+
+/// Answer an AdmissionReview of a workload or HelmRelease
+pub async fn review_workload(
+    Json(review): Json<AdmissionReview<DynamicObject>>,
+) -> Json<AdmissionReview<DynamicObject>> {
+    let request: AdmissionRequest<DynamicObject> = match review.try_into() {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid workload admission review: {}", e);
+            return Json(AdmissionResponse::invalid(e.to_string()).into_review());
+        },
+    };
+
+    let mut response = AdmissionResponse::from(&request);
+    let Some(object) = &request.object else {
+        return Json(response.into_review());
+    };
+    let kind = request.kind.kind.as_str();
+    let empty = BTreeMap::new();
+    let new = object.metadata.annotations.as_ref().unwrap_or(&empty);
+    let old = request
+        .old_object
+        .as_ref()
+        .and_then(|old| old.metadata.annotations.as_ref());
+    let namespace = request.namespace.as_deref().unwrap_or_default();
+
+    let (denied, warnings): (Vec<Finding>, Vec<Finding>) = validate_annotations(new, namespace)
+        .into_iter()
+        .partition(|finding| {
+            finding.severity == Severity::Error
+                && (request.operation != Operation::Update
+                    || old.and_then(|old| old.get(&finding.annotation))
+                        != new.get(&finding.annotation))
+        });
+
+    if !warnings.is_empty() {
+        response.warnings = Some(warnings.iter().map(ToString::to_string).collect());
+    }
+    if denied.is_empty() {
+        let result = if warnings.is_empty() {
+            "allowed"
+        } else {
+            "warned"
+        };
+        ANNOTATION_REVIEWS.with_label_values(&[kind, result]).inc();
+        return Json(response.into_review());
+    }
+
+    ANNOTATION_REVIEWS
+        .with_label_values(&[kind, "denied"])
+        .inc();
+    let message = denied
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ");
+    info!(
+        "Denied {} {}/{}: {}",
+        kind, namespace, request.name, message
+    );
+    Json(response.deny(message).into_review())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotations(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn errors(entries: &[(&str, &str)]) -> Vec<String> {
+        validate_annotations(&annotations(entries), "default")
+            .into_iter()
+            .filter(|f| f.severity == Severity::Error)
+            .map(|f| f.annotation)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_annotations() {
+        let findings = validate_annotations(
+            &annotations(&[
+                (annotations::POLICY, "regex"),
+                (annotations::PATTERN, r"^v\d+\.\d+\.\d+$"),
+                (annotations::REQUIRE_APPROVAL, "false"),
+                (annotations::MIN_UPDATE_INTERVAL, "600"),
+                (annotations::APPROVAL_TTL, "7d"),
+                (annotations::EVENT_SOURCE, "both"),
+                (annotations::SCHEDULE, "Mon-Fri 02:00-05:00"),
+                (annotations::CANARY_PERCENT, "25%"),
+                (annotations::ROLLOUT_AFTER, "StatefulSet/db/postgres"),
+                (annotations::PRE_UPDATE_HOOK, "https://ci.example.com/hook"),
+                ("app.kubernetes.io/name", "api"),
+            ]),
+            "default",
+        );
+        assert_eq!(findings, Vec::new());
+    }
+
+    #[test]
+    fn test_invalid_annotations() {
+        assert_eq!(
+            errors(&[(annotations::POLICY, "minorr")]),
+            [annotations::POLICY]
+        );
+        assert_eq!(
+            errors(&[
+                (annotations::POLICY, "minor"),
+                (annotations::MIN_UPDATE_INTERVAL, "5m"),
+                (annotations::REQUIRE_APPROVAL, "yes"),
+                (annotations::EVENT_SOURCE, "push"),
+            ]),
+            [
+                annotations::EVENT_SOURCE,
+                annotations::MIN_UPDATE_INTERVAL,
+                annotations::REQUIRE_APPROVAL,
+            ]
+        );
+        assert_eq!(
+            errors(&[
+                (annotations::POLICY, "regex"),
+                (annotations::PATTERN, "^v(")
+            ]),
+            [annotations::PATTERN]
+        );
+        assert_eq!(
+            errors(&[(annotations::POLICY, "range")]),
+            [annotations::PATTERN]
+        );
+        assert_eq!(
+            errors(&[(annotations::PRE_UPDATE_HOOK, "ftp://example.com")]),
+            [annotations::PRE_UPDATE_HOOK]
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let findings = validate_annotations(
+            &annotations(&[
+                (annotations::POLICY, "minor"),
+                (annotations::PATTERN, "v1.*"),
+                ("headwind.sh/polcy", "minor"),
+            ]),
+            "default",
+        );
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|f| f.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn test_container_overrides() {
+        assert!(
+            errors(&[
+                (annotations::POLICY, "minor"),
+                ("headwind.sh/container.nginx.policy", "patch"),
+                ("headwind.sh/container.sidecar.policy", "none"),
+            ])
+            .is_empty()
+        );
+        assert_eq!(
+            errors(&[("headwind.sh/container.nginx.policy", "sometimes")]),
+            vec!["headwind.sh/container.nginx.policy"]
+        );
+        // A container switching to the regex policy needs a pattern, its own or the workload's
+        assert_eq!(
+            errors(&[("headwind.sh/container.nginx.policy", "regex")]),
+            vec!["headwind.sh/container.nginx.pattern"]
+        );
+
+        let findings = validate_annotations(
+            &annotations(&[("headwind.sh/container.nginx.dry-run", "true")]),
+            "default",
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+    }
+}
@@ -0,0 +1,118 @@
+//! Conversion webhook between the UpdateRequest API versions.
+//!
+//! The API server sends a `ConversionReview` to `POST /convert/updaterequests` (webhook server)
+//! whenever an UpdateRequest is read or written in a version other than the stored one
+//! (v1alpha1). Like the admission webhook it is only called over HTTPS.
+
+use crate::models::{crd, v1beta1};
+use anyhow::{Result, anyhow};
+use axum::Json;
+use kube::core::Status;
+use kube::core::conversion::{ConversionRequest, ConversionResponse, ConversionReview};
+use serde_json::Value;
+use tracing::warn;
+
+const V1ALPHA1: &str = "headwind.sh/v1alpha1";
+const V1BETA1: &str = "headwind.sh/v1beta1";
+
+/// Answer an UpdateRequest ConversionReview
+pub async fn convert_update_requests(
+    Json(review): Json<ConversionReview>,
+) -> Json<ConversionReview> {
+    let request = match ConversionRequest::from_review(review) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid UpdateRequest conversion review: {}", e);
+            return Json(
+                ConversionResponse::invalid(Status::failure(&e.to_string(), "InvalidRequest"))
+                    .into_review(),
+            );
+        },
+    };
+
+    let desired = request.desired_api_version.clone();
+    let converted: Result<Vec<Value>> = request
+        .objects
+        .iter()
+        .cloned()
+        .map(|object| convert(object, &desired))
+        .collect();
+
+    let response = ConversionResponse::for_request(request);
+    match converted {
+        Ok(objects) => Json(response.success(objects).into_review()),
+        Err(e) => {
+            warn!("Failed to convert UpdateRequests to {}: {}", desired, e);
+            Json(
+                response
+                    .failure(Status::failure(&e.to_string(), "ConversionFailed"))
+                    .into_review(),
+            )
+        },
+    }
+}
+
+/// Convert one UpdateRequest to `desired_api_version`
+pub fn convert(object: Value, desired_api_version: &str) -> Result<Value> {
+    let api_version = object["apiVersion"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    match (api_version.as_str(), desired_api_version) {
+        (from, to) if from == to => Ok(object),
+        (V1ALPHA1, V1BETA1) => {
+            let old: crd::UpdateRequest = serde_json::from_value(object)?;
+            Ok(serde_json::to_value(v1beta1::UpdateRequest::from(old))?)
+        },
+        (V1BETA1, V1ALPHA1) => {
+            let new: v1beta1::UpdateRequest = serde_json::from_value(object)?;
+            Ok(serde_json::to_value(crd::UpdateRequest::from(new))?)
+        },
+        (from, to) => Err(anyhow!(
+            "cannot convert UpdateRequest from {} to {}",
+            from,
+            to
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let old = serde_json::json!({
+            "apiVersion": V1ALPHA1,
+            "kind": "UpdateRequest",
+            "metadata": { "name": "web-1-26", "namespace": "default" },
+            "spec": {
+                "targetRef": {
+                    "apiVersion": "apps/v1",
+                    "kind": "Deployment",
+                    "name": "web",
+                    "namespace": "default"
+                },
+                "updateType": "image",
+                "containerName": "app",
+                "currentImage": "ghcr.io/org/web:1.25.0",
+                "newImage": "ghcr.io/org/web:1.26.0",
+                "policy": "minor",
+                "requireApproval": true
+            },
+            "status": { "phase": "Pending" }
+        });
+
+        let new = convert(old.clone(), V1BETA1).unwrap();
+        assert_eq!(new["apiVersion"], V1BETA1);
+        assert_eq!(new["spec"]["target"]["name"], "ghcr.io/org/web");
+        assert_eq!(new["spec"]["target"]["version"], "1.26.0");
+        assert!(new["spec"].get("newImage").is_none());
+
+        let back = convert(new, V1ALPHA1).unwrap();
+        assert_eq!(back, old);
+
+        assert_eq!(convert(old.clone(), V1ALPHA1).unwrap(), old);
+        assert!(convert(old, "headwind.sh/v2").is_err());
+    }
+}
@@ -0,0 +1,355 @@
+//! Validating admission webhook for UpdateRequests.
+//!
+//! The API server sends an `AdmissionReview` to `POST /admission/updaterequests` (webhook
+//! server) for every UpdateRequest that is created. Specs Headwind could not apply later, such
+//! as unsupported target kinds, malformed image references or chart versions that are not
+//! semver, are denied with the list of problems instead of failing at execution time with a
+//! vague error. The API server only calls webhooks over HTTPS, so TLS has to be enabled (see
+//! [`crate::server::tls`]).
+//!
+//! The conversion webhook between the UpdateRequest API versions lives in [`conversion`], the
+//! validation of `headwind.sh/*` annotations on workloads in [`annotations`].
+
+pub mod annotations;
+pub mod conversion;
+
+use crate::controller::workload::split_image;
+use crate::metrics::ADMISSION_REVIEWS;
+use crate::models::crd::{UpdateRequestSpec, UpdateType};
+use axum::Json;
+use kube::core::DynamicObject;
+use kube::core::admission::{AdmissionRequest, AdmissionResponse, AdmissionReview};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tracing::{info, warn};
+
+/// Target kinds the approval API knows how to update
+pub const SUPPORTED_KINDS: &[&str] = &[
+    "Deployment",
+    "StatefulSet",
+    "DaemonSet",
+    "CronJob",
+    "HelmRelease",
+    "Application",
+    "Kustomization",
+    "Release",
+];
+
+/// Kinds whose image updates patch a container of the pod template
+const WORKLOAD_KINDS: &[&str] = crate::controller::tracked::KINDS;
+
+/// Kinds that can receive chart version updates
+const CHART_KINDS: &[&str] = &["HelmRelease", "Application", "Release"];
+
+/// Path component of an image repository (distribution reference grammar)
+static PATH_COMPONENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*$").unwrap());
+
+/// Registry host with optional port
+static REGISTRY_HOST: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?)*(?::[0-9]+)?$")
+        .unwrap()
+});
+
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[\w][\w.-]{0,127}$").unwrap());
+
+static DIGEST: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-z0-9]+(?:[.+_-][a-z0-9]+)*:[a-fA-F0-9]{32,}$").unwrap());
+
+/// Answer an UpdateRequest AdmissionReview
+pub async fn review_update_request(
+    Json(review): Json<AdmissionReview<DynamicObject>>,
+) -> Json<AdmissionReview<DynamicObject>> {
+    let request: AdmissionRequest<DynamicObject> = match review.try_into() {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid UpdateRequest admission review: {}", e);
+            return Json(AdmissionResponse::invalid(e.to_string()).into_review());
+        },
+    };
+
+    let response = AdmissionResponse::from(&request);
+    let Some(object) = &request.object else {
+        return Json(response.into_review());
+    };
+
+    let problems = match serde_json::from_value::<UpdateRequestSpec>(object.data["spec"].clone()) {
+        Ok(spec) => validate_spec(&spec),
+        Err(e) => vec![format!("invalid spec: {}", e)],
+    };
+
+    if problems.is_empty() {
+        ADMISSION_REVIEWS.with_label_values(&["allowed"]).inc();
+        return Json(response.into_review());
+    }
+
+    ADMISSION_REVIEWS.with_label_values(&["denied"]).inc();
+    let message = problems.join("; ");
+    info!(
+        "Denied UpdateRequest {}/{}: {}",
+        request.namespace.as_deref().unwrap_or_default(),
+        request.name,
+        message
+    );
+    Json(response.deny(message).into_review())
+}
+
+/// Problems that would keep an UpdateRequest from being applied. Empty for valid specs.
+pub fn validate_spec(spec: &UpdateRequestSpec) -> Vec<String> {
+    let mut problems = Vec::new();
+    let target = &spec.target_ref;
+    let kind = target.kind.as_str();
+
+    for (field, value) in [
+        ("targetRef.apiVersion", &target.api_version),
+        ("targetRef.name", &target.name),
+        ("targetRef.namespace", &target.namespace),
+    ] {
+        if value.trim().is_empty() {
+            problems.push(format!("{} must not be empty", field));
+        }
+    }
+
+    if !SUPPORTED_KINDS.contains(&kind) {
+        problems.push(format!(
+            "targetRef.kind {:?} is not supported (expected one of {})",
+            target.kind,
+            SUPPORTED_KINDS.join(", ")
+        ));
+    }
+
+    match spec.update_type {
+        UpdateType::Image => {
+            for (field, image) in [
+                ("currentImage", &spec.current_image),
+                ("newImage", &spec.new_image),
+            ] {
+                if let Err(e) = validate_image_reference(image) {
+                    problems.push(format!("{} {:?}: {}", field, image, e));
+                }
+            }
+
+            if WORKLOAD_KINDS.contains(&kind)
+                && spec.git_write_back.is_none()
+                && spec.container_name.as_deref().is_none_or(str::is_empty)
+            {
+                problems.push(format!("containerName is required for {} updates", kind));
+            }
+            if kind == "HelmRelease" && spec.image_parameter.is_none() {
+                problems.push(
+                    "imageParameter (values path of the image) is required for HelmRelease image updates"
+                        .to_string(),
+                );
+            }
+        },
+        UpdateType::HelmChart => {
+            if SUPPORTED_KINDS.contains(&kind) && !CHART_KINDS.contains(&kind) {
+                problems.push(format!("{} targets cannot receive chart updates", kind));
+            }
+            for (field, chart) in [
+                ("currentImage", &spec.current_image),
+                ("newImage", &spec.new_image),
+            ] {
+                if let Err(e) = validate_chart_version(chart) {
+                    problems.push(format!("{} {:?}: {}", field, chart, e));
+                }
+            }
+        },
+    }
+
+    for (i, container) in spec.grouped_containers.iter().enumerate() {
+        if container.container_name.trim().is_empty() {
+            problems.push(format!(
+                "groupedContainers[{}].containerName must not be empty",
+                i
+            ));
+        }
+        if let Err(e) = validate_image_reference(&container.new_image) {
+            problems.push(format!(
+                "groupedContainers[{}].newImage {:?}: {}",
+                i, container.new_image, e
+            ));
+        }
+    }
+
+    if spec.required_approvals == Some(0) {
+        problems.push("requiredApprovals must be at least 1".to_string());
+    }
+
+    problems
+}
+
+/// Check an image reference: `[registry/]repository(:tag|@digest|:tag@digest)`
+fn validate_image_reference(image: &str) -> Result<(), String> {
+    if image.is_empty() {
+        return Err("image must not be empty".to_string());
+    }
+    if image.chars().any(char::is_whitespace) {
+        return Err("image must not contain whitespace".to_string());
+    }
+
+    let (reference, digest) = match image.split_once('@') {
+        Some((reference, digest)) => (reference, Some(digest)),
+        None => (image, None),
+    };
+    if let Some(digest) = digest
+        && !DIGEST.is_match(digest)
+    {
+        return Err(format!("invalid digest {:?}", digest));
+    }
+
+    let repository = match split_image(reference) {
+        Some((repository, tag)) => {
+            if !TAG.is_match(tag) {
+                return Err(format!("invalid tag {:?}", tag));
+            }
+            repository
+        },
+        None if digest.is_some() => reference,
+        None => return Err("image has neither a tag nor a digest".to_string()),
+    };
+
+    let mut components: Vec<&str> = repository.split('/').collect();
+    if components.len() > 1 {
+        let first = components[0];
+        if first.contains('.') || first.contains(':') || first == "localhost" {
+            if !REGISTRY_HOST.is_match(first) {
+                return Err(format!("invalid registry {:?}", first));
+            }
+            components.remove(0);
+        }
+    }
+    match components
+        .iter()
+        .find(|component| !PATH_COMPONENT.is_match(component))
+    {
+        Some(component) => Err(format!(
+            "invalid repository component {:?} (lowercase letters, digits and separators only)",
+            component
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Check a chart reference in `chart:version` form with a semver version
+fn validate_chart_version(chart: &str) -> Result<(), String> {
+    let Some((name, version)) = chart.rsplit_once(':') else {
+        return Err("expected chart:version".to_string());
+    };
+    if name.is_empty() {
+        return Err("chart name must not be empty".to_string());
+    }
+    semver::Version::parse(version.strip_prefix('v').unwrap_or(version))
+        .map(|_| ())
+        .map_err(|e| format!("version {:?} is not semver: {}", version, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{TargetRef, UpdatePolicyType};
+
+    fn spec(kind: &str, update_type: UpdateType, current: &str, new: &str) -> UpdateRequestSpec {
+        UpdateRequestSpec {
+            target_ref: TargetRef {
+                api_version: "apps/v1".to_string(),
+                kind: kind.to_string(),
+                name: "web".to_string(),
+                namespace: "default".to_string(),
+            },
+            update_type,
+            container_name: Some("app".to_string()),
+            image_parameter: None,
+            current_image: current.to_string(),
+            new_image: new.to_string(),
+            policy: UpdatePolicyType::Minor,
+            reason: None,
+            require_approval: true,
+            expires_at: None,
+            required_approvals: None,
+            git_write_back: None,
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_image_references() {
+        for image in [
+            "nginx:1.25",
+            "library/nginx:1.25.3-alpine",
+            "ghcr.io/org/app:v1.2.3",
+            "registry.local:5000/team/app:1.0",
+            "localhost/app:latest",
+            "app@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            "app:1.0@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+        ] {
+            assert_eq!(validate_image_reference(image), Ok(()), "{}", image);
+        }
+    }
+
+    #[test]
+    fn test_invalid_image_references() {
+        for image in [
+            "",
+            "nginx",
+            "nginx:",
+            "nginx: 1.25",
+            "Org/App:1.0",
+            "ghcr.io//app:1.0",
+            "app:1.0@sha256:xyz",
+            "app:-bad",
+        ] {
+            assert!(validate_image_reference(image).is_err(), "{}", image);
+        }
+    }
+
+    #[test]
+    fn test_validate_chart_version() {
+        assert_eq!(validate_chart_version("podinfo:6.5.4"), Ok(()));
+        assert_eq!(validate_chart_version("podinfo:v6.5.4-rc.1"), Ok(()));
+        assert!(validate_chart_version("podinfo").is_err());
+        assert!(validate_chart_version(":1.0.0").is_err());
+        assert!(validate_chart_version("podinfo:latest").is_err());
+    }
+
+    #[test]
+    fn test_validate_spec() {
+        let valid = spec("Deployment", UpdateType::Image, "nginx:1.25", "nginx:1.26");
+        assert!(validate_spec(&valid).is_empty());
+
+        let chart = spec(
+            "HelmRelease",
+            UpdateType::HelmChart,
+            "podinfo:6.5.3",
+            "podinfo:6.5.4",
+        );
+        assert!(validate_spec(&chart).is_empty());
+
+        let unknown_kind = spec("ReplicaSet", UpdateType::Image, "nginx:1.25", "nginx:1.26");
+        assert_eq!(validate_spec(&unknown_kind).len(), 1);
+
+        let mut no_container = valid.clone();
+        no_container.container_name = None;
+        assert_eq!(
+            validate_spec(&no_container),
+            vec!["containerName is required for Deployment updates"]
+        );
+
+        let chart_on_workload = spec(
+            "Deployment",
+            UpdateType::HelmChart,
+            "podinfo:6.5.3",
+            "podinfo:latest",
+        );
+        assert_eq!(validate_spec(&chart_on_workload).len(), 2);
+
+        let mut zero_approvals = valid;
+        zero_approvals.required_approvals = Some(0);
+        assert_eq!(
+            validate_spec(&zero_approvals),
+            vec!["requiredApprovals must be at least 1"]
+        );
+    }
+}
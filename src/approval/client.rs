@@ -0,0 +1,345 @@
+//! Typed client for the approval API, for tools that integrate with Headwind. It covers the
+//! operations of the document at `/api/v1/openapi.json`.
+
+use super::{
+    BatchRequest, BatchResponse, LastGoodRollbackRequest, PolicyEvaluationRequest,
+    PolicyEvaluationResponse, RollbackQuery, RollbackRequest, RollbackResponse,
+    SimpleApprovalRequest, SimpleRejectionRequest, UpdatesQuery,
+};
+use crate::audit::AuditQuery;
+use crate::models::audit::UpdateAudit;
+use crate::models::crd::UpdateRequest;
+use crate::openapi::OPENAPI_PATH;
+use crate::rollback::{UpdateHistory, UpdateHistoryEntry};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    /// The API answered with a non-success status
+    #[error("{status}: {message}")]
+    Status { status: StatusCode, message: String },
+    /// The request couldn't be sent or the response couldn't be read
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+impl ApiError {
+    /// HTTP status of the response, if the API answered
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ApiError::Status { status, .. } => Some(*status),
+            ApiError::Http(e) => e.status(),
+        }
+    }
+}
+
+/// One page of `GET /api/v1/updates`
+#[derive(Debug, Clone)]
+pub struct UpdatesPage {
+    pub items: Vec<UpdateRequest>,
+    /// Number of UpdateRequests matching the query across all pages
+    pub total: Option<usize>,
+    /// Value for `UpdatesQuery::continue_token` to fetch the next page
+    pub continue_token: Option<usize>,
+}
+
+/// Outcome of an approval
+#[derive(Debug, Clone)]
+pub struct Approval {
+    pub update_request: UpdateRequest,
+    /// The approval was recorded but the update hasn't been applied yet: more approvals are
+    /// required, a canary is running or the update waits for its rollout order
+    pub accepted: bool,
+}
+
+/// Client for the approval API
+#[derive(Debug, Clone)]
+pub struct ApprovalClient {
+    base_url: String,
+    http: Client,
+}
+
+impl ApprovalClient {
+    /// Client for the approval API at `base_url`, e.g. `http://headwind-api.headwind-system:8081`
+    pub fn new(base_url: &str) -> Result<Self, ApiError> {
+        let http = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("headwind")
+            .build()?;
+        Ok(Self::with_client(base_url, http))
+    }
+
+    /// Client using a preconfigured `reqwest::Client`, e.g. with TLS or auth headers
+    pub fn with_client(base_url: &str, http: Client) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn update_url(&self, namespace: &str, name: &str, action: Option<&str>) -> String {
+        let mut url = self.url(&format!("/api/v1/updates/{}/{}", namespace, name));
+        if let Some(action) = action {
+            url.push('/');
+            url.push_str(action);
+        }
+        url
+    }
+
+    /// List UpdateRequests matching `query`
+    pub async fn list_updates(&self, query: &UpdatesQuery) -> Result<UpdatesPage, ApiError> {
+        let response = check(
+            self.http
+                .get(self.url("/api/v1/updates"))
+                .query(query)
+                .send()
+                .await?,
+        )
+        .await?;
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+        };
+        let total = header("x-total-count");
+        let continue_token = header("x-continue-token");
+        Ok(UpdatesPage {
+            items: response.json().await?,
+            total,
+            continue_token,
+        })
+    }
+
+    /// Get an UpdateRequest
+    pub async fn get_update(&self, namespace: &str, name: &str) -> Result<UpdateRequest, ApiError> {
+        send(self.http.get(self.update_url(namespace, name, None))).await
+    }
+
+    /// Approve an UpdateRequest
+    pub async fn approve(
+        &self,
+        namespace: &str,
+        name: &str,
+        request: &SimpleApprovalRequest,
+    ) -> Result<Approval, ApiError> {
+        let response = check(
+            self.http
+                .post(self.update_url(namespace, name, Some("approve")))
+                .json(request)
+                .send()
+                .await?,
+        )
+        .await?;
+        let accepted = response.status() == StatusCode::ACCEPTED;
+        Ok(Approval {
+            update_request: response.json().await?,
+            accepted,
+        })
+    }
+
+    /// Reject an UpdateRequest
+    pub async fn reject(
+        &self,
+        namespace: &str,
+        name: &str,
+        request: &SimpleRejectionRequest,
+    ) -> Result<UpdateRequest, ApiError> {
+        send(
+            self.http
+                .post(self.update_url(namespace, name, Some("reject")))
+                .json(request),
+        )
+        .await
+    }
+
+    /// Approve or reject several UpdateRequests
+    pub async fn batch(&self, request: &BatchRequest) -> Result<BatchResponse, ApiError> {
+        send(
+            self.http
+                .post(self.url("/api/v1/updates/batch"))
+                .json(request),
+        )
+        .await
+    }
+
+    /// Update history of a Deployment
+    pub async fn rollback_history(
+        &self,
+        namespace: &str,
+        deployment: &str,
+        query: &RollbackQuery,
+    ) -> Result<UpdateHistory, ApiError> {
+        send(
+            self.http
+                .get(self.url(&format!("/api/v1/rollback/{}/{}", namespace, deployment)))
+                .query(query),
+        )
+        .await
+    }
+
+    /// Roll a Deployment container back to an earlier image
+    pub async fn rollback(
+        &self,
+        namespace: &str,
+        deployment: &str,
+        request: &RollbackRequest,
+    ) -> Result<RollbackResponse, ApiError> {
+        send(
+            self.http
+                .post(self.url(&format!("/api/v1/rollback/{}/{}", namespace, deployment)))
+                .json(request),
+        )
+        .await
+    }
+
+    /// Last known good images of a Deployment, one entry per container
+    pub async fn last_known_good(
+        &self,
+        namespace: &str,
+        deployment: &str,
+        query: &RollbackQuery,
+    ) -> Result<Vec<UpdateHistoryEntry>, ApiError> {
+        send(
+            self.http
+                .get(self.url(&format!(
+                    "/api/v1/rollback/{}/{}/last-good",
+                    namespace, deployment
+                )))
+                .query(query),
+        )
+        .await
+    }
+
+    /// Roll a Deployment container back to its last known good image
+    pub async fn rollback_to_last_good(
+        &self,
+        namespace: &str,
+        deployment: &str,
+        request: &LastGoodRollbackRequest,
+    ) -> Result<RollbackResponse, ApiError> {
+        send(
+            self.http
+                .post(self.url(&format!(
+                    "/api/v1/rollback/{}/{}/last-good",
+                    namespace, deployment
+                )))
+                .json(request),
+        )
+        .await
+    }
+
+    /// List audit records matching `query`, newest first
+    pub async fn list_audit(&self, query: &AuditQuery) -> Result<Vec<UpdateAudit>, ApiError> {
+        send(self.http.get(self.url("/api/v1/audit")).query(query)).await
+    }
+
+    /// Whether a policy would update from one version to another, and which rule decided
+    pub async fn evaluate_policy(
+        &self,
+        request: &PolicyEvaluationRequest,
+    ) -> Result<PolicyEvaluationResponse, ApiError> {
+        send(
+            self.http
+                .post(self.url("/api/v1/policy/evaluate"))
+                .json(request),
+        )
+        .await
+    }
+
+    /// The OpenAPI document of the approval API
+    pub async fn openapi(&self) -> Result<Value, ApiError> {
+        send(self.http.get(self.url(OPENAPI_PATH))).await
+    }
+}
+
+async fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, ApiError> {
+    let response = check(request.send().await?).await?;
+    Ok(response.json().await?)
+}
+
+/// Turn non-success responses into `ApiError::Status`, using the `error` field of JSON
+/// bodies as the message
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, ApiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(ApiError::Status {
+        status,
+        message: error_message(status, &body),
+    })
+}
+
+fn error_message(status: StatusCode, body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v.get("error").and_then(Value::as_str).map(str::to_string))
+        .or_else(|| Some(body.trim().to_string()).filter(|b| !b.is_empty()))
+        .unwrap_or_else(|| {
+            status
+                .canonical_reason()
+                .unwrap_or("request failed")
+                .to_string()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        let client = ApprovalClient::with_client("http://headwind:8081/", Client::new());
+        assert_eq!(
+            client.update_url("prod", "web-1-2-3", Some("approve")),
+            "http://headwind:8081/api/v1/updates/prod/web-1-2-3/approve"
+        );
+        assert_eq!(
+            client.update_url("prod", "web-1-2-3", None),
+            "http://headwind:8081/api/v1/updates/prod/web-1-2-3"
+        );
+        assert_eq!(
+            client.url(OPENAPI_PATH),
+            "http://headwind:8081/api/v1/openapi.json"
+        );
+    }
+
+    #[test]
+    fn test_query_skips_unset_fields() {
+        let query = UpdatesQuery {
+            namespace: Some("prod".to_string()),
+            sort: Some("-created".parse().unwrap()),
+            ..Default::default()
+        };
+        let request = Client::new()
+            .get("http://headwind:8081/api/v1/updates")
+            .query(&query)
+            .build()
+            .unwrap();
+        assert_eq!(request.url().query(), Some("namespace=prod&sort=-created"));
+    }
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(
+            error_message(StatusCode::CONFLICT, r#"{"error":"Already approved"}"#),
+            "Already approved"
+        );
+        assert_eq!(
+            error_message(StatusCode::BAD_REQUEST, "invalid selector\n"),
+            "invalid selector"
+        );
+        assert_eq!(error_message(StatusCode::NOT_FOUND, ""), "Not Found");
+    }
+}
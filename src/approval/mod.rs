@@ -1,41 +1,65 @@
+use crate::audit;
+use crate::controller::tracked::request_images;
+use crate::controller::workload::{is_digest_change, rollout_restart, split_image};
 use crate::controller::{
-    update_daemonset_image_with_tracking, update_deployment_image_with_tracking,
-    update_statefulset_image_with_tracking,
+    TrackedWorkload, track_rollout, update_deployment_image_with_tracking,
+    update_deployment_images_with_tracking,
 };
-use crate::models::crd::{UpdatePhase, UpdateRequest, UpdateRequestStatus};
+use crate::lint::{LintContext, LintQuery, LintReport};
+use crate::models::audit::{AuditAction, UpdateAudit, UpdateAuditSpec};
+use crate::models::crd::{
+    Approval, GitWriteBack, UpdatePhase, UpdateRequest, UpdateRequestSpec, UpdateRequestStatus,
+    conditions,
+};
+use crate::models::parse_dry_run;
+use crate::models::policy::UpdatePolicy;
 use crate::notifications::{self, DeploymentInfo};
+use crate::policy::{Evaluation, PolicyEngine};
+use crate::rollback::canary::{CanaryConfig, CanaryRollout};
 use crate::rollback::{
     AutoRollbackConfig, HealthChecker, HealthStatus, RollbackManager, UpdateHistory,
+    UpdateHistoryEntry,
 };
 use anyhow::Result;
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
+    middleware,
     response::IntoResponse,
     routing::{get, post},
 };
 use chrono::Utc;
-use k8s_openapi::api::apps::v1::Deployment;
-use kube::api::{Patch, PatchParams};
-use kube::{Api, Client};
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use k8s_openapi::api::batch::v1::CronJob;
+use kube::api::{ListParams, Patch, PatchParams};
+use kube::{Api, Client, ResourceExt};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use tokio::task::JoinHandle;
 use tower_http::trace::TraceLayer;
-use tracing::{debug, error, info, warn};
+use tracing::{Instrument, debug, error, info, instrument, warn};
+
+pub mod client;
+mod namespaced;
 
 #[derive(Clone)]
 pub struct ApprovalState {
     pub client: Client,
 }
 
-pub async fn start_approval_server() -> Result<JoinHandle<()>> {
+/// Routes of the approval API, without `/health`
+pub async fn router() -> Result<Router> {
     let client = Client::try_default().await?;
     let state = ApprovalState { client };
 
-    let app = Router::new()
+    // The cluster-wide update routes are unauthenticated, teams get the namespaced ones
+    let updates = Router::new()
         .route("/api/v1/updates", get(list_updates))
+        .route("/api/v1/updates/batch", post(batch_update))
         .route("/api/v1/updates/{namespace}/{name}", get(get_update))
         .route(
             "/api/v1/updates/{namespace}/{name}/approve",
@@ -44,7 +68,16 @@ pub async fn start_approval_server() -> Result<JoinHandle<()>> {
         .route(
             "/api/v1/updates/{namespace}/{name}/reject",
             post(reject_update),
-        )
+        );
+    let updates = if namespaced::namespaced_only() {
+        info!("Serving UpdateRequests to other clients only through the namespaced approval API");
+        updates.route_layer(middleware::from_fn(namespaced::require_local))
+    } else {
+        updates
+    };
+
+    Ok(Router::new()
+        .merge(updates)
         .route(
             "/api/v1/rollback/{namespace}/{deployment}",
             get(get_rollback_history),
@@ -53,19 +86,30 @@ pub async fn start_approval_server() -> Result<JoinHandle<()>> {
             "/api/v1/rollback/{namespace}/{deployment}",
             post(rollback_deployment),
         )
-        .route("/health", get(health_check))
+        .route(
+            "/api/v1/rollback/{namespace}/{deployment}/last-good",
+            get(get_last_known_good).post(rollback_to_last_good),
+        )
+        .route("/api/v1/audit", get(list_audit))
+        .route("/api/v1/policy/evaluate", post(evaluate_policy))
+        .route("/api/v1/lint", get(lint_annotations))
+        .route(notifications::ACTIONS_PATH, post(teams_action))
+        .merge(namespaced::routes())
+        .route(
+            crate::openapi::OPENAPI_PATH,
+            get(crate::openapi::serve_approval_api),
+        )
         .layer(TraceLayer::new_for_http())
-        .with_state(state);
+        .with_state(state))
+}
+
+pub async fn start_approval_server(addr: SocketAddr) -> Result<JoinHandle<()>> {
+    let app = router().await?.route("/health", get(crate::health::livez));
 
-    let addr = "0.0.0.0:8081";
     info!("Starting approval API server on {}", addr);
 
     let handle = tokio::spawn(async move {
-        let listener = tokio::net::TcpListener::bind(addr)
-            .await
-            .expect("Failed to bind approval server");
-
-        axum::serve(listener, app)
+        crate::server::serve(addr, app)
             .await
             .expect("Approval server failed");
     });
@@ -73,14 +117,191 @@ pub async fn start_approval_server() -> Result<JoinHandle<()>> {
     Ok(handle)
 }
 
+/// Filters, sorting and pagination for `GET /api/v1/updates`. Without parameters every
+/// UpdateRequest in the cluster is returned.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatesQuery {
+    /// Only UpdateRequests in this namespace
+    #[serde(default, deserialize_with = "audit::empty_as_none")]
+    pub namespace: Option<String>,
+    /// Only UpdateRequests in this phase
+    #[serde(default, deserialize_with = "audit::empty_as_none")]
+    pub phase: Option<UpdatePhase>,
+    /// Only UpdateRequests targeting this kind (Deployment, HelmRelease, ...)
+    #[serde(default, deserialize_with = "audit::empty_as_none")]
+    pub kind: Option<String>,
+    /// Kubernetes label selector on the UpdateRequests
+    #[serde(default, deserialize_with = "audit::empty_as_none")]
+    pub label_selector: Option<String>,
+    /// Maximum number of UpdateRequests per page
+    #[serde(default, deserialize_with = "audit::empty_as_none")]
+    pub limit: Option<usize>,
+    /// Token from the `X-Continue-Token` header of the previous page
+    #[serde(
+        default,
+        rename = "continue",
+        deserialize_with = "audit::empty_as_none"
+    )]
+    pub continue_token: Option<usize>,
+    /// Sort order, e.g. `created` or `-created` for newest first (default namespace, name)
+    #[serde(default, deserialize_with = "audit::empty_as_none")]
+    #[schemars(with = "Option<String>")]
+    pub sort: Option<UpdatesSort>,
+}
+
+/// Sort order for listing UpdateRequests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdatesSort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Name,
+    Namespace,
+    Created,
+    Phase,
+}
+
+impl std::str::FromStr for UpdatesSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (descending, field) = match s.strip_prefix('-') {
+            Some(field) => (true, field),
+            None => (false, s),
+        };
+        let field = match field {
+            "name" => SortField::Name,
+            "namespace" => SortField::Namespace,
+            "created" => SortField::Created,
+            "phase" => SortField::Phase,
+            _ => {
+                return Err(format!(
+                    "unknown sort field '{}' (expected name, namespace, created or phase)",
+                    field
+                ));
+            },
+        };
+        Ok(Self { field, descending })
+    }
+}
+
+impl std::fmt::Display for UpdatesSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let field = match self.field {
+            SortField::Name => "name",
+            SortField::Namespace => "namespace",
+            SortField::Created => "created",
+            SortField::Phase => "phase",
+        };
+        if self.descending {
+            write!(f, "-{}", field)
+        } else {
+            f.write_str(field)
+        }
+    }
+}
+
+impl Serialize for UpdatesSort {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// One page of UpdateRequests
+#[derive(Debug)]
+struct UpdatesPage {
+    items: Vec<UpdateRequest>,
+    total: usize,
+    continue_token: Option<usize>,
+}
+
+fn select_updates(items: Vec<UpdateRequest>, query: &UpdatesQuery) -> UpdatesPage {
+    let mut items: Vec<_> = items
+        .into_iter()
+        .filter(|ur| {
+            query.phase.as_ref().is_none_or(|p| phase(ur) == *p)
+                && query
+                    .kind
+                    .as_deref()
+                    .is_none_or(|kind| ur.spec.target_ref.kind.eq_ignore_ascii_case(kind))
+        })
+        .collect();
+
+    items.sort_by(|a, b| {
+        let by_name = (a.namespace(), a.name_any()).cmp(&(b.namespace(), b.name_any()));
+        let Some(sort) = query.sort else {
+            return by_name;
+        };
+        let ordering = match sort.field {
+            SortField::Name => a.name_any().cmp(&b.name_any()),
+            SortField::Namespace => a.namespace().cmp(&b.namespace()),
+            SortField::Created => a
+                .metadata
+                .creation_timestamp
+                .as_ref()
+                .map(|t| t.0)
+                .cmp(&b.metadata.creation_timestamp.as_ref().map(|t| t.0)),
+            SortField::Phase => phase(a).cmp(&phase(b)),
+        }
+        .then(by_name);
+        if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    let total = items.len();
+    let start = query.continue_token.unwrap_or(0).min(total);
+    let end = query
+        .limit
+        .map_or(total, |limit| start.saturating_add(limit.max(1)).min(total));
+    UpdatesPage {
+        items: items.drain(start..end).collect(),
+        total,
+        continue_token: (end < total).then_some(end),
+    }
+}
+
+fn phase(update_request: &UpdateRequest) -> UpdatePhase {
+    update_request
+        .status
+        .as_ref()
+        .map(|s| s.phase.clone())
+        .unwrap_or_default()
+}
+
 async fn list_updates(
     State(state): State<ApprovalState>,
-) -> Result<Json<Vec<UpdateRequest>>, StatusCode> {
-    // Query all UpdateRequest CRDs across all namespaces
-    let update_requests: Api<UpdateRequest> = Api::all(state.client);
+    Query(query): Query<UpdatesQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let update_requests: Api<UpdateRequest> = match query.namespace.as_deref() {
+        Some(namespace) => Api::namespaced(state.client, namespace),
+        None => Api::all(state.client),
+    };
+    let mut params = ListParams::default();
+    if let Some(selector) = query.label_selector.as_deref() {
+        params = params.labels(selector);
+    }
 
-    match update_requests.list(&Default::default()).await {
-        Ok(list) => Ok(Json(list.items)),
+    match update_requests.list(&params).await {
+        Ok(list) => {
+            let page = select_updates(list.items, &query);
+            let mut headers = HeaderMap::new();
+            headers.insert("X-Total-Count", page.total.into());
+            if let Some(token) = page.continue_token {
+                headers.insert("X-Continue-Token", token.into());
+            }
+            Ok((headers, Json(page.items)))
+        },
+        Err(kube::Error::Api(e)) if e.code == 400 => {
+            warn!("Invalid UpdateRequest list query: {}", e.message);
+            Err(StatusCode::BAD_REQUEST)
+        },
         Err(e) => {
             error!("Failed to list UpdateRequests: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -104,23 +325,39 @@ async fn get_update(
 }
 
 /// Simple approval request for UI (doesn't require update_id since it's in the path)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SimpleApprovalRequest {
     pub approver: Option<String>,
+    /// Image (or chart:version) the approver expects to roll out. If set and the
+    /// UpdateRequest no longer targets it, the approval fails with 412 Precondition Failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_new_image: Option<String>,
+    /// Image (or chart:version) the approver expects the target to be running. If set and
+    /// the target has drifted from it, the approval fails with 412 Precondition Failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_current_image: Option<String>,
+}
+
+impl SimpleApprovalRequest {
+    /// Whether the caller supplied any expected-version preconditions
+    fn has_preconditions(&self) -> bool {
+        self.expected_new_image.is_some() || self.expected_current_image.is_some()
+    }
 }
 
 /// Simple rejection request for UI (doesn't require update_id since it's in the path)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SimpleRejectionRequest {
     pub approver: Option<String>,
     pub reason: Option<String>,
 }
 
+#[instrument(skip_all, fields(namespace = %namespace, update_request = %name))]
 pub async fn approve_update(
     State(state): State<ApprovalState>,
     Path((namespace, name)): Path<(String, String)>,
     Json(approval): Json<SimpleApprovalRequest>,
-) -> impl IntoResponse {
+) -> (StatusCode, Json<serde_json::Value>) {
     let update_requests: Api<UpdateRequest> = Api::namespaced(state.client.clone(), &namespace);
 
     // Get the UpdateRequest
@@ -134,11 +371,34 @@ pub async fn approve_update(
             );
         },
     };
+    if let Some(trace) = &update_request.metadata.annotations {
+        crate::telemetry::continue_trace(trace);
+    }
+
+    // Reject stale approvals for an UpdateRequest that no longer matches what the approver saw
+    if let Err(reason) = check_expected_versions(&update_request, &approval) {
+        warn!(
+            "Precondition failed approving UpdateRequest {}/{}: {}",
+            namespace, name, reason
+        );
+        return precondition_failed(&update_request, reason);
+    }
 
     // Check if already approved/rejected
     if let Some(status) = &update_request.status
         && status.phase != UpdatePhase::Pending
     {
+        // Repeating an approval that already rolled out the expected version is a no-op
+        if status.phase == UpdatePhase::Completed
+            && approval.expected_new_image.as_deref() == Some(&update_request.spec.new_image)
+        {
+            info!(
+                "UpdateRequest {}/{} already completed with {}, ignoring duplicate approval",
+                namespace, name, update_request.spec.new_image
+            );
+            return (StatusCode::OK, Json(json!(update_request)));
+        }
+
         warn!(
             "UpdateRequest {}/{} is not in pending state: {:?}",
             namespace, name, status.phase
@@ -152,39 +412,178 @@ pub async fn approve_update(
         );
     }
 
+    // Pending requests past their expiry are about to be marked Expired
+    if let Some(expires_at) = update_request.spec.expires_at
+        && expires_at <= Utc::now()
+    {
+        warn!(
+            "UpdateRequest {}/{} expired at {}, cannot approve",
+            namespace, name, expires_at
+        );
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!("UpdateRequest expired at {}, cannot approve", expires_at.to_rfc3339()),
+                "current_phase": "Expired"
+            })),
+        );
+    }
+
+    // Change-managed updates wait for their ticket to be approved, scanned images for the scan
+    let gate = match crate::scanning::check_gate(&update_request) {
+        Ok(()) => crate::tickets::check_gate(&update_request).await,
+        Err(reason) => Err(reason),
+    };
+    if let Err(reason) = gate {
+        warn!(
+            "Refusing approval of UpdateRequest {}/{}: {}",
+            namespace, name, reason
+        );
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": reason,
+                "current_phase": "Pending"
+            })),
+        );
+    }
+
+    if approval.has_preconditions() {
+        if let Some(newer) = find_superseding_request(&update_requests, &update_request).await {
+            let reason = format!(
+                "UpdateRequest has been superseded by {} ({})",
+                newer.metadata.name.as_deref().unwrap_or("unknown"),
+                newer.spec.new_image
+            );
+            warn!(
+                "Precondition failed approving {}/{}: {}",
+                namespace, name, reason
+            );
+            return precondition_failed(&update_request, reason);
+        }
+
+        match current_target_image(&state.client, &update_request).await {
+            Ok(live_image) => {
+                if let Err(reason) = check_target_drift(&update_request, live_image.as_deref()) {
+                    warn!(
+                        "Precondition failed approving {}/{}: {}",
+                        namespace, name, reason
+                    );
+                    return precondition_failed(&update_request, reason);
+                }
+            },
+            Err(e) => {
+                let reason = format!("Failed to read current state of target: {}", e);
+                warn!(
+                    "Precondition failed approving {}/{}: {}",
+                    namespace, name, reason
+                );
+                return precondition_failed(&update_request, reason);
+            },
+        }
+    }
+
+    // Updates that need several approvers only run once the last distinct approval arrives
+    let required_approvals = update_request.spec.required_approvals.unwrap_or(1);
+    let mut approvals = update_request
+        .status
+        .as_ref()
+        .map(|s| s.approvals.clone())
+        .unwrap_or_default();
+    if required_approvals > 1 {
+        let quorum = match add_approval(
+            &mut approvals,
+            approval.approver.as_deref(),
+            required_approvals,
+        ) {
+            Ok(quorum) => quorum,
+            Err((status, body)) => {
+                warn!(
+                    "Refusing approval of UpdateRequest {}/{}: {}",
+                    namespace, name, body["error"]
+                );
+                return (status, Json(body));
+            },
+        };
+
+        match record_approvals(&update_requests, &update_request, &approvals, quorum).await {
+            Ok(updated) if !quorum => {
+                audit::record(
+                    UpdateAuditSpec::for_update_request(
+                        AuditAction::Approved,
+                        &update_request.spec,
+                    )
+                    .with_actor(approvals.last().map(|a| a.approver.as_str()))
+                    .with_source("api")
+                    .with_update_request(name.clone())
+                    .with_message(format!(
+                        "{} of {} approvals",
+                        approvals.len(),
+                        required_approvals
+                    )),
+                );
+                info!(
+                    "Recorded approval {} of {} for UpdateRequest {}/{}",
+                    approvals.len(),
+                    required_approvals,
+                    namespace,
+                    name
+                );
+                return (StatusCode::ACCEPTED, Json(json!(updated)));
+            },
+            Ok(_) => {},
+            Err(e) => {
+                warn!(
+                    "Failed to record approval for UpdateRequest {}/{}: {}",
+                    namespace, name, e
+                );
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({
+                        "error": format!("Failed to record approval, retry: {}", e)
+                    })),
+                );
+            },
+        }
+    }
+    let approved_by = if approvals.is_empty() {
+        approval.approver.clone()
+    } else {
+        Some(
+            approvals
+                .iter()
+                .map(|a| a.approver.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    };
+
     info!(
         "Approving UpdateRequest {}/{} by {:?}",
         namespace,
         name,
-        approval.approver.as_deref().unwrap_or("unknown")
+        approved_by.as_deref().unwrap_or("unknown")
     );
 
     // Increment approved counter
-    crate::metrics::UPDATES_APPROVED.inc();
-
-    // Execute the update
-    let update_result = execute_update(
-        &state.client,
-        &update_request,
-        Some(name.clone()),
-        approval.approver.clone(),
-        true, // Enable automatic rollback monitoring
-    )
-    .await;
-
-    // Build deployment info for notifications
-    let deployment_info = DeploymentInfo {
-        name: update_request.spec.target_ref.name.clone(),
-        namespace: update_request.spec.target_ref.namespace.clone(),
-        current_image: update_request.spec.current_image.clone(),
-        new_image: update_request.spec.new_image.clone(),
-        container: update_request.spec.container_name.clone(),
-        resource_kind: Some(update_request.spec.target_ref.kind.clone()),
-    };
-
-    // Send approval notification
+    crate::metrics::UPDATES_APPROVED
+        .with_label_values(&crate::metrics::update_request_labels(&update_request.spec))
+        .inc();
+    crate::metrics::observe_approval_latency(&update_request, "approved");
+
+    audit::record(
+        UpdateAuditSpec::for_update_request(AuditAction::Approved, &update_request.spec)
+            .with_actor(
+                approvals
+                    .last()
+                    .map(|a| a.approver.as_str())
+                    .or(approval.approver.as_deref()),
+            )
+            .with_source("api")
+            .with_update_request(name.clone()),
+    );
     notifications::notify_update_approved(
-        deployment_info.clone(),
+        update_request_info(&update_request),
         approval
             .approver
             .clone()
@@ -192,55 +591,145 @@ pub async fn approve_update(
         name.clone(),
     );
 
-    // Update the CRD status
-    let new_status = match update_result {
-        Ok(()) => {
-            info!("Successfully applied update {}/{}", namespace, name);
+    // Targets in dry-run mode are never changed, approving only records the decision
+    let target_annotations =
+        crate::hooks::target_annotations(&state.client, &update_request.spec.target_ref).await;
+    if parse_dry_run(&target_annotations) {
+        return finish_dry_run(&update_requests, &update_request, approved_by, approvals).await;
+    }
 
-            // Send completion notification
-            notifications::notify_update_completed(deployment_info.clone());
+    // Targets ordered after other workloads (headwind.sh/rollout-after) wait for their updates
+    if let Some(reason) = crate::ordering::blocking_dependency(
+        &state.client,
+        &update_request.spec.target_ref.namespace,
+        &target_annotations,
+    )
+    .await
+    {
+        return hold_update(
+            &update_requests,
+            &update_request,
+            approved_by,
+            approvals,
+            reason,
+        )
+        .await;
+    }
 
-            UpdateRequestStatus {
-                phase: UpdatePhase::Completed,
-                approved_by: approval.approver.clone(),
-                approved_at: Some(Utc::now()),
-                message: Some("Update applied successfully".to_string()),
-                last_updated: Some(Utc::now()),
-                ..Default::default()
-            }
-        },
-        Err(e) => {
-            error!("Failed to apply update {}/{}: {}", namespace, name, e);
+    roll_out(
+        &state.client,
+        update_request,
+        approval.approver.clone(),
+        approved_by,
+        approvals,
+    )
+    .await
+}
 
-            // Send failure notification
-            notifications::notify_update_failed(deployment_info.clone(), e.to_string());
+/// Apply an approved update, through a canary for Deployments that ask for one, and record
+/// the result on the UpdateRequest
+async fn roll_out(
+    client: &Client,
+    update_request: UpdateRequest,
+    approver: Option<String>,
+    approved_by: Option<String>,
+    approvals: Vec<Approval>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    let update_requests: Api<UpdateRequest> = Api::namespaced(client.clone(), &namespace);
+
+    // Nothing is applied while the update gate (HEADWIND_UPDATE_GATE_URL) reports a freeze
+    if let Err(reason) = crate::freeze::check_gate().await {
+        return defer_update(
+            &update_requests,
+            &update_request,
+            approved_by,
+            approvals,
+            reason,
+        )
+        .await;
+    }
 
-            UpdateRequestStatus {
-                phase: UpdatePhase::Failed,
-                approved_by: approval.approver.clone(),
-                approved_at: Some(Utc::now()),
-                message: Some(format!("Update failed: {}", e)),
-                last_updated: Some(Utc::now()),
-                ..Default::default()
-            }
+    // Deployments with headwind.sh/canary run the new image on a share of their replicas first
+    let update_result = match start_canary(client, &update_request).await {
+        Ok(Some(canary)) => {
+            return observe_canary(
+                client.clone(),
+                update_request,
+                approver,
+                approved_by,
+                approvals,
+                canary,
+            )
+            .await;
+        },
+        Ok(None) => {
+            execute_update(
+                client,
+                &update_request,
+                Some(name.clone()),
+                approver,
+                true, // Enable automatic rollback monitoring
+            )
+            .await
         },
+        Err(e) => Err(e.context("Failed to start canary")),
     };
 
-    // Patch the status
+    finish_update(
+        &update_requests,
+        &update_request,
+        approved_by,
+        approvals,
+        update_result,
+    )
+    .await
+}
+
+/// Leave an approved UpdateRequest Approved with `status.waitingFor` set until the workloads
+/// it is ordered after finished their updates; the ordering loop then rolls it out
+async fn hold_update(
+    update_requests: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+    approved_by: Option<String>,
+    approvals: Vec<Approval>,
+    reason: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    info!(
+        "Holding approved UpdateRequest {}/{}: {}",
+        namespace, name, reason
+    );
+
+    let mut status = UpdateRequestStatus {
+        phase: UpdatePhase::Approved,
+        approved_by,
+        approved_at: Some(Utc::now()),
+        approvals,
+        message: Some(format!("Approved, {}", reason)),
+        last_updated: Some(Utc::now()),
+        waiting_for: Some(reason.clone()),
+        ..UpdateRequestStatus::with_conditions_of(update_request.status.as_ref())
+    };
+    status.set_condition(conditions::APPROVED, true, "Approved", None);
+    status.set_condition(
+        conditions::APPLIED,
+        false,
+        "WaitingForRollout",
+        Some(reason),
+    );
     let status_patch = json!({
         "apiVersion": "headwind.sh/v1alpha1",
         "kind": "UpdateRequest",
-        "status": new_status
+        "status": status
     });
-
     match update_requests
         .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
         .await
     {
-        Ok(updated_ur) => {
-            info!("Updated status for UpdateRequest {}/{}", namespace, name);
-            (StatusCode::OK, Json(json!(updated_ur)))
-        },
+        Ok(updated_ur) => (StatusCode::ACCEPTED, Json(json!(updated_ur))),
         Err(e) => {
             error!(
                 "Failed to update status for UpdateRequest {}/{}: {}",
@@ -254,59 +743,967 @@ pub async fn approve_update(
     }
 }
 
-pub async fn reject_update(
-    State(state): State<ApprovalState>,
-    Path((namespace, name)): Path<(String, String)>,
-    Json(approval): Json<SimpleRejectionRequest>,
-) -> impl IntoResponse {
-    let update_requests: Api<UpdateRequest> = Api::namespaced(state.client.clone(), &namespace);
+/// Move an approved UpdateRequest to Deferred while the update gate reports a freeze; the
+/// gate watcher rolls it out once the gate opens
+async fn defer_update(
+    update_requests: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+    approved_by: Option<String>,
+    approvals: Vec<Approval>,
+    reason: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    info!(
+        "Deferring approved UpdateRequest {}/{}: {}",
+        namespace, name, reason
+    );
+    crate::freeze::record_deferral(update_request);
 
-    // Get the UpdateRequest
-    let update_request = match update_requests.get(&name).await {
-        Ok(ur) => ur,
+    let approved_at = update_request
+        .status
+        .as_ref()
+        .and_then(|s| s.approved_at)
+        .unwrap_or_else(Utc::now);
+    let mut status = UpdateRequestStatus {
+        phase: UpdatePhase::Deferred,
+        approved_by,
+        approved_at: Some(approved_at),
+        approvals,
+        message: Some(format!("Approved, deferred: {}", reason)),
+        last_updated: Some(Utc::now()),
+        waiting_for: Some(reason.clone()),
+        ..UpdateRequestStatus::with_conditions_of(update_request.status.as_ref())
+    };
+    status.set_condition(conditions::APPROVED, true, "Approved", None);
+    status.set_condition(conditions::APPLIED, false, "Deferred", Some(reason));
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": status
+    });
+    match update_requests
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await
+    {
+        Ok(updated_ur) => (StatusCode::ACCEPTED, Json(json!(updated_ur))),
         Err(e) => {
-            warn!("UpdateRequest {}/{} not found: {}", namespace, name, e);
-            return (
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": format!("UpdateRequest not found: {}", e)})),
+            error!(
+                "Failed to update status for UpdateRequest {}/{}: {}",
+                namespace, name, e
             );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to update status: {}", e)})),
+            )
         },
-    };
+    }
+}
 
-    // Check if already approved/rejected
-    if let Some(status) = &update_request.status
-        && status.phase != UpdatePhase::Pending
+/// Roll out a deferred UpdateRequest once the update gate opened or its minimum update
+/// interval passed. Requests deferred before they were approved go on to wait for approval;
+/// targets still waiting for their rollout order are held instead.
+pub(crate) async fn release_deferred_update(client: &Client, update_request: UpdateRequest) {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    let update_requests: Api<UpdateRequest> = Api::namespaced(client.clone(), &namespace);
+    let status = update_request.status.clone().unwrap_or_default();
+
+    if update_request.spec.require_approval
+        && status.approved_at.is_none()
+        && status.approvals.is_empty()
+    {
+        let mut pending = UpdateRequestStatus::default();
+        pending.set_condition(conditions::APPROVED, false, "PendingApproval", None);
+        let status_patch = json!({
+            "status": {
+                "phase": UpdatePhase::Pending,
+                "message": null,
+                "waitingFor": null,
+                "deferredUntil": null,
+                "lastUpdated": Utc::now(),
+                "conditions": pending.conditions
+            }
+        });
+        match update_requests
+            .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+            .await
+        {
+            Ok(_) => info!(
+                "UpdateRequest {}/{} no longer deferred, waiting for approval",
+                namespace, name
+            ),
+            Err(e) => warn!(
+                "Failed to release UpdateRequest {}/{}: {}",
+                namespace, name, e
+            ),
+        }
+        return;
+    }
+
+    let clear = json!({
+        "status": { "phase": UpdatePhase::Approved, "waitingFor": null, "deferredUntil": null }
+    });
+    if let Err(e) = update_requests
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(clear))
+        .await
     {
         warn!(
-            "UpdateRequest {}/{} is not in pending state: {:?}",
-            namespace, name, status.phase
-        );
-        return (
-            StatusCode::CONFLICT,
-            Json(json!({
-                "error": format!("UpdateRequest is in {:?} state, cannot reject", status.phase),
-                "current_phase": format!("{:?}", status.phase)
-            })),
+            "Failed to release UpdateRequest {}/{}: {}",
+            namespace, name, e
         );
+        return;
     }
 
-    info!(
-        "Rejecting UpdateRequest {}/{} by {:?}: {:?}",
-        namespace,
-        name,
-        approval.approver.as_deref().unwrap_or("unknown"),
-        approval.reason
-    );
-
-    // Build deployment info for notifications
-    let deployment_info = DeploymentInfo {
-        name: update_request.spec.target_ref.name.clone(),
-        namespace: update_request.spec.target_ref.namespace.clone(),
-        current_image: update_request.spec.current_image.clone(),
+    let target_annotations =
+        crate::hooks::target_annotations(client, &update_request.spec.target_ref).await;
+    if let Some(reason) = crate::ordering::blocking_dependency(
+        client,
+        &update_request.spec.target_ref.namespace,
+        &target_annotations,
+    )
+    .await
+    {
+        let (code, _) = hold_update(
+            &update_requests,
+            &update_request,
+            status.approved_by,
+            status.approvals,
+            reason,
+        )
+        .await;
+        if !code.is_success() {
+            warn!(
+                "Failed to hold deferred UpdateRequest {}/{}: HTTP {}",
+                namespace, name, code
+            );
+        }
+        return;
+    }
+
+    info!("Applying deferred UpdateRequest {}/{}", namespace, name);
+    let approver = status
+        .approvals
+        .last()
+        .map(|a| a.approver.clone())
+        .or_else(|| status.approved_by.clone());
+    let (code, _) = roll_out(
+        client,
+        update_request,
+        approver,
+        status.approved_by,
+        status.approvals,
+    )
+    .await;
+    debug!("Released UpdateRequest {}/{}: {}", namespace, name, code);
+}
+
+/// Roll out an approved UpdateRequest that was held back by its rollout order, once the
+/// workloads it waited for finished their updates
+pub(crate) async fn release_held_update(client: &Client, update_request: UpdateRequest) {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    let update_requests: Api<UpdateRequest> = Api::namespaced(client.clone(), &namespace);
+    let status = update_request.status.clone().unwrap_or_default();
+
+    let clear = json!({ "status": { "waitingFor": null } });
+    if let Err(e) = update_requests
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(clear))
+        .await
+    {
+        warn!(
+            "Failed to release UpdateRequest {}/{}: {}",
+            namespace, name, e
+        );
+        return;
+    }
+
+    info!(
+        "Rollout order reached, applying UpdateRequest {}/{}",
+        namespace, name
+    );
+    let approver = status
+        .approvals
+        .last()
+        .map(|a| a.approver.clone())
+        .or_else(|| status.approved_by.clone());
+    let (code, _) = roll_out(
+        client,
+        update_request,
+        approver,
+        status.approved_by,
+        status.approvals,
+    )
+    .await;
+    debug!("Released UpdateRequest {}/{}: {}", namespace, name, code);
+}
+
+/// Notification details of the target of an UpdateRequest
+fn update_request_info(update_request: &UpdateRequest) -> DeploymentInfo {
+    DeploymentInfo {
+        name: update_request.spec.target_ref.name.clone(),
+        namespace: update_request.spec.target_ref.namespace.clone(),
+        current_image: update_request.spec.current_image.clone(),
         new_image: update_request.spec.new_image.clone(),
         container: update_request.spec.container_name.clone(),
         resource_kind: Some(update_request.spec.target_ref.kind.clone()),
+    }
+}
+
+/// Record the result of applying an approved update and move the UpdateRequest to Completed or
+/// Failed
+async fn finish_update(
+    update_requests: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+    approved_by: Option<String>,
+    approvals: Vec<Approval>,
+    update_result: Result<()>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    let deployment_info = update_request_info(update_request);
+
+    audit::record(
+        UpdateAuditSpec::for_update_request(AuditAction::Applied, &update_request.spec)
+            .with_actor(approved_by.as_deref())
+            .with_source("api")
+            .with_update_request(name.clone())
+            .with_result(&update_result),
+    );
+
+    // Update the CRD status
+    let previous = update_request.status.as_ref();
+    let mut new_status = match update_result {
+        Ok(()) => {
+            info!("Successfully applied update {}/{}", namespace, name);
+
+            // Send completion notification
+            notifications::notify_update_completed(deployment_info.clone());
+
+            let mut status = UpdateRequestStatus {
+                phase: UpdatePhase::Completed,
+                approved_by: approved_by.clone(),
+                approved_at: Some(Utc::now()),
+                approvals: approvals.clone(),
+                message: Some("Update applied successfully".to_string()),
+                last_updated: Some(Utc::now()),
+                ..UpdateRequestStatus::with_conditions_of(previous)
+            };
+            status.set_condition(conditions::APPLIED, true, "UpdateApplied", None);
+            status
+        },
+        Err(e) => {
+            error!("Failed to apply update {}/{}: {}", namespace, name, e);
+            crate::metrics::UPDATES_FAILED
+                .with_label_values(&[
+                    update_request.spec.target_ref.namespace.as_str(),
+                    update_request.spec.target_ref.kind.as_str(),
+                ])
+                .inc();
+
+            // Send failure notification
+            notifications::notify_update_failed(deployment_info.clone(), e.to_string());
+
+            let mut status = UpdateRequestStatus {
+                phase: UpdatePhase::Failed,
+                approved_by: approved_by.clone(),
+                approved_at: Some(Utc::now()),
+                approvals: approvals.clone(),
+                message: Some(format!("Update failed: {}", e)),
+                last_updated: Some(Utc::now()),
+                ..UpdateRequestStatus::with_conditions_of(previous)
+            };
+            status.set_condition(
+                conditions::APPLIED,
+                false,
+                "UpdateFailed",
+                Some(e.to_string()),
+            );
+            status
+        },
+    };
+    new_status.set_condition(conditions::APPROVED, true, "Approved", None);
+
+    // Patch the status
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": new_status
+    });
+
+    match update_requests
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await
+    {
+        Ok(updated_ur) => {
+            info!("Updated status for UpdateRequest {}/{}", namespace, name);
+            (StatusCode::OK, Json(json!(updated_ur)))
+        },
+        Err(e) => {
+            error!(
+                "Failed to update status for UpdateRequest {}/{}: {}",
+                namespace, name, e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to update status: {}", e)})),
+            )
+        },
+    }
+}
+
+/// Set one condition of an UpdateRequest from a background task (health monitoring, rollback).
+/// Failures are only logged: the update itself already happened.
+async fn record_condition(
+    client: &Client,
+    namespace: &str,
+    name: Option<&str>,
+    type_: &str,
+    status: bool,
+    reason: &str,
+    message: Option<String>,
+) {
+    let Some(name) = name else {
+        return;
     };
+    let update_requests: Api<UpdateRequest> = Api::namespaced(client.clone(), namespace);
+    let result = match update_requests.get_status(name).await {
+        Ok(update_request) => {
+            let mut new_status =
+                UpdateRequestStatus::with_conditions_of(update_request.status.as_ref());
+            new_status.set_condition(type_, status, reason, message);
+            let status_patch = json!({
+                "apiVersion": "headwind.sh/v1alpha1",
+                "kind": "UpdateRequest",
+                "status": { "conditions": new_status.conditions }
+            });
+            update_requests
+                .patch_status(name, &PatchParams::default(), &Patch::Merge(status_patch))
+                .await
+                .map(|_| ())
+        },
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(
+            "Failed to set condition {} of UpdateRequest {}/{}: {}",
+            type_, namespace, name, e
+        );
+    }
+}
+
+/// Complete an approved UpdateRequest of a target in dry-run mode (`headwind.sh/dry-run`)
+/// without applying it
+async fn finish_dry_run(
+    update_requests: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+    approved_by: Option<String>,
+    approvals: Vec<Approval>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    let target = &update_request.spec.target_ref;
+    let message = format!(
+        "Dry run: {} {}/{} was not updated to {}",
+        target.kind, target.namespace, target.name, update_request.spec.new_image
+    );
+    info!("UpdateRequest {}/{} approved. {}", namespace, name, message);
+
+    let mut status = UpdateRequestStatus {
+        phase: UpdatePhase::Completed,
+        approved_by,
+        approved_at: Some(Utc::now()),
+        approvals,
+        message: Some(message.clone()),
+        last_updated: Some(Utc::now()),
+        ..UpdateRequestStatus::with_conditions_of(update_request.status.as_ref())
+    };
+    status.set_condition(conditions::APPROVED, true, "Approved", None);
+    status.set_condition(conditions::APPLIED, false, "DryRun", Some(message));
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": status
+    });
+    match update_requests
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await
+    {
+        Ok(updated_ur) => (StatusCode::OK, Json(json!(updated_ur))),
+        Err(e) => {
+            error!(
+                "Failed to update status for UpdateRequest {}/{}: {}",
+                namespace, name, e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to update status: {}", e)})),
+            )
+        },
+    }
+}
+
+/// Start a canary for an approved Deployment update when the Deployment asks for one
+/// (`headwind.sh/canary`). Returns None for updates that roll out directly.
+async fn start_canary(
+    client: &Client,
+    update_request: &UpdateRequest,
+) -> Result<Option<CanaryRollout>> {
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+    if spec.container_name.is_none() {
+        return Ok(None);
+    }
+    if target.kind != "Deployment" || spec.git_write_back.is_some() {
+        return Ok(None);
+    }
+
+    let deployments: Api<Deployment> = Api::namespaced(client.clone(), &target.namespace);
+    let deployment = deployments.get(&target.name).await?;
+    let config = CanaryConfig::from_annotations(deployment.annotations());
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let canary = CanaryRollout::start(
+        client.clone(),
+        &deployment,
+        &spec.container_images(),
+        config,
+    )
+    .await?;
+    Ok(Some(canary))
+}
+
+/// Leave the UpdateRequest Approved while the canary is observed in the background, then
+/// promote the update or abandon it
+async fn observe_canary(
+    client: Client,
+    update_request: UpdateRequest,
+    approver: Option<String>,
+    approved_by: Option<String>,
+    approvals: Vec<Approval>,
+    canary: CanaryRollout,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    let update_requests: Api<UpdateRequest> = Api::namespaced(client.clone(), &namespace);
+
+    let message = format!(
+        "Canary running {} on {} of {} replicas",
+        update_request.spec.new_image, canary.replicas, canary.total_replicas
+    );
+    let mut status = UpdateRequestStatus {
+        phase: UpdatePhase::Approved,
+        approved_by: approved_by.clone(),
+        approved_at: Some(Utc::now()),
+        approvals: approvals.clone(),
+        message: Some(message.clone()),
+        last_updated: Some(Utc::now()),
+        ..UpdateRequestStatus::with_conditions_of(update_request.status.as_ref())
+    };
+    status.set_condition(conditions::APPROVED, true, "Approved", None);
+    status.set_condition(conditions::APPLIED, false, "CanaryRunning", Some(message));
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": &status
+    });
+    let response = match update_requests
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await
+    {
+        Ok(updated_ur) => (StatusCode::ACCEPTED, Json(json!(updated_ur))),
+        Err(e) => {
+            error!(
+                "Failed to update status for UpdateRequest {}/{}: {}",
+                namespace, name, e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to update status: {}", e)})),
+            )
+        },
+    };
+
+    tokio::spawn(
+        async move {
+            let mut status = status;
+            let target = &update_request.spec.target_ref;
+            let verdict = canary.observe().await;
+            if let Err(e) = canary.remove().await {
+                warn!(
+                    "Failed to remove canary of {}/{}: {}",
+                    target.namespace, target.name, e
+                );
+            }
+
+            let update_result = match verdict {
+                HealthStatus::Healthy => {
+                    info!(
+                        "Canary of {}/{} is healthy, promoting {}",
+                        target.namespace, target.name, update_request.spec.new_image
+                    );
+                    crate::metrics::CANARY_ROLLOUTS
+                        .with_label_values(&[target.namespace.as_str(), "promoted"])
+                        .inc();
+                    status.set_condition(conditions::HEALTH_CHECKED, true, "CanaryHealthy", None);
+                    execute_update(
+                        &client,
+                        &update_request,
+                        Some(name.clone()),
+                        approver,
+                        true, // Enable automatic rollback monitoring
+                    )
+                    .await
+                },
+                verdict => {
+                    let reason = match verdict {
+                        HealthStatus::Failed(reason) => reason,
+                        _ => "Canary did not become healthy in time".to_string(),
+                    };
+                    error!(
+                        "Canary of {}/{} failed, keeping {}: {}",
+                        target.namespace, target.name, update_request.spec.current_image, reason
+                    );
+                    crate::metrics::CANARY_ROLLOUTS
+                        .with_label_values(&[target.namespace.as_str(), "failed"])
+                        .inc();
+                    status.set_condition(
+                        conditions::HEALTH_CHECKED,
+                        false,
+                        "CanaryFailed",
+                        Some(reason.clone()),
+                    );
+                    status.set_condition(
+                        conditions::ROLLED_BACK,
+                        true,
+                        "CanaryFailed",
+                        Some(reason.clone()),
+                    );
+
+                    // Removing the canary is the rollback: the Deployment was never changed
+                    let deployment_info = update_request_info(&update_request);
+                    notifications::notify_rollback_triggered(
+                        deployment_info.clone(),
+                        reason.clone(),
+                    );
+                    audit::record(
+                        UpdateAuditSpec::for_update_request(
+                            AuditAction::RolledBack,
+                            &update_request.spec,
+                        )
+                        .with_source("canary")
+                        .with_update_request(name.clone())
+                        .with_message(reason.clone()),
+                    );
+                    notifications::notify_rollback_completed(deployment_info);
+                    Err(anyhow::anyhow!("Canary failed: {}", reason))
+                },
+            };
+
+            let update_request = UpdateRequest {
+                status: Some(status),
+                ..update_request
+            };
+            let _ = finish_update(
+                &update_requests,
+                &update_request,
+                approved_by,
+                approvals,
+                update_result,
+            )
+            .await;
+        }
+        .in_current_span(),
+    );
+
+    response
+}
+
+/// Build a 412 Precondition Failed response describing what the UpdateRequest currently holds
+/// Add an approver to the approvals collected so far, returning whether the quorum is reached.
+/// Approvals must be attributed, and each identity counts once.
+fn add_approval(
+    approvals: &mut Vec<Approval>,
+    approver: Option<&str>,
+    required_approvals: u32,
+) -> Result<bool, (StatusCode, serde_json::Value)> {
+    let Some(approver) = approver.map(str::trim).filter(|a| !a.is_empty()) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            json!({
+                "error": format!(
+                    "UpdateRequest needs {} approvals, an approver identity is required",
+                    required_approvals
+                )
+            }),
+        ));
+    };
+
+    if approvals.iter().any(|a| a.approver == approver) {
+        return Err((
+            StatusCode::CONFLICT,
+            json!({
+                "error": format!("{} has already approved this update", approver),
+                "approvals": approvals.len(),
+                "required_approvals": required_approvals
+            }),
+        ));
+    }
+
+    approvals.push(Approval {
+        approver: approver.to_string(),
+        approved_at: Utc::now(),
+    });
+    Ok(approvals.len() as u32 >= required_approvals)
+}
+
+/// Store the approvals collected so far. The resourceVersion precondition makes concurrent
+/// approvals conflict rather than overwrite each other, so only one of them can complete the
+/// quorum and apply the update.
+async fn record_approvals(
+    update_requests: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+    approvals: &[Approval],
+    quorum: bool,
+) -> Result<UpdateRequest, kube::Error> {
+    let required_approvals = update_request.spec.required_approvals.unwrap_or(1);
+    let message = format!("{} of {} approvals", approvals.len(), required_approvals);
+    let mut status = UpdateRequestStatus::with_conditions_of(update_request.status.as_ref());
+    if quorum {
+        status.set_condition(
+            conditions::APPROVED,
+            true,
+            "Approved",
+            Some(message.clone()),
+        );
+    } else {
+        status.set_condition(
+            conditions::APPROVED,
+            false,
+            "PendingApproval",
+            Some(message.clone()),
+        );
+    }
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "metadata": {
+            "resourceVersion": update_request.metadata.resource_version
+        },
+        "status": {
+            "phase": if quorum { UpdatePhase::Approved } else { UpdatePhase::Pending },
+            "approvals": approvals,
+            "message": message,
+            "lastUpdated": Utc::now(),
+            "conditions": status.conditions
+        }
+    });
+
+    update_requests
+        .patch_status(
+            update_request.metadata.name.as_deref().unwrap_or_default(),
+            &PatchParams::default(),
+            &Patch::Merge(status_patch),
+        )
+        .await
+}
+
+fn precondition_failed(
+    update_request: &UpdateRequest,
+    reason: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::PRECONDITION_FAILED,
+        Json(json!({
+            "error": reason,
+            "current_image": update_request.spec.current_image,
+            "new_image": update_request.spec.new_image,
+            "current_phase": update_request
+                .status
+                .as_ref()
+                .map(|s| format!("{:?}", s.phase))
+                .unwrap_or_else(|| "Pending".to_string()),
+        })),
+    )
+}
+
+/// Compare the approver's expected images against the UpdateRequest spec
+fn check_expected_versions(
+    update_request: &UpdateRequest,
+    approval: &SimpleApprovalRequest,
+) -> std::result::Result<(), String> {
+    let spec = &update_request.spec;
+
+    if let Some(expected) = &approval.expected_new_image
+        && *expected != spec.new_image
+    {
+        return Err(format!(
+            "UpdateRequest now targets {}, expected {}",
+            spec.new_image, expected
+        ));
+    }
+
+    if let Some(expected) = &approval.expected_current_image
+        && *expected != spec.current_image
+    {
+        return Err(format!(
+            "UpdateRequest was created against {}, expected {}",
+            spec.current_image, expected
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that the target is still running the image the UpdateRequest was created against
+fn check_target_drift(
+    update_request: &UpdateRequest,
+    live_image: Option<&str>,
+) -> std::result::Result<(), String> {
+    let spec = &update_request.spec;
+
+    match live_image {
+        Some(live) if live == spec.current_image => Ok(()),
+        Some(live) => Err(format!(
+            "Target {} {}/{} has drifted: running {}, UpdateRequest expects {}",
+            spec.target_ref.kind,
+            spec.target_ref.namespace,
+            spec.target_ref.name,
+            live,
+            spec.current_image
+        )),
+        None => Err(format!(
+            "Target {} {}/{} no longer has a matching container",
+            spec.target_ref.kind, spec.target_ref.namespace, spec.target_ref.name
+        )),
+    }
+}
+
+/// Find a newer pending or completed UpdateRequest for the same target and container
+async fn find_superseding_request(
+    update_requests: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+) -> Option<UpdateRequest> {
+    let list = match update_requests.list(&Default::default()).await {
+        Ok(list) => list,
+        Err(e) => {
+            warn!("Failed to list UpdateRequests for supersede check: {}", e);
+            return None;
+        },
+    };
+
+    list.items
+        .into_iter()
+        .find(|other| is_superseded_by(update_request, other))
+}
+
+/// Whether `other` supersedes `update_request`: same target and container, a different
+/// new image, not rejected/failed/expired, and created later
+fn is_superseded_by(update_request: &UpdateRequest, other: &UpdateRequest) -> bool {
+    let spec = &update_request.spec;
+    let other_spec = &other.spec;
+
+    if other.metadata.name == update_request.metadata.name
+        || other_spec.target_ref.kind != spec.target_ref.kind
+        || other_spec.target_ref.name != spec.target_ref.name
+        || other_spec.target_ref.namespace != spec.target_ref.namespace
+        || other_spec.container_name != spec.container_name
+        || other_spec.new_image == spec.new_image
+    {
+        return false;
+    }
+
+    let other_phase = other
+        .status
+        .as_ref()
+        .map(|s| s.phase.clone())
+        .unwrap_or_default();
+    if !matches!(other_phase, UpdatePhase::Pending | UpdatePhase::Completed) {
+        return false;
+    }
+
+    match (
+        &other.metadata.creation_timestamp,
+        &update_request.metadata.creation_timestamp,
+    ) {
+        (Some(other_created), Some(created)) => other_created.0 > created.0,
+        _ => false,
+    }
+}
+
+/// Read the image (or chart:version for HelmReleases and chart Applications) the target is
+/// currently running
+async fn current_target_image(
+    client: &Client,
+    update_request: &UpdateRequest,
+) -> Result<Option<String>> {
+    use crate::controller::tracked::fetch_pod_spec;
+    use k8s_openapi::api::core::v1::PodSpec;
+
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+    let (namespace, name) = (&target.namespace, &target.name);
+
+    let pod_spec: Option<PodSpec> = match target.kind.as_str() {
+        "Deployment" => fetch_pod_spec::<Deployment>(client, namespace, name).await?,
+        "StatefulSet" => fetch_pod_spec::<StatefulSet>(client, namespace, name).await?,
+        "DaemonSet" => fetch_pod_spec::<DaemonSet>(client, namespace, name).await?,
+        "CronJob" => fetch_pod_spec::<CronJob>(client, namespace, name).await?,
+        "HelmRelease" => {
+            use crate::models::HelmRelease;
+
+            use crate::models::crd::UpdateType;
+
+            let api: Api<HelmRelease> = Api::namespaced(client.clone(), &target.namespace);
+            let release_spec = api.get(&target.name).await?.spec;
+            if spec.update_type == UpdateType::Image {
+                return Ok(spec.image_parameter.as_deref().and_then(|path| {
+                    crate::helm::values::image_at(release_spec.values.as_ref()?, path)
+                }));
+            }
+            let chart = release_spec.chart.spec;
+            return Ok(chart
+                .version
+                .map(|version| format!("{}:{}", chart.chart, version)));
+        },
+        "Release" => {
+            let release = crate::controller::plainhelm::deployed_release(
+                client,
+                &target.namespace,
+                &target.name,
+            )
+            .await?;
+            return Ok(Some(release.chart_ref()));
+        },
+        "Kustomization" => {
+            use crate::models::kustomization::Kustomization;
+
+            let api: Api<Kustomization> = Api::namespaced(client.clone(), &target.namespace);
+            let kustomization = api.get(&target.name).await?;
+            return Ok(Some(
+                crate::controller::kustomization::current_kustomization_image(&kustomization, spec),
+            ));
+        },
+        "Application" => {
+            use crate::models::Application;
+
+            let api: Api<Application> = Api::namespaced(client.clone(), &target.namespace);
+            let application = api.get(&target.name).await?;
+            return Ok(crate::controller::argocd::current_application_image(
+                &application,
+                spec,
+            ));
+        },
+        other => return Err(anyhow::anyhow!("Unsupported resource kind: {}", other)),
+    };
+
+    let containers = pod_spec.map(|s| s.containers).unwrap_or_default();
+    Ok(select_container_image(
+        &containers,
+        spec.container_name.as_deref(),
+        &spec.current_image,
+    ))
+}
+
+/// Pick the container image an UpdateRequest applies to: by name when the request names a
+/// container, otherwise the first container running the same repository as `current_image`
+fn select_container_image(
+    containers: &[k8s_openapi::api::core::v1::Container],
+    container_name: Option<&str>,
+    current_image: &str,
+) -> Option<String> {
+    let repository = |image: &str| {
+        split_image(image)
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_else(|| image.split('@').next().unwrap_or(image).to_string())
+    };
+
+    containers
+        .iter()
+        .find(|c| match container_name {
+            Some(name) => c.name == name,
+            None => c
+                .image
+                .as_deref()
+                .is_some_and(|image| repository(image) == repository(current_image)),
+        })
+        .and_then(|c| c.image.clone())
+}
+
+#[instrument(skip_all, fields(namespace = %namespace, update_request = %name))]
+pub async fn reject_update(
+    State(state): State<ApprovalState>,
+    Path((namespace, name)): Path<(String, String)>,
+    Json(approval): Json<SimpleRejectionRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let update_requests: Api<UpdateRequest> = Api::namespaced(state.client.clone(), &namespace);
+
+    // Get the UpdateRequest
+    let update_request = match update_requests.get(&name).await {
+        Ok(ur) => ur,
+        Err(e) => {
+            warn!("UpdateRequest {}/{} not found: {}", namespace, name, e);
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": format!("UpdateRequest not found: {}", e)})),
+            );
+        },
+    };
+    if let Some(trace) = &update_request.metadata.annotations {
+        crate::telemetry::continue_trace(trace);
+    }
+
+    // Check if already approved/rejected
+    if let Some(status) = &update_request.status
+        && status.phase != UpdatePhase::Pending
+    {
+        warn!(
+            "UpdateRequest {}/{} is not in pending state: {:?}",
+            namespace, name, status.phase
+        );
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!("UpdateRequest is in {:?} state, cannot reject", status.phase),
+                "current_phase": format!("{:?}", status.phase)
+            })),
+        );
+    }
+
+    info!(
+        "Rejecting UpdateRequest {}/{} by {:?}: {:?}",
+        namespace,
+        name,
+        approval.approver.as_deref().unwrap_or("unknown"),
+        approval.reason
+    );
+
+    crate::metrics::UPDATES_REJECTED
+        .with_label_values(&crate::metrics::update_request_labels(&update_request.spec))
+        .inc();
+    crate::metrics::observe_approval_latency(&update_request, "rejected");
+
+    // Build deployment info for notifications
+    let deployment_info = DeploymentInfo {
+        name: update_request.spec.target_ref.name.clone(),
+        namespace: update_request.spec.target_ref.namespace.clone(),
+        current_image: update_request.spec.current_image.clone(),
+        new_image: update_request.spec.new_image.clone(),
+        container: update_request.spec.container_name.clone(),
+        resource_kind: Some(update_request.spec.target_ref.kind.clone()),
+    };
+
+    let mut audit_entry =
+        UpdateAuditSpec::for_update_request(AuditAction::Rejected, &update_request.spec)
+            .with_actor(approval.approver.as_deref())
+            .with_source("api")
+            .with_update_request(name.clone());
+    if let Some(reason) = &approval.reason {
+        audit_entry = audit_entry.with_message(reason.clone());
+    }
+    audit::record(audit_entry);
 
     // Send rejection notification
     notifications::notify_update_rejected(
@@ -322,47 +1719,352 @@ pub async fn reject_update(
         name.clone(),
     );
 
-    // Update the CRD status
-    let new_status = UpdateRequestStatus {
-        phase: UpdatePhase::Rejected,
-        rejected_by: approval.approver.clone(),
-        rejected_at: Some(Utc::now()),
-        message: approval
-            .reason
-            .clone()
-            .or(Some("Rejected by user".to_string())),
-        last_updated: Some(Utc::now()),
-        ..Default::default()
+    // Update the CRD status
+    let mut new_status = UpdateRequestStatus {
+        phase: UpdatePhase::Rejected,
+        rejected_by: approval.approver.clone(),
+        rejected_at: Some(Utc::now()),
+        message: approval
+            .reason
+            .clone()
+            .or(Some("Rejected by user".to_string())),
+        last_updated: Some(Utc::now()),
+        ..UpdateRequestStatus::with_conditions_of(update_request.status.as_ref())
+    };
+    new_status.set_condition(
+        conditions::APPROVED,
+        false,
+        "Rejected",
+        new_status.message.clone(),
+    );
+
+    // Patch the status
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": new_status
+    });
+
+    match update_requests
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await
+    {
+        Ok(updated_ur) => {
+            info!("Updated status for UpdateRequest {}/{}", namespace, name);
+            (StatusCode::OK, Json(json!(updated_ur)))
+        },
+        Err(e) => {
+            error!(
+                "Failed to update status for UpdateRequest {}/{}: {}",
+                namespace, name, e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to update status: {}", e)})),
+            )
+        },
+    }
+}
+
+/// Apply an automatic update that was held back until its maintenance window opened
+pub(crate) async fn apply_scheduled_update(
+    client: &Client,
+    spec: &UpdateRequestSpec,
+) -> Result<()> {
+    let name = format!("{}-scheduled", spec.target_ref.name);
+    execute_update(
+        client,
+        &UpdateRequest::new(&name, spec.clone()),
+        None,
+        None,
+        false,
+    )
+    .await
+}
+
+/// Action applied to every UpdateRequest of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchAction {
+    Approve,
+    Reject,
+}
+
+/// UpdateRequest addressed by a batch
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchItem {
+    pub namespace: String,
+    pub name: String,
+}
+
+/// Selects the Pending UpdateRequests a batch applies to
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct BatchSelector {
+    /// Only UpdateRequests in this namespace
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Only UpdateRequests targeting this kind
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Kubernetes label selector on the UpdateRequests
+    #[serde(default)]
+    pub label_selector: Option<String>,
+    /// Only UpdateRequests to exactly this image (or chart:version)
+    #[serde(default)]
+    pub new_image: Option<String>,
+}
+
+/// Approve or reject several UpdateRequests, listed in `items` or matched by `selector`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchRequest {
+    pub action: BatchAction,
+    #[serde(default)]
+    pub items: Vec<BatchItem>,
+    #[serde(default)]
+    pub selector: Option<BatchSelector>,
+    pub approver: Option<String>,
+    /// Rejection reason
+    pub reason: Option<String>,
+}
+
+/// Outcome of a batch
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchResponse {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+/// Outcome of one UpdateRequest of a batch
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BatchItemResult {
+    pub namespace: String,
+    pub name: String,
+    /// HTTP status the single-item endpoint would have returned
+    pub status: u16,
+    pub success: bool,
+    /// Response body the single-item endpoint would have returned
+    pub result: serde_json::Value,
+}
+
+/// Resolve the UpdateRequests a batch applies to
+async fn batch_items(
+    client: &Client,
+    batch: &BatchRequest,
+) -> Result<Vec<BatchItem>, (StatusCode, String)> {
+    let selector = match (&batch.selector, batch.items.is_empty()) {
+        (None, false) => return Ok(batch.items.clone()),
+        (Some(selector), true) => selector,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "exactly one of items or selector is required".to_string(),
+            ));
+        },
+    };
+
+    let api: Api<UpdateRequest> = match selector.namespace.as_deref() {
+        Some(namespace) => Api::namespaced(client.clone(), namespace),
+        None => Api::all(client.clone()),
+    };
+    let mut params = ListParams::default();
+    if let Some(labels) = selector.label_selector.as_deref() {
+        params = params.labels(labels);
+    }
+    let list = api.list(&params).await.map_err(|e| match e {
+        kube::Error::Api(e) if e.code == 400 => (StatusCode::BAD_REQUEST, e.message),
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    Ok(select_batch_items(list.items, selector))
+}
+
+fn select_batch_items(items: Vec<UpdateRequest>, selector: &BatchSelector) -> Vec<BatchItem> {
+    let query = UpdatesQuery {
+        phase: Some(UpdatePhase::Pending),
+        kind: selector.kind.clone(),
+        ..Default::default()
+    };
+    select_updates(items, &query)
+        .items
+        .into_iter()
+        .filter(|ur| {
+            selector
+                .new_image
+                .as_deref()
+                .is_none_or(|image| ur.spec.new_image == image)
+        })
+        .map(|ur| BatchItem {
+            namespace: ur.namespace().unwrap_or_default(),
+            name: ur.name_any(),
+        })
+        .collect()
+}
+
+/// Approve or reject many UpdateRequests in one call. Items are processed one after another
+/// exactly like the single-item endpoints, and each gets its own status in the response.
+async fn batch_update(
+    State(state): State<ApprovalState>,
+    Json(batch): Json<BatchRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let items = match batch_items(&state.client, &batch).await {
+        Ok(items) => items,
+        Err((status, error)) => return (status, Json(json!({"error": error}))),
+    };
+    info!(
+        "Batch {:?} of {} UpdateRequest(s) by {}",
+        batch.action,
+        items.len(),
+        batch.approver.as_deref().unwrap_or("unknown")
+    );
+
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        let path = Path((item.namespace.clone(), item.name.clone()));
+        let (status, Json(body)) = match batch.action {
+            BatchAction::Approve => {
+                let approval = SimpleApprovalRequest {
+                    approver: batch.approver.clone(),
+                    expected_new_image: None,
+                    expected_current_image: None,
+                };
+                approve_update(State(state.clone()), path, Json(approval)).await
+            },
+            BatchAction::Reject => {
+                let rejection = SimpleRejectionRequest {
+                    approver: batch.approver.clone(),
+                    reason: batch.reason.clone(),
+                };
+                reject_update(State(state.clone()), path, Json(rejection)).await
+            },
+        };
+        results.push(BatchItemResult {
+            namespace: item.namespace,
+            name: item.name,
+            status: status.as_u16(),
+            success: status.is_success(),
+            result: body,
+        });
+    }
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    (
+        StatusCode::OK,
+        Json(json!(BatchResponse {
+            succeeded,
+            failed: results.len() - succeeded,
+            results,
+        })),
+    )
+}
+
+/// Body of the Approve and Reject buttons on Teams Adaptive Cards
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct TeamsAction {
+    pub namespace: String,
+    pub name: String,
+    pub action: BatchAction,
+    /// `notifications::teams::action_token` for this action and UpdateRequest
+    pub token: String,
+}
+
+/// Identity recorded for approvals and rejections made from a Teams card
+const TEAMS_APPROVER: &str = "Microsoft Teams";
+
+/// Apply an Approve or Reject button pressed on a Teams card. The outcome is returned in the
+/// `CARD-ACTION-STATUS` header, which Teams shows under the card.
+async fn teams_action(
+    State(state): State<ApprovalState>,
+    Json(action): Json<TeamsAction>,
+) -> (StatusCode, HeaderMap, Json<serde_json::Value>) {
+    let (action_name, done) = match action.action {
+        BatchAction::Approve => ("approve", "approved"),
+        BatchAction::Reject => ("reject", "rejected"),
     };
 
-    // Patch the status
-    let status_patch = json!({
-        "apiVersion": "headwind.sh/v1alpha1",
-        "kind": "UpdateRequest",
-        "status": new_status
-    });
+    let Some(secret) = notifications::teams_action_secret() else {
+        return teams_action_response(
+            StatusCode::NOT_FOUND,
+            "Teams card actions are not enabled".to_string(),
+        );
+    };
+    if !notifications::verify_action_token(
+        &secret,
+        action_name,
+        &action.namespace,
+        &action.name,
+        &action.token,
+    ) {
+        warn!(
+            "Rejected Teams {} of UpdateRequest {}/{}: invalid token",
+            action_name, action.namespace, action.name
+        );
+        return teams_action_response(StatusCode::FORBIDDEN, "Invalid action token".to_string());
+    }
 
-    match update_requests
-        .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
-        .await
-    {
-        Ok(updated_ur) => {
-            info!("Updated status for UpdateRequest {}/{}", namespace, name);
-            (StatusCode::OK, Json(json!(updated_ur)))
+    info!(
+        "Teams {} of UpdateRequest {}/{}",
+        action_name, action.namespace, action.name
+    );
+    let path = Path((action.namespace.clone(), action.name.clone()));
+    let (status, Json(body)) = match action.action {
+        BatchAction::Approve => {
+            let approval = SimpleApprovalRequest {
+                approver: Some(TEAMS_APPROVER.to_string()),
+                expected_new_image: None,
+                expected_current_image: None,
+            };
+            approve_update(State(state), path, Json(approval)).await
         },
-        Err(e) => {
-            error!(
-                "Failed to update status for UpdateRequest {}/{}: {}",
-                namespace, name, e
-            );
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to update status: {}", e)})),
-            )
+        BatchAction::Reject => {
+            let rejection = SimpleRejectionRequest {
+                approver: Some(TEAMS_APPROVER.to_string()),
+                reason: Some("Rejected from Microsoft Teams".to_string()),
+            };
+            reject_update(State(state), path, Json(rejection)).await
         },
+    };
+
+    let message = if status.is_success() {
+        body["status"]["message"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                format!(
+                    "UpdateRequest {}/{} {}",
+                    action.namespace, action.name, done
+                )
+            })
+    } else {
+        body["error"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Failed to {} UpdateRequest", action_name))
+    };
+    let (_, headers, _) = teams_action_response(status, message);
+    (status, headers, Json(body))
+}
+
+fn teams_action_response(
+    status: StatusCode,
+    message: String,
+) -> (StatusCode, HeaderMap, Json<serde_json::Value>) {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = message.parse() {
+        headers.insert(notifications::CARD_ACTION_STATUS_HEADER, value);
     }
+    (status, headers, Json(json!({ "message": message })))
 }
 
+#[instrument(
+    name = "apply_update",
+    skip_all,
+    fields(
+        kind = %update_request.spec.target_ref.kind,
+        namespace = %update_request.spec.target_ref.namespace,
+        target = %update_request.spec.target_ref.name,
+        new_image = %update_request.spec.new_image,
+    )
+)]
 async fn execute_update(
     client: &Client,
     update_request: &UpdateRequest,
@@ -378,6 +2080,43 @@ async fn execute_update(
         target.kind, target.name, target.namespace
     );
 
+    crate::hooks::pre_update(client, update_request).await?;
+
+    let result = apply_update(
+        client,
+        update_request,
+        update_request_name,
+        approved_by,
+        enable_auto_rollback,
+    )
+    .await;
+
+    crate::hooks::post_update(client, update_request, &result).await;
+    result
+}
+
+async fn apply_update(
+    client: &Client,
+    update_request: &UpdateRequest,
+    update_request_name: Option<String>,
+    approved_by: Option<String>,
+    enable_auto_rollback: bool,
+) -> Result<()> {
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+
+    // Workloads deployed through GitOps are updated by committing to their repository
+    if let Some(git) = &spec.git_write_back {
+        return execute_git_write_back(update_request, git).await;
+    }
+
+    // Restart-mode workloads pick up their tag's new digest by restarting, keeping the image
+    if is_digest_change(&spec.current_image, &spec.new_image)
+        && execute_restart(client, update_request, approved_by.as_deref()).await?
+    {
+        return Ok(());
+    }
+
     // Route to appropriate update handler based on resource kind
     match target.kind.as_str() {
         "Deployment" => {
@@ -397,17 +2136,104 @@ async fn execute_update(
         "DaemonSet" => {
             execute_daemonset_update(client, update_request, update_request_name, approved_by).await
         },
+        "CronJob" => {
+            execute_cronjob_update(client, update_request, update_request_name, approved_by).await
+        },
         "HelmRelease" => {
             execute_helmrelease_update(client, update_request, update_request_name, approved_by)
                 .await
         },
+        "Application" => execute_application_update(client, update_request).await,
+        "Kustomization" => execute_kustomization_update(client, update_request).await,
+        "Release" => execute_helm_cli_release_update(client, update_request).await,
         _ => Err(anyhow::anyhow!(
-            "Unsupported resource kind: {}. Only Deployment, StatefulSet, DaemonSet, and HelmRelease are supported.",
+            "Unsupported resource kind: {}. Only Deployment, StatefulSet, DaemonSet, CronJob, HelmRelease, Application, Kustomization and Release are supported.",
             target.kind
         )),
     }
 }
 
+/// Restart the target workload when it is in `headwind.sh/update-mode: restart`. Returns
+/// false when the workload's image should be updated instead.
+async fn execute_restart(
+    client: &Client,
+    update_request: &UpdateRequest,
+    approved_by: Option<&str>,
+) -> Result<bool> {
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+
+    if !rollout_restart(
+        client,
+        &target.kind,
+        &target.namespace,
+        &target.name,
+        spec.container_name.as_deref(),
+        approved_by,
+    )
+    .await?
+    {
+        return Ok(false);
+    }
+
+    info!(
+        "Restarted {} {}/{} to pick up {}",
+        target.kind, target.namespace, target.name, spec.new_image
+    );
+
+    crate::notifications::notify_update_completed(crate::notifications::DeploymentInfo {
+        name: target.name.clone(),
+        namespace: target.namespace.clone(),
+        current_image: spec.current_image.clone(),
+        new_image: spec.new_image.clone(),
+        container: spec.container_name.clone(),
+        resource_kind: Some(target.kind.clone()),
+    });
+
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[&target.namespace, &target.kind])
+        .inc();
+
+    Ok(true)
+}
+
+async fn execute_git_write_back(update_request: &UpdateRequest, git: &GitWriteBack) -> Result<()> {
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+
+    let outcome = crate::gitops::write_back(
+        git,
+        &crate::gitops::WriteBackChange {
+            kind: &target.kind,
+            namespace: &target.namespace,
+            name: &target.name,
+            current_image: &spec.current_image,
+            new_image: &spec.new_image,
+        },
+    )
+    .await?;
+
+    info!(
+        "Wrote {} for {} {}/{} back to {} on branch {}",
+        spec.new_image, target.kind, target.namespace, target.name, git.repository, outcome.branch
+    );
+
+    crate::notifications::notify_update_completed(crate::notifications::DeploymentInfo {
+        name: target.name.clone(),
+        namespace: target.namespace.clone(),
+        current_image: spec.current_image.clone(),
+        new_image: spec.new_image.clone(),
+        container: spec.container_name.clone(),
+        resource_kind: Some(target.kind.clone()),
+    });
+
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[&target.namespace, &target.kind])
+        .inc();
+
+    Ok(())
+}
+
 async fn execute_deployment_update(
     client: &Client,
     update_request: &UpdateRequest,
@@ -435,17 +2261,16 @@ async fn execute_deployment_update(
         .and_then(|s| s.template.spec.as_ref())
         .ok_or_else(|| anyhow::anyhow!("Deployment has no pod spec"))?;
 
-    let container_exists = pod_spec
-        .containers
-        .iter()
-        .any(|c| c.name == *container_name);
-
-    if !container_exists {
-        return Err(anyhow::anyhow!(
-            "Container '{}' not found in deployment {}",
-            container_name,
-            target.name
-        ));
+    // Grouped containers (headwind.sh/update-group) are updated in the same patch
+    let images = spec.container_images();
+    for (name, _) in &images {
+        if !pod_spec.containers.iter().any(|c| c.name == *name) {
+            return Err(anyhow::anyhow!(
+                "Container '{}' not found in deployment {}",
+                name,
+                target.name
+            ));
+        }
     }
 
     // Get auto-rollback config from deployment annotations
@@ -463,19 +2288,40 @@ async fn execute_deployment_update(
         .find(|c| c.name == *container_name)
         .and_then(|c| c.image.as_ref())
         .cloned();
+    let rollback_images: Vec<(String, String)> = images
+        .iter()
+        .filter_map(|(name, _)| {
+            pod_spec
+                .containers
+                .iter()
+                .find(|c| c.name == *name)
+                .and_then(|c| c.image.clone())
+                .map(|image| (name.clone(), image))
+        })
+        .collect();
 
     // Call the update function with tracking metadata
-    update_deployment_image_with_tracking(
+    update_deployment_images_with_tracking(
         client.clone(),
         &target.namespace,
         &target.name,
-        container_name,
-        &spec.new_image,
+        &images,
         update_request_name.clone(),
         approved_by.clone(),
     )
     .await?;
 
+    // Mirror the rollout into the UpdateRequest's status until it completes or fails
+    if let Some(name) = &update_request_name {
+        track_rollout(
+            client.clone(),
+            target.namespace.clone(),
+            target.name.clone(),
+            update_request.namespace().unwrap_or_default(),
+            name.clone(),
+        );
+    }
+
     // If auto-rollback is enabled, spawn a background task to monitor health
     if enable_auto_rollback && auto_rollback_config.enabled {
         let client_clone = client.clone();
@@ -484,14 +2330,32 @@ async fn execute_deployment_update(
         let container_name_clone = container_name.clone();
         let new_image = spec.new_image.clone();
         let current_image_clone = current_image.clone();
+        let updated_images = images.clone();
+        let conditions_client = client.clone();
+        let update_request_namespace = update_request.namespace().unwrap_or_default();
 
         tokio::spawn(async move {
+            let record = |type_: &'static str, status: bool, reason: &'static str, message| {
+                record_condition(
+                    &conditions_client,
+                    &update_request_namespace,
+                    update_request_name.as_deref(),
+                    type_,
+                    status,
+                    reason,
+                    message,
+                )
+            };
             info!(
                 "Auto-rollback enabled for {}/{}, monitoring deployment health...",
                 namespace, deployment_name
             );
 
-            let health_checker = HealthChecker::new(client_clone.clone());
+            let health_checker = if auto_rollback_config.metric_gates.is_empty() {
+                HealthChecker::new(client_clone.clone())
+            } else {
+                HealthChecker::with_metrics_backend(client_clone.clone()).await
+            };
             match health_checker
                 .monitor_deployment_health(&deployment_name, &namespace, &auto_rollback_config)
                 .await
@@ -501,12 +2365,29 @@ async fn execute_deployment_update(
                         "Deployment {}/{} is healthy after update to {}",
                         namespace, deployment_name, new_image
                     );
+                    record(conditions::HEALTH_CHECKED, true, "Healthy", None).await;
+                    if let Err(e) = RollbackManager::new(client_clone)
+                        .mark_healthy(&deployment_name, &namespace, &updated_images)
+                        .await
+                    {
+                        warn!(
+                            "Failed to record last known good of {}/{}: {}",
+                            namespace, deployment_name, e
+                        );
+                    }
                 },
                 Ok(HealthStatus::Failed(reason)) => {
                     error!(
                         "Automatic rollback triggered for {}/{}: {}",
                         namespace, deployment_name, reason
                     );
+                    record(
+                        conditions::HEALTH_CHECKED,
+                        false,
+                        "Unhealthy",
+                        Some(reason.clone()),
+                    )
+                    .await;
 
                     // Send rollback trigger notification
                     let deployment_info = DeploymentInfo {
@@ -524,23 +2405,35 @@ async fn execute_deployment_update(
 
                     // Attempt rollback
                     if let Some(rollback_image) = current_image_clone.as_ref() {
-                        match update_deployment_image_with_tracking(
+                        let rollback_result = update_deployment_images_with_tracking(
                             client_clone,
                             &namespace,
                             &deployment_name,
-                            &container_name_clone,
-                            rollback_image,
+                            &rollback_images,
                             None,
                             Some("headwind-auto-rollback".to_string()),
                         )
-                        .await
-                        {
+                        .await;
+                        audit::record(
+                            UpdateAuditSpec::new(AuditAction::RolledBack, &deployment_info)
+                                .with_source("rollback")
+                                .with_message(reason.clone())
+                                .with_result(&rollback_result),
+                        );
+                        match rollback_result {
                             Ok(()) => {
                                 info!(
                                     "Successfully rolled back {}/{} from {} to {}",
                                     namespace, deployment_name, new_image, rollback_image
                                 );
                                 notifications::notify_rollback_completed(deployment_info.clone());
+                                record(
+                                    conditions::ROLLED_BACK,
+                                    true,
+                                    "HealthCheckFailed",
+                                    Some(format!("Rolled back to {}", rollback_image)),
+                                )
+                                .await;
                             },
                             Err(e) => {
                                 error!(
@@ -551,6 +2444,13 @@ async fn execute_deployment_update(
                                     deployment_info.clone(),
                                     e.to_string(),
                                 );
+                                record(
+                                    conditions::ROLLED_BACK,
+                                    false,
+                                    "RollbackFailed",
+                                    Some(e.to_string()),
+                                )
+                                .await;
                             },
                         }
                     } else {
@@ -565,6 +2465,13 @@ async fn execute_deployment_update(
                         "Automatic rollback triggered for {}/{}: Health check timeout",
                         namespace, deployment_name
                     );
+                    record(
+                        conditions::HEALTH_CHECKED,
+                        false,
+                        "HealthCheckTimeout",
+                        None,
+                    )
+                    .await;
 
                     // Send rollback trigger notification
                     let deployment_info = DeploymentInfo {
@@ -582,23 +2489,35 @@ async fn execute_deployment_update(
 
                     // Attempt rollback
                     if let Some(rollback_image) = current_image_clone.as_ref() {
-                        match update_deployment_image_with_tracking(
+                        let rollback_result = update_deployment_images_with_tracking(
                             client_clone,
                             &namespace,
                             &deployment_name,
-                            &container_name_clone,
-                            rollback_image,
+                            &rollback_images,
                             None,
                             Some("headwind-auto-rollback".to_string()),
                         )
-                        .await
-                        {
+                        .await;
+                        audit::record(
+                            UpdateAuditSpec::new(AuditAction::RolledBack, &deployment_info)
+                                .with_source("rollback")
+                                .with_message("Health check timeout")
+                                .with_result(&rollback_result),
+                        );
+                        match rollback_result {
                             Ok(()) => {
                                 info!(
                                     "Successfully rolled back {}/{} from {} to {} due to timeout",
                                     namespace, deployment_name, new_image, rollback_image
                                 );
                                 notifications::notify_rollback_completed(deployment_info.clone());
+                                record(
+                                    conditions::ROLLED_BACK,
+                                    true,
+                                    "HealthCheckFailed",
+                                    Some(format!("Rolled back to {}", rollback_image)),
+                                )
+                                .await;
                             },
                             Err(e) => {
                                 error!(
@@ -609,6 +2528,13 @@ async fn execute_deployment_update(
                                     deployment_info.clone(),
                                     e.to_string(),
                                 );
+                                record(
+                                    conditions::ROLLED_BACK,
+                                    false,
+                                    "RollbackFailed",
+                                    Some(e.to_string()),
+                                )
+                                .await;
                             },
                         }
                     } else {
@@ -631,11 +2557,13 @@ async fn execute_deployment_update(
                     );
                 },
             }
-        });
+        }.in_current_span());
     }
 
     // Increment metrics
-    crate::metrics::UPDATES_APPLIED.inc();
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[&target.namespace, &target.kind])
+        .inc();
 
     Ok(())
 }
@@ -678,6 +2606,9 @@ pub async fn update_helmrelease_chart_version(
         ));
     }
 
+    // Make sure the release's values still fit the new chart
+    crate::helm::schema::check_upgrade(client, &helm_release, new_version).await?;
+
     // Prepare the patch to update the chart version
     let patch = json!({
         "spec": {
@@ -710,47 +2641,293 @@ pub async fn update_helmrelease_chart_version(
         resource_kind: Some("HelmRelease".to_string()),
     };
 
-    crate::notifications::notify_update_completed(deployment_info);
+    crate::notifications::notify_update_completed(deployment_info);
+
+    // Increment metrics
+    crate::metrics::HELM_UPDATES_APPLIED
+        .with_label_values(&[namespace])
+        .inc();
+
+    Ok(())
+}
+
+/// Update an image pinned at a values path of a HelmRelease
+pub async fn update_helmrelease_values_image(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    values_path: &str,
+    current_image: &str,
+    new_image: &str,
+) -> Result<()> {
+    use crate::models::HelmRelease;
+    use kube::api::{Patch, PatchParams};
+
+    debug!(
+        "Updating HelmRelease {}/{} values path .{} from {} to {}",
+        namespace, name, values_path, current_image, new_image
+    );
+
+    let helm_releases: Api<HelmRelease> = Api::namespaced(client.clone(), namespace);
+    let helm_release = helm_releases.get(name).await?;
+
+    let patch = crate::helm::values::image_patch(
+        helm_release.spec.values.as_ref(),
+        values_path,
+        new_image,
+    )?;
+    helm_releases
+        .patch(
+            name,
+            &PatchParams::default(),
+            &Patch::Json::<()>(serde_json::from_value(patch)?),
+        )
+        .await?;
+
+    info!(
+        "Successfully updated HelmRelease {}/{} values path .{} to image {}",
+        namespace, name, values_path, new_image
+    );
+
+    crate::notifications::notify_update_completed(crate::notifications::DeploymentInfo {
+        name: name.to_string(),
+        namespace: namespace.to_string(),
+        current_image: current_image.to_string(),
+        new_image: new_image.to_string(),
+        container: None,
+        resource_kind: Some("HelmRelease".to_string()),
+    });
+
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[namespace, "HelmRelease"])
+        .inc();
+
+    Ok(())
+}
+
+async fn execute_helmrelease_update(
+    client: &Client,
+    update_request: &UpdateRequest,
+    _update_request_name: Option<String>,
+    _approved_by: Option<String>,
+) -> Result<()> {
+    use crate::models::crd::UpdateType;
+
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+
+    // Image updates carry the values path holding the image
+    if spec.update_type == UpdateType::Image {
+        let values_path = spec
+            .image_parameter
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Image update for a HelmRelease has no values path"))?;
+        return update_helmrelease_values_image(
+            client,
+            &target.namespace,
+            &target.name,
+            values_path,
+            &spec.current_image,
+            &spec.new_image,
+        )
+        .await;
+    }
+
+    // Extract chart name and new version from the new_image field (format: "chart:version")
+    let (chart_name, new_version) = spec
+        .new_image
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Invalid chart version format in new_image"))?;
+
+    let (_, current_version) = spec
+        .current_image
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Invalid chart version format in current_image"))?;
+
+    // Call the reusable function
+    update_helmrelease_chart_version(
+        client,
+        &target.namespace,
+        &target.name,
+        chart_name,
+        current_version,
+        new_version,
+    )
+    .await
+}
+
+/// Upgrade a release installed by the Helm CLI through a `helm upgrade` Job
+async fn execute_helm_cli_release_update(
+    client: &Client,
+    update_request: &UpdateRequest,
+) -> Result<()> {
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+
+    // Chart versions are stored as "chart:version"
+    let (chart_name, new_version) = spec
+        .new_image
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Invalid chart version format in new_image"))?;
+    let (_, current_version) = spec
+        .current_image
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Invalid chart version format in current_image"))?;
+
+    crate::controller::plainhelm::upgrade_release(
+        client,
+        &target.namespace,
+        &target.name,
+        chart_name,
+        current_version,
+        new_version,
+    )
+    .await
+}
+
+async fn execute_application_update(client: &Client, update_request: &UpdateRequest) -> Result<()> {
+    use crate::controller::argocd;
+    use crate::models::crd::UpdateType;
+
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+
+    match spec.update_type {
+        UpdateType::HelmChart => {
+            // Chart versions are stored as "chart:version"
+            let (chart_name, new_version) = spec
+                .new_image
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid chart version format in new_image"))?;
+            let (_, current_version) = spec
+                .current_image
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid chart version format in current_image"))?;
+
+            argocd::update_application_chart_version(
+                client,
+                &target.namespace,
+                &target.name,
+                chart_name,
+                current_version,
+                new_version,
+            )
+            .await
+        },
+        UpdateType::Image => {
+            argocd::update_application_image(
+                client,
+                &target.namespace,
+                &target.name,
+                spec.image_parameter
+                    .as_deref()
+                    .unwrap_or(argocd::DEFAULT_IMAGE_PARAMETER),
+                &spec.new_image,
+            )
+            .await?;
+
+            info!(
+                "Successfully updated Application {}/{} to image {}",
+                target.namespace, target.name, spec.new_image
+            );
+
+            crate::notifications::notify_update_completed(crate::notifications::DeploymentInfo {
+                name: target.name.clone(),
+                namespace: target.namespace.clone(),
+                current_image: spec.current_image.clone(),
+                new_image: spec.new_image.clone(),
+                container: spec.container_name.clone(),
+                resource_kind: Some("Application".to_string()),
+            });
+
+            Ok(())
+        },
+    }
+}
+
+async fn execute_kustomization_update(
+    client: &Client,
+    update_request: &UpdateRequest,
+) -> Result<()> {
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+
+    crate::controller::kustomization::update_kustomization_image(
+        client,
+        &target.namespace,
+        &target.name,
+        &spec.new_image,
+    )
+    .await?;
 
-    // Increment metrics
-    crate::metrics::HELM_UPDATES_APPLIED.inc();
+    info!(
+        "Successfully updated Kustomization {}/{} to image {}",
+        target.namespace, target.name, spec.new_image
+    );
+
+    crate::notifications::notify_update_completed(crate::notifications::DeploymentInfo {
+        name: target.name.clone(),
+        namespace: target.namespace.clone(),
+        current_image: spec.current_image.clone(),
+        new_image: spec.new_image.clone(),
+        container: spec.container_name.clone(),
+        resource_kind: Some("Kustomization".to_string()),
+    });
 
     Ok(())
 }
 
-async fn execute_helmrelease_update(
+async fn execute_statefulset_update(
     client: &Client,
     update_request: &UpdateRequest,
     _update_request_name: Option<String>,
-    _approved_by: Option<String>,
+    approved_by: Option<String>,
 ) -> Result<()> {
     let spec = &update_request.spec;
     let target = &spec.target_ref;
 
-    // Extract chart name and new version from the new_image field (format: "chart:version")
-    let (chart_name, new_version) = spec
-        .new_image
-        .split_once(':')
-        .ok_or_else(|| anyhow::anyhow!("Invalid chart version format in new_image"))?;
-
-    let (_, current_version) = spec
-        .current_image
-        .split_once(':')
-        .ok_or_else(|| anyhow::anyhow!("Invalid chart version format in current_image"))?;
+    info!(
+        "Executing StatefulSet update for {}/{} in namespace {}",
+        target.kind, target.name, target.namespace
+    );
 
-    // Call the reusable function
-    update_helmrelease_chart_version(
+    // Apply the update to the containers the request targets
+    let images = request_images::<StatefulSet>(client, spec).await?;
+    StatefulSet::update_images(
         client,
         &target.namespace,
         &target.name,
-        chart_name,
-        current_version,
-        new_version,
+        &images,
+        approved_by.as_deref(),
     )
-    .await
+    .await?;
+
+    info!(
+        "Successfully updated StatefulSet {}/{} to version {}",
+        target.namespace, target.name, spec.new_image
+    );
+
+    // Send success notification
+    let deployment_info = crate::notifications::DeploymentInfo {
+        name: target.name.clone(),
+        namespace: target.namespace.clone(),
+        current_image: spec.current_image.clone(),
+        new_image: spec.new_image.clone(),
+        container: spec.container_name.clone(),
+        resource_kind: Some("StatefulSet".to_string()),
+    };
+
+    crate::notifications::notify_update_completed(deployment_info);
+
+    // Increment metrics
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[&target.namespace, &target.kind])
+        .inc();
+
+    Ok(())
 }
 
-async fn execute_statefulset_update(
+async fn execute_cronjob_update(
     client: &Client,
     update_request: &UpdateRequest,
     _update_request_name: Option<String>,
@@ -760,29 +2937,23 @@ async fn execute_statefulset_update(
     let target = &spec.target_ref;
 
     info!(
-        "Executing StatefulSet update for {}/{} in namespace {}",
+        "Executing CronJob update for {}/{} in namespace {}",
         target.kind, target.name, target.namespace
     );
 
-    // Apply the update using the statefulset controller function
-    update_statefulset_image_with_tracking(
+    // Apply the update to the containers the request targets
+    let images = request_images::<CronJob>(client, spec).await?;
+    CronJob::update_images(
         client,
         &target.namespace,
         &target.name,
-        spec.current_image
-            .rsplit_once(':')
-            .map(|(image, _)| image)
-            .unwrap_or(&spec.current_image),
-        spec.new_image
-            .rsplit_once(':')
-            .map(|(_, version)| version)
-            .unwrap_or(&spec.new_image),
+        &images,
         approved_by.as_deref(),
     )
     .await?;
 
     info!(
-        "Successfully updated StatefulSet {}/{} to version {}",
+        "Successfully updated CronJob {}/{} to version {}",
         target.namespace, target.name, spec.new_image
     );
 
@@ -793,13 +2964,15 @@ async fn execute_statefulset_update(
         current_image: spec.current_image.clone(),
         new_image: spec.new_image.clone(),
         container: spec.container_name.clone(),
-        resource_kind: Some("StatefulSet".to_string()),
+        resource_kind: Some("CronJob".to_string()),
     };
 
     crate::notifications::notify_update_completed(deployment_info);
 
     // Increment metrics
-    crate::metrics::UPDATES_APPLIED.inc();
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[&target.namespace, &target.kind])
+        .inc();
 
     Ok(())
 }
@@ -818,19 +2991,13 @@ async fn execute_daemonset_update(
         target.kind, target.name, target.namespace
     );
 
-    // Apply the update using the daemonset controller function
-    update_daemonset_image_with_tracking(
+    // Apply the update to the containers the request targets
+    let images = request_images::<DaemonSet>(client, spec).await?;
+    DaemonSet::update_images(
         client,
         &target.namespace,
         &target.name,
-        spec.current_image
-            .rsplit_once(':')
-            .map(|(image, _)| image)
-            .unwrap_or(&spec.current_image),
-        spec.new_image
-            .rsplit_once(':')
-            .map(|(_, version)| version)
-            .unwrap_or(&spec.new_image),
+        &images,
         approved_by.as_deref(),
     )
     .await?;
@@ -853,21 +3020,23 @@ async fn execute_daemonset_update(
     crate::notifications::notify_update_completed(deployment_info);
 
     // Increment metrics
-    crate::metrics::UPDATES_APPLIED.inc();
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[&target.namespace, &target.kind])
+        .inc();
 
     Ok(())
 }
 
 /// Query parameters for rollback
-#[derive(Debug, Deserialize)]
-struct RollbackQuery {
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct RollbackQuery {
     /// Container name to rollback (optional, defaults to all containers)
-    container: Option<String>,
+    pub container: Option<String>,
 }
 
 /// Request body for rollback
-#[derive(Debug, Deserialize, Serialize)]
-struct RollbackRequest {
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct RollbackRequest {
     /// Container name to rollback
     pub container: String,
     /// Index of the history entry to rollback to (0 = current, 1 = previous, etc.)
@@ -879,6 +3048,30 @@ struct RollbackRequest {
     pub reason: Option<String>,
 }
 
+/// Request body for rolling back to the last known good image
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct LastGoodRollbackRequest {
+    /// Container name to rollback
+    pub container: String,
+    /// User performing the rollback
+    pub user: Option<String>,
+    /// Reason for rollback
+    pub reason: Option<String>,
+}
+
+/// Result of a successful rollback
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct RollbackResponse {
+    pub message: String,
+    pub deployment: String,
+    pub namespace: String,
+    pub container: String,
+    /// Image the container was rolled back to
+    pub image: String,
+    pub user: Option<String>,
+    pub reason: Option<String>,
+}
+
 /// Get rollback history for a deployment
 async fn get_rollback_history(
     State(state): State<ApprovalState>,
@@ -968,59 +3161,603 @@ async fn rollback_deployment(
         },
     };
 
+    roll_back_to(
+        &state.client,
+        namespace,
+        deployment,
+        request.container,
+        target_image,
+        request.user,
+        request.reason,
+    )
+    .await
+}
+
+/// Last known good entries of a deployment's containers: the newest images that passed
+/// health monitoring after an update
+async fn get_last_known_good(
+    State(state): State<ApprovalState>,
+    Path((namespace, deployment)): Path<(String, String)>,
+    Query(query): Query<RollbackQuery>,
+) -> Result<Json<Vec<UpdateHistoryEntry>>, StatusCode> {
+    let rollback_manager = RollbackManager::new(state.client);
+
+    match rollback_manager.get_history(&deployment, &namespace).await {
+        Ok(history) => Ok(Json(last_known_good_entries(
+            &history,
+            query.container.as_deref(),
+        ))),
+        Err(e) => {
+            error!(
+                "Failed to get rollback history for {}/{}: {}",
+                namespace, deployment, e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        },
+    }
+}
+
+/// The last known good entry of each container in a history, or of one container
+fn last_known_good_entries(
+    history: &UpdateHistory,
+    container: Option<&str>,
+) -> Vec<UpdateHistoryEntry> {
+    let mut containers: Vec<&str> = Vec::new();
+    for entry in history.entries() {
+        if !containers.contains(&entry.container.as_str())
+            && container.is_none_or(|c| c == entry.container)
+        {
+            containers.push(&entry.container);
+        }
+    }
+    containers
+        .into_iter()
+        .filter_map(|c| history.last_known_good(c).cloned())
+        .collect()
+}
+
+/// Rollback a deployment container to its last known good image, wherever it is in the
+/// history
+async fn rollback_to_last_good(
+    State(state): State<ApprovalState>,
+    Path((namespace, deployment)): Path<(String, String)>,
+    Json(request): Json<LastGoodRollbackRequest>,
+) -> impl IntoResponse {
+    info!(
+        "Rollback to last known good requested for {}/{} container {} by {:?}",
+        namespace,
+        deployment,
+        request.container,
+        request.user.as_deref().unwrap_or("unknown")
+    );
+
+    let target_image = match RollbackManager::new(state.client.clone())
+        .get_last_known_good(&deployment, &namespace, &request.container)
+        .await
+    {
+        Ok(Some(entry)) => entry.image,
+        Ok(None) => {
+            warn!(
+                "No last known good image for {}/{} container {}",
+                namespace, deployment, request.container
+            );
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "No last known good image recorded",
+                    "deployment": deployment,
+                    "container": request.container
+                })),
+            );
+        },
+        Err(e) => {
+            error!(
+                "Failed to get rollback history for {}/{}: {}",
+                namespace, deployment, e
+            );
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to retrieve rollback history: {}", e)})),
+            );
+        },
+    };
+
+    roll_back_to(
+        &state.client,
+        namespace,
+        deployment,
+        request.container,
+        target_image,
+        request.user,
+        request.reason,
+    )
+    .await
+}
+
+/// Set a deployment container to an image from its history, recording the rollback
+async fn roll_back_to(
+    client: &Client,
+    namespace: String,
+    deployment: String,
+    container: String,
+    target_image: String,
+    user: Option<String>,
+    reason: Option<String>,
+) -> (StatusCode, Json<serde_json::Value>) {
     info!(
         "Rolling back {}/{} container {} to image {}",
-        namespace, deployment, request.container, target_image
+        namespace, deployment, container, target_image
     );
 
+    // The newest history entry is the image being rolled back from
+    let current_image = RollbackManager::new(client.clone())
+        .get_image_by_index(&deployment, &namespace, &container, 0)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+
     // Perform the rollback
     let rollback_result = update_deployment_image_with_tracking(
-        state.client.clone(),
+        client.clone(),
         &namespace,
         &deployment,
-        &request.container,
+        &container,
         &target_image,
         None, // No UpdateRequest for manual rollbacks
-        request.user.clone(),
+        user.clone(),
     )
     .await;
 
+    let mut audit_entry = UpdateAuditSpec::new(
+        AuditAction::RolledBack,
+        &DeploymentInfo {
+            name: deployment.clone(),
+            namespace: namespace.clone(),
+            current_image,
+            new_image: target_image.clone(),
+            container: Some(container.clone()),
+            resource_kind: None,
+        },
+    )
+    .with_actor(user.as_deref())
+    .with_source("api");
+    if let Some(reason) = &reason {
+        audit_entry = audit_entry.with_message(reason.clone());
+    }
+    audit::record(audit_entry.with_result(&rollback_result));
+
     match rollback_result {
         Ok(()) => {
             info!(
                 "Successfully rolled back {}/{} container {} to {}",
-                namespace, deployment, request.container, target_image
+                namespace, deployment, container, target_image
             );
             (
                 StatusCode::OK,
-                Json(json!({
-                    "message": "Rollback successful",
-                    "deployment": deployment,
-                    "namespace": namespace,
-                    "container": request.container,
-                    "image": target_image,
-                    "user": request.user,
-                    "reason": request.reason
+                Json(json!(RollbackResponse {
+                    message: "Rollback successful".to_string(),
+                    deployment,
+                    namespace,
+                    container,
+                    image: target_image,
+                    user,
+                    reason,
                 })),
             )
         },
         Err(e) => {
             error!(
                 "Failed to rollback {}/{} container {}: {}",
-                namespace, deployment, request.container, e
+                namespace, deployment, container, e
             );
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({
                     "error": format!("Rollback failed: {}", e),
                     "deployment": deployment,
-                    "container": request.container
+                    "container": container
                 })),
             )
         },
     }
 }
 
-async fn health_check() -> impl IntoResponse {
-    (StatusCode::OK, "OK")
+/// Body of `POST /api/v1/policy/evaluate`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyEvaluationRequest {
+    /// Version the workload runs, e.g. `1.2.3` or `1.2.3@sha256:...`
+    pub current_version: String,
+    /// Version to evaluate
+    pub candidate_version: String,
+    /// Policy annotations as set on the workload (`headwind.sh/policy`, `headwind.sh/pattern`,
+    /// `headwind.sh/ignore-pattern`, `headwind.sh/max-version`, ...)
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+}
+
+/// Decision of `POST /api/v1/policy/evaluate`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PolicyEvaluationResponse {
+    /// Policy the annotations resolve to
+    pub policy: UpdatePolicy,
+    #[serde(flatten)]
+    pub evaluation: Evaluation,
+}
+
+/// Evaluate a policy for one version and explain which rule decided, the way the
+/// controllers would when the version is detected
+async fn evaluate_policy(
+    Json(request): Json<PolicyEvaluationRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let policy = match crate::webhook::parse_policy_from_annotations(&request.annotations) {
+        Ok(policy) => policy,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Invalid policy: {}", e)})),
+            );
+        },
+    };
+
+    match PolicyEngine.evaluate(
+        &policy,
+        &request.current_version,
+        &request.candidate_version,
+    ) {
+        Ok(evaluation) => (
+            StatusCode::OK,
+            Json(json!(PolicyEvaluationResponse {
+                policy: policy.policy,
+                evaluation,
+            })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("{:#}", e)})),
+        ),
+    }
+}
+
+/// Report invalid and conflicting `headwind.sh/*` annotations in the cluster
+async fn lint_annotations(
+    State(state): State<ApprovalState>,
+    Query(query): Query<LintQuery>,
+) -> Result<Json<LintReport>, StatusCode> {
+    let context = LintContext::current();
+    match crate::lint::lint_cluster(&state.client, query.namespace.as_deref(), &context).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            error!("Failed to lint annotations: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        },
+    }
+}
+
+/// List audit records, newest first
+async fn list_audit(
+    State(state): State<ApprovalState>,
+    Query(query): Query<audit::AuditQuery>,
+) -> Result<Json<Vec<UpdateAudit>>, StatusCode> {
+    match audit::list_audit_records(&state.client, &query).await {
+        Ok(records) => Ok(Json(records)),
+        Err(e) => {
+            error!("Failed to list audit records: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{TargetRef, UpdatePolicyType, UpdateRequestSpec, UpdateType};
+    use k8s_openapi::api::core::v1::Container;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+
+    fn update_request(name: &str, current: &str, new: &str) -> UpdateRequest {
+        let mut ur = UpdateRequest::new(
+            name,
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "web".to_string(),
+                    namespace: "default".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: Some("app".to_string()),
+                image_parameter: None,
+                current_image: current.to_string(),
+                new_image: new.to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at: None,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        );
+        ur.metadata.namespace = Some("default".to_string());
+        ur
+    }
+
+    fn approval(new: Option<&str>, current: Option<&str>) -> SimpleApprovalRequest {
+        SimpleApprovalRequest {
+            approver: Some("alice".to_string()),
+            expected_new_image: new.map(String::from),
+            expected_current_image: current.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_last_known_good_entries() {
+        let mut history = UpdateHistory::new();
+        for (container, image, healthy) in [
+            ("app", "web:1.0", true),
+            ("sidecar", "proxy:2.0", false),
+            ("app", "web:1.1", false),
+        ] {
+            history.add_entry(UpdateHistoryEntry {
+                container: container.to_string(),
+                image: image.to_string(),
+                timestamp: chrono::Utc::now(),
+                update_request_name: None,
+                approved_by: None,
+                healthy,
+            });
+        }
+
+        let entries = last_known_good_entries(&history, None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].image, "web:1.0");
+        assert_eq!(last_known_good_entries(&history, Some("app")).len(), 1);
+        assert!(last_known_good_entries(&history, Some("sidecar")).is_empty());
+    }
+
+    #[test]
+    fn test_approval_request_without_preconditions() {
+        let request: SimpleApprovalRequest =
+            serde_json::from_str(r#"{"approver": "alice"}"#).unwrap();
+        assert!(!request.has_preconditions());
+
+        let ur = update_request("web-app-1-26-0", "nginx:1.25.0", "nginx:1.26.0");
+        assert!(check_expected_versions(&ur, &request).is_ok());
+    }
+
+    #[test]
+    fn test_check_expected_versions() {
+        let ur = update_request("web-app-1-26-0", "nginx:1.25.0", "nginx:1.26.0");
+
+        assert!(
+            check_expected_versions(&ur, &approval(Some("nginx:1.26.0"), Some("nginx:1.25.0")))
+                .is_ok()
+        );
+        assert!(check_expected_versions(&ur, &approval(Some("nginx:1.27.0"), None)).is_err());
+        assert!(check_expected_versions(&ur, &approval(None, Some("nginx:1.24.0"))).is_err());
+    }
+
+    #[test]
+    fn test_check_target_drift() {
+        let ur = update_request("web-app-1-26-0", "nginx:1.25.0", "nginx:1.26.0");
+
+        assert!(check_target_drift(&ur, Some("nginx:1.25.0")).is_ok());
+        assert!(check_target_drift(&ur, Some("nginx:1.25.1")).is_err());
+        assert!(check_target_drift(&ur, None).is_err());
+    }
+
+    #[test]
+    fn test_is_superseded_by() {
+        let created = chrono::Utc::now() - chrono::Duration::hours(3);
+        let mut ur = update_request("web-app-1-26-0", "nginx:1.25.0", "nginx:1.26.0");
+        ur.metadata.creation_timestamp = Some(Time(created));
+
+        let mut newer = update_request("web-app-1-27-0", "nginx:1.25.0", "nginx:1.27.0");
+        newer.metadata.creation_timestamp = Some(Time(created + chrono::Duration::hours(1)));
+        assert!(is_superseded_by(&ur, &newer));
+
+        // Older requests never supersede
+        assert!(!is_superseded_by(&newer, &ur));
+
+        // Rejected requests don't supersede
+        newer.status = Some(UpdateRequestStatus {
+            phase: UpdatePhase::Rejected,
+            ..Default::default()
+        });
+        assert!(!is_superseded_by(&ur, &newer));
+
+        // Requests for another container don't supersede
+        let mut other_container = update_request("web-sidecar-1-27-0", "envoy:1.0", "envoy:1.1");
+        other_container.spec.container_name = Some("sidecar".to_string());
+        other_container.metadata.creation_timestamp =
+            Some(Time(created + chrono::Duration::hours(1)));
+        assert!(!is_superseded_by(&ur, &other_container));
+    }
+
+    #[test]
+    fn test_select_container_image() {
+        let containers = vec![
+            Container {
+                name: "app".to_string(),
+                image: Some("registry.example.com:5000/app:1.0.0".to_string()),
+                ..Default::default()
+            },
+            Container {
+                name: "sidecar".to_string(),
+                image: Some("envoy:1.28".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(
+            select_container_image(&containers, Some("sidecar"), "envoy:1.27"),
+            Some("envoy:1.28".to_string())
+        );
+        assert_eq!(
+            select_container_image(&containers, None, "registry.example.com:5000/app:0.9.0"),
+            Some("registry.example.com:5000/app:1.0.0".to_string())
+        );
+        assert_eq!(
+            select_container_image(&containers, Some("missing"), "envoy:1.27"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_add_approval_quorum() {
+        let mut approvals = Vec::new();
+
+        assert_eq!(add_approval(&mut approvals, Some("alice"), 2), Ok(false));
+        assert_eq!(add_approval(&mut approvals, Some(" bob "), 2), Ok(true));
+        assert_eq!(
+            approvals
+                .iter()
+                .map(|a| a.approver.as_str())
+                .collect::<Vec<_>>(),
+            vec!["alice", "bob"]
+        );
+    }
+
+    #[test]
+    fn test_add_approval_refuses_duplicates_and_anonymous() {
+        let mut approvals = Vec::new();
+        add_approval(&mut approvals, Some("alice"), 3).unwrap();
+
+        let (status, _) = add_approval(&mut approvals, Some("alice"), 3).unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        for approver in [None, Some(""), Some("  ")] {
+            let (status, _) = add_approval(&mut approvals, approver, 3).unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(approvals.len(), 1);
+    }
+
+    fn names(page: &UpdatesPage) -> Vec<String> {
+        page.items.iter().map(|ur| ur.name_any()).collect()
+    }
+
+    #[test]
+    fn test_select_updates_filters() {
+        let pending = update_request("web-1-1-0", "web:1.0.0", "web:1.1.0");
+        let mut completed = update_request("web-1-0-0", "web:0.9.0", "web:1.0.0");
+        completed.status = Some(UpdateRequestStatus {
+            phase: UpdatePhase::Completed,
+            ..Default::default()
+        });
+        let mut chart = update_request("redis-18-0-0", "17.0.0", "18.0.0");
+        chart.spec.target_ref.kind = "HelmRelease".to_string();
+        let all = vec![pending, completed, chart];
+
+        let query: UpdatesQuery = serde_json::from_value(json!({"phase": "pending"})).unwrap();
+        let page = select_updates(all.clone(), &query);
+        assert_eq!(names(&page), vec!["redis-18-0-0", "web-1-1-0"]);
+
+        let query: UpdatesQuery =
+            serde_json::from_value(json!({"kind": "helmrelease", "phase": ""})).unwrap();
+        assert_eq!(names(&select_updates(all, &query)), vec!["redis-18-0-0"]);
+
+        assert!(serde_json::from_value::<UpdatesQuery>(json!({"phase": "done"})).is_err());
+        assert!(serde_json::from_value::<UpdatesQuery>(json!({"sort": "size"})).is_err());
+    }
+
+    #[test]
+    fn test_select_updates_sorts_and_paginates() {
+        let all: Vec<_> = (0..5)
+            .map(|i| {
+                let mut ur = update_request(&format!("web-{}", i), "web:1.0.0", "web:1.1.0");
+                ur.metadata.creation_timestamp = Some(Time(
+                    chrono::DateTime::from_timestamp(1_700_000_000 + i * 60, 0).unwrap(),
+                ));
+                ur
+            })
+            .collect();
+
+        let query: UpdatesQuery =
+            serde_json::from_value(json!({"sort": "-created", "limit": "2"})).unwrap();
+        let page = select_updates(all.clone(), &query);
+        assert_eq!(names(&page), vec!["web-4", "web-3"]);
+        assert_eq!(page.total, 5);
+        assert_eq!(page.continue_token, Some(2));
+
+        let query: UpdatesQuery =
+            serde_json::from_value(json!({"sort": "-created", "limit": "2", "continue": "4"}))
+                .unwrap();
+        let page = select_updates(all.clone(), &query);
+        assert_eq!(names(&page), vec!["web-0"]);
+        assert_eq!(page.continue_token, None);
+
+        // Without a limit everything is returned in one page
+        let page = select_updates(all, &UpdatesQuery::default());
+        assert_eq!(page.items.len(), 5);
+        assert_eq!(page.continue_token, None);
+    }
+
+    #[test]
+    fn test_select_batch_items() {
+        let patched = update_request("web-1-1-1", "base:1.1.0", "base:1.1.1");
+        let mut other_ns = update_request("api-1-1-1", "base:1.1.0", "base:1.1.1");
+        other_ns.metadata.namespace = Some("payments".to_string());
+        let unrelated = update_request("web-2-0-0", "web:1.0.0", "web:2.0.0");
+        let mut done = update_request("worker-1-1-1", "base:1.1.0", "base:1.1.1");
+        done.status = Some(UpdateRequestStatus {
+            phase: UpdatePhase::Completed,
+            ..Default::default()
+        });
+
+        let batch: BatchRequest = serde_json::from_value(json!({
+            "action": "approve",
+            "selector": {"new_image": "base:1.1.1"},
+            "approver": "alice"
+        }))
+        .unwrap();
+        let items = select_batch_items(
+            vec![patched, other_ns, unrelated, done],
+            batch.selector.as_ref().unwrap(),
+        );
+        let names: Vec<_> = items
+            .iter()
+            .map(|i| format!("{}/{}", i.namespace, i.name))
+            .collect();
+        assert_eq!(names, vec!["default/web-1-1-1", "payments/api-1-1-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_policy() {
+        let request = |candidate: &str| PolicyEvaluationRequest {
+            current_version: "1.2.3".to_string(),
+            candidate_version: candidate.to_string(),
+            annotations: BTreeMap::from([
+                ("headwind.sh/policy".to_string(), "minor".to_string()),
+                ("headwind.sh/max-version".to_string(), "1.x".to_string()),
+            ]),
+        };
+
+        let (status, Json(body)) = evaluate_policy(Json(request("1.3.0"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["policy"], "minor");
+        assert_eq!(body["update"], true);
+        assert_eq!(body["rule"], "semver");
+
+        let (_, Json(body)) = evaluate_policy(Json(request("2.0.0"))).await;
+        assert_eq!(body["update"], false);
+        assert_eq!(body["rule"], "max-version");
+        assert!(body["explanation"].as_str().unwrap().contains("1.x"));
+
+        let (status, Json(body)) = evaluate_policy(Json(request("latest"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["rule"], "max-version");
+
+        let mut invalid = request("1.3.0");
+        invalid
+            .annotations
+            .insert("headwind.sh/policy".to_string(), "sometimes".to_string());
+        let (status, Json(body)) = evaluate_policy(Json(invalid)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+    }
 }
@@ -0,0 +1,302 @@
+//! Namespaced approval API.
+//!
+//! `/api/v1/namespaces/{namespace}/updates...` offer listing, reading, approving and rejecting
+//! UpdateRequests of one namespace, authorized by Kubernetes RBAC instead of network access:
+//! callers send their Kubernetes token as `Authorization: Bearer`, Headwind identifies them
+//! with a TokenReview and asks a SubjectAccessReview whether they may
+//!
+//! | Route | Verb | Resource |
+//! |-------|------|----------|
+//! | `GET .../updates` | `list` | `updaterequests` |
+//! | `GET .../updates/{name}` | `get` | `updaterequests` |
+//! | `POST .../updates/{name}/approve`, `.../reject` | `update` | `updaterequests/approval` |
+//!
+//! in the namespace (API group `headwind.sh`). Approvals and rejections are recorded with the
+//! authenticated user as approver, whatever the request body says.
+//!
+//! The cluster-wide `/api/v1/updates` routes aren't authenticated. With
+//! HEADWIND_APPROVAL_NAMESPACED_ONLY they only answer the operator's own requests, like the
+//! Web UI's (which checks its users itself), so other clients can only read and decide on
+//! UpdateRequests through these routes.
+
+use super::{
+    ApprovalState, SimpleApprovalRequest, SimpleRejectionRequest, UpdatesQuery, approve_update,
+    get_update, list_updates, reject_update,
+};
+use crate::server;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SubjectAccessReview, SubjectAccessReviewSpec,
+};
+use kube::{Api, Client, api::PostParams};
+use serde_json::json;
+use tracing::{debug, error, warn};
+
+/// Subresource approving and rejecting is authorized on
+pub const APPROVAL_SUBRESOURCE: &str = "approval";
+
+/// Whether the cluster-wide `/api/v1/updates` routes are kept to the operator's own requests,
+/// leaving other clients the namespaced ones (HEADWIND_APPROVAL_NAMESPACED_ONLY, default: false)
+pub fn namespaced_only() -> bool {
+    std::env::var("HEADWIND_APPROVAL_NAMESPACED_ONLY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Answer only requests of the operator itself (see [`server::is_local`]); to others the
+/// route doesn't exist
+pub async fn require_local(request: Request, next: Next) -> Response {
+    if server::is_local(request.headers()) {
+        next.run(request).await
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+/// Routes of the namespaced approval API
+pub fn routes() -> Router<ApprovalState> {
+    Router::new()
+        .route("/api/v1/namespaces/{namespace}/updates", get(list))
+        .route("/api/v1/namespaces/{namespace}/updates/{name}", get(read))
+        .route(
+            "/api/v1/namespaces/{namespace}/updates/{name}/approve",
+            post(approve),
+        )
+        .route(
+            "/api/v1/namespaces/{namespace}/updates/{name}/reject",
+            post(reject),
+        )
+}
+
+async fn list(
+    State(state): State<ApprovalState>,
+    Path(namespace): Path<String>,
+    headers: HeaderMap,
+    Query(mut query): Query<UpdatesQuery>,
+) -> Response {
+    let access = Access::new("list", None, None);
+    if let Err(response) = access.check(&state.client, &headers, &namespace).await {
+        return response;
+    }
+    query.namespace = Some(namespace);
+    list_updates(State(state), Query(query))
+        .await
+        .into_response()
+}
+
+async fn read(
+    State(state): State<ApprovalState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    let access = Access::new("get", None, Some(&name));
+    if let Err(response) = access.check(&state.client, &headers, &namespace).await {
+        return response;
+    }
+    get_update(State(state), Path((namespace, name)))
+        .await
+        .into_response()
+}
+
+async fn approve(
+    State(state): State<ApprovalState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(mut approval): Json<SimpleApprovalRequest>,
+) -> Response {
+    let access = Access::new("update", Some(APPROVAL_SUBRESOURCE), Some(&name));
+    match access.check(&state.client, &headers, &namespace).await {
+        Ok(username) => approval.approver = Some(username),
+        Err(response) => return response,
+    }
+    approve_update(State(state), Path((namespace, name)), Json(approval))
+        .await
+        .into_response()
+}
+
+async fn reject(
+    State(state): State<ApprovalState>,
+    Path((namespace, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(mut rejection): Json<SimpleRejectionRequest>,
+) -> Response {
+    let access = Access::new("update", Some(APPROVAL_SUBRESOURCE), Some(&name));
+    match access.check(&state.client, &headers, &namespace).await {
+        Ok(username) => rejection.approver = Some(username),
+        Err(response) => return response,
+    }
+    reject_update(State(state), Path((namespace, name)), Json(rejection))
+        .await
+        .into_response()
+}
+
+/// Operation on UpdateRequests a caller needs permission for
+struct Access<'a> {
+    verb: &'a str,
+    subresource: Option<&'a str>,
+    name: Option<&'a str>,
+}
+
+impl<'a> Access<'a> {
+    fn new(verb: &'a str, subresource: Option<&'a str>, name: Option<&'a str>) -> Self {
+        Self {
+            verb,
+            subresource,
+            name,
+        }
+    }
+
+    /// Authenticate the caller and check they may perform the operation in `namespace`.
+    /// Returns their username, or the 401/403 response to send.
+    async fn check(
+        &self,
+        client: &Client,
+        headers: &HeaderMap,
+        namespace: &str,
+    ) -> Result<String, Response> {
+        let token = bearer_token(headers).ok_or_else(|| {
+            failure(
+                StatusCode::UNAUTHORIZED,
+                "Missing Authorization: Bearer <token> header".to_string(),
+            )
+        })?;
+        let (username, groups) = crate::ui::auth::validate_token(token).await.map_err(|e| {
+            failure(
+                StatusCode::UNAUTHORIZED,
+                format!("Token validation failed: {}", e),
+            )
+        })?;
+
+        let review = self.review(&username, groups, namespace);
+        let api: Api<SubjectAccessReview> = Api::all(client.clone());
+        let status = match api.create(&PostParams::default(), &review).await {
+            Ok(review) => review.status.unwrap_or_default(),
+            Err(e) => {
+                error!("SubjectAccessReview failed: {}", e);
+                return Err(failure(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Authorization check failed: {}", e),
+                ));
+            },
+        };
+
+        if !status.allowed {
+            warn!(
+                "User {} may not {} {} in namespace {}",
+                username,
+                self.verb,
+                self.resource(),
+                namespace
+            );
+            let reason = status
+                .reason
+                .filter(|reason| !reason.is_empty())
+                .map(|reason| format!(": {}", reason))
+                .unwrap_or_default();
+            return Err(failure(
+                StatusCode::FORBIDDEN,
+                format!(
+                    "User {} may not {} {} in namespace {}{}",
+                    username,
+                    self.verb,
+                    self.resource(),
+                    namespace,
+                    reason
+                ),
+            ));
+        }
+
+        debug!(
+            "User {} may {} {} in namespace {}",
+            username,
+            self.verb,
+            self.resource(),
+            namespace
+        );
+        Ok(username)
+    }
+
+    /// `updaterequests` or `updaterequests/<subresource>`, as written in RBAC rules
+    fn resource(&self) -> String {
+        match self.subresource {
+            Some(subresource) => format!("updaterequests/{}", subresource),
+            None => "updaterequests".to_string(),
+        }
+    }
+
+    fn review(&self, username: &str, groups: Vec<String>, namespace: &str) -> SubjectAccessReview {
+        SubjectAccessReview {
+            spec: SubjectAccessReviewSpec {
+                user: Some(username.to_string()),
+                groups: Some(groups),
+                resource_attributes: Some(ResourceAttributes {
+                    group: Some("headwind.sh".to_string()),
+                    resource: Some("updaterequests".to_string()),
+                    subresource: self.subresource.map(str::to_string),
+                    verb: Some(self.verb.to_string()),
+                    namespace: Some(namespace.to_string()),
+                    name: self.name.map(str::to_string),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+fn failure(status: StatusCode, error: String) -> Response {
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_attributes() {
+        let access = Access::new("update", Some(APPROVAL_SUBRESOURCE), Some("web-1-2-0"));
+        let review = access.review("alice", vec!["team-a".to_string()], "team-a");
+        let attributes = review.spec.resource_attributes.unwrap();
+
+        assert_eq!(review.spec.user.as_deref(), Some("alice"));
+        assert_eq!(review.spec.groups, Some(vec!["team-a".to_string()]));
+        assert_eq!(attributes.group.as_deref(), Some("headwind.sh"));
+        assert_eq!(attributes.resource.as_deref(), Some("updaterequests"));
+        assert_eq!(attributes.subresource.as_deref(), Some("approval"));
+        assert_eq!(attributes.verb.as_deref(), Some("update"));
+        assert_eq!(attributes.namespace.as_deref(), Some("team-a"));
+        assert_eq!(attributes.name.as_deref(), Some("web-1-2-0"));
+        assert_eq!(access.resource(), "updaterequests/approval");
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(AUTHORIZATION, "Basic dXNlcg==".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(AUTHORIZATION, "Bearer abc.def".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc.def"));
+    }
+}
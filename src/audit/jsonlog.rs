@@ -0,0 +1,173 @@
+//! Structured audit log.
+//!
+//! With `HEADWIND_AUDIT_LOG` set to `stdout` or a file path, every audit entry is also written
+//! as one JSON object per line, independently of tracing and of `UpdateAudit` resources. The
+//! line format is versioned by its `schema` field (`headwind.audit/v1`); fields are never
+//! removed or renamed within a version and absent values are `null`, so the log can be fed to
+//! SIEM tooling without parsing Headwind's regular logs.
+
+use crate::models::audit::{AuditAction, AuditOutcome, UpdateAuditSpec};
+use crate::models::crd::TargetRef;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Value of the `schema` field of every line
+pub const SCHEMA: &str = "headwind.audit/v1";
+
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+
+enum Sink {
+    Stdout,
+    File(File),
+}
+
+/// One line of the audit log
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditLogLine<'a> {
+    schema: &'static str,
+    timestamp: DateTime<Utc>,
+    action: AuditAction,
+    outcome: AuditOutcome,
+    actor: &'a str,
+    source: &'a str,
+    cluster: Option<&'a str>,
+    target: &'a TargetRef,
+    container: Option<&'a str>,
+    current_image: &'a str,
+    new_image: &'a str,
+    update_request: Option<&'a str>,
+    message: Option<&'a str>,
+    correlation_id: Option<&'a str>,
+}
+
+impl<'a> From<&'a UpdateAuditSpec> for AuditLogLine<'a> {
+    fn from(entry: &'a UpdateAuditSpec) -> Self {
+        Self {
+            schema: SCHEMA,
+            timestamp: entry.timestamp,
+            action: entry.action,
+            outcome: entry.outcome,
+            actor: &entry.actor,
+            source: &entry.source,
+            cluster: crate::config::cluster::name(),
+            target: &entry.target_ref,
+            container: entry.container_name.as_deref(),
+            current_image: &entry.current_image,
+            new_image: &entry.new_image,
+            update_request: entry.update_request.as_deref(),
+            message: entry.message.as_deref(),
+            correlation_id: entry.correlation_id.as_deref(),
+        }
+    }
+}
+
+/// Open the audit log configured with `HEADWIND_AUDIT_LOG`: `stdout`, or a file that is
+/// appended to. Unset or empty disables it.
+pub fn init() {
+    let target = std::env::var("HEADWIND_AUDIT_LOG").unwrap_or_default();
+    let sink = match target.trim() {
+        "" => return,
+        "stdout" | "-" => Sink::Stdout,
+        path => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Sink::File(file),
+            Err(e) => {
+                warn!(
+                    "Failed to open audit log {}, audit log disabled: {}",
+                    path, e
+                );
+                return;
+            },
+        },
+    };
+
+    info!("Writing audit log to {}", target.trim());
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(sink);
+}
+
+/// Write `entry` to the audit log. Does nothing when the audit log is disabled.
+pub fn write(entry: &UpdateAuditSpec) {
+    let mut sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(sink) = sink.as_mut() else {
+        return;
+    };
+
+    let line = match to_line(entry) {
+        Ok(line) => line,
+        Err(e) => {
+            warn!("Failed to serialize audit log entry: {}", e);
+            return;
+        },
+    };
+    let result = match sink {
+        Sink::Stdout => std::io::stdout().lock().write_all(line.as_bytes()),
+        Sink::File(file) => file.write_all(line.as_bytes()),
+    };
+    if let Err(e) = result {
+        warn!("Failed to write audit log entry: {}", e);
+    }
+}
+
+/// `entry` as a newline-terminated JSON line
+fn to_line(entry: &UpdateAuditSpec) -> serde_json::Result<String> {
+    let mut line = serde_json::to_string(&AuditLogLine::from(entry))?;
+    line.push('\n');
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::DeploymentInfo;
+
+    #[test]
+    fn test_line_schema() {
+        let resource = DeploymentInfo {
+            name: "web".to_string(),
+            namespace: "production".to_string(),
+            current_image: "nginx:1.25.0".to_string(),
+            new_image: "nginx:1.26.0".to_string(),
+            container: Some("nginx".to_string()),
+            resource_kind: None,
+        };
+        let mut entry = UpdateAuditSpec::new(AuditAction::Approved, &resource)
+            .with_actor(Some("alice@example.com"))
+            .with_source("api")
+            .with_update_request("web-nginx-1-26-0");
+        entry.correlation_id = None;
+        entry.timestamp = "2026-03-01T12:00:00Z".parse().unwrap();
+
+        let line = to_line(&entry).unwrap();
+        assert!(line.ends_with('\n') && !line.trim_end().contains('\n'));
+
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "schema": "headwind.audit/v1",
+                "timestamp": "2026-03-01T12:00:00Z",
+                "action": "Approved",
+                "outcome": "Success",
+                "actor": "alice@example.com",
+                "source": "api",
+                "cluster": null,
+                "target": {
+                    "apiVersion": "apps/v1",
+                    "kind": "Deployment",
+                    "name": "web",
+                    "namespace": "production"
+                },
+                "container": "nginx",
+                "currentImage": "nginx:1.25.0",
+                "newImage": "nginx:1.26.0",
+                "updateRequest": "web-nginx-1-26-0",
+                "message": null,
+                "correlationId": null
+            })
+        );
+    }
+}
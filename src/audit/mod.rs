@@ -0,0 +1,409 @@
+//! Persistent audit trail of update activity.
+//!
+//! Detections, approvals, rejections, rollouts, expiries and rollbacks are each recorded as an
+//! `UpdateAudit` resource in the namespace of the workload they concern, so the trail
+//! survives restarts and is subject to Kubernetes RBAC. Records are written in the background
+//! and never block or fail the action they describe. Recording is disabled with
+//! `HEADWIND_AUDIT_ENABLED=false`, and records older than `HEADWIND_AUDIT_RETENTION_DAYS`
+//! (default 30, `0` keeps them forever) are pruned hourly. Entries can additionally be written
+//! as JSON lines to stdout or a file with `HEADWIND_AUDIT_LOG` (see [`jsonlog`]).
+
+pub mod jsonlog;
+
+use crate::models::audit::{AuditAction, AuditOutcome, UpdateAudit, UpdateAuditSpec};
+use crate::models::crd::{TargetRef, UpdateRequestSpec};
+use crate::notifications::DeploymentInfo;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use kube::api::{ListParams, ObjectMeta, PostParams};
+use kube::{Api, Client, ResourceExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Actor recorded for actions Headwind takes on its own
+pub const SYSTEM_ACTOR: &str = "headwind";
+
+/// How often old records are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// Records returned by a query without a limit
+const DEFAULT_QUERY_LIMIT: usize = 100;
+
+static AUDIT_CLIENT: RwLock<Option<Client>> = RwLock::new(None);
+
+/// Enable audit recording with the given client, unless `HEADWIND_AUDIT_ENABLED=false`, and
+/// open the audit log configured with `HEADWIND_AUDIT_LOG`
+pub fn init_audit(client: Client) {
+    jsonlog::init();
+
+    let enabled = std::env::var("HEADWIND_AUDIT_ENABLED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true);
+    if !enabled {
+        info!("Audit trail disabled");
+        return;
+    }
+
+    *AUDIT_CLIENT.write().unwrap_or_else(|e| e.into_inner()) = Some(client);
+}
+
+impl UpdateAuditSpec {
+    /// A successful automatic action on the resource described by `resource`
+    pub fn new(action: AuditAction, resource: &DeploymentInfo) -> Self {
+        let kind = resource.resource_kind.as_deref().unwrap_or("Deployment");
+        Self {
+            action,
+            target_ref: TargetRef {
+                api_version: api_version(kind).to_string(),
+                kind: kind.to_string(),
+                name: resource.name.clone(),
+                namespace: resource.namespace.clone(),
+            },
+            container_name: resource.container.clone(),
+            current_image: resource.current_image.clone(),
+            new_image: resource.new_image.clone(),
+            actor: SYSTEM_ACTOR.to_string(),
+            source: "controller".to_string(),
+            outcome: AuditOutcome::Success,
+            update_request: None,
+            message: None,
+            correlation_id: crate::telemetry::correlation_id(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// A successful automatic action on the target of an UpdateRequest
+    pub fn for_update_request(action: AuditAction, spec: &UpdateRequestSpec) -> Self {
+        Self {
+            action,
+            target_ref: spec.target_ref.clone(),
+            container_name: spec.container_name.clone(),
+            current_image: spec.current_image.clone(),
+            new_image: spec.new_image.clone(),
+            actor: SYSTEM_ACTOR.to_string(),
+            source: "controller".to_string(),
+            outcome: AuditOutcome::Success,
+            update_request: None,
+            message: None,
+            correlation_id: crate::telemetry::correlation_id(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    pub fn with_actor(mut self, actor: Option<&str>) -> Self {
+        self.actor = actor
+            .filter(|a| !a.is_empty())
+            .unwrap_or("unknown")
+            .to_string();
+        self
+    }
+
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = source.to_string();
+        self
+    }
+
+    pub fn with_update_request(mut self, name: impl Into<String>) -> Self {
+        self.update_request = Some(name.into());
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Mark the action as failed with the given error
+    pub fn failed(mut self, error: impl Into<String>) -> Self {
+        self.outcome = AuditOutcome::Failure;
+        self.message = Some(error.into());
+        self
+    }
+
+    /// Record a successful outcome, or a failure with the error
+    pub fn with_result<T, E: std::fmt::Display>(self, result: &Result<T, E>) -> Self {
+        match result {
+            Ok(_) => self,
+            Err(e) => self.failed(e.to_string()),
+        }
+    }
+}
+
+pub(crate) fn api_version(kind: &str) -> &'static str {
+    match kind {
+        "CronJob" => "batch/v1",
+        "HelmRelease" => "helm.toolkit.fluxcd.io/v2",
+        "Application" => "argoproj.io/v1alpha1",
+        "Kustomization" => "kustomize.toolkit.fluxcd.io/v1",
+        "Release" => "helm.sh/v3",
+        _ => "apps/v1",
+    }
+}
+
+/// Record an audit entry in the background and write it to the audit log. Does nothing when
+/// both are disabled.
+pub fn record(entry: UpdateAuditSpec) {
+    jsonlog::write(&entry);
+
+    let client = AUDIT_CLIENT
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let Some(client) = client else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let namespace = entry.target_ref.namespace.clone();
+        let audit = UpdateAudit {
+            metadata: ObjectMeta {
+                generate_name: Some(generate_name(&entry)),
+                namespace: Some(namespace.clone()),
+                ..Default::default()
+            },
+            spec: entry,
+        };
+
+        let api: Api<UpdateAudit> = Api::namespaced(client, &namespace);
+        match api.create(&PostParams::default(), &audit).await {
+            Ok(_) => {},
+            Err(kube::Error::Api(e)) if e.code == 404 => {
+                debug!("UpdateAudit CRD not installed, audit entry not stored");
+            },
+            Err(e) => warn!(
+                "Failed to store audit entry for {} {}/{}: {}",
+                audit.spec.target_ref.kind, namespace, audit.spec.target_ref.name, e
+            ),
+        }
+    });
+}
+
+/// Name prefix for a record: `<target>-<action>-`, to which the API server appends a suffix
+fn generate_name(entry: &UpdateAuditSpec) -> String {
+    let target: String = entry.target_ref.name.chars().take(200).collect();
+    format!(
+        "{}-{}-",
+        target.trim_end_matches('-'),
+        entry.action.as_str().to_lowercase()
+    )
+}
+
+/// Filters for listing audit records. Empty parameters (as submitted by the UI's filter
+/// form) are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AuditQuery {
+    /// Only records in this namespace
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub namespace: Option<String>,
+    /// Only records of this action
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub action: Option<AuditAction>,
+    /// Only records for targets with this name
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub target: Option<String>,
+    /// Only records by this actor
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub actor: Option<String>,
+    /// Maximum number of records, newest first (default 100)
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub limit: Option<usize>,
+}
+
+pub(crate) fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(v) => v.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+impl AuditQuery {
+    fn matches(&self, spec: &UpdateAuditSpec) -> bool {
+        self.action.is_none_or(|action| spec.action == action)
+            && self
+                .target
+                .as_deref()
+                .is_none_or(|target| spec.target_ref.name == target)
+            && self
+                .actor
+                .as_deref()
+                .is_none_or(|actor| spec.actor == actor)
+    }
+}
+
+/// List audit records matching `query`, newest first
+pub async fn list_audit_records(client: &Client, query: &AuditQuery) -> Result<Vec<UpdateAudit>> {
+    let api: Api<UpdateAudit> = match query.namespace.as_deref() {
+        Some(namespace) => Api::namespaced(client.clone(), namespace),
+        None => Api::all(client.clone()),
+    };
+    let records = api.list(&ListParams::default()).await?.items;
+    Ok(select_records(records, query))
+}
+
+fn select_records(records: Vec<UpdateAudit>, query: &AuditQuery) -> Vec<UpdateAudit> {
+    let mut records: Vec<_> = records
+        .into_iter()
+        .filter(|r| query.matches(&r.spec))
+        .collect();
+    records.sort_by_key(|record| std::cmp::Reverse(record.spec.timestamp));
+    records.truncate(query.limit.unwrap_or(DEFAULT_QUERY_LIMIT));
+    records
+}
+
+/// Prune records older than the retention period every hour
+pub fn start_audit_pruner(client: Client) -> JoinHandle<()> {
+    let retention_days = std::env::var("HEADWIND_AUDIT_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+
+    tokio::spawn(async move {
+        if retention_days <= 0 {
+            info!("Audit records are kept forever");
+            return;
+        }
+        let retention = chrono::Duration::days(retention_days);
+
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if AUDIT_CLIENT
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .is_none()
+            {
+                continue;
+            }
+            if let Err(e) = prune(&client, Utc::now() - retention).await {
+                warn!("Failed to prune audit records: {}", e);
+            }
+        }
+    })
+}
+
+async fn prune(client: &Client, cutoff: DateTime<Utc>) -> Result<()> {
+    let api: Api<UpdateAudit> = Api::all(client.clone());
+    let records = match api.list(&ListParams::default()).await {
+        Ok(list) => list.items,
+        Err(kube::Error::Api(e)) if e.code == 404 => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut pruned = 0;
+    for record in records.iter().filter(|r| r.spec.timestamp < cutoff) {
+        let namespace = record.namespace().unwrap_or_default();
+        let api: Api<UpdateAudit> = Api::namespaced(client.clone(), &namespace);
+        match api.delete(&record.name_any(), &Default::default()).await {
+            Ok(_) => pruned += 1,
+            Err(kube::Error::Api(e)) if e.code == 404 => {},
+            Err(e) => warn!(
+                "Failed to delete audit record {}/{}: {}",
+                namespace,
+                record.name_any(),
+                e
+            ),
+        }
+    }
+    if pruned > 0 {
+        info!("Pruned {} audit record(s) older than {}", pruned, cutoff);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, action: AuditAction, minutes_ago: i64) -> UpdateAudit {
+        let resource = DeploymentInfo {
+            name: name.to_string(),
+            namespace: "default".to_string(),
+            current_image: "nginx:1.25.0".to_string(),
+            new_image: "nginx:1.26.0".to_string(),
+            container: Some("nginx".to_string()),
+            resource_kind: None,
+        };
+        let mut spec = UpdateAuditSpec::new(action, &resource);
+        spec.timestamp = Utc::now() - chrono::Duration::minutes(minutes_ago);
+        UpdateAudit::new(&format!("{}-{}", name, minutes_ago), spec)
+    }
+
+    #[test]
+    fn test_entry_from_deployment_info() {
+        let entry = record("web", AuditAction::Detected, 0).spec;
+        assert_eq!(entry.target_ref.kind, "Deployment");
+        assert_eq!(entry.target_ref.api_version, "apps/v1");
+        assert_eq!(entry.actor, SYSTEM_ACTOR);
+        assert_eq!(entry.outcome, AuditOutcome::Success);
+
+        let failed = entry
+            .with_actor(Some("alice"))
+            .with_result(&Err::<(), _>("image pull failed"));
+        assert_eq!(failed.actor, "alice");
+        assert_eq!(failed.outcome, AuditOutcome::Failure);
+        assert_eq!(failed.message.as_deref(), Some("image pull failed"));
+    }
+
+    #[test]
+    fn test_query_ignores_empty_parameters() {
+        let query: AuditQuery = serde_json::from_str(
+            r#"{"namespace": "", "action": "", "target": "web", "limit": "5"}"#,
+        )
+        .unwrap();
+        assert_eq!(query.namespace, None);
+        assert_eq!(query.action, None);
+        assert_eq!(query.target.as_deref(), Some("web"));
+        assert_eq!(query.limit, Some(5));
+
+        let query: AuditQuery = serde_json::from_str(r#"{"action": "RolledBack"}"#).unwrap();
+        assert_eq!(query.action, Some(AuditAction::RolledBack));
+        assert!(serde_json::from_str::<AuditQuery>(r#"{"action": "Deleted"}"#).is_err());
+    }
+
+    #[test]
+    fn test_generate_name() {
+        let entry = record("web", AuditAction::RolledBack, 0).spec;
+        assert_eq!(generate_name(&entry), "web-rolledback-");
+    }
+
+    #[test]
+    fn test_select_records_filters_and_sorts() {
+        let records = vec![
+            record("web", AuditAction::Detected, 30),
+            record("web", AuditAction::Applied, 10),
+            record("api", AuditAction::Applied, 20),
+        ];
+
+        let all = select_records(records.clone(), &AuditQuery::default());
+        let order: Vec<_> = all.iter().map(|r| r.name_any()).collect();
+        assert_eq!(order, vec!["web-10", "api-20", "web-30"]);
+
+        let query = AuditQuery {
+            action: Some(AuditAction::Applied),
+            target: Some("web".to_string()),
+            ..Default::default()
+        };
+        let selected = select_records(records.clone(), &query);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name_any(), "web-10");
+
+        let limited = AuditQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(select_records(records, &limited).len(), 2);
+    }
+}
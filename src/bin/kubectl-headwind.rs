@@ -0,0 +1,652 @@
+//! `kubectl-headwind` - kubectl plugin for reviewing and approving Headwind updates.
+//!
+//! `list`, `history` and `lint` read UpdateRequests and workloads straight from the cluster
+//! with the current kubeconfig, or from the approval API when `--api-url` is given. `approve`,
+//! `reject` and `rollback` always go through the approval API so the operator applies,
+//! audits and notifies them exactly like approvals from the Web UI.
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use headwind::admission::annotations::Severity;
+use headwind::lint::{LintContext, LintReport};
+use headwind::models::crd::{UpdatePhase, UpdateRequest};
+use headwind::rollback::{RollbackManager, UpdateHistory};
+use k8s_openapi::api::apps::v1::Deployment;
+use kube::api::ListParams;
+use kube::config::KubeConfigOptions;
+use kube::{Api, Client, Config, ResourceExt};
+use serde_json::{Value, json};
+
+const DEFAULT_API_URL: &str = "http://localhost:8081";
+
+const USAGE: &str = "\
+kubectl-headwind - Manage Headwind image updates and rollbacks
+
+Usage:
+  kubectl headwind list [--phase <phase>] [-A]
+  kubectl headwind approve <update-request>
+  kubectl headwind reject <update-request> [reason]
+  kubectl headwind history <deployment> [--container <name>]
+  kubectl headwind rollback <deployment> [container] [--index <n>]
+  kubectl headwind lint [-A]
+
+Options:
+  -n, --namespace <namespace>   Namespace (default: namespace of the current context)
+  -A, --all-namespaces          List UpdateRequests or lint resources in all namespaces
+      --context <context>       Kubeconfig context to use
+      --api-url <url>           Headwind approval API (env HEADWIND_API_URL or HEADWIND_API_SERVICE,
+                                default http://localhost:8081)
+      --approver <name>         Approver for approve/reject/rollback (env HEADWIND_APPROVER)
+      --phase <phase>           Only list UpdateRequests in this phase (Pending, Completed, ...)
+      --container <name>        Container for history/rollback
+      --index <n>               History entry to roll back to (default 1, the previous image)
+  -o, --output <table|json>     Output format (default table)
+
+`lint` exits with an error when an annotation is invalid. Checks against Headwind's
+configuration (polling enabled, push events received) need --api-url.
+
+The approval API is usually reached through a port-forward:
+  kubectl port-forward -n headwind-system svc/headwind-api 8081:8081
+";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    #[default]
+    Table,
+    Json,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    namespace: Option<String>,
+    all_namespaces: bool,
+    context: Option<String>,
+    api_url: Option<String>,
+    approver: Option<String>,
+    output: Output,
+    phase: Option<UpdatePhase>,
+    container: Option<String>,
+    index: Option<usize>,
+    /// Command followed by its positional arguments
+    args: Vec<String>,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| -> Result<String> {
+            match inline_value {
+                Some(v) => Ok(v.to_string()),
+                None => args
+                    .next()
+                    .ok_or_else(|| anyhow!("{} requires a value", name)),
+            }
+        };
+
+        match flag.as_str() {
+            "-n" | "--namespace" => options.namespace = Some(value(&flag)?),
+            "-A" | "--all-namespaces" => options.all_namespaces = true,
+            "--context" => options.context = Some(value(&flag)?),
+            "--api-url" => options.api_url = Some(value(&flag)?),
+            "--approver" => options.approver = Some(value(&flag)?),
+            "--container" => options.container = Some(value(&flag)?),
+            "--phase" => {
+                options.phase = Some(value(&flag)?.parse().map_err(|e: String| anyhow!(e))?)
+            },
+            "--index" => {
+                options.index = Some(
+                    value(&flag)?
+                        .parse()
+                        .context("--index must be a non-negative number")?,
+                )
+            },
+            "-o" | "--output" => {
+                options.output = match value(&flag)?.as_str() {
+                    "table" => Output::Table,
+                    "json" => Output::Json,
+                    other => bail!("unknown output format '{}' (expected table or json)", other),
+                }
+            },
+            _ if flag.starts_with('-') && flag.len() > 1 => bail!("unknown flag {}", flag),
+            _ => options.args.push(arg),
+        }
+    }
+
+    if options.api_url.is_none() {
+        options.api_url = std::env::var("HEADWIND_API_URL").ok().or_else(|| {
+            std::env::var("HEADWIND_API_SERVICE")
+                .ok()
+                .map(|service| format!("http://{}", service))
+        });
+    }
+    if options.approver.is_none() {
+        options.approver = std::env::var("HEADWIND_APPROVER").ok();
+    }
+    Ok(options)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let options = parse_args(std::env::args().skip(1))?;
+    let cli = Cli::new(options).await?;
+
+    let args: Vec<&str> = cli.options.args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["list"] => cli.list().await,
+        ["approve", name] => cli.approve(name).await,
+        ["reject", name] => cli.reject(name, None).await,
+        ["reject", name, reason @ ..] => cli.reject(name, Some(reason.join(" "))).await,
+        ["history", deployment] => cli.history(deployment).await,
+        ["rollback", deployment] => cli.rollback(deployment, None).await,
+        ["rollback", deployment, container] => cli.rollback(deployment, Some(container)).await,
+        ["lint"] => cli.lint().await,
+        [] | ["help"] => {
+            print!("{}", USAGE);
+            Ok(())
+        },
+        _ => bail!("invalid arguments\n\n{}", USAGE),
+    }
+}
+
+struct Cli {
+    options: Options,
+    /// Kubernetes client, if a kubeconfig or in-cluster config is available
+    client: Option<Client>,
+    namespace: String,
+    http: reqwest::Client,
+}
+
+impl Cli {
+    async fn new(options: Options) -> Result<Self> {
+        let config = match &options.context {
+            Some(context) => Some(
+                Config::from_kubeconfig(&KubeConfigOptions {
+                    context: Some(context.clone()),
+                    ..Default::default()
+                })
+                .await
+                .with_context(|| format!("Failed to load kubeconfig context {}", context))?,
+            ),
+            None => Config::infer().await.ok(),
+        };
+
+        let namespace = options
+            .namespace
+            .clone()
+            .or_else(|| config.as_ref().map(|c| c.default_namespace.clone()))
+            .unwrap_or_else(|| "default".to_string());
+        let client = config.map(Client::try_from).transpose()?;
+
+        Ok(Self {
+            options,
+            client,
+            namespace,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    fn client(&self) -> Result<Client> {
+        self.client
+            .clone()
+            .ok_or_else(|| anyhow!("no kubeconfig found; pass --api-url to use the approval API"))
+    }
+
+    fn api_url(&self) -> &str {
+        self.options
+            .api_url
+            .as_deref()
+            .unwrap_or(DEFAULT_API_URL)
+            .trim_end_matches('/')
+    }
+
+    /// Send a request to the approval API and return the status and JSON body
+    async fn call_api(&self, request: reqwest::RequestBuilder) -> Result<(u16, Value)> {
+        let response = request.send().await.with_context(|| {
+            format!(
+                "Cannot reach the Headwind API at {}. Port-forward it with\n  \
+                 kubectl port-forward -n headwind-system svc/headwind-api 8081:8081\n\
+                 or set HEADWIND_API_URL",
+                self.api_url()
+            )
+        })?;
+        let status = response.status().as_u16();
+        let body = response.json().await.unwrap_or(Value::Null);
+        Ok((status, body))
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T> {
+        let url = reqwest::Url::parse_with_params(&format!("{}{}", self.api_url(), path), query)
+            .context("Invalid API URL")?;
+        let (status, body) = self.call_api(self.http.get(url.clone())).await?;
+        if !(200..300).contains(&status) {
+            bail!(
+                "GET {} failed with HTTP {}: {}",
+                url,
+                status,
+                error_message(&body)
+            );
+        }
+        serde_json::from_value(body).with_context(|| format!("Unexpected response from {}", url))
+    }
+
+    async fn list(&self) -> Result<()> {
+        let phase = self.options.phase.as_ref().map(|p| format!("{:?}", p));
+        let mut update_requests: Vec<UpdateRequest> = if self.options.api_url.is_some() {
+            let mut query = vec![];
+            if !self.options.all_namespaces {
+                query.push(("namespace", self.namespace.as_str()));
+            }
+            if let Some(phase) = phase.as_deref() {
+                query.push(("phase", phase));
+            }
+            self.get_json("/api/v1/updates", &query).await?
+        } else {
+            let api: Api<UpdateRequest> = if self.options.all_namespaces {
+                Api::all(self.client()?)
+            } else {
+                Api::namespaced(self.client()?, &self.namespace)
+            };
+            api.list(&ListParams::default())
+                .await
+                .context("Failed to list UpdateRequests")?
+                .items
+                .into_iter()
+                .filter(|ur| {
+                    self.options
+                        .phase
+                        .as_ref()
+                        .is_none_or(|p| phase_of(ur) == *p)
+                })
+                .collect()
+        };
+        update_requests.sort_by_key(|ur| (ur.namespace(), ur.name_any()));
+
+        if self.options.output == Output::Json {
+            println!("{}", serde_json::to_string_pretty(&update_requests)?);
+            return Ok(());
+        }
+        if update_requests.is_empty() {
+            println!("No UpdateRequests found");
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let rows = update_requests
+            .iter()
+            .map(|ur| {
+                let spec = &ur.spec;
+                vec![
+                    ur.namespace().unwrap_or_default(),
+                    ur.name_any(),
+                    format!("{}/{}", spec.target_ref.kind, spec.target_ref.name),
+                    spec.current_image.clone(),
+                    spec.new_image.clone(),
+                    format!("{:?}", phase_of(ur)),
+                    age(ur.metadata.creation_timestamp.as_ref().map(|t| t.0), now),
+                ]
+            })
+            .collect();
+        print!(
+            "{}",
+            render_table(
+                &[
+                    "NAMESPACE",
+                    "NAME",
+                    "TARGET",
+                    "CURRENT",
+                    "NEW",
+                    "PHASE",
+                    "AGE"
+                ],
+                rows
+            )
+        );
+        Ok(())
+    }
+
+    async fn approve(&self, name: &str) -> Result<()> {
+        let url = format!(
+            "{}/api/v1/updates/{}/{}/approve",
+            self.api_url(),
+            self.namespace,
+            name
+        );
+        let body = json!({ "approver": self.options.approver });
+        let (status, body) = self.call_api(self.http.post(&url).json(&body)).await?;
+        match status {
+            200 => println!("UpdateRequest {}/{} approved", self.namespace, name),
+            202 => println!(
+                "Approval recorded for {}/{} ({})",
+                self.namespace,
+                name,
+                body["message"]
+                    .as_str()
+                    .unwrap_or("waiting for more approvals")
+            ),
+            _ => bail!(
+                "Failed to approve (HTTP {}): {}",
+                status,
+                error_message(&body)
+            ),
+        }
+        self.print_json(&body)
+    }
+
+    async fn reject(&self, name: &str, reason: Option<String>) -> Result<()> {
+        let url = format!(
+            "{}/api/v1/updates/{}/{}/reject",
+            self.api_url(),
+            self.namespace,
+            name
+        );
+        let body = json!({ "approver": self.options.approver, "reason": reason });
+        let (status, body) = self.call_api(self.http.post(&url).json(&body)).await?;
+        if !(200..300).contains(&status) {
+            bail!(
+                "Failed to reject (HTTP {}): {}",
+                status,
+                error_message(&body)
+            );
+        }
+        println!("UpdateRequest {}/{} rejected", self.namespace, name);
+        self.print_json(&body)
+    }
+
+    async fn history(&self, deployment: &str) -> Result<()> {
+        let history: UpdateHistory = if self.options.api_url.is_some() {
+            let path = format!("/api/v1/rollback/{}/{}", self.namespace, deployment);
+            let mut query = vec![];
+            if let Some(container) = self.options.container.as_deref() {
+                query.push(("container", container));
+            }
+            self.get_json(&path, &query).await?
+        } else {
+            RollbackManager::new(self.client()?)
+                .get_history(deployment, &self.namespace)
+                .await?
+        };
+        let entries: Vec<_> = history
+            .entries()
+            .iter()
+            .filter(|e| {
+                self.options
+                    .container
+                    .as_deref()
+                    .is_none_or(|c| e.container == c)
+            })
+            .collect();
+
+        if self.options.output == Output::Json {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+            return Ok(());
+        }
+        if entries.is_empty() {
+            println!(
+                "No update history for deployment {}/{}",
+                self.namespace, deployment
+            );
+            return Ok(());
+        }
+
+        // INDEX is the value to pass to `rollback --index` (0 = current image)
+        let mut seen: Vec<&str> = vec![];
+        let rows = entries
+            .iter()
+            .map(|e| {
+                let index = seen.iter().filter(|c| **c == e.container).count();
+                seen.push(&e.container);
+                vec![
+                    e.container.clone(),
+                    index.to_string(),
+                    e.image.clone(),
+                    e.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    e.approved_by.clone().unwrap_or_else(|| "-".to_string()),
+                    e.update_request_name
+                        .clone()
+                        .unwrap_or_else(|| "-".to_string()),
+                ]
+            })
+            .collect();
+        print!(
+            "{}",
+            render_table(
+                &[
+                    "CONTAINER",
+                    "INDEX",
+                    "IMAGE",
+                    "UPDATED",
+                    "APPROVED BY",
+                    "UPDATE REQUEST"
+                ],
+                rows
+            )
+        );
+        Ok(())
+    }
+
+    async fn rollback(&self, deployment: &str, container: Option<&str>) -> Result<()> {
+        let container = match container.or(self.options.container.as_deref()) {
+            Some(container) => container.to_string(),
+            None => {
+                let deployments: Api<Deployment> = Api::namespaced(self.client()?, &self.namespace);
+                deployments
+                    .get(deployment)
+                    .await
+                    .with_context(|| format!("Failed to get deployment {}", deployment))?
+                    .spec
+                    .and_then(|s| s.template.spec)
+                    .and_then(|s| s.containers.into_iter().next())
+                    .map(|c| c.name)
+                    .ok_or_else(|| anyhow!("deployment {} has no containers", deployment))?
+            },
+        };
+
+        let url = format!(
+            "{}/api/v1/rollback/{}/{}",
+            self.api_url(),
+            self.namespace,
+            deployment
+        );
+        let body = json!({
+            "container": container,
+            "index": self.options.index,
+            "user": self.options.approver,
+        });
+        let (status, body) = self.call_api(self.http.post(&url).json(&body)).await?;
+        if !(200..300).contains(&status) {
+            bail!(
+                "Rollback failed (HTTP {}): {}",
+                status,
+                error_message(&body)
+            );
+        }
+        println!(
+            "Rolled back deployment {}/{} container {}",
+            self.namespace, deployment, container
+        );
+        self.print_json(&body)
+    }
+
+    async fn lint(&self) -> Result<()> {
+        let namespace = (!self.options.all_namespaces).then_some(self.namespace.as_str());
+        let report: LintReport = if self.options.api_url.is_some() {
+            let query: Vec<_> = namespace.map(|ns| ("namespace", ns)).into_iter().collect();
+            self.get_json("/api/v1/lint", &query).await?
+        } else {
+            headwind::lint::lint_cluster(&self.client()?, namespace, &LintContext::default())
+                .await?
+        };
+
+        if self.options.output == Output::Json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else if report.issues.is_empty() {
+            println!(
+                "No problems found in {} annotated resources",
+                report.resources
+            );
+        } else {
+            let rows = report
+                .issues
+                .iter()
+                .map(|issue| {
+                    vec![
+                        issue.namespace.clone(),
+                        format!("{}/{}", issue.kind, issue.name),
+                        issue.annotation.clone(),
+                        match issue.severity {
+                            Severity::Error => "error".to_string(),
+                            Severity::Warning => "warning".to_string(),
+                        },
+                        issue.message.clone(),
+                    ]
+                })
+                .collect();
+            print!(
+                "{}",
+                render_table(
+                    &["NAMESPACE", "RESOURCE", "ANNOTATION", "SEVERITY", "MESSAGE"],
+                    rows
+                )
+            );
+        }
+
+        if report.errors > 0 {
+            bail!(
+                "{} invalid annotations in {} resources",
+                report.errors,
+                report.resources
+            );
+        }
+        Ok(())
+    }
+
+    fn print_json(&self, body: &Value) -> Result<()> {
+        if self.options.output == Output::Json {
+            println!("{}", serde_json::to_string_pretty(body)?);
+        }
+        Ok(())
+    }
+}
+
+fn phase_of(update_request: &UpdateRequest) -> UpdatePhase {
+    update_request
+        .status
+        .as_ref()
+        .map(|s| s.phase.clone())
+        .unwrap_or_default()
+}
+
+fn error_message(body: &Value) -> String {
+    body["error"]
+        .as_str()
+        .map(String::from)
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Age in the style of `kubectl get` (`45s`, `12m`, `5h`, `3d`)
+fn age(timestamp: Option<DateTime<Utc>>, now: DateTime<Utc>) -> String {
+    let Some(timestamp) = timestamp else {
+        return "<unknown>".to_string();
+    };
+    let seconds = (now - timestamp).num_seconds().max(0);
+    match seconds {
+        s if s < 120 => format!("{}s", s),
+        s if s < 2 * 3600 => format!("{}m", s / 60),
+        s if s < 2 * 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
+
+/// Left-aligned columns separated by three spaces, like `kubectl get`
+fn render_table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let header = headers.iter().map(|h| h.to_string()).collect();
+    let mut out = String::new();
+    for row in std::iter::once(header).chain(rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        out.push_str(line.join("   ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args(&[
+            "reject",
+            "-n",
+            "prod",
+            "web-1-2-0",
+            "--context=staging",
+            "not",
+            "yet",
+            "-o",
+            "json",
+            "--phase",
+            "pending",
+        ]))
+        .unwrap();
+        assert_eq!(options.args, args(&["reject", "web-1-2-0", "not", "yet"]));
+        assert_eq!(options.namespace.as_deref(), Some("prod"));
+        assert_eq!(options.context.as_deref(), Some("staging"));
+        assert_eq!(options.output, Output::Json);
+        assert_eq!(options.phase, Some(UpdatePhase::Pending));
+
+        assert!(parse_args(args(&["list", "--bogus"])).is_err());
+        assert!(parse_args(args(&["list", "-n"])).is_err());
+        assert!(parse_args(args(&["list", "-o", "yaml"])).is_err());
+    }
+
+    #[test]
+    fn test_render_table() {
+        let table = render_table(
+            &["NAME", "PHASE"],
+            vec![
+                vec!["web-1-2-0".to_string(), "Pending".to_string()],
+                vec!["api".to_string(), "Completed".to_string()],
+            ],
+        );
+        assert_eq!(
+            table,
+            "NAME        PHASE\nweb-1-2-0   Pending\napi         Completed\n"
+        );
+    }
+
+    #[test]
+    fn test_age() {
+        let now = Utc::now();
+        assert_eq!(age(Some(now - chrono::Duration::seconds(30)), now), "30s");
+        assert_eq!(age(Some(now - chrono::Duration::minutes(45)), now), "45m");
+        assert_eq!(age(Some(now - chrono::Duration::hours(5)), now), "5h");
+        assert_eq!(age(Some(now - chrono::Duration::days(3)), now), "3d");
+        assert_eq!(age(None, now), "<unknown>");
+    }
+}
@@ -0,0 +1,350 @@
+//! Bounded in-memory caches with TTL expiry
+//!
+//! Long-running instances in large clusters track many images, charts and
+//! registries. The caches used for that tracking are capped in size and expire
+//! entries after a TTL so memory usage stays bounded. Every cache registers
+//! itself so operators can inspect it via `GET /api/v1/debug/caches`.
+
+use crate::metrics::{CACHE_ENTRIES, CACHE_EVICTIONS_TOTAL, CACHE_EXPIRATIONS_TOTAL};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Default maximum number of entries per cache
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// Default time-to-live for cache entries (24 hours)
+pub const DEFAULT_TTL_SECS: u64 = 86_400;
+
+/// Size and TTL limits for a cache
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheLimits {
+    /// Maximum number of entries before the oldest entries are evicted
+    pub max_entries: usize,
+    /// Entries older than this are treated as missing and purged (None = never expire)
+    pub ttl: Option<Duration>,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            ttl: Some(Duration::from_secs(DEFAULT_TTL_SECS)),
+        }
+    }
+}
+
+impl CacheLimits {
+    /// Load limits from `HEADWIND_CACHE_MAX_ENTRIES` and `HEADWIND_CACHE_TTL_SECONDS`
+    ///
+    /// A TTL of 0 disables expiry. Invalid values fall back to the defaults.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let max_entries = std::env::var("HEADWIND_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(defaults.max_entries);
+
+        let ttl = match std::env::var("HEADWIND_CACHE_TTL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => defaults.ttl,
+        };
+
+        Self { max_entries, ttl }
+    }
+}
+
+struct CacheEntry<V> {
+    value: V,
+    inserted_at: Instant,
+}
+
+/// A string-keyed cache with a maximum size and optional TTL
+///
+/// When full, inserting a new key evicts the oldest entry. Expired entries are
+/// never returned and are purged on the next insert.
+pub struct BoundedCache<V> {
+    name: &'static str,
+    limits: CacheLimits,
+    entries: HashMap<String, CacheEntry<V>>,
+    evictions: u64,
+    expirations: u64,
+}
+
+impl<V> BoundedCache<V> {
+    pub fn new(name: &'static str, limits: CacheLimits) -> Self {
+        Self {
+            name,
+            limits,
+            entries: HashMap::new(),
+            evictions: 0,
+            expirations: 0,
+        }
+    }
+
+    fn is_expired(&self, entry: &CacheEntry<V>, now: Instant) -> bool {
+        self.limits
+            .ttl
+            .is_some_and(|ttl| now.duration_since(entry.inserted_at) >= ttl)
+    }
+
+    /// Get a live (non-expired) entry
+    pub fn get(&self, key: &str) -> Option<&V> {
+        let now = Instant::now();
+        self.entries
+            .get(key)
+            .filter(|entry| !self.is_expired(entry, now))
+            .map(|entry| &entry.value)
+    }
+
+    /// Insert or replace an entry, evicting the oldest entries if the cache is full
+    pub fn insert(&mut self, key: String, value: V) {
+        let now = Instant::now();
+
+        if !self.entries.contains_key(&key) {
+            self.purge_expired_at(now);
+            while self.entries.len() >= self.limits.max_entries {
+                let Some(oldest) = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.inserted_at)
+                    .map(|(k, _)| k.clone())
+                else {
+                    break;
+                };
+                self.entries.remove(&oldest);
+                self.evictions += 1;
+                CACHE_EVICTIONS_TOTAL.inc();
+                CACHE_ENTRIES.dec();
+            }
+            CACHE_ENTRIES.inc();
+        }
+
+        self.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                inserted_at: now,
+            },
+        );
+    }
+
+    /// Remove all expired entries
+    pub fn purge_expired(&mut self) {
+        self.purge_expired_at(Instant::now());
+    }
+
+    fn purge_expired_at(&mut self, now: Instant) {
+        let Some(ttl) = self.limits.ttl else {
+            return;
+        };
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, entry| now.duration_since(entry.inserted_at) < ttl);
+        let expired = (before - self.entries.len()) as u64;
+        if expired > 0 {
+            self.expirations += expired;
+            CACHE_EXPIRATIONS_TOTAL.inc_by(expired);
+            CACHE_ENTRIES.sub(expired as i64);
+        }
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        CACHE_ENTRIES.sub(self.entries.len() as i64);
+        self.entries.clear();
+    }
+
+    /// Number of stored entries, including expired entries not yet purged
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Summarize the current state of the cache
+    pub fn stats(&self) -> CacheStats {
+        let now = Instant::now();
+        let expired = self
+            .entries
+            .values()
+            .filter(|entry| self.is_expired(entry, now))
+            .count();
+        let oldest_entry_age_seconds = self
+            .entries
+            .values()
+            .map(|entry| now.duration_since(entry.inserted_at).as_secs())
+            .max();
+
+        CacheStats {
+            name: self.name.to_string(),
+            entries: self.entries.len(),
+            expired_entries: expired,
+            max_entries: self.limits.max_entries,
+            ttl_seconds: self.limits.ttl.map(|ttl| ttl.as_secs()),
+            evictions: self.evictions,
+            expirations: self.expirations,
+            oldest_entry_age_seconds,
+        }
+    }
+}
+
+impl<V> Drop for BoundedCache<V> {
+    fn drop(&mut self) {
+        CACHE_ENTRIES.sub(self.entries.len() as i64);
+    }
+}
+
+/// Point-in-time summary of a cache, served by the debug API
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub name: String,
+    pub entries: usize,
+    /// Entries past their TTL that have not been purged yet
+    pub expired_entries: usize,
+    pub max_entries: usize,
+    pub ttl_seconds: Option<u64>,
+    /// Entries evicted because the cache was full
+    pub evictions: u64,
+    /// Entries purged because their TTL elapsed
+    pub expirations: u64,
+    pub oldest_entry_age_seconds: Option<u64>,
+}
+
+/// A cache that can report its statistics
+#[async_trait::async_trait]
+pub trait InspectableCache: Send + Sync {
+    async fn stats(&self) -> CacheStats;
+}
+
+#[async_trait::async_trait]
+impl<V: Send + Sync> InspectableCache for RwLock<BoundedCache<V>> {
+    async fn stats(&self) -> CacheStats {
+        self.read().await.stats()
+    }
+}
+
+/// A shared bounded cache
+pub type SharedCache<V> = Arc<RwLock<BoundedCache<V>>>;
+
+/// Caches are held weakly so dropped caches disappear from the debug API
+static REGISTERED_CACHES: Lazy<std::sync::RwLock<Vec<Weak<dyn InspectableCache>>>> =
+    Lazy::new(|| std::sync::RwLock::new(Vec::new()));
+
+/// Create a shared bounded cache and register it for inspection
+pub fn new_shared_cache<V: Send + Sync + 'static>(
+    name: &'static str,
+    limits: CacheLimits,
+) -> SharedCache<V> {
+    let cache = Arc::new(RwLock::new(BoundedCache::new(name, limits)));
+    if let Ok(mut registered) = REGISTERED_CACHES.write() {
+        registered.retain(|weak| weak.strong_count() > 0);
+        let inspectable: Arc<dyn InspectableCache> = cache.clone();
+        registered.push(Arc::downgrade(&inspectable));
+    }
+    cache
+}
+
+/// Collect statistics for every registered cache
+pub async fn all_cache_stats() -> Vec<CacheStats> {
+    let caches: Vec<Arc<dyn InspectableCache>> = REGISTERED_CACHES
+        .read()
+        .map(|registered| registered.iter().filter_map(Weak::upgrade).collect())
+        .unwrap_or_default();
+
+    let mut stats = Vec::with_capacity(caches.len());
+    for cache in caches {
+        stats.push(cache.stats().await);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_entries: usize, ttl: Option<Duration>) -> CacheLimits {
+        CacheLimits { max_entries, ttl }
+    }
+
+    #[test]
+    fn test_evicts_oldest_when_full() {
+        let mut cache = BoundedCache::new("test", limits(2, None));
+        cache.insert("a".to_string(), 1);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert("b".to_string(), 2);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert("c".to_string(), 3);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.get("b"), Some(&2));
+        assert_eq!(cache.get("c"), Some(&3));
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_replacing_existing_key_does_not_evict() {
+        let mut cache = BoundedCache::new("test", limits(2, None));
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        cache.insert("a".to_string(), 10);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("a"), Some(&10));
+        assert_eq!(cache.stats().evictions, 0);
+    }
+
+    #[test]
+    fn test_expired_entries_are_hidden_and_purged() {
+        let mut cache = BoundedCache::new("test", limits(10, Some(Duration::from_millis(1))));
+        cache.insert("a".to_string(), 1);
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.stats().expired_entries, 1);
+
+        cache.insert("b".to_string(), 2);
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.expirations, 1);
+        assert_eq!(stats.expired_entries, 0);
+    }
+
+    #[test]
+    fn test_stats_report_limits() {
+        let cache: BoundedCache<u32> =
+            BoundedCache::new("images", limits(5, Some(Duration::from_secs(60))));
+        let stats = cache.stats();
+        assert_eq!(stats.name, "images");
+        assert_eq!(stats.max_entries, 5);
+        assert_eq!(stats.ttl_seconds, Some(60));
+        assert_eq!(stats.entries, 0);
+        assert_eq!(stats.oldest_entry_age_seconds, None);
+    }
+
+    #[tokio::test]
+    async fn test_shared_caches_are_registered() {
+        let cache: SharedCache<u32> = new_shared_cache("registered-test", CacheLimits::default());
+        cache.write().await.insert("a".to_string(), 1);
+
+        let stats = all_cache_stats().await;
+        let entry = stats
+            .iter()
+            .find(|s| s.name == "registered-test")
+            .expect("cache should be registered");
+        assert_eq!(entry.entries, 1);
+    }
+}
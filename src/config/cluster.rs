@@ -0,0 +1,97 @@
+//! Cluster identity for multi-cluster setups.
+//!
+//! Every cluster runs its own Headwind, named with `HEADWIND_CLUSTER_NAME`. The name labels
+//! the UpdateRequests it creates (`headwind.sh/cluster`), is a constant `cluster` label on its
+//! metrics and is shown in its notifications. A hub instance lists the other clusters' Headwind
+//! deployments (agents) in `HEADWIND_HUB_AGENTS` as `name=approval API URL` pairs and shows
+//! their UpdateRequests in its Web UI next to its own (see [`crate::ui::hub`]).
+
+use std::collections::HashMap;
+
+/// Label holding the cluster an UpdateRequest was created in
+pub const CLUSTER_LABEL: &str = "headwind.sh/cluster";
+
+static NAME: once_cell::sync::Lazy<Option<String>> = once_cell::sync::Lazy::new(|| {
+    std::env::var("HEADWIND_CLUSTER_NAME")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+});
+
+static AGENTS: once_cell::sync::Lazy<Vec<Agent>> = once_cell::sync::Lazy::new(|| {
+    std::env::var("HEADWIND_HUB_AGENTS")
+        .map(|v| parse_agents(&v))
+        .unwrap_or_default()
+});
+
+/// Name of this cluster (HEADWIND_CLUSTER_NAME), if set
+pub fn name() -> Option<&'static str> {
+    NAME.as_deref()
+}
+
+/// Constant labels added to every metric: `cluster` when the cluster is named
+pub fn metric_labels() -> Option<HashMap<String, String>> {
+    name().map(|name| HashMap::from([("cluster".to_string(), name.to_string())]))
+}
+
+/// Headwind deployment of another cluster, managed from this hub
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Agent {
+    /// Cluster name, as the agent's HEADWIND_CLUSTER_NAME
+    pub name: String,
+    /// Base URL of the agent's approval API, e.g. `https://headwind.staging.example.com:8081`
+    pub api_url: String,
+}
+
+/// Agents managed from this hub (HEADWIND_HUB_AGENTS). Empty unless this instance is a hub.
+pub fn agents() -> &'static [Agent] {
+    &AGENTS
+}
+
+/// The agent of `cluster`, unless it is this cluster or unknown
+pub fn agent(cluster: &str) -> Option<&'static Agent> {
+    agents().iter().find(|agent| agent.name == cluster)
+}
+
+/// Parse `staging=https://headwind.staging:8081, prod=https://headwind.prod:8081`. Entries
+/// without a name or URL are skipped; trailing slashes are dropped from URLs.
+pub fn parse_agents(value: &str) -> Vec<Agent> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (name, url) = entry.split_once('=')?;
+            let (name, url) = (name.trim(), url.trim().trim_end_matches('/'));
+            (!name.is_empty() && !url.is_empty()).then(|| Agent {
+                name: name.to_string(),
+                api_url: url.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_agents() {
+        let agents = parse_agents(
+            "staging=https://headwind.staging:8081/, prod = http://10.0.0.5:8081,broken,=http://x",
+        );
+
+        assert_eq!(
+            agents,
+            vec![
+                Agent {
+                    name: "staging".to_string(),
+                    api_url: "https://headwind.staging:8081".to_string(),
+                },
+                Agent {
+                    name: "prod".to_string(),
+                    api_url: "http://10.0.0.5:8081".to_string(),
+                },
+            ]
+        );
+        assert!(parse_agents("").is_empty());
+    }
+}
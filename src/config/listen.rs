@@ -0,0 +1,125 @@
+//! Listen addresses of the HTTP servers in split mode.
+//!
+//! Each server binds the address of its `listen.*` key in the `headwind-config` ConfigMap,
+//! unless the matching environment variable is set:
+//!
+//! | Server       | ConfigMap key    | Environment variable     | Default        |
+//! |--------------|------------------|--------------------------|----------------|
+//! | Webhooks     | `listen.webhook` | `HEADWIND_WEBHOOK_ADDR`  | `0.0.0.0:8080` |
+//! | Approval API | `listen.api`     | `HEADWIND_API_ADDR`      | `0.0.0.0:8081` |
+//! | Web UI       | `listen.ui`      | `HEADWIND_UI_ADDR`       | `0.0.0.0:8082` |
+//! | Metrics      | `listen.metrics` | `HEADWIND_METRICS_ADDR`  | `0.0.0.0:9090` |
+//!
+//! The addresses are read once at startup; changing them requires a restart.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use tracing::warn;
+
+/// ConfigMap keys of the listen addresses and the environment variables overriding them
+pub const KEYS: [(&str, &str); 4] = [
+    ("listen.webhook", "HEADWIND_WEBHOOK_ADDR"),
+    ("listen.api", "HEADWIND_API_ADDR"),
+    ("listen.ui", "HEADWIND_UI_ADDR"),
+    ("listen.metrics", "HEADWIND_METRICS_ADDR"),
+];
+
+/// Bind address of every server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ListenConfig {
+    pub webhook: SocketAddr,
+    pub api: SocketAddr,
+    pub ui: SocketAddr,
+    pub metrics: SocketAddr,
+}
+
+impl Default for ListenConfig {
+    fn default() -> Self {
+        Self {
+            webhook: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            api: SocketAddr::from(([0, 0, 0, 0], 8081)),
+            ui: SocketAddr::from(([0, 0, 0, 0], 8082)),
+            metrics: SocketAddr::from(([0, 0, 0, 0], 9090)),
+        }
+    }
+}
+
+impl ListenConfig {
+    /// Addresses from the `listen.*` ConfigMap keys; invalid values fall back to the default
+    pub fn from_data(config_data: &BTreeMap<String, String>) -> Self {
+        let mut config = Self::default();
+        config.apply(|key, _| config_data.get(key).cloned());
+        config
+    }
+
+    /// Override addresses with the `HEADWIND_*_ADDR` environment variables that are set
+    pub fn with_env(mut self) -> Self {
+        self.apply(|_, env| std::env::var(env).ok());
+        self
+    }
+
+    fn apply(&mut self, value: impl Fn(&str, &str) -> Option<String>) {
+        let [webhook, api, ui, metrics] = KEYS;
+        for ((key, env), addr) in [
+            (webhook, &mut self.webhook),
+            (api, &mut self.api),
+            (ui, &mut self.ui),
+            (metrics, &mut self.metrics),
+        ] {
+            let Some(raw) = value(key, env).filter(|v| !v.trim().is_empty()) else {
+                continue;
+            };
+            match raw.trim().parse() {
+                Ok(parsed) => *addr = parsed,
+                Err(_) => warn!(
+                    "Ignoring invalid listen address {:?} for {}, using {}",
+                    raw, key, addr
+                ),
+            }
+        }
+    }
+}
+
+/// Listen addresses from the cached configuration with environment overrides applied
+pub fn current() -> ListenConfig {
+    super::get_cached_config()
+        .map(|config| config.listen)
+        .unwrap_or_default()
+        .with_env()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_data() {
+        let data = BTreeMap::from([
+            ("listen.webhook".to_string(), "127.0.0.1:18080".to_string()),
+            ("listen.api".to_string(), "[::]:18081".to_string()),
+            ("listen.ui".to_string(), "not-an-address".to_string()),
+            ("listen.metrics".to_string(), "".to_string()),
+        ]);
+
+        let config = ListenConfig::from_data(&data);
+        assert_eq!(config.webhook, "127.0.0.1:18080".parse().unwrap());
+        assert_eq!(config.api, "[::]:18081".parse().unwrap());
+        assert_eq!(config.ui, ListenConfig::default().ui);
+        assert_eq!(config.metrics, ListenConfig::default().metrics);
+    }
+
+    #[test]
+    fn test_apply_env_wins() {
+        let mut config = ListenConfig::from_data(&BTreeMap::from([(
+            "listen.ui".to_string(),
+            "127.0.0.1:1".to_string(),
+        )]));
+        let env = BTreeMap::from([("HEADWIND_UI_ADDR", "0.0.0.0:3000")]);
+        config.apply(|_, var| env.get(var).map(|v| v.to_string()));
+
+        assert_eq!(config.ui, "0.0.0.0:3000".parse().unwrap());
+        assert_eq!(config.webhook, ListenConfig::default().webhook);
+    }
+}
@@ -1,7 +1,10 @@
+use crate::models::settings::{HeadwindSettings, SETTINGS_NAME};
 use futures::StreamExt;
 use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use kube::api::{ListParams, Patch, PatchParams};
 use kube::runtime::{WatchStreamExt, watcher};
 use kube::{Api, Client};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
@@ -12,41 +15,185 @@ const CONFIGMAP_NAME: &str = "headwind-config";
 const SECRET_NAME: &str = "headwind-secrets";
 const NAMESPACE: &str = "headwind-system";
 
+pub mod cluster;
+pub mod listen;
+pub mod namespaces;
+pub mod notificationroutes;
+pub mod plainhelm;
+pub mod policydefaults;
+pub mod registries;
+pub mod uiroles;
+pub mod validation;
+
+/// Raw configuration from every source (missing objects yield empty maps)
+#[derive(Debug, Clone, Default)]
+pub struct RawConfigData {
+    /// `headwind-config` ConfigMap data
+    pub config_data: BTreeMap<String, String>,
+    /// Fields set in the HeadwindSettings resource, as ConfigMap keys
+    pub settings_data: BTreeMap<String, String>,
+    /// Decoded `headwind-secrets` Secret data
+    pub secret_data: BTreeMap<String, String>,
+    /// The HeadwindSettings resource, if one exists
+    pub settings: Option<HeadwindSettings>,
+}
+
+impl RawConfigData {
+    /// ConfigMap data with HeadwindSettings values layered on top
+    pub fn merged_config_data(&self) -> BTreeMap<String, String> {
+        let mut merged = self.config_data.clone();
+        merged.extend(
+            self.settings_data
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+        merged
+    }
+}
+
+/// Shared secrets authenticating the registry webhook endpoints, read from the
+/// `headwind-secrets` Secret. Kept out of [`HeadwindConfig`] so they are never returned by
+/// the settings API.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebhookSecrets {
+    /// `webhook-registry-secret`, for `/webhook/registry`
+    pub registry: Option<String>,
+    /// `webhook-dockerhub-secret`, for `/webhook/dockerhub`
+    pub dockerhub: Option<String>,
+    /// `webhook-ghcr-secret`, for `/webhook/ghcr`
+    pub ghcr: Option<String>,
+    /// Path tokens of senders, from `webhook-tokens`: one `sender=token` per line, accepted
+    /// on `/webhook/<endpoint>/<token>` of every endpoint
+    pub tokens: BTreeMap<String, String>,
+}
+
+impl WebhookSecrets {
+    pub fn from_secret_data(secret_data: &BTreeMap<String, String>) -> Self {
+        Self {
+            registry: get_secret_value(secret_data, "webhook-registry-secret"),
+            dockerhub: get_secret_value(secret_data, "webhook-dockerhub-secret"),
+            ghcr: get_secret_value(secret_data, "webhook-ghcr-secret"),
+            tokens: get_secret_value(secret_data, "webhook-tokens")
+                .map(|value| parse_webhook_tokens(&value))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Parse `sender=token` lines, skipping blank lines, `#` comments and entries without a token
+fn parse_webhook_tokens(value: &str) -> BTreeMap<String, String> {
+    value
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let Some((sender, token)) = line.split_once('=') else {
+                warn!("Ignoring webhook token entry without a sender name");
+                return None;
+            };
+            let (sender, token) = (sender.trim(), token.trim());
+            (!sender.is_empty() && !token.is_empty())
+                .then(|| (sender.to_string(), token.to_string()))
+        })
+        .collect()
+}
+
 /// Headwind configuration loaded from ConfigMap and Secret
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HeadwindConfig {
     pub polling: PollingConfig,
+    #[serde(default)]
+    pub pubsub: PubSubConfig,
     pub helm: HelmConfig,
     pub controllers: ControllersConfig,
     pub notifications: NotificationsConfig,
     pub observability: ObservabilityConfig,
+    /// TLS options per registry host, from the `registries` key
+    #[serde(default)]
+    pub registries: BTreeMap<String, registries::RegistryTls>,
+    /// Helm CLI releases to track (`namespace/name`), from the `plainHelmReleases` key
+    #[serde(default)]
+    pub plain_helm_releases: BTreeMap<String, plainhelm::PlainHelmRelease>,
+    /// Notification destinations per namespace or route name, from the `notificationRoutes` key
+    #[serde(default)]
+    pub notification_routes: BTreeMap<String, notificationroutes::NotificationRoute>,
+    /// Web UI roles of users and groups, from the `uiRoles` key
+    #[serde(default)]
+    pub ui_roles: uiroles::UiRoles,
+    /// Default policy annotations per namespace, from the `policyDefaults` key
+    #[serde(default)]
+    pub policy_defaults: policydefaults::PolicyDefaults,
+    /// Bind addresses of the servers, from the `listen.*` keys
+    #[serde(default)]
+    pub listen: listen::ListenConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PollingConfig {
     pub enabled: bool,
     pub interval: u64,
+    /// Images and charts polled at the same time
+    #[serde(default = "default_polling_concurrency")]
+    pub concurrency: u64,
+    /// Concurrent requests to a single registry host
+    #[serde(
+        default = "default_registry_concurrency",
+        rename = "registryConcurrency"
+    )]
+    pub registry_concurrency: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_polling_concurrency() -> u64 {
+    8
+}
+
+fn default_registry_concurrency() -> u64 {
+    2
+}
+
+/// Google Cloud Pub/Sub subscription receiving Artifact Registry / GCR push notifications
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PubSubConfig {
+    pub enabled: bool,
+    /// Project of the subscription (not needed when `subscription` is a full resource name)
+    pub project: Option<String>,
+    /// Subscription name, or `projects/<project>/subscriptions/<name>`
+    pub subscription: Option<String>,
+    /// Messages pulled per request
+    #[serde(rename = "maxMessages")]
+    pub max_messages: u32,
+}
+
+impl Default for PubSubConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            project: None,
+            subscription: None,
+            max_messages: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HelmConfig {
     #[serde(rename = "autoDiscovery")]
     pub auto_discovery: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ControllersConfig {
     pub enabled: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct NotificationsConfig {
     pub slack: SlackConfig,
     pub teams: TeamsConfig,
     pub webhook: WebhookConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SlackConfig {
     pub enabled: bool,
     #[serde(rename = "webhookUrl")]
@@ -57,41 +204,65 @@ pub struct SlackConfig {
     pub icon_emoji: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TeamsConfig {
     pub enabled: bool,
     #[serde(rename = "webhookUrl")]
     pub webhook_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WebhookConfig {
     pub enabled: bool,
     pub url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ObservabilityConfig {
     #[serde(rename = "metricsBackend")]
     pub metrics_backend: String, // auto, prometheus, victoriametrics, influxdb, live
     pub prometheus: PrometheusConfig,
     pub victoriametrics: VictoriaMetricsConfig,
     pub influxdb: InfluxDBConfig,
+    /// Push metrics to the enabled VictoriaMetrics and InfluxDB backends
+    #[serde(default)]
+    pub push: MetricsPushConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PrometheusConfig {
     pub enabled: bool,
     pub url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VictoriaMetricsConfig {
     pub enabled: bool,
     pub url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MetricsPushConfig {
+    pub enabled: bool,
+    /// Seconds between pushes
+    #[serde(default = "default_push_interval")]
+    pub interval: u64,
+}
+
+impl Default for MetricsPushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: default_push_interval(),
+        }
+    }
+}
+
+fn default_push_interval() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InfluxDBConfig {
     pub enabled: bool,
     pub url: Option<String>,
@@ -100,13 +271,26 @@ pub struct InfluxDBConfig {
     pub token: Option<String>,
 }
 
+impl InfluxDBConfig {
+    /// API token: HEADWIND_INFLUXDB_TOKEN, falling back to `observability.influxdb.token`
+    pub fn resolved_token(&self) -> Option<String> {
+        std::env::var("HEADWIND_INFLUXDB_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .or_else(|| self.token.clone())
+    }
+}
+
 impl Default for HeadwindConfig {
     fn default() -> Self {
         Self {
             polling: PollingConfig {
                 enabled: false,
                 interval: 300,
+                concurrency: default_polling_concurrency(),
+                registry_concurrency: default_registry_concurrency(),
             },
+            pubsub: PubSubConfig::default(),
             helm: HelmConfig {
                 auto_discovery: true,
             },
@@ -149,16 +333,29 @@ impl Default for HeadwindConfig {
                     bucket: Some("metrics".to_string()),
                     token: Some("headwind-test-token".to_string()),
                 },
+                push: MetricsPushConfig::default(),
             },
+            registries: BTreeMap::new(),
+            plain_helm_releases: BTreeMap::new(),
+            notification_routes: BTreeMap::new(),
+            ui_roles: uiroles::UiRoles::default(),
+            policy_defaults: policydefaults::PolicyDefaults::default(),
+            listen: listen::ListenConfig::default(),
         }
     }
 }
 
 impl HeadwindConfig {
-    /// Load configuration from ConfigMap and Secret
-    pub async fn load(client: Client) -> Result<Self, Box<dyn std::error::Error>> {
+    /// Load the raw HeadwindSettings, ConfigMap and Secret data
+    pub async fn load_raw(client: Client) -> Result<RawConfigData, Box<dyn std::error::Error>> {
         info!("Loading Headwind configuration from ConfigMap and Secret");
 
+        let settings = load_settings(&client).await;
+        let settings_data = settings
+            .as_ref()
+            .map(|s| s.spec.to_config_data())
+            .unwrap_or_default();
+
         let configmap_api: Api<ConfigMap> = Api::namespaced(client.clone(), NAMESPACE);
         let secret_api: Api<Secret> = Api::namespaced(client.clone(), NAMESPACE);
 
@@ -199,46 +396,92 @@ impl HeadwindConfig {
             },
         };
 
-        // Parse configuration
-        let config = Self {
+        Ok(RawConfigData {
+            config_data,
+            settings_data,
+            secret_data,
+            settings,
+        })
+    }
+
+    /// Load configuration from HeadwindSettings, ConfigMap and Secret
+    pub async fn load(client: Client) -> Result<Self, Box<dyn std::error::Error>> {
+        let raw = Self::load_raw(client.clone()).await?;
+
+        let report = validation::validate_with_settings(
+            &raw.config_data,
+            &raw.settings_data,
+            &raw.secret_data,
+            &validation::env_snapshot(),
+        );
+        if let Some(settings) = &raw.settings {
+            update_settings_status(&client, settings, &report).await;
+        }
+        validation::store_report(report);
+        update_webhook_secrets(WebhookSecrets::from_secret_data(&raw.secret_data));
+
+        let config = Self::from_data(&raw.merged_config_data(), &raw.secret_data);
+        debug!("Loaded configuration: {:?}", config);
+        Ok(config)
+    }
+
+    /// Build configuration from raw ConfigMap and Secret data, falling back to defaults
+    pub fn from_data(
+        config_data: &BTreeMap<String, String>,
+        secret_data: &BTreeMap<String, String>,
+    ) -> Self {
+        Self {
             polling: PollingConfig {
-                enabled: parse_bool(&config_data, "polling.enabled", false),
-                interval: parse_u64(&config_data, "polling.interval", 300),
+                enabled: parse_bool(config_data, "polling.enabled", false),
+                interval: parse_u64(config_data, "polling.interval", 300),
+                concurrency: parse_u64(
+                    config_data,
+                    "polling.concurrency",
+                    default_polling_concurrency(),
+                ),
+                registry_concurrency: parse_u64(
+                    config_data,
+                    "polling.registryConcurrency",
+                    default_registry_concurrency(),
+                ),
+            },
+            pubsub: PubSubConfig {
+                enabled: parse_bool(config_data, "pubsub.enabled", false),
+                project: parse_optional_string(config_data, "pubsub.project"),
+                subscription: parse_optional_string(config_data, "pubsub.subscription"),
+                max_messages: parse_u64(config_data, "pubsub.maxMessages", 100) as u32,
             },
             helm: HelmConfig {
-                auto_discovery: parse_bool(&config_data, "helm.autoDiscovery", true),
+                auto_discovery: parse_bool(config_data, "helm.autoDiscovery", true),
             },
             controllers: ControllersConfig {
-                enabled: parse_bool(&config_data, "controllers.enabled", true),
+                enabled: parse_bool(config_data, "controllers.enabled", true),
             },
             notifications: NotificationsConfig {
                 slack: SlackConfig {
-                    enabled: parse_bool(&config_data, "slack.enabled", false),
-                    webhook_url: get_secret_value(&secret_data, "slack-webhook-url"),
-                    channel: parse_optional_string(&config_data, "slack.channel"),
-                    username: parse_optional_string(&config_data, "slack.username")
+                    enabled: parse_bool(config_data, "slack.enabled", false),
+                    webhook_url: get_secret_value(secret_data, "slack-webhook-url"),
+                    channel: parse_optional_string(config_data, "slack.channel"),
+                    username: parse_optional_string(config_data, "slack.username")
                         .or_else(|| Some("Headwind".to_string())),
-                    icon_emoji: parse_optional_string(&config_data, "slack.iconEmoji")
+                    icon_emoji: parse_optional_string(config_data, "slack.iconEmoji")
                         .or_else(|| Some(":sailboat:".to_string())),
                 },
                 teams: TeamsConfig {
-                    enabled: parse_bool(&config_data, "teams.enabled", false),
-                    webhook_url: get_secret_value(&secret_data, "teams-webhook-url"),
+                    enabled: parse_bool(config_data, "teams.enabled", false),
+                    webhook_url: get_secret_value(secret_data, "teams-webhook-url"),
                 },
                 webhook: WebhookConfig {
-                    enabled: parse_bool(&config_data, "webhook.enabled", false),
-                    url: get_secret_value(&secret_data, "webhook-url"),
+                    enabled: parse_bool(config_data, "webhook.enabled", false),
+                    url: get_secret_value(secret_data, "webhook-url"),
                 },
             },
             observability: ObservabilityConfig {
-                metrics_backend: parse_optional_string(
-                    &config_data,
-                    "observability.metricsBackend",
-                )
-                .unwrap_or_else(|| "auto".to_string()),
+                metrics_backend: parse_optional_string(config_data, "observability.metricsBackend")
+                    .unwrap_or_else(|| "auto".to_string()),
                 prometheus: PrometheusConfig {
-                    enabled: parse_bool(&config_data, "observability.prometheus.enabled", true),
-                    url: parse_optional_string(&config_data, "observability.prometheus.url")
+                    enabled: parse_bool(config_data, "observability.prometheus.enabled", true),
+                    url: parse_optional_string(config_data, "observability.prometheus.url")
                         .or_else(|| {
                             Some(
                                 "http://prometheus-server.monitoring.svc.cluster.local:80"
@@ -247,12 +490,8 @@ impl HeadwindConfig {
                         }),
                 },
                 victoriametrics: VictoriaMetricsConfig {
-                    enabled: parse_bool(
-                        &config_data,
-                        "observability.victoriametrics.enabled",
-                        true,
-                    ),
-                    url: parse_optional_string(&config_data, "observability.victoriametrics.url")
+                    enabled: parse_bool(config_data, "observability.victoriametrics.enabled", true),
+                    url: parse_optional_string(config_data, "observability.victoriametrics.url")
                         .or_else(|| {
                             Some(
                                 "http://victoria-metrics.monitoring.svc.cluster.local:8428"
@@ -261,22 +500,80 @@ impl HeadwindConfig {
                         }),
                 },
                 influxdb: InfluxDBConfig {
-                    enabled: parse_bool(&config_data, "observability.influxdb.enabled", false),
-                    url: parse_optional_string(&config_data, "observability.influxdb.url").or_else(
+                    enabled: parse_bool(config_data, "observability.influxdb.enabled", false),
+                    url: parse_optional_string(config_data, "observability.influxdb.url").or_else(
                         || Some("http://influxdb.monitoring.svc.cluster.local:8086".to_string()),
                     ),
-                    org: parse_optional_string(&config_data, "observability.influxdb.org")
+                    org: parse_optional_string(config_data, "observability.influxdb.org")
                         .or_else(|| Some("headwind".to_string())),
-                    bucket: parse_optional_string(&config_data, "observability.influxdb.bucket")
+                    bucket: parse_optional_string(config_data, "observability.influxdb.bucket")
                         .or_else(|| Some("metrics".to_string())),
-                    token: parse_optional_string(&config_data, "observability.influxdb.token")
+                    token: parse_optional_string(config_data, "observability.influxdb.token")
                         .or_else(|| Some("headwind-test-token".to_string())),
                 },
+                push: MetricsPushConfig {
+                    enabled: parse_bool(config_data, "observability.push.enabled", false),
+                    interval: parse_u64(
+                        config_data,
+                        "observability.push.interval",
+                        default_push_interval(),
+                    ),
+                },
             },
-        };
-
-        debug!("Loaded configuration: {:?}", config);
-        Ok(config)
+            registries: config_data
+                .get("registries")
+                .map(|yaml| {
+                    registries::parse(yaml).unwrap_or_else(|e| {
+                        warn!("Ignoring invalid registries configuration: {}", e);
+                        BTreeMap::new()
+                    })
+                })
+                .unwrap_or_default(),
+            plain_helm_releases: config_data
+                .get("plainHelmReleases")
+                .map(|yaml| {
+                    plainhelm::parse(yaml).unwrap_or_else(|e| {
+                        warn!("Ignoring invalid plainHelmReleases configuration: {}", e);
+                        BTreeMap::new()
+                    })
+                })
+                .unwrap_or_default(),
+            notification_routes: config_data
+                .get("notificationRoutes")
+                .map(|yaml| {
+                    notificationroutes::parse(yaml).unwrap_or_else(|e| {
+                        warn!("Ignoring invalid notificationRoutes configuration: {}", e);
+                        BTreeMap::new()
+                    })
+                })
+                .unwrap_or_default(),
+            ui_roles: config_data
+                .get("uiRoles")
+                .map(|yaml| {
+                    uiroles::parse(yaml).unwrap_or_else(|e| {
+                        // Without a valid mapping nobody may approve, rather than everybody
+                        warn!(
+                            "Invalid uiRoles configuration, every user is a viewer: {}",
+                            e
+                        );
+                        uiroles::UiRoles {
+                            default_role: uiroles::UiRole::Viewer,
+                            ..Default::default()
+                        }
+                    })
+                })
+                .unwrap_or_default(),
+            policy_defaults: config_data
+                .get("policyDefaults")
+                .map(|yaml| {
+                    policydefaults::parse(yaml).unwrap_or_else(|e| {
+                        warn!("Ignoring invalid policyDefaults configuration: {}", e);
+                        policydefaults::PolicyDefaults::default()
+                    })
+                })
+                .unwrap_or_default(),
+            listen: listen::ListenConfig::from_data(config_data),
+        }
     }
 
     /// Save configuration to ConfigMap and Secret
@@ -296,6 +593,22 @@ impl HeadwindConfig {
             "polling.interval".to_string(),
             self.polling.interval.to_string(),
         );
+        config_data.insert(
+            "pubsub.enabled".to_string(),
+            self.pubsub.enabled.to_string(),
+        );
+        config_data.insert(
+            "pubsub.project".to_string(),
+            self.pubsub.project.clone().unwrap_or_default(),
+        );
+        config_data.insert(
+            "pubsub.subscription".to_string(),
+            self.pubsub.subscription.clone().unwrap_or_default(),
+        );
+        config_data.insert(
+            "pubsub.maxMessages".to_string(),
+            self.pubsub.max_messages.to_string(),
+        );
         config_data.insert(
             "helm.autoDiscovery".to_string(),
             self.helm.auto_discovery.to_string(),
@@ -392,6 +705,14 @@ impl HeadwindConfig {
                 .clone()
                 .unwrap_or_default(),
         );
+        config_data.insert(
+            "observability.push.enabled".to_string(),
+            self.observability.push.enabled.to_string(),
+        );
+        config_data.insert(
+            "observability.push.interval".to_string(),
+            self.observability.push.interval.to_string(),
+        );
 
         // Update or create ConfigMap
         let configmap = ConfigMap {
@@ -410,7 +731,14 @@ impl HeadwindConfig {
         };
 
         match configmap_api.get(CONFIGMAP_NAME).await {
-            Ok(_) => {
+            Ok(existing) => {
+                // Keep keys the settings API doesn't manage, such as `registries`
+                let mut data = existing.data.unwrap_or_default();
+                data.extend(configmap.data.clone().unwrap_or_default());
+                let configmap = ConfigMap {
+                    data: Some(data),
+                    ..configmap
+                };
                 configmap_api
                     .replace(CONFIGMAP_NAME, &Default::default(), &configmap)
                     .await?;
@@ -453,7 +781,16 @@ impl HeadwindConfig {
         };
 
         match secret_api.get(SECRET_NAME).await {
-            Ok(_) => {
+            Ok(existing) => {
+                // Keep keys the settings API doesn't manage, such as the webhook secrets
+                let mut data = existing.data.unwrap_or_default();
+                for key in ["slack-webhook-url", "teams-webhook-url", "webhook-url"] {
+                    data.remove(key);
+                }
+                let secret = Secret {
+                    data: Some(data),
+                    ..secret
+                };
                 secret_api
                     .replace(SECRET_NAME, &Default::default(), &secret)
                     .await?;
@@ -469,6 +806,50 @@ impl HeadwindConfig {
     }
 }
 
+/// Fetch the HeadwindSettings resource. Returns None if it (or its CRD) doesn't exist.
+async fn load_settings(client: &Client) -> Option<HeadwindSettings> {
+    let api: Api<HeadwindSettings> = Api::all(client.clone());
+    match api.get_opt(SETTINGS_NAME).await {
+        Ok(settings) => settings,
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            debug!("HeadwindSettings CRD not installed, using ConfigMap only");
+            None
+        },
+        Err(e) => {
+            warn!(
+                "Failed to read HeadwindSettings, using ConfigMap only: {}",
+                e
+            );
+            None
+        },
+    }
+}
+
+/// Write a validation report to the HeadwindSettings status (skipped when unchanged)
+async fn update_settings_status(
+    client: &Client,
+    settings: &HeadwindSettings,
+    report: &validation::ValidationReport,
+) {
+    let status = report.to_settings_status(settings.metadata.generation);
+    if settings.status.as_ref() == Some(&status) {
+        return;
+    }
+
+    let api: Api<HeadwindSettings> = Api::all(client.clone());
+    let patch = serde_json::json!({ "status": status });
+    if let Err(e) = api
+        .patch_status(
+            SETTINGS_NAME,
+            &PatchParams::default(),
+            &Patch::Merge(&patch),
+        )
+        .await
+    {
+        warn!("Failed to update HeadwindSettings status: {}", e);
+    }
+}
+
 // Helper functions for parsing configuration values
 fn parse_bool(data: &BTreeMap<String, String>, key: &str, default: bool) -> bool {
     data.get(key)
@@ -494,6 +875,14 @@ fn get_secret_value(data: &BTreeMap<String, String>, key: &str) -> Option<String
         .map(|v| v.to_string())
 }
 
+/// Namespace Headwind runs in (`HEADWIND_NAMESPACE`, default: headwind-system)
+pub fn operator_namespace() -> String {
+    std::env::var("HEADWIND_NAMESPACE")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| NAMESPACE.to_string())
+}
+
 /// Global configuration cache for hot-reload
 static GLOBAL_CONFIG: once_cell::sync::Lazy<Arc<RwLock<Option<HeadwindConfig>>>> =
     once_cell::sync::Lazy::new(|| Arc::new(RwLock::new(None)));
@@ -511,6 +900,23 @@ fn update_cached_config(config: HeadwindConfig) {
     }
 }
 
+static WEBHOOK_SECRETS: once_cell::sync::Lazy<RwLock<WebhookSecrets>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(WebhookSecrets::default()));
+
+/// Get the current webhook secrets (empty until configuration has been loaded)
+pub fn webhook_secrets() -> WebhookSecrets {
+    WEBHOOK_SECRETS
+        .read()
+        .map(|secrets| secrets.clone())
+        .unwrap_or_default()
+}
+
+fn update_webhook_secrets(secrets: WebhookSecrets) {
+    if let Ok(mut cache) = WEBHOOK_SECRETS.write() {
+        *cache = secrets;
+    }
+}
+
 /// Start watching ConfigMap and Secret for changes
 /// This enables hot-reload of configuration without restarting the application
 pub async fn start_config_watcher(client: Client) {
@@ -634,6 +1040,81 @@ pub async fn start_config_watcher(client: Client) {
         }
     });
 
+    // Spawn HeadwindSettings watcher (only if the CRD is installed)
+    let settings_api: Api<HeadwindSettings> = Api::all(client.clone());
+    match settings_api.list(&ListParams::default().limit(1)).await {
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            info!("HeadwindSettings CRD not installed, not watching it");
+        },
+        _ => {
+            let settings_client = client.clone();
+            tokio::spawn(async move {
+                // Status updates also produce watch events; only reload when the spec changes
+                let mut observed_generation: Option<i64> = None;
+
+                loop {
+                    let watcher_config = watcher::Config::default()
+                        .timeout(60)
+                        .any_semantic()
+                        .fields(&format!("metadata.name={}", SETTINGS_NAME));
+
+                    let mut stream = watcher(settings_api.clone(), watcher_config)
+                        .default_backoff()
+                        .boxed();
+
+                    info!("HeadwindSettings watcher started for {}", SETTINGS_NAME);
+
+                    while let Some(event) = stream.next().await {
+                        match event {
+                            Ok(watcher::Event::Apply(settings)) => {
+                                if settings.metadata.generation == observed_generation {
+                                    continue;
+                                }
+                                observed_generation = settings.metadata.generation;
+                                info!(
+                                    "HeadwindSettings {} changed, reloading configuration",
+                                    SETTINGS_NAME
+                                );
+                                if let Ok(config) =
+                                    HeadwindConfig::load(settings_client.clone()).await
+                                {
+                                    update_cached_config(config);
+                                } else {
+                                    error!(
+                                        "Failed to reload configuration after HeadwindSettings change"
+                                    );
+                                }
+                            },
+                            Ok(watcher::Event::Delete(_)) => {
+                                warn!(
+                                    "HeadwindSettings {} was deleted, falling back to ConfigMap",
+                                    SETTINGS_NAME
+                                );
+                                observed_generation = None;
+                                if let Ok(config) =
+                                    HeadwindConfig::load(settings_client.clone()).await
+                                {
+                                    update_cached_config(config);
+                                }
+                            },
+                            Ok(watcher::Event::InitApply(settings)) => {
+                                info!("HeadwindSettings {} initial load", SETTINGS_NAME);
+                                observed_generation = settings.metadata.generation;
+                            },
+                            Ok(watcher::Event::Init) | Ok(watcher::Event::InitDone) => {},
+                            Err(e) => {
+                                error!("HeadwindSettings watcher error: {}", e);
+                            },
+                        }
+                    }
+
+                    warn!("HeadwindSettings watcher stream ended, restarting in 5 seconds...");
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            });
+        },
+    }
+
     info!("Configuration watchers started successfully");
 }
 
@@ -671,4 +1152,26 @@ mod tests {
 
         assert_eq!(parse_u64(&data, "missing.key", 100), 100);
     }
+
+    #[test]
+    fn test_webhook_secrets_from_secret_data() {
+        let mut data = BTreeMap::new();
+        data.insert("webhook-registry-secret".to_string(), "s3cret".to_string());
+        data.insert("webhook-dockerhub-secret".to_string(), String::new());
+
+        let secrets = WebhookSecrets::from_secret_data(&data);
+        assert_eq!(secrets.registry.as_deref(), Some("s3cret"));
+        assert_eq!(secrets.dockerhub, None);
+        assert!(secrets.tokens.is_empty());
+    }
+
+    #[test]
+    fn test_parse_webhook_tokens() {
+        let tokens = parse_webhook_tokens(
+            "# Harbor of team A\nharbor-a = 9f86d081\n\nquay=2c26b46b\nbroken\nempty=\n",
+        );
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens["harbor-a"], "9f86d081");
+        assert_eq!(tokens["quay"], "2c26b46b");
+    }
 }
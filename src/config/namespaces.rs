@@ -0,0 +1,148 @@
+//! Namespace scoping for multi-tenant clusters.
+//!
+//! `HEADWIND_WATCH_NAMESPACES` restricts Headwind to a comma-separated list of namespaces and
+//! `HEADWIND_EXCLUDE_NAMESPACES` removes namespaces from that scope (exclusions win). Entries
+//! ending in `*` match by prefix, e.g. `team-*`. With exactly one watched namespace and no
+//! wildcard, resources are listed and watched in that namespace only; otherwise Headwind
+//! lists cluster-wide and skips namespaces outside the scope.
+
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::ListParams;
+use kube::{Api, Client, Resource, ResourceExt};
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+
+static SCOPE: once_cell::sync::Lazy<NamespaceScope> = once_cell::sync::Lazy::new(|| {
+    NamespaceScope::parse(
+        std::env::var("HEADWIND_WATCH_NAMESPACES").ok().as_deref(),
+        std::env::var("HEADWIND_EXCLUDE_NAMESPACES").ok().as_deref(),
+    )
+});
+
+/// Namespaces Headwind acts on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamespaceScope {
+    /// Namespaces to watch (empty means all)
+    pub watch: Vec<String>,
+    /// Namespaces to skip
+    pub exclude: Vec<String>,
+}
+
+impl NamespaceScope {
+    pub fn parse(watch: Option<&str>, exclude: Option<&str>) -> Self {
+        fn split(list: Option<&str>) -> Vec<String> {
+            list.unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|ns| !ns.is_empty())
+                .map(String::from)
+                .collect()
+        }
+        Self {
+            watch: split(watch),
+            exclude: split(exclude),
+        }
+    }
+
+    /// Whether resources in `namespace` are in scope
+    pub fn allows(&self, namespace: &str) -> bool {
+        (self.watch.is_empty() || self.watch.iter().any(|p| matches(p, namespace)))
+            && !self.exclude.iter().any(|p| matches(p, namespace))
+    }
+
+    /// The only namespace in scope, when the scope is a single namespace without wildcards
+    pub fn single_namespace(&self) -> Option<&str> {
+        match self.watch.as_slice() {
+            [namespace] if !namespace.ends_with('*') => Some(namespace),
+            _ => None,
+        }
+    }
+
+    /// Whether any namespace is filtered out
+    pub fn is_restricted(&self) -> bool {
+        !self.watch.is_empty() || !self.exclude.is_empty()
+    }
+}
+
+fn matches(pattern: &str, namespace: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => namespace.starts_with(prefix),
+        None => pattern == namespace,
+    }
+}
+
+/// The namespace scope configured for this process
+pub fn scope() -> &'static NamespaceScope {
+    &SCOPE
+}
+
+/// Whether Headwind should act on resources in `namespace`
+pub fn is_watched(namespace: &str) -> bool {
+    SCOPE.allows(namespace)
+}
+
+/// Api for listing and watching `K` within the configured scope. Callers must still skip
+/// objects for which [`is_watched`] is false unless the scope is a single namespace.
+pub fn scoped_api<K>(client: Client) -> Api<K>
+where
+    K: Resource<Scope = NamespaceResourceScope>,
+    K::DynamicType: Default,
+{
+    match SCOPE.single_namespace() {
+        Some(namespace) => Api::namespaced(client, namespace),
+        None => Api::all(client),
+    }
+}
+
+/// List all `K` in watched namespaces
+pub async fn list_watched<K>(client: &Client) -> kube::Result<Vec<K>>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + Debug,
+    K::DynamicType: Default,
+{
+    let list = scoped_api::<K>(client.clone())
+        .list(&ListParams::default())
+        .await?;
+    Ok(list
+        .items
+        .into_iter()
+        .filter(|resource| is_watched(&resource.namespace().unwrap_or_default()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unrestricted_scope_allows_everything() {
+        let scope = NamespaceScope::parse(None, Some(" , "));
+        assert!(!scope.is_restricted());
+        assert!(scope.allows("default"));
+        assert_eq!(scope.single_namespace(), None);
+    }
+
+    #[test]
+    fn test_watch_and_exclude() {
+        let scope = NamespaceScope::parse(Some("team-*, shared"), Some("team-legacy"));
+        assert!(scope.allows("team-payments"));
+        assert!(scope.allows("shared"));
+        assert!(!scope.allows("team-legacy"));
+        assert!(!scope.allows("kube-system"));
+        assert_eq!(scope.single_namespace(), None);
+
+        let scope = NamespaceScope::parse(None, Some("kube-*"));
+        assert!(scope.allows("default"));
+        assert!(!scope.allows("kube-system"));
+    }
+
+    #[test]
+    fn test_single_namespace() {
+        let scope = NamespaceScope::parse(Some("payments"), None);
+        assert_eq!(scope.single_namespace(), Some("payments"));
+        assert!(!scope.allows("default"));
+
+        let scope = NamespaceScope::parse(Some("payments-*"), None);
+        assert_eq!(scope.single_namespace(), None);
+    }
+}
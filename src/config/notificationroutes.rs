@@ -0,0 +1,129 @@
+//! Notification routes: where notifications about resources of a team go instead of the
+//! global Slack, Teams and webhook destinations.
+//!
+//! The `notificationRoutes` key of the `headwind-config` ConfigMap maps route names to
+//! destinations. A route named after a namespace applies to every resource in it; resources
+//! pick any route with the `headwind.sh/notify-route` annotation:
+//!
+//! ```yaml
+//! notificationRoutes: |
+//!   payments:
+//!     slackChannel: "#team-payments"
+//!     slackWebhookUrl: https://hooks.slack.com/services/T000/B000/XXXX
+//!   platform:
+//!     teamsWebhookUrl: https://example.webhook.office.com/webhookb2/...
+//!     webhookUrl: https://platform.example.com/headwind
+//! ```
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Destinations overriding the global notification settings. Unset fields keep the global
+/// value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NotificationRoute {
+    /// Slack channel to post to, e.g. `#team-payments`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slack_channel: Option<String>,
+    /// Slack incoming webhook to post to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slack_webhook_url: Option<String>,
+    /// Teams incoming webhook to post to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teams_webhook_url: Option<String>,
+    /// URL the generic webhook notifier posts to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+}
+
+impl NotificationRoute {
+    /// This route with the fields set in `other` taking precedence
+    pub fn overridden_by(self, other: NotificationRoute) -> Self {
+        Self {
+            slack_channel: other.slack_channel.or(self.slack_channel),
+            slack_webhook_url: other.slack_webhook_url.or(self.slack_webhook_url),
+            teams_webhook_url: other.teams_webhook_url.or(self.teams_webhook_url),
+            webhook_url: other.webhook_url.or(self.webhook_url),
+        }
+    }
+}
+
+/// Parse the `notificationRoutes` ConfigMap value
+pub fn parse(yaml: &str) -> Result<BTreeMap<String, NotificationRoute>, String> {
+    if yaml.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let routes: BTreeMap<String, NotificationRoute> =
+        serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+    for (name, route) in &routes {
+        let urls = [
+            &route.slack_webhook_url,
+            &route.teams_webhook_url,
+            &route.webhook_url,
+        ];
+        for url in urls.into_iter().flatten() {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(format!("{}: '{}' is not an http(s) URL", name, url));
+            }
+        }
+    }
+    Ok(routes)
+}
+
+/// The configured route named `name`, if any
+pub fn route(name: &str) -> Option<NotificationRoute> {
+    super::get_cached_config()?
+        .notification_routes
+        .get(name)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_notification_routes() {
+        let routes = parse(
+            r##"
+payments:
+  slackChannel: "#team-payments"
+platform:
+  webhookUrl: https://platform.example.com/headwind
+"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            routes["payments"].slack_channel.as_deref(),
+            Some("#team-payments")
+        );
+        assert_eq!(routes["platform"].slack_channel, None);
+
+        assert!(parse("payments:\n  webhookUrl: platform.example.com\n").is_err());
+        assert!(parse("payments:\n  channel: '#payments'\n").is_err());
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_overridden_by() {
+        let namespace = NotificationRoute {
+            slack_channel: Some("#payments".to_string()),
+            webhook_url: Some("https://payments.example.com/hook".to_string()),
+            ..Default::default()
+        };
+        let resource = NotificationRoute {
+            slack_channel: Some("#payments-api".to_string()),
+            ..Default::default()
+        };
+
+        let route = namespace.overridden_by(resource);
+        assert_eq!(route.slack_channel.as_deref(), Some("#payments-api"));
+        assert_eq!(
+            route.webhook_url.as_deref(),
+            Some("https://payments.example.com/hook")
+        );
+    }
+}
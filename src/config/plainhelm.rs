@@ -0,0 +1,100 @@
+//! Sources of releases installed by the Helm CLI.
+//!
+//! Helm doesn't record which repository a release's chart came from, so the
+//! `plainHelmReleases` key of the `headwind-config` ConfigMap maps each tracked release
+//! (`namespace/name`) to its repository and Headwind annotations:
+//!
+//! ```yaml
+//! plainHelmReleases: |
+//!   monitoring/prometheus:
+//!     repository: https://prometheus-community.github.io/helm-charts
+//!     annotations:
+//!       headwind.sh/policy: minor
+//!   apps/podinfo:
+//!     repository: oci://ghcr.io/stefanprodan/charts
+//!     secretRef: ghcr-auth
+//! ```
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Repository and policy of one Helm CLI release
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PlainHelmRelease {
+    /// Chart repository: an `http(s)://` index URL, or the `oci://` path the chart is under
+    pub repository: String,
+    /// Secret in the release namespace with `username` and `password` for the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_ref: Option<String>,
+    /// ServiceAccount the upgrade Job runs as, in the release namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_account_name: Option<String>,
+    /// Headwind annotations (`headwind.sh/policy`, `headwind.sh/require-approval`, ...)
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+}
+
+/// Parse the `plainHelmReleases` ConfigMap value
+pub fn parse(yaml: &str) -> Result<BTreeMap<String, PlainHelmRelease>, String> {
+    if yaml.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let releases: BTreeMap<String, PlainHelmRelease> =
+        serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+    for (key, release) in &releases {
+        if key
+            .split_once('/')
+            .is_none_or(|(ns, name)| ns.is_empty() || name.is_empty())
+        {
+            return Err(format!("Expected namespace/name, found '{}'", key));
+        }
+        if release.repository.trim().is_empty() {
+            return Err(format!("{}: repository is required", key));
+        }
+    }
+    Ok(releases)
+}
+
+/// Settings for the release `name` in `namespace`, when it is tracked
+pub fn for_release(namespace: &str, name: &str) -> Option<PlainHelmRelease> {
+    super::get_cached_config()?
+        .plain_helm_releases
+        .get(&format!("{}/{}", namespace, name))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_helm_releases() {
+        let releases = parse(
+            r#"
+monitoring/prometheus:
+  repository: https://prometheus-community.github.io/helm-charts
+  annotations:
+    headwind.sh/policy: minor
+apps/podinfo:
+  repository: oci://ghcr.io/stefanprodan/charts
+  secretRef: ghcr-auth
+  serviceAccountName: deployer
+"#,
+        )
+        .unwrap();
+
+        let prometheus = &releases["monitoring/prometheus"];
+        assert_eq!(prometheus.annotations["headwind.sh/policy"], "minor");
+        assert!(prometheus.secret_ref.is_none());
+        let podinfo = &releases["apps/podinfo"];
+        assert_eq!(podinfo.secret_ref.as_deref(), Some("ghcr-auth"));
+        assert_eq!(podinfo.service_account_name.as_deref(), Some("deployer"));
+
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("prometheus:\n  repository: https://example.com\n").is_err());
+        assert!(parse("monitoring/prometheus:\n  repository: ''\n").is_err());
+        assert!(parse("monitoring/prometheus:\n  repo: https://example.com\n").is_err());
+    }
+}
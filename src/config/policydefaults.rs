@@ -0,0 +1,204 @@
+//! Policy defaults: `headwind.sh/*` annotation values for workloads that don't set them.
+//!
+//! The `policyDefaults` key of the `headwind-config` ConfigMap sets cluster-wide defaults and
+//! overrides per namespace. Entries ending in `*` match namespaces by prefix; an exact name
+//! wins over prefixes, and longer prefixes over shorter ones:
+//!
+//! ```yaml
+//! policyDefaults: |
+//!   requireApproval: true
+//!   eventSource: both
+//!   namespaces:
+//!     payments:
+//!       policy: patch
+//!     dev-*:
+//!       policy: minor
+//!       requireApproval: false
+//! ```
+//!
+//! With a default policy, workloads in the namespace are tracked without any annotation;
+//! annotations on the workload (or from an UpdateTarget) always take precedence, so
+//! `headwind.sh/policy: none` opts a workload out.
+
+use crate::models::{EventSource, UpdatePolicy, annotations};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Default annotation values. Unset fields leave the annotation's own default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Defaults {
+    /// Default `headwind.sh/policy`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    /// Default `headwind.sh/require-approval`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_approval: Option<bool>,
+    /// Default `headwind.sh/event-source`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_source: Option<String>,
+}
+
+impl Defaults {
+    /// These defaults with the fields set in `other` taking precedence
+    pub fn overridden_by(self, other: &Defaults) -> Self {
+        Self {
+            policy: other.policy.clone().or(self.policy),
+            require_approval: other.require_approval.or(self.require_approval),
+            event_source: other.event_source.clone().or(self.event_source),
+        }
+    }
+
+    /// The set fields as `headwind.sh/*` annotations
+    pub fn to_annotations(&self) -> BTreeMap<String, String> {
+        let entries = [
+            (annotations::POLICY, self.policy.clone()),
+            (
+                annotations::REQUIRE_APPROVAL,
+                self.require_approval.map(|v| v.to_string()),
+            ),
+            (annotations::EVENT_SOURCE, self.event_source.clone()),
+        ];
+        entries
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|v| (key.to_string(), v)))
+            .collect()
+    }
+}
+
+/// Cluster-wide defaults and per-namespace overrides
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PolicyDefaults {
+    /// Default `headwind.sh/policy` in every namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    /// Default `headwind.sh/require-approval` in every namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_approval: Option<bool>,
+    /// Default `headwind.sh/event-source` in every namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_source: Option<String>,
+    /// Overrides per namespace, or per namespace prefix when the key ends in `*`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub namespaces: BTreeMap<String, Defaults>,
+}
+
+impl PolicyDefaults {
+    /// The cluster-wide defaults
+    pub fn cluster(&self) -> Defaults {
+        Defaults {
+            policy: self.policy.clone(),
+            require_approval: self.require_approval,
+            event_source: self.event_source.clone(),
+        }
+    }
+
+    /// Defaults of workloads in `namespace`
+    pub fn for_namespace(&self, namespace: &str) -> Defaults {
+        let mut matching: Vec<(&String, &Defaults)> = self
+            .namespaces
+            .iter()
+            .filter(|(pattern, _)| match pattern.strip_suffix('*') {
+                Some(prefix) => namespace.starts_with(prefix),
+                None => pattern.as_str() == namespace,
+            })
+            .collect();
+        // Least specific first: prefixes by length, then the exact name
+        matching.sort_by_key(|(pattern, _)| (!pattern.ends_with('*'), pattern.len()));
+
+        matching
+            .into_iter()
+            .fold(self.cluster(), |defaults, (_, overrides)| {
+                defaults.overridden_by(overrides)
+            })
+    }
+}
+
+/// Parse the `policyDefaults` ConfigMap value
+pub fn parse(yaml: &str) -> Result<PolicyDefaults, String> {
+    if yaml.trim().is_empty() {
+        return Ok(PolicyDefaults::default());
+    }
+    let defaults: PolicyDefaults = serde_yaml::from_str(yaml).map_err(|e| e.to_string())?;
+    let cluster = defaults.cluster();
+    let scopes = std::iter::once(("cluster", &cluster)).chain(
+        defaults
+            .namespaces
+            .iter()
+            .map(|(namespace, d)| (namespace.as_str(), d)),
+    );
+    for (scope, d) in scopes {
+        if let Some(policy) = &d.policy {
+            policy
+                .parse::<UpdatePolicy>()
+                .map_err(|e| format!("{}: {}", scope, e))?;
+        }
+        if let Some(event_source) = &d.event_source {
+            event_source
+                .parse::<EventSource>()
+                .map_err(|e| format!("{}: {}", scope, e))?;
+        }
+    }
+    Ok(defaults)
+}
+
+/// Default annotations of workloads in `namespace` from the current configuration
+pub fn annotations_for(namespace: &str) -> BTreeMap<String, String> {
+    super::get_cached_config()
+        .map(|config| {
+            config
+                .policy_defaults
+                .for_namespace(namespace)
+                .to_annotations()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_defaults() {
+        let defaults = parse(
+            r#"
+requireApproval: true
+eventSource: both
+namespaces:
+  dev-*:
+    policy: minor
+    requireApproval: false
+  dev-payments:
+    policy: patch
+  dev-pay*:
+    eventSource: polling
+"#,
+        )
+        .unwrap();
+
+        let cluster = defaults.for_namespace("default").to_annotations();
+        assert_eq!(cluster.get(annotations::POLICY), None);
+        assert_eq!(cluster[annotations::REQUIRE_APPROVAL], "true");
+        assert_eq!(cluster[annotations::EVENT_SOURCE], "both");
+
+        let dev = defaults.for_namespace("dev-api");
+        assert_eq!(dev.policy.as_deref(), Some("minor"));
+        assert_eq!(dev.require_approval, Some(false));
+        assert_eq!(dev.event_source.as_deref(), Some("both"));
+
+        let payments = defaults.for_namespace("dev-payments");
+        assert_eq!(payments.policy.as_deref(), Some("patch"));
+        assert_eq!(payments.require_approval, Some(false));
+        assert_eq!(payments.event_source.as_deref(), Some("polling"));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_values() {
+        assert!(parse("").unwrap().namespaces.is_empty());
+        assert!(parse("policy: sometimes").is_err());
+        assert!(parse("namespaces:\n  api:\n    eventSource: carrier-pigeon\n").is_err());
+        assert!(parse("namespaces:\n  api:\n    schedule: daily\n").is_err());
+    }
+}
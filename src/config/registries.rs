@@ -0,0 +1,188 @@
+//! Per-registry TLS settings.
+//!
+//! The `registries` key of the `headwind-config` ConfigMap holds a YAML map from registry
+//! host (`host` or `host:port`) to its TLS options:
+//!
+//! ```yaml
+//! registries: |
+//!   registry.internal:5000:
+//!     plainHttp: true
+//!   harbor.example.com:
+//!     ca: |
+//!       -----BEGIN CERTIFICATE-----
+//!       ...
+//!       -----END CERTIFICATE-----
+//! ```
+//!
+//! The settings apply to the registry polling OCI client, the Helm OCI client and the Helm
+//! HTTP repository client. Flux HelmRepository `insecure` and `certSecretRef` settings are
+//! layered on top for chart repositories.
+
+use oci_distribution::client::{
+    Certificate as OciCertificate, CertificateEncoding, ClientConfig, ClientProtocol,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// TLS options for one registry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RegistryTls {
+    /// PEM CA certificates trusted in addition to the system roots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca: Option<String>,
+    /// Skip TLS certificate and hostname verification
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+    /// Use plain HTTP instead of HTTPS
+    #[serde(default)]
+    pub plain_http: bool,
+}
+
+impl RegistryTls {
+    /// Whether nothing differs from the defaults
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// oci-distribution client configuration for a registry `host` with these settings
+    pub fn oci_client_config(&self, host: &str) -> ClientConfig {
+        ClientConfig {
+            protocol: if self.plain_http {
+                ClientProtocol::HttpsExcept(vec![host.to_string()])
+            } else {
+                ClientProtocol::Https
+            },
+            accept_invalid_certificates: self.insecure_skip_verify,
+            accept_invalid_hostnames: self.insecure_skip_verify,
+            extra_root_certificates: self
+                .ca
+                .as_deref()
+                .map(pem_certificates)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|pem| OciCertificate {
+                    encoding: CertificateEncoding::Pem,
+                    data: pem.into_bytes(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse the `registries` ConfigMap value
+pub fn parse(yaml: &str) -> Result<BTreeMap<String, RegistryTls>, serde_yaml::Error> {
+    if yaml.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let registries: BTreeMap<String, RegistryTls> = serde_yaml::from_str(yaml)?;
+    Ok(registries
+        .into_iter()
+        .map(|(host, tls)| (normalize_host(&host), tls))
+        .collect())
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("oci://")
+        .trim_end_matches('/')
+        .to_lowercase()
+}
+
+/// TLS settings configured for a registry host (`host` or `host:port`)
+pub fn for_host(host: &str) -> RegistryTls {
+    super::get_cached_config()
+        .and_then(|config| config.registries.get(&normalize_host(host)).cloned())
+        .unwrap_or_default()
+}
+
+/// Host (with port) of an `oci://`, `http://` or `https://` URL
+pub fn url_host(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme.split('/').next().unwrap_or_default()
+}
+
+/// Split a PEM bundle into individual certificates (oci-distribution reads one per entry)
+fn pem_certificates(bundle: &str) -> Vec<String> {
+    const END: &str = "-----END CERTIFICATE-----";
+    bundle
+        .split_inclusive(END)
+        .filter_map(|chunk| {
+            let start = chunk.find("-----BEGIN CERTIFICATE-----")?;
+            chunk
+                .ends_with(END)
+                .then(|| format!("{}\n", &chunk[start..]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_registries() {
+        let registries = parse(
+            r#"
+registry.internal:5000:
+  plainHttp: true
+https://Harbor.example.com/:
+  insecureSkipVerify: true
+  ca: |
+    -----BEGIN CERTIFICATE-----
+    MIIB
+    -----END CERTIFICATE-----
+"#,
+        )
+        .unwrap();
+
+        assert!(registries["registry.internal:5000"].plain_http);
+        let harbor = &registries["harbor.example.com"];
+        assert!(harbor.insecure_skip_verify);
+        assert!(!harbor.plain_http);
+        assert!(harbor.ca.as_deref().unwrap().contains("MIIB"));
+
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("registry.internal:\n  plainHTTP: true\n").is_err());
+    }
+
+    #[test]
+    fn test_oci_client_config() {
+        let tls = RegistryTls {
+            ca: Some(
+                "-----BEGIN CERTIFICATE-----\nA\n-----END CERTIFICATE-----\n\
+                 -----BEGIN CERTIFICATE-----\nB\n-----END CERTIFICATE-----\n"
+                    .to_string(),
+            ),
+            insecure_skip_verify: false,
+            plain_http: true,
+        };
+        let config = tls.oci_client_config("registry.internal:5000");
+        assert!(matches!(
+            config.protocol,
+            ClientProtocol::HttpsExcept(ref hosts) if hosts == &["registry.internal:5000"]
+        ));
+        assert_eq!(config.extra_root_certificates.len(), 2);
+        assert!(!config.accept_invalid_certificates);
+
+        assert!(RegistryTls::default().is_default());
+        assert!(!tls.is_default());
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(
+            url_host("oci://registry.internal:5000/charts/app"),
+            "registry.internal:5000"
+        );
+        assert_eq!(
+            url_host("https://charts.example.com/stable"),
+            "charts.example.com"
+        );
+        assert_eq!(url_host("ghcr.io/org/app"), "ghcr.io");
+    }
+}
@@ -0,0 +1,138 @@
+//! Web UI roles: who may only view UpdateRequests and who may also approve, reject and roll
+//! back.
+//!
+//! The `uiRoles` key of the `headwind-config` ConfigMap maps users and groups to roles.
+//! Groups come from the Kubernetes TokenReview in `token` auth mode (the OIDC group claims
+//! when the API server uses OIDC) and from the groups header in `proxy` auth mode:
+//!
+//! ```yaml
+//! uiRoles: |
+//!   defaultRole: viewer
+//!   approvers:
+//!     groups: [platform-admins]
+//!     users: [alice@example.com]
+//!   viewers:
+//!     users: [auditor@example.com]
+//! ```
+//!
+//! Without the key everyone is an approver.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What a Web UI user may do
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UiRole {
+    /// View UpdateRequests, history and metrics
+    Viewer,
+    /// Also approve, reject and roll back
+    #[default]
+    Approver,
+}
+
+impl fmt::Display for UiRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UiRole::Viewer => "viewer",
+            UiRole::Approver => "approver",
+        })
+    }
+}
+
+/// Users and groups given a role
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RoleSubjects {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+}
+
+impl RoleSubjects {
+    fn matches(&self, username: &str, groups: &[String]) -> bool {
+        self.users.iter().any(|u| u == username) || self.groups.iter().any(|g| groups.contains(g))
+    }
+}
+
+/// Role mapping of the Web UI
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UiRoles {
+    /// Role of users matched by neither list
+    #[serde(default)]
+    pub default_role: UiRole,
+    #[serde(default)]
+    pub approvers: RoleSubjects,
+    #[serde(default)]
+    pub viewers: RoleSubjects,
+}
+
+impl UiRoles {
+    /// Role of a user. Being listed as an approver wins over being listed as a viewer.
+    pub fn role_for(&self, username: &str, groups: &[String]) -> UiRole {
+        if self.approvers.matches(username, groups) {
+            UiRole::Approver
+        } else if self.viewers.matches(username, groups) {
+            UiRole::Viewer
+        } else {
+            self.default_role
+        }
+    }
+}
+
+/// Parse the `uiRoles` ConfigMap value
+pub fn parse(yaml: &str) -> Result<UiRoles, serde_yaml::Error> {
+    if yaml.trim().is_empty() {
+        return Ok(UiRoles::default());
+    }
+    serde_yaml::from_str(yaml)
+}
+
+/// Role of a user under the configured mapping
+pub fn role_for(username: &str, groups: &[String]) -> UiRole {
+    super::get_cached_config()
+        .map(|config| config.ui_roles.role_for(username, groups))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_for() {
+        let roles = parse(
+            r#"
+defaultRole: viewer
+approvers:
+  groups: [platform-admins]
+  users: [alice@example.com]
+viewers:
+  users: [bob@example.com]
+"#,
+        )
+        .unwrap();
+        let groups = |g: &[&str]| g.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(roles.role_for("alice@example.com", &[]), UiRole::Approver);
+        assert_eq!(
+            roles.role_for("bob@example.com", &groups(&["platform-admins"])),
+            UiRole::Approver
+        );
+        assert_eq!(roles.role_for("bob@example.com", &[]), UiRole::Viewer);
+        assert_eq!(
+            roles.role_for("carol@example.com", &groups(&["developers"])),
+            UiRole::Viewer
+        );
+
+        // Without a mapping everyone approves
+        let roles = parse("").unwrap();
+        assert_eq!(roles.role_for("carol@example.com", &[]), UiRole::Approver);
+
+        assert!(parse("defaultRole: admin").is_err());
+        assert!(parse("approvers:\n  teams: [a]").is_err());
+    }
+}
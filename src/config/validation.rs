@@ -0,0 +1,1027 @@
+//! Validation of the merged configuration (HeadwindSettings + ConfigMap + Secret + environment)
+//!
+//! Configuration parsing falls back to defaults for missing or malformed values, which makes
+//! typos like `poling.enabled` silently ineffective. This pass reports unknown keys, invalid
+//! values and conflicting settings. It runs at startup, on every configuration reload and via
+//! `GET /api/v1/settings/validate`. With `HEADWIND_CONFIG_STRICT=true`, startup fails if any
+//! issue is found. When a `HeadwindSettings` resource exists, the report is also written to its
+//! status.
+
+use super::HeadwindConfig;
+use crate::models::settings::{HeadwindSettingsStatus, SettingsIssue, spec_path};
+use kube::Client;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use tracing::{error, info, warn};
+
+/// Type of a configuration value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ValueKind {
+    Bool,
+    /// Unsigned integer that must be greater than zero
+    PositiveInt,
+    /// Unsigned integer (zero allowed)
+    Int,
+    Url,
+    Text,
+    /// `ip:port`, e.g. `0.0.0.0:8080`
+    SocketAddr,
+    /// YAML map of registry host to TLS options
+    Registries,
+    /// YAML map of Helm CLI release to its repository and annotations
+    PlainHelmReleases,
+    /// YAML map of route name to notification destinations
+    NotificationRoutes,
+    /// YAML Web UI role mapping
+    UiRoles,
+    /// YAML policy defaults
+    PolicyDefaults,
+    OneOf(&'static [&'static str]),
+}
+
+const METRICS_BACKENDS: &[&str] = &["auto", "prometheus", "victoriametrics", "influxdb", "live"];
+const UI_AUTH_MODES: &[&str] = &["none", "simple", "token", "proxy"];
+const TICKET_SYSTEMS: &[&str] = &["jira", "servicenow"];
+const PLATFORM_CHECK_MODES: &[&str] = &["off", "skip", "flag"];
+const SERVER_MODES: &[&str] = &["split", "single"];
+const EVENT_BUSES: &[&str] = &["nats", "kafka"];
+const EVENT_BUS_MODES: &[&str] = &["consume", "route"];
+const VALUES_SCHEMA_MODES: &[&str] = &["enforce", "warn", "off"];
+
+/// Keys recognized in the `headwind-config` ConfigMap
+const CONFIGMAP_KEYS: &[(&str, ValueKind)] = &[
+    ("polling.enabled", ValueKind::Bool),
+    ("polling.interval", ValueKind::PositiveInt),
+    ("polling.concurrency", ValueKind::PositiveInt),
+    ("polling.registryConcurrency", ValueKind::PositiveInt),
+    ("pubsub.enabled", ValueKind::Bool),
+    ("pubsub.project", ValueKind::Text),
+    ("pubsub.subscription", ValueKind::Text),
+    ("pubsub.maxMessages", ValueKind::PositiveInt),
+    ("helm.autoDiscovery", ValueKind::Bool),
+    ("controllers.enabled", ValueKind::Bool),
+    ("slack.enabled", ValueKind::Bool),
+    ("slack.channel", ValueKind::Text),
+    ("slack.username", ValueKind::Text),
+    ("slack.iconEmoji", ValueKind::Text),
+    ("teams.enabled", ValueKind::Bool),
+    ("webhook.enabled", ValueKind::Bool),
+    (
+        "observability.metricsBackend",
+        ValueKind::OneOf(METRICS_BACKENDS),
+    ),
+    ("observability.prometheus.enabled", ValueKind::Bool),
+    ("observability.prometheus.url", ValueKind::Url),
+    ("observability.victoriametrics.enabled", ValueKind::Bool),
+    ("observability.victoriametrics.url", ValueKind::Url),
+    ("observability.influxdb.enabled", ValueKind::Bool),
+    ("observability.influxdb.url", ValueKind::Url),
+    ("observability.influxdb.org", ValueKind::Text),
+    ("observability.influxdb.bucket", ValueKind::Text),
+    ("observability.influxdb.token", ValueKind::Text),
+    ("observability.push.enabled", ValueKind::Bool),
+    ("observability.push.interval", ValueKind::PositiveInt),
+    ("registries", ValueKind::Registries),
+    ("plainHelmReleases", ValueKind::PlainHelmReleases),
+    ("notificationRoutes", ValueKind::NotificationRoutes),
+    ("uiRoles", ValueKind::UiRoles),
+    ("policyDefaults", ValueKind::PolicyDefaults),
+    ("listen.webhook", ValueKind::SocketAddr),
+    ("listen.api", ValueKind::SocketAddr),
+    ("listen.ui", ValueKind::SocketAddr),
+    ("listen.metrics", ValueKind::SocketAddr),
+];
+
+/// Keys recognized in the `headwind-secrets` Secret
+const SECRET_KEYS: &[(&str, ValueKind)] = &[
+    ("slack-webhook-url", ValueKind::Url),
+    ("teams-webhook-url", ValueKind::Url),
+    ("webhook-url", ValueKind::Url),
+    ("webhook-registry-secret", ValueKind::Text),
+    ("webhook-dockerhub-secret", ValueKind::Text),
+    ("webhook-ghcr-secret", ValueKind::Text),
+    ("webhook-tokens", ValueKind::Text),
+];
+
+/// Environment variables read by Headwind. Any other `HEADWIND_*` variable is reported as unknown.
+const ENV_KEYS: &[(&str, ValueKind)] = &[
+    ("HEADWIND_POLLING_ENABLED", ValueKind::Bool),
+    ("HEADWIND_POLLING_INTERVAL", ValueKind::PositiveInt),
+    ("HEADWIND_POLLING_CONCURRENCY", ValueKind::PositiveInt),
+    (
+        "HEADWIND_POLLING_REGISTRY_CONCURRENCY",
+        ValueKind::PositiveInt,
+    ),
+    ("HEADWIND_POLLING_MAX_TAGS", ValueKind::PositiveInt),
+    ("HEADWIND_CONTROLLERS_ENABLED", ValueKind::Bool),
+    ("HEADWIND_HELM_AUTO_DISCOVERY", ValueKind::Bool),
+    ("HEADWIND_UI_AUTH_MODE", ValueKind::OneOf(UI_AUTH_MODES)),
+    ("HEADWIND_UI_PROXY_HEADER", ValueKind::Text),
+    ("HEADWIND_UI_PROXY_GROUPS_HEADER", ValueKind::Text),
+    ("HEADWIND_UI_URL", ValueKind::Url),
+    ("HEADWIND_API_URL", ValueKind::Url),
+    ("HEADWIND_DOCKER_HUB_ALIASES", ValueKind::Text),
+    ("HEADWIND_REGISTRY_PROVIDERS", ValueKind::Text),
+    ("HEADWIND_REGISTRY_WORKLOAD_IDENTITY", ValueKind::Bool),
+    ("HEADWIND_WATCH_NAMESPACES", ValueKind::Text),
+    ("HEADWIND_EXCLUDE_NAMESPACES", ValueKind::Text),
+    ("HEADWIND_CACHE_MAX_ENTRIES", ValueKind::PositiveInt),
+    ("HEADWIND_CACHE_TTL_SECONDS", ValueKind::Int),
+    ("HEADWIND_EXPIRED_REQUEST_RETENTION_SECONDS", ValueKind::Int),
+    ("HEADWIND_UPDATE_REQUEST_RETENTION_DAYS", ValueKind::Int),
+    ("HEADWIND_UPDATE_REQUEST_KEEP_PER_TARGET", ValueKind::Int),
+    ("HEADWIND_AUDIT_ENABLED", ValueKind::Bool),
+    ("HEADWIND_AUDIT_RETENTION_DAYS", ValueKind::Int),
+    ("HEADWIND_AUDIT_LOG", ValueKind::Text),
+    ("HEADWIND_CONFIG_STRICT", ValueKind::Bool),
+    ("HEADWIND_ARGOCD_ENABLED", ValueKind::Bool),
+    ("HEADWIND_FLUX_IMAGE_POLICIES_ENABLED", ValueKind::Bool),
+    ("HEADWIND_PLAIN_HELM_ENABLED", ValueKind::Bool),
+    ("HEADWIND_HELM_UPGRADE_IMAGE", ValueKind::Text),
+    ("HEADWIND_HELM_UPGRADE_SERVICE_ACCOUNT", ValueKind::Text),
+    ("HEADWIND_HELM_SOURCE_ARTIFACTS", ValueKind::Bool),
+    (
+        "HEADWIND_HELM_VALUES_SCHEMA",
+        ValueKind::OneOf(VALUES_SCHEMA_MODES),
+    ),
+    ("HEADWIND_GITOPS_TOKEN", ValueKind::Text),
+    ("HEADWIND_PRE_UPDATE_HOOK_URL", ValueKind::Url),
+    ("HEADWIND_POST_UPDATE_HOOK_URL", ValueKind::Url),
+    ("HEADWIND_HOOK_TIMEOUT", ValueKind::PositiveInt),
+    ("HEADWIND_HOOK_SECRET", ValueKind::Text),
+    ("HEADWIND_CHANGE_TICKETS", ValueKind::OneOf(TICKET_SYSTEMS)),
+    ("HEADWIND_CHANGE_TICKET_GATE", ValueKind::Bool),
+    ("HEADWIND_UPDATE_GATE_URL", ValueKind::Url),
+    ("HEADWIND_UPDATE_GATE_TOKEN", ValueKind::Text),
+    ("HEADWIND_UPDATE_GATE_TIMEOUT", ValueKind::PositiveInt),
+    ("HEADWIND_RELEASE_NOTES", ValueKind::Bool),
+    ("HEADWIND_IMAGE_METADATA", ValueKind::Bool),
+    ("HEADWIND_GITHUB_TOKEN", ValueKind::Text),
+    ("HEADWIND_ARTIFACT_HUB_URL", ValueKind::Url),
+    ("HEADWIND_VULNERABILITY_SCAN", ValueKind::Bool),
+    ("HEADWIND_TRIVY_IMAGE", ValueKind::Text),
+    ("HEADWIND_TRIVY_SERVER", ValueKind::Url),
+    ("HEADWIND_TRIVY_CREDENTIALS_SECRET", ValueKind::Text),
+    ("HEADWIND_DRY_RUN", ValueKind::Bool),
+    ("HEADWIND_NOTIFY_DETECTED", ValueKind::Bool),
+    (
+        "HEADWIND_PLATFORM_CHECK",
+        ValueKind::OneOf(PLATFORM_CHECK_MODES),
+    ),
+    ("HEADWIND_GRAPHQL_ENABLED", ValueKind::Bool),
+    ("HEADWIND_APPROVAL_NAMESPACED_ONLY", ValueKind::Bool),
+    ("HEADWIND_SERVER_MODE", ValueKind::OneOf(SERVER_MODES)),
+    ("HEADWIND_SERVER_PORT", ValueKind::PositiveInt),
+    ("HEADWIND_BASE_PATH", ValueKind::Text),
+    ("HEADWIND_WEBHOOK_ADDR", ValueKind::SocketAddr),
+    ("HEADWIND_API_ADDR", ValueKind::SocketAddr),
+    ("HEADWIND_UI_ADDR", ValueKind::SocketAddr),
+    ("HEADWIND_METRICS_ADDR", ValueKind::SocketAddr),
+    ("HEADWIND_MAX_BODY_BYTES", ValueKind::PositiveInt),
+    ("HEADWIND_REQUEST_TIMEOUT", ValueKind::PositiveInt),
+    ("HEADWIND_MAX_CONCURRENT_REQUESTS", ValueKind::PositiveInt),
+    ("HEADWIND_TLS_CERT_FILE", ValueKind::Text),
+    ("HEADWIND_TLS_KEY_FILE", ValueKind::Text),
+    ("HEADWIND_OTLP_ENDPOINT", ValueKind::Url),
+    ("OTEL_SERVICE_NAME", ValueKind::Text),
+    ("JIRA_URL", ValueKind::Url),
+    ("JIRA_USER", ValueKind::Text),
+    ("JIRA_API_TOKEN", ValueKind::Text),
+    ("JIRA_PROJECT", ValueKind::Text),
+    ("JIRA_ISSUE_TYPE", ValueKind::Text),
+    ("JIRA_APPROVED_STATUSES", ValueKind::Text),
+    ("SERVICENOW_URL", ValueKind::Url),
+    ("SERVICENOW_USER", ValueKind::Text),
+    ("SERVICENOW_PASSWORD", ValueKind::Text),
+    ("SERVICENOW_CHANGE_TYPE", ValueKind::Text),
+    ("SERVICENOW_ASSIGNMENT_GROUP", ValueKind::Text),
+    ("HEADWIND_INFLUXDB_TOKEN", ValueKind::Text),
+    ("HEADWIND_NAMESPACE", ValueKind::Text),
+    ("HEADWIND_CLUSTER_NAME", ValueKind::Text),
+    ("HEADWIND_HUB_AGENTS", ValueKind::Text),
+    ("HEADWIND_EVENT_QUEUE_CAPACITY", ValueKind::PositiveInt),
+    ("HEADWIND_EVENT_BUS", ValueKind::OneOf(EVENT_BUSES)),
+    ("HEADWIND_EVENT_BUS_URL", ValueKind::Text),
+    ("HEADWIND_EVENT_BUS_MODE", ValueKind::OneOf(EVENT_BUS_MODES)),
+    ("HEADWIND_EVENT_BUS_PREFIX", ValueKind::Text),
+    ("HEADWIND_EVENT_BUS_GROUP", ValueKind::Text),
+    ("HEADWIND_EVENT_BUS_STREAM", ValueKind::Text),
+    ("HEADWIND_EVENT_RETRY_MAX_ATTEMPTS", ValueKind::PositiveInt),
+    ("HEADWIND_EVENT_RETRY_BASE_DELAY", ValueKind::PositiveInt),
+    ("SLACK_ENABLED", ValueKind::Bool),
+    ("SLACK_WEBHOOK_URL", ValueKind::Url),
+    ("SLACK_CHANNEL", ValueKind::Text),
+    ("SLACK_USERNAME", ValueKind::Text),
+    ("SLACK_ICON_EMOJI", ValueKind::Text),
+    ("TEAMS_ENABLED", ValueKind::Bool),
+    ("TEAMS_WEBHOOK_URL", ValueKind::Url),
+    ("TEAMS_ACTION_SECRET", ValueKind::Text),
+    ("WEBHOOK_ENABLED", ValueKind::Bool),
+    ("WEBHOOK_URL", ValueKind::Url),
+    ("WEBHOOK_SECRET", ValueKind::Text),
+    ("WEBHOOK_TIMEOUT", ValueKind::PositiveInt),
+    ("WEBHOOK_MAX_RETRIES", ValueKind::Int),
+    ("CLOUDEVENTS_ENABLED", ValueKind::Bool),
+    ("CLOUDEVENTS_URL", ValueKind::Url),
+    ("CLOUDEVENTS_NATS_URL", ValueKind::Text),
+    ("CLOUDEVENTS_NATS_SUBJECT", ValueKind::Text),
+    ("CLOUDEVENTS_SOURCE", ValueKind::Text),
+    ("CLOUDEVENTS_TIMEOUT", ValueKind::PositiveInt),
+    ("HEADWIND_NOTIFICATION_DEDUP_WINDOW", ValueKind::Int),
+    ("HEADWIND_NOTIFICATION_MAX_RETRIES", ValueKind::Int),
+    (
+        "HEADWIND_NOTIFICATION_CIRCUIT_THRESHOLD",
+        ValueKind::PositiveInt,
+    ),
+    (
+        "HEADWIND_NOTIFICATION_CIRCUIT_COOLDOWN",
+        ValueKind::PositiveInt,
+    ),
+];
+
+/// ConfigMap keys that are overridden by an environment variable
+const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("polling.enabled", "HEADWIND_POLLING_ENABLED"),
+    ("polling.interval", "HEADWIND_POLLING_INTERVAL"),
+    ("polling.concurrency", "HEADWIND_POLLING_CONCURRENCY"),
+    (
+        "polling.registryConcurrency",
+        "HEADWIND_POLLING_REGISTRY_CONCURRENCY",
+    ),
+    ("controllers.enabled", "HEADWIND_CONTROLLERS_ENABLED"),
+    ("helm.autoDiscovery", "HEADWIND_HELM_AUTO_DISCOVERY"),
+    ("listen.webhook", "HEADWIND_WEBHOOK_ADDR"),
+    ("listen.api", "HEADWIND_API_ADDR"),
+    ("listen.ui", "HEADWIND_UI_ADDR"),
+    ("listen.metrics", "HEADWIND_METRICS_ADDR"),
+];
+
+/// Severity of a validation issue
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Where a configuration value came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigSource {
+    Settings,
+    ConfigMap,
+    Secret,
+    Env,
+}
+
+/// Category of a validation issue
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueKind {
+    UnknownKey,
+    InvalidValue,
+    Conflict,
+}
+
+/// A single problem found in the configuration
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub source: ConfigSource,
+    pub kind: IssueKind,
+    pub key: String,
+    pub message: String,
+}
+
+/// Result of validating the merged configuration
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    /// True when there are no errors (warnings are allowed)
+    pub valid: bool,
+    /// Whether strict mode is enabled (any issue fails startup)
+    pub strict: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn from_issues(issues: Vec<ValidationIssue>, strict: bool) -> Self {
+        let errors = issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .count();
+        Self {
+            valid: errors == 0,
+            strict,
+            errors,
+            warnings: issues.len() - errors,
+            issues,
+        }
+    }
+
+    /// Whether startup may proceed: no errors, and in strict mode no warnings either
+    pub fn passes(&self) -> bool {
+        if self.strict {
+            self.issues.is_empty()
+        } else {
+            self.valid
+        }
+    }
+
+    /// Render the report as a HeadwindSettings status
+    pub fn to_settings_status(&self, observed_generation: Option<i64>) -> HeadwindSettingsStatus {
+        HeadwindSettingsStatus {
+            valid: self.valid,
+            errors: self.errors as u32,
+            warnings: self.warnings as u32,
+            issues: self
+                .issues
+                .iter()
+                .map(|issue| SettingsIssue {
+                    severity: serde_name(&issue.severity),
+                    source: serde_name(&issue.source),
+                    kind: serde_name(&issue.kind),
+                    key: issue.key.clone(),
+                    message: issue.message.clone(),
+                })
+                .collect(),
+            observed_generation,
+        }
+    }
+
+    /// Log each issue plus a one-line summary
+    pub fn log(&self) {
+        for issue in &self.issues {
+            match issue.severity {
+                Severity::Error => error!(
+                    source = ?issue.source,
+                    key = %issue.key,
+                    "Invalid configuration: {}",
+                    issue.message
+                ),
+                Severity::Warning => warn!(
+                    source = ?issue.source,
+                    key = %issue.key,
+                    "Configuration warning: {}",
+                    issue.message
+                ),
+            }
+        }
+
+        if self.issues.is_empty() {
+            info!("Configuration validated with no issues");
+        } else {
+            warn!(
+                "Configuration validation found {} error(s) and {} warning(s)",
+                self.errors, self.warnings
+            );
+        }
+    }
+}
+
+/// The serialized name of a unit enum variant
+fn serde_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Snapshot of the environment variables relevant to validation
+pub fn env_snapshot() -> BTreeMap<String, String> {
+    std::env::vars()
+        .filter(|(key, _)| {
+            key.starts_with("HEADWIND_") || ENV_KEYS.iter().any(|(known, _)| known == key)
+        })
+        .collect()
+}
+
+fn strict_mode(env: &BTreeMap<String, String>) -> bool {
+    env.get("HEADWIND_CONFIG_STRICT")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Validate raw ConfigMap data, decoded Secret data and environment variables
+pub fn validate(
+    config_data: &BTreeMap<String, String>,
+    secret_data: &BTreeMap<String, String>,
+    env: &BTreeMap<String, String>,
+) -> ValidationReport {
+    validate_with_settings(config_data, &BTreeMap::new(), secret_data, env)
+}
+
+/// Validate all configuration sources. `settings_data` holds the fields set in the
+/// HeadwindSettings resource as ConfigMap keys, and takes precedence over `config_data`.
+pub fn validate_with_settings(
+    config_data: &BTreeMap<String, String>,
+    settings_data: &BTreeMap<String, String>,
+    secret_data: &BTreeMap<String, String>,
+    env: &BTreeMap<String, String>,
+) -> ValidationReport {
+    let mut issues = Vec::new();
+
+    check_keys(
+        settings_data,
+        ConfigSource::Settings,
+        CONFIGMAP_KEYS,
+        |_| true,
+        &mut issues,
+    );
+    check_keys(
+        config_data,
+        ConfigSource::ConfigMap,
+        CONFIGMAP_KEYS,
+        |_| true,
+        &mut issues,
+    );
+    check_keys(
+        secret_data,
+        ConfigSource::Secret,
+        SECRET_KEYS,
+        |_| true,
+        &mut issues,
+    );
+    check_keys(
+        env,
+        ConfigSource::Env,
+        ENV_KEYS,
+        |key| key.starts_with("HEADWIND_"),
+        &mut issues,
+    );
+    check_settings_overrides(config_data, settings_data, &mut issues);
+
+    let mut merged = config_data.clone();
+    merged.extend(settings_data.iter().map(|(k, v)| (k.clone(), v.clone())));
+    check_conflicts(&merged, settings_data, secret_data, env, &mut issues);
+
+    ValidationReport::from_issues(issues, strict_mode(env))
+}
+
+/// Check every key in `data` is known and that its value parses as the expected type.
+/// Unknown keys are only reported when `check_unknown` returns true for them.
+fn check_keys(
+    data: &BTreeMap<String, String>,
+    source: ConfigSource,
+    known: &[(&'static str, ValueKind)],
+    check_unknown: impl Fn(&str) -> bool,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    for (key, value) in data {
+        match known.iter().find(|(k, _)| k == key) {
+            Some((_, kind)) => {
+                if let Some(problem) = check_value(*kind, value) {
+                    // Never echo secret values back
+                    let message = if source == ConfigSource::Secret {
+                        problem
+                    } else {
+                        format!("{} (got \"{}\")", problem, value)
+                    };
+                    issues.push(ValidationIssue {
+                        severity: Severity::Error,
+                        source,
+                        kind: IssueKind::InvalidValue,
+                        key: display_key(source, key),
+                        message,
+                    });
+                }
+            },
+            None if check_unknown(key) => {
+                let message = match closest_key(key, known) {
+                    Some(suggestion) => {
+                        format!("Unknown key, ignored (did you mean \"{}\"?)", suggestion)
+                    },
+                    None => "Unknown key, ignored".to_string(),
+                };
+                issues.push(ValidationIssue {
+                    severity: Severity::Warning,
+                    source,
+                    kind: IssueKind::UnknownKey,
+                    key: display_key(source, key),
+                    message,
+                });
+            },
+            None => {},
+        }
+    }
+}
+
+/// Settings issues are reported by field path, everything else by key
+fn display_key(source: ConfigSource, key: &str) -> String {
+    if source == ConfigSource::Settings {
+        spec_path(key)
+    } else {
+        key.to_string()
+    }
+}
+
+/// Describe why `value` is not a valid `kind`, or None if it is valid.
+/// Empty values are treated as unset.
+fn check_value(kind: ValueKind, value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    match kind {
+        ValueKind::Bool => value
+            .parse::<bool>()
+            .err()
+            .map(|_| "Expected \"true\" or \"false\"".to_string()),
+        ValueKind::PositiveInt => match value.parse::<u64>() {
+            Ok(0) => Some("Expected a number greater than 0".to_string()),
+            Ok(_) => None,
+            Err(_) => Some("Expected a positive integer".to_string()),
+        },
+        ValueKind::Int => value
+            .parse::<u64>()
+            .err()
+            .map(|_| "Expected a non-negative integer".to_string()),
+        ValueKind::Url => (!value.starts_with("http://") && !value.starts_with("https://"))
+            .then(|| "Expected an http:// or https:// URL".to_string()),
+        ValueKind::Text => None,
+        ValueKind::SocketAddr => value
+            .parse::<std::net::SocketAddr>()
+            .err()
+            .map(|_| "Expected an ip:port address, e.g. 0.0.0.0:8080".to_string()),
+        ValueKind::Registries => super::registries::parse(value).err().map(|e| e.to_string()),
+        ValueKind::PlainHelmReleases => super::plainhelm::parse(value).err(),
+        ValueKind::NotificationRoutes => super::notificationroutes::parse(value).err(),
+        ValueKind::UiRoles => super::uiroles::parse(value).err().map(|e| e.to_string()),
+        ValueKind::PolicyDefaults => super::policydefaults::parse(value).err(),
+        ValueKind::OneOf(allowed) => (!allowed.contains(&value.to_lowercase().as_str()))
+            .then(|| format!("Expected one of: {}", allowed.join(", "))),
+    }
+}
+
+/// HeadwindSettings wins over the ConfigMap; flag ConfigMap values that are being ignored
+fn check_settings_overrides(
+    config_data: &BTreeMap<String, String>,
+    settings_data: &BTreeMap<String, String>,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    for (key, settings_value) in settings_data {
+        if let Some(config_value) = config_data.get(key)
+            && !config_value.trim().is_empty()
+            && config_value.trim() != settings_value.trim()
+        {
+            issues.push(ValidationIssue {
+                severity: Severity::Warning,
+                source: ConfigSource::Settings,
+                kind: IssueKind::Conflict,
+                key: spec_path(key),
+                message: format!(
+                    "HeadwindSettings value \"{}\" overrides ConfigMap {}={}",
+                    settings_value.trim(),
+                    key,
+                    config_value.trim()
+                ),
+            });
+        }
+    }
+}
+
+/// `config_data` is the merged ConfigMap and HeadwindSettings data; keys present in
+/// `settings_data` are attributed to the HeadwindSettings resource.
+fn check_conflicts(
+    config_data: &BTreeMap<String, String>,
+    settings_data: &BTreeMap<String, String>,
+    secret_data: &BTreeMap<String, String>,
+    env: &BTreeMap<String, String>,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    let source_of = |key: &str| {
+        if settings_data.contains_key(key) {
+            ConfigSource::Settings
+        } else {
+            ConfigSource::ConfigMap
+        }
+    };
+
+    let is_true = |data: &BTreeMap<String, String>, key: &str| {
+        data.get(key).is_some_and(|v| v.trim() == "true")
+    };
+    let is_set = |data: &BTreeMap<String, String>, key: &str| {
+        data.get(key).is_some_and(|v| !v.trim().is_empty())
+    };
+
+    // Enabled notifiers need a URL from the Secret or the environment
+    for (enabled_key, enabled_env, secret_key, url_env) in [
+        (
+            "slack.enabled",
+            "SLACK_ENABLED",
+            "slack-webhook-url",
+            "SLACK_WEBHOOK_URL",
+        ),
+        (
+            "teams.enabled",
+            "TEAMS_ENABLED",
+            "teams-webhook-url",
+            "TEAMS_WEBHOOK_URL",
+        ),
+        (
+            "webhook.enabled",
+            "WEBHOOK_ENABLED",
+            "webhook-url",
+            "WEBHOOK_URL",
+        ),
+    ] {
+        let enabled = is_true(config_data, enabled_key) || is_true(env, enabled_env);
+        if enabled && !is_set(secret_data, secret_key) && !is_set(env, url_env) {
+            let source = source_of(enabled_key);
+            issues.push(ValidationIssue {
+                severity: Severity::Error,
+                source,
+                kind: IssueKind::Conflict,
+                key: display_key(source, enabled_key),
+                message: format!(
+                    "Notifier is enabled but no URL is configured (set Secret key \"{}\" or {})",
+                    secret_key, url_env
+                ),
+            });
+        }
+    }
+
+    // Pub/Sub needs a subscription, and a project unless the subscription is a full name
+    if config_data
+        .get("pubsub.enabled")
+        .is_some_and(|v| v.trim() == "true")
+    {
+        let subscription = config_data
+            .get("pubsub.subscription")
+            .map(|v| v.trim())
+            .unwrap_or_default();
+        let project = config_data
+            .get("pubsub.project")
+            .map(|v| v.trim())
+            .unwrap_or_default();
+        let problem = if subscription.is_empty() {
+            Some("Pub/Sub is enabled but pubsub.subscription is not set")
+        } else if project.is_empty() && !subscription.starts_with("projects/") {
+            Some("Pub/Sub is enabled but pubsub.project is not set")
+        } else {
+            None
+        };
+        if let Some(message) = problem {
+            let source = source_of("pubsub.enabled");
+            issues.push(ValidationIssue {
+                severity: Severity::Error,
+                source,
+                kind: IssueKind::Conflict,
+                key: display_key(source, "pubsub.enabled"),
+                message: message.to_string(),
+            });
+        }
+    }
+
+    // An explicit metrics backend must not be disabled
+    if let Some(backend) = config_data.get("observability.metricsBackend") {
+        let backend = backend.trim().to_lowercase();
+        if ["prometheus", "victoriametrics", "influxdb"].contains(&backend.as_str()) {
+            let enabled_key = format!("observability.{}.enabled", backend);
+            if config_data
+                .get(&enabled_key)
+                .is_some_and(|v| v.trim() == "false")
+            {
+                let source = source_of("observability.metricsBackend");
+                issues.push(ValidationIssue {
+                    severity: Severity::Error,
+                    source,
+                    kind: IssueKind::Conflict,
+                    key: display_key(source, "observability.metricsBackend"),
+                    message: format!(
+                        "Metrics backend is \"{}\" but {} is false",
+                        backend, enabled_key
+                    ),
+                });
+            }
+        }
+    }
+
+    // Environment variables win over these keys at startup
+    for (config_key, env_key) in ENV_OVERRIDES {
+        if let (Some(config_value), Some(env_value)) =
+            (config_data.get(*config_key), env.get(*env_key))
+            && !config_value.trim().is_empty()
+            && config_value.trim() != env_value.trim()
+        {
+            issues.push(ValidationIssue {
+                severity: Severity::Warning,
+                source: ConfigSource::Env,
+                kind: IssueKind::Conflict,
+                key: env_key.to_string(),
+                message: format!(
+                    "{}={} overrides {} {}={}",
+                    env_key,
+                    env_value.trim(),
+                    match source_of(config_key) {
+                        ConfigSource::Settings => "HeadwindSettings",
+                        _ => "ConfigMap",
+                    },
+                    config_key,
+                    config_value.trim()
+                ),
+            });
+        }
+    }
+}
+
+/// Suggest the closest known key for a likely typo
+fn closest_key(key: &str, known: &[(&'static str, ValueKind)]) -> Option<&'static str> {
+    let lowered = key.to_lowercase();
+    known
+        .iter()
+        .map(|(k, _)| (*k, edit_distance(&lowered, &k.to_lowercase())))
+        .filter(|(_, distance)| *distance <= 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(k, _)| k)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Most recent validation report, refreshed on every configuration load
+static LAST_REPORT: once_cell::sync::Lazy<RwLock<Option<ValidationReport>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(None));
+
+/// Get the report from the most recent configuration load, if any
+pub fn last_report() -> Option<ValidationReport> {
+    LAST_REPORT.read().ok().and_then(|guard| guard.clone())
+}
+
+/// Store a report, logging it only if it differs from the previous one
+pub(crate) fn store_report(report: ValidationReport) {
+    if let Ok(mut guard) = LAST_REPORT.write() {
+        if guard.as_ref() != Some(&report) {
+            report.log();
+        }
+        *guard = Some(report);
+    }
+}
+
+/// Validate the current HeadwindSettings, ConfigMap, Secret and environment
+pub async fn validate_current(
+    client: Client,
+) -> Result<ValidationReport, Box<dyn std::error::Error>> {
+    let raw = HeadwindConfig::load_raw(client).await?;
+    Ok(validate_with_settings(
+        &raw.config_data,
+        &raw.settings_data,
+        &raw.secret_data,
+        &env_snapshot(),
+    ))
+}
+
+/// Validate configuration at startup, failing if the report does not pass
+/// (any error, or any issue at all in strict mode)
+pub async fn validate_startup(client: Client) -> anyhow::Result<ValidationReport> {
+    let report = validate_current(client)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to load configuration for validation: {}", e))?;
+    store_report(report.clone());
+
+    if report.strict && !report.passes() {
+        anyhow::bail!(
+            "Configuration validation failed in strict mode with {} error(s) and {} warning(s)",
+            report.errors,
+            report.warnings
+        );
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_config_has_no_issues() {
+        let config = map(&[
+            ("polling.enabled", "true"),
+            ("polling.interval", "600"),
+            ("slack.enabled", "true"),
+            ("observability.metricsBackend", "prometheus"),
+        ]);
+        let secrets = map(&[("slack-webhook-url", "https://hooks.slack.com/services/x")]);
+
+        let report = validate(&config, &secrets, &BTreeMap::new());
+        assert!(report.valid);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert!(report.passes());
+    }
+
+    #[test]
+    fn test_unknown_key_suggests_closest() {
+        let config = map(&[("poling.enabled", "true")]);
+
+        let report = validate(&config, &BTreeMap::new(), &BTreeMap::new());
+        assert_eq!(report.warnings, 1);
+        let issue = &report.issues[0];
+        assert_eq!(issue.kind, IssueKind::UnknownKey);
+        assert_eq!(issue.key, "poling.enabled");
+        assert!(issue.message.contains("polling.enabled"));
+        // Warnings alone don't fail outside strict mode
+        assert!(report.passes());
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let config = map(&[
+            ("polling.enabled", "yes"),
+            ("polling.interval", "0"),
+            ("observability.metricsBackend", "graphite"),
+            ("registries", "harbor.example.com:\n  insecure: true\n"),
+            (
+                "plainHelmReleases",
+                "prometheus:\n  repository: https://example.com\n",
+            ),
+            ("listen.ui", "localhost:8082"),
+        ]);
+        let env = map(&[("HEADWIND_UI_AUTH_MODE", "oauth")]);
+
+        let report = validate(&config, &BTreeMap::new(), &env);
+        assert_eq!(report.errors, 7);
+        assert!(!report.valid);
+        assert!(
+            report
+                .issues
+                .iter()
+                .all(|i| i.kind == IssueKind::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn test_secret_values_are_not_echoed() {
+        let secrets = map(&[("webhook-url", "not-a-url-but-secret")]);
+
+        let report = validate(&BTreeMap::new(), &secrets, &BTreeMap::new());
+        assert_eq!(report.errors, 1);
+        assert!(!report.issues[0].message.contains("not-a-url-but-secret"));
+    }
+
+    #[test]
+    fn test_enabled_notifier_without_url_conflicts() {
+        let config = map(&[("teams.enabled", "true")]);
+
+        let report = validate(&config, &BTreeMap::new(), &BTreeMap::new());
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.issues[0].kind, IssueKind::Conflict);
+
+        // A URL from the environment satisfies the requirement
+        let env = map(&[("TEAMS_WEBHOOK_URL", "https://example.com/hook")]);
+        assert!(validate(&config, &BTreeMap::new(), &env).issues.is_empty());
+    }
+
+    #[test]
+    fn test_disabled_metrics_backend_conflicts() {
+        let config = map(&[
+            ("observability.metricsBackend", "influxdb"),
+            ("observability.influxdb.enabled", "false"),
+        ]);
+
+        let report = validate(&config, &BTreeMap::new(), &BTreeMap::new());
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.issues[0].key, "observability.metricsBackend");
+    }
+
+    #[test]
+    fn test_enabled_pubsub_without_subscription_conflicts() {
+        let config = map(&[("pubsub.enabled", "true"), ("pubsub.project", "my-project")]);
+
+        let report = validate(&config, &BTreeMap::new(), &BTreeMap::new());
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.issues[0].key, "pubsub.enabled");
+
+        // A full subscription name doesn't need a separate project
+        let config = map(&[
+            ("pubsub.enabled", "true"),
+            (
+                "pubsub.subscription",
+                "projects/my-project/subscriptions/headwind",
+            ),
+        ]);
+        assert!(
+            validate(&config, &BTreeMap::new(), &BTreeMap::new())
+                .issues
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_env_override_and_unknown_env_var() {
+        let config = map(&[("polling.enabled", "false")]);
+        let env = map(&[
+            ("HEADWIND_POLLING_ENABLED", "true"),
+            ("HEADWIND_POLING_INTERVAL", "60"),
+        ]);
+
+        let report = validate(&config, &BTreeMap::new(), &env);
+        assert_eq!(report.errors, 0);
+        assert_eq!(report.warnings, 2);
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| i.kind == IssueKind::Conflict && i.key == "HEADWIND_POLLING_ENABLED")
+        );
+        assert!(
+            report.issues.iter().any(|i| i.kind == IssueKind::UnknownKey
+                && i.message.contains("HEADWIND_POLLING_INTERVAL"))
+        );
+    }
+
+    #[test]
+    fn test_strict_mode_fails_on_warnings() {
+        let config = map(&[("poling.enabled", "true")]);
+        let env = map(&[("HEADWIND_CONFIG_STRICT", "true")]);
+
+        let report = validate(&config, &BTreeMap::new(), &env);
+        assert!(report.strict);
+        assert!(report.valid);
+        assert!(!report.passes());
+    }
+
+    #[test]
+    fn test_settings_override_configmap() {
+        let config = map(&[("polling.interval", "300"), ("slack.enabled", "false")]);
+        let settings = map(&[("polling.interval", "0"), ("slack.enabled", "true")]);
+
+        let report = validate_with_settings(&config, &settings, &BTreeMap::new(), &BTreeMap::new());
+
+        // Invalid settings values are reported by field path
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| i.source == ConfigSource::Settings
+                    && i.kind == IssueKind::InvalidValue
+                    && i.key == "spec.polling.interval")
+        );
+        // The merged value (slack enabled by settings) needs a URL
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| i.source == ConfigSource::Settings
+                    && i.kind == IssueKind::Conflict
+                    && i.key == "spec.notifications.slack.enabled"
+                    && i.severity == Severity::Error)
+        );
+        // Both overridden ConfigMap values are flagged
+        assert_eq!(
+            report
+                .issues
+                .iter()
+                .filter(|i| i.kind == IssueKind::Conflict && i.severity == Severity::Warning)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_to_settings_status() {
+        let config = map(&[("poling.enabled", "true")]);
+        let report = validate(&config, &BTreeMap::new(), &BTreeMap::new());
+
+        let status = report.to_settings_status(Some(3));
+        assert!(status.valid);
+        assert_eq!(status.warnings, 1);
+        assert_eq!(status.observed_generation, Some(3));
+        assert_eq!(status.issues[0].severity, "warning");
+        assert_eq!(status.issues[0].source, "configMap");
+        assert_eq!(status.issues[0].kind, "unknownKey");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("poling.enabled", "polling.enabled"), 1);
+        assert_eq!(edit_distance("abc", "abc"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
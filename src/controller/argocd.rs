@@ -0,0 +1,958 @@
+use crate::audit;
+use crate::config::namespaces;
+use crate::controller::workload::{WorkloadImageUpdate, create_update_request, map_policy_to_crd};
+use crate::health;
+use crate::helm::{HelmRepositoryClient, OciHelmClient};
+use crate::metrics::{
+    HELM_REPOSITORY_ERRORS, HELM_REPOSITORY_QUERIES, RECONCILE_DURATION, RECONCILE_ERRORS,
+};
+use crate::models::argocd::{Application, ApplicationSet, ApplicationSpec};
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
+use crate::models::crd::{TargetRef, UpdateRequestSpec, UpdateType};
+use crate::models::policy::annotations;
+use crate::models::{
+    PrereleasePolicy, ResourcePolicy, UpdateMode, UpdatePolicy, parse_approval_ttl,
+    parse_ignore_patterns, parse_max_version, parse_require_approval, parse_required_approvals,
+};
+use crate::notifications::{self, DeploymentInfo};
+use crate::policy::PolicyEngine;
+use anyhow::Result;
+use chrono::Utc;
+use futures::StreamExt;
+use kube::{
+    Api, Client, ResourceExt,
+    api::{Patch, PatchParams},
+    runtime::{Controller, controller::Action, watcher::Config},
+};
+use serde_json::{Value, json};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tracing::{debug, error, info, warn};
+
+const API_VERSION: &str = "argoproj.io/v1alpha1";
+
+/// Namespace Applications are looked up in when the annotation doesn't name one
+pub const DEFAULT_APPLICATION_NAMESPACE: &str = "argocd";
+
+/// Helm parameter that receives the new tag when the workload doesn't name one
+pub const DEFAULT_IMAGE_PARAMETER: &str = "image.tag";
+
+/// Whether the ArgoCD Application controller is enabled (HEADWIND_ARGOCD_ENABLED, default: false)
+pub fn argocd_enabled() -> bool {
+    std::env::var("HEADWIND_ARGOCD_ENABLED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Watches annotated ArgoCD Applications and moves their chart `targetRevision`
+/// forward when a newer chart version is published
+pub struct ArgoCdController {
+    client: Client,
+    policy_engine: Arc<PolicyEngine>,
+}
+
+impl ArgoCdController {
+    pub async fn new(policy_engine: Arc<PolicyEngine>) -> Result<Self> {
+        let client = Client::try_default().await?;
+
+        info!("ArgoCD controller initialized");
+
+        Ok(Self {
+            client,
+            policy_engine,
+        })
+    }
+
+    pub async fn run(self) {
+        let api: Api<Application> = namespaces::scoped_api(self.client.clone());
+
+        let helm_repo_client =
+            HelmRepositoryClient::new().expect("Failed to create Helm repository client");
+
+        let context = Arc::new(ControllerContext {
+            client: self.client.clone(),
+            policy_engine: self.policy_engine.clone(),
+            helm_repo_client,
+            oci_helm_client: OciHelmClient::new(),
+        });
+
+        Controller::new(api, Config::default())
+            .shutdown_on_signal()
+            .run(reconcile, error_policy, context)
+            .inspect(|res| health::track_controller("Application", res))
+            .filter_map(|x| async move { std::result::Result::ok(x) })
+            .for_each(|_| futures::future::ready(()))
+            .await;
+    }
+}
+
+struct ControllerContext {
+    client: Client,
+    policy_engine: Arc<PolicyEngine>,
+    helm_repo_client: HelmRepositoryClient,
+    oci_helm_client: OciHelmClient,
+}
+
+async fn reconcile(
+    application: Arc<Application>,
+    ctx: Arc<ControllerContext>,
+) -> Result<Action, kube::Error> {
+    let _timer = RECONCILE_DURATION.start_timer();
+
+    let namespace = application.namespace().unwrap_or_default();
+    let name = application.name_any();
+
+    if !namespaces::is_watched(&namespace) {
+        return Ok(Action::await_change());
+    }
+
+    let Some(policy) = resource_policy(application.annotations()) else {
+        debug!(
+            "Application {}/{} has no update policy, skipping",
+            namespace, name
+        );
+        return Ok(Action::requeue(Duration::from_secs(3600)));
+    };
+
+    // Only Applications deploying a chart from a Helm repository have a version to move
+    let Some((_, source)) = application.spec.chart_source() else {
+        debug!(
+            "Application {}/{} has no Helm chart source, skipping",
+            namespace, name
+        );
+        return Ok(Action::requeue(Duration::from_secs(3600)));
+    };
+    let chart_name = source.chart.as_deref().unwrap_or_default();
+    let Some(current_version) = source.target_revision.as_deref() else {
+        debug!(
+            "Application {}/{} has no targetRevision, skipping",
+            namespace, name
+        );
+        return Ok(Action::requeue(Duration::from_secs(3600)));
+    };
+
+    let Some(new_version) = discover_chart_version(
+        &ctx,
+        &source.repo_url,
+        chart_name,
+        current_version,
+        &policy.policy,
+    )
+    .await
+    else {
+        debug!("Application {}/{} - No new chart version", namespace, name);
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    };
+
+    match ctx
+        .policy_engine
+        .should_update(&policy, current_version, &new_version)
+    {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!(
+                "Application {}/{} - Update from {} to {} rejected by policy",
+                namespace, name, current_version, new_version
+            );
+            return Ok(Action::requeue(Duration::from_secs(300)));
+        },
+        Err(e) => {
+            warn!(
+                "Application {}/{} - Error checking update policy: {}",
+                namespace, name, e
+            );
+            return Ok(Action::requeue(Duration::from_secs(300)));
+        },
+    }
+
+    let current_chart = format!("{}:{}", chart_name, current_version);
+    let new_chart = format!("{}:{}", chart_name, new_version);
+    let resource = DeploymentInfo {
+        name: name.clone(),
+        namespace: namespace.clone(),
+        current_image: current_chart.clone(),
+        new_image: new_chart.clone(),
+        container: None,
+        resource_kind: Some("Application".to_string()),
+    };
+    audit::record(UpdateAuditSpec::new(AuditAction::Detected, &resource));
+
+    if policy.require_approval {
+        let spec = UpdateRequestSpec {
+            target_ref: target_ref(&namespace, &name),
+            update_type: UpdateType::HelmChart,
+            container_name: None,
+            image_parameter: None,
+            current_image: current_chart,
+            new_image: new_chart,
+            policy: map_policy_to_crd(&policy.policy),
+            reason: Some(format!("New chart version {} available", new_version)),
+            require_approval: true,
+            expires_at: policy.approval_expiry(),
+            required_approvals: policy.required_approvals,
+            git_write_back: None,
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
+        };
+
+        if let Err(e) = create_update_request(&ctx.client, &namespace, spec).await {
+            warn!(
+                "Failed to create UpdateRequest for Application {}/{}: {}",
+                namespace, name, e
+            );
+        }
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    }
+
+    // Check minimum update interval
+    let min_update_interval = policy.min_update_interval.unwrap_or(300);
+    if let Some(last_update_str) = application.annotations().get(annotations::LAST_UPDATE)
+        && let Ok(last_update) = chrono::DateTime::parse_from_rfc3339(last_update_str)
+    {
+        let elapsed = Utc::now().signed_duration_since(last_update.with_timezone(&Utc));
+        if elapsed < chrono::Duration::seconds(min_update_interval as i64) {
+            info!(
+                "Skipping update for Application {}/{}: minimum interval not reached ({} < {}s)",
+                namespace,
+                name,
+                elapsed.num_seconds(),
+                min_update_interval
+            );
+            crate::metrics::UPDATES_SKIPPED_INTERVAL
+                .with_label_values(&[namespace.as_str(), "Application"])
+                .inc();
+            return Ok(Action::requeue(Duration::from_secs(min_update_interval)));
+        }
+    }
+
+    let result = update_application_chart_version(
+        &ctx.client,
+        &namespace,
+        &name,
+        chart_name,
+        current_version,
+        &new_version,
+    )
+    .await;
+    audit::record(UpdateAuditSpec::new(AuditAction::Applied, &resource).with_result(&result));
+    match result {
+        Ok(()) => {
+            let apps: Api<Application> = Api::namespaced(ctx.client.clone(), &namespace);
+            let patch = json!({
+                "metadata": {
+                    "annotations": {
+                        annotations::LAST_UPDATE: Utc::now().to_rfc3339()
+                    }
+                }
+            });
+            if let Err(e) = apps
+                .patch(&name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await
+            {
+                warn!(
+                    "Failed to update last-update annotation for Application {}/{}: {}",
+                    namespace, name, e
+                );
+            }
+        },
+        Err(e) => {
+            warn!(
+                "Failed to update Application {}/{} to chart version {}: {}",
+                namespace, name, new_version, e
+            );
+        },
+    }
+
+    Ok(Action::requeue(Duration::from_secs(300)))
+}
+
+fn error_policy(
+    _application: Arc<Application>,
+    error: &kube::Error,
+    _ctx: Arc<ControllerContext>,
+) -> Action {
+    RECONCILE_ERRORS.with_label_values(&["Application"]).inc();
+    error!("Reconciliation error: {}", error);
+    Action::requeue(Duration::from_secs(60))
+}
+
+/// Find the best chart version allowed by the policy. ArgoCD writes OCI Helm repositories
+/// without a scheme (`registry.example.com/charts`), so anything that isn't http(s) is
+/// treated as OCI. Only public repositories are queried; ArgoCD repository credentials
+/// are not read.
+async fn discover_chart_version(
+    ctx: &ControllerContext,
+    repo_url: &str,
+    chart_name: &str,
+    current_version: &str,
+    policy: &UpdatePolicy,
+) -> Option<String> {
+    HELM_REPOSITORY_QUERIES.inc();
+
+    if repo_url.starts_with("http://") || repo_url.starts_with("https://") {
+        match ctx.helm_repo_client.fetch_index(repo_url).await {
+            Ok(index) => {
+                ctx.helm_repo_client
+                    .find_best_version(&index, chart_name, current_version, policy)
+            },
+            Err(e) => {
+                warn!("Failed to fetch index from {}: {}", repo_url, e);
+                HELM_REPOSITORY_ERRORS.inc();
+                None
+            },
+        }
+    } else {
+        let oci_url = format!(
+            "oci://{}/{}",
+            repo_url.trim_start_matches("oci://").trim_end_matches('/'),
+            chart_name
+        );
+        match ctx
+            .oci_helm_client
+            .get_chart_versions(&oci_url, None, None)
+            .await
+        {
+            Ok(versions) => {
+                ctx.oci_helm_client
+                    .find_best_version(&versions, current_version, policy)
+            },
+            Err(e) => {
+                warn!("Failed to list OCI tags from {}: {}", oci_url, e);
+                HELM_REPOSITORY_ERRORS.inc();
+                None
+            },
+        }
+    }
+}
+
+/// Build the update policy from an Application's annotations. Returns None when the
+/// Application has no policy or policy `none`.
+fn resource_policy(annotations: &BTreeMap<String, String>) -> Option<ResourcePolicy> {
+    let policy: UpdatePolicy = annotations.get(annotations::POLICY)?.parse().ok()?;
+    if policy == UpdatePolicy::None {
+        return None;
+    }
+
+    Some(ResourcePolicy {
+        policy,
+        pattern: annotations.get(annotations::PATTERN).cloned(),
+        require_approval: parse_require_approval(annotations),
+        min_update_interval: annotations
+            .get(annotations::MIN_UPDATE_INTERVAL)
+            .and_then(|v| v.parse().ok()),
+        images: Vec::new(),
+        event_source: Default::default(),
+        polling_interval: None,
+        ignore_patterns: annotations
+            .get(annotations::IGNORE_PATTERN)
+            .map(|v| parse_ignore_patterns(v))
+            .unwrap_or_default(),
+        prerelease: PrereleasePolicy::from_annotations(annotations),
+        pin_digest: false,
+        channel_tag: None,
+        update_mode: UpdateMode::Image,
+        approval_ttl: parse_approval_ttl(annotations),
+        required_approvals: parse_required_approvals(annotations),
+        max_version: parse_max_version(annotations),
+    })
+}
+
+fn target_ref(namespace: &str, name: &str) -> TargetRef {
+    TargetRef {
+        api_version: API_VERSION.to_string(),
+        kind: "Application".to_string(),
+        name: name.to_string(),
+        namespace: namespace.to_string(),
+    }
+}
+
+/// The ArgoCD Application a workload is deployed by
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplicationRef {
+    pub namespace: String,
+    pub name: String,
+    /// Helm parameter that receives the new image tag
+    pub image_parameter: String,
+}
+
+/// Read the `headwind.sh/argocd-application` annotation (`name` or `namespace/name`).
+/// Workloads carrying it are updated through their Application instead of being patched
+/// directly, so ArgoCD self-heal doesn't revert the change.
+pub fn managing_application(annotations: &BTreeMap<String, String>) -> Option<ApplicationRef> {
+    let value = annotations.get(annotations::ARGOCD_APPLICATION)?.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let (namespace, name) = value
+        .split_once('/')
+        .unwrap_or((DEFAULT_APPLICATION_NAMESPACE, value));
+
+    let image_parameter = annotations
+        .get(annotations::ARGOCD_IMAGE_PARAMETER)
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .unwrap_or(DEFAULT_IMAGE_PARAMETER);
+
+    Some(ApplicationRef {
+        namespace: namespace.to_string(),
+        name: name.to_string(),
+        image_parameter: image_parameter.to_string(),
+    })
+}
+
+/// Route a workload image update to the Application that manages the workload. The caller
+/// has already checked the policy and minimum update interval.
+pub async fn handle_workload_image_update(
+    client: &Client,
+    application: &ApplicationRef,
+    update: WorkloadImageUpdate<'_>,
+) -> Result<()> {
+    info!(
+        "{} {}/{} is managed by Application {}/{}, updating the Application",
+        update.kind, update.namespace, update.name, application.namespace, application.name
+    );
+
+    if update.policy.require_approval {
+        let spec = UpdateRequestSpec {
+            target_ref: target_ref(&application.namespace, &application.name),
+            update_type: UpdateType::Image,
+            container_name: update.container_name.map(str::to_string),
+            image_parameter: Some(application.image_parameter.clone()),
+            current_image: update.current_image.to_string(),
+            new_image: update.new_image.to_string(),
+            policy: map_policy_to_crd(&update.policy.policy),
+            reason: Some(format!(
+                "New image for {} {}/{}: {} -> {}",
+                update.kind, update.namespace, update.name, update.current_image, update.new_image
+            )),
+            require_approval: true,
+            expires_at: update.policy.approval_expiry(),
+            required_approvals: update.policy.required_approvals,
+            git_write_back: None,
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
+        };
+
+        create_update_request(client, &application.namespace, spec).await?;
+    } else {
+        let resource = DeploymentInfo {
+            name: application.name.clone(),
+            namespace: application.namespace.clone(),
+            current_image: update.current_image.to_string(),
+            new_image: update.new_image.to_string(),
+            container: update.container_name.map(str::to_string),
+            resource_kind: Some("Application".to_string()),
+        };
+        let result = update_application_image(
+            client,
+            &application.namespace,
+            &application.name,
+            &application.image_parameter,
+            update.new_image,
+        )
+        .await;
+        audit::record(
+            UpdateAuditSpec::new(AuditAction::Applied, &resource)
+                .with_message(format!(
+                    "{} {}/{} is managed by this Application",
+                    update.kind, update.namespace, update.name
+                ))
+                .with_result(&result),
+        );
+        result?;
+
+        notifications::notify_update_completed(resource);
+    }
+
+    Ok(())
+}
+
+/// Set the image tag on an Application: as a Helm parameter for Helm sources, or as a
+/// kustomize image override for Kustomize sources
+pub async fn update_application_image(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    image_parameter: &str,
+    new_image: &str,
+) -> Result<()> {
+    info!(
+        "Updating Application {}/{} to image {}",
+        namespace, name, new_image
+    );
+
+    patch_application(client, namespace, name, |spec| {
+        image_patch(spec, image_parameter, new_image)
+    })
+    .await?;
+
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[namespace, "Application"])
+        .inc();
+
+    Ok(())
+}
+
+/// Move the chart source of an Application to a new version
+pub async fn update_application_chart_version(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    chart_name: &str,
+    current_version: &str,
+    new_version: &str,
+) -> Result<()> {
+    patch_application(client, namespace, name, |spec| {
+        let (path, source) = spec.chart_source().ok_or_else(|| {
+            anyhow::anyhow!("Application {}/{} has no chart source", namespace, name)
+        })?;
+
+        if source.chart.as_deref() != Some(chart_name) {
+            return Err(anyhow::anyhow!(
+                "Chart name mismatch: expected {}, found {}",
+                chart_name,
+                source.chart.as_deref().unwrap_or_default()
+            ));
+        }
+
+        Ok(json!([{
+            "op": "replace",
+            "path": format!("{}/targetRevision", path),
+            "value": new_version
+        }]))
+    })
+    .await?;
+
+    info!(
+        "Successfully updated Application {}/{} to chart version {}",
+        namespace, name, new_version
+    );
+
+    notifications::notify_update_completed(DeploymentInfo {
+        name: name.to_string(),
+        namespace: namespace.to_string(),
+        current_image: format!("{}:{}", chart_name, current_version),
+        new_image: format!("{}:{}", chart_name, new_version),
+        container: None,
+        resource_kind: Some("Application".to_string()),
+    });
+
+    crate::metrics::HELM_UPDATES_APPLIED
+        .with_label_values(&[namespace])
+        .inc();
+
+    Ok(())
+}
+
+/// Apply the JSON patch `build` returns for an Application's spec. Applications generated
+/// by an ApplicationSet get the patch on the ApplicationSet's template instead, which
+/// changes every Application it generates.
+async fn patch_application(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    build: impl Fn(&ApplicationSpec) -> Result<Value>,
+) -> Result<()> {
+    let apps: Api<Application> = Api::namespaced(client.clone(), namespace);
+    let application = apps.get(name).await?;
+
+    let Some(set_name) = owning_application_set(&application) else {
+        let patch = build(&application.spec)?;
+        apps.patch(
+            name,
+            &PatchParams::default(),
+            &Patch::Json::<()>(serde_json::from_value(patch)?),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    info!(
+        "Application {}/{} is generated by ApplicationSet {}, updating its template",
+        namespace, name, set_name
+    );
+
+    let sets: Api<ApplicationSet> = Api::namespaced(client.clone(), namespace);
+    let set = sets.get(&set_name).await?;
+    let patch = template_patch(&set, build(&set.spec.template.spec)?)?;
+    sets.patch(
+        &set_name,
+        &PatchParams::default(),
+        &Patch::Json::<()>(serde_json::from_value(patch)?),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Name of the ApplicationSet that generated an Application, if any
+fn owning_application_set(application: &Application) -> Option<String> {
+    application
+        .owner_references()
+        .iter()
+        .find(|owner| owner.kind == "ApplicationSet" && owner.api_version == API_VERSION)
+        .map(|owner| owner.name.clone())
+}
+
+/// Move a patch built for an Application spec onto an ApplicationSet's template. Values
+/// filled in by a generator (`{{...}}`) are never replaced, since that would pin every
+/// generated Application to one value.
+fn template_patch(set: &ApplicationSet, patch: Value) -> Result<Value> {
+    let current = serde_json::to_value(set)?;
+    let ops = patch
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("Expected a JSON patch"))?
+        .iter()
+        .map(|op| {
+            let path = op["path"].as_str().unwrap_or_default();
+            let path = format!("/spec/template{}", path);
+            if current
+                .pointer(&path)
+                .and_then(Value::as_str)
+                .is_some_and(|value| value.contains("{{"))
+            {
+                return Err(anyhow::anyhow!(
+                    "ApplicationSet {} sets {} from its generators; update the generator instead",
+                    set.name_any(),
+                    path
+                ));
+            }
+            let mut op = op.clone();
+            op["path"] = json!(path);
+            Ok(op)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Value::Array(ops))
+}
+
+/// Build the JSON patch that sets `new_image` on an Application. Helm sources get the tag
+/// in `image_parameter`; Kustomize sources get an image override for the repository.
+fn image_patch(spec: &ApplicationSpec, image_parameter: &str, new_image: &str) -> Result<Value> {
+    let (repository, tag) = split_image(new_image);
+    let sources = spec.sources_with_paths();
+
+    if let Some((path, source)) = sources.iter().find(|(_, source)| source.is_helm()) {
+        let parameters = source
+            .helm
+            .as_ref()
+            .map(|helm| helm.parameters.as_slice())
+            .unwrap_or_default();
+        let parameter = json!({ "name": image_parameter, "value": tag });
+
+        let op = if source.helm.is_none() {
+            json!({ "op": "add", "path": format!("{}/helm", path), "value": { "parameters": [parameter] } })
+        } else if let Some(index) = parameters.iter().position(|p| p.name == image_parameter) {
+            json!({ "op": "replace", "path": format!("{}/helm/parameters/{}/value", path, index), "value": tag })
+        } else if parameters.is_empty() {
+            json!({ "op": "add", "path": format!("{}/helm/parameters", path), "value": [parameter] })
+        } else {
+            json!({ "op": "add", "path": format!("{}/helm/parameters/-", path), "value": parameter })
+        };
+        return Ok(json!([op]));
+    }
+
+    if let Some((path, source)) = sources
+        .iter()
+        .find(|(_, source)| source.kustomize.is_some())
+    {
+        let images_empty = source
+            .kustomize
+            .as_ref()
+            .is_none_or(|kustomize| kustomize.images.is_empty());
+
+        let op = if let Some(index) = source.kustomize_image_index(repository) {
+            json!({ "op": "replace", "path": format!("{}/kustomize/images/{}", path, index), "value": new_image })
+        } else if images_empty {
+            json!({ "op": "add", "path": format!("{}/kustomize/images", path), "value": [new_image] })
+        } else {
+            json!({ "op": "add", "path": format!("{}/kustomize/images/-", path), "value": new_image })
+        };
+        return Ok(json!([op]));
+    }
+
+    Err(anyhow::anyhow!(
+        "Application has no Helm or Kustomize source to override the image on"
+    ))
+}
+
+/// The image (or `chart:version` for chart updates) an Application currently pins for an
+/// UpdateRequest. When Headwind hasn't set an override yet the image comes from the
+/// manifests in Git, so the request's current image is assumed.
+pub fn current_application_image(
+    application: &Application,
+    spec: &UpdateRequestSpec,
+) -> Option<String> {
+    if spec.update_type == UpdateType::HelmChart {
+        let (_, source) = application.spec.chart_source()?;
+        return Some(format!(
+            "{}:{}",
+            source.chart.as_deref()?,
+            source.target_revision.as_deref()?
+        ));
+    }
+
+    let (repository, _) = split_image(&spec.current_image);
+    let parameter = spec
+        .image_parameter
+        .as_deref()
+        .unwrap_or(DEFAULT_IMAGE_PARAMETER);
+
+    let pinned = application
+        .spec
+        .sources_with_paths()
+        .into_iter()
+        .find_map(|(_, source)| {
+            if source.is_helm() {
+                source.helm_parameter(parameter)
+            } else {
+                source.kustomize_image_tag(repository)
+            }
+        });
+
+    Some(match pinned {
+        Some(tag) => format!("{}:{}", repository, tag),
+        None => spec.current_image.clone(),
+    })
+}
+
+/// Split `repo:tag` into its repository and tag, treating a registry port as part of the repository.
+/// The tag of a pinned image keeps its digest (`1.2.3@sha256:...`).
+fn split_image(image: &str) -> (&str, &str) {
+    crate::controller::workload::split_image(image).unwrap_or((image, "latest"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::argocd::{
+        ApplicationSource, ApplicationSourceHelm, ApplicationSourceKustomize, HelmParameter,
+    };
+    use crate::models::crd::UpdatePolicyType;
+
+    fn annotations(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn spec(source: ApplicationSource) -> ApplicationSpec {
+        ApplicationSpec {
+            source: Some(source),
+            sources: Vec::new(),
+            project: None,
+        }
+    }
+
+    fn git_source() -> ApplicationSource {
+        ApplicationSource {
+            repo_url: "https://github.com/example/deploy.git".to_string(),
+            path: Some("apps/web".to_string()),
+            target_revision: Some("main".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_managing_application() {
+        assert_eq!(managing_application(&annotations(&[])), None);
+
+        let app = managing_application(&annotations(&[(annotations::ARGOCD_APPLICATION, "web")]))
+            .unwrap();
+        assert_eq!(app.namespace, DEFAULT_APPLICATION_NAMESPACE);
+        assert_eq!(app.name, "web");
+        assert_eq!(app.image_parameter, DEFAULT_IMAGE_PARAMETER);
+
+        let app = managing_application(&annotations(&[
+            (annotations::ARGOCD_APPLICATION, "gitops/web"),
+            (annotations::ARGOCD_IMAGE_PARAMETER, "api.image.tag"),
+        ]))
+        .unwrap();
+        assert_eq!(app.namespace, "gitops");
+        assert_eq!(app.name, "web");
+        assert_eq!(app.image_parameter, "api.image.tag");
+    }
+
+    #[test]
+    fn test_resource_policy() {
+        assert!(resource_policy(&annotations(&[])).is_none());
+        assert!(resource_policy(&annotations(&[(annotations::POLICY, "none")])).is_none());
+
+        let policy = resource_policy(&annotations(&[
+            (annotations::POLICY, "minor"),
+            (annotations::REQUIRE_APPROVAL, "false"),
+        ]))
+        .unwrap();
+        assert_eq!(policy.policy, UpdatePolicy::Minor);
+        assert!(!policy.require_approval);
+    }
+
+    #[test]
+    fn test_image_patch_helm_parameter() {
+        // No helm block yet
+        let mut source = git_source();
+        source.chart = Some("web".to_string());
+        let patch = image_patch(&spec(source.clone()), "image.tag", "nginx:1.26.0").unwrap();
+        assert_eq!(patch[0]["op"], "add");
+        assert_eq!(patch[0]["path"], "/spec/source/helm");
+        assert_eq!(patch[0]["value"]["parameters"][0]["value"], "1.26.0");
+
+        // Existing parameter is replaced in place
+        source.helm = Some(ApplicationSourceHelm {
+            parameters: vec![
+                HelmParameter {
+                    name: "replicas".to_string(),
+                    value: "2".to_string(),
+                    force_string: None,
+                },
+                HelmParameter {
+                    name: "image.tag".to_string(),
+                    value: "1.25.0".to_string(),
+                    force_string: None,
+                },
+            ],
+        });
+        let patch = image_patch(&spec(source.clone()), "image.tag", "nginx:1.26.0").unwrap();
+        assert_eq!(patch[0]["op"], "replace");
+        assert_eq!(patch[0]["path"], "/spec/source/helm/parameters/1/value");
+
+        // New parameter is appended
+        let patch = image_patch(&spec(source), "worker.image.tag", "nginx:1.26.0").unwrap();
+        assert_eq!(patch[0]["op"], "add");
+        assert_eq!(patch[0]["path"], "/spec/source/helm/parameters/-");
+    }
+
+    #[test]
+    fn test_image_patch_kustomize() {
+        let mut source = git_source();
+        source.kustomize = Some(ApplicationSourceKustomize {
+            images: vec!["registry.example.com/web:1.0.0".to_string()],
+        });
+        let app = ApplicationSpec {
+            source: None,
+            sources: vec![git_source(), source],
+            project: None,
+        };
+
+        let patch = image_patch(&app, "image.tag", "registry.example.com/web:1.1.0").unwrap();
+        assert_eq!(patch[0]["op"], "replace");
+        assert_eq!(patch[0]["path"], "/spec/sources/1/kustomize/images/0");
+        assert_eq!(patch[0]["value"], "registry.example.com/web:1.1.0");
+
+        let patch = image_patch(&app, "image.tag", "redis:7.2").unwrap();
+        assert_eq!(patch[0]["op"], "add");
+        assert_eq!(patch[0]["path"], "/spec/sources/1/kustomize/images/-");
+    }
+
+    #[test]
+    fn test_image_patch_plain_directory_fails() {
+        assert!(image_patch(&spec(git_source()), "image.tag", "nginx:1.26.0").is_err());
+    }
+
+    #[test]
+    fn test_current_application_image() {
+        let mut source = git_source();
+        source.helm = Some(ApplicationSourceHelm {
+            parameters: vec![HelmParameter {
+                name: "image.tag".to_string(),
+                value: "1.25.0".to_string(),
+                force_string: None,
+            }],
+        });
+        let application = Application::new("web", spec(source));
+
+        let mut request = UpdateRequestSpec {
+            target_ref: target_ref("argocd", "web"),
+            update_type: UpdateType::Image,
+            container_name: None,
+            image_parameter: Some("image.tag".to_string()),
+            current_image: "nginx:1.25.0".to_string(),
+            new_image: "nginx:1.26.0".to_string(),
+            policy: UpdatePolicyType::Minor,
+            reason: None,
+            require_approval: true,
+            expires_at: None,
+            required_approvals: None,
+            git_write_back: None,
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
+        };
+        assert_eq!(
+            current_application_image(&application, &request).as_deref(),
+            Some("nginx:1.25.0")
+        );
+
+        // Parameter not set yet: the request's current image is assumed
+        request.image_parameter = Some("other.tag".to_string());
+        request.current_image = "nginx:1.24.0".to_string();
+        assert_eq!(
+            current_application_image(&application, &request).as_deref(),
+            Some("nginx:1.24.0")
+        );
+    }
+
+    #[test]
+    fn test_template_patch() {
+        use crate::models::argocd::{ApplicationSetSpec, ApplicationSetTemplate};
+
+        let mut source = git_source();
+        source.helm = Some(ApplicationSourceHelm {
+            parameters: vec![
+                HelmParameter {
+                    name: "image.tag".to_string(),
+                    value: "1.0.0".to_string(),
+                    force_string: None,
+                },
+                HelmParameter {
+                    name: "sidecar.tag".to_string(),
+                    value: "{{values.sidecarTag}}".to_string(),
+                    force_string: None,
+                },
+            ],
+        });
+        let set = ApplicationSet::new(
+            "web",
+            ApplicationSetSpec {
+                template: ApplicationSetTemplate { spec: spec(source) },
+            },
+        );
+        let template = &set.spec.template.spec;
+
+        let patch = template_patch(
+            &set,
+            image_patch(template, "image.tag", "nginx:1.1.0").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            patch[0]["path"],
+            "/spec/template/spec/source/helm/parameters/0/value"
+        );
+        assert_eq!(patch[0]["value"], "1.1.0");
+
+        // Generator parameters are left alone
+        let patch = image_patch(template, "sidecar.tag", "envoy:1.31.0").unwrap();
+        assert!(template_patch(&set, patch).is_err());
+    }
+
+    #[test]
+    fn test_owning_application_set() {
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+
+        let mut application = Application::new("web-prod", spec(git_source()));
+        assert_eq!(owning_application_set(&application), None);
+
+        application.metadata.owner_references = Some(vec![OwnerReference {
+            api_version: API_VERSION.to_string(),
+            kind: "ApplicationSet".to_string(),
+            name: "web".to_string(),
+            uid: "1234".to_string(),
+            ..Default::default()
+        }]);
+        assert_eq!(owning_application_set(&application).as_deref(), Some("web"));
+    }
+}
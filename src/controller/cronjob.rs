@@ -0,0 +1,245 @@
+use crate::config::namespaces;
+use crate::controller::stores;
+use crate::health;
+use crate::metrics::{RECONCILE_DURATION, RECONCILE_ERRORS};
+use crate::models::has_policy;
+use crate::policy::PolicyEngine;
+use crate::policy::defaults::apply_policy_defaults;
+use crate::webhook;
+use anyhow::Result;
+use futures::StreamExt;
+use k8s_openapi::api::batch::v1::CronJob;
+use k8s_openapi::api::core::v1::PodSpec;
+use kube::{
+    ResourceExt,
+    api::Api,
+    client::Client,
+    runtime::{
+        controller::{Action, Controller},
+        watcher::Config,
+    },
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument};
+
+pub struct CronJobController {
+    client: Client,
+    policy_engine: Arc<PolicyEngine>,
+}
+
+impl CronJobController {
+    pub async fn new() -> Result<Self> {
+        let client = Client::try_default().await?;
+        let policy_engine = Arc::new(PolicyEngine);
+
+        Ok(Self {
+            client,
+            policy_engine,
+        })
+    }
+
+    pub async fn run(self) {
+        info!("CronJob controller starting...");
+
+        // Run the controller in a loop with exponential backoff
+        // This handles transient errors during startup or runtime
+        let mut backoff_seconds = 1;
+        const MAX_BACKOFF: u64 = 60;
+
+        loop {
+            let cronjobs: Api<CronJob> = namespaces::scoped_api(self.client.clone());
+
+            info!("Creating controller for cronjobs");
+
+            let controller = Controller::new(cronjobs, Config::default());
+            stores::register(controller.store());
+
+            let result = controller
+                .run(
+                    reconcile,
+                    error_policy,
+                    Arc::new(ControllerContext {
+                        client: self.client.clone(),
+                        policy_engine: self.policy_engine.clone(),
+                    }),
+                )
+                .inspect(|res| health::track_controller("CronJob", res))
+                .for_each(|res| async move {
+                    match res {
+                        Ok((obj_ref, _action)) => {
+                            info!(
+                                "Reconciled cronjob: {}/{}",
+                                obj_ref.namespace.as_deref().unwrap_or("default"),
+                                obj_ref.name
+                            );
+                        },
+                        Err(e) => {
+                            // Log reconciliation errors but continue processing
+                            error!("Reconciliation error: {}", e);
+                            RECONCILE_ERRORS.with_label_values(&["CronJob"]).inc();
+                        },
+                    }
+                })
+                .await;
+
+            // If the controller stream ends, log it and restart after backoff
+            stores::unregister::<CronJob>();
+            error!(
+                "CronJob controller stream ended, restarting in {}s...",
+                backoff_seconds
+            );
+            tokio::time::sleep(Duration::from_secs(backoff_seconds)).await;
+
+            // Exponential backoff up to MAX_BACKOFF seconds
+            backoff_seconds = (backoff_seconds * 2).min(MAX_BACKOFF);
+
+            debug!("Controller loop result: {:?}", result);
+        }
+    }
+}
+
+struct ControllerContext {
+    #[allow(dead_code)]
+    client: Client,
+    #[allow(dead_code)]
+    policy_engine: Arc<PolicyEngine>,
+}
+
+#[instrument(skip(_ctx), fields(cronjob = %cronjob.name_any()))]
+async fn reconcile(
+    cronjob: Arc<CronJob>,
+    _ctx: Arc<ControllerContext>,
+) -> Result<Action, kube::Error> {
+    let _timer = RECONCILE_DURATION.start_timer();
+
+    let namespace = cronjob.namespace().unwrap_or_default();
+    let name = cronjob.name_any();
+
+    if !namespaces::is_watched(&namespace) {
+        return Ok(Action::await_change());
+    }
+
+    // Fill in the policy defaults of the namespace
+    let mut cronjob = Arc::unwrap_or_clone(cronjob);
+    apply_policy_defaults(&mut cronjob);
+
+    debug!("Reconciling cronjob {}/{} - starting", namespace, name);
+
+    // Parse headwind annotations to get update policy
+    let annotations = cronjob.metadata.annotations.as_ref();
+    if annotations.is_none() {
+        debug!(
+            "CronJob {}/{} has no annotations, skipping",
+            namespace, name
+        );
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    }
+
+    let annotations = annotations.unwrap();
+
+    // Check if this cronjob has headwind annotations
+    if !has_policy(annotations) {
+        debug!(
+            "CronJob {}/{} has no headwind policy annotation, skipping",
+            namespace, name
+        );
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    }
+
+    // Parse the policy from annotations
+    let policy = match webhook::parse_policy_from_annotations(annotations) {
+        Ok(p) => p,
+        Err(e) => {
+            error!(
+                "Failed to parse policy for cronjob {}/{}: {}",
+                namespace, name, e
+            );
+            return Err(create_error(&format!("Failed to parse policy: {}", e)));
+        },
+    };
+
+    debug!(
+        "CronJob {}/{} has policy: {:?}",
+        namespace, name, policy.policy
+    );
+
+    // Check if there are any available updates for this cronjob
+    // This would be triggered by webhook/polling events
+    // For now, we just requeue to check again later
+    debug!("CronJob {}/{} reconciliation complete", namespace, name);
+
+    Ok(Action::requeue(Duration::from_secs(300)))
+}
+
+fn error_policy(
+    _object: Arc<CronJob>,
+    _error: &kube::Error,
+    _ctx: Arc<ControllerContext>,
+) -> Action {
+    // Requeue after 60 seconds on errors
+    Action::requeue(Duration::from_secs(60))
+}
+
+/// Helper to create a kube::Error from a string message
+fn create_error(msg: &str) -> kube::Error {
+    kube::Error::Api(kube::error::ErrorResponse {
+        status: "Failure".to_string(),
+        message: msg.to_string(),
+        reason: "InvalidConfiguration".to_string(),
+        code: 400,
+    })
+}
+
+/// The pod spec of a CronJob's job template (`spec.jobTemplate.spec.template.spec`)
+pub fn pod_spec(cronjob: &CronJob) -> Option<&PodSpec> {
+    cronjob
+        .spec
+        .as_ref()?
+        .job_template
+        .spec
+        .as_ref()?
+        .template
+        .spec
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pod_spec_reads_job_template() {
+        use k8s_openapi::api::batch::v1::{CronJobSpec, JobSpec, JobTemplateSpec};
+        use k8s_openapi::api::core::v1::{Container, PodTemplateSpec};
+
+        let cronjob = CronJob {
+            spec: Some(CronJobSpec {
+                schedule: "0 2 * * *".to_string(),
+                job_template: JobTemplateSpec {
+                    spec: Some(JobSpec {
+                        template: PodTemplateSpec {
+                            spec: Some(PodSpec {
+                                containers: vec![Container {
+                                    name: "backup".to_string(),
+                                    image: Some("myregistry.com/db-backup:1.4.0".to_string()),
+                                    ..Default::default()
+                                }],
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let spec = pod_spec(&cronjob).unwrap();
+        assert_eq!(spec.containers[0].name, "backup");
+        assert!(pod_spec(&CronJob::default()).is_none());
+    }
+}
@@ -1,18 +1,22 @@
+use crate::config::namespaces;
+use crate::controller::stores;
+use crate::controller::workload::{annotate_build_metadata, split_image};
+use crate::health;
 use crate::metrics::{RECONCILE_DURATION, RECONCILE_ERRORS};
 use crate::models::{
-    ResourcePolicy, TargetRef, UpdatePolicy, UpdatePolicyType, UpdateRequest, UpdateRequestSpec,
-    UpdateType, annotations,
+    PrereleasePolicy, ResourcePolicy, UpdatePolicy, annotations, parse_approval_ttl,
+    parse_channel_tag, parse_ignore_patterns, parse_max_version, parse_pin_digest,
+    parse_require_approval, parse_required_approvals, parse_update_mode,
 };
-use crate::notifications::{self, DeploymentInfo};
 use crate::policy::PolicyEngine;
+use crate::policy::defaults::apply_policy_defaults;
 use crate::rollback::RollbackManager;
 use anyhow::Result;
-use chrono::Utc;
 use futures::StreamExt;
 use k8s_openapi::api::apps::v1::Deployment;
 use kube::{
     ResourceExt,
-    api::{Api, Patch, PatchParams, PostParams},
+    api::{Api, Patch, PatchParams},
     client::Client,
     runtime::{
         controller::{Action, Controller},
@@ -49,11 +53,14 @@ impl DeploymentController {
         const MAX_BACKOFF: u64 = 60;
 
         loop {
-            let deployments: Api<Deployment> = Api::all(self.client.clone());
+            let deployments: Api<Deployment> = namespaces::scoped_api(self.client.clone());
 
             info!("Creating controller for deployments");
 
-            let result = Controller::new(deployments, Config::default())
+            let controller = Controller::new(deployments, Config::default());
+            stores::register(controller.store());
+
+            let result = controller
                 .run(
                     reconcile,
                     error_policy,
@@ -62,6 +69,7 @@ impl DeploymentController {
                         policy_engine: self.policy_engine.clone(),
                     }),
                 )
+                .inspect(|res| health::track_controller("Deployment", res))
                 .for_each(|res| async move {
                     match res {
                         Ok((obj_ref, _action)) => {
@@ -74,13 +82,14 @@ impl DeploymentController {
                         Err(e) => {
                             // Log reconciliation errors but continue processing
                             error!("Reconciliation error: {}", e);
-                            RECONCILE_ERRORS.inc();
+                            RECONCILE_ERRORS.with_label_values(&["Deployment"]).inc();
                         },
                     }
                 })
                 .await;
 
             // If the controller stream ends, log it and restart after backoff
+            stores::unregister::<Deployment>();
             error!(
                 "Deployment controller stream ended, restarting in {}s...",
                 backoff_seconds
@@ -119,6 +128,14 @@ async fn reconcile(
         })
     })?;
 
+    if !namespaces::is_watched(&namespace) {
+        return Ok(Action::await_change());
+    }
+
+    // Fill in the policy defaults of the namespace
+    let mut deployment = Arc::unwrap_or_clone(deployment);
+    apply_policy_defaults(&mut deployment);
+
     debug!("Reconciling deployment {}/{}", namespace, name);
 
     // Check if deployment has headwind annotations
@@ -190,7 +207,7 @@ fn error_policy(
     _ctx: Arc<ControllerContext>,
 ) -> Action {
     error!("Reconciliation failed: {}", error);
-    RECONCILE_ERRORS.inc();
+    RECONCILE_ERRORS.with_label_values(&["Deployment"]).inc();
     Action::requeue(Duration::from_secs(60))
 }
 
@@ -209,258 +226,15 @@ fn create_error(msg: &str) -> kube::Error {
 ///   "nginx:1.25.0" -> ("nginx", "1.25.0")
 ///   "gcr.io/project/image:v1.0" -> ("gcr.io/project/image", "v1.0")
 ///   "nginx" -> ("nginx", "latest")
+///   "nginx:1.25.0@sha256:..." -> ("nginx", "1.25.0@sha256:...")
 fn parse_image(image: &str) -> Result<(String, String), kube::Error> {
-    match image.rsplit_once(':') {
-        Some((name, tag)) => {
-            // Check if the part after ':' looks like a port (e.g., "localhost:5000/image")
-            if tag.contains('/') {
-                // It's a registry with port, no tag specified
-                Ok((image.to_string(), "latest".to_string()))
-            } else {
-                Ok((name.to_string(), tag.to_string()))
-            }
-        },
-        None => Ok((image.to_string(), "latest".to_string())),
-    }
-}
-
-/// Handle an available image update
-/// This is called when we detect a new image version is available
-pub async fn handle_image_update(
-    client: Client,
-    policy_engine: Arc<PolicyEngine>,
-    deployment: &Deployment,
-    policy: &ResourcePolicy,
-    container_name: &str,
-    current_image: &str,
-    new_image: &str,
-) -> Result<(), kube::Error> {
-    // Create a temporary context for this operation
-    let ctx = Arc::new(ControllerContext {
-        client,
-        policy_engine,
-    });
-    let namespace = deployment.namespace().unwrap();
-    let name = deployment.name_any();
-
-    // Parse images to get tags
-    let (_, current_tag) = parse_image(current_image)?;
-    let (image_name, new_tag) = parse_image(new_image)?;
-
-    // Evaluate policy to see if we should update
-    let should_update = ctx
-        .policy_engine
-        .should_update(policy, &current_tag, &new_tag)
-        .map_err(|e| create_error(&format!("Failed to evaluate policy: {}", e)))?;
-
-    if !should_update {
-        debug!(
-            "Policy {:?} does not allow update from {} to {}",
-            policy.policy, current_tag, new_tag
-        );
-        return Ok(());
-    }
-
-    // Check minimum update interval
-    let min_interval_seconds = policy.min_update_interval.unwrap_or(300);
-    if let Some(annotations) = &deployment.metadata.annotations
-        && let Some(last_update_str) =
-            annotations.get(crate::models::policy::annotations::LAST_UPDATE)
-        && let Ok(last_update) = chrono::DateTime::parse_from_rfc3339(last_update_str)
-    {
-        let now = chrono::Utc::now();
-        let elapsed = now.signed_duration_since(last_update.with_timezone(&chrono::Utc));
-        let min_interval = chrono::Duration::seconds(min_interval_seconds as i64);
-
-        if elapsed < min_interval {
-            let remaining = min_interval - elapsed;
-            info!(
-                "Skipping update for {}/{} container {}: minimum interval not reached ({} < {}s), {} seconds remaining",
-                namespace,
-                name,
-                container_name,
-                elapsed.num_seconds(),
-                min_interval_seconds,
-                remaining.num_seconds()
-            );
-            crate::metrics::UPDATES_SKIPPED_INTERVAL.inc();
-            return Ok(());
-        }
-    }
-
-    info!(
-        "Update available for {}/{} container {}: {} -> {}",
-        namespace, name, container_name, current_tag, new_tag
-    );
-
-    // Send update detected notification
-    let deployment_info = DeploymentInfo {
-        name: name.clone(),
-        namespace: namespace.clone(),
-        current_image: current_image.to_string(),
-        new_image: new_image.to_string(),
-        container: Some(container_name.to_string()),
-        resource_kind: None,
-    };
-    notifications::notify_update_detected(deployment_info);
-
-    // Check if approval is required
-    if policy.require_approval {
-        // Create UpdateRequest CRD
-        create_update_request(
-            ctx.client.clone(),
-            &namespace,
-            &name,
-            container_name,
-            &image_name,
-            current_image,
-            new_image,
-            &policy.policy,
-        )
-        .await?;
-    } else {
-        // Auto-update without approval
-        info!(
-            "Auto-updating {}/{} container {} to {}",
-            namespace, name, container_name, new_image
-        );
-        update_deployment_image(
-            ctx.client.clone(),
-            &namespace,
-            &name,
-            container_name,
-            new_image,
-        )
-        .await
-        .map_err(|e| create_error(&format!("Failed to update deployment: {}", e)))?;
-    }
-
-    Ok(())
-}
-
-/// Create an UpdateRequest custom resource
-#[allow(clippy::too_many_arguments)]
-async fn create_update_request(
-    client: Client,
-    namespace: &str,
-    deployment_name: &str,
-    container_name: &str,
-    image_name: &str,
-    current_image: &str,
-    new_image: &str,
-    policy: &UpdatePolicy,
-) -> Result<(), kube::Error> {
-    let update_requests: Api<UpdateRequest> = Api::namespaced(client, namespace);
-
-    // Generate a deterministic name for the update request (without timestamp for deduplication)
-    let (_, current_tag) = parse_image(current_image)?;
-    let (_, new_tag) = parse_image(new_image)?;
-    let request_name = format!(
-        "{}-{}-{}",
-        deployment_name,
-        container_name,
-        new_tag.replace(['.', ':'], "-")
-    );
-
-    let update_request = UpdateRequest::new(
-        &request_name,
-        UpdateRequestSpec {
-            target_ref: TargetRef {
-                api_version: "apps/v1".to_string(),
-                kind: "Deployment".to_string(),
-                name: deployment_name.to_string(),
-                namespace: namespace.to_string(),
-            },
-            update_type: UpdateType::Image,
-            container_name: Some(container_name.to_string()),
-            current_image: current_image.to_string(),
-            new_image: new_image.to_string(),
-            policy: map_policy_to_crd(policy),
-            reason: Some(format!(
-                "New version available for {}: {} -> {}",
-                image_name, current_tag, new_tag
-            )),
-            require_approval: true,
-            expires_at: Some(Utc::now() + chrono::Duration::hours(24)),
-        },
-    );
-
-    // Check if UpdateRequest already exists
-    match update_requests.get(&request_name).await {
-        Ok(existing) => {
-            debug!(
-                "UpdateRequest {}/{} already exists, skipping creation",
-                namespace, request_name
-            );
-            // Check if it's in a terminal state (Completed, Rejected, Failed)
-            if let Some(status) = &existing.status {
-                use crate::models::crd::UpdatePhase;
-                if status.phase == UpdatePhase::Completed
-                    || status.phase == UpdatePhase::Rejected
-                    || status.phase == UpdatePhase::Failed
-                {
-                    info!(
-                        "Existing UpdateRequest is in terminal state ({:?}), creating new one",
-                        status.phase
-                    );
-                    // Delete the old one and create a new one
-                    update_requests
-                        .delete(&request_name, &Default::default())
-                        .await?;
-                    update_requests
-                        .create(&PostParams::default(), &update_request)
-                        .await?;
-
-                    info!(
-                        "Created UpdateRequest {} for deployment {}/{}",
-                        request_name, namespace, deployment_name
-                    );
-                }
-            }
-        },
-        Err(kube::Error::Api(err)) if err.code == 404 => {
-            // Doesn't exist, create it
-            update_requests
-                .create(&PostParams::default(), &update_request)
-                .await?;
-            info!(
-                "Created UpdateRequest {} for deployment {}/{}",
-                request_name, namespace, deployment_name
-            );
-        },
-        Err(e) => {
-            error!("Failed to check for existing UpdateRequest: {}", e);
-            return Err(e);
-        },
-    }
-
-    // Send notification about update request creation (only if it was newly created)
-    let deployment_info = DeploymentInfo {
-        name: deployment_name.to_string(),
-        namespace: namespace.to_string(),
-        current_image: current_image.to_string(),
-        new_image: new_image.to_string(),
-        container: Some(container_name.to_string()),
-        resource_kind: None,
-    };
-    notifications::notify_update_request_created(
-        deployment_info,
-        format!("{:?}", policy),
-        true, // require_approval is true in this flow
-        request_name.clone(),
-    );
-
-    Ok(())
-}
-
-/// Map UpdatePolicy to UpdatePolicyType for CRD
-fn map_policy_to_crd(policy: &UpdatePolicy) -> UpdatePolicyType {
-    match policy {
-        UpdatePolicy::Major => UpdatePolicyType::Major,
-        UpdatePolicy::Minor => UpdatePolicyType::Minor,
-        UpdatePolicy::Patch => UpdatePolicyType::Patch,
-        UpdatePolicy::Glob => UpdatePolicyType::Glob,
-        _ => UpdatePolicyType::None,
+    match split_image(image) {
+        Some((name, tag)) => Ok((name.to_string(), tag.to_string())),
+        // No tag, or a registry port (e.g., "localhost:5000/image")
+        None => Ok((
+            image.split('@').next().unwrap_or(image).to_string(),
+            "latest".to_string(),
+        )),
     }
 }
 
@@ -500,10 +274,20 @@ fn parse_policy_from_annotations(
         policy.pattern = Some(pattern.clone());
     }
 
-    if let Some(require_approval) = annotations.get(annotations::REQUIRE_APPROVAL) {
-        policy.require_approval = require_approval.parse().unwrap_or(true);
+    if let Some(ignore_patterns) = annotations.get(annotations::IGNORE_PATTERN) {
+        policy.ignore_patterns = parse_ignore_patterns(ignore_patterns);
     }
 
+    policy.prerelease = PrereleasePolicy::from_annotations(annotations);
+    policy.pin_digest = parse_pin_digest(annotations);
+    policy.channel_tag = parse_channel_tag(annotations);
+    policy.update_mode = parse_update_mode(annotations);
+    policy.approval_ttl = parse_approval_ttl(annotations);
+    policy.required_approvals = parse_required_approvals(annotations);
+    policy.max_version = parse_max_version(annotations);
+
+    policy.require_approval = parse_require_approval(annotations);
+
     if let Some(interval) = annotations.get(annotations::MIN_UPDATE_INTERVAL) {
         policy.min_update_interval = interval.parse().ok();
     }
@@ -563,26 +347,50 @@ pub async fn update_deployment_image_with_tracking(
     new_image: &str,
     update_request_name: Option<String>,
     approved_by: Option<String>,
+) -> Result<()> {
+    update_deployment_images_with_tracking(
+        client,
+        namespace,
+        name,
+        &[(container_name.to_string(), new_image.to_string())],
+        update_request_name,
+        approved_by,
+    )
+    .await
+}
+
+/// Update the images of several containers of a deployment in a single patch, so they roll
+/// out together, with optional rollback tracking metadata
+pub async fn update_deployment_images_with_tracking(
+    client: Client,
+    namespace: &str,
+    name: &str,
+    images: &[(String, String)],
+    update_request_name: Option<String>,
+    approved_by: Option<String>,
 ) -> Result<()> {
     let deployments: Api<Deployment> = Api::namespaced(client.clone(), namespace);
 
+    let containers: Vec<_> = images
+        .iter()
+        .map(|(container_name, new_image)| json!({ "name": container_name, "image": new_image }))
+        .collect();
     let patch = json!({
         "spec": {
             "template": {
                 "spec": {
-                    "containers": [{
-                        "name": container_name,
-                        "image": new_image
-                    }]
+                    "containers": containers
                 }
             }
         }
     });
 
-    info!(
-        "Updating deployment {}/{} container {} to image {}",
-        namespace, name, container_name, new_image
-    );
+    for (container_name, new_image) in images {
+        info!(
+            "Updating deployment {}/{} container {} to image {}",
+            namespace, name, container_name, new_image
+        );
+    }
 
     deployments
         .patch(name, &PatchParams::default(), &Patch::Strategic(patch))
@@ -590,24 +398,30 @@ pub async fn update_deployment_image_with_tracking(
 
     info!("Successfully updated deployment {}/{}", namespace, name);
 
+    if let Some((_, new_image)) = images.first() {
+        annotate_build_metadata(&client, "Deployment", namespace, name, new_image).await;
+    }
+
     // Track the update in rollback history
     let rollback_manager = RollbackManager::new(client);
-    if let Err(e) = rollback_manager
-        .track_update(
-            name,
-            namespace,
-            container_name,
-            new_image,
-            update_request_name,
-            approved_by,
-        )
-        .await
-    {
-        // Log the error but don't fail the update
-        warn!(
-            "Failed to track update in rollback history for {}/{}: {}",
-            namespace, name, e
-        );
+    for (container_name, new_image) in images {
+        if let Err(e) = rollback_manager
+            .track_update(
+                name,
+                namespace,
+                container_name,
+                new_image,
+                update_request_name.clone(),
+                approved_by.clone(),
+            )
+            .await
+        {
+            // Log the error but don't fail the update
+            warn!(
+                "Failed to track update in rollback history for {}/{}: {}",
+                namespace, name, e
+            );
+        }
     }
 
     Ok(())
@@ -635,6 +449,24 @@ mod tests {
         assert_eq!(policy.images, vec!["nginx", "redis"]);
     }
 
+    #[test]
+    fn test_dry_run_requires_approval() {
+        let mut annotations = BTreeMap::new();
+        annotations.insert(annotations::POLICY.to_string(), "minor".to_string());
+        annotations.insert(
+            annotations::REQUIRE_APPROVAL.to_string(),
+            "false".to_string(),
+        );
+        annotations.insert(annotations::DRY_RUN.to_string(), "true".to_string());
+
+        let policy = parse_policy_from_annotations(&annotations).unwrap();
+        assert!(policy.require_approval);
+
+        annotations.insert(annotations::DRY_RUN.to_string(), "false".to_string());
+        let policy = parse_policy_from_annotations(&annotations).unwrap();
+        assert!(!policy.require_approval);
+    }
+
     #[test]
     fn test_parse_policy_defaults() {
         let annotations = BTreeMap::new();
@@ -694,38 +526,6 @@ mod tests {
         assert!(!glob_match("*-beta", "v1.0"));
     }
 
-    #[test]
-    fn test_map_policy_to_crd() {
-        assert_eq!(
-            map_policy_to_crd(&UpdatePolicy::Major),
-            UpdatePolicyType::Major
-        );
-        assert_eq!(
-            map_policy_to_crd(&UpdatePolicy::Minor),
-            UpdatePolicyType::Minor
-        );
-        assert_eq!(
-            map_policy_to_crd(&UpdatePolicy::Patch),
-            UpdatePolicyType::Patch
-        );
-        assert_eq!(
-            map_policy_to_crd(&UpdatePolicy::Glob),
-            UpdatePolicyType::Glob
-        );
-        assert_eq!(
-            map_policy_to_crd(&UpdatePolicy::None),
-            UpdatePolicyType::None
-        );
-        assert_eq!(
-            map_policy_to_crd(&UpdatePolicy::All),
-            UpdatePolicyType::None
-        );
-        assert_eq!(
-            map_policy_to_crd(&UpdatePolicy::Force),
-            UpdatePolicyType::None
-        );
-    }
-
     #[test]
     fn test_min_update_interval_parsing() {
         use chrono::{DateTime, Utc};
@@ -1,26 +1,41 @@
+use crate::audit;
+use crate::config::namespaces;
+use crate::controller::stores;
+use crate::controller::workload::{
+    self, Submission, WorkloadImageUpdate, map_policy_to_crd, split_image, submit_update_request,
+};
+use crate::health;
+use crate::helm::source;
+use crate::helm::values::ValuesImage;
 use crate::helm::{HelmRepositoryClient, OciHelmClient};
 use crate::metrics::{
-    HELM_CHART_VERSIONS_CHECKED, HELM_RELEASES_WATCHED, HELM_REPOSITORY_ERRORS,
-    HELM_REPOSITORY_QUERIES, HELM_REPOSITORY_QUERY_DURATION, HELM_UPDATES_APPROVED,
-    HELM_UPDATES_FOUND, HELM_UPDATES_REJECTED, RECONCILE_DURATION, RECONCILE_ERRORS,
+    HELM_CHART_VERSIONS_CHECKED, HELM_REPOSITORY_ERRORS, HELM_REPOSITORY_QUERIES,
+    HELM_REPOSITORY_QUERY_DURATION, HELM_UPDATES_APPROVED, HELM_UPDATES_FOUND,
+    HELM_UPDATES_REJECTED, RECONCILE_DURATION, RECONCILE_ERRORS,
 };
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
 use crate::models::crd::{
     TargetRef, UpdatePhase, UpdatePolicyType, UpdateRequest, UpdateRequestSpec,
     UpdateRequestStatus, UpdateType,
 };
 use crate::models::policy::annotations;
-use crate::models::{HelmRelease, HelmRepository, ResourcePolicy, UpdatePolicy};
+use crate::models::{
+    DEFAULT_APPROVAL_TTL, HelmRelease, HelmRepository, PrereleasePolicy, ResourcePolicy,
+    UpdateMode, UpdatePolicy, parse_approval_ttl, parse_ignore_patterns, parse_max_version,
+    parse_require_approval, parse_required_approvals,
+};
+use crate::notifications::DeploymentInfo;
 use crate::policy::PolicyEngine;
+use crate::schedule;
 use anyhow::Result;
 use futures::StreamExt;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 use kube::{
     Api, Client, ResourceExt,
-    api::ListParams,
     runtime::{Controller, controller::Action, watcher::Config},
 };
 use std::{collections::BTreeMap, sync::Arc, time::Duration};
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, warn};
 
 pub struct HelmController {
     client: Client,
@@ -51,7 +66,7 @@ impl HelmController {
     }
 
     pub async fn run(self) {
-        let api: Api<HelmRelease> = Api::all(self.client.clone());
+        let api: Api<HelmRelease> = namespaces::scoped_api(self.client.clone());
 
         // Create Helm repository client for version discovery
         let helm_repo_client = HelmRepositoryClient::with_kube_client()
@@ -71,12 +86,17 @@ impl HelmController {
         });
 
         // Set up controller with exponential backoff
-        Controller::new(api, Config::default())
+        let controller = Controller::new(api, Config::default());
+        stores::register(controller.store());
+
+        controller
             .shutdown_on_signal()
             .run(reconcile, error_policy, context)
+            .inspect(|res| health::track_controller("HelmRelease", res))
             .filter_map(|x| async move { std::result::Result::ok(x) })
             .for_each(|_| futures::future::ready(()))
             .await;
+        stores::unregister::<HelmRelease>();
     }
 }
 
@@ -104,6 +124,10 @@ async fn reconcile(
     })?;
     let name = helm_release.name_any();
 
+    if !namespaces::is_watched(&namespace) {
+        return Ok(Action::await_change());
+    }
+
     debug!(
         "Reconciling HelmRelease {}/{} (generation {})",
         namespace,
@@ -137,9 +161,6 @@ async fn reconcile(
         namespace, name, chart_name, current_version, policy
     );
 
-    // Update metrics
-    update_helm_releases_count(&ctx.client).await;
-
     // Get current deployed version from status (last_attempted_revision for Flux v2)
     let deployed_version = helm_release
         .status
@@ -172,25 +193,36 @@ async fn reconcile(
         HELM_CHART_VERSIONS_CHECKED.inc();
 
         // Potential update available - increment found metric
-        HELM_UPDATES_FOUND.inc();
+        HELM_UPDATES_FOUND.with_label_values(&[&namespace]).inc();
 
         // Build resource policy from annotations
         let resource_policy =
             build_resource_policy(helm_release.metadata.annotations.as_ref(), policy);
 
         // Check if update should proceed based on policy
-        match ctx
-            .policy_engine
-            .should_update(&resource_policy, base_version, &new_version)
-        {
+        match info_span!("policy_evaluation", current = %base_version, new = %new_version).in_scope(
+            || {
+                ctx.policy_engine
+                    .should_update(&resource_policy, base_version, &new_version)
+            },
+        ) {
             Ok(true) => {
                 // Increment approved metric
-                HELM_UPDATES_APPROVED.inc();
+                HELM_UPDATES_APPROVED.with_label_values(&[&namespace]).inc();
 
                 info!(
                     "HelmRelease {}/{} - Update from {} to {} approved by policy",
                     namespace, name, base_version, new_version
                 );
+                let resource = DeploymentInfo {
+                    name: name.clone(),
+                    namespace: namespace.clone(),
+                    current_image: format!("{}:{}", chart_name, base_version),
+                    new_image: format!("{}:{}", chart_name, new_version),
+                    container: None,
+                    resource_kind: Some("HelmRelease".to_string()),
+                };
+                audit::record(UpdateAuditSpec::new(AuditAction::Detected, &resource));
 
                 // Check if approval is required
                 if resource_policy.require_approval {
@@ -206,7 +238,11 @@ async fn reconcile(
                     )
                     .await
                     {
-                        Ok(update_request_name) => {
+                        Ok(Submission::Observed(_)) => {},
+                        Ok(Submission::Created {
+                            name: update_request_name,
+                            release_notes,
+                        }) => {
                             info!(
                                 "Created update request {} for HelmRelease {}/{}",
                                 update_request_name, namespace, name
@@ -214,17 +250,11 @@ async fn reconcile(
 
                             // Send notification for UpdateRequest creation
                             crate::notifications::notify_update_request_created(
-                                crate::notifications::DeploymentInfo {
-                                    name: name.clone(),
-                                    namespace: namespace.clone(),
-                                    current_image: format!("{}:{}", chart_name, base_version),
-                                    new_image: format!("{}:{}", chart_name, new_version),
-                                    container: None,
-                                    resource_kind: Some("HelmRelease".to_string()),
-                                },
+                                resource,
                                 format!("{:?}", resource_policy.policy),
                                 resource_policy.require_approval,
                                 update_request_name,
+                                release_notes,
                             );
                         },
                         Err(e) => {
@@ -263,15 +293,39 @@ async fn reconcile(
                                 min_update_interval,
                                 remaining.num_seconds()
                             );
-                            crate::metrics::UPDATES_SKIPPED_INTERVAL.inc();
-                            return Ok(Action::requeue(Duration::from_secs(
-                                min_update_interval as u64,
-                            )));
+                            crate::metrics::UPDATES_SKIPPED_INTERVAL
+                                .with_label_values(&[namespace.as_str(), "HelmRelease"])
+                                .inc();
+                            return Ok(Action::requeue(Duration::from_secs(min_update_interval)));
                         }
                     }
 
+                    // Hold the update until the maintenance window opens
+                    let current_chart = format!("{}:{}", chart_name, base_version);
+                    let new_chart = format!("{}:{}", chart_name, new_version);
+                    let update = WorkloadImageUpdate {
+                        kind: "HelmRelease",
+                        namespace: &namespace,
+                        name: &name,
+                        container_name: None,
+                        current_image: &current_chart,
+                        new_image: &new_chart,
+                        values_path: None,
+                        policy: &resource_policy,
+                    };
+                    if helm_release
+                        .metadata
+                        .annotations
+                        .as_ref()
+                        .is_some_and(|annotations| {
+                            schedule::defer_outside_window(annotations, &update)
+                        })
+                    {
+                        return Ok(Action::requeue(Duration::from_secs(300)));
+                    }
+
                     // Perform the direct update
-                    match crate::approval::update_helmrelease_chart_version(
+                    let result = crate::approval::update_helmrelease_chart_version(
                         &ctx.client,
                         &namespace,
                         &name,
@@ -279,8 +333,11 @@ async fn reconcile(
                         base_version,
                         &new_version,
                     )
-                    .await
-                    {
+                    .await;
+                    audit::record(
+                        UpdateAuditSpec::new(AuditAction::Applied, &resource).with_result(&result),
+                    );
+                    match result {
                         Ok(_) => {
                             // Update the last-update annotation on the HelmRelease
                             use crate::models::HelmRelease;
@@ -324,7 +381,7 @@ async fn reconcile(
             },
             Ok(false) => {
                 // Increment rejected metric
-                HELM_UPDATES_REJECTED.inc();
+                HELM_UPDATES_REJECTED.with_label_values(&[&namespace]).inc();
 
                 debug!(
                     "HelmRelease {}/{} - Update from {} to {} rejected by policy",
@@ -360,13 +417,16 @@ async fn discover_new_version(
     // Get the HelmRepository reference from the HelmRelease
     let source_ref = &helm_release.spec.chart.spec.source_ref;
 
-    // Only handle HelmRepository sources (not GitRepository, Bucket, etc.)
+    // Charts built from a GitRepository or Bucket are versioned by the source's artifact
     if source_ref.kind != "HelmRepository" {
-        debug!(
-            "HelmRelease references {}, not HelmRepository - skipping auto-discovery",
-            source_ref.kind
-        );
-        return None;
+        if !source::chart_versions_tracked(helm_release) {
+            debug!(
+                "HelmRelease references {} {}, set HEADWIND_HELM_SOURCE_ARTIFACTS=true to track its chart versions",
+                source_ref.kind, source_ref.name
+            );
+            return None;
+        }
+        return discover_artifact_version(ctx, helm_release, current_version, policy).await;
     }
 
     let namespace = helm_release.namespace().unwrap_or_default();
@@ -495,60 +555,37 @@ async fn discover_http_version(
 ) -> Option<String> {
     let repo_url = &helm_repo.spec.url;
 
-    // Check if authentication is required
-    let index = if let Some(secret_ref) = &helm_repo.spec.secret_ref {
-        // Fetch credentials from Secret
-        match ctx
-            .helm_repo_client
-            .read_secret_credentials(repo_namespace, &secret_ref.name)
-            .await
-        {
-            Ok(creds) => {
-                debug!(
-                    "Using authentication for repository {} (secret: {})",
-                    repo_url, secret_ref.name
-                );
-
-                // Increment repository query counter
-                HELM_REPOSITORY_QUERIES.inc();
-
-                match ctx
-                    .helm_repo_client
-                    .fetch_index_with_auth(repo_url, &creds.username, &creds.password)
-                    .await
-                {
-                    Ok(idx) => idx,
-                    Err(e) => {
-                        warn!("Failed to fetch index from {} (with auth): {}", repo_url, e);
-                        HELM_REPOSITORY_ERRORS.inc();
-                        return None;
-                    },
-                }
-            },
-            Err(e) => {
-                warn!(
-                    "Failed to read credentials from secret {}/{}: {}",
-                    repo_namespace, secret_ref.name, e
-                );
-                HELM_REPOSITORY_ERRORS.inc();
-                return None;
-            },
-        }
-    } else {
-        // No authentication required
-        debug!("Fetching public repository index from {}", repo_url);
+    // Credentials and TLS material from secretRef / certSecretRef
+    let auth = match ctx
+        .helm_repo_client
+        .read_repository_auth(repo_namespace, &helm_repo.spec)
+        .await
+    {
+        Ok(auth) => auth,
+        Err(e) => {
+            warn!(
+                "Failed to read credentials for repository {}: {:#}",
+                repo_url, e
+            );
+            HELM_REPOSITORY_ERRORS.inc();
+            return None;
+        },
+    };
 
-        // Increment repository query counter
-        HELM_REPOSITORY_QUERIES.inc();
+    // Increment repository query counter
+    HELM_REPOSITORY_QUERIES.inc();
 
-        match ctx.helm_repo_client.fetch_index(repo_url).await {
-            Ok(idx) => idx,
-            Err(e) => {
-                warn!("Failed to fetch index from {}: {}", repo_url, e);
-                HELM_REPOSITORY_ERRORS.inc();
-                return None;
-            },
-        }
+    let index = match ctx
+        .helm_repo_client
+        .fetch_index_authenticated(repo_url, &auth)
+        .await
+    {
+        Ok(idx) => idx,
+        Err(e) => {
+            warn!("Failed to fetch index from {}: {}", repo_url, e);
+            HELM_REPOSITORY_ERRORS.inc();
+            return None;
+        },
     };
 
     // Find the best version matching the policy
@@ -556,17 +593,54 @@ async fn discover_http_version(
         .find_best_version(&index, chart_name, current_version, policy)
 }
 
+/// Discover the chart version in the artifact of a GitRepository or Bucket source
+async fn discover_artifact_version(
+    ctx: &Arc<ControllerContext>,
+    helm_release: &HelmRelease,
+    current_version: &str,
+    policy: &UpdatePolicy,
+) -> Option<String> {
+    let source_ref = &helm_release.spec.chart.spec.source_ref;
+    HELM_REPOSITORY_QUERIES.inc();
+    let version = match source::artifact_chart_version(
+        &ctx.client,
+        &ctx.helm_repo_client,
+        helm_release,
+    )
+    .await
+    {
+        Ok(version) => version,
+        Err(e) => {
+            warn!(
+                "Failed to read the chart version from {} {}: {:#}",
+                source_ref.kind, source_ref.name, e
+            );
+            HELM_REPOSITORY_ERRORS.inc();
+            return None;
+        },
+    };
+    debug!(
+        "{} {} holds chart {} version {}",
+        source_ref.kind, source_ref.name, helm_release.spec.chart.spec.chart, version
+    );
+
+    ctx.oci_helm_client
+        .find_best_version(&[version], current_version, policy)
+}
+
 fn error_policy(
     _helm_release: Arc<HelmRelease>,
     error: &kube::Error,
     _ctx: Arc<ControllerContext>,
 ) -> Action {
-    RECONCILE_ERRORS.inc();
+    RECONCILE_ERRORS.with_label_values(&["HelmRelease"]).inc();
     error!("Reconciliation error: {}", error);
     Action::requeue(Duration::from_secs(60))
 }
 
-fn parse_policy_from_annotations(annotations: Option<&BTreeMap<String, String>>) -> UpdatePolicy {
+pub(super) fn parse_policy_from_annotations(
+    annotations: Option<&BTreeMap<String, String>>,
+) -> UpdatePolicy {
     annotations
         .and_then(|ann| ann.get(annotations::POLICY))
         .map(|policy_str| match policy_str.to_lowercase().as_str() {
@@ -575,6 +649,8 @@ fn parse_policy_from_annotations(annotations: Option<&BTreeMap<String, String>>)
             "major" => UpdatePolicy::Major,
             "all" => UpdatePolicy::All,
             "glob" => UpdatePolicy::Glob,
+            "regex" => UpdatePolicy::Regex,
+            "range" => UpdatePolicy::Range,
             "force" => UpdatePolicy::Force,
             "none" => UpdatePolicy::None,
             _ => {
@@ -585,19 +661,7 @@ fn parse_policy_from_annotations(annotations: Option<&BTreeMap<String, String>>)
         .unwrap_or(UpdatePolicy::None)
 }
 
-async fn update_helm_releases_count(client: &Client) {
-    let api: Api<HelmRelease> = Api::all(client.clone());
-    match api.list(&ListParams::default()).await {
-        Ok(list) => {
-            HELM_RELEASES_WATCHED.set(list.items.len() as i64);
-        },
-        Err(e) => {
-            error!("Failed to count HelmReleases: {}", e);
-        },
-    }
-}
-
-fn build_resource_policy(
+pub(super) fn build_resource_policy(
     annotations: Option<&BTreeMap<String, String>>,
     policy: UpdatePolicy,
 ) -> ResourcePolicy {
@@ -605,10 +669,10 @@ fn build_resource_policy(
         .and_then(|ann| ann.get(annotations::PATTERN))
         .map(|s| s.to_string());
 
-    let require_approval = annotations
-        .and_then(|ann| ann.get(annotations::REQUIRE_APPROVAL))
-        .and_then(|v| v.parse::<bool>().ok())
-        .unwrap_or(true);
+    let require_approval = annotations.map_or_else(
+        || parse_require_approval(&BTreeMap::new()),
+        parse_require_approval,
+    );
 
     let min_update_interval = annotations
         .and_then(|ann| ann.get(annotations::MIN_UPDATE_INTERVAL))
@@ -623,6 +687,11 @@ fn build_resource_policy(
         .and_then(|ann| ann.get(annotations::POLLING_INTERVAL))
         .and_then(|v| v.parse::<u64>().ok());
 
+    let ignore_patterns = annotations
+        .and_then(|ann| ann.get(annotations::IGNORE_PATTERN))
+        .map(|v| parse_ignore_patterns(v))
+        .unwrap_or_default();
+
     ResourcePolicy {
         policy,
         pattern,
@@ -631,6 +700,18 @@ fn build_resource_policy(
         images: Vec::new(),
         event_source,
         polling_interval,
+        ignore_patterns,
+        prerelease: annotations
+            .map(PrereleasePolicy::from_annotations)
+            .unwrap_or_default(),
+        pin_digest: false,
+        channel_tag: None,
+        update_mode: UpdateMode::Image,
+        approval_ttl: annotations
+            .map(parse_approval_ttl)
+            .unwrap_or(DEFAULT_APPROVAL_TTL),
+        required_approvals: annotations.and_then(parse_required_approvals),
+        max_version: annotations.and_then(parse_max_version),
     }
 }
 
@@ -642,8 +723,8 @@ async fn create_update_request(
     current_version: &str,
     new_version: &str,
     policy: &ResourcePolicy,
-) -> Result<String, kube::Error> {
-    use kube::{Api, api::PostParams};
+) -> Result<Submission, kube::Error> {
+    use kube::Api;
 
     let update_requests: Api<UpdateRequest> = Api::namespaced(client, namespace);
 
@@ -652,6 +733,8 @@ async fn create_update_request(
         UpdatePolicy::Minor => UpdatePolicyType::Minor,
         UpdatePolicy::Major => UpdatePolicyType::Major,
         UpdatePolicy::Glob => UpdatePolicyType::Glob,
+        UpdatePolicy::Regex => UpdatePolicyType::Regex,
+        UpdatePolicy::Range => UpdatePolicyType::Range,
         _ => UpdatePolicyType::None,
     };
 
@@ -667,12 +750,18 @@ async fn create_update_request(
         },
         update_type: UpdateType::HelmChart,
         container_name: None,
+        image_parameter: None,
         current_image: format!("{}:{}", chart_name, current_version),
         new_image: format!("{}:{}", chart_name, new_version),
         policy: policy_type,
         reason: Some(format!("New chart version {} available", new_version)),
         require_approval: policy.require_approval,
-        expires_at: Some(chrono::Utc::now() + chrono::Duration::hours(24)),
+        expires_at: policy.approval_expiry(),
+        required_approvals: policy.required_approvals,
+        git_write_back: None,
+        grouped_containers: Vec::new(),
+        source: None,
+        resolved_version: None,
     };
 
     let status = UpdateRequestStatus {
@@ -694,54 +783,7 @@ async fn create_update_request(
         status: Some(status),
     };
 
-    // Check if UpdateRequest already exists
-    match update_requests.get(&request_name).await {
-        Ok(existing) => {
-            debug!(
-                "UpdateRequest {}/{} already exists, skipping creation",
-                namespace, request_name
-            );
-            // Check if it's in a terminal state (Completed, Rejected, Failed)
-            if let Some(status) = &existing.status
-                && (status.phase == UpdatePhase::Completed
-                    || status.phase == UpdatePhase::Rejected
-                    || status.phase == UpdatePhase::Failed)
-            {
-                info!(
-                    "Existing UpdateRequest is in terminal state ({:?}), creating new one",
-                    status.phase
-                );
-                // Delete the old one and create a new one
-                update_requests
-                    .delete(&request_name, &Default::default())
-                    .await?;
-                update_requests
-                    .create(&PostParams::default(), &update_request)
-                    .await?;
-
-                info!(
-                    "Created UpdateRequest {} for HelmRelease {}/{}",
-                    request_name, namespace, name
-                );
-            }
-        },
-        Err(kube::Error::Api(err)) if err.code == 404 => {
-            // Doesn't exist, create it
-            update_requests
-                .create(&PostParams::default(), &update_request)
-                .await?;
-            info!(
-                "Created UpdateRequest {} for HelmRelease {}/{}",
-                request_name, namespace, name
-            );
-        },
-        Err(e) => {
-            error!("Failed to check for existing UpdateRequest: {}", e);
-            return Err(e);
-        },
-    }
-
-    Ok(request_name)
+    submit_update_request(&update_requests, &update_request).await
 }
 
 /// Handle a Helm chart update event from webhooks
@@ -778,6 +820,20 @@ pub async fn handle_chart_update(
     // Get chart name
     let chart_name = &helm_release.spec.chart.spec.chart;
 
+    let ignore_patterns = helm_release
+        .metadata
+        .annotations
+        .as_ref()
+        .and_then(|a| a.get(annotations::IGNORE_PATTERN))
+        .map(|v| parse_ignore_patterns(v))
+        .unwrap_or_default();
+    let prerelease = helm_release
+        .metadata
+        .annotations
+        .as_ref()
+        .map(PrereleasePolicy::from_annotations)
+        .unwrap_or_default();
+
     // Build temporary ResourcePolicy for policy check
     let temp_policy = ResourcePolicy {
         policy: update_policy,
@@ -787,6 +843,18 @@ pub async fn handle_chart_update(
         images: Vec::new(),
         event_source: Default::default(),
         polling_interval: None,
+        ignore_patterns: ignore_patterns.clone(),
+        prerelease: prerelease.clone(),
+        pin_digest: false,
+        channel_tag: None,
+        update_mode: UpdateMode::Image,
+        approval_ttl: DEFAULT_APPROVAL_TTL,
+        required_approvals: None,
+        max_version: helm_release
+            .metadata
+            .annotations
+            .as_ref()
+            .and_then(parse_max_version),
     };
 
     // Check if update is allowed by policy
@@ -806,13 +874,22 @@ pub async fn handle_chart_update(
         "Update from {} to {} allowed by policy {:?} for HelmRelease {}/{}",
         current_version, new_version, update_policy, namespace, name
     );
+    let resource = DeploymentInfo {
+        name: name.clone(),
+        namespace: namespace.clone(),
+        current_image: format!("{}:{}", chart_name, current_version),
+        new_image: format!("{}:{}", chart_name, new_version),
+        container: None,
+        resource_kind: Some("HelmRelease".to_string()),
+    };
+    audit::record(UpdateAuditSpec::new(AuditAction::Detected, &resource));
 
     // Build ResourcePolicy struct from annotations
     let annotations = helm_release.metadata.annotations.as_ref();
-    let require_approval = annotations
-        .and_then(|a| a.get(annotations::REQUIRE_APPROVAL))
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(true);
+    let require_approval = annotations.map_or_else(
+        || parse_require_approval(&BTreeMap::new()),
+        parse_require_approval,
+    );
 
     let min_update_interval = annotations
         .and_then(|a| a.get(annotations::MIN_UPDATE_INTERVAL))
@@ -831,6 +908,16 @@ pub async fn handle_chart_update(
         images: Vec::new(),
         event_source: Default::default(),
         polling_interval: None,
+        ignore_patterns,
+        prerelease,
+        pin_digest: false,
+        channel_tag: None,
+        update_mode: UpdateMode::Image,
+        approval_ttl: annotations
+            .map(parse_approval_ttl)
+            .unwrap_or(DEFAULT_APPROVAL_TTL),
+        required_approvals: annotations.and_then(parse_required_approvals),
+        max_version: annotations.and_then(parse_max_version),
     };
 
     // Check if approval is required
@@ -874,13 +961,15 @@ pub async fn handle_chart_update(
                     min_update_interval,
                     remaining.num_seconds()
                 );
-                crate::metrics::UPDATES_SKIPPED_INTERVAL.inc();
+                crate::metrics::UPDATES_SKIPPED_INTERVAL
+                    .with_label_values(&[namespace.as_str(), "HelmRelease"])
+                    .inc();
                 return Ok(());
             }
         }
 
         // Perform the direct update
-        crate::approval::update_helmrelease_chart_version(
+        let result = crate::approval::update_helmrelease_chart_version(
             client,
             &namespace,
             &name,
@@ -888,7 +977,9 @@ pub async fn handle_chart_update(
             current_version,
             new_version,
         )
-        .await?;
+        .await;
+        audit::record(UpdateAuditSpec::new(AuditAction::Applied, &resource).with_result(&result));
+        result?;
 
         // Update the last-update annotation on the HelmRelease
         use crate::models::HelmRelease;
@@ -917,3 +1008,148 @@ pub async fn handle_chart_update(
 
     Ok(())
 }
+
+/// Handle a new tag for an image pinned in HelmRelease values
+/// (`headwind.sh/values-image-paths`), from webhooks or polling
+pub async fn handle_values_image_update(
+    client: &Client,
+    policy_engine: &Arc<PolicyEngine>,
+    helm_release: &HelmRelease,
+    values_image: &ValuesImage,
+    new_version: &str,
+) -> Result<()> {
+    let namespace = helm_release.namespace().unwrap_or_default();
+    let name = helm_release.name_any();
+    let annotations = helm_release.metadata.annotations.as_ref();
+
+    let update_policy = parse_policy_from_annotations(annotations);
+    if update_policy == UpdatePolicy::None {
+        return Ok(());
+    }
+    let resource_policy = build_resource_policy(annotations, update_policy);
+
+    let (repository, current_version) = split_image(&values_image.image)
+        .ok_or_else(|| anyhow::anyhow!("Image {} has no tag", values_image.image))?;
+
+    if !policy_engine.should_update(&resource_policy, current_version, new_version)? {
+        info!(
+            "Update of HelmRelease {}/{} values path .{} from {} to {} not allowed by policy {:?}",
+            namespace, name, values_image.path, current_version, new_version, update_policy
+        );
+        return Ok(());
+    }
+
+    // Check minimum update interval
+    if let (Some(min_interval), Some(last_update)) = (
+        resource_policy.min_update_interval,
+        annotations
+            .and_then(|a| a.get(annotations::LAST_UPDATE))
+            .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok()),
+    ) {
+        let elapsed = chrono::Utc::now().signed_duration_since(last_update);
+        if elapsed < chrono::Duration::seconds(min_interval as i64) {
+            info!(
+                "Skipping update for HelmRelease {}/{}: minimum interval not reached ({} < {}s)",
+                namespace,
+                name,
+                elapsed.num_seconds(),
+                min_interval
+            );
+            crate::metrics::UPDATES_SKIPPED_INTERVAL
+                .with_label_values(&[namespace.as_str(), "HelmRelease"])
+                .inc();
+            return Ok(());
+        }
+    }
+
+    let new_image = format!("{}:{}", repository, new_version);
+    let resource = DeploymentInfo {
+        name: name.clone(),
+        namespace: namespace.clone(),
+        current_image: values_image.image.clone(),
+        new_image: new_image.clone(),
+        container: None,
+        resource_kind: Some("HelmRelease".to_string()),
+    };
+    audit::record(UpdateAuditSpec::new(AuditAction::Detected, &resource));
+
+    if resource_policy.require_approval {
+        let spec = UpdateRequestSpec {
+            target_ref: TargetRef {
+                api_version: "helm.toolkit.fluxcd.io/v2".to_string(),
+                kind: "HelmRelease".to_string(),
+                name: name.clone(),
+                namespace: namespace.clone(),
+            },
+            update_type: UpdateType::Image,
+            container_name: None,
+            image_parameter: Some(values_image.path.clone()),
+            current_image: values_image.image.clone(),
+            new_image,
+            policy: map_policy_to_crd(&update_policy),
+            reason: Some(format!(
+                "New image for values path .{}: {} -> {}",
+                values_image.path, current_version, new_version
+            )),
+            require_approval: true,
+            expires_at: resource_policy.approval_expiry(),
+            required_approvals: resource_policy.required_approvals,
+            git_write_back: None,
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
+        };
+        workload::create_update_request(client, &namespace, spec).await?;
+        return Ok(());
+    }
+
+    // Hold the update until the maintenance window opens
+    let update = WorkloadImageUpdate {
+        kind: "HelmRelease",
+        namespace: &namespace,
+        name: &name,
+        container_name: None,
+        current_image: &values_image.image,
+        new_image: &new_image,
+        values_path: Some(&values_image.path),
+        policy: &resource_policy,
+    };
+    if annotations.is_some_and(|annotations| schedule::defer_outside_window(annotations, &update)) {
+        return Ok(());
+    }
+
+    info!(
+        "Approval not required, updating HelmRelease {}/{} values path .{} directly",
+        namespace, name, values_image.path
+    );
+    let result = crate::approval::update_helmrelease_values_image(
+        client,
+        &namespace,
+        &name,
+        &values_image.path,
+        &values_image.image,
+        &new_image,
+    )
+    .await;
+    audit::record(UpdateAuditSpec::new(AuditAction::Applied, &resource).with_result(&result));
+    result?;
+
+    // Record the update time for the minimum update interval
+    let helm_api: Api<HelmRelease> = Api::namespaced(client.clone(), &namespace);
+    let patch = serde_json::json!({
+        "metadata": {
+            "annotations": {
+                annotations::LAST_UPDATE: chrono::Utc::now().to_rfc3339()
+            }
+        }
+    });
+    helm_api
+        .patch(
+            &name,
+            &kube::api::PatchParams::default(),
+            &kube::api::Patch::Merge(&patch),
+        )
+        .await?;
+
+    Ok(())
+}
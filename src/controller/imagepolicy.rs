@@ -0,0 +1,106 @@
+use crate::config::namespaces;
+use crate::controller::stores;
+use crate::health;
+use crate::metrics::{RECONCILE_DURATION, RECONCILE_ERRORS};
+use crate::models::imagepolicy::{ImagePolicy, ImageRepository};
+use crate::policy::flux::FluxImagePolicy;
+use anyhow::Result;
+use futures::StreamExt;
+use kube::{
+    Api, Client, ResourceExt,
+    runtime::{Controller, controller::Action, watcher::Config},
+};
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, error, info, warn};
+
+/// Watches Flux ImagePolicies so polling and webhook processing can serve them from the
+/// informer cache, and reports policies Headwind can't apply
+pub struct ImagePolicyController {
+    client: Client,
+}
+
+impl ImagePolicyController {
+    pub async fn new() -> Result<Self> {
+        let client = Client::try_default().await?;
+
+        info!("Flux ImagePolicy controller initialized");
+
+        Ok(Self { client })
+    }
+
+    pub async fn run(self) {
+        let api: Api<ImagePolicy> = namespaces::scoped_api(self.client.clone());
+
+        let controller = Controller::new(api, Config::default());
+        stores::register(controller.store());
+
+        controller
+            .shutdown_on_signal()
+            .run(
+                reconcile,
+                error_policy,
+                Arc::new(ControllerContext {
+                    client: self.client.clone(),
+                }),
+            )
+            .inspect(|res| health::track_controller("ImagePolicy", res))
+            .filter_map(|x| async move { std::result::Result::ok(x) })
+            .for_each(|_| futures::future::ready(()))
+            .await;
+
+        stores::unregister::<ImagePolicy>();
+    }
+}
+
+struct ControllerContext {
+    client: Client,
+}
+
+async fn reconcile(
+    policy: Arc<ImagePolicy>,
+    ctx: Arc<ControllerContext>,
+) -> Result<Action, kube::Error> {
+    let _timer = RECONCILE_DURATION.start_timer();
+
+    let namespace = policy.namespace().unwrap_or_default();
+    let name = policy.name_any();
+
+    if !namespaces::is_watched(&namespace) {
+        return Ok(Action::await_change());
+    }
+
+    let reference = &policy.spec.image_repository_ref;
+    let repository_namespace = reference.namespace.as_deref().unwrap_or(&namespace);
+    let repositories: Api<ImageRepository> =
+        Api::namespaced(ctx.client.clone(), repository_namespace);
+    let Some(repository) = repositories.get_opt(&reference.name).await? else {
+        warn!(
+            "ImagePolicy {}/{} references missing ImageRepository {}/{}",
+            namespace, name, repository_namespace, reference.name
+        );
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    };
+
+    match FluxImagePolicy::resolve(&policy, &repository) {
+        Ok(resolved) => debug!(
+            "ImagePolicy {}/{} tracks {} with {:?}",
+            namespace, name, resolved.repository, resolved.annotations
+        ),
+        Err(e) => warn!(
+            "ImagePolicy {}/{} can't be applied by Headwind: {}",
+            namespace, name, e
+        ),
+    }
+
+    Ok(Action::requeue(Duration::from_secs(3600)))
+}
+
+fn error_policy(
+    _policy: Arc<ImagePolicy>,
+    error: &kube::Error,
+    _ctx: Arc<ControllerContext>,
+) -> Action {
+    RECONCILE_ERRORS.with_label_values(&["ImagePolicy"]).inc();
+    error!("Reconciliation error: {}", error);
+    Action::requeue(Duration::from_secs(60))
+}
@@ -0,0 +1,286 @@
+use crate::audit;
+use crate::controller::workload::{
+    WorkloadImageUpdate, create_update_request, map_policy_to_crd, split_image,
+};
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
+use crate::models::crd::{TargetRef, UpdateRequestSpec, UpdateType};
+use crate::models::kustomization::{Kustomization, KustomizationSpec, KustomizeImage};
+use crate::models::policy::annotations;
+use crate::notifications::{self, DeploymentInfo};
+use anyhow::Result;
+use kube::{
+    Api, Client,
+    api::{Patch, PatchParams},
+};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use tracing::info;
+
+pub const API_VERSION: &str = "kustomize.toolkit.fluxcd.io/v1";
+pub const KIND: &str = "Kustomization";
+
+/// Namespace Kustomizations are looked up in when the annotation doesn't name one
+pub const DEFAULT_KUSTOMIZATION_NAMESPACE: &str = "flux-system";
+
+/// The Flux Kustomization a workload is applied by
+#[derive(Debug, Clone, PartialEq)]
+pub struct KustomizationRef {
+    pub namespace: String,
+    pub name: String,
+}
+
+/// Read the `headwind.sh/flux-kustomization` annotation (`name` or `namespace/name`).
+/// Workloads carrying it are updated through the Kustomization's `spec.images` overrides
+/// instead of being patched directly, so the next Flux reconciliation doesn't revert the
+/// change.
+pub fn managing_kustomization(annotations: &BTreeMap<String, String>) -> Option<KustomizationRef> {
+    let value = annotations.get(annotations::FLUX_KUSTOMIZATION)?.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let (namespace, name) = value
+        .split_once('/')
+        .unwrap_or((DEFAULT_KUSTOMIZATION_NAMESPACE, value));
+
+    Some(KustomizationRef {
+        namespace: namespace.to_string(),
+        name: name.to_string(),
+    })
+}
+
+fn target_ref(namespace: &str, name: &str) -> TargetRef {
+    TargetRef {
+        api_version: API_VERSION.to_string(),
+        kind: KIND.to_string(),
+        name: name.to_string(),
+        namespace: namespace.to_string(),
+    }
+}
+
+/// Route a workload image update to the Kustomization that applies the workload. The
+/// caller has already checked the policy and minimum update interval.
+pub async fn handle_workload_image_update(
+    client: &Client,
+    kustomization: &KustomizationRef,
+    update: WorkloadImageUpdate<'_>,
+) -> Result<()> {
+    info!(
+        "{} {}/{} is applied by Kustomization {}/{}, updating its image overrides",
+        update.kind, update.namespace, update.name, kustomization.namespace, kustomization.name
+    );
+
+    if update.policy.require_approval {
+        let spec = UpdateRequestSpec {
+            target_ref: target_ref(&kustomization.namespace, &kustomization.name),
+            update_type: UpdateType::Image,
+            container_name: update.container_name.map(str::to_string),
+            image_parameter: None,
+            current_image: update.current_image.to_string(),
+            new_image: update.new_image.to_string(),
+            policy: map_policy_to_crd(&update.policy.policy),
+            reason: Some(format!(
+                "New image for {} {}/{}: {} -> {}",
+                update.kind, update.namespace, update.name, update.current_image, update.new_image
+            )),
+            require_approval: true,
+            expires_at: update.policy.approval_expiry(),
+            required_approvals: update.policy.required_approvals,
+            git_write_back: None,
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
+        };
+
+        create_update_request(client, &kustomization.namespace, spec).await?;
+    } else {
+        let resource = DeploymentInfo {
+            name: kustomization.name.clone(),
+            namespace: kustomization.namespace.clone(),
+            current_image: update.current_image.to_string(),
+            new_image: update.new_image.to_string(),
+            container: update.container_name.map(str::to_string),
+            resource_kind: Some(KIND.to_string()),
+        };
+        let result = update_kustomization_image(
+            client,
+            &kustomization.namespace,
+            &kustomization.name,
+            update.new_image,
+        )
+        .await;
+        audit::record(
+            UpdateAuditSpec::new(AuditAction::Applied, &resource)
+                .with_message(format!(
+                    "{} {}/{} is applied by this Kustomization",
+                    update.kind, update.namespace, update.name
+                ))
+                .with_result(&result),
+        );
+        result?;
+
+        notifications::notify_update_completed(resource);
+    }
+
+    Ok(())
+}
+
+/// Set the image override for the repository of `new_image` on a Kustomization
+pub async fn update_kustomization_image(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    new_image: &str,
+) -> Result<()> {
+    let api: Api<Kustomization> = Api::namespaced(client.clone(), namespace);
+    let kustomization = api.get(name).await?;
+
+    let patch = image_patch(&kustomization.spec, new_image)?;
+
+    info!(
+        "Updating Kustomization {}/{} to image {}",
+        namespace, name, new_image
+    );
+
+    api.patch(
+        name,
+        &PatchParams::default(),
+        &Patch::Json::<()>(serde_json::from_value(patch)?),
+    )
+    .await?;
+
+    crate::metrics::UPDATES_APPLIED
+        .with_label_values(&[namespace, KIND])
+        .inc();
+
+    Ok(())
+}
+
+/// Build the JSON patch that points the override for the repository of `new_image` at its
+/// tag, adding an override when the Kustomization has none for it. A digest in the new
+/// image (`repo:tag@sha256:...`) is set as `digest`; otherwise a stale digest is dropped,
+/// since it would take precedence over the tag.
+fn image_patch(spec: &KustomizationSpec, new_image: &str) -> Result<Value> {
+    let (repository, tag) =
+        split_image(new_image).ok_or_else(|| anyhow::anyhow!("Image {} has no tag", new_image))?;
+    let (tag, digest) = match tag.split_once('@') {
+        Some((tag, digest)) => (tag, Some(digest.to_string())),
+        None => (tag, None),
+    };
+
+    let op = match spec.image_index(repository) {
+        Some(index) => {
+            let entry = KustomizeImage {
+                new_tag: Some(tag.to_string()),
+                digest,
+                ..spec.images[index].clone()
+            };
+            json!({ "op": "replace", "path": format!("/spec/images/{}", index), "value": entry })
+        },
+        None => {
+            let entry = KustomizeImage {
+                name: repository.to_string(),
+                new_name: None,
+                new_tag: Some(tag.to_string()),
+                digest,
+            };
+            if spec.images.is_empty() {
+                json!({ "op": "add", "path": "/spec/images", "value": [entry] })
+            } else {
+                json!({ "op": "add", "path": "/spec/images/-", "value": entry })
+            }
+        },
+    };
+
+    Ok(json!([op]))
+}
+
+/// The image a Kustomization currently pins for an UpdateRequest. Without an override the
+/// image comes from the manifests in the source, so the request's current image is assumed.
+pub fn current_kustomization_image(
+    kustomization: &Kustomization,
+    spec: &UpdateRequestSpec,
+) -> String {
+    let Some((repository, _)) = split_image(&spec.current_image) else {
+        return spec.current_image.clone();
+    };
+
+    match kustomization.spec.image_tag(repository) {
+        Some(tag) => format!("{}:{}", repository, tag),
+        None => spec.current_image.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(images: Vec<KustomizeImage>) -> KustomizationSpec {
+        KustomizationSpec {
+            path: Some("./apps".to_string()),
+            images,
+            suspend: None,
+        }
+    }
+
+    #[test]
+    fn test_managing_kustomization() {
+        let mut annotations = BTreeMap::new();
+        assert_eq!(managing_kustomization(&annotations), None);
+
+        annotations.insert(
+            annotations::FLUX_KUSTOMIZATION.to_string(),
+            "apps".to_string(),
+        );
+        assert_eq!(
+            managing_kustomization(&annotations),
+            Some(KustomizationRef {
+                namespace: "flux-system".to_string(),
+                name: "apps".to_string(),
+            })
+        );
+
+        annotations.insert(
+            annotations::FLUX_KUSTOMIZATION.to_string(),
+            "team-a/web".to_string(),
+        );
+        assert_eq!(
+            managing_kustomization(&annotations).unwrap().namespace,
+            "team-a"
+        );
+    }
+
+    #[test]
+    fn test_image_patch() {
+        let existing = spec(vec![KustomizeImage {
+            name: "app".to_string(),
+            new_name: Some("ghcr.io/org/app".to_string()),
+            new_tag: Some("1.0.0".to_string()),
+            digest: Some("sha256:old".to_string()),
+        }]);
+
+        let patch = image_patch(&existing, "ghcr.io/org/app:1.1.0").unwrap();
+        assert_eq!(
+            patch,
+            json!([{
+                "op": "replace",
+                "path": "/spec/images/0",
+                "value": { "name": "app", "newName": "ghcr.io/org/app", "newTag": "1.1.0" }
+            }])
+        );
+
+        let patch = image_patch(&existing, "nginx:1.26.0@sha256:abc").unwrap();
+        assert_eq!(
+            patch,
+            json!([{
+                "op": "add",
+                "path": "/spec/images/-",
+                "value": { "name": "nginx", "newTag": "1.26.0", "digest": "sha256:abc" }
+            }])
+        );
+
+        let patch = image_patch(&spec(Vec::new()), "nginx:1.26.0").unwrap();
+        assert_eq!(patch[0]["path"], "/spec/images");
+        assert_eq!(patch[0]["value"][0]["newTag"], "1.26.0");
+    }
+}
@@ -1,25 +1,41 @@
+pub mod argocd;
+mod cronjob;
 mod daemonset;
 mod deployment;
 mod helm;
+mod imagepolicy;
+pub mod kustomization;
+pub mod plainhelm;
+mod retention;
+mod rollout;
 mod statefulset;
+pub mod stores;
+pub mod tracked;
+mod updaterequest;
+pub mod workload;
 
 use anyhow::Result;
 use tokio::task::JoinHandle;
 use tracing::info;
 
-pub use daemonset::{
-    DaemonSetController, handle_image_update as handle_daemonset_image_update,
-    update_daemonset_image, update_daemonset_image_with_tracking,
-};
+pub use argocd::ArgoCdController;
+pub use cronjob::{CronJobController, pod_spec as cronjob_pod_spec};
+pub use daemonset::DaemonSetController;
 pub use deployment::{
-    DeploymentController, handle_image_update as handle_deployment_image_update,
-    update_deployment_image, update_deployment_image_with_tracking,
+    DeploymentController, update_deployment_image, update_deployment_image_with_tracking,
+    update_deployment_images_with_tracking,
 };
-pub use helm::{HelmController, handle_chart_update as handle_helm_chart_update};
-pub use statefulset::{
-    StatefulSetController, handle_image_update as handle_statefulset_image_update,
-    update_statefulset_image, update_statefulset_image_with_tracking,
+pub use helm::{
+    HelmController, handle_chart_update as handle_helm_chart_update,
+    handle_values_image_update as handle_helm_values_image_update,
 };
+pub use imagepolicy::ImagePolicyController;
+pub use plainhelm::PlainHelmController;
+pub use retention::start_update_request_pruner;
+pub use rollout::track_rollout;
+pub use statefulset::StatefulSetController;
+pub use tracked::{DetectedImage, TrackedWorkload};
+pub use updaterequest::UpdateRequestController;
 
 pub async fn start_controllers() -> Result<JoinHandle<()>> {
     info!("Starting Kubernetes controllers");
@@ -30,6 +46,23 @@ pub async fn start_controllers() -> Result<JoinHandle<()>> {
         .and_then(|v| v.parse().ok())
         .unwrap_or(true);
 
+    let scope = crate::config::namespaces::scope();
+    if scope.is_restricted() {
+        info!(
+            "Namespace scope: watching {}, excluding {}",
+            if scope.watch.is_empty() {
+                "all namespaces".to_string()
+            } else {
+                scope.watch.join(", ")
+            },
+            if scope.exclude.is_empty() {
+                "none".to_string()
+            } else {
+                scope.exclude.join(", ")
+            }
+        );
+    }
+
     let handle = if controllers_enabled {
         // Start deployment controller
         let deployment_controller = DeploymentController::new().await?;
@@ -40,9 +73,36 @@ pub async fn start_controllers() -> Result<JoinHandle<()>> {
         // Start DaemonSet controller
         let daemonset_controller = DaemonSetController::new().await?;
 
+        // Start CronJob controller
+        let cronjob_controller = CronJobController::new().await?;
+
         // Start Helm controller
         let policy_engine = std::sync::Arc::new(crate::policy::PolicyEngine);
-        let helm_controller = HelmController::new(policy_engine).await?;
+        let helm_controller = HelmController::new(policy_engine.clone()).await?;
+
+        // Start UpdateRequest controller (approval expiry)
+        let update_request_controller = UpdateRequestController::new().await?;
+
+        // Start ArgoCD Application controller (opt-in)
+        let argocd_controller = if argocd::argocd_enabled() {
+            Some(ArgoCdController::new(policy_engine.clone()).await?)
+        } else {
+            None
+        };
+
+        // Start Flux ImagePolicy controller (opt-in)
+        let image_policy_controller = if crate::policy::flux::flux_image_policies_enabled() {
+            Some(ImagePolicyController::new().await?)
+        } else {
+            None
+        };
+
+        // Start Helm CLI release controller (opt-in)
+        let plain_helm_controller = if plainhelm::plain_helm_enabled() {
+            Some(PlainHelmController::new(policy_engine).await?)
+        } else {
+            None
+        };
 
         tokio::spawn(async move {
             // Run all controllers concurrently
@@ -61,17 +121,62 @@ pub async fn start_controllers() -> Result<JoinHandle<()>> {
                 tracing::info!("DaemonSet controller stopped");
             });
 
+            let cronjob_handle = tokio::spawn(async move {
+                cronjob_controller.run().await;
+                tracing::info!("CronJob controller stopped");
+            });
+
             let helm_handle = tokio::spawn(async move {
                 helm_controller.run().await;
                 tracing::info!("Helm controller stopped");
             });
 
+            let update_request_handle = tokio::spawn(async move {
+                update_request_controller.run().await;
+                tracing::info!("UpdateRequest controller stopped");
+            });
+
+            let argocd_handle = tokio::spawn(async move {
+                match argocd_controller {
+                    Some(controller) => {
+                        controller.run().await;
+                        tracing::info!("ArgoCD controller stopped");
+                    },
+                    None => std::future::pending::<()>().await,
+                }
+            });
+
+            let image_policy_handle = tokio::spawn(async move {
+                match image_policy_controller {
+                    Some(controller) => {
+                        controller.run().await;
+                        tracing::info!("Flux ImagePolicy controller stopped");
+                    },
+                    None => std::future::pending::<()>().await,
+                }
+            });
+
+            let plain_helm_handle = tokio::spawn(async move {
+                match plain_helm_controller {
+                    Some(controller) => {
+                        controller.run().await;
+                        tracing::info!("Helm CLI release controller stopped");
+                    },
+                    None => std::future::pending::<()>().await,
+                }
+            });
+
             // Wait for any controller to stop
             tokio::select! {
                 _ = deployment_handle => {},
                 _ = statefulset_handle => {},
                 _ = daemonset_handle => {},
+                _ = cronjob_handle => {},
                 _ = helm_handle => {},
+                _ = update_request_handle => {},
+                _ = argocd_handle => {},
+                _ = image_policy_handle => {},
+                _ = plain_helm_handle => {},
             }
         })
     } else {
@@ -0,0 +1,429 @@
+use crate::audit;
+use crate::config::{namespaces, plainhelm};
+use crate::controller::helm::{build_resource_policy, parse_policy_from_annotations};
+use crate::controller::workload::{WorkloadImageUpdate, create_update_request, map_policy_to_crd};
+use crate::health;
+use crate::helm::release::{self, DEPLOYED_RELEASE_SELECTOR, RELEASE_SECRET_TYPE, Release};
+use crate::helm::{HelmRepositoryClient, OciHelmClient};
+use crate::metrics::{
+    HELM_REPOSITORY_ERRORS, HELM_REPOSITORY_QUERIES, HELM_UPDATES_APPLIED, HELM_UPDATES_FOUND,
+    RECONCILE_DURATION, RECONCILE_ERRORS,
+};
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
+use crate::models::crd::{TargetRef, UpdateRequestSpec, UpdateType};
+use crate::models::{HelmRepositorySpec, SecretReference, UpdatePolicy};
+use crate::notifications::{self, DeploymentInfo};
+use crate::policy::PolicyEngine;
+use crate::schedule;
+use anyhow::Result;
+use futures::StreamExt;
+use k8s_openapi::api::batch::v1::Job;
+use k8s_openapi::api::core::v1::Secret;
+use kube::{
+    Api, Client, ResourceExt,
+    api::{ListParams, PostParams},
+    runtime::{Controller, controller::Action, watcher::Config},
+};
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, error, info, warn};
+
+/// TargetRef API version and kind of Helm CLI releases in UpdateRequests
+pub const API_VERSION: &str = "helm.sh/v3";
+pub const KIND: &str = "Release";
+
+/// Whether releases installed by the Helm CLI are tracked (HEADWIND_PLAIN_HELM_ENABLED,
+/// default: false)
+pub fn plain_helm_enabled() -> bool {
+    std::env::var("HEADWIND_PLAIN_HELM_ENABLED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Watches the release Secrets of the Helm CLI and upgrades releases listed in the
+/// `plainHelmReleases` configuration when a newer chart version is published
+pub struct PlainHelmController {
+    client: Client,
+    policy_engine: Arc<PolicyEngine>,
+}
+
+impl PlainHelmController {
+    pub async fn new(policy_engine: Arc<PolicyEngine>) -> Result<Self> {
+        let client = Client::try_default().await?;
+
+        info!("Helm CLI release controller initialized");
+
+        Ok(Self {
+            client,
+            policy_engine,
+        })
+    }
+
+    pub async fn run(self) {
+        let api: Api<Secret> = namespaces::scoped_api(self.client.clone());
+        let config = Config::default()
+            .fields(&format!("type={}", RELEASE_SECRET_TYPE))
+            .labels(DEPLOYED_RELEASE_SELECTOR);
+
+        let context = Arc::new(ControllerContext {
+            client: self.client.clone(),
+            policy_engine: self.policy_engine.clone(),
+            helm_repo_client: HelmRepositoryClient::with_client(self.client.clone())
+                .expect("Failed to create Helm repository client"),
+            oci_helm_client: OciHelmClient::new(),
+        });
+
+        Controller::new(api, config)
+            .shutdown_on_signal()
+            .run(reconcile, error_policy, context)
+            .inspect(|res| health::track_controller(KIND, res))
+            .filter_map(|x| async move { std::result::Result::ok(x) })
+            .for_each(|_| futures::future::ready(()))
+            .await;
+    }
+}
+
+struct ControllerContext {
+    client: Client,
+    policy_engine: Arc<PolicyEngine>,
+    helm_repo_client: HelmRepositoryClient,
+    oci_helm_client: OciHelmClient,
+}
+
+async fn reconcile(
+    secret: Arc<Secret>,
+    ctx: Arc<ControllerContext>,
+) -> Result<Action, kube::Error> {
+    let _timer = RECONCILE_DURATION.start_timer();
+
+    let namespace = secret.namespace().unwrap_or_default();
+    if !namespaces::is_watched(&namespace) {
+        return Ok(Action::await_change());
+    }
+
+    let release = match Release::from_secret(&secret) {
+        Ok(release) => release,
+        Err(e) => {
+            warn!(
+                "Failed to read Helm release Secret {}/{}: {:#}",
+                namespace,
+                secret.name_any(),
+                e
+            );
+            return Ok(Action::await_change());
+        },
+    };
+    let name = release.name.clone();
+
+    // Only releases with a configured source are tracked. The configuration can change at
+    // any time, so untracked releases are looked at again later.
+    let Some(source) = plainhelm::for_release(&namespace, &name) else {
+        debug!(
+            "Helm release {}/{} is not listed in plainHelmReleases, skipping",
+            namespace, name
+        );
+        return Ok(Action::requeue(Duration::from_secs(3600)));
+    };
+
+    let update_policy = parse_policy_from_annotations(Some(&source.annotations));
+    if update_policy == UpdatePolicy::None {
+        debug!(
+            "Helm release {}/{} has policy=none, skipping",
+            namespace, name
+        );
+        return Ok(Action::requeue(Duration::from_secs(3600)));
+    }
+    let policy = build_resource_policy(Some(&source.annotations), update_policy);
+
+    let chart_name = &release.chart.metadata.name;
+    let current_version = &release.chart.metadata.version;
+
+    let Some(new_version) = discover_chart_version(
+        &ctx,
+        &namespace,
+        &source,
+        chart_name,
+        current_version,
+        &policy.policy,
+    )
+    .await
+    else {
+        debug!("Helm release {}/{} - No new chart version", namespace, name);
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    };
+
+    match ctx
+        .policy_engine
+        .should_update(&policy, current_version, &new_version)
+    {
+        Ok(true) => {},
+        Ok(false) => {
+            debug!(
+                "Helm release {}/{} - Update from {} to {} rejected by policy",
+                namespace, name, current_version, new_version
+            );
+            return Ok(Action::requeue(Duration::from_secs(300)));
+        },
+        Err(e) => {
+            warn!(
+                "Helm release {}/{} - Error checking update policy: {}",
+                namespace, name, e
+            );
+            return Ok(Action::requeue(Duration::from_secs(300)));
+        },
+    }
+
+    HELM_UPDATES_FOUND.with_label_values(&[&namespace]).inc();
+
+    let current_chart = release.chart_ref();
+    let new_chart = format!("{}:{}", chart_name, new_version);
+    let resource = DeploymentInfo {
+        name: name.clone(),
+        namespace: namespace.clone(),
+        current_image: current_chart.clone(),
+        new_image: new_chart.clone(),
+        container: None,
+        resource_kind: Some(KIND.to_string()),
+    };
+    audit::record(UpdateAuditSpec::new(AuditAction::Detected, &resource));
+
+    if policy.require_approval {
+        let spec = UpdateRequestSpec {
+            target_ref: target_ref(&namespace, &name),
+            update_type: UpdateType::HelmChart,
+            container_name: None,
+            image_parameter: None,
+            current_image: current_chart,
+            new_image: new_chart,
+            policy: map_policy_to_crd(&policy.policy),
+            reason: Some(format!("New chart version {} available", new_version)),
+            require_approval: true,
+            expires_at: policy.approval_expiry(),
+            required_approvals: policy.required_approvals,
+            git_write_back: None,
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
+        };
+
+        if let Err(e) = create_update_request(&ctx.client, &namespace, spec).await {
+            warn!(
+                "Failed to create UpdateRequest for Helm release {}/{}: {}",
+                namespace, name, e
+            );
+        }
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    }
+
+    // Hold the update until the maintenance window opens
+    let update = WorkloadImageUpdate {
+        kind: KIND,
+        namespace: &namespace,
+        name: &name,
+        container_name: None,
+        current_image: &current_chart,
+        new_image: &new_chart,
+        values_path: None,
+        policy: &policy,
+    };
+    if schedule::defer_outside_window(&source.annotations, &update) {
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    }
+
+    let result = upgrade_release(
+        &ctx.client,
+        &namespace,
+        &name,
+        chart_name,
+        current_version,
+        &new_version,
+    )
+    .await;
+    audit::record(UpdateAuditSpec::new(AuditAction::Applied, &resource).with_result(&result));
+    if let Err(e) = result {
+        warn!(
+            "Failed to upgrade Helm release {}/{} to chart version {}: {:#}",
+            namespace, name, new_version, e
+        );
+    }
+
+    Ok(Action::requeue(Duration::from_secs(300)))
+}
+
+fn error_policy(_secret: Arc<Secret>, error: &kube::Error, _ctx: Arc<ControllerContext>) -> Action {
+    RECONCILE_ERRORS.with_label_values(&[KIND]).inc();
+    error!("Reconciliation error: {}", error);
+    Action::requeue(Duration::from_secs(60))
+}
+
+fn target_ref(namespace: &str, name: &str) -> TargetRef {
+    TargetRef {
+        api_version: API_VERSION.to_string(),
+        kind: KIND.to_string(),
+        name: name.to_string(),
+        namespace: namespace.to_string(),
+    }
+}
+
+/// Find the best chart version allowed by the policy in the release's repository
+async fn discover_chart_version(
+    ctx: &ControllerContext,
+    namespace: &str,
+    source: &plainhelm::PlainHelmRelease,
+    chart_name: &str,
+    current_version: &str,
+    policy: &UpdatePolicy,
+) -> Option<String> {
+    // Credentials are read the way a Flux HelmRepository's secretRef is
+    let repository = HelmRepositorySpec {
+        url: source.repository.clone(),
+        secret_ref: source
+            .secret_ref
+            .clone()
+            .map(|name| SecretReference { name }),
+        ..Default::default()
+    };
+    let auth = match ctx
+        .helm_repo_client
+        .read_repository_auth(namespace, &repository)
+        .await
+    {
+        Ok(auth) => auth,
+        Err(e) => {
+            warn!(
+                "Failed to read credentials for repository {}: {:#}",
+                source.repository, e
+            );
+            HELM_REPOSITORY_ERRORS.inc();
+            return None;
+        },
+    };
+
+    HELM_REPOSITORY_QUERIES.inc();
+
+    if source.repository.starts_with("oci://") {
+        let oci_url = format!("{}/{}", source.repository.trim_end_matches('/'), chart_name);
+        let credentials = auth.basic.as_ref();
+        match ctx
+            .oci_helm_client
+            .get_chart_versions(
+                &oci_url,
+                credentials.map(|c| c.username.as_str()),
+                credentials.map(|c| c.password.as_str()),
+            )
+            .await
+        {
+            Ok(versions) => {
+                ctx.oci_helm_client
+                    .find_best_version(&versions, current_version, policy)
+            },
+            Err(e) => {
+                warn!("Failed to list OCI tags from {}: {}", oci_url, e);
+                HELM_REPOSITORY_ERRORS.inc();
+                None
+            },
+        }
+    } else {
+        match ctx
+            .helm_repo_client
+            .fetch_index_authenticated(&source.repository, &auth)
+            .await
+        {
+            Ok(index) => {
+                ctx.helm_repo_client
+                    .find_best_version(&index, chart_name, current_version, policy)
+            },
+            Err(e) => {
+                warn!("Failed to fetch index from {}: {}", source.repository, e);
+                HELM_REPOSITORY_ERRORS.inc();
+                None
+            },
+        }
+    }
+}
+
+/// The deployed revision of a Helm CLI release
+pub async fn deployed_release(client: &Client, namespace: &str, name: &str) -> Result<Release> {
+    let secrets: Api<Secret> = Api::namespaced(client.clone(), namespace);
+    let list = secrets
+        .list(
+            &ListParams::default()
+                .fields(&format!("type={}", RELEASE_SECRET_TYPE))
+                .labels(&format!("{},name={}", DEPLOYED_RELEASE_SELECTOR, name)),
+        )
+        .await?;
+
+    list.items
+        .iter()
+        .filter_map(|secret| Release::from_secret(secret).ok())
+        .max_by_key(|release| release.version)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Helm release {}/{} has no deployed revision",
+                namespace,
+                name
+            )
+        })
+}
+
+/// Start a Job running `helm upgrade` to move a release to `new_version` of its chart. The
+/// upgrade keeps the release's values. The Job is named after the release and version, so
+/// an upgrade that was already started isn't started again.
+pub async fn upgrade_release(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    chart_name: &str,
+    current_version: &str,
+    new_version: &str,
+) -> Result<()> {
+    let source = plainhelm::for_release(namespace, name).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Helm release {}/{} is not listed in plainHelmReleases",
+            namespace,
+            name
+        )
+    })?;
+
+    let deployed = deployed_release(client, namespace, name).await?;
+    if deployed.chart.metadata.name != chart_name {
+        return Err(anyhow::anyhow!(
+            "Chart name mismatch: expected {}, found {}",
+            chart_name,
+            deployed.chart.metadata.name
+        ));
+    }
+
+    let job = release::upgrade_job(namespace, name, chart_name, new_version, &source);
+    let job_name = job.metadata.name.clone().unwrap_or_default();
+    let jobs: Api<Job> = Api::namespaced(client.clone(), namespace);
+    match jobs.create(&PostParams::default(), &job).await {
+        Ok(_) => {},
+        Err(kube::Error::Api(err)) if err.code == 409 => {
+            debug!(
+                "Upgrade Job {}/{} already exists, not starting it again",
+                namespace, job_name
+            );
+            return Ok(());
+        },
+        Err(e) => return Err(e.into()),
+    }
+
+    info!(
+        "Started Job {}/{} upgrading Helm release {} from {} to {}",
+        namespace, job_name, name, current_version, new_version
+    );
+
+    notifications::notify_update_completed(DeploymentInfo {
+        name: name.to_string(),
+        namespace: namespace.to_string(),
+        current_image: format!("{}:{}", chart_name, current_version),
+        new_image: format!("{}:{}", chart_name, new_version),
+        container: None,
+        resource_kind: Some(KIND.to_string()),
+    });
+
+    HELM_UPDATES_APPLIED.with_label_values(&[namespace]).inc();
+
+    Ok(())
+}
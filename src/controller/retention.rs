@@ -0,0 +1,285 @@
+//! Retention of finished UpdateRequests.
+//!
+//! Completed, Rejected and Expired UpdateRequests are never reused, so they pile up over time.
+//! Every hour the pruner deletes those finished more than `HEADWIND_UPDATE_REQUEST_RETENTION_DAYS`
+//! ago, and keeps at most `HEADWIND_UPDATE_REQUEST_KEEP_PER_TARGET` finished requests for each
+//! target. Both are off when unset or `0`. Failed requests are kept for investigation, and
+//! `HEADWIND_EXPIRED_REQUEST_RETENTION_SECONDS` (see the UpdateRequest controller) still applies
+//! to expired requests on its own.
+
+use crate::config::namespaces;
+use crate::metrics::UPDATE_REQUESTS_PRUNED;
+use crate::models::crd::{UpdatePhase, UpdateRequest};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use kube::{Api, Client, ResourceExt};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// How often finished UpdateRequests are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Namespace, kind and name of the resource an UpdateRequest targets
+type Target<'a> = (&'a str, &'a str, &'a str);
+
+/// How long finished UpdateRequests are kept and how many per target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<chrono::Duration>,
+    pub keep_per_target: Option<usize>,
+}
+
+impl RetentionPolicy {
+    pub fn from_env() -> Self {
+        let positive = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .filter(|value| *value > 0)
+        };
+        Self {
+            max_age: positive("HEADWIND_UPDATE_REQUEST_RETENTION_DAYS").map(chrono::Duration::days),
+            keep_per_target: positive("HEADWIND_UPDATE_REQUEST_KEEP_PER_TARGET")
+                .map(|count| count as usize),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_age.is_some() || self.keep_per_target.is_some()
+    }
+}
+
+/// Prune finished UpdateRequests every hour, when a retention policy is configured
+pub fn start_update_request_pruner(client: Client) -> JoinHandle<()> {
+    let policy = RetentionPolicy::from_env();
+
+    tokio::spawn(async move {
+        if !policy.is_enabled() {
+            info!("Finished UpdateRequests are kept until deleted");
+            return;
+        }
+        info!(
+            "Pruning finished UpdateRequests (max age: {}, kept per target: {})",
+            policy
+                .max_age
+                .map(|age| format!("{} days", age.num_days()))
+                .unwrap_or_else(|| "unlimited".to_string()),
+            policy
+                .keep_per_target
+                .map(|count| count.to_string())
+                .unwrap_or_else(|| "unlimited".to_string())
+        );
+
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = prune(&client, &policy).await {
+                warn!("Failed to prune UpdateRequests: {}", e);
+            }
+        }
+    })
+}
+
+async fn prune(client: &Client, policy: &RetentionPolicy) -> Result<()> {
+    let update_requests = namespaces::list_watched::<UpdateRequest>(client).await?;
+
+    let mut pruned = 0;
+    for update_request in select_for_pruning(&update_requests, policy, Utc::now()) {
+        let namespace = update_request.namespace().unwrap_or_default();
+        let name = update_request.name_any();
+        let api: Api<UpdateRequest> = Api::namespaced(client.clone(), &namespace);
+        match api.delete(&name, &Default::default()).await {
+            Ok(_) => {
+                pruned += 1;
+                let phase = update_request
+                    .status
+                    .as_ref()
+                    .map(|status| status.phase.clone())
+                    .unwrap_or_default();
+                UPDATE_REQUESTS_PRUNED
+                    .with_label_values(&[namespace.as_str(), &format!("{:?}", phase)])
+                    .inc();
+                debug!("Pruned UpdateRequest {}/{} ({:?})", namespace, name, phase);
+            },
+            Err(kube::Error::Api(e)) if e.code == 404 => {},
+            Err(e) => warn!(
+                "Failed to delete UpdateRequest {}/{}: {}",
+                namespace, name, e
+            ),
+        }
+    }
+    if pruned > 0 {
+        info!("Pruned {} finished UpdateRequest(s)", pruned);
+    }
+    Ok(())
+}
+
+/// When a finished UpdateRequest finished, or None when it is still open (or Failed)
+fn finished_at(update_request: &UpdateRequest) -> Option<DateTime<Utc>> {
+    let status = update_request.status.as_ref()?;
+    if !matches!(
+        status.phase,
+        UpdatePhase::Completed | UpdatePhase::Rejected | UpdatePhase::Expired
+    ) {
+        return None;
+    }
+    status
+        .last_updated
+        .or_else(|| {
+            update_request
+                .metadata
+                .creation_timestamp
+                .as_ref()
+                .map(|t| t.0)
+        })
+        .or(Some(DateTime::<Utc>::MIN_UTC))
+}
+
+/// Finished UpdateRequests that are older than the maximum age, or beyond the newest
+/// `keep_per_target` for their target
+fn select_for_pruning<'a>(
+    update_requests: &'a [UpdateRequest],
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Vec<&'a UpdateRequest> {
+    let mut by_target: BTreeMap<Target, Vec<(DateTime<Utc>, &UpdateRequest)>> = BTreeMap::new();
+    for update_request in update_requests {
+        if let Some(finished_at) = finished_at(update_request) {
+            let target = &update_request.spec.target_ref;
+            by_target
+                .entry((
+                    target.namespace.as_str(),
+                    target.kind.as_str(),
+                    target.name.as_str(),
+                ))
+                .or_default()
+                .push((finished_at, update_request));
+        }
+    }
+
+    let cutoff = policy.max_age.map(|age| now - age);
+    let mut selected = Vec::new();
+    for mut finished in by_target.into_values() {
+        // Newest first
+        finished.sort_by_key(|(finished_at, _)| std::cmp::Reverse(*finished_at));
+        for (index, (finished_at, update_request)) in finished.into_iter().enumerate() {
+            let too_old = cutoff.is_some_and(|cutoff| finished_at < cutoff);
+            let too_many = policy.keep_per_target.is_some_and(|keep| index >= keep);
+            if too_old || too_many {
+                selected.push(update_request);
+            }
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{
+        TargetRef, UpdatePolicyType, UpdateRequestSpec, UpdateRequestStatus, UpdateType,
+    };
+
+    fn update_request(
+        name: &str,
+        target: &str,
+        phase: Option<UpdatePhase>,
+        days_ago: i64,
+    ) -> UpdateRequest {
+        let mut update_request = UpdateRequest::new(
+            name,
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: target.to_string(),
+                    namespace: "default".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: None,
+                image_parameter: None,
+                current_image: "nginx:1.25.0".to_string(),
+                new_image: "nginx:1.26.0".to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at: None,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        );
+        update_request.status = phase.map(|phase| UpdateRequestStatus {
+            phase,
+            last_updated: Some(Utc::now() - chrono::Duration::days(days_ago)),
+            ..Default::default()
+        });
+        update_request
+    }
+
+    fn names(selected: Vec<&UpdateRequest>) -> Vec<String> {
+        let mut names: Vec<_> = selected.iter().map(|r| r.name_any()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_prunes_finished_requests_by_age() {
+        let requests = vec![
+            update_request("old-completed", "web", Some(UpdatePhase::Completed), 40),
+            update_request("old-rejected", "web", Some(UpdatePhase::Rejected), 40),
+            update_request("old-expired", "api", Some(UpdatePhase::Expired), 31),
+            update_request("old-failed", "api", Some(UpdatePhase::Failed), 40),
+            update_request("old-pending", "api", Some(UpdatePhase::Pending), 40),
+            update_request("new-completed", "web", Some(UpdatePhase::Completed), 2),
+            update_request("no-status", "web", None, 40),
+        ];
+        let policy = RetentionPolicy {
+            max_age: Some(chrono::Duration::days(30)),
+            keep_per_target: None,
+        };
+
+        assert_eq!(
+            names(select_for_pruning(&requests, &policy, Utc::now())),
+            vec!["old-completed", "old-expired", "old-rejected"]
+        );
+    }
+
+    #[test]
+    fn test_keeps_most_recent_per_target() {
+        let requests = vec![
+            update_request("web-1", "web", Some(UpdatePhase::Completed), 3),
+            update_request("web-2", "web", Some(UpdatePhase::Completed), 2),
+            update_request("web-3", "web", Some(UpdatePhase::Rejected), 1),
+            update_request("web-pending", "web", Some(UpdatePhase::Pending), 5),
+            update_request("api-1", "api", Some(UpdatePhase::Completed), 9),
+        ];
+        let policy = RetentionPolicy {
+            max_age: None,
+            keep_per_target: Some(2),
+        };
+
+        assert_eq!(
+            names(select_for_pruning(&requests, &policy, Utc::now())),
+            vec!["web-1"]
+        );
+    }
+
+    #[test]
+    fn test_no_policy_prunes_nothing() {
+        let requests = vec![update_request(
+            "old-completed",
+            "web",
+            Some(UpdatePhase::Completed),
+            400,
+        )];
+        let policy = RetentionPolicy::default();
+
+        assert!(!policy.is_enabled());
+        assert!(select_for_pruning(&requests, &policy, Utc::now()).is_empty());
+    }
+}
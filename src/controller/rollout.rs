@@ -0,0 +1,292 @@
+//! Rollout progress of applied Deployment updates.
+//!
+//! Once an UpdateRequest's image patch is applied, [`track_rollout`] follows the Deployment
+//! until its rollout completes or fails and mirrors its replica counts into the UpdateRequest's
+//! `status.rollout`, so the API and Web UI show the rollout rather than only the patch.
+
+use crate::models::crd::{RolloutProgress, RolloutState, UpdateRequest};
+use chrono::Utc;
+use k8s_openapi::api::apps::v1::Deployment;
+use kube::api::{Patch, PatchParams};
+use kube::{Api, Client};
+use serde_json::json;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// How often the Deployment is read while it rolls out
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Progress deadline of Deployments without `spec.progressDeadlineSeconds`
+const DEFAULT_PROGRESS_DEADLINE: u64 = 600;
+
+/// Time beyond the progress deadline before a rollout still in progress is given up on
+const DEADLINE_GRACE: Duration = Duration::from_secs(60);
+
+/// Follow the rollout of `deployment` in the background and record its progress in the
+/// UpdateRequest `update_request_namespace/update_request_name`
+pub fn track_rollout(
+    client: Client,
+    namespace: String,
+    deployment: String,
+    update_request_namespace: String,
+    update_request_name: String,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let deployments: Api<Deployment> = Api::namespaced(client.clone(), &namespace);
+        let update_requests: Api<UpdateRequest> =
+            Api::namespaced(client, &update_request_namespace);
+
+        let mut deadline = None;
+        let mut last: Option<RolloutProgress> = None;
+        loop {
+            let progress = match deployments.get(&deployment).await {
+                Ok(current) => {
+                    let deadline = *deadline.get_or_insert_with(|| {
+                        tokio::time::Instant::now() + progress_deadline(&current)
+                    });
+                    let mut progress = rollout_progress(&current);
+                    if progress.state == RolloutState::Progressing
+                        && tokio::time::Instant::now() >= deadline
+                    {
+                        progress.state = RolloutState::Failed;
+                        progress.message = Some(format!(
+                            "Rollout did not complete in time: {}",
+                            progress.message.unwrap_or_default()
+                        ));
+                    }
+                    progress
+                },
+                Err(kube::Error::Api(e)) if e.code == 404 => {
+                    debug!(
+                        "Deployment {}/{} is gone, no longer tracking its rollout",
+                        namespace, deployment
+                    );
+                    return;
+                },
+                Err(e) => {
+                    warn!(
+                        "Failed to read rollout of deployment {}/{}: {}",
+                        namespace, deployment, e
+                    );
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                },
+            };
+
+            if last.as_ref().is_none_or(|last| changed(last, &progress)) {
+                debug!(
+                    "Rollout of deployment {}/{}: {:?} ({}/{} updated, {} ready)",
+                    namespace,
+                    deployment,
+                    progress.state,
+                    progress.updated_replicas,
+                    progress.replicas,
+                    progress.ready_replicas
+                );
+                record(&update_requests, &update_request_name, &progress).await;
+            }
+
+            match progress.state {
+                RolloutState::Progressing => {},
+                RolloutState::Complete => {
+                    info!(
+                        "Rollout of deployment {}/{} complete",
+                        namespace, deployment
+                    );
+                    return;
+                },
+                RolloutState::Failed => {
+                    warn!(
+                        "Rollout of deployment {}/{} failed: {}",
+                        namespace,
+                        deployment,
+                        progress.message.as_deref().unwrap_or_default()
+                    );
+                    return;
+                },
+            }
+            last = Some(progress);
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    })
+}
+
+/// How long the rollout may take: the Deployment's progress deadline plus a grace period
+fn progress_deadline(deployment: &Deployment) -> Duration {
+    let seconds = deployment
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.progress_deadline_seconds)
+        .and_then(|seconds| u64::try_from(seconds).ok())
+        .unwrap_or(DEFAULT_PROGRESS_DEADLINE);
+    Duration::from_secs(seconds) + DEADLINE_GRACE
+}
+
+/// Rollout progress of a Deployment, decided the way `kubectl rollout status` does
+pub fn rollout_progress(deployment: &Deployment) -> RolloutProgress {
+    let status = deployment.status.clone().unwrap_or_default();
+    let desired = deployment
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.replicas)
+        .unwrap_or(1);
+    let updated = status.updated_replicas.unwrap_or(0);
+    let available = status.available_replicas.unwrap_or(0);
+    let total = status.replicas.unwrap_or(0);
+
+    let deadline_exceeded = status.conditions.iter().flatten().any(|condition| {
+        condition.type_ == "Progressing"
+            && condition.reason.as_deref() == Some("ProgressDeadlineExceeded")
+    });
+    let observed = status.observed_generation >= deployment.metadata.generation;
+
+    let (state, message) = if !observed {
+        (
+            RolloutState::Progressing,
+            Some("Waiting for the Deployment controller to observe the update".to_string()),
+        )
+    } else if deadline_exceeded {
+        (
+            RolloutState::Failed,
+            Some("Deployment exceeded its progress deadline".to_string()),
+        )
+    } else if updated < desired {
+        (
+            RolloutState::Progressing,
+            Some(format!("{} of {} replicas updated", updated, desired)),
+        )
+    } else if total > updated {
+        (
+            RolloutState::Progressing,
+            Some(format!(
+                "{} old replicas pending termination",
+                total - updated
+            )),
+        )
+    } else if available < updated {
+        (
+            RolloutState::Progressing,
+            Some(format!(
+                "{} of {} updated replicas available",
+                available, updated
+            )),
+        )
+    } else {
+        (RolloutState::Complete, None)
+    };
+
+    RolloutProgress {
+        state,
+        replicas: desired,
+        updated_replicas: updated,
+        ready_replicas: status.ready_replicas.unwrap_or(0),
+        available_replicas: available,
+        message,
+        last_updated: Utc::now(),
+    }
+}
+
+/// Whether the progress differs from the last recorded one in more than its timestamp
+fn changed(last: &RolloutProgress, progress: &RolloutProgress) -> bool {
+    RolloutProgress {
+        last_updated: progress.last_updated,
+        ..last.clone()
+    } != *progress
+}
+
+async fn record(update_requests: &Api<UpdateRequest>, name: &str, progress: &RolloutProgress) {
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": { "rollout": progress }
+    });
+    if let Err(e) = update_requests
+        .patch_status(name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await
+    {
+        warn!(
+            "Failed to record rollout progress of UpdateRequest {}: {}",
+            name, e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::{DeploymentCondition, DeploymentSpec, DeploymentStatus};
+
+    fn deployment(generation: i64, replicas: i32, status: DeploymentStatus) -> Deployment {
+        let mut deployment = Deployment {
+            spec: Some(DeploymentSpec {
+                replicas: Some(replicas),
+                ..Default::default()
+            }),
+            status: Some(status),
+            ..Default::default()
+        };
+        deployment.metadata.generation = Some(generation);
+        deployment
+    }
+
+    fn status(observed: i64, total: i32, updated: i32, available: i32) -> DeploymentStatus {
+        DeploymentStatus {
+            observed_generation: Some(observed),
+            replicas: Some(total),
+            updated_replicas: Some(updated),
+            ready_replicas: Some(available),
+            available_replicas: Some(available),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rollout_progress() {
+        let progress = rollout_progress(&deployment(2, 3, status(1, 3, 0, 3)));
+        assert_eq!(progress.state, RolloutState::Progressing);
+        assert!(progress.message.unwrap().contains("observe"));
+
+        let progress = rollout_progress(&deployment(2, 3, status(2, 4, 1, 3)));
+        assert_eq!(progress.state, RolloutState::Progressing);
+        assert_eq!(progress.message.as_deref(), Some("1 of 3 replicas updated"));
+        assert_eq!(progress.updated_replicas, 1);
+
+        let progress = rollout_progress(&deployment(2, 3, status(2, 4, 3, 3)));
+        assert_eq!(
+            progress.message.as_deref(),
+            Some("1 old replicas pending termination")
+        );
+
+        let progress = rollout_progress(&deployment(2, 3, status(2, 3, 3, 2)));
+        assert_eq!(
+            progress.message.as_deref(),
+            Some("2 of 3 updated replicas available")
+        );
+
+        let progress = rollout_progress(&deployment(2, 3, status(2, 3, 3, 3)));
+        assert_eq!(progress.state, RolloutState::Complete);
+        assert_eq!(progress.message, None);
+
+        let mut stuck = status(2, 4, 1, 3);
+        stuck.conditions = Some(vec![DeploymentCondition {
+            type_: "Progressing".to_string(),
+            status: "False".to_string(),
+            reason: Some("ProgressDeadlineExceeded".to_string()),
+            ..Default::default()
+        }]);
+        let progress = rollout_progress(&deployment(2, 3, stuck));
+        assert_eq!(progress.state, RolloutState::Failed);
+    }
+
+    #[test]
+    fn test_changed_ignores_timestamp() {
+        let progress = rollout_progress(&deployment(1, 2, status(1, 2, 1, 1)));
+        let mut later = progress.clone();
+        later.last_updated = progress.last_updated + chrono::Duration::seconds(5);
+        assert!(!changed(&progress, &later));
+
+        later.updated_replicas = 2;
+        assert!(changed(&progress, &later));
+    }
+}
@@ -0,0 +1,151 @@
+//! Shared informer caches.
+//!
+//! Each workload controller registers the reflector store its watcher keeps up to date.
+//! Webhook processors, registry polling and the resource gauges read from these stores
+//! instead of listing from the API server on every event. Until a store has synced (at
+//! startup, after a watch restart, or with `HEADWIND_CONTROLLERS_ENABLED=false`) reads fall
+//! back to a LIST.
+
+use crate::config::namespaces;
+use crate::health::{self, Status};
+use crate::metrics::{INFORMER_CACHE_HITS, INFORMER_CACHE_MISSES};
+use futures::FutureExt;
+use k8s_openapi::NamespaceResourceScope;
+use kube::runtime::reflector::Store;
+use kube::{Client, Resource, ResourceExt};
+use serde::de::DeserializeOwned;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::RwLock;
+use tracing::debug;
+
+type AnyStore = Box<dyn Any + Send + Sync>;
+
+static STORES: once_cell::sync::Lazy<RwLock<HashMap<TypeId, AnyStore>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Make a controller's store available to other components. The controller is not ready
+/// until the store has synced.
+pub fn register<K>(store: Store<K>)
+where
+    K: Resource + Clone + Send + Sync + 'static,
+    K::DynamicType: Default + Eq + std::hash::Hash + Clone + Send + Sync,
+{
+    let synced = store.clone();
+    health::register(health_check_name::<K>(), move || {
+        match synced.wait_until_ready().now_or_never() {
+            Some(Ok(())) => Status::Ok,
+            _ => Status::NotReady("initial list not complete".to_string()),
+        }
+    });
+
+    if let Ok(mut stores) = STORES.write() {
+        stores.insert(TypeId::of::<K>(), Box::new(store));
+    }
+}
+
+/// Drop the store of a controller whose watch stream ended
+pub fn unregister<K>()
+where
+    K: Resource + 'static,
+    K::DynamicType: Default,
+{
+    health::set(
+        health_check_name::<K>(),
+        Status::NotReady("watch restarting".to_string()),
+    );
+
+    if let Ok(mut stores) = STORES.write() {
+        stores.remove(&TypeId::of::<K>());
+    }
+}
+
+fn health_check_name<K>() -> String
+where
+    K: Resource,
+    K::DynamicType: Default,
+{
+    format!("watch/{}", K::kind(&Default::default()))
+}
+
+/// The registered store for `K`, if it has synced
+fn ready_store<K>() -> Option<Store<K>>
+where
+    K: Resource + Clone + 'static,
+    K::DynamicType: Eq + std::hash::Hash + Clone,
+{
+    let store = STORES
+        .read()
+        .ok()?
+        .get(&TypeId::of::<K>())?
+        .downcast_ref::<Store<K>>()?
+        .clone();
+    matches!(store.wait_until_ready().now_or_never(), Some(Ok(()))).then_some(store)
+}
+
+/// All `K` in watched namespaces, ordered by namespace and name. Served from the controller's
+/// informer cache when available, otherwise listed from the API server.
+pub async fn list<K>(client: &Client) -> kube::Result<Vec<K>>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + Debug + 'static,
+    K::DynamicType: Default + Eq + std::hash::Hash + Clone,
+{
+    let Some(store) = ready_store::<K>() else {
+        INFORMER_CACHE_MISSES.inc();
+        debug!("No synced informer cache, listing from the API server");
+        return namespaces::list_watched(client).await;
+    };
+
+    INFORMER_CACHE_HITS.inc();
+    Ok(from_store(&store))
+}
+
+fn from_store<K>(store: &Store<K>) -> Vec<K>
+where
+    K: Resource + Clone + 'static,
+    K::DynamicType: Eq + std::hash::Hash + Clone,
+{
+    let mut resources: Vec<K> = store
+        .state()
+        .into_iter()
+        .filter(|r| namespaces::is_watched(&r.namespace().unwrap_or_default()))
+        .map(|r| (*r).clone())
+        .collect();
+    resources.sort_by_key(|r| (r.namespace(), r.name_any()));
+    resources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::Deployment;
+    use kube::runtime::reflector::store;
+    use kube::runtime::watcher::Event;
+
+    fn deployment(namespace: &str, name: &str) -> Deployment {
+        let mut deployment = Deployment::default();
+        deployment.metadata.namespace = Some(namespace.to_string());
+        deployment.metadata.name = Some(name.to_string());
+        deployment
+    }
+
+    #[test]
+    fn test_store_is_used_once_synced() {
+        let (reader, mut writer) = store::<Deployment>();
+        register(reader);
+        assert!(ready_store::<Deployment>().is_none());
+
+        writer.apply_watcher_event(&Event::Init);
+        writer.apply_watcher_event(&Event::InitApply(deployment("prod", "web")));
+        writer.apply_watcher_event(&Event::InitApply(deployment("default", "api")));
+        writer.apply_watcher_event(&Event::InitDone);
+
+        let store = ready_store::<Deployment>().expect("store should be ready");
+        let names: Vec<_> = from_store(&store).iter().map(|d| d.name_any()).collect();
+        assert_eq!(names, vec!["api", "web"]);
+
+        unregister::<Deployment>();
+        assert!(ready_store::<Deployment>().is_none());
+    }
+}
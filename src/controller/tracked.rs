@@ -0,0 +1,797 @@
+//! The workload kinds Headwind updates images of, behind one trait.
+//!
+//! Webhook event processing, registry polling, rollout ordering, approvals and the handling
+//! of a detected image update are written once against [`TrackedWorkload`]; each kind only
+//! says where its pod template is, when it has rolled out and how its images are patched.
+//! Supporting a new kind means implementing the trait and adding it to [`KINDS`] and the
+//! dispatching call sites.
+
+use crate::audit;
+use crate::controller::cronjob;
+use crate::controller::deployment::update_deployment_images_with_tracking;
+use crate::controller::workload::{
+    Submission, WorkloadImageUpdate, annotate_build_metadata, defer_update_request,
+    is_digest_change, map_policy_to_crd, rollout_restart, route_external_update, routes_externally,
+    split_image, submit_update_request,
+};
+use crate::metrics::UPDATES_SKIPPED_INTERVAL;
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
+use crate::models::{
+    ContainerUpdate, ResourcePolicy, TargetRef, UpdateMode, UpdateRequest, UpdateRequestSpec,
+    UpdateType, annotations, parse_min_tag_age, parse_update_group,
+};
+use crate::notifications::{self, DeploymentInfo};
+use crate::ordering;
+use crate::platforms::{self, Verdict};
+use crate::policy::{PolicyEngine, interval};
+use crate::polling;
+use crate::schedule;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use k8s_openapi::NamespaceResourceScope;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use k8s_openapi::api::batch::v1::CronJob;
+use k8s_openapi::api::core::v1::PodSpec;
+use kube::api::{Patch, PatchParams};
+use kube::{Api, Client, Resource, ResourceExt};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::future::Future;
+use tracing::{debug, info, info_span, instrument};
+
+/// Kinds implementing [`TrackedWorkload`]
+pub const KINDS: &[&str] = &["Deployment", "StatefulSet", "DaemonSet", "CronJob"];
+
+/// A new version of the image of one of a workload's containers, from a push event or polling
+#[derive(Debug, Clone, Copy)]
+pub struct DetectedImage<'a> {
+    pub container: &'a str,
+    /// Policy of the container, including its per-container overrides
+    pub policy: &'a ResourcePolicy,
+    /// Image without tag (`registry/repository`)
+    pub image_name: &'a str,
+    /// Full current image (`repo:tag`)
+    pub current_image: &'a str,
+    /// New tag, with the digest when the image is pinned (`tag@sha256:...`)
+    pub new_version: &'a str,
+    /// Full new image (`repo:tag`)
+    pub new_image: &'a str,
+}
+
+/// A workload kind whose container images Headwind tracks
+pub trait TrackedWorkload:
+    Resource<DynamicType = (), Scope = NamespaceResourceScope>
+    + Clone
+    + DeserializeOwned
+    + Debug
+    + Send
+    + Sync
+    + 'static
+{
+    /// Minimum update interval in seconds when `headwind.sh/min-update-interval` isn't set
+    const DEFAULT_MIN_UPDATE_INTERVAL: Option<u64> = None;
+
+    /// Pod template of the workload
+    fn pod_spec(&self) -> Option<&PodSpec>;
+
+    /// Whether the current template has rolled out, for `headwind.sh/rollout-after`
+    fn rolled_out(&self) -> bool;
+
+    /// Merge patch of the workload changing its pod template's spec by `pod_spec`
+    fn pod_spec_patch(pod_spec: Value) -> Value {
+        json!({ "spec": { "template": { "spec": pod_spec } } })
+    }
+
+    /// Set the images of containers, by name, in one patch so they roll out together.
+    /// `approved_by` is recorded with the update.
+    fn update_images(
+        client: &Client,
+        namespace: &str,
+        name: &str,
+        images: &[(String, String)],
+        approved_by: Option<&str>,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl TrackedWorkload for Deployment {
+    const DEFAULT_MIN_UPDATE_INTERVAL: Option<u64> = Some(300);
+
+    fn pod_spec(&self) -> Option<&PodSpec> {
+        self.spec.as_ref()?.template.spec.as_ref()
+    }
+
+    /// All replicas of the current template are updated and available
+    fn rolled_out(&self) -> bool {
+        let Some(status) = &self.status else {
+            return false;
+        };
+        let replicas = self.spec.as_ref().and_then(|s| s.replicas).unwrap_or(1);
+        status.observed_generation >= self.metadata.generation
+            && status.updated_replicas.unwrap_or(0) >= replicas
+            && status.available_replicas.unwrap_or(0) >= replicas
+            && status.replicas.unwrap_or(0) <= replicas
+    }
+
+    /// Deployments also record the update in their rollback history
+    async fn update_images(
+        client: &Client,
+        namespace: &str,
+        name: &str,
+        images: &[(String, String)],
+        approved_by: Option<&str>,
+    ) -> Result<()> {
+        update_deployment_images_with_tracking(
+            client.clone(),
+            namespace,
+            name,
+            images,
+            None,
+            approved_by.map(str::to_string),
+        )
+        .await
+    }
+}
+
+impl TrackedWorkload for StatefulSet {
+    fn pod_spec(&self) -> Option<&PodSpec> {
+        self.spec.as_ref()?.template.spec.as_ref()
+    }
+
+    fn rolled_out(&self) -> bool {
+        let Some(status) = &self.status else {
+            return false;
+        };
+        let replicas = self.spec.as_ref().and_then(|s| s.replicas).unwrap_or(1);
+        status.observed_generation >= self.metadata.generation
+            && status.updated_replicas.unwrap_or(0) >= replicas
+            && status.ready_replicas.unwrap_or(0) >= replicas
+            && (status.update_revision.is_none()
+                || status.current_revision == status.update_revision)
+    }
+
+    async fn update_images(
+        client: &Client,
+        namespace: &str,
+        name: &str,
+        images: &[(String, String)],
+        approved_by: Option<&str>,
+    ) -> Result<()> {
+        patch_images::<Self>(client, namespace, name, images, approved_by).await
+    }
+}
+
+impl TrackedWorkload for DaemonSet {
+    fn pod_spec(&self) -> Option<&PodSpec> {
+        self.spec.as_ref()?.template.spec.as_ref()
+    }
+
+    fn rolled_out(&self) -> bool {
+        let Some(status) = &self.status else {
+            return false;
+        };
+        status.observed_generation >= self.metadata.generation
+            && status.updated_number_scheduled.unwrap_or(0) >= status.desired_number_scheduled
+            && status.number_available.unwrap_or(0) >= status.desired_number_scheduled
+    }
+
+    async fn update_images(
+        client: &Client,
+        namespace: &str,
+        name: &str,
+        images: &[(String, String)],
+        approved_by: Option<&str>,
+    ) -> Result<()> {
+        patch_images::<Self>(client, namespace, name, images, approved_by).await
+    }
+}
+
+impl TrackedWorkload for CronJob {
+    /// CronJobs keep their pod template under spec.jobTemplate.spec.template
+    fn pod_spec(&self) -> Option<&PodSpec> {
+        cronjob::pod_spec(self)
+    }
+
+    /// Jobs only start on the next schedule, so there is nothing to wait for
+    fn rolled_out(&self) -> bool {
+        true
+    }
+
+    /// Only the job template is patched; Jobs already created from it keep their image
+    fn pod_spec_patch(pod_spec: Value) -> Value {
+        json!({ "spec": { "jobTemplate": { "spec": { "template": { "spec": pod_spec } } } } })
+    }
+
+    async fn update_images(
+        client: &Client,
+        namespace: &str,
+        name: &str,
+        images: &[(String, String)],
+        approved_by: Option<&str>,
+    ) -> Result<()> {
+        patch_images::<Self>(client, namespace, name, images, approved_by).await
+    }
+}
+
+/// Pod template of the `W` named `name`
+pub async fn fetch_pod_spec<W: TrackedWorkload>(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+) -> kube::Result<Option<PodSpec>> {
+    let api: Api<W> = Api::namespaced(client.clone(), namespace);
+    Ok(api.get(name).await?.pod_spec().cloned())
+}
+
+/// Whether the `W` named `name` has rolled out. Missing workloads don't hold anything back.
+pub async fn fetch_rolled_out<W: TrackedWorkload>(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+) -> kube::Result<bool> {
+    let api: Api<W> = Api::namespaced(client.clone(), namespace);
+    Ok(api.get_opt(name).await?.is_none_or(|w| w.rolled_out()))
+}
+
+/// Set container images of the `W` named `name`, recording the update (and its approver) in
+/// the `headwind.sh/last-update` annotation
+pub async fn patch_images<W: TrackedWorkload>(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    images: &[(String, String)],
+    approved_by: Option<&str>,
+) -> Result<()> {
+    let api: Api<W> = Api::namespaced(client.clone(), namespace);
+    let kind = W::kind(&());
+
+    let now = Utc::now().to_rfc3339();
+    let last_update = match approved_by {
+        Some(approver) => format!("{} (approved by {})", now, approver),
+        None => now,
+    };
+    let containers: Vec<_> = images
+        .iter()
+        .map(|(container_name, new_image)| json!({ "name": container_name, "image": new_image }))
+        .collect();
+    let mut patch = W::pod_spec_patch(json!({ "containers": containers }));
+    patch["metadata"] = json!({ "annotations": { annotations::LAST_UPDATE: last_update } });
+
+    for (container_name, new_image) in images {
+        info!(
+            "Updating {} {}/{} container {} to image {}",
+            kind, namespace, name, container_name, new_image
+        );
+    }
+    api.patch(
+        name,
+        &PatchParams::apply("headwind"),
+        &Patch::Strategic(patch),
+    )
+    .await?;
+    info!("Successfully updated {} {}/{}", kind, namespace, name);
+
+    if let Some((_, new_image)) = images.first() {
+        annotate_build_metadata(client, &kind, namespace, name, new_image).await;
+    }
+    Ok(())
+}
+
+/// Containers and new images an UpdateRequest for a `W` sets. Requests without
+/// `containerName`, created before it was recorded for every kind, update the container
+/// running the same repository.
+pub async fn request_images<W: TrackedWorkload>(
+    client: &Client,
+    spec: &UpdateRequestSpec,
+) -> Result<Vec<(String, String)>> {
+    if spec.container_name.is_some() {
+        return Ok(spec.container_images());
+    }
+
+    let target = &spec.target_ref;
+    let repository = repository(&spec.current_image);
+    let pod_spec = fetch_pod_spec::<W>(client, &target.namespace, &target.name)
+        .await?
+        .ok_or_else(|| anyhow!("{} {} has no pod template", target.kind, target.name))?;
+    let container = pod_spec
+        .containers
+        .iter()
+        .find(|c| c.image.as_deref().map(self::repository) == Some(repository))
+        .ok_or_else(|| anyhow!("Container with image {} not found", repository))?;
+    Ok(vec![(container.name.clone(), spec.new_image.clone())])
+}
+
+/// Handle a new image version of one of a workload's containers: check the container's
+/// policy, then propose the update in an UpdateRequest or apply it. Containers of the same
+/// update group (`headwind.sh/update-group`) move to the new tag together.
+#[instrument(
+    skip_all,
+    fields(
+        kind = %W::kind(&()),
+        workload = %workload.name_any(),
+        container = %detected.container,
+        new_image = %detected.new_image
+    )
+)]
+pub async fn handle_image_update<W: TrackedWorkload>(
+    client: &Client,
+    policy_engine: &PolicyEngine,
+    workload: &W,
+    detected: &DetectedImage<'_>,
+) -> Result<()> {
+    let kind = W::kind(&());
+    let namespace = workload.namespace().unwrap_or_default();
+    let name = workload.name_any();
+    let annotations = workload.annotations();
+    let policy = detected.policy;
+    let current_tag = tag(detected.current_image);
+    let new_tag = detected.new_version;
+
+    // Evaluate policy to see if we should update
+    let should_update = info_span!("policy_evaluation", current = %current_tag, new = %new_tag)
+        .in_scope(|| policy_engine.should_update(policy, current_tag, new_tag))
+        .map_err(|e| anyhow!("Failed to evaluate policy: {}", e))?;
+    if !should_update {
+        debug!(
+            "Policy {:?} does not allow update from {} to {}",
+            policy.policy, current_tag, new_tag
+        );
+        return Ok(());
+    }
+
+    // Newly published tags wait until they are old enough
+    if let Some(min_age) = parse_min_tag_age(annotations)
+        && !polling::tag_old_enough(client, detected.new_image, &namespace, min_age).await
+    {
+        return Ok(());
+    }
+
+    let pod_spec = workload
+        .pod_spec()
+        .ok_or_else(|| anyhow!("{} {}/{} has no pod template", kind, namespace, name))?;
+
+    // Containers of the same update group move to the new tag together, in one update led by
+    // the first container of the group
+    let detected_update = ContainerUpdate {
+        container_name: detected.container.to_string(),
+        current_image: detected.current_image.to_string(),
+        new_image: detected.new_image.to_string(),
+    };
+    let Some(mut updates) = group_updates(
+        client,
+        annotations,
+        pod_spec,
+        &namespace,
+        &name,
+        detected_update,
+        new_tag,
+    )
+    .await
+    else {
+        return Ok(());
+    };
+    let primary = updates.remove(0);
+    let grouped = updates;
+    let container_name = primary.container_name.as_str();
+    let current_image = primary.current_image.as_str();
+    let new_image = primary.new_image.as_str();
+    let current_tag = tag(current_image);
+
+    // Check the new image is built for the platforms of the workload's nodes
+    let platform_warning =
+        match platforms::check(client, &namespace, annotations, pod_spec, new_image).await {
+            Verdict::Compatible => None,
+            Verdict::Skip(_) => return Ok(()),
+            Verdict::Flag(warning) => Some(warning),
+        };
+
+    info!(
+        "Update available for {} {}/{} container {}: {} -> {}",
+        kind, namespace, name, container_name, current_tag, new_tag
+    );
+
+    let resource = DeploymentInfo {
+        name: name.clone(),
+        namespace: namespace.clone(),
+        current_image: current_image.to_string(),
+        new_image: new_image.to_string(),
+        container: Some(container_name.to_string()),
+        resource_kind: Some(kind.to_string()),
+    };
+    audit::record(UpdateAuditSpec::new(AuditAction::Detected, &resource));
+    notifications::notify_update_detected(resource.clone());
+
+    // Updates within the minimum interval since the last update wait in a Deferred
+    // UpdateRequest
+    let deferred_until = deferred_until::<W>(annotations, Utc::now());
+    if let Some(until) = deferred_until {
+        info!(
+            "Deferring update for {} {}/{} container {}: {}",
+            kind,
+            namespace,
+            name,
+            container_name,
+            interval::describe(until, Utc::now())
+        );
+        UPDATES_SKIPPED_INTERVAL
+            .with_label_values(&[namespace.as_str(), kind.as_ref()])
+            .inc();
+        // Updates applied outside the cluster are not deferred, they are detected again once
+        // the interval has passed
+        if routes_externally(annotations) {
+            return Ok(());
+        }
+    }
+
+    // Workloads deployed through GitOps are updated in Git or through their ArgoCD Application
+    let update = WorkloadImageUpdate {
+        kind: &kind,
+        namespace: &namespace,
+        name: &name,
+        container_name: Some(container_name),
+        current_image,
+        new_image,
+        values_path: None,
+        policy,
+    };
+    if route_external_update(client, annotations, update).await? {
+        for member in &grouped {
+            let member_update = WorkloadImageUpdate {
+                container_name: Some(&member.container_name),
+                current_image: &member.current_image,
+                new_image: &member.new_image,
+                ..update
+            };
+            route_external_update(client, annotations, member_update).await?;
+        }
+        return Ok(());
+    }
+
+    // Check if approval is required (always for images flagged by the platform check)
+    if policy.require_approval || platform_warning.is_some() || deferred_until.is_some() {
+        create_update_request::<W>(
+            client,
+            &namespace,
+            &name,
+            &primary,
+            policy,
+            platform_warning.as_deref(),
+            grouped,
+            deferred_until,
+        )
+        .await?;
+    } else if schedule::defer_outside_window(annotations, &update)
+        || ordering::defer_until_ordered(client, annotations, &update).await
+    {
+        return Ok(());
+    } else {
+        info!(
+            "Auto-updating {} {}/{} container {} to {}",
+            kind, namespace, name, container_name, new_image
+        );
+        let images: Vec<(String, String)> = std::iter::once(primary.clone())
+            .chain(grouped)
+            .map(|u| (u.container_name, u.new_image))
+            .collect();
+        let result = async {
+            // Workloads in restart mode pick up a new digest of their tag by restarting
+            if policy.update_mode == UpdateMode::Restart
+                && is_digest_change(current_image, new_image)
+                && rollout_restart(client, &kind, &namespace, &name, Some(container_name), None)
+                    .await?
+            {
+                return Ok(());
+            }
+            W::update_images(client, &namespace, &name, &images, None).await
+        }
+        .await;
+        audit::record(UpdateAuditSpec::new(AuditAction::Applied, &resource).with_result(&result));
+        result?;
+
+        notifications::notify_update_completed(resource);
+    }
+
+    Ok(())
+}
+
+/// Until when an update of a `W` is held back by its minimum update interval, or None when it
+/// may be applied at `now`. Without a `headwind.sh/min-update-interval` annotation the kind's
+/// default applies; the parsed policy always carries the Deployment default, so it isn't used.
+fn deferred_until<W: TrackedWorkload>(
+    annotations: &BTreeMap<String, String>,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let min_interval = annotations
+        .get(annotations::MIN_UPDATE_INTERVAL)
+        .and_then(|v| v.parse().ok())
+        .or(W::DEFAULT_MIN_UPDATE_INTERVAL);
+    interval::deferred_until(annotations, min_interval, now)
+}
+
+/// Expand an update of one container to its update group (`headwind.sh/update-group`): the
+/// other containers of the group in the workload move to `new_tag` of their own repository.
+/// The updates are returned in group order, so the same container leads the group whichever
+/// container the update was detected for. Returns None while the new tag of a grouped image
+/// is not available yet.
+async fn group_updates(
+    client: &Client,
+    annotations: &BTreeMap<String, String>,
+    pod_spec: &PodSpec,
+    namespace: &str,
+    name: &str,
+    update: ContainerUpdate,
+    new_tag: &str,
+) -> Option<Vec<ContainerUpdate>> {
+    let group = parse_update_group(annotations, &update.container_name);
+
+    let mut updates = vec![update];
+    for container in &pod_spec.containers {
+        if container.name == updates[0].container_name || !group.contains(&container.name) {
+            continue;
+        }
+        let Some(current_image) = container.image.as_deref() else {
+            continue;
+        };
+        if tag(current_image) == new_tag {
+            continue;
+        }
+        let new_image = format!("{}:{}", repository(current_image), new_tag);
+        if let Err(e) = polling::resolve_digest(client, &new_image, namespace).await {
+            info!(
+                "Waiting for {} of update group container {} in {}/{}: {}",
+                new_image, container.name, namespace, name, e
+            );
+            return None;
+        }
+        updates.push(ContainerUpdate {
+            container_name: container.name.clone(),
+            current_image: current_image.to_string(),
+            new_image,
+        });
+    }
+
+    sort_by_group(&mut updates, &group);
+    Some(updates)
+}
+
+/// Order container updates by the position of their container in the update group
+fn sort_by_group(updates: &mut [ContainerUpdate], group: &[String]) {
+    updates.sort_by_key(|u| group.iter().position(|name| *name == u.container_name));
+}
+
+/// Create an UpdateRequest for an update of a `W`, Deferred until `deferred_until` when the
+/// minimum update interval holds it back
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(namespace = %namespace, workload = %name))]
+async fn create_update_request<W: TrackedWorkload>(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    update: &ContainerUpdate,
+    policy: &ResourcePolicy,
+    platform_warning: Option<&str>,
+    grouped_containers: Vec<ContainerUpdate>,
+    deferred_until: Option<DateTime<Utc>>,
+) -> Result<()> {
+    let update_requests: Api<UpdateRequest> = Api::namespaced(client.clone(), namespace);
+    let require_approval = policy.require_approval || platform_warning.is_some();
+    let kind = W::kind(&());
+
+    // A deterministic name, so repeated detections of the same update are deduplicated
+    let current_tag = tag(&update.current_image);
+    let new_tag = tag(&update.new_image);
+    let request_name = format!(
+        "{}-{}-{}",
+        name,
+        update.container_name,
+        new_tag.replace(['.', ':', '@'], "-").to_lowercase()
+    );
+
+    let mut update_request = UpdateRequest::new(
+        &request_name,
+        UpdateRequestSpec {
+            target_ref: TargetRef {
+                api_version: W::api_version(&()).to_string(),
+                kind: kind.to_string(),
+                name: name.to_string(),
+                namespace: namespace.to_string(),
+            },
+            update_type: UpdateType::Image,
+            container_name: Some(update.container_name.clone()),
+            image_parameter: None,
+            current_image: update.current_image.clone(),
+            new_image: update.new_image.clone(),
+            policy: map_policy_to_crd(&policy.policy),
+            reason: Some(platforms::with_warning(
+                format!(
+                    "New version available for {}: {} -> {}",
+                    repository(&update.new_image),
+                    current_tag,
+                    new_tag
+                ),
+                platform_warning,
+            )),
+            require_approval,
+            expires_at: policy.approval_expiry(),
+            required_approvals: policy.required_approvals,
+            git_write_back: None,
+            grouped_containers,
+            source: None,
+            resolved_version: None,
+        },
+    );
+    update_request.metadata.annotations =
+        Some(crate::telemetry::trace_annotations()).filter(|a| !a.is_empty());
+
+    let Submission::Created { release_notes, .. } =
+        submit_update_request(&update_requests, &update_request).await?
+    else {
+        return Ok(());
+    };
+    if let Some(until) = deferred_until {
+        defer_update_request(&update_requests, &update_request, until).await?;
+    }
+
+    // Notify about the new UpdateRequest (not about requests observed again)
+    let resource = DeploymentInfo {
+        name: name.to_string(),
+        namespace: namespace.to_string(),
+        current_image: update.current_image.clone(),
+        new_image: update.new_image.clone(),
+        container: Some(update.container_name.clone()),
+        resource_kind: Some(kind.to_string()),
+    };
+    notifications::notify_update_request_created(
+        resource,
+        format!("{:?}", policy.policy),
+        require_approval,
+        request_name,
+        release_notes,
+    );
+
+    Ok(())
+}
+
+/// Repository of an image, without tag and digest
+fn repository(image: &str) -> &str {
+    split_image(image)
+        .map(|(repository, _)| repository)
+        .unwrap_or_else(|| image.split('@').next().unwrap_or(image))
+}
+
+/// Tag of an image, with the digest of pinned images; `latest` for images without a tag
+fn tag(image: &str) -> &str {
+    split_image(image).map_or("latest", |(_, tag)| tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::{DeploymentSpec, DeploymentStatus};
+
+    #[test]
+    fn test_deployment_rolled_out() {
+        let deployment = |updated: i32, available: i32, total: i32| Deployment {
+            metadata: kube::api::ObjectMeta {
+                generation: Some(3),
+                ..Default::default()
+            },
+            spec: Some(DeploymentSpec {
+                replicas: Some(3),
+                ..Default::default()
+            }),
+            status: Some(DeploymentStatus {
+                observed_generation: Some(3),
+                updated_replicas: Some(updated),
+                available_replicas: Some(available),
+                replicas: Some(total),
+                ..Default::default()
+            }),
+        };
+
+        assert!(deployment(3, 3, 3).rolled_out());
+        assert!(!deployment(2, 3, 4).rolled_out());
+        assert!(!deployment(3, 2, 3).rolled_out());
+        assert!(!Deployment::default().rolled_out());
+        assert!(CronJob::default().rolled_out());
+    }
+
+    #[test]
+    fn test_pod_spec_patch() {
+        let pod_spec = json!({ "containers": [{ "name": "app", "image": "app:1.1.0" }] });
+
+        assert_eq!(
+            StatefulSet::pod_spec_patch(pod_spec.clone()),
+            json!({ "spec": { "template": { "spec": pod_spec.clone() } } })
+        );
+        assert_eq!(
+            CronJob::pod_spec_patch(pod_spec.clone()),
+            json!({ "spec": { "jobTemplate": { "spec": { "template": { "spec": pod_spec } } } } })
+        );
+    }
+
+    #[test]
+    fn test_update_within_interval_is_deferred() {
+        let now = Utc::now();
+        // Approved updates record their approver after the timestamp
+        let last_update = format!(
+            "{} (approved by admin@example.com)",
+            (now - chrono::Duration::seconds(60)).to_rfc3339()
+        );
+        let mut annotations = BTreeMap::from([
+            (annotations::POLICY.to_string(), "minor".to_string()),
+            (annotations::LAST_UPDATE.to_string(), last_update),
+        ]);
+
+        // Deployments default to 5 minutes, other kinds to no interval
+        assert_eq!(
+            deferred_until::<Deployment>(&annotations, now),
+            Some(now + chrono::Duration::seconds(240))
+        );
+        assert_eq!(deferred_until::<CronJob>(&annotations, now), None);
+
+        annotations.insert(
+            annotations::MIN_UPDATE_INTERVAL.to_string(),
+            "600".to_string(),
+        );
+        assert_eq!(
+            deferred_until::<CronJob>(&annotations, now),
+            Some(now + chrono::Duration::seconds(540))
+        );
+        assert_eq!(
+            deferred_until::<CronJob>(&annotations, now + chrono::Duration::seconds(540)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_update_group() {
+        let annotations = BTreeMap::from([(
+            annotations::UPDATE_GROUP.to_string(),
+            "app, sidecar; worker,worker-init".to_string(),
+        )]);
+
+        assert_eq!(
+            parse_update_group(&annotations, "sidecar"),
+            vec!["app", "sidecar"]
+        );
+        assert_eq!(
+            parse_update_group(&annotations, "worker"),
+            vec!["worker", "worker-init"]
+        );
+        assert!(parse_update_group(&annotations, "proxy").is_empty());
+        assert!(parse_update_group(&BTreeMap::new(), "app").is_empty());
+    }
+
+    #[test]
+    fn test_sort_by_group() {
+        let update = |name: &str| ContainerUpdate {
+            container_name: name.to_string(),
+            current_image: format!("{}:1.0.0", name),
+            new_image: format!("{}:1.1.0", name),
+        };
+        let group = vec!["app".to_string(), "sidecar".to_string()];
+        let mut updates = vec![update("sidecar"), update("app")];
+
+        sort_by_group(&mut updates, &group);
+        assert_eq!(updates[0].container_name, "app");
+        assert_eq!(updates[1].container_name, "sidecar");
+    }
+
+    #[test]
+    fn test_repository_and_tag() {
+        assert_eq!(repository("nginx:1.25.0"), "nginx");
+        assert_eq!(tag("nginx:1.25.0"), "1.25.0");
+        assert_eq!(
+            repository("registry.example.com:5000/image:v2.0"),
+            "registry.example.com:5000/image"
+        );
+        assert_eq!(tag("app:1.2.3@sha256:abc"), "1.2.3@sha256:abc");
+        assert_eq!(
+            repository("localhost:5000/myimage"),
+            "localhost:5000/myimage"
+        );
+        assert_eq!(tag("localhost:5000/myimage"), "latest");
+    }
+}
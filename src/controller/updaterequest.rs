@@ -0,0 +1,575 @@
+//! Expiry of UpdateRequests that wait too long for approval.
+//!
+//! A pending UpdateRequest moves to the `Expired` phase once its `expiresAt` has passed (set
+//! from the workload's `headwind.sh/approval-ttl`, 24 hours by default). When
+//! `HEADWIND_EXPIRED_REQUEST_RETENTION_SECONDS` is set, expired requests are deleted after
+//! that long.
+//!
+//! When change tickets are enabled (HEADWIND_CHANGE_TICKETS), a ticket is opened for every
+//! pending UpdateRequest and recorded in its status.
+//!
+//! When vulnerability scans are enabled (HEADWIND_VULNERABILITY_SCAN), the new image of every
+//! pending image UpdateRequest is scanned and the result recorded in its status. Requests over
+//! their target's `headwind.sh/max-critical-cves` are rejected.
+//!
+//! UpdateRequests Deferred by their target's minimum update interval are released once their
+//! `deferredUntil` has passed.
+
+use crate::approval;
+use crate::audit;
+use crate::config::namespaces;
+use crate::health;
+use crate::hooks;
+use crate::metrics::{
+    CHANGE_TICKET_ERRORS, CHANGE_TICKETS_OPENED, RECONCILE_ERRORS, UPDATE_REQUESTS_PRUNED,
+    UPDATES_EXPIRED, UPDATES_REJECTED, VULNERABILITY_SCANS,
+};
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
+use crate::models::crd::{UpdatePhase, UpdateRequest, UpdateRequestStatus, conditions};
+use crate::notifications::{self, DeploymentInfo};
+use crate::scanning::{self, ScanProgress};
+use crate::tickets::{self, ChangeTickets};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use kube::{
+    ResourceExt,
+    api::{Api, Patch, PatchParams},
+    client::Client,
+    runtime::{
+        controller::{Action, Controller},
+        watcher::Config,
+    },
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn};
+
+pub struct UpdateRequestController {
+    client: Client,
+    expired_retention: Option<Duration>,
+    tickets: Option<Arc<dyn ChangeTickets>>,
+}
+
+impl UpdateRequestController {
+    pub async fn new() -> Result<Self> {
+        let client = Client::try_default().await?;
+        let expired_retention = std::env::var("HEADWIND_EXPIRED_REQUEST_RETENTION_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs);
+
+        let tickets = match tickets::connect() {
+            Ok(tickets) => tickets.map(Arc::from),
+            Err(e) => {
+                error!("Change tickets disabled: {:#}", e);
+                None
+            },
+        };
+
+        Ok(Self {
+            client,
+            expired_retention,
+            tickets,
+        })
+    }
+
+    pub async fn run(self) {
+        info!("UpdateRequest controller starting...");
+
+        let mut backoff_seconds = 1;
+        const MAX_BACKOFF: u64 = 60;
+
+        loop {
+            let update_requests: Api<UpdateRequest> = namespaces::scoped_api(self.client.clone());
+
+            Controller::new(update_requests, Config::default())
+                .run(
+                    reconcile,
+                    error_policy,
+                    Arc::new(ControllerContext {
+                        client: self.client.clone(),
+                        expired_retention: self.expired_retention,
+                        tickets: self.tickets.clone(),
+                    }),
+                )
+                .inspect(|res| health::track_controller("UpdateRequest", res))
+                .for_each(|res| async move {
+                    match res {
+                        Ok((obj_ref, _action)) => {
+                            debug!(
+                                "Reconciled UpdateRequest: {}/{}",
+                                obj_ref.namespace.as_deref().unwrap_or("default"),
+                                obj_ref.name
+                            );
+                        },
+                        Err(e) => {
+                            error!("Reconciliation error: {}", e);
+                            RECONCILE_ERRORS.with_label_values(&["UpdateRequest"]).inc();
+                        },
+                    }
+                })
+                .await;
+
+            error!(
+                "UpdateRequest controller stream ended, restarting in {}s...",
+                backoff_seconds
+            );
+            tokio::time::sleep(Duration::from_secs(backoff_seconds)).await;
+            backoff_seconds = (backoff_seconds * 2).min(MAX_BACKOFF);
+        }
+    }
+}
+
+/// How often a running vulnerability scan is checked
+const SCAN_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+struct ControllerContext {
+    client: Client,
+    expired_retention: Option<Duration>,
+    tickets: Option<Arc<dyn ChangeTickets>>,
+}
+
+/// What to do with an UpdateRequest at a given time
+#[derive(Debug, PartialEq, Eq)]
+enum Step {
+    /// Pending past its expiry
+    Expire,
+    /// Expired for longer than the retention
+    Delete,
+    /// Deferred past its minimum update interval
+    Release,
+    /// Check again after this long
+    Wait(Duration),
+    /// Nothing to do until the request changes
+    Idle,
+}
+
+fn next_step(
+    update_request: &UpdateRequest,
+    expired_retention: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Step {
+    let status = update_request.status.as_ref();
+    match status.map(|s| &s.phase).unwrap_or(&UpdatePhase::Pending) {
+        UpdatePhase::Pending => match update_request.spec.expires_at {
+            Some(expires_at) if expires_at <= now => Step::Expire,
+            Some(expires_at) => Step::Wait(until(now, expires_at)),
+            None => Step::Idle,
+        },
+        // Deferred by the update gate (without deferredUntil) is released by the gate watcher
+        UpdatePhase::Deferred => match status.and_then(|s| s.deferred_until) {
+            Some(deferred_until) if deferred_until <= now => Step::Release,
+            Some(deferred_until) => Step::Wait(until(now, deferred_until)),
+            None => Step::Idle,
+        },
+        UpdatePhase::Expired => {
+            let Some(retention) = expired_retention else {
+                return Step::Idle;
+            };
+            let expired_at = status
+                .and_then(|s| s.last_updated)
+                .or(update_request.spec.expires_at)
+                .unwrap_or(now);
+            let delete_at =
+                expired_at + chrono::Duration::from_std(retention).unwrap_or(chrono::Duration::MAX);
+            if delete_at <= now {
+                Step::Delete
+            } else {
+                Step::Wait(until(now, delete_at))
+            }
+        },
+        _ => Step::Idle,
+    }
+}
+
+fn until(now: DateTime<Utc>, at: DateTime<Utc>) -> Duration {
+    // Requeue just after the deadline so it has passed when the request is checked again
+    (at - now).to_std().unwrap_or_default() + Duration::from_secs(1)
+}
+
+#[instrument(skip(update_request, ctx), fields(update_request = %update_request.name_any()))]
+async fn reconcile(
+    update_request: Arc<UpdateRequest>,
+    ctx: Arc<ControllerContext>,
+) -> Result<Action, kube::Error> {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let name = update_request.name_any();
+    if !namespaces::is_watched(&namespace) {
+        return Ok(Action::await_change());
+    }
+    let api: Api<UpdateRequest> = Api::namespaced(ctx.client.clone(), &namespace);
+
+    if let Some(tickets) = &ctx.tickets
+        && tickets::needs_ticket(&update_request)
+        && let Err(e) = open_ticket(&api, &update_request, tickets.as_ref()).await
+    {
+        CHANGE_TICKET_ERRORS.inc();
+        warn!(
+            "Failed to open change ticket for UpdateRequest {}/{}: {:#}",
+            namespace, name, e
+        );
+        return Ok(Action::requeue(Duration::from_secs(300)));
+    }
+
+    if scanning::enabled() && scanning::needs_scan(&update_request) {
+        match scan_image(&ctx.client, &api, &update_request).await {
+            // Recording the result changes the request, which reconciles it again
+            Ok(true) => return Ok(Action::await_change()),
+            Ok(false) => return Ok(Action::requeue(SCAN_POLL_INTERVAL)),
+            Err(e) => {
+                warn!(
+                    "Failed to scan the image of UpdateRequest {}/{}: {:#}",
+                    namespace, name, e
+                );
+                return Ok(Action::requeue(Duration::from_secs(60)));
+            },
+        }
+    }
+
+    match next_step(&update_request, ctx.expired_retention, Utc::now()) {
+        Step::Expire => {
+            expire(&api, &update_request).await?;
+            Ok(Action::await_change())
+        },
+        Step::Delete => {
+            info!("Deleting expired UpdateRequest {}/{}", namespace, name);
+            api.delete(&name, &Default::default()).await?;
+            UPDATE_REQUESTS_PRUNED
+                .with_label_values(&[namespace.as_str(), "Expired"])
+                .inc();
+            Ok(Action::await_change())
+        },
+        Step::Release => {
+            approval::release_deferred_update(&ctx.client, (*update_request).clone()).await;
+            Ok(Action::await_change())
+        },
+        Step::Wait(duration) => Ok(Action::requeue(duration)),
+        Step::Idle => Ok(Action::await_change()),
+    }
+}
+
+async fn open_ticket(
+    api: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+    tickets: &dyn ChangeTickets,
+) -> Result<()> {
+    let ticket = tickets.open(update_request).await?;
+    CHANGE_TICKETS_OPENED.inc();
+    info!(
+        "Opened change ticket {} for UpdateRequest {}",
+        ticket.id,
+        update_request.name_any()
+    );
+
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": { "changeTicket": ticket }
+    });
+    api.patch_status(
+        &update_request.name_any(),
+        &PatchParams::default(),
+        &Patch::Merge(status_patch),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Advance the vulnerability scan of an UpdateRequest's new image. Returns whether the scan
+/// finished and was recorded, rejecting the request when it found too many critical
+/// vulnerabilities.
+async fn scan_image(
+    client: &Client,
+    api: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+) -> Result<bool> {
+    let scan = match scanning::scan(client, update_request).await? {
+        ScanProgress::Running => return Ok(false),
+        ScanProgress::Finished(scan) => scan,
+    };
+    let name = update_request.name_any();
+    let spec = &update_request.spec;
+    let namespace = spec.target_ref.namespace.as_str();
+    info!(
+        "Scanned {} for UpdateRequest {}/{}: {}",
+        scan.image,
+        namespace,
+        name,
+        scanning::summary(&scan)
+    );
+
+    let target_annotations = hooks::target_annotations(client, &spec.target_ref).await;
+    let violation = scanning::violation(&scan, scanning::max_critical(&target_annotations));
+    let result = match (&violation, &scan.error) {
+        (Some(_), _) => "blocked",
+        (None, Some(_)) => "error",
+        (None, None) => "passed",
+    };
+    VULNERABILITY_SCANS
+        .with_label_values(&[namespace, result])
+        .inc();
+
+    let Some(reason) = violation else {
+        let status_patch = json!({
+            "apiVersion": "headwind.sh/v1alpha1",
+            "kind": "UpdateRequest",
+            "status": { "vulnerabilityScan": scan }
+        });
+        api.patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+            .await?;
+        return Ok(true);
+    };
+
+    warn!("Rejecting UpdateRequest {}/{}: {}", namespace, name, reason);
+    let mut status = UpdateRequestStatus {
+        phase: UpdatePhase::Rejected,
+        rejected_by: Some(scanning::REJECTED_BY.to_string()),
+        rejected_at: Some(Utc::now()),
+        message: Some(reason.clone()),
+        last_updated: Some(Utc::now()),
+        vulnerability_scan: Some(scan),
+        ..UpdateRequestStatus::with_conditions_of(update_request.status.as_ref())
+    };
+    status.set_condition(
+        conditions::APPROVED,
+        false,
+        "VulnerabilityPolicy",
+        Some(reason.clone()),
+    );
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": status
+    });
+    api.patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await?;
+
+    UPDATES_REJECTED
+        .with_label_values(&crate::metrics::update_request_labels(spec))
+        .inc();
+    audit::record(
+        UpdateAuditSpec::for_update_request(AuditAction::Rejected, spec)
+            .with_actor(Some(scanning::REJECTED_BY))
+            .with_source("vulnerability-scan")
+            .with_update_request(name.clone())
+            .with_message(reason.clone()),
+    );
+    notifications::notify_update_rejected(
+        DeploymentInfo {
+            name: spec.target_ref.name.clone(),
+            namespace: spec.target_ref.namespace.clone(),
+            current_image: spec.current_image.clone(),
+            new_image: spec.new_image.clone(),
+            container: spec.container_name.clone(),
+            resource_kind: Some(spec.target_ref.kind.clone()),
+        },
+        scanning::REJECTED_BY.to_string(),
+        reason,
+        name,
+    );
+
+    Ok(true)
+}
+
+#[instrument(name = "expire_update", skip_all, fields(update_request = %update_request.name_any()))]
+async fn expire(
+    api: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+) -> Result<(), kube::Error> {
+    crate::telemetry::continue_trace(update_request.annotations());
+    let name = update_request.name_any();
+    let spec = &update_request.spec;
+    info!(
+        "UpdateRequest {}/{} was not approved before {}, marking it expired",
+        spec.target_ref.namespace,
+        name,
+        spec.expires_at.map(|t| t.to_rfc3339()).unwrap_or_default()
+    );
+
+    let mut status = UpdateRequestStatus {
+        phase: UpdatePhase::Expired,
+        message: Some("Approval window expired".to_string()),
+        last_updated: Some(Utc::now()),
+        ..UpdateRequestStatus::with_conditions_of(update_request.status.as_ref())
+    };
+    status.set_condition(
+        conditions::APPROVED,
+        false,
+        "Expired",
+        status.message.clone(),
+    );
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": status
+    });
+    api.patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await?;
+
+    UPDATES_EXPIRED
+        .with_label_values(&crate::metrics::update_request_labels(spec))
+        .inc();
+    audit::record(
+        UpdateAuditSpec::for_update_request(AuditAction::Expired, spec)
+            .with_update_request(name.clone()),
+    );
+    notifications::notify_update_expired(
+        DeploymentInfo {
+            name: spec.target_ref.name.clone(),
+            namespace: spec.target_ref.namespace.clone(),
+            current_image: spec.current_image.clone(),
+            new_image: spec.new_image.clone(),
+            container: spec.container_name.clone(),
+            resource_kind: Some(spec.target_ref.kind.clone()),
+        },
+        name,
+    );
+
+    Ok(())
+}
+
+fn error_policy(
+    _object: Arc<UpdateRequest>,
+    _error: &kube::Error,
+    _ctx: Arc<ControllerContext>,
+) -> Action {
+    Action::requeue(Duration::from_secs(60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{TargetRef, UpdatePolicyType, UpdateRequestSpec, UpdateType};
+
+    fn update_request(
+        expires_at: Option<DateTime<Utc>>,
+        status: Option<UpdateRequestStatus>,
+    ) -> UpdateRequest {
+        let mut update_request = UpdateRequest::new(
+            "nginx-1-26-0",
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "nginx".to_string(),
+                    namespace: "default".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: Some("nginx".to_string()),
+                image_parameter: None,
+                current_image: "nginx:1.25.0".to_string(),
+                new_image: "nginx:1.26.0".to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        );
+        update_request.status = status;
+        update_request
+    }
+
+    fn status(phase: UpdatePhase, last_updated: DateTime<Utc>) -> Option<UpdateRequestStatus> {
+        Some(UpdateRequestStatus {
+            phase,
+            last_updated: Some(last_updated),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_pending_request_expires() {
+        let now = Utc::now();
+        let hour = chrono::Duration::hours(1);
+
+        let fresh = update_request(Some(now + hour), None);
+        assert_eq!(
+            next_step(&fresh, None, now),
+            Step::Wait(Duration::from_secs(3601))
+        );
+
+        let stale = update_request(Some(now - hour), None);
+        assert_eq!(next_step(&stale, None, now), Step::Expire);
+
+        let no_expiry = update_request(None, None);
+        assert_eq!(next_step(&no_expiry, None, now), Step::Idle);
+    }
+
+    #[test]
+    fn test_decided_requests_never_expire() {
+        let now = Utc::now();
+        let past = now - chrono::Duration::hours(1);
+
+        for phase in [
+            UpdatePhase::Approved,
+            UpdatePhase::Rejected,
+            UpdatePhase::Completed,
+            UpdatePhase::Failed,
+        ] {
+            let request = update_request(Some(past), status(phase, past));
+            assert_eq!(next_step(&request, None, now), Step::Idle);
+        }
+    }
+
+    #[test]
+    fn test_expired_request_cleanup() {
+        let now = Utc::now();
+        let expired_at = now - chrono::Duration::hours(2);
+        let request = update_request(Some(expired_at), status(UpdatePhase::Expired, expired_at));
+
+        // Kept without a retention
+        assert_eq!(next_step(&request, None, now), Step::Idle);
+
+        assert_eq!(
+            next_step(&request, Some(Duration::from_secs(3600)), now),
+            Step::Delete
+        );
+        assert_eq!(
+            next_step(&request, Some(Duration::from_secs(3 * 3600)), now),
+            Step::Wait(Duration::from_secs(3601))
+        );
+    }
+
+    #[test]
+    fn test_deferred_request_released_after_interval() {
+        let now = Utc::now();
+        let deferred = |deferred_until| {
+            update_request(
+                None,
+                Some(UpdateRequestStatus {
+                    phase: UpdatePhase::Deferred,
+                    deferred_until,
+                    ..Default::default()
+                }),
+            )
+        };
+
+        assert_eq!(
+            next_step(
+                &deferred(Some(now + chrono::Duration::minutes(5))),
+                None,
+                now
+            ),
+            Step::Wait(Duration::from_secs(301))
+        );
+        assert_eq!(
+            next_step(
+                &deferred(Some(now - chrono::Duration::seconds(1))),
+                None,
+                now
+            ),
+            Step::Release
+        );
+        // Deferred by the update gate
+        assert_eq!(next_step(&deferred(None), None, now), Step::Idle);
+    }
+}
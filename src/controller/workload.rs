@@ -0,0 +1,849 @@
+use crate::config::cluster;
+use crate::controller::{argocd, kustomization};
+use crate::gitops;
+use crate::models::crd::{
+    ReleaseNotes, UpdatePhase, UpdatePolicyType, UpdateRequest, UpdateRequestSpec,
+    UpdateRequestStatus, UpdateType, conditions,
+};
+use crate::models::{
+    ResourcePolicy, UpdateMode, UpdatePolicy, annotations, parse_dry_run, parse_update_mode,
+};
+use crate::notifications::{self, DeploymentInfo};
+use crate::policy::interval;
+use crate::policy::split_digest;
+use crate::releasenotes;
+use crate::webhook::provenance;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use kube::api::{
+    ApiResource, DynamicObject, GroupVersionKind, ListParams, Patch, PatchParams, PostParams,
+};
+use kube::{Api, Client, ResourceExt};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::{debug, info, instrument, warn};
+
+/// OCI annotation (and image label) holding the version an image was built from
+const OCI_VERSION_ANNOTATION: &str = "org.opencontainers.image.version";
+
+/// Longest wait for the labels of a channel tag's image
+const CHANNEL_LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait for the metadata of a new UpdateRequest's image
+const METADATA_LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Image labels `headwind.sh/build-metadata: "true"` copies to the workload
+pub const BUILD_METADATA_LABELS: &[&str] = &[
+    "org.opencontainers.image.revision",
+    "org.opencontainers.image.source",
+    "org.opencontainers.image.url",
+    "org.opencontainers.image.version",
+    "org.opencontainers.image.created",
+];
+
+/// Pod template annotation `kubectl rollout restart` sets to replace a workload's pods
+const RESTARTED_AT_ANNOTATION: &str = "kubectl.kubernetes.io/restartedAt";
+
+/// Workload kinds `headwind.sh/update-mode: restart` applies to
+pub const RESTARTABLE_KINDS: &[&str] = &["Deployment", "StatefulSet", "DaemonSet"];
+
+/// An image update detected on a workload, after the policy and minimum update interval
+/// checks have passed. Used when the update is applied somewhere other than the live
+/// workload (an ArgoCD Application, a Flux Kustomization or a Git repository) or held back
+/// until a maintenance window opens.
+#[derive(Clone, Copy)]
+pub struct WorkloadImageUpdate<'a> {
+    pub kind: &'a str,
+    pub namespace: &'a str,
+    pub name: &'a str,
+    pub container_name: Option<&'a str>,
+    /// Full current image (`repo:tag`)
+    pub current_image: &'a str,
+    /// Full new image (`repo:tag`)
+    pub new_image: &'a str,
+    /// HelmRelease values path holding the image, for images pinned in values
+    pub values_path: Option<&'a str>,
+    pub policy: &'a ResourcePolicy,
+}
+
+/// Apply an image update outside the live workload when its annotations ask for it: a Git
+/// write-back takes precedence over an ArgoCD Application, which takes precedence over a
+/// Flux Kustomization. Returns false when the workload should be updated in-cluster as usual.
+/// Workloads in dry-run mode are never routed, so their UpdateRequests target the workload
+/// itself and are not applied when approved.
+pub async fn route_external_update(
+    client: &Client,
+    annotations: &BTreeMap<String, String>,
+    update: WorkloadImageUpdate<'_>,
+) -> Result<bool> {
+    if parse_dry_run(annotations) {
+        return Ok(false);
+    }
+    if let Some(target) = gitops::write_back_target(annotations) {
+        gitops::handle_workload_image_update(client, &target, update).await?;
+        return Ok(true);
+    }
+    if let Some(application) = argocd::managing_application(annotations) {
+        argocd::handle_workload_image_update(client, &application, update).await?;
+        return Ok(true);
+    }
+    if let Some(kustomization) = kustomization::managing_kustomization(annotations) {
+        kustomization::handle_workload_image_update(client, &kustomization, update).await?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Whether [`route_external_update`] applies updates of a workload with these annotations
+/// outside the cluster
+pub fn routes_externally(annotations: &BTreeMap<String, String>) -> bool {
+    !parse_dry_run(annotations)
+        && (gitops::write_back_target(annotations).is_some()
+            || argocd::managing_application(annotations).is_some()
+            || kustomization::managing_kustomization(annotations).is_some())
+}
+
+/// Create an UpdateRequest in `namespace`. The request is named after the target and the new
+/// version; repeated detections of the same version are deduplicated by
+/// [`submit_update_request`].
+#[instrument(
+    skip_all,
+    fields(kind = %spec.target_ref.kind, namespace = %namespace, target = %spec.target_ref.name)
+)]
+pub async fn create_update_request(
+    client: &Client,
+    namespace: &str,
+    spec: UpdateRequestSpec,
+) -> Result<String> {
+    let update_requests: Api<UpdateRequest> = Api::namespaced(client.clone(), namespace);
+
+    let new_version = split_image(&spec.new_image)
+        .map(|(_, version)| version)
+        .unwrap_or(&spec.new_image);
+    let request_name = format!(
+        "{}-{}",
+        spec.target_ref.name,
+        new_version
+            .replace([':', '.', '/', '@'], "-")
+            .to_lowercase()
+    );
+
+    let target = spec.target_ref.clone();
+    let deployment_info = DeploymentInfo {
+        name: target.name.clone(),
+        namespace: target.namespace.clone(),
+        current_image: spec.current_image.clone(),
+        new_image: spec.new_image.clone(),
+        container: spec.container_name.clone(),
+        resource_kind: Some(target.kind.clone()),
+    };
+    let policy = format!("{:?}", spec.policy);
+    let mut update_request = UpdateRequest::new(&request_name, spec);
+    update_request.metadata.annotations =
+        Some(crate::telemetry::trace_annotations()).filter(|a| !a.is_empty());
+
+    let (request_name, release_notes) =
+        match submit_update_request(&update_requests, &update_request).await? {
+            Submission::Created {
+                name,
+                release_notes,
+            } => (name, release_notes),
+            Submission::Observed(name) => return Ok(name),
+        };
+
+    notifications::notify_update_request_created(
+        deployment_info,
+        policy,
+        true,
+        request_name.clone(),
+        release_notes,
+    );
+
+    Ok(request_name)
+}
+
+/// Outcome of [`submit_update_request`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Submission {
+    /// The UpdateRequest was created (replacing a finished one with the same name), with the
+    /// release notes found for the new version
+    Created {
+        name: String,
+        release_notes: Option<ReleaseNotes>,
+    },
+    /// An open UpdateRequest for the same update already existed and was marked as observed
+    /// again
+    Observed(String),
+}
+
+/// Create `update_request` unless an open UpdateRequest for the same target, container and
+/// new version exists. Repeated webhook and polling events for a version then bump the open
+/// request's `observedCount` and `lastObservedAt` instead of creating duplicates. A finished
+/// request (Completed, Rejected or Failed) with the same name is replaced. Requests without
+/// `spec.source` get the provenance of the event being processed
+/// ([`crate::webhook::provenance`]), and requests are labelled with the cluster name when one
+/// is set.
+pub async fn submit_update_request(
+    api: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+) -> kube::Result<Submission> {
+    let name = update_request.name_any();
+    let namespace = update_request.namespace().unwrap_or_default();
+
+    let existing = api.get_opt(&name).await?;
+    let open = match &existing {
+        Some(existing) if is_open(existing) => Some(existing.clone()),
+        _ => api
+            .list(&ListParams::default())
+            .await?
+            .items
+            .into_iter()
+            .find(|candidate| is_open(candidate) && is_same_update(candidate, update_request)),
+    };
+
+    if let Some(open) = open {
+        let count = observe(api, &open).await?;
+        debug!(
+            "UpdateRequest {}/{} already open for {}, observed {} times",
+            namespace,
+            open.name_any(),
+            update_request.spec.new_image,
+            count
+        );
+        return Ok(Submission::Observed(open.name_any()));
+    }
+
+    if let Some(finished) = existing {
+        info!(
+            "Replacing finished UpdateRequest {}/{} ({:?})",
+            namespace,
+            name,
+            finished
+                .status
+                .map(|status| status.phase)
+                .unwrap_or_default()
+        );
+        api.delete(&name, &Default::default()).await?;
+    }
+
+    let mut update_request = update_request.clone();
+    if let Some(cluster) = cluster::name() {
+        update_request
+            .labels_mut()
+            .insert(cluster::CLUSTER_LABEL.to_string(), cluster.to_string());
+    }
+    if update_request.spec.source.is_none() {
+        update_request.spec.source = Some(provenance::current());
+    }
+    if update_request.spec.resolved_version.is_none() {
+        update_request.spec.resolved_version =
+            channel_version(&api.clone().into_client(), &update_request.spec).await;
+    }
+    api.create(&PostParams::default(), &update_request).await?;
+    let target = &update_request.spec.target_ref;
+    info!(
+        "Created UpdateRequest {} for {} {}/{}",
+        name, target.kind, target.namespace, target.name
+    );
+    attach_image_metadata(api, &update_request).await;
+    let release_notes = releasenotes::attach(api, &update_request).await;
+    Ok(Submission::Created {
+        name,
+        release_notes,
+    })
+}
+
+/// Move a newly created UpdateRequest to Deferred until `until`, when its target's minimum
+/// update interval (`headwind.sh/min-update-interval`) has passed. The UpdateRequest
+/// controller releases it then: to Pending when it still needs approval, otherwise it is
+/// applied.
+pub async fn defer_update_request(
+    api: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+    until: DateTime<Utc>,
+) -> kube::Result<()> {
+    let name = update_request.name_any();
+    let reason = interval::describe(until, Utc::now());
+    info!(
+        "Deferring UpdateRequest {}/{} until {}: {}",
+        update_request.namespace().unwrap_or_default(),
+        name,
+        until.to_rfc3339(),
+        reason
+    );
+
+    let mut status = UpdateRequestStatus {
+        phase: UpdatePhase::Deferred,
+        message: Some(format!("Deferred: {}", reason)),
+        last_updated: Some(Utc::now()),
+        waiting_for: Some(reason.clone()),
+        deferred_until: Some(until),
+        ..Default::default()
+    };
+    status.set_condition(
+        conditions::APPLIED,
+        false,
+        "MinUpdateInterval",
+        Some(reason),
+    );
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": status
+    });
+    api.patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await?;
+    Ok(())
+}
+
+/// Version the channel tag of an image update (`stable@sha256:...`) stands for, read from the
+/// image's `org.opencontainers.image.version`. Tags that are versions themselves, and images
+/// without the annotation, have none.
+async fn channel_version(client: &Client, spec: &UpdateRequestSpec) -> Option<String> {
+    if spec.update_type != UpdateType::Image {
+        return None;
+    }
+    let (_, version) = split_image(&spec.new_image)?;
+    let (tag, digest) = split_digest(version);
+    if digest.is_none() || is_version_tag(tag) {
+        return None;
+    }
+
+    let lookup = crate::polling::image_labels(client, &spec.new_image, &spec.target_ref.namespace);
+    match tokio::time::timeout(CHANNEL_LOOKUP_TIMEOUT, lookup).await {
+        Ok(Ok(mut labels)) => labels.remove(OCI_VERSION_ANNOTATION),
+        Ok(Err(e)) => {
+            debug!("Failed to read the version of {}: {:#}", spec.new_image, e);
+            None
+        },
+        Err(_) => {
+            debug!("Timed out reading the version of {}", spec.new_image);
+            None
+        },
+    }
+}
+
+/// Whether the new image's metadata is recorded on UpdateRequests (HEADWIND_IMAGE_METADATA,
+/// default: true)
+fn image_metadata_enabled() -> bool {
+    std::env::var("HEADWIND_IMAGE_METADATA")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true)
+}
+
+/// Record the creation time, size, labels and exposed ports of a new UpdateRequest's image in
+/// `status.imageMetadata`, so approvers see more than a tag. Failures leave the request
+/// without metadata.
+async fn attach_image_metadata(api: &Api<UpdateRequest>, update_request: &UpdateRequest) {
+    let spec = &update_request.spec;
+    if spec.update_type != UpdateType::Image || !image_metadata_enabled() {
+        return;
+    }
+    let name = update_request.name_any();
+    let client = api.clone().into_client();
+
+    let lookup =
+        crate::polling::image_metadata(&client, &spec.new_image, &spec.target_ref.namespace);
+    let metadata = match tokio::time::timeout(METADATA_LOOKUP_TIMEOUT, lookup).await {
+        Ok(Ok(metadata)) => metadata,
+        Ok(Err(e)) => {
+            debug!("Failed to read the metadata of {}: {:#}", spec.new_image, e);
+            return;
+        },
+        Err(_) => {
+            debug!("Timed out reading the metadata of {}", spec.new_image);
+            return;
+        },
+    };
+
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": { "imageMetadata": metadata }
+    });
+    if let Err(e) = api
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await
+    {
+        warn!(
+            "Failed to record image metadata on UpdateRequest {}: {}",
+            name, e
+        );
+    }
+}
+
+/// Whether an update only moves an image to another digest of the same tag
+/// (`app:latest` -> `app:latest@sha256:...`), which restart-mode workloads pick up by
+/// restarting rather than pinning the new digest
+pub fn is_digest_change(current_image: &str, new_image: &str) -> bool {
+    let (current, current_digest) = split_digest(current_image);
+    let (new, new_digest) = split_digest(new_image);
+    current == new && new_digest.is_some() && new_digest != current_digest
+}
+
+/// Restart a Deployment, StatefulSet or DaemonSet in `headwind.sh/update-mode: restart` the
+/// way `kubectl rollout restart` does, leaving its images as they are. Returns false, without
+/// touching the workload, when it updates its images instead.
+pub async fn rollout_restart(
+    client: &Client,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    container_name: Option<&str>,
+    approver: Option<&str>,
+) -> Result<bool> {
+    if !RESTARTABLE_KINDS.contains(&kind) {
+        return Ok(false);
+    }
+
+    let resource = ApiResource::from_gvk(&GroupVersionKind::gvk("apps", "v1", kind));
+    let api: Api<DynamicObject> = Api::namespaced_with(client.clone(), namespace, &resource);
+    let workload = api.get(name).await?;
+    if parse_update_mode(workload.annotations()) != UpdateMode::Restart {
+        return Ok(false);
+    }
+
+    // New pods only run the tag's new digest when the node pulls it again
+    let containers = workload.data["spec"]["template"]["spec"]["containers"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    for container in containers
+        .iter()
+        .filter(|c| container_name.is_none() || c["name"].as_str() == container_name)
+    {
+        if let Some(pull_policy) = container["imagePullPolicy"]
+            .as_str()
+            .filter(|policy| *policy != "Always")
+        {
+            warn!(
+                "{} {}/{} restarts for a new digest, but container {} pulls with imagePullPolicy {}",
+                kind,
+                namespace,
+                name,
+                container["name"].as_str().unwrap_or_default(),
+                pull_policy
+            );
+        }
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let last_update = match approver {
+        Some(approver) => format!("{} (approved by {})", now, approver),
+        None => now.clone(),
+    };
+    let patch = json!({
+        "metadata": {
+            "annotations": {
+                annotations::LAST_UPDATE: last_update
+            }
+        },
+        "spec": {
+            "template": {
+                "metadata": {
+                    "annotations": {
+                        RESTARTED_AT_ANNOTATION: now
+                    }
+                }
+            }
+        }
+    });
+    api.patch(name, &PatchParams::default(), &Patch::Merge(patch))
+        .await?;
+
+    info!("Restarted {} {}/{}", kind, namespace, name);
+    Ok(true)
+}
+
+/// Copy build metadata labels of `new_image` into the annotations of a Deployment,
+/// StatefulSet, DaemonSet or CronJob whose image was just updated, as asked for by its
+/// `headwind.sh/build-metadata` annotation. Labels the new image doesn't have are removed, so
+/// the annotations never describe a previous build. Failures are logged and don't fail the
+/// update.
+pub async fn annotate_build_metadata(
+    client: &Client,
+    kind: &str,
+    namespace: &str,
+    name: &str,
+    new_image: &str,
+) {
+    let Some((group, version)) = crate::audit::api_version(kind).split_once('/') else {
+        return;
+    };
+    let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(group, version, kind));
+    let api: Api<DynamicObject> = Api::namespaced_with(client.clone(), namespace, &resource);
+
+    let result = async {
+        let workload = api.get(name).await?;
+        let keys = build_metadata_keys(workload.annotations());
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let lookup = crate::polling::image_labels(client, new_image, namespace);
+        let labels = tokio::time::timeout(CHANNEL_LOOKUP_TIMEOUT, lookup)
+            .await
+            .map_err(|_| anyhow::anyhow!("timed out reading the labels of {}", new_image))??;
+        let patch = build_metadata_patch(&keys, &labels);
+        api.patch(name, &PatchParams::default(), &Patch::Merge(patch))
+            .await?;
+        debug!(
+            "Copied build metadata of {} to {} {}/{}",
+            new_image, kind, namespace, name
+        );
+        anyhow::Ok(())
+    };
+    if let Err(e) = result.await {
+        warn!(
+            "Failed to copy build metadata of {} to {} {}/{}: {:#}",
+            new_image, kind, namespace, name, e
+        );
+    }
+}
+
+/// Image labels to copy to a workload: none without `headwind.sh/build-metadata` or for
+/// `false`, [`BUILD_METADATA_LABELS`] for `true`, otherwise the comma-separated label names.
+/// Names that aren't valid annotation keys are skipped.
+pub fn build_metadata_keys(workload_annotations: &BTreeMap<String, String>) -> Vec<String> {
+    let Some(value) = workload_annotations.get(annotations::BUILD_METADATA) else {
+        return Vec::new();
+    };
+    match value.trim() {
+        "" | "false" => Vec::new(),
+        "true" => BUILD_METADATA_LABELS
+            .iter()
+            .map(|k| k.to_string())
+            .collect(),
+        keys => keys
+            .split(',')
+            .map(str::trim)
+            .filter(|key| is_annotation_key(key))
+            .map(str::to_string)
+            .collect(),
+    }
+}
+
+/// Merge patch setting the workload annotations `keys` to the image's labels, and removing
+/// those the image doesn't have
+fn build_metadata_patch(
+    keys: &[String],
+    labels: &std::collections::HashMap<String, String>,
+) -> serde_json::Value {
+    let values: serde_json::Map<_, _> = keys
+        .iter()
+        .map(|key| (key.clone(), json!(labels.get(key))))
+        .collect();
+    json!({ "metadata": { "annotations": values } })
+}
+
+/// Whether `key` is a valid annotation key: an optional DNS subdomain prefix and `/`, then up
+/// to 63 alphanumerics, `-`, `_` and `.`, beginning and ending with an alphanumeric
+fn is_annotation_key(key: &str) -> bool {
+    let (prefix, name) = match key.rsplit_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+    let valid_name = !name.is_empty()
+        && name.len() <= 63
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    let valid_prefix = prefix.is_none_or(|prefix| {
+        !prefix.is_empty()
+            && prefix.len() <= 253
+            && prefix.split('.').all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            })
+    });
+    valid_name && valid_prefix
+}
+
+/// Whether a tag names a version (`1.4.2`, `v2`) rather than a channel (`stable`, `latest`)
+fn is_version_tag(tag: &str) -> bool {
+    tag.trim_start_matches('v')
+        .starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Whether an UpdateRequest still waits for a decision or is being applied
+fn is_open(update_request: &UpdateRequest) -> bool {
+    !update_request.status.as_ref().is_some_and(|status| {
+        matches!(
+            status.phase,
+            UpdatePhase::Completed | UpdatePhase::Rejected | UpdatePhase::Failed
+        )
+    })
+}
+
+/// Whether two UpdateRequests move the same target and container to the same version
+fn is_same_update(a: &UpdateRequest, b: &UpdateRequest) -> bool {
+    a.spec.target_ref.kind == b.spec.target_ref.kind
+        && a.spec.target_ref.name == b.spec.target_ref.name
+        && a.spec.target_ref.namespace == b.spec.target_ref.namespace
+        && a.spec.update_type == b.spec.update_type
+        && a.spec.container_name == b.spec.container_name
+        && a.spec.image_parameter == b.spec.image_parameter
+        && a.spec.new_image == b.spec.new_image
+}
+
+/// Record another detection of an open UpdateRequest. Returns how often it has been observed.
+async fn observe(api: &Api<UpdateRequest>, update_request: &UpdateRequest) -> kube::Result<u32> {
+    let count = observed_count(update_request) + 1;
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": {
+            "observedCount": count,
+            "lastObservedAt": Utc::now(),
+        }
+    });
+    api.patch_status(
+        &update_request.name_any(),
+        &PatchParams::default(),
+        &Patch::Merge(status_patch),
+    )
+    .await?;
+    Ok(count)
+}
+
+/// How often the update has been detected; a request that was never observed again counts once
+pub fn observed_count(update_request: &UpdateRequest) -> u32 {
+    update_request
+        .status
+        .as_ref()
+        .and_then(|status| status.observed_count)
+        .unwrap_or(1)
+}
+
+/// Split an image into its repository and version. The version is the tag, followed by the
+/// digest for pinned images: `repo:1.2.3@sha256:...` -> (`repo`, `1.2.3@sha256:...`).
+/// Returns None when the image has no tag.
+pub fn split_image(image: &str) -> Option<(&str, &str)> {
+    let reference = image.split('@').next().unwrap_or(image);
+    let (repository, _) = reference
+        .rsplit_once(':')
+        .filter(|(_, tag)| !tag.contains('/'))?;
+    Some((repository, &image[repository.len() + 1..]))
+}
+
+/// Map UpdatePolicy to UpdatePolicyType for the CRD
+pub fn map_policy_to_crd(policy: &UpdatePolicy) -> UpdatePolicyType {
+    match policy {
+        UpdatePolicy::Patch => UpdatePolicyType::Patch,
+        UpdatePolicy::Minor => UpdatePolicyType::Minor,
+        UpdatePolicy::Major => UpdatePolicyType::Major,
+        UpdatePolicy::Glob => UpdatePolicyType::Glob,
+        UpdatePolicy::Regex => UpdatePolicyType::Regex,
+        UpdatePolicy::Range => UpdatePolicyType::Range,
+        UpdatePolicy::Digest => UpdatePolicyType::Digest,
+        _ => UpdatePolicyType::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_version_tag() {
+        assert!(is_version_tag("1.4.2"));
+        assert!(is_version_tag("v2"));
+        assert!(!is_version_tag("stable"));
+        assert!(!is_version_tag("latest"));
+        assert!(!is_version_tag("edge"));
+    }
+
+    #[test]
+    fn test_is_digest_change() {
+        assert!(is_digest_change("app:latest", "app:latest@sha256:bbb"));
+        assert!(is_digest_change(
+            "app:latest@sha256:aaa",
+            "app:latest@sha256:bbb"
+        ));
+        assert!(!is_digest_change(
+            "app:latest@sha256:aaa",
+            "app:latest@sha256:aaa"
+        ));
+        assert!(!is_digest_change("app:latest", "app:stable@sha256:bbb"));
+        assert!(!is_digest_change("app:1.0.0", "app:1.1.0"));
+    }
+
+    #[test]
+    fn test_split_image() {
+        assert_eq!(split_image("nginx:1.25.0"), Some(("nginx", "1.25.0")));
+        assert_eq!(
+            split_image("localhost:5000/app:v1"),
+            Some(("localhost:5000/app", "v1"))
+        );
+        assert_eq!(
+            split_image("ghcr.io/org/app:1.2.3@sha256:abc"),
+            Some(("ghcr.io/org/app", "1.2.3@sha256:abc"))
+        );
+        assert_eq!(split_image("localhost:5000/app"), None);
+        assert_eq!(split_image("nginx@sha256:abc"), None);
+    }
+
+    #[test]
+    fn test_map_policy_to_crd() {
+        assert_eq!(
+            map_policy_to_crd(&UpdatePolicy::Major),
+            UpdatePolicyType::Major
+        );
+        assert_eq!(
+            map_policy_to_crd(&UpdatePolicy::Minor),
+            UpdatePolicyType::Minor
+        );
+        assert_eq!(
+            map_policy_to_crd(&UpdatePolicy::Patch),
+            UpdatePolicyType::Patch
+        );
+        assert_eq!(
+            map_policy_to_crd(&UpdatePolicy::Glob),
+            UpdatePolicyType::Glob
+        );
+        assert_eq!(
+            map_policy_to_crd(&UpdatePolicy::None),
+            UpdatePolicyType::None
+        );
+        assert_eq!(
+            map_policy_to_crd(&UpdatePolicy::All),
+            UpdatePolicyType::None
+        );
+        assert_eq!(
+            map_policy_to_crd(&UpdatePolicy::Force),
+            UpdatePolicyType::None
+        );
+    }
+
+    #[test]
+    fn test_build_metadata_keys() {
+        let keys = |value: &str| {
+            build_metadata_keys(&BTreeMap::from([(
+                annotations::BUILD_METADATA.to_string(),
+                value.to_string(),
+            )]))
+        };
+        assert!(build_metadata_keys(&BTreeMap::new()).is_empty());
+        assert!(keys("false").is_empty());
+        assert_eq!(keys("true").len(), BUILD_METADATA_LABELS.len());
+        assert_eq!(
+            keys(
+                "org.opencontainers.image.revision, com.example/build-url, Bad Key, ci.example.com/"
+            ),
+            vec![
+                "org.opencontainers.image.revision".to_string(),
+                "com.example/build-url".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_metadata_patch() {
+        let labels = std::collections::HashMap::from([(
+            "org.opencontainers.image.revision".to_string(),
+            "3f2a9c1".to_string(),
+        )]);
+        let keys = vec![
+            "org.opencontainers.image.revision".to_string(),
+            "org.opencontainers.image.source".to_string(),
+        ];
+        assert_eq!(
+            build_metadata_patch(&keys, &labels),
+            json!({
+                "metadata": {
+                    "annotations": {
+                        "org.opencontainers.image.revision": "3f2a9c1",
+                        "org.opencontainers.image.source": null
+                    }
+                }
+            })
+        );
+    }
+
+    fn update_request(name: &str, container: &str, new_image: &str) -> UpdateRequest {
+        use crate::models::crd::{TargetRef, UpdateType};
+
+        UpdateRequest::new(
+            name,
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "web".to_string(),
+                    namespace: "default".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: Some(container.to_string()),
+                image_parameter: None,
+                current_image: "nginx:1.25.0".to_string(),
+                new_image: new_image.to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at: None,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        )
+    }
+
+    fn with_phase(mut update_request: UpdateRequest, phase: UpdatePhase) -> UpdateRequest {
+        update_request.status = Some(crate::models::crd::UpdateRequestStatus {
+            phase,
+            ..Default::default()
+        });
+        update_request
+    }
+
+    #[test]
+    fn test_same_update_ignores_request_name() {
+        let new = update_request("web-nginx-1-26-0", "nginx", "nginx:1.26.0");
+
+        assert!(is_same_update(
+            &update_request("web-1-26-0", "nginx", "nginx:1.26.0"),
+            &new
+        ));
+        assert!(!is_same_update(
+            &update_request("web-1-27-0", "nginx", "nginx:1.27.0"),
+            &new
+        ));
+        assert!(!is_same_update(
+            &update_request("web-sidecar-1-26-0", "sidecar", "nginx:1.26.0"),
+            &new
+        ));
+    }
+
+    #[test]
+    fn test_only_finished_requests_are_replaced() {
+        let request = || update_request("web-1-26-0", "nginx", "nginx:1.26.0");
+
+        assert!(is_open(&request()));
+        assert!(is_open(&with_phase(request(), UpdatePhase::Pending)));
+        assert!(is_open(&with_phase(request(), UpdatePhase::Approved)));
+        assert!(is_open(&with_phase(request(), UpdatePhase::Expired)));
+        assert!(!is_open(&with_phase(request(), UpdatePhase::Completed)));
+        assert!(!is_open(&with_phase(request(), UpdatePhase::Rejected)));
+        assert!(!is_open(&with_phase(request(), UpdatePhase::Failed)));
+    }
+
+    #[test]
+    fn test_observed_count_defaults_to_one() {
+        let mut request = update_request("web-1-26-0", "nginx", "nginx:1.26.0");
+        assert_eq!(observed_count(&request), 1);
+
+        request.status = Some(crate::models::crd::UpdateRequestStatus {
+            observed_count: Some(4),
+            ..Default::default()
+        });
+        assert_eq!(observed_count(&request), 4);
+    }
+}
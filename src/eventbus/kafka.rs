@@ -0,0 +1,80 @@
+//! Kafka event bus (cargo feature `kafka`).
+//!
+//! Each queue is a topic named like its subject. Replicas join one consumer group
+//! (`HEADWIND_EVENT_BUS_GROUP`), so each message is delivered to one of them; offsets are
+//! committed after a message is handed over, and a new group starts at the earliest offset.
+
+use super::EventBusConfig;
+use anyhow::{Context, Result, anyhow};
+use rdkafka::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::Message;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Longest wait for the brokers to acknowledge a message
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct KafkaBus {
+    brokers: String,
+    group: String,
+    producer: FutureProducer,
+}
+
+impl KafkaBus {
+    pub fn connect(config: &EventBusConfig) -> Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &config.url)
+            .set(
+                "message.timeout.ms",
+                PUBLISH_TIMEOUT.as_millis().to_string(),
+            )
+            .create()
+            .context("Failed to create Kafka producer")?;
+
+        Ok(Self {
+            brokers: config.url.clone(),
+            group: config.group.clone(),
+            producer,
+        })
+    }
+
+    pub async fn publish(&self, topic: &str, payload: Vec<u8>) -> Result<()> {
+        self.producer
+            .send(
+                FutureRecord::<(), _>::to(topic).payload(&payload),
+                PUBLISH_TIMEOUT,
+            )
+            .await
+            .map_err(|(e, _)| anyhow!("Failed to publish to {}: {}", topic, e))?;
+        Ok(())
+    }
+
+    pub async fn consume(&self, topic: &str, tx: &mpsc::Sender<Vec<u8>>) -> Result<()> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .set("group.id", &self.group)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .context("Failed to create Kafka consumer")?;
+        consumer
+            .subscribe(&[topic])
+            .with_context(|| format!("Failed to subscribe to {}", topic))?;
+
+        loop {
+            let message = consumer
+                .recv()
+                .await
+                .with_context(|| format!("Failed to receive from {}", topic))?;
+            let payload = message.payload().unwrap_or_default().to_vec();
+            if tx.send(payload).await.is_err() {
+                return Ok(());
+            }
+            consumer
+                .commit_message(&message, CommitMode::Async)
+                .context("Failed to commit offset")?;
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! External event bus for push events.
+//!
+//! With `HEADWIND_EVENT_BUS=nats` (JetStream) or `kafka`, image and chart push events are also
+//! consumed from the bus, on `<prefix>.images` and `<prefix>.charts` (`HEADWIND_EVENT_BUS_PREFIX`,
+//! default `headwind.push`), as JSON [`ImagePushEvent`]s and [`ChartPushEvent`]s. External
+//! producers (CI pipelines, other clusters) can publish there instead of calling a webhook.
+//!
+//! `HEADWIND_EVENT_BUS_MODE` decides what happens to events Headwind receives itself:
+//!
+//! - `consume` (default): they stay on the in-process queues
+//! - `route`: they are published to the bus and processed when consumed from it. Replicas
+//!   share one consumer group (`HEADWIND_EVENT_BUS_GROUP`), so with several replicas behind the
+//!   webhook Service each event is processed once, by whichever replica consumes it. Events
+//!   that can't be published are processed locally.
+//!
+//! Messages are acknowledged as they are handed to the event processors, so events waiting on
+//! the bus survive restarts of the consuming replicas.
+
+#[cfg(feature = "kafka")]
+mod kafka;
+mod nats;
+
+use crate::metrics::EVENT_BUS_MESSAGES;
+use crate::models::crd::SourceEvent;
+use crate::models::webhook::{ChartPushEvent, ImagePushEvent};
+use crate::webhook::provenance;
+use crate::webhook::{ChartEventReceiver, ChartEventSender, EventReceiver, EventSender};
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// Delay before reconnecting after the bus failed
+const ERROR_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Bus implementation (`HEADWIND_EVENT_BUS`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Nats,
+    Kafka,
+}
+
+/// What happens to events received by this instance (`HEADWIND_EVENT_BUS_MODE`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Only consume from the bus; received events stay in-process
+    Consume,
+    /// Publish received events to the bus and process what is consumed from it
+    Route,
+}
+
+/// Queue of push events, each with its own subject or topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Queue {
+    Images,
+    Charts,
+}
+
+impl Queue {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Queue::Images => "images",
+            Queue::Charts => "charts",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventBusConfig {
+    pub backend: Backend,
+    pub mode: Mode,
+    /// NATS server URL or Kafka bootstrap servers
+    pub url: String,
+    /// Prefix of the subjects or topics, followed by the queue name
+    pub prefix: String,
+    /// JetStream durable consumer prefix or Kafka consumer group
+    pub group: String,
+    /// JetStream stream holding the subjects
+    pub stream: String,
+}
+
+impl EventBusConfig {
+    /// Configuration from the `HEADWIND_EVENT_BUS*` variables; `None` when no bus is configured
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let backend = match var("HEADWIND_EVENT_BUS")?.to_lowercase().as_str() {
+            "nats" => Backend::Nats,
+            "kafka" => Backend::Kafka,
+            other => {
+                warn!("Unknown event bus {:?}, event bus disabled", other);
+                return None;
+            },
+        };
+        let Some(url) = var("HEADWIND_EVENT_BUS_URL") else {
+            warn!(
+                "HEADWIND_EVENT_BUS is set but HEADWIND_EVENT_BUS_URL is not, event bus disabled"
+            );
+            return None;
+        };
+        let mode = match var("HEADWIND_EVENT_BUS_MODE").as_deref() {
+            Some("route") => Mode::Route,
+            _ => Mode::Consume,
+        };
+
+        Some(Self {
+            backend,
+            mode,
+            url,
+            prefix: var("HEADWIND_EVENT_BUS_PREFIX").unwrap_or_else(|| "headwind.push".to_string()),
+            group: var("HEADWIND_EVENT_BUS_GROUP").unwrap_or_else(|| "headwind".to_string()),
+            stream: var("HEADWIND_EVENT_BUS_STREAM").unwrap_or_else(|| "HEADWIND_PUSH".to_string()),
+        })
+    }
+
+    /// Subject or topic of `queue`
+    pub fn subject(&self, queue: Queue) -> String {
+        format!("{}.{}", self.prefix, queue.as_str())
+    }
+}
+
+/// Connection to the configured bus
+enum Bus {
+    Nats(nats::NatsBus),
+    #[cfg(feature = "kafka")]
+    Kafka(kafka::KafkaBus),
+}
+
+impl Bus {
+    async fn connect(config: &EventBusConfig) -> Result<Self> {
+        match config.backend {
+            Backend::Nats => Ok(Bus::Nats(nats::NatsBus::connect(config).await?)),
+            #[cfg(feature = "kafka")]
+            Backend::Kafka => Ok(Bus::Kafka(kafka::KafkaBus::connect(config)?)),
+            #[cfg(not(feature = "kafka"))]
+            Backend::Kafka => Err(anyhow!(
+                "Headwind was built without Kafka support (cargo feature \"kafka\")"
+            )),
+        }
+    }
+
+    async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()> {
+        match self {
+            Bus::Nats(bus) => bus.publish(subject, payload).await,
+            #[cfg(feature = "kafka")]
+            Bus::Kafka(bus) => bus.publish(subject, payload).await,
+        }
+    }
+
+    /// Send the payloads of `subject` to `tx` until the bus fails, acknowledging each once sent
+    async fn consume(&self, subject: &str, tx: &mpsc::Sender<Vec<u8>>) -> Result<()> {
+        match self {
+            Bus::Nats(bus) => bus.consume(subject, tx).await,
+            #[cfg(feature = "kafka")]
+            Bus::Kafka(bus) => bus.consume(subject, tx).await,
+        }
+    }
+}
+
+/// Attach the event queues to the event bus, if one is configured. Returns the receivers the
+/// event processors read from: in `route` mode those of the events consumed from the bus,
+/// otherwise `event_rx` and `chart_event_rx` themselves.
+pub fn attach(
+    capacity: usize,
+    event_tx: &EventSender,
+    chart_event_tx: &ChartEventSender,
+    event_rx: EventReceiver,
+    chart_event_rx: ChartEventReceiver,
+) -> (EventReceiver, ChartEventReceiver) {
+    let Some(config) = EventBusConfig::from_env() else {
+        return (event_rx, chart_event_rx);
+    };
+    if config.backend == Backend::Kafka && !cfg!(feature = "kafka") {
+        error!(
+            "HEADWIND_EVENT_BUS=kafka needs a build with the \"kafka\" feature, event bus disabled"
+        );
+        return (event_rx, chart_event_rx);
+    }
+    info!(
+        "Using {:?} event bus at {} in {:?} mode",
+        config.backend, config.url, config.mode
+    );
+    let config = Arc::new(config);
+
+    match config.mode {
+        Mode::Consume => {
+            spawn_consumer(config.clone(), Queue::Images, event_tx.clone());
+            spawn_consumer(config, Queue::Charts, chart_event_tx.clone());
+            (event_rx, chart_event_rx)
+        },
+        Mode::Route => {
+            let (bus_event_tx, bus_event_rx) = mpsc::channel(capacity);
+            let (bus_chart_event_tx, bus_chart_event_rx) = mpsc::channel(capacity);
+            spawn_forwarder(
+                config.clone(),
+                Queue::Images,
+                event_rx,
+                bus_event_tx.clone(),
+            );
+            spawn_forwarder(
+                config.clone(),
+                Queue::Charts,
+                chart_event_rx,
+                bus_chart_event_tx.clone(),
+            );
+            spawn_consumer(config.clone(), Queue::Images, bus_event_tx);
+            spawn_consumer(config, Queue::Charts, bus_chart_event_tx);
+            (bus_event_rx, bus_chart_event_rx)
+        },
+    }
+}
+
+/// Push events that can be carried over the bus
+trait BusEvent: Serialize + DeserializeOwned + Send + 'static {
+    fn digest(&self) -> Option<&str>;
+    fn source_mut(&mut self) -> &mut Option<SourceEvent>;
+}
+
+impl BusEvent for ImagePushEvent {
+    fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
+    fn source_mut(&mut self) -> &mut Option<SourceEvent> {
+        &mut self.source
+    }
+}
+
+impl BusEvent for ChartPushEvent {
+    fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
+    fn source_mut(&mut self) -> &mut Option<SourceEvent> {
+        &mut self.source
+    }
+}
+
+/// Publish the events of `rx` to the bus, handing them to `local` when that fails
+fn spawn_forwarder<T: BusEvent>(
+    config: Arc<EventBusConfig>,
+    queue: Queue,
+    mut rx: mpsc::Receiver<T>,
+    local: mpsc::Sender<T>,
+) {
+    tokio::spawn(async move {
+        let subject = config.subject(queue);
+        let mut bus = None;
+        while let Some(event) = rx.recv().await {
+            if bus.is_none() {
+                bus = Bus::connect(&config)
+                    .await
+                    .inspect_err(|e| warn!("Failed to connect to the event bus: {:#}", e))
+                    .ok();
+            }
+
+            let published = match (&bus, serde_json::to_vec(&event)) {
+                (Some(connected), Ok(payload)) => connected.publish(&subject, payload).await,
+                (None, _) => Err(anyhow!("not connected")),
+                (_, Err(e)) => Err(e.into()),
+            };
+            match published {
+                Ok(()) => {
+                    debug!("Published {} event to {}", queue.as_str(), subject);
+                    record(queue, "published", "success");
+                },
+                Err(e) => {
+                    warn!(
+                        "Failed to publish {} event to the event bus, processing it locally: {:#}",
+                        queue.as_str(),
+                        e
+                    );
+                    record(queue, "published", "error");
+                    bus = None;
+                    if local.send(event).await.is_err() {
+                        error!(
+                            "Failed to send push event: {} processor stopped",
+                            queue.as_str()
+                        );
+                        return;
+                    }
+                },
+            }
+        }
+    });
+}
+
+/// Consume the events of `queue` from the bus into `tx`, reconnecting after failures
+fn spawn_consumer<T: BusEvent>(config: Arc<EventBusConfig>, queue: Queue, tx: mpsc::Sender<T>) {
+    tokio::spawn(async move {
+        let subject = config.subject(queue);
+        let (raw_tx, mut raw_rx) = mpsc::channel::<Vec<u8>>(1);
+
+        // Decode payloads as they are consumed. The bus acknowledges a message once it is
+        // sent to `raw_tx`, which holds one, so it stays unacknowledged while the processor
+        // queue is full.
+        let decoder_subject = subject.clone();
+        tokio::spawn(async move {
+            while let Some(payload) = raw_rx.recv().await {
+                let Some(event) = decode::<T>(&payload, &decoder_subject) else {
+                    record(queue, "consumed", "invalid");
+                    continue;
+                };
+                record(queue, "consumed", "success");
+                if tx.send(event).await.is_err() {
+                    error!(
+                        "Failed to send push event: {} processor stopped",
+                        queue.as_str()
+                    );
+                    return;
+                }
+            }
+        });
+
+        loop {
+            let result = match Bus::connect(&config).await {
+                Ok(bus) => {
+                    info!("Consuming {} events from {}", queue.as_str(), subject);
+                    bus.consume(&subject, &raw_tx).await
+                },
+                Err(e) => Err(e),
+            };
+            if raw_tx.is_closed() {
+                return;
+            }
+            if let Err(e) = result {
+                error!("Event bus consumer of {} failed: {:#}", subject, e);
+                record(queue, "consumed", "error");
+            }
+            tokio::time::sleep(ERROR_BACKOFF).await;
+        }
+    });
+}
+
+/// Decode a consumed payload. Events from external producers get the subject as provenance.
+fn decode<T: BusEvent>(payload: &[u8], subject: &str) -> Option<T> {
+    match serde_json::from_slice::<T>(payload) {
+        Ok(mut event) => {
+            if event.source_mut().is_none() {
+                let source = provenance::eventbus(subject, event.digest());
+                *event.source_mut() = Some(source);
+            }
+            Some(event)
+        },
+        Err(e) => {
+            warn!("Ignoring invalid event from {}: {}", subject, e);
+            None
+        },
+    }
+}
+
+fn record(queue: Queue, operation: &str, result: &str) {
+    EVENT_BUS_MESSAGES
+        .with_label_values(&[queue.as_str(), operation, result])
+        .inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let payload =
+            br#"{"registry":"ghcr.io","repository":"acme/api","tag":"v2.3.0","digest":null}"#;
+        let event: ImagePushEvent = decode(payload, "headwind.push.images").unwrap();
+        assert_eq!(event.full_image(), "ghcr.io/acme/api:v2.3.0");
+        let source = event.source.unwrap();
+        assert_eq!(source.kind, "eventbus");
+        assert_eq!(source.origin.as_deref(), Some("headwind.push.images"));
+
+        // Events routed through the bus keep their provenance
+        let routed = ChartPushEvent {
+            registry: "ghcr.io".to_string(),
+            repository: "acme/charts/api".to_string(),
+            version: "1.4.0".to_string(),
+            digest: None,
+            source: Some(SourceEvent {
+                kind: "webhook".to_string(),
+                origin: Some("ghcr".to_string()),
+                ..Default::default()
+            }),
+        };
+        let payload = serde_json::to_vec(&routed).unwrap();
+        let event: ChartPushEvent = decode(&payload, "headwind.push.charts").unwrap();
+        assert_eq!(event.source.unwrap().kind, "webhook");
+
+        assert!(decode::<ImagePushEvent>(b"{}", "headwind.push.images").is_none());
+    }
+}
@@ -0,0 +1,103 @@
+//! NATS JetStream event bus.
+//!
+//! The stream (`HEADWIND_EVENT_BUS_STREAM`) is created with the subjects `<prefix>.>` if it
+//! doesn't exist. Each queue is read through a durable pull consumer `<group>-<queue>` that all
+//! replicas share, so every message is delivered to one of them.
+
+use super::EventBusConfig;
+use anyhow::{Context, Result, anyhow};
+use async_nats::jetstream::{self, consumer, stream};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+
+pub struct NatsBus {
+    jetstream: jetstream::Context,
+    stream: stream::Stream,
+    group: String,
+}
+
+impl NatsBus {
+    pub async fn connect(config: &EventBusConfig) -> Result<Self> {
+        let client = async_nats::connect(&config.url)
+            .await
+            .with_context(|| format!("Failed to connect to NATS at {}", config.url))?;
+        let jetstream = jetstream::new(client);
+        let stream = jetstream
+            .get_or_create_stream(stream::Config {
+                name: config.stream.clone(),
+                subjects: vec![format!("{}.>", config.prefix)],
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to get JetStream stream {}: {}", config.stream, e))?;
+
+        Ok(Self {
+            jetstream,
+            stream,
+            group: config.group.clone(),
+        })
+    }
+
+    /// Publish and wait for JetStream to store the message
+    pub async fn publish(&self, subject: &str, payload: Vec<u8>) -> Result<()> {
+        self.jetstream
+            .publish(subject.to_string(), payload.into())
+            .await
+            .map_err(|e| anyhow!("Failed to publish to {}: {}", subject, e))?
+            .await
+            .map_err(|e| anyhow!("JetStream didn't acknowledge the message: {}", e))?;
+        Ok(())
+    }
+
+    pub async fn consume(&self, subject: &str, tx: &mpsc::Sender<Vec<u8>>) -> Result<()> {
+        let durable = durable_name(&self.group, subject);
+        let consumer = self
+            .stream
+            .get_or_create_consumer(
+                &durable,
+                consumer::pull::Config {
+                    durable_name: Some(durable.clone()),
+                    filter_subject: subject.to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| anyhow!("Failed to create JetStream consumer {}: {}", durable, e))?;
+
+        let mut messages = consumer
+            .messages()
+            .await
+            .map_err(|e| anyhow!("Failed to read from consumer {}: {}", durable, e))?;
+        while let Some(message) = messages.next().await {
+            let message = message.map_err(|e| anyhow!("Failed to receive message: {}", e))?;
+            if tx.send(message.payload.to_vec()).await.is_err() {
+                return Ok(());
+            }
+            message
+                .ack()
+                .await
+                .map_err(|e| anyhow!("Failed to acknowledge message: {}", e))?;
+        }
+        Err(anyhow!("JetStream consumer {} ended", durable))
+    }
+}
+
+/// Durable consumer name of `subject`; JetStream names can't contain dots
+fn durable_name(group: &str, subject: &str) -> String {
+    let queue = subject.rsplit('.').next().unwrap_or(subject);
+    format!("{}-{}", group, queue).replace(['.', '*', '>', ' '], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_durable_name() {
+        assert_eq!(
+            durable_name("headwind", "headwind.push.images"),
+            "headwind-images"
+        );
+        assert_eq!(durable_name("team.a", "ci.push.charts"), "team_a-charts");
+    }
+}
@@ -0,0 +1,222 @@
+//! Update gate: an external freeze check, such as an incident-management freeze flag, that
+//! Headwind asks before it applies an approved UpdateRequest.
+//!
+//! With HEADWIND_UPDATE_GATE_URL set, Headwind sends `GET` to the URL before rolling out an
+//! approved update. The update goes ahead on `200 OK`, unless the body is JSON with
+//! `"frozen": true`. Any other status, a frozen answer or an unreachable gate defers the
+//! update: the UpdateRequest moves to the Deferred phase with `status.waitingFor` set to the
+//! reason, and the gate watcher rolls it out once the gate opens again.
+
+use crate::config::namespaces;
+use crate::metrics::{UPDATE_GATE_CHECKS, UPDATES_DEFERRED_BY_GATE};
+use crate::models::crd::{UpdatePhase, UpdateRequest};
+use anyhow::{Context, Result};
+use kube::api::{Api, ListParams};
+use kube::{Client, ResourceExt};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// How often deferred UpdateRequests are checked against the gate
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long the gate may take when HEADWIND_UPDATE_GATE_TIMEOUT isn't set
+pub const DEFAULT_TIMEOUT_SECONDS: u64 = 5;
+
+/// Gate settings from the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateConfig {
+    /// HEADWIND_UPDATE_GATE_URL
+    pub url: String,
+    /// Bearer token sent to the gate (HEADWIND_UPDATE_GATE_TOKEN)
+    pub token: Option<String>,
+    /// HEADWIND_UPDATE_GATE_TIMEOUT, seconds
+    pub timeout: Duration,
+}
+
+impl GateConfig {
+    /// The configured gate, or None when updates aren't gated
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("HEADWIND_UPDATE_GATE_URL")
+            .ok()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())?;
+        Some(Self {
+            url,
+            token: std::env::var("HEADWIND_UPDATE_GATE_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty()),
+            timeout: Duration::from_secs(
+                std::env::var("HEADWIND_UPDATE_GATE_TIMEOUT")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|&secs| secs > 0)
+                    .unwrap_or(DEFAULT_TIMEOUT_SECONDS),
+            ),
+        })
+    }
+}
+
+/// Answer of the gate. Fields other than these are ignored.
+#[derive(Debug, Default, Deserialize)]
+struct GateAnswer {
+    #[serde(default)]
+    frozen: bool,
+    /// Shown as the reason of the deferral, e.g. the incident
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Ask the gate before applying an approved update. Returns why updates are frozen; Ok when
+/// no gate is configured or it is open.
+pub async fn check_gate() -> std::result::Result<(), String> {
+    let Some(config) = GateConfig::from_env() else {
+        return Ok(());
+    };
+
+    let (result, label) = match query(&config).await {
+        Ok((status, body)) => {
+            let result = evaluate(status, &body);
+            let label = if result.is_ok() { "open" } else { "frozen" };
+            (result, label)
+        },
+        Err(e) => (
+            Err(format!("update gate unavailable: {:#}", e)),
+            "unavailable",
+        ),
+    };
+    UPDATE_GATE_CHECKS.with_label_values(&[label]).inc();
+    result
+}
+
+async fn query(config: &GateConfig) -> Result<(u16, String)> {
+    let http = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .build()
+        .context("Failed to create HTTP client")?;
+    let mut request = http.get(&config.url);
+    if let Some(token) = &config.token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("GET {} failed", config.url))?;
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    Ok((status, body))
+}
+
+/// Decide from the gate's answer. Bodies that aren't JSON count as open on 200.
+fn evaluate(status: u16, body: &str) -> std::result::Result<(), String> {
+    if status != 200 {
+        let detail = body.trim().chars().take(200).collect::<String>();
+        return Err(if detail.is_empty() {
+            format!("update gate answered HTTP {}", status)
+        } else {
+            format!("update gate answered HTTP {}: {}", status, detail)
+        });
+    }
+
+    let answer: GateAnswer = serde_json::from_str(body).unwrap_or_default();
+    if !answer.frozen {
+        return Ok(());
+    }
+    Err(match answer.reason.filter(|r| !r.trim().is_empty()) {
+        Some(reason) => format!("updates are frozen: {}", reason.trim()),
+        None => "updates are frozen".to_string(),
+    })
+}
+
+/// Count an UpdateRequest deferred by the gate
+pub fn record_deferral(update_request: &UpdateRequest) {
+    let target = &update_request.spec.target_ref;
+    UPDATES_DEFERRED_BY_GATE
+        .with_label_values(&[target.namespace.as_str(), target.kind.as_str()])
+        .inc();
+}
+
+/// Start the loop that rolls out deferred UpdateRequests once the gate opens
+pub fn start_gate_watcher(client: Client) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Update gate watcher started");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = release_deferred_requests(&client).await {
+                warn!("Failed to check deferred UpdateRequests: {}", e);
+            }
+        }
+    })
+}
+
+async fn release_deferred_requests(client: &Client) -> Result<()> {
+    let update_requests: Api<UpdateRequest> = Api::all(client.clone());
+    let deferred: Vec<UpdateRequest> = update_requests
+        .list(&ListParams::default())
+        .await?
+        .items
+        .into_iter()
+        // Requests waiting for their minimum update interval are released by the
+        // UpdateRequest controller
+        .filter(|ur| {
+            ur.status
+                .as_ref()
+                .is_some_and(|s| s.phase == UpdatePhase::Deferred && s.deferred_until.is_none())
+        })
+        .filter(|ur| namespaces::is_watched(&ur.namespace().unwrap_or_default()))
+        .collect();
+    if deferred.is_empty() {
+        return Ok(());
+    }
+
+    // Without a gate (e.g. HEADWIND_UPDATE_GATE_URL was removed) the check passes and
+    // deferred requests are released
+    if let Err(reason) = check_gate().await {
+        debug!(
+            "{} deferred UpdateRequest(s) still held: {}",
+            deferred.len(),
+            reason
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Update gate open, releasing {} deferred UpdateRequest(s)",
+        deferred.len()
+    );
+    for update_request in deferred {
+        crate::approval::release_deferred_update(client, update_request).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        assert!(evaluate(200, "").is_ok());
+        assert!(evaluate(200, "OK").is_ok());
+        assert!(evaluate(200, r#"{"frozen": false, "incident": null}"#).is_ok());
+
+        assert_eq!(
+            evaluate(200, r#"{"frozen": true, "reason": "INC-1234 in progress"}"#),
+            Err("updates are frozen: INC-1234 in progress".to_string())
+        );
+        assert_eq!(
+            evaluate(200, r#"{"frozen": true}"#),
+            Err("updates are frozen".to_string())
+        );
+        assert_eq!(
+            evaluate(503, ""),
+            Err("update gate answered HTTP 503".to_string())
+        );
+        assert_eq!(
+            evaluate(423, "change freeze"),
+            Err("update gate answered HTTP 423: change freeze".to_string())
+        );
+    }
+}
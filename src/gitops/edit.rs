@@ -0,0 +1,431 @@
+//! Line-based YAML edits. Files are edited in place rather than re-serialized so comments,
+//! ordering and formatting in the repository are preserved.
+
+use crate::models::crd::{GitFileFormat, GitWriteBack};
+use anyhow::{Result, anyhow};
+
+/// Default dotted key of the image tag in a Helm values file
+pub const DEFAULT_VALUE_PATH: &str = "image.tag";
+
+/// Apply `new_image` to a file according to the write-back format
+pub fn apply(target: &GitWriteBack, content: &str, new_image: &str) -> Result<String> {
+    let (repository, tag) = split_image(new_image);
+
+    match target.format {
+        GitFileFormat::Manifest => set_manifest_image(content, repository, new_image),
+        GitFileFormat::Kustomize => Ok(set_kustomize_tag(content, repository, tag)),
+        GitFileFormat::Helm => set_helm_value(
+            content,
+            target.value_path.as_deref().unwrap_or(DEFAULT_VALUE_PATH),
+            tag,
+        ),
+    }
+}
+
+/// Split `repo:tag` into its repository and tag, treating a registry port as part of the repository.
+/// The tag of a pinned image keeps its digest (`1.2.3@sha256:...`).
+pub fn split_image(image: &str) -> (&str, &str) {
+    crate::controller::workload::split_image(image).unwrap_or((image, "latest"))
+}
+
+/// Replace every `image:` field running `repository` with `new_image`
+pub fn set_manifest_image(content: &str, repository: &str, new_image: &str) -> Result<String> {
+    let mut lines = split_lines(content);
+    let mut found = false;
+
+    for line in lines.iter_mut() {
+        let Some(key) = parse_key(line) else {
+            continue;
+        };
+        if key.name != "image" {
+            continue;
+        }
+        let Some(scalar) = parse_scalar(&line[key.value_offset..]) else {
+            continue;
+        };
+        if split_image(scalar.value).0 != repository {
+            continue;
+        }
+
+        found = true;
+        *line = replace_scalar(line, key.value_offset, &scalar, new_image);
+    }
+
+    if !found {
+        return Err(anyhow!("No image field for {} found", repository));
+    }
+    Ok(join_lines(&lines, content))
+}
+
+/// Set `newTag` on the kustomization `images:` entry for `repository`, adding the entry
+/// (and the `images:` list) when missing
+pub fn set_kustomize_tag(content: &str, repository: &str, tag: &str) -> String {
+    let mut lines = split_lines(content);
+
+    let images = lines.iter().position(|line| {
+        parse_key(line).is_some_and(|key| {
+            key.indent == 0
+                && key.name == "images"
+                && parse_scalar(&line[key.value_offset..]).is_none()
+        })
+    });
+
+    let Some(images) = images else {
+        lines.push("images:".to_string());
+        lines.push(format!("- name: {}", repository));
+        lines.push(format!("  newTag: {}", render(tag, Some('"'))));
+        return join_lines(&lines, content) + if content.ends_with('\n') { "" } else { "\n" };
+    };
+
+    // Collect the list items: (first line, last line) of each `- ` entry
+    let mut items: Vec<(usize, usize)> = Vec::new();
+    let mut dash_indent = None;
+    for (i, line) in lines.iter().enumerate().skip(images + 1) {
+        if is_blank(line) {
+            continue;
+        }
+        let indent = indent_of(line);
+        let is_dash = line[indent..].starts_with("- ");
+
+        match dash_indent {
+            None if is_dash => {
+                dash_indent = Some(indent);
+                items.push((i, i));
+            },
+            Some(d) if is_dash && indent == d => items.push((i, i)),
+            Some(d) if indent > d => {
+                if let Some(item) = items.last_mut() {
+                    item.1 = i;
+                }
+            },
+            _ => break,
+        }
+    }
+    let dash_indent = dash_indent.unwrap_or(0);
+    let field_indent = dash_indent + 2;
+
+    let item_field = |lines: &[String], (first, last): (usize, usize), field: &str| {
+        (first..=last).find(|&i| parse_key(&lines[i]).is_some_and(|key| key.name == field))
+    };
+
+    for &item in &items {
+        let Some(name_line) = item_field(&lines, item, "name") else {
+            continue;
+        };
+        let key = parse_key(&lines[name_line]).expect("name key");
+        let name_matches = parse_scalar(&lines[name_line][key.value_offset..])
+            .is_some_and(|scalar| scalar.value == repository);
+        if !name_matches {
+            continue;
+        }
+
+        match item_field(&lines, item, "newTag") {
+            Some(tag_line) => {
+                let key = parse_key(&lines[tag_line]).expect("newTag key");
+                let replaced = match parse_scalar(&lines[tag_line][key.value_offset..]) {
+                    Some(scalar) => {
+                        replace_scalar(&lines[tag_line], key.value_offset, &scalar, tag)
+                    },
+                    None => format!("{} {}", &lines[tag_line], render(tag, Some('"'))),
+                };
+                lines[tag_line] = replaced;
+            },
+            None => lines.insert(
+                item.1 + 1,
+                format!(
+                    "{}newTag: {}",
+                    " ".repeat(field_indent),
+                    render(tag, Some('"'))
+                ),
+            ),
+        }
+        return join_lines(&lines, content);
+    }
+
+    // No entry for this image yet: append one to the list
+    let insert_at = items.last().map_or(images + 1, |item| item.1 + 1);
+    lines.insert(
+        insert_at,
+        format!("{}- name: {}", " ".repeat(dash_indent), repository),
+    );
+    lines.insert(
+        insert_at + 1,
+        format!(
+            "{}newTag: {}",
+            " ".repeat(field_indent),
+            render(tag, Some('"'))
+        ),
+    );
+    join_lines(&lines, content)
+}
+
+/// Set the scalar at a dotted key path (e.g. `image.tag`) in a Helm values file.
+/// The key must already exist.
+pub fn set_helm_value(content: &str, key_path: &str, value: &str) -> Result<String> {
+    let mut lines = split_lines(content);
+    let segments: Vec<&str> = key_path.split('.').collect();
+
+    let mut start = 0;
+    let mut end = lines.len();
+    let mut parent_indent: Option<usize> = None;
+
+    for (depth, segment) in segments.iter().enumerate() {
+        // Direct children share the indentation of the first key in the block
+        let child_indent = (start..end)
+            .find(|&i| !is_blank(&lines[i]))
+            .map(|i| indent_of(&lines[i]));
+        let found = child_indent.and_then(|child_indent| {
+            (start..end).find(|&i| {
+                parse_key(&lines[i]).is_some_and(|key| {
+                    key.indent == child_indent
+                        && !key.dash
+                        && key.name == *segment
+                        && parent_indent.is_none_or(|p| key.indent > p)
+                })
+            })
+        });
+        let Some(line_index) = found else {
+            return Err(anyhow!("Key {} not found", segments[..=depth].join(".")));
+        };
+        let key = parse_key(&lines[line_index]).expect("matched key");
+
+        if depth == segments.len() - 1 {
+            let scalar = parse_scalar(&lines[line_index][key.value_offset..])
+                .ok_or_else(|| anyhow!("Key {} is not a scalar value", key_path))?;
+            lines[line_index] =
+                replace_scalar(&lines[line_index], key.value_offset, &scalar, value);
+            return Ok(join_lines(&lines, content));
+        }
+
+        // Narrow the search to the nested block
+        parent_indent = Some(key.indent);
+        start = line_index + 1;
+        end = (start..end)
+            .find(|&i| !is_blank(&lines[i]) && indent_of(&lines[i]) <= key.indent)
+            .unwrap_or(end);
+    }
+
+    Err(anyhow!("Empty key path"))
+}
+
+/// A `key:` on a YAML line
+struct Key<'a> {
+    /// Column of the key
+    indent: usize,
+    /// Whether the key starts a list item (`- key:`)
+    dash: bool,
+    name: &'a str,
+    /// Byte offset just after the colon
+    value_offset: usize,
+}
+
+fn parse_key(line: &str) -> Option<Key<'_>> {
+    let mut indent = indent_of(line);
+    let mut dash = false;
+    if line[indent..].starts_with("- ") {
+        dash = true;
+        indent += 2;
+        indent += indent_of(&line[indent..]);
+    }
+
+    let rest = &line[indent..];
+    if rest.starts_with('#') {
+        return None;
+    }
+    let colon = rest.find(':')?;
+    let after = &rest[colon + 1..];
+    if !(after.is_empty() || after.starts_with(' ')) {
+        return None;
+    }
+
+    let name = rest[..colon].trim_matches(|c| c == '"' || c == '\'');
+    if name.is_empty() || name.contains(' ') {
+        return None;
+    }
+
+    Some(Key {
+        indent,
+        dash,
+        name,
+        value_offset: indent + colon + 1,
+    })
+}
+
+/// A scalar value after a key: its text without quotes and its byte span (quotes included)
+struct Scalar<'a> {
+    value: &'a str,
+    start: usize,
+    end: usize,
+    quote: Option<char>,
+}
+
+fn parse_scalar(rest: &str) -> Option<Scalar<'_>> {
+    let start = rest.len() - rest.trim_start().len();
+    let trimmed = &rest[start..];
+    let first = trimmed.chars().next()?;
+
+    if first == '"' || first == '\'' {
+        let close = trimmed[1..].find(first)? + 1;
+        return Some(Scalar {
+            value: &trimmed[1..close],
+            start,
+            end: start + close + 1,
+            quote: Some(first),
+        });
+    }
+
+    if first == '#' || first == '|' || first == '>' {
+        return None;
+    }
+
+    // Unquoted values end at a trailing comment
+    let len = trimmed.find(" #").unwrap_or(trimmed.len());
+    let value = trimmed[..len].trim_end();
+    Some(Scalar {
+        value,
+        start,
+        end: start + value.len(),
+        quote: None,
+    })
+}
+
+fn replace_scalar(line: &str, value_offset: usize, scalar: &Scalar<'_>, new_value: &str) -> String {
+    let rest = &line[value_offset..];
+    format!(
+        "{}{}{}{}",
+        &line[..value_offset],
+        &rest[..scalar.start],
+        render(new_value, scalar.quote),
+        &rest[scalar.end..]
+    )
+}
+
+/// Render a value with the original quoting. Unquoted values that YAML would read as a
+/// number (e.g. `1.10`) are double-quoted so they stay strings.
+fn render(value: &str, quote: Option<char>) -> String {
+    match quote {
+        Some(q) => format!("{q}{value}{q}"),
+        None if value.parse::<f64>().is_ok() => format!("\"{}\"", value),
+        None => value.to_string(),
+    }
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_blank(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+fn split_lines(content: &str) -> Vec<String> {
+    content.lines().map(str::to_string).collect()
+}
+
+fn join_lines(lines: &[String], original: &str) -> String {
+    let mut joined = lines.join("\n");
+    if original.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_manifest_image() {
+        let manifest = r#"apiVersion: apps/v1
+kind: Deployment
+spec:
+  template:
+    spec:
+      containers:
+      - name: web
+        image: registry.example.com/web:1.4.0 # pinned by Headwind
+      - name: proxy
+        image: "envoyproxy/envoy:v1.30.0"
+"#;
+
+        let updated = set_manifest_image(
+            manifest,
+            "registry.example.com/web",
+            "registry.example.com/web:1.5.0",
+        )
+        .unwrap();
+        assert!(updated.contains("image: registry.example.com/web:1.5.0 # pinned by Headwind"));
+        assert!(updated.contains("image: \"envoyproxy/envoy:v1.30.0\""));
+        assert!(updated.ends_with('\n'));
+
+        let updated =
+            set_manifest_image(manifest, "envoyproxy/envoy", "envoyproxy/envoy:v1.31.0").unwrap();
+        assert!(updated.contains("image: \"envoyproxy/envoy:v1.31.0\""));
+
+        assert!(set_manifest_image(manifest, "redis", "redis:7.2").is_err());
+    }
+
+    #[test]
+    fn test_set_kustomize_tag_existing_entry() {
+        let kustomization = "resources:\n- deployment.yaml\nimages:\n- name: nginx\n  newTag: 1.25.0\n- name: registry.example.com/web\n  newName: registry.example.com/web-mirror\n";
+
+        let updated = set_kustomize_tag(kustomization, "nginx", "1.26.0");
+        assert!(updated.contains("- name: nginx\n  newTag: 1.26.0\n"));
+
+        // Entry without newTag gets one
+        let updated = set_kustomize_tag(kustomization, "registry.example.com/web", "2.0.0");
+        assert!(
+            updated.ends_with("  newName: registry.example.com/web-mirror\n  newTag: \"2.0.0\"\n")
+        );
+    }
+
+    #[test]
+    fn test_set_kustomize_tag_new_entry() {
+        let kustomization =
+            "images:\n  - name: nginx\n    newTag: \"1.25.0\"\nresources:\n  - deployment.yaml\n";
+        let updated = set_kustomize_tag(kustomization, "redis", "7.2");
+        assert_eq!(
+            updated,
+            "images:\n  - name: nginx\n    newTag: \"1.25.0\"\n  - name: redis\n    newTag: \"7.2\"\nresources:\n  - deployment.yaml\n"
+        );
+
+        let updated = set_kustomize_tag("resources:\n- deployment.yaml\n", "redis", "7.2");
+        assert_eq!(
+            updated,
+            "resources:\n- deployment.yaml\nimages:\n- name: redis\n  newTag: \"7.2\"\n"
+        );
+    }
+
+    #[test]
+    fn test_set_helm_value() {
+        let values = r#"replicaCount: 2
+# Application image
+image:
+  repository: registry.example.com/web
+  tag: "1.4.0"
+worker:
+  image:
+    repository: registry.example.com/worker
+    tag: 1.4.0
+"#;
+
+        let updated = set_helm_value(values, "image.tag", "1.5.0").unwrap();
+        assert!(updated.contains("  tag: \"1.5.0\"\n"));
+        assert!(updated.contains("    tag: 1.4.0\n"));
+
+        let updated = set_helm_value(values, "worker.image.tag", "1.5.0").unwrap();
+        assert!(updated.contains("    tag: 1.5.0\n"));
+        assert!(updated.contains("  tag: \"1.4.0\"\n"));
+
+        assert!(set_helm_value(values, "image.digest", "sha256:abc").is_err());
+        assert!(set_helm_value(values, "image", "1.5.0").is_err());
+    }
+
+    #[test]
+    fn test_render_keeps_numeric_tags_strings() {
+        assert_eq!(render("1.10", None), "\"1.10\"");
+        assert_eq!(render("1.10.0", None), "1.10.0");
+        assert_eq!(render("1.10", Some('\'')), "'1.10'");
+    }
+}
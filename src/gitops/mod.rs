@@ -0,0 +1,471 @@
+//! Git write-back: commit new image tags to the repository a workload is deployed from,
+//! instead of patching the live workload. Used in clusters where Flux or ArgoCD would
+//! revert in-cluster changes.
+
+pub mod edit;
+pub mod provider;
+
+use crate::audit;
+use crate::controller::workload::{WorkloadImageUpdate, create_update_request, map_policy_to_crd};
+use crate::metrics::{GITOPS_COMMITS, GITOPS_ERRORS, GITOPS_PULL_REQUESTS};
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
+use crate::models::crd::{GitFileFormat, GitWriteBack, TargetRef, UpdateRequestSpec, UpdateType};
+use crate::models::policy::annotations;
+use crate::notifications::{self, DeploymentInfo};
+use anyhow::{Result, anyhow};
+use kube::Client;
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+pub use provider::{GitProvider, ProviderKind, RepositoryRef};
+
+/// Branch committed to when the workload doesn't name one
+pub const DEFAULT_BRANCH: &str = "main";
+
+/// Token used to authenticate against the Git hosting API (HEADWIND_GITOPS_TOKEN)
+pub fn token() -> Option<String> {
+    std::env::var("HEADWIND_GITOPS_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
+/// Read the write-back target from a workload's annotations. Both
+/// `headwind.sh/gitops-repository` and `headwind.sh/gitops-path` must be set.
+pub fn write_back_target(annotations: &BTreeMap<String, String>) -> Option<GitWriteBack> {
+    let repository = annotations.get(annotations::GITOPS_REPOSITORY)?.trim();
+    let path = annotations.get(annotations::GITOPS_PATH)?.trim();
+    if repository.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    let format = match annotations
+        .get(annotations::GITOPS_FORMAT)
+        .map(|f| f.trim().to_lowercase())
+        .as_deref()
+    {
+        None | Some("manifest") | Some("yaml") => GitFileFormat::Manifest,
+        Some("kustomize") => GitFileFormat::Kustomize,
+        Some("helm") => GitFileFormat::Helm,
+        Some(other) => {
+            warn!(
+                "Unknown {} value '{}', expected manifest, kustomize or helm",
+                annotations::GITOPS_FORMAT,
+                other
+            );
+            return None;
+        },
+    };
+
+    Some(GitWriteBack {
+        repository: repository.to_string(),
+        path: path.to_string(),
+        format,
+        branch: annotations
+            .get(annotations::GITOPS_BRANCH)
+            .map(|b| b.trim().to_string())
+            .filter(|b| !b.is_empty())
+            .unwrap_or_else(|| DEFAULT_BRANCH.to_string()),
+        value_path: annotations.get(annotations::GITOPS_VALUE_PATH).cloned(),
+        pull_request: annotations
+            .get(annotations::GITOPS_PULL_REQUEST)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false),
+        provider: annotations.get(annotations::GITOPS_PROVIDER).cloned(),
+    })
+}
+
+/// Route a workload image update to Git. With approval required an UpdateRequest carrying
+/// the write-back target is created; otherwise the change is committed right away.
+pub async fn handle_workload_image_update(
+    client: &Client,
+    target: &GitWriteBack,
+    update: WorkloadImageUpdate<'_>,
+) -> Result<()> {
+    info!(
+        "{} {}/{} uses Git write-back, writing {} to {}/{}",
+        update.kind,
+        update.namespace,
+        update.name,
+        update.new_image,
+        target.repository,
+        target.path
+    );
+
+    if update.policy.require_approval {
+        let spec = UpdateRequestSpec {
+            target_ref: TargetRef {
+                api_version: api_version(update.kind).to_string(),
+                kind: update.kind.to_string(),
+                name: update.name.to_string(),
+                namespace: update.namespace.to_string(),
+            },
+            update_type: UpdateType::Image,
+            container_name: update.container_name.map(str::to_string),
+            image_parameter: None,
+            current_image: update.current_image.to_string(),
+            new_image: update.new_image.to_string(),
+            policy: map_policy_to_crd(&update.policy.policy),
+            reason: Some(format!(
+                "New image {} to commit to {}",
+                update.new_image, target.repository
+            )),
+            require_approval: true,
+            expires_at: update.policy.approval_expiry(),
+            required_approvals: update.policy.required_approvals,
+            git_write_back: Some(target.clone()),
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
+        };
+
+        create_update_request(client, update.namespace, spec).await?;
+        return Ok(());
+    }
+
+    let change = WriteBackChange {
+        kind: update.kind,
+        namespace: update.namespace,
+        name: update.name,
+        current_image: update.current_image,
+        new_image: update.new_image,
+    };
+    let resource = DeploymentInfo {
+        name: update.name.to_string(),
+        namespace: update.namespace.to_string(),
+        current_image: update.current_image.to_string(),
+        new_image: update.new_image.to_string(),
+        container: update.container_name.map(str::to_string),
+        resource_kind: Some(update.kind.to_string()),
+    };
+    let result = write_back(target, &change).await;
+    audit::record(
+        UpdateAuditSpec::new(AuditAction::Applied, &resource)
+            .with_source("gitops")
+            .with_message(format!("Committed to {}", target.repository))
+            .with_result(&result),
+    );
+    result?;
+
+    notifications::notify_update_completed(resource);
+
+    Ok(())
+}
+
+fn api_version(kind: &str) -> &'static str {
+    match kind {
+        "CronJob" => "batch/v1",
+        _ => "apps/v1",
+    }
+}
+
+/// The workload change being written back
+pub struct WriteBackChange<'a> {
+    pub kind: &'a str,
+    pub namespace: &'a str,
+    pub name: &'a str,
+    pub current_image: &'a str,
+    pub new_image: &'a str,
+}
+
+/// Result of a write-back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteBackOutcome {
+    /// Branch the change was committed to
+    pub branch: String,
+    /// False when the file already referenced the new image
+    pub committed: bool,
+    pub pull_request_url: Option<String>,
+}
+
+/// Commit the change to the target repository, opening a pull request if configured
+pub async fn write_back(
+    target: &GitWriteBack,
+    change: &WriteBackChange<'_>,
+) -> Result<WriteBackOutcome> {
+    let result = async {
+        let token = token().ok_or_else(|| anyhow!("HEADWIND_GITOPS_TOKEN is not set"))?;
+        let repository = RepositoryRef::parse(&target.repository)?;
+        let kind = ProviderKind::resolve(&repository, target.provider.as_deref())?;
+        let provider = provider::connect(&repository, kind, token)?;
+        write_back_with(provider.as_ref(), target, change).await
+    }
+    .await;
+
+    if let Err(e) = &result {
+        GITOPS_ERRORS.inc();
+        warn!(
+            "Git write-back to {}/{} failed: {}",
+            target.repository, target.path, e
+        );
+    }
+    result
+}
+
+/// Commit the change using a specific provider
+pub async fn write_back_with(
+    provider: &dyn GitProvider,
+    target: &GitWriteBack,
+    change: &WriteBackChange<'_>,
+) -> Result<WriteBackOutcome> {
+    let (repository, tag) = edit::split_image(change.new_image);
+
+    let branch = if target.pull_request {
+        let branch = format!(
+            "headwind/{}-{}-{}",
+            change.namespace,
+            change.name,
+            tag.replace([':', '.', '/', '@'], "-")
+        );
+        provider.create_branch(&target.branch, &branch).await?;
+        branch
+    } else {
+        target.branch.clone()
+    };
+
+    let file = provider.read_file(&branch, &target.path).await?;
+    let content = edit::apply(target, &file.content, change.new_image)
+        .map_err(|e| anyhow!("Failed to edit {}: {}", target.path, e))?;
+
+    let committed = content != file.content;
+    if committed {
+        let message = format!(
+            "Update {} to {}\n\n{} {}/{}: {} -> {}",
+            repository,
+            tag,
+            change.kind,
+            change.namespace,
+            change.name,
+            change.current_image,
+            change.new_image
+        );
+        provider
+            .write_file(&branch, &target.path, &content, &file, &message)
+            .await?;
+        GITOPS_COMMITS.inc();
+        info!(
+            "Committed {} to {}/{} on branch {}",
+            change.new_image, target.repository, target.path, branch
+        );
+    } else {
+        info!(
+            "{}/{} already references {}, nothing to commit",
+            target.repository, target.path, change.new_image
+        );
+    }
+
+    let pull_request_url = if target.pull_request {
+        let title = format!("Update {} to {}", repository, tag);
+        let body = format!(
+            "Headwind detected a new image for {} {}/{}.\n\n- Current: `{}`\n- New: `{}`",
+            change.kind, change.namespace, change.name, change.current_image, change.new_image
+        );
+        let url = provider
+            .open_pull_request(&target.branch, &branch, &title, &body)
+            .await?;
+        if committed {
+            GITOPS_PULL_REQUESTS.inc();
+        }
+        info!("Pull request for {}: {}", change.new_image, url);
+        Some(url)
+    } else {
+        None
+    };
+
+    Ok(WriteBackOutcome {
+        branch,
+        committed,
+        pull_request_url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gitops::provider::GitFile;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    fn annotations(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// In-memory provider recording what was committed
+    #[derive(Default)]
+    struct FakeProvider {
+        files: Mutex<BTreeMap<(String, String), String>>,
+        branches: Mutex<Vec<String>>,
+        pull_requests: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl GitProvider for FakeProvider {
+        async fn read_file(&self, branch: &str, path: &str) -> Result<GitFile> {
+            let files = self.files.lock().unwrap();
+            let content = files
+                .get(&(branch.to_string(), path.to_string()))
+                .or_else(|| files.get(&("main".to_string(), path.to_string())))
+                .cloned()
+                .ok_or_else(|| anyhow!("not found"))?;
+            Ok(GitFile {
+                content,
+                revision: "abc".to_string(),
+            })
+        }
+
+        async fn write_file(
+            &self,
+            branch: &str,
+            path: &str,
+            content: &str,
+            _previous: &GitFile,
+            _message: &str,
+        ) -> Result<()> {
+            self.files
+                .lock()
+                .unwrap()
+                .insert((branch.to_string(), path.to_string()), content.to_string());
+            Ok(())
+        }
+
+        async fn create_branch(&self, _base: &str, branch: &str) -> Result<()> {
+            self.branches.lock().unwrap().push(branch.to_string());
+            Ok(())
+        }
+
+        async fn open_pull_request(
+            &self,
+            base: &str,
+            head: &str,
+            _title: &str,
+            _body: &str,
+        ) -> Result<String> {
+            self.pull_requests
+                .lock()
+                .unwrap()
+                .push((base.to_string(), head.to_string()));
+            Ok(format!("https://git.example.com/pulls/{}", head))
+        }
+    }
+
+    fn target(pull_request: bool) -> GitWriteBack {
+        GitWriteBack {
+            repository: "github.com/example/deploy".to_string(),
+            path: "apps/web/values.yaml".to_string(),
+            format: GitFileFormat::Helm,
+            branch: "main".to_string(),
+            value_path: None,
+            pull_request,
+            provider: None,
+        }
+    }
+
+    fn change() -> WriteBackChange<'static> {
+        WriteBackChange {
+            kind: "Deployment",
+            namespace: "production",
+            name: "web",
+            current_image: "registry.example.com/web:1.4.0",
+            new_image: "registry.example.com/web:1.5.0",
+        }
+    }
+
+    fn provider() -> FakeProvider {
+        let provider = FakeProvider::default();
+        provider.files.lock().unwrap().insert(
+            ("main".to_string(), "apps/web/values.yaml".to_string()),
+            "image:\n  repository: registry.example.com/web\n  tag: 1.4.0\n".to_string(),
+        );
+        provider
+    }
+
+    #[test]
+    fn test_write_back_target() {
+        assert!(write_back_target(&annotations(&[])).is_none());
+        assert!(
+            write_back_target(&annotations(&[(
+                annotations::GITOPS_REPOSITORY,
+                "github.com/example/deploy"
+            )]))
+            .is_none()
+        );
+
+        let target = write_back_target(&annotations(&[
+            (annotations::GITOPS_REPOSITORY, "github.com/example/deploy"),
+            (annotations::GITOPS_PATH, "apps/web/kustomization.yaml"),
+            (annotations::GITOPS_FORMAT, "kustomize"),
+            (annotations::GITOPS_PULL_REQUEST, "true"),
+        ]))
+        .unwrap();
+        assert_eq!(target.format, GitFileFormat::Kustomize);
+        assert_eq!(target.branch, DEFAULT_BRANCH);
+        assert!(target.pull_request);
+
+        assert!(
+            write_back_target(&annotations(&[
+                (annotations::GITOPS_REPOSITORY, "github.com/example/deploy"),
+                (annotations::GITOPS_PATH, "values.yaml"),
+                (annotations::GITOPS_FORMAT, "jsonnet"),
+            ]))
+            .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_back_commits_to_branch() {
+        let provider = provider();
+        let outcome = write_back_with(&provider, &target(false), &change())
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.branch, "main");
+        assert!(outcome.committed);
+        assert!(outcome.pull_request_url.is_none());
+        let files = provider.files.lock().unwrap();
+        assert!(
+            files[&("main".to_string(), "apps/web/values.yaml".to_string())].contains("tag: 1.5.0")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_back_opens_pull_request() {
+        let provider = provider();
+        let outcome = write_back_with(&provider, &target(true), &change())
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.branch, "headwind/production-web-1-5-0");
+        assert_eq!(
+            provider.branches.lock().unwrap().as_slice(),
+            ["headwind/production-web-1-5-0"]
+        );
+        assert_eq!(
+            provider.pull_requests.lock().unwrap()[0],
+            (
+                "main".to_string(),
+                "headwind/production-web-1-5-0".to_string()
+            )
+        );
+        assert!(outcome.pull_request_url.is_some());
+
+        // The base branch is untouched
+        let files = provider.files.lock().unwrap();
+        assert!(
+            files[&("main".to_string(), "apps/web/values.yaml".to_string())].contains("tag: 1.4.0")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_back_is_idempotent() {
+        let provider = provider();
+        write_back_with(&provider, &target(false), &change())
+            .await
+            .unwrap();
+        let outcome = write_back_with(&provider, &target(false), &change())
+            .await
+            .unwrap();
+        assert!(!outcome.committed);
+    }
+}
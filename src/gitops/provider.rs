@@ -0,0 +1,523 @@
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use base64::prelude::*;
+use reqwest::{Client, Response, StatusCode, Url};
+use serde_json::{Value, json};
+use std::time::Duration;
+use tracing::debug;
+
+/// A repository on a Git hosting service, e.g. `github.com/org/repo`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepositoryRef {
+    pub host: String,
+    /// Owner/name (GitHub) or full project path (GitLab)
+    pub path: String,
+}
+
+impl RepositoryRef {
+    /// Parse `host/path`, `https://host/path(.git)` or `git@host:path(.git)`
+    pub fn parse(repository: &str) -> Result<Self> {
+        let trimmed = repository.trim();
+        let without_scheme = trimmed
+            .strip_prefix("https://")
+            .or_else(|| trimmed.strip_prefix("http://"))
+            .unwrap_or(trimmed);
+
+        let (host, path) = match without_scheme.strip_prefix("git@") {
+            Some(ssh) => ssh.split_once(':'),
+            None => without_scheme.split_once('/'),
+        }
+        .ok_or_else(|| anyhow!("Invalid Git repository: {}", repository))?;
+
+        let path = path.trim_end_matches('/').trim_end_matches(".git");
+        if host.is_empty() || !path.contains('/') {
+            return Err(anyhow!(
+                "Invalid Git repository: {} (expected host/owner/name)",
+                repository
+            ));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Supported Git hosting providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    GitHub,
+    GitLab,
+}
+
+impl ProviderKind {
+    /// Use the explicit provider when given, otherwise infer it from the host
+    pub fn resolve(repository: &RepositoryRef, explicit: Option<&str>) -> Result<Self> {
+        match explicit.map(str::to_lowercase).as_deref() {
+            Some("github") => Ok(Self::GitHub),
+            Some("gitlab") => Ok(Self::GitLab),
+            Some(other) => Err(anyhow!("Unsupported Git provider: {}", other)),
+            None if repository.host.contains("gitlab") => Ok(Self::GitLab),
+            None => Ok(Self::GitHub),
+        }
+    }
+}
+
+/// A file read from a repository, with the revision needed to update it safely
+#[derive(Debug, Clone)]
+pub struct GitFile {
+    pub content: String,
+    /// Blob SHA (GitHub) or last commit id (GitLab) the content was read at
+    pub revision: String,
+}
+
+/// Operations Git write-back needs from a hosting provider
+#[async_trait]
+pub trait GitProvider: Send + Sync {
+    async fn read_file(&self, branch: &str, path: &str) -> Result<GitFile>;
+
+    /// Commit new content for a file previously read with `read_file`
+    async fn write_file(
+        &self,
+        branch: &str,
+        path: &str,
+        content: &str,
+        previous: &GitFile,
+        message: &str,
+    ) -> Result<()>;
+
+    /// Create `branch` from `base`. Succeeds if the branch already exists.
+    async fn create_branch(&self, base: &str, branch: &str) -> Result<()>;
+
+    /// Open a pull (merge) request and return its URL. Returns the existing one if open.
+    async fn open_pull_request(
+        &self,
+        base: &str,
+        head: &str,
+        title: &str,
+        body: &str,
+    ) -> Result<String>;
+}
+
+/// Create the provider client for a repository
+pub fn connect(
+    repository: &RepositoryRef,
+    kind: ProviderKind,
+    token: String,
+) -> Result<Box<dyn GitProvider>> {
+    let http = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .user_agent("headwind")
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    Ok(match kind {
+        ProviderKind::GitHub => Box::new(GitHubProvider {
+            http,
+            api_url: github_api_url(&repository.host),
+            repository: repository.path.clone(),
+            token,
+        }),
+        ProviderKind::GitLab => Box::new(GitLabProvider {
+            http,
+            api_url: format!("https://{}/api/v4", repository.host),
+            project: repository.path.clone(),
+            token,
+        }),
+    })
+}
+
+/// github.com uses api.github.com; GitHub Enterprise serves the API under /api/v3
+fn github_api_url(host: &str) -> String {
+    if host == "github.com" {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{}/api/v3", host)
+    }
+}
+
+/// Build a URL from a base and path segments, percent-encoding each segment
+fn api_url(base: &str, segments: &[&str]) -> Result<Url> {
+    let mut url = Url::parse(base).with_context(|| format!("Invalid API URL: {}", base))?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("Invalid API URL: {}", base))?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+async fn error_for_status(response: Response, action: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(anyhow!("Failed to {}: {} {}", action, status, body))
+}
+
+pub struct GitHubProvider {
+    http: Client,
+    api_url: String,
+    /// owner/name
+    repository: String,
+    token: String,
+}
+
+impl GitHubProvider {
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut all = vec!["repos"];
+        all.extend(self.repository.split('/'));
+        all.extend_from_slice(segments);
+        api_url(&self.api_url, &all)
+    }
+
+    fn contents_url(&self, path: &str) -> Result<Url> {
+        let mut segments = vec!["contents"];
+        segments.extend(path.trim_start_matches('/').split('/'));
+        self.url(&segments)
+    }
+
+    fn request(&self, method: reqwest::Method, url: Url) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+    }
+}
+
+#[async_trait]
+impl GitProvider for GitHubProvider {
+    async fn read_file(&self, branch: &str, path: &str) -> Result<GitFile> {
+        let mut url = self.contents_url(path)?;
+        url.query_pairs_mut().append_pair("ref", branch);
+
+        let response = self.request(reqwest::Method::GET, url).send().await?;
+        let body: Value = error_for_status(response, &format!("read {}", path))
+            .await?
+            .json()
+            .await?;
+
+        let encoded: String = body["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("{} is not a file", path))?
+            .split_whitespace()
+            .collect();
+        let content = String::from_utf8(BASE64_STANDARD.decode(encoded)?)?;
+        let revision = body["sha"].as_str().unwrap_or_default().to_string();
+
+        Ok(GitFile { content, revision })
+    }
+
+    async fn write_file(
+        &self,
+        branch: &str,
+        path: &str,
+        content: &str,
+        previous: &GitFile,
+        message: &str,
+    ) -> Result<()> {
+        let body = json!({
+            "message": message,
+            "content": BASE64_STANDARD.encode(content),
+            "sha": previous.revision,
+            "branch": branch,
+        });
+
+        let response = self
+            .request(reqwest::Method::PUT, self.contents_url(path)?)
+            .json(&body)
+            .send()
+            .await?;
+        error_for_status(response, &format!("commit {}", path)).await?;
+        Ok(())
+    }
+
+    async fn create_branch(&self, base: &str, branch: &str) -> Result<()> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                self.url(&["git", "ref", "heads", base])?,
+            )
+            .send()
+            .await?;
+        let base_ref: Value = error_for_status(response, &format!("read branch {}", base))
+            .await?
+            .json()
+            .await?;
+        let sha = base_ref["object"]["sha"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Branch {} has no commit", base))?;
+
+        let response = self
+            .request(reqwest::Method::POST, self.url(&["git", "refs"])?)
+            .json(&json!({ "ref": format!("refs/heads/{}", branch), "sha": sha }))
+            .send()
+            .await?;
+
+        // 422 means the branch already exists
+        if response.status() == StatusCode::UNPROCESSABLE_ENTITY {
+            debug!("Branch {} already exists", branch);
+            return Ok(());
+        }
+        error_for_status(response, &format!("create branch {}", branch)).await?;
+        Ok(())
+    }
+
+    async fn open_pull_request(
+        &self,
+        base: &str,
+        head: &str,
+        title: &str,
+        body: &str,
+    ) -> Result<String> {
+        let response = self
+            .request(reqwest::Method::POST, self.url(&["pulls"])?)
+            .json(&json!({ "title": title, "head": head, "base": base, "body": body }))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::UNPROCESSABLE_ENTITY {
+            // A pull request for this branch is already open
+            let owner = self.repository.split('/').next().unwrap_or_default();
+            let mut url = self.url(&["pulls"])?;
+            url.query_pairs_mut()
+                .append_pair("head", &format!("{}:{}", owner, head))
+                .append_pair("state", "open");
+            let response = self.request(reqwest::Method::GET, url).send().await?;
+            let pulls: Value = error_for_status(response, "list pull requests")
+                .await?
+                .json()
+                .await?;
+            return pulls[0]["html_url"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("Failed to open pull request for {}", head));
+        }
+
+        let pull: Value = error_for_status(response, "open pull request")
+            .await?
+            .json()
+            .await?;
+        Ok(pull["html_url"].as_str().unwrap_or_default().to_string())
+    }
+}
+
+pub struct GitLabProvider {
+    http: Client,
+    api_url: String,
+    /// Full project path (group/subgroup/name)
+    project: String,
+    token: String,
+}
+
+impl GitLabProvider {
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        // The project path is a single, encoded segment
+        let mut all = vec!["projects", self.project.as_str()];
+        all.extend_from_slice(segments);
+        api_url(&self.api_url, &all)
+    }
+
+    fn file_url(&self, path: &str) -> Result<Url> {
+        self.url(&["repository", "files", path.trim_start_matches('/')])
+    }
+
+    fn request(&self, method: reqwest::Method, url: Url) -> reqwest::RequestBuilder {
+        self.http.request(method, url).bearer_auth(&self.token)
+    }
+}
+
+#[async_trait]
+impl GitProvider for GitLabProvider {
+    async fn read_file(&self, branch: &str, path: &str) -> Result<GitFile> {
+        let mut url = self.file_url(path)?;
+        url.query_pairs_mut().append_pair("ref", branch);
+
+        let response = self.request(reqwest::Method::GET, url).send().await?;
+        let body: Value = error_for_status(response, &format!("read {}", path))
+            .await?
+            .json()
+            .await?;
+
+        let encoded = body["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("{} has no content", path))?;
+        let content = String::from_utf8(BASE64_STANDARD.decode(encoded)?)?;
+        let revision = body["last_commit_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        Ok(GitFile { content, revision })
+    }
+
+    async fn write_file(
+        &self,
+        branch: &str,
+        path: &str,
+        content: &str,
+        previous: &GitFile,
+        message: &str,
+    ) -> Result<()> {
+        let body = json!({
+            "branch": branch,
+            "content": content,
+            "commit_message": message,
+            "last_commit_id": previous.revision,
+        });
+
+        let response = self
+            .request(reqwest::Method::PUT, self.file_url(path)?)
+            .json(&body)
+            .send()
+            .await?;
+        error_for_status(response, &format!("commit {}", path)).await?;
+        Ok(())
+    }
+
+    async fn create_branch(&self, base: &str, branch: &str) -> Result<()> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                self.url(&["repository", "branches"])?,
+            )
+            .json(&json!({ "branch": branch, "ref": base }))
+            .send()
+            .await?;
+
+        // 400 "Branch already exists"
+        if response.status() == StatusCode::BAD_REQUEST {
+            debug!("Branch {} already exists", branch);
+            return Ok(());
+        }
+        error_for_status(response, &format!("create branch {}", branch)).await?;
+        Ok(())
+    }
+
+    async fn open_pull_request(
+        &self,
+        base: &str,
+        head: &str,
+        title: &str,
+        body: &str,
+    ) -> Result<String> {
+        let response = self
+            .request(reqwest::Method::POST, self.url(&["merge_requests"])?)
+            .json(&json!({
+                "source_branch": head,
+                "target_branch": base,
+                "title": title,
+                "description": body,
+            }))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::CONFLICT {
+            // A merge request for this branch is already open
+            let mut url = self.url(&["merge_requests"])?;
+            url.query_pairs_mut()
+                .append_pair("source_branch", head)
+                .append_pair("state", "opened");
+            let response = self.request(reqwest::Method::GET, url).send().await?;
+            let requests: Value = error_for_status(response, "list merge requests")
+                .await?
+                .json()
+                .await?;
+            return requests[0]["web_url"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("Failed to open merge request for {}", head));
+        }
+
+        let request: Value = error_for_status(response, "open merge request")
+            .await?
+            .json()
+            .await?;
+        Ok(request["web_url"].as_str().unwrap_or_default().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repository() {
+        let expected = RepositoryRef {
+            host: "github.com".to_string(),
+            path: "example/deploy".to_string(),
+        };
+        assert_eq!(
+            RepositoryRef::parse("github.com/example/deploy").unwrap(),
+            expected
+        );
+        assert_eq!(
+            RepositoryRef::parse("https://github.com/example/deploy.git").unwrap(),
+            expected
+        );
+        assert_eq!(
+            RepositoryRef::parse("git@github.com:example/deploy.git").unwrap(),
+            expected
+        );
+
+        let nested = RepositoryRef::parse("gitlab.example.com/platform/apps/deploy").unwrap();
+        assert_eq!(nested.path, "platform/apps/deploy");
+
+        assert!(RepositoryRef::parse("github.com/deploy").is_err());
+        assert!(RepositoryRef::parse("deploy").is_err());
+    }
+
+    #[test]
+    fn test_resolve_provider() {
+        let github = RepositoryRef::parse("github.com/example/deploy").unwrap();
+        let gitlab = RepositoryRef::parse("gitlab.example.com/example/deploy").unwrap();
+
+        assert_eq!(
+            ProviderKind::resolve(&github, None).unwrap(),
+            ProviderKind::GitHub
+        );
+        assert_eq!(
+            ProviderKind::resolve(&gitlab, None).unwrap(),
+            ProviderKind::GitLab
+        );
+        assert_eq!(
+            ProviderKind::resolve(&github, Some("GitLab")).unwrap(),
+            ProviderKind::GitLab
+        );
+        assert!(ProviderKind::resolve(&github, Some("bitbucket")).is_err());
+    }
+
+    #[test]
+    fn test_api_urls() {
+        let repository = RepositoryRef::parse("github.com/example/deploy").unwrap();
+        let github = GitHubProvider {
+            http: Client::new(),
+            api_url: github_api_url(&repository.host),
+            repository: repository.path,
+            token: String::new(),
+        };
+        assert_eq!(
+            github
+                .contents_url("apps/web/deployment.yaml")
+                .unwrap()
+                .as_str(),
+            "https://api.github.com/repos/example/deploy/contents/apps/web/deployment.yaml"
+        );
+        assert_eq!(
+            github_api_url("git.example.com"),
+            "https://git.example.com/api/v3"
+        );
+
+        let gitlab = GitLabProvider {
+            http: Client::new(),
+            api_url: "https://gitlab.com/api/v4".to_string(),
+            project: "group/deploy".to_string(),
+            token: String::new(),
+        };
+        assert_eq!(
+            gitlab.file_url("apps/values.yaml").unwrap().as_str(),
+            "https://gitlab.com/api/v4/projects/group%2Fdeploy/repository/files/apps%2Fvalues.yaml"
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Liveness and readiness of the operator.
+//!
+//! Components register checks here, and the metrics server serves them as `/livez` and
+//! `/readyz` for the Kubernetes probes. Liveness fails only when a restart is the fix, such as
+//! an event processor that has exited. Readiness also requires the API server to be reachable,
+//! every controller watch to have completed its initial list, and no controller watch to be
+//! failing.
+
+use axum::Json;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use kube::Client;
+use kube::runtime::{controller, watcher};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::Duration;
+use tracing::warn;
+
+/// How long to wait for the API server before reporting it unreachable
+const APISERVER_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// State of a single component
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// Working, but not able to serve yet (fails readiness only)
+    NotReady(String),
+    /// Broken for good (fails liveness and readiness)
+    Failed(String),
+}
+
+type Check = Box<dyn Fn() -> Status + Send + Sync>;
+
+static CHECKS: once_cell::sync::Lazy<RwLock<BTreeMap<String, Check>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// Watch problems of controllers, by kind
+static WATCH_ERRORS: once_cell::sync::Lazy<RwLock<BTreeMap<String, WatchError>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+#[derive(Debug, Clone)]
+enum WatchError {
+    /// The watch fails with this error
+    Failing(String),
+    /// The resource type isn't installed in the cluster (e.g. no Flux or ArgoCD CRDs), so
+    /// there is nothing to watch
+    NotInstalled,
+}
+
+static CLIENT: RwLock<Option<Client>> = RwLock::new(None);
+
+/// Check API server connectivity with this client on readiness probes
+pub fn init(client: Client) {
+    *CLIENT.write().unwrap_or_else(|e| e.into_inner()) = Some(client);
+}
+
+/// Register (or replace) the check for a component. Checks run on every probe, so they must be
+/// cheap and must not block.
+pub fn register(name: impl Into<String>, check: impl Fn() -> Status + Send + Sync + 'static) {
+    CHECKS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.into(), Box::new(check));
+}
+
+/// Report a fixed status for a component
+pub fn set(name: impl Into<String>, status: Status) {
+    register(name, move || status.clone());
+}
+
+/// Record an item of a controller's result stream. Watch errors make the controller not ready
+/// until its stream produces anything else, unless the resource type isn't installed.
+pub fn track_controller<T, E>(
+    kind: &str,
+    result: &Result<T, controller::Error<E, watcher::Error>>,
+) {
+    match result {
+        Err(controller::Error::QueueError(e)) => {
+            let error = match e {
+                watcher::Error::InitialListFailed(kube::Error::Api(response))
+                    if response.code == 404 =>
+                {
+                    WatchError::NotInstalled
+                },
+                e => WatchError::Failing(e.to_string()),
+            };
+            WATCH_ERRORS
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(kind.to_string(), error);
+        },
+        _ => {
+            let failing = WATCH_ERRORS
+                .read()
+                .map(|errors| errors.contains_key(kind))
+                .unwrap_or(false);
+            if failing {
+                WATCH_ERRORS
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(kind);
+            }
+        },
+    }
+}
+
+/// Result of a single check in a probe response
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct CheckResult {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Probe response
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub status: &'static str,
+    pub checks: BTreeMap<String, CheckResult>,
+}
+
+impl Report {
+    /// Summarize component statuses. Liveness ignores components that are only not ready.
+    fn new(statuses: BTreeMap<String, Status>, readiness: bool) -> Self {
+        let mut healthy = true;
+        let checks = statuses
+            .into_iter()
+            .map(|(name, status)| {
+                let result = match status {
+                    Status::Ok => CheckResult {
+                        status: "ok",
+                        message: None,
+                    },
+                    Status::NotReady(message) => {
+                        healthy &= !readiness;
+                        CheckResult {
+                            status: "not ready",
+                            message: Some(message),
+                        }
+                    },
+                    Status::Failed(message) => {
+                        healthy = false;
+                        CheckResult {
+                            status: "failed",
+                            message: Some(message),
+                        }
+                    },
+                };
+                (name, result)
+            })
+            .collect();
+
+        Self {
+            status: if healthy { "ok" } else { "failed" },
+            checks,
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+fn statuses() -> BTreeMap<String, Status> {
+    let mut statuses: BTreeMap<String, Status> = CHECKS
+        .read()
+        .map(|checks| {
+            checks
+                .iter()
+                .map(|(name, check)| (name.clone(), check()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Ok(errors) = WATCH_ERRORS.read() {
+        for (kind, error) in errors.iter() {
+            let status = match error {
+                WatchError::Failing(error) => Status::NotReady(format!("watch failing: {}", error)),
+                WatchError::NotInstalled => Status::Ok,
+            };
+            statuses.insert(format!("watch/{}", kind), status);
+        }
+    }
+    statuses
+}
+
+async fn apiserver_status() -> Status {
+    let client = CLIENT.read().ok().and_then(|client| client.clone());
+    let Some(client) = client else {
+        return Status::NotReady("Kubernetes client not initialized".to_string());
+    };
+
+    match tokio::time::timeout(APISERVER_TIMEOUT, client.apiserver_version()).await {
+        Ok(Ok(_)) => Status::Ok,
+        Ok(Err(e)) => Status::NotReady(format!("API server unreachable: {}", e)),
+        Err(_) => Status::NotReady(format!(
+            "API server did not respond within {}s",
+            APISERVER_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Whether the operator should be restarted
+pub fn liveness() -> Report {
+    Report::new(statuses(), false)
+}
+
+/// Whether the operator can do its work
+pub async fn readiness() -> Report {
+    let mut statuses = statuses();
+    statuses.insert("apiserver".to_string(), apiserver_status().await);
+    Report::new(statuses, true)
+}
+
+fn respond(probe: &str, report: Report) -> (StatusCode, Json<Report>) {
+    if report.is_healthy() {
+        (StatusCode::OK, Json(report))
+    } else {
+        let failing: Vec<_> = report
+            .checks
+            .iter()
+            .filter(|(_, check)| check.status != "ok")
+            .map(|(name, check)| {
+                format!(
+                    "{} ({})",
+                    name,
+                    check.message.as_deref().unwrap_or_default()
+                )
+            })
+            .collect();
+        warn!("{} probe failed: {}", probe, failing.join(", "));
+        (StatusCode::SERVICE_UNAVAILABLE, Json(report))
+    }
+}
+
+/// `GET /livez`
+pub async fn livez() -> impl IntoResponse {
+    respond("Liveness", liveness())
+}
+
+/// `GET /readyz`
+pub async fn readyz() -> impl IntoResponse {
+    respond("Readiness", readiness().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses(entries: &[(&str, Status)]) -> BTreeMap<String, Status> {
+        entries
+            .iter()
+            .map(|(name, status)| (name.to_string(), status.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_not_ready_fails_readiness_only() {
+        let components = statuses(&[
+            ("events/images", Status::Ok),
+            ("watch/Deployment", Status::NotReady("initial list".into())),
+        ]);
+
+        assert!(Report::new(components.clone(), false).is_healthy());
+        let readiness = Report::new(components, true);
+        assert!(!readiness.is_healthy());
+        assert_eq!(readiness.checks["watch/Deployment"].status, "not ready");
+        assert_eq!(readiness.checks["events/images"].message, None);
+    }
+
+    #[test]
+    fn test_failed_fails_both() {
+        let components = statuses(&[
+            ("events/images", Status::Failed("processor exited".into())),
+            ("watch/Deployment", Status::Ok),
+        ]);
+
+        assert!(!Report::new(components.clone(), false).is_healthy());
+        assert!(!Report::new(components, true).is_healthy());
+    }
+
+    type Item = Result<(), controller::Error<kube::Error, watcher::Error>>;
+
+    fn list_failed(code: u16, reason: &str) -> controller::Error<kube::Error, watcher::Error> {
+        controller::Error::QueueError(watcher::Error::InitialListFailed(kube::Error::Api(
+            kube::core::ErrorResponse {
+                status: "Failure".to_string(),
+                message: reason.to_string(),
+                reason: reason.to_string(),
+                code,
+            },
+        )))
+    }
+
+    #[test]
+    fn test_watch_errors_until_stream_recovers() {
+        let kind = "TestWatchKind";
+
+        let failed: Item = Err(list_failed(403, "Forbidden"));
+        track_controller(kind, &failed);
+        let status = &super::statuses()["watch/TestWatchKind"];
+        assert!(
+            matches!(status, Status::NotReady(message) if message.contains("Forbidden")),
+            "{:?}",
+            status
+        );
+
+        let reconciled: Item = Ok(());
+        track_controller(kind, &reconciled);
+        assert!(!super::statuses().contains_key("watch/TestWatchKind"));
+    }
+
+    #[test]
+    fn test_missing_resource_type_is_not_a_failure() {
+        let kind = "TestMissingKind";
+        set(
+            "watch/TestMissingKind",
+            Status::NotReady("initial list not complete".into()),
+        );
+
+        let not_found: Item = Err(list_failed(404, "NotFound"));
+        track_controller(kind, &not_found);
+        assert_eq!(super::statuses()["watch/TestMissingKind"], Status::Ok);
+    }
+}
@@ -0,0 +1,99 @@
+//! Reading the gzipped tarballs charts and Flux source artifacts are packaged as.
+
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use std::io::Read;
+#[cfg(test)]
+use std::io::Write;
+
+/// Decompress a gzip stream
+pub fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    GzDecoder::new(data).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Files of a tar archive: 512-byte headers, each followed by the file's data padded to a
+/// multiple of 512 bytes
+pub fn tar_files(data: &[u8]) -> Vec<(String, &[u8])> {
+    let field = |bytes: &[u8]| {
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+    };
+
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while offset + 512 <= data.len() {
+        let header = &data[offset..offset + 512];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let Ok(size) = usize::from_str_radix(&field(&header[124..136]), 8) else {
+            break;
+        };
+        let start = offset + 512;
+        let Some(content) = data.get(start..start + size) else {
+            break;
+        };
+
+        // Regular files only; POSIX archives keep long paths in a prefix field
+        if matches!(header[156], b'0' | 0) {
+            let name = field(&header[..100]);
+            let prefix = field(&header[345..500]);
+            let path = if &header[257..263] == b"ustar\0" && !prefix.is_empty() {
+                format!("{}/{}", prefix, name)
+            } else {
+                name
+            };
+            files.push((path, content));
+        }
+        offset = start + size.div_ceil(512) * 512;
+    }
+    files
+}
+
+/// A gzipped tar archive of `files`
+#[cfg(test)]
+pub(crate) fn gzipped_tar(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut tar = Vec::new();
+    for (path, content) in files {
+        let mut header = [0u8; 512];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        let size = format!("{:011o}\0", content.len());
+        header[124..136].copy_from_slice(size.as_bytes());
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        tar.extend_from_slice(&header);
+        tar.extend_from_slice(content.as_bytes());
+        tar.resize(tar.len().div_ceil(512) * 512, 0);
+    }
+    tar.resize(tar.len() + 1024, 0);
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&tar).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tar_files() {
+        let archive = gzipped_tar(&[
+            ("podinfo/Chart.yaml", "name: podinfo\n"),
+            ("podinfo/values.yaml", &"x".repeat(600)),
+        ]);
+        let tar = gunzip(&archive).unwrap();
+        let files = tar_files(&tar);
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            files[0],
+            ("podinfo/Chart.yaml".to_string(), &b"name: podinfo\n"[..])
+        );
+        assert_eq!(files[1].0, "podinfo/values.yaml");
+        assert_eq!(files[1].1.len(), 600);
+
+        assert!(gunzip(b"not gzip").is_err());
+    }
+}
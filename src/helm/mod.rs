@@ -1,5 +1,12 @@
+pub mod archive;
 pub mod oci;
+pub mod release;
 pub mod repository;
+pub mod schema;
+pub mod source;
+pub mod values;
 
 pub use oci::OciHelmClient;
-pub use repository::{ChartEntry, HelmRepositoryClient, IndexYaml, RepositoryCredentials};
+pub use repository::{
+    ChartEntry, HelmRepositoryClient, IndexYaml, RepositoryAuth, RepositoryCredentials,
+};
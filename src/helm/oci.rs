@@ -1,9 +1,13 @@
+use crate::config::registries;
 use anyhow::{Context, Result};
 use oci_distribution::secrets::RegistryAuth;
 use oci_distribution::{Client, Reference};
 use std::str::FromStr;
 use tracing::{debug, warn};
 
+/// Media type of the layer holding a packaged chart
+const HELM_CHART_CONTENT_MEDIA_TYPE: &str = "application/vnd.cncf.helm.chart.content.v1.tar+gzip";
+
 /// OCI client for Helm charts stored in OCI registries
 pub struct OciHelmClient {
     client: Client,
@@ -43,9 +47,18 @@ impl OciHelmClient {
             reference.repository()
         );
 
+        // Registries with their own TLS settings get a dedicated client
+        let tls = registries::for_host(reference.registry());
+        let custom_client;
+        let client = if tls.is_default() {
+            &self.client
+        } else {
+            custom_client = Client::new(tls.oci_client_config(reference.registry()));
+            &custom_client
+        };
+
         // List tags
-        let tag_response = self
-            .client
+        let tag_response = client
             .list_tags(
                 &reference,
                 &auth.unwrap_or(RegistryAuth::Anonymous),
@@ -65,6 +78,42 @@ impl OciHelmClient {
         Ok(tag_response.tags)
     }
 
+    /// Pull the packaged chart (`.tgz` layer) of `version`
+    ///
+    /// oci_url format: oci://registry.example.com/repo/chart
+    pub async fn pull_chart(
+        &self,
+        oci_url: &str,
+        version: &str,
+        auth: RegistryAuth,
+    ) -> Result<Vec<u8>> {
+        let url = oci_url
+            .strip_prefix("oci://")
+            .ok_or_else(|| anyhow::anyhow!("OCI URL must start with oci://"))?;
+        let reference = Reference::from_str(&format!("{}:{}", url, version))
+            .context("Failed to parse OCI reference")?;
+
+        let tls = registries::for_host(reference.registry());
+        let custom_client;
+        let client = if tls.is_default() {
+            &self.client
+        } else {
+            custom_client = Client::new(tls.oci_client_config(reference.registry()));
+            &custom_client
+        };
+
+        let image = client
+            .pull(&reference, &auth, vec![HELM_CHART_CONTENT_MEDIA_TYPE])
+            .await
+            .context("Failed to pull chart")?;
+        image
+            .layers
+            .into_iter()
+            .find(|layer| layer.media_type == HELM_CHART_CONTENT_MEDIA_TYPE)
+            .map(|layer| layer.data.to_vec())
+            .ok_or_else(|| anyhow::anyhow!("{} has no chart layer", reference))
+    }
+
     /// Get all available versions for a Helm chart in OCI format
     pub async fn get_chart_versions(
         &self,
@@ -120,6 +169,14 @@ impl OciHelmClient {
             images: Vec::new(),
             event_source: Default::default(),
             polling_interval: None,
+            ignore_patterns: Vec::new(),
+            prerelease: Default::default(),
+            pin_digest: false,
+            channel_tag: None,
+            update_mode: crate::models::UpdateMode::Image,
+            approval_ttl: crate::models::DEFAULT_APPROVAL_TTL,
+            required_approvals: None,
+            max_version: None,
         };
 
         let mut valid_versions: Vec<String> = versions
@@ -0,0 +1,391 @@
+//! Releases installed by the Helm CLI.
+//!
+//! Helm 3 stores every revision of a release in a Secret of type `helm.sh/release.v1`,
+//! named `sh.helm.release.v1.<release>.v<revision>` and labeled `owner=helm`,
+//! `name=<release>`, `status=<status>`. Its `release` key holds the release as
+//! base64-encoded, gzipped JSON. Headwind upgrades such releases by running
+//! `helm upgrade` in a Job.
+
+use crate::config::plainhelm::PlainHelmRelease;
+use anyhow::{Context, Result};
+use base64::Engine;
+use flate2::read::GzDecoder;
+use k8s_openapi::api::batch::v1::{Job, JobSpec};
+use k8s_openapi::api::core::v1::{
+    Container, EnvVar, EnvVarSource, PodSpec, PodTemplateSpec, Secret, SecretKeySelector,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::Read;
+
+/// Secret type Helm 3 stores releases in
+pub const RELEASE_SECRET_TYPE: &str = "helm.sh/release.v1";
+
+/// Label selector for the deployed revision of every release
+pub const DEPLOYED_RELEASE_SELECTOR: &str = "owner=helm,status=deployed";
+
+/// Image running `helm upgrade` when HEADWIND_HELM_UPGRADE_IMAGE isn't set
+pub const DEFAULT_UPGRADE_IMAGE: &str = "alpine/helm:3.16.4";
+
+/// ServiceAccount upgrade Jobs run as when neither the release nor
+/// HEADWIND_HELM_UPGRADE_SERVICE_ACCOUNT names one
+pub const DEFAULT_UPGRADE_SERVICE_ACCOUNT: &str = "headwind-helm-upgrade";
+
+/// The parts of a Helm release Headwind reads
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub name: String,
+    pub namespace: String,
+    /// Revision number
+    pub version: u64,
+    pub info: ReleaseInfo,
+    pub chart: ReleaseChart,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseInfo {
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseChart {
+    pub metadata: ChartMetadata,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartMetadata {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub app_version: Option<String>,
+}
+
+impl Release {
+    /// Read the release stored in a Helm release Secret
+    pub fn from_secret(secret: &Secret) -> Result<Self> {
+        let data = secret
+            .data
+            .as_ref()
+            .and_then(|data| data.get("release"))
+            .ok_or_else(|| anyhow::anyhow!("Secret has no 'release' key"))?;
+        decode(&data.0)
+    }
+
+    /// `chart:version`, as UpdateRequests record chart versions
+    pub fn chart_ref(&self) -> String {
+        format!(
+            "{}:{}",
+            self.chart.metadata.name, self.chart.metadata.version
+        )
+    }
+}
+
+/// Decode the `release` value of a Helm release Secret: base64, then gzip (releases written
+/// by very old Helm 3 versions aren't compressed), then JSON
+pub fn decode(encoded: &[u8]) -> Result<Release> {
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim_ascii())
+        .context("Release is not base64")?;
+
+    let json = if compressed.starts_with(&[0x1f, 0x8b]) {
+        let mut json = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut json)
+            .context("Release is not valid gzip")?;
+        json
+    } else {
+        compressed
+    };
+
+    serde_json::from_slice(&json).context("Release is not valid JSON")
+}
+
+/// Image running `helm upgrade` (HEADWIND_HELM_UPGRADE_IMAGE)
+pub fn upgrade_image() -> String {
+    std::env::var("HEADWIND_HELM_UPGRADE_IMAGE")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_UPGRADE_IMAGE.to_string())
+}
+
+/// ServiceAccount upgrade Jobs run as by default (HEADWIND_HELM_UPGRADE_SERVICE_ACCOUNT)
+pub fn upgrade_service_account() -> String {
+    std::env::var("HEADWIND_HELM_UPGRADE_SERVICE_ACCOUNT")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_UPGRADE_SERVICE_ACCOUNT.to_string())
+}
+
+/// `helm upgrade` arguments moving a release to `version` of its chart, keeping the values
+/// it was installed with
+pub fn upgrade_args(
+    namespace: &str,
+    release: &str,
+    chart: &str,
+    version: &str,
+    source: &PlainHelmRelease,
+) -> Vec<String> {
+    let repository = source.repository.trim_end_matches('/');
+    let mut args = vec!["upgrade".to_string(), release.to_string()];
+
+    if repository.starts_with("oci://") {
+        args.push(format!("{}/{}", repository, chart));
+    } else {
+        args.extend([
+            chart.to_string(),
+            "--repo".to_string(),
+            repository.to_string(),
+        ]);
+    }
+    args.extend(
+        [
+            "--version",
+            version,
+            "--namespace",
+            namespace,
+            "--reuse-values",
+        ]
+        .map(str::to_string),
+    );
+
+    if source.secret_ref.is_some() {
+        // Expanded by the kubelet from the container environment
+        args.extend(
+            [
+                "--username",
+                "$(HELM_USERNAME)",
+                "--password",
+                "$(HELM_PASSWORD)",
+            ]
+            .map(str::to_string),
+        );
+    }
+
+    let tls = crate::config::registries::for_host(crate::config::registries::url_host(repository));
+    if tls.insecure_skip_verify {
+        args.push("--insecure-skip-tls-verify".to_string());
+    }
+    if tls.plain_http {
+        args.push("--plain-http".to_string());
+    }
+
+    args
+}
+
+/// Job running `helm upgrade` for a release, in the release's namespace
+pub fn upgrade_job(
+    namespace: &str,
+    release: &str,
+    chart: &str,
+    version: &str,
+    source: &PlainHelmRelease,
+) -> Job {
+    let env = source.secret_ref.as_ref().map(|secret| {
+        ["username", "password"]
+            .into_iter()
+            .map(|key| EnvVar {
+                name: format!("HELM_{}", key.to_uppercase()),
+                value_from: Some(EnvVarSource {
+                    secret_key_ref: Some(SecretKeySelector {
+                        name: secret.clone(),
+                        key: key.to_string(),
+                        optional: None,
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .collect()
+    });
+
+    let labels = BTreeMap::from([
+        (
+            "app.kubernetes.io/managed-by".to_string(),
+            "headwind".to_string(),
+        ),
+        ("headwind.sh/helm-release".to_string(), release.to_string()),
+    ]);
+
+    Job {
+        metadata: ObjectMeta {
+            name: Some(upgrade_job_name(release, version)),
+            namespace: Some(namespace.to_string()),
+            labels: Some(labels.clone()),
+            ..Default::default()
+        },
+        spec: Some(JobSpec {
+            backoff_limit: Some(0),
+            ttl_seconds_after_finished: Some(24 * 60 * 60),
+            template: PodTemplateSpec {
+                metadata: Some(ObjectMeta {
+                    labels: Some(labels),
+                    ..Default::default()
+                }),
+                spec: Some(PodSpec {
+                    service_account_name: Some(
+                        source
+                            .service_account_name
+                            .clone()
+                            .unwrap_or_else(upgrade_service_account),
+                    ),
+                    restart_policy: Some("Never".to_string()),
+                    containers: vec![Container {
+                        name: "helm".to_string(),
+                        image: Some(upgrade_image()),
+                        args: Some(upgrade_args(namespace, release, chart, version, source)),
+                        env,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+            },
+            ..Default::default()
+        }),
+        status: None,
+    }
+}
+
+/// Deterministic Job name, so an upgrade to the same version is only started once
+fn upgrade_job_name(release: &str, version: &str) -> String {
+    let name: String = format!("helm-upgrade-{}-{}", release, version)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let name = &name[..name.len().min(63)];
+    name.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
+
+    const RELEASE_JSON: &str = r#"{
+        "name": "prometheus",
+        "namespace": "monitoring",
+        "version": 3,
+        "info": { "status": "deployed" },
+        "chart": { "metadata": { "name": "kube-prometheus-stack", "version": "55.5.0", "appVersion": "v0.70.0" } },
+        "config": { "grafana": { "enabled": false } }
+    }"#;
+
+    fn encode(json: &[u8], gzip: bool) -> Vec<u8> {
+        let payload = if gzip {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(json).unwrap();
+            encoder.finish().unwrap()
+        } else {
+            json.to_vec()
+        };
+        base64::engine::general_purpose::STANDARD
+            .encode(payload)
+            .into_bytes()
+    }
+
+    fn source(repository: &str, secret_ref: Option<&str>) -> PlainHelmRelease {
+        PlainHelmRelease {
+            repository: repository.to_string(),
+            secret_ref: secret_ref.map(str::to_string),
+            service_account_name: None,
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_decode_release() {
+        for gzip in [true, false] {
+            let release = decode(&encode(RELEASE_JSON.as_bytes(), gzip)).unwrap();
+            assert_eq!(release.name, "prometheus");
+            assert_eq!(release.namespace, "monitoring");
+            assert_eq!(release.version, 3);
+            assert_eq!(release.info.status, "deployed");
+            assert_eq!(release.chart_ref(), "kube-prometheus-stack:55.5.0");
+            assert_eq!(
+                release.chart.metadata.app_version.as_deref(),
+                Some("v0.70.0")
+            );
+        }
+
+        assert!(decode(b"not base64!").is_err());
+        assert!(decode(&encode(b"{}", true)).is_err());
+    }
+
+    #[test]
+    fn test_upgrade_args() {
+        let http = upgrade_args(
+            "monitoring",
+            "prometheus",
+            "kube-prometheus-stack",
+            "56.0.0",
+            &source("https://prometheus-community.github.io/helm-charts/", None),
+        );
+        assert_eq!(
+            http.join(" "),
+            "upgrade prometheus kube-prometheus-stack \
+             --repo https://prometheus-community.github.io/helm-charts \
+             --version 56.0.0 --namespace monitoring --reuse-values"
+        );
+
+        let oci = upgrade_args(
+            "apps",
+            "podinfo",
+            "podinfo",
+            "6.5.0",
+            &source("oci://ghcr.io/stefanprodan/charts", Some("ghcr-auth")),
+        );
+        assert_eq!(
+            oci.join(" "),
+            "upgrade podinfo oci://ghcr.io/stefanprodan/charts/podinfo \
+             --version 6.5.0 --namespace apps --reuse-values \
+             --username $(HELM_USERNAME) --password $(HELM_PASSWORD)"
+        );
+    }
+
+    #[test]
+    fn test_upgrade_job() {
+        let job = upgrade_job(
+            "apps",
+            "podinfo",
+            "podinfo",
+            "6.5.0",
+            &source("oci://ghcr.io/stefanprodan/charts", Some("ghcr-auth")),
+        );
+
+        assert_eq!(
+            job.metadata.name.as_deref(),
+            Some("helm-upgrade-podinfo-6-5-0")
+        );
+        assert_eq!(job.metadata.namespace.as_deref(), Some("apps"));
+        let pod = job.spec.unwrap().template.spec.unwrap();
+        assert_eq!(pod.restart_policy.as_deref(), Some("Never"));
+        assert_eq!(
+            pod.service_account_name.as_deref(),
+            Some(DEFAULT_UPGRADE_SERVICE_ACCOUNT)
+        );
+        let env = pod.containers[0].env.as_ref().unwrap();
+        assert_eq!(env.len(), 2);
+        assert_eq!(
+            env[0]
+                .value_from
+                .as_ref()
+                .and_then(|v| v.secret_key_ref.as_ref())
+                .map(|s| (s.name.as_str(), s.key.as_str())),
+            Some(("ghcr-auth", "username"))
+        );
+    }
+
+    #[test]
+    fn test_upgrade_job_name() {
+        assert_eq!(
+            upgrade_job_name("my_app", "1.2.3+build.1"),
+            "helm-upgrade-my-app-1-2-3-build-1"
+        );
+        let long = upgrade_job_name(&"a".repeat(70), "1.0.0");
+        assert_eq!(long.len(), 63);
+        assert!(!long.ends_with('-'));
+    }
+}
@@ -1,9 +1,12 @@
+use crate::config::registries::{self, RegistryTls};
+use crate::models::HelmRepositorySpec;
 use anyhow::{Context, Result};
+use k8s_openapi::ByteString;
 use k8s_openapi::api::core::v1::Secret;
 use kube::Api;
-use reqwest::Client;
+use reqwest::{Certificate, Client, Identity};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Duration;
 use tracing::{debug, warn};
 
@@ -44,6 +47,66 @@ pub struct RepositoryCredentials {
     pub password: String,
 }
 
+/// Authentication and TLS settings for a Helm repository, read from a Flux HelmRepository's
+/// `secretRef` (basic auth, optionally TLS) and `certSecretRef` (TLS)
+#[derive(Debug, Clone, Default)]
+pub struct RepositoryAuth {
+    pub basic: Option<RepositoryCredentials>,
+    /// PEM client certificate followed by its private key
+    pub client_identity: Option<Vec<u8>>,
+    /// PEM CA certificates trusted in addition to the system roots
+    pub ca_bundle: Option<Vec<u8>>,
+    /// Skip TLS certificate verification
+    pub insecure_skip_verify: bool,
+}
+
+impl RepositoryAuth {
+    /// Take the keys Flux understands from a Secret: `username`/`password`, `tls.crt`/`tls.key`
+    /// and `ca.crt` (or the deprecated `certFile`/`keyFile`/`caFile`)
+    pub fn apply_secret(&mut self, data: &BTreeMap<String, ByteString>) {
+        let get = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| data.get(*key))
+                .map(|v| v.0.clone())
+        };
+
+        if let (Some(username), Some(password)) = (get(&["username"]), get(&["password"])) {
+            self.basic = Some(RepositoryCredentials {
+                username: String::from_utf8_lossy(&username).into_owned(),
+                password: String::from_utf8_lossy(&password).into_owned(),
+            });
+        }
+        if let (Some(mut cert), Some(key)) =
+            (get(&["tls.crt", "certFile"]), get(&["tls.key", "keyFile"]))
+        {
+            cert.push(b'\n');
+            cert.extend(key);
+            self.client_identity = Some(cert);
+        }
+        if let Some(ca) = get(&["ca.crt", "caFile"]) {
+            self.ca_bundle = Some(ca);
+        }
+    }
+
+    /// Layer the `registries` ConfigMap settings for the repository host on top: its CA is
+    /// trusted alongside the Secret's, and either may skip verification
+    pub fn with_registry_tls(mut self, tls: &RegistryTls) -> Self {
+        if let Some(ca) = &tls.ca {
+            let bundle = self.ca_bundle.get_or_insert_with(Vec::new);
+            if !bundle.is_empty() {
+                bundle.push(b'\n');
+            }
+            bundle.extend_from_slice(ca.as_bytes());
+        }
+        self.insecure_skip_verify |= tls.insecure_skip_verify;
+        self
+    }
+
+    fn has_tls(&self) -> bool {
+        self.client_identity.is_some() || self.ca_bundle.is_some() || self.insecure_skip_verify
+    }
+}
+
 /// Client for querying Helm chart repositories
 pub struct HelmRepositoryClient {
     client: Client,
@@ -65,17 +128,14 @@ impl HelmRepositoryClient {
     }
 
     pub async fn with_kube_client() -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent("headwind/0.1.0")
-            .build()
-            .context("Failed to create HTTP client")?;
-
-        let kube_client = kube::Client::try_default().await?;
+        Self::with_client(kube::Client::try_default().await?)
+    }
 
+    /// Create a client that reads repository Secrets with an existing Kubernetes client
+    pub fn with_client(kube_client: kube::Client) -> Result<Self> {
         Ok(Self {
-            client,
             kube_client: Some(kube_client),
+            ..Self::new()?
         })
     }
 
@@ -115,95 +175,166 @@ impl HelmRepositoryClient {
         Ok(RepositoryCredentials { username, password })
     }
 
-    /// Fetch and parse the index.yaml from a Helm repository
-    pub async fn fetch_index(&self, repo_url: &str) -> Result<IndexYaml> {
-        let index_url = if repo_url.ends_with('/') {
-            format!("{}index.yaml", repo_url)
-        } else {
-            format!("{}/index.yaml", repo_url)
-        };
+    /// Read the credentials and TLS material referenced by a HelmRepository. A missing or
+    /// unreadable Secret is an error, so a private repository isn't queried anonymously.
+    pub async fn read_repository_auth(
+        &self,
+        namespace: &str,
+        spec: &HelmRepositorySpec,
+    ) -> Result<RepositoryAuth> {
+        let mut auth = RepositoryAuth::default();
+        let secret_names = [&spec.secret_ref, &spec.cert_secret_ref];
+        if secret_names.iter().all(|name| name.is_none()) {
+            return Ok(auth);
+        }
+
+        let kube_client = self
+            .kube_client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Kubernetes client not initialized"))?;
+        let secrets: Api<Secret> = Api::namespaced(kube_client.clone(), namespace);
+        for secret_ref in secret_names.into_iter().flatten() {
+            let secret = secrets.get(&secret_ref.name).await.context(format!(
+                "Failed to get secret {}/{}",
+                namespace, secret_ref.name
+            ))?;
+            auth.apply_secret(&secret.data.unwrap_or_default());
+        }
+        Ok(auth)
+    }
 
+    /// Fetch index.yaml from a Helm repository with the given credentials and TLS settings,
+    /// returning the raw document
+    pub async fn fetch_index_body(&self, repo_url: &str, auth: &RepositoryAuth) -> Result<String> {
+        let index_url = format!("{}/index.yaml", repo_url.trim_end_matches('/'));
         debug!("Fetching Helm repository index from: {}", index_url);
 
+        let auth = &auth
+            .clone()
+            .with_registry_tls(&registries::for_host(registries::url_host(repo_url)));
         let response = self
-            .client
-            .get(&index_url)
+            .get(&index_url, auth)?
             .send()
             .await
             .context("Failed to fetch index.yaml")?;
-
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
-                "HTTP {} fetching index.yaml from {}",
+                "HTTP {} fetching index.yaml from {}{}",
                 response.status(),
-                index_url
+                index_url,
+                if auth.basic.is_some() {
+                    " (with auth)"
+                } else {
+                    ""
+                }
             ));
         }
 
-        let body = response
+        response
             .text()
             .await
-            .context("Failed to read index.yaml response")?;
-
-        let index: IndexYaml = serde_yaml::from_str(&body).context("Failed to parse index.yaml")?;
-
-        debug!(
-            "Successfully parsed index.yaml with {} charts",
-            index.entries.len()
-        );
-
-        Ok(index)
+            .context("Failed to read index.yaml response")
     }
 
-    /// Fetch index with basic authentication
-    pub async fn fetch_index_with_auth(
+    /// Download a packaged chart (`.tgz`) listed in the index of `repo_url`. Relative chart
+    /// URLs are resolved against the repository; credentials are only sent to its host.
+    pub async fn fetch_chart_archive(
         &self,
         repo_url: &str,
-        username: &str,
-        password: &str,
-    ) -> Result<IndexYaml> {
-        let index_url = if repo_url.ends_with('/') {
-            format!("{}index.yaml", repo_url)
+        chart_url: &str,
+        auth: &RepositoryAuth,
+    ) -> Result<Vec<u8>> {
+        let chart_url = if chart_url.contains("://") {
+            chart_url.to_string()
         } else {
-            format!("{}/index.yaml", repo_url)
+            format!(
+                "{}/{}",
+                repo_url.trim_end_matches('/'),
+                chart_url.trim_start_matches('/')
+            )
         };
+        debug!("Downloading chart from: {}", chart_url);
 
-        debug!(
-            "Fetching Helm repository index with auth from: {}",
-            index_url
-        );
-
+        let host = registries::url_host(&chart_url);
+        let mut auth = auth.clone();
+        if host != registries::url_host(repo_url) {
+            auth.basic = None;
+        }
+        let auth = auth.with_registry_tls(&registries::for_host(host));
         let response = self
-            .client
-            .get(&index_url)
-            .basic_auth(username, Some(password))
+            .get(&chart_url, &auth)?
             .send()
             .await
-            .context("Failed to fetch index.yaml with auth")?;
-
+            .context("Failed to download chart")?;
         if !response.status().is_success() {
             return Err(anyhow::anyhow!(
-                "HTTP {} fetching index.yaml from {} (with auth)",
+                "HTTP {} downloading {}",
                 response.status(),
-                index_url
+                chart_url
             ));
         }
 
-        let body = response
-            .text()
+        Ok(response
+            .bytes()
             .await
-            .context("Failed to read index.yaml response")?;
+            .context("Failed to read chart")?
+            .to_vec())
+    }
+
+    /// GET request with the repository's credentials and TLS settings
+    fn get(&self, url: &str, auth: &RepositoryAuth) -> Result<reqwest::RequestBuilder> {
+        let client = if auth.has_tls() {
+            tls_client(auth)?
+        } else {
+            self.client.clone()
+        };
+        let mut request = client.get(url);
+        if let Some(creds) = &auth.basic {
+            request = request.basic_auth(&creds.username, Some(&creds.password));
+        }
+        Ok(request)
+    }
 
+    /// Fetch and parse index.yaml with the given credentials and TLS settings
+    pub async fn fetch_index_authenticated(
+        &self,
+        repo_url: &str,
+        auth: &RepositoryAuth,
+    ) -> Result<IndexYaml> {
+        let body = self.fetch_index_body(repo_url, auth).await?;
         let index: IndexYaml = serde_yaml::from_str(&body).context("Failed to parse index.yaml")?;
 
         debug!(
-            "Successfully parsed index.yaml with {} charts (authenticated)",
+            "Successfully parsed index.yaml with {} charts",
             index.entries.len()
         );
 
         Ok(index)
     }
 
+    /// Fetch and parse the index.yaml from a Helm repository
+    pub async fn fetch_index(&self, repo_url: &str) -> Result<IndexYaml> {
+        self.fetch_index_authenticated(repo_url, &RepositoryAuth::default())
+            .await
+    }
+
+    /// Fetch index with basic authentication
+    pub async fn fetch_index_with_auth(
+        &self,
+        repo_url: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<IndexYaml> {
+        let auth = RepositoryAuth {
+            basic: Some(RepositoryCredentials {
+                username: username.to_string(),
+                password: password.to_string(),
+            }),
+            ..Default::default()
+        };
+        self.fetch_index_authenticated(repo_url, &auth).await
+    }
+
     /// Get all available versions for a specific chart
     pub fn get_chart_versions(&self, index: &IndexYaml, chart_name: &str) -> Vec<String> {
         index
@@ -250,6 +381,14 @@ impl HelmRepositoryClient {
                     images: Vec::new(),
                     event_source: Default::default(),
                     polling_interval: None,
+                    ignore_patterns: Vec::new(),
+                    prerelease: Default::default(),
+                    pin_digest: false,
+                    channel_tag: None,
+                    update_mode: crate::models::UpdateMode::Image,
+                    approval_ttl: crate::models::DEFAULT_APPROVAL_TTL,
+                    required_approvals: None,
+                    max_version: None,
                 };
 
                 match policy_engine.should_update(&resource_policy, current_version, v) {
@@ -289,6 +428,26 @@ impl HelmRepositoryClient {
     }
 }
 
+/// HTTP client presenting the repository's client certificate and trusting its CA
+fn tls_client(auth: &RepositoryAuth) -> Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .user_agent("headwind/0.1.0");
+    if let Some(ca_bundle) = &auth.ca_bundle {
+        for certificate in Certificate::from_pem_bundle(ca_bundle).context("Invalid CA bundle")? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if auth.insecure_skip_verify {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    if let Some(identity) = &auth.client_identity {
+        builder = builder
+            .identity(Identity::from_pem(identity).context("Invalid client certificate or key")?);
+    }
+    builder.build().context("Failed to create HTTP client")
+}
+
 impl Default for HelmRepositoryClient {
     fn default() -> Self {
         Self::new().expect("Failed to create default HelmRepositoryClient")
@@ -299,6 +458,63 @@ impl Default for HelmRepositoryClient {
 mod tests {
     use super::*;
 
+    fn secret_data(pairs: &[(&str, &str)]) -> BTreeMap<String, ByteString> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), ByteString(v.as_bytes().to_vec())))
+            .collect()
+    }
+
+    #[test]
+    fn test_repository_auth_from_secrets() {
+        let mut auth = RepositoryAuth::default();
+        assert!(!auth.has_tls());
+
+        auth.apply_secret(&secret_data(&[("username", "bot"), ("password", "s3cret")]));
+        let basic = auth.basic.as_ref().unwrap();
+        assert_eq!(
+            (basic.username.as_str(), basic.password.as_str()),
+            ("bot", "s3cret")
+        );
+        assert!(!auth.has_tls());
+
+        auth.apply_secret(&secret_data(&[
+            ("tls.crt", "CERT"),
+            ("tls.key", "KEY"),
+            ("ca.crt", "CA"),
+        ]));
+        assert_eq!(auth.client_identity.as_deref(), Some(&b"CERT\nKEY"[..]));
+        assert_eq!(auth.ca_bundle.as_deref(), Some(&b"CA"[..]));
+        assert!(auth.basic.is_some());
+
+        // Deprecated keys, and a certificate without its key is ignored
+        let mut auth = RepositoryAuth::default();
+        auth.apply_secret(&secret_data(&[("caFile", "CA"), ("certFile", "CERT")]));
+        assert_eq!(auth.ca_bundle.as_deref(), Some(&b"CA"[..]));
+        assert!(auth.client_identity.is_none());
+        assert!(auth.basic.is_none());
+    }
+
+    #[test]
+    fn test_repository_auth_with_registry_tls() {
+        let auth = RepositoryAuth::default().with_registry_tls(&RegistryTls::default());
+        assert!(!auth.has_tls());
+
+        let mut auth = RepositoryAuth::default();
+        auth.apply_secret(&secret_data(&[("ca.crt", "SECRET CA")]));
+        let auth = auth.with_registry_tls(&RegistryTls {
+            ca: Some("CONFIG CA".to_string()),
+            insecure_skip_verify: true,
+            plain_http: false,
+        });
+        assert_eq!(
+            auth.ca_bundle.as_deref(),
+            Some(&b"SECRET CA\nCONFIG CA"[..])
+        );
+        assert!(auth.insecure_skip_verify);
+        assert!(auth.has_tls());
+    }
+
     #[test]
     fn test_parse_index_yaml() {
         let yaml = r#"
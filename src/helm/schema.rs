@@ -0,0 +1,634 @@
+//! Values schema checks for HelmRelease upgrades.
+//!
+//! Helm validates a release's values against the chart's `values.schema.json` on every
+//! upgrade, so a chart version with a stricter schema only shows up as a failed release.
+//! Before a HelmRelease is moved to a new chart version, the packaged chart is downloaded
+//! from its HelmRepository, its `values.yaml` is merged with the release's values the way
+//! Helm does, and the result is checked against the schema. `HEADWIND_HELM_VALUES_SCHEMA`
+//! decides what a mismatch does: `enforce` (default) fails the update, `warn` only logs it
+//! and `off` skips the check.
+//!
+//! The JSON Schema keywords charts use are supported: `type`, `enum`, `const`,
+//! `properties`, `patternProperties`, `additionalProperties`, `required`, `items`,
+//! `minItems`/`maxItems`, `minLength`/`maxLength`, `pattern`, `minimum`/`maximum` (and their
+//! exclusive forms), `allOf`/`anyOf`/`oneOf`/`not`, `if`/`then`/`else` and local `$ref`s.
+//! Other keywords are ignored.
+
+use super::{HelmRepositoryClient, OciHelmClient, archive};
+use crate::metrics::HELM_VALUES_SCHEMA_VIOLATIONS;
+use crate::models::{HelmRelease, HelmRepository};
+use anyhow::{Context, Result, anyhow};
+use kube::{Api, Client, ResourceExt};
+use oci_distribution::secrets::RegistryAuth;
+use regex::Regex;
+use serde_json::{Map, Value, json};
+use tracing::{debug, warn};
+
+/// What a values schema mismatch does to a chart upgrade (`HEADWIND_HELM_VALUES_SCHEMA`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaCheck {
+    /// Fail the update
+    #[default]
+    Enforce,
+    /// Log the mismatch and update anyway
+    Warn,
+    /// Don't check
+    Off,
+}
+
+impl SchemaCheck {
+    pub fn from_env() -> Self {
+        match std::env::var("HEADWIND_HELM_VALUES_SCHEMA")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "warn" => Self::Warn,
+            "off" => Self::Off,
+            _ => Self::Enforce,
+        }
+    }
+}
+
+/// Check the values of `helm_release` against the values schema of the chart `version` it is
+/// about to be upgraded to. A chart that can't be downloaded doesn't block the upgrade.
+pub async fn check_upgrade(
+    client: &Client,
+    helm_release: &HelmRelease,
+    version: &str,
+) -> Result<()> {
+    let mode = SchemaCheck::from_env();
+    if mode == SchemaCheck::Off {
+        return Ok(());
+    }
+
+    let namespace = helm_release.namespace().unwrap_or_default();
+    let name = helm_release.name_any();
+    let chart = &helm_release.spec.chart.spec.chart;
+    let archive = match fetch_chart(client, helm_release, version).await {
+        Ok(Some(archive)) => archive,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            warn!(
+                "Couldn't download chart {} {} to check the values of HelmRelease {}/{}: {:#}",
+                chart, version, namespace, name, e
+            );
+            return Ok(());
+        },
+    };
+    let violations = match chart_violations(&archive, helm_release.spec.values.as_ref()) {
+        Ok(violations) => violations,
+        Err(e) => {
+            warn!(
+                "Couldn't read the values schema of chart {} {}: {:#}",
+                chart, version, e
+            );
+            return Ok(());
+        },
+    };
+    if violations.is_empty() {
+        debug!(
+            "Values of HelmRelease {}/{} match the schema of {} {}",
+            namespace, name, chart, version
+        );
+        return Ok(());
+    }
+
+    HELM_VALUES_SCHEMA_VIOLATIONS
+        .with_label_values(&[&namespace])
+        .inc();
+    let message = format!(
+        "Values of HelmRelease {}/{} don't match the values schema of {} {}: {}",
+        namespace,
+        name,
+        chart,
+        version,
+        violations.join("; ")
+    );
+    match mode {
+        SchemaCheck::Enforce => Err(anyhow!(message)),
+        _ => {
+            warn!("{}", message);
+            Ok(())
+        },
+    }
+}
+
+/// Download the packaged chart of `helm_release` at `version` from its HelmRepository.
+/// Other sources (GitRepository, Bucket) don't serve packaged charts and return None.
+pub async fn fetch_chart(
+    client: &Client,
+    helm_release: &HelmRelease,
+    version: &str,
+) -> Result<Option<Vec<u8>>> {
+    let chart = &helm_release.spec.chart.spec;
+    if chart.source_ref.kind != "HelmRepository" {
+        return Ok(None);
+    }
+
+    let namespace = helm_release.namespace().unwrap_or_default();
+    let repo_namespace = chart.source_ref.namespace.as_deref().unwrap_or(&namespace);
+    let repo = Api::<HelmRepository>::namespaced(client.clone(), repo_namespace)
+        .get(&chart.source_ref.name)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to get HelmRepository {}/{}",
+                repo_namespace, chart.source_ref.name
+            )
+        })?;
+    let repositories = HelmRepositoryClient::with_client(client.clone())?;
+    let auth = repositories
+        .read_repository_auth(repo_namespace, &repo.spec)
+        .await?;
+
+    if repo.spec.url.starts_with("oci://") {
+        let url = format!("{}/{}", repo.spec.url.trim_end_matches('/'), chart.chart);
+        let auth = match &auth.basic {
+            Some(creds) => RegistryAuth::Basic(creds.username.clone(), creds.password.clone()),
+            None => RegistryAuth::Anonymous,
+        };
+        return OciHelmClient::new()
+            .pull_chart(&url, version, auth)
+            .await
+            .map(Some);
+    }
+
+    let index = repositories
+        .fetch_index_authenticated(&repo.spec.url, &auth)
+        .await?;
+    let url = index
+        .entries
+        .get(&chart.chart)
+        .and_then(|entries| entries.iter().find(|entry| entry.version == version))
+        .and_then(|entry| entry.urls.first())
+        .ok_or_else(|| {
+            anyhow!(
+                "{} {} is not in the index of {}",
+                chart.chart,
+                version,
+                repo.spec.url
+            )
+        })?;
+    repositories
+        .fetch_chart_archive(&repo.spec.url, url, &auth)
+        .await
+        .map(Some)
+}
+
+/// Violations of a packaged chart's values schema by `values` merged over the chart's
+/// defaults. Charts without a schema have none.
+pub fn chart_violations(archive: &[u8], values: Option<&Value>) -> Result<Vec<String>> {
+    let tar = archive::gunzip(archive).context("Chart is not valid gzip")?;
+
+    let mut defaults = None;
+    let mut schema = None;
+    for (path, data) in archive::tar_files(&tar) {
+        // Only the chart's own files, not those of its subcharts
+        match path.split('/').collect::<Vec<_>>()[..] {
+            [_, "values.yaml"] => {
+                defaults =
+                    Some(serde_yaml::from_slice::<Value>(data).context("Invalid values.yaml")?)
+            },
+            [_, "values.schema.json"] => {
+                schema = Some(
+                    serde_json::from_slice::<Value>(data).context("Invalid values.schema.json")?,
+                )
+            },
+            _ => {},
+        }
+    }
+
+    let Some(schema) = schema else {
+        return Ok(Vec::new());
+    };
+    let mut merged = defaults
+        .filter(Value::is_object)
+        .unwrap_or_else(|| json!({}));
+    if let Some(values) = values {
+        merge_values(&mut merged, values);
+    }
+    Ok(validate(&schema, &merged))
+}
+
+/// Merge release values over chart defaults the way Helm coalesces them: maps are merged
+/// key by key, anything else replaces the default, and `null` removes it
+pub fn merge_values(defaults: &mut Value, values: &Value) {
+    match (defaults, values) {
+        (Value::Object(defaults), Value::Object(values)) => {
+            for (key, value) in values {
+                if value.is_null() {
+                    defaults.remove(key);
+                    continue;
+                }
+                match defaults.get_mut(key) {
+                    Some(existing) if existing.is_object() && value.is_object() => {
+                        merge_values(existing, value)
+                    },
+                    _ => {
+                        defaults.insert(key.clone(), value.clone());
+                    },
+                }
+            }
+        },
+        (defaults, values) => *defaults = values.clone(),
+    }
+}
+
+/// Violations of `schema` by `value`, each starting with the path of the offending value
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut violations = Vec::new();
+    Validator { root: schema }.check(schema, value, "$", &mut violations);
+    violations
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+}
+
+impl Validator<'_> {
+    fn passes(&self, schema: &Value, value: &Value) -> bool {
+        let mut violations = Vec::new();
+        self.check(schema, value, "$", &mut violations);
+        violations.is_empty()
+    }
+
+    fn check(&self, schema: &Value, value: &Value, path: &str, out: &mut Vec<String>) {
+        let schema = match schema {
+            Value::Object(schema) => schema,
+            Value::Bool(false) => {
+                out.push(format!("{}: not allowed", path));
+                return;
+            },
+            _ => return,
+        };
+
+        // `#/definitions/...` and the like; remote references aren't followed
+        if let Some(target) = schema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix('#'))
+            .and_then(|pointer| self.root.pointer(pointer))
+        {
+            self.check(target, value, path, out);
+        }
+
+        if let Some(types) = schema.get("type") {
+            let allowed: Vec<&str> = match types {
+                Value::String(type_) => vec![type_.as_str()],
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.is_empty() && !allowed.iter().any(|type_| has_type(value, type_)) {
+                out.push(format!(
+                    "{}: expected {}, got {}",
+                    path,
+                    allowed.join(" or "),
+                    type_name(value)
+                ));
+                return;
+            }
+        }
+        if let Some(Value::Array(allowed)) = schema.get("enum")
+            && !allowed.contains(value)
+        {
+            out.push(format!(
+                "{}: must be one of {}",
+                path,
+                Value::from(allowed.clone())
+            ));
+        }
+        if let Some(constant) = schema.get("const")
+            && constant != value
+        {
+            out.push(format!("{}: must be {}", path, constant));
+        }
+
+        match value {
+            Value::Object(map) => self.check_object(schema, map, path, out),
+            Value::Array(items) => {
+                if let Some(items_schema) = schema.get("items").filter(|s| !s.is_array()) {
+                    for (i, item) in items.iter().enumerate() {
+                        self.check(items_schema, item, &format!("{}[{}]", path, i), out);
+                    }
+                }
+                check_bounds(
+                    schema,
+                    "minItems",
+                    "maxItems",
+                    items.len(),
+                    "items",
+                    path,
+                    out,
+                );
+            },
+            Value::String(s) => {
+                check_bounds(
+                    schema,
+                    "minLength",
+                    "maxLength",
+                    s.chars().count(),
+                    "characters",
+                    path,
+                    out,
+                );
+                if let Some(pattern) = schema.get("pattern").and_then(Value::as_str)
+                    && let Ok(re) = Regex::new(pattern)
+                    && !re.is_match(s)
+                {
+                    out.push(format!("{}: must match {}", path, pattern));
+                }
+            },
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                let limit = |key: &str| schema.get(key).and_then(Value::as_f64);
+                if let Some(min) = limit("minimum")
+                    && n < min
+                {
+                    out.push(format!("{}: must be at least {}", path, min));
+                }
+                if let Some(max) = limit("maximum")
+                    && n > max
+                {
+                    out.push(format!("{}: must be at most {}", path, max));
+                }
+                if let Some(min) = limit("exclusiveMinimum")
+                    && n <= min
+                {
+                    out.push(format!("{}: must be greater than {}", path, min));
+                }
+                if let Some(max) = limit("exclusiveMaximum")
+                    && n >= max
+                {
+                    out.push(format!("{}: must be less than {}", path, max));
+                }
+            },
+            _ => {},
+        }
+
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            for sub in schemas {
+                self.check(sub, value, path, out);
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("anyOf")
+            && !schemas.iter().any(|sub| self.passes(sub, value))
+        {
+            out.push(format!(
+                "{}: doesn't match any of the allowed schemas",
+                path
+            ));
+        }
+        if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+            let matching = schemas.iter().filter(|sub| self.passes(sub, value)).count();
+            if matching != 1 {
+                out.push(format!(
+                    "{}: must match exactly one schema, matches {}",
+                    path, matching
+                ));
+            }
+        }
+        if let Some(not) = schema.get("not")
+            && self.passes(not, value)
+        {
+            out.push(format!("{}: matches a schema it must not", path));
+        }
+        if let Some(condition) = schema.get("if") {
+            let branch = if self.passes(condition, value) {
+                schema.get("then")
+            } else {
+                schema.get("else")
+            };
+            if let Some(branch) = branch {
+                self.check(branch, value, path, out);
+            }
+        }
+    }
+
+    fn check_object(
+        &self,
+        schema: &Map<String, Value>,
+        map: &Map<String, Value>,
+        path: &str,
+        out: &mut Vec<String>,
+    ) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns: Vec<(Regex, &Value)> = schema
+            .get("patternProperties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(pattern, sub)| Some((Regex::new(pattern).ok()?, sub)))
+            .collect();
+
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !map.contains_key(name) {
+                    out.push(format!("{}: missing required property {}", path, name));
+                }
+            }
+        }
+
+        for (key, value) in map {
+            let key_path = format!("{}.{}", path, key);
+            let mut known = false;
+            if let Some(sub) = properties.and_then(|properties| properties.get(key)) {
+                known = true;
+                self.check(sub, value, &key_path, out);
+            }
+            for (re, sub) in &patterns {
+                if re.is_match(key) {
+                    known = true;
+                    self.check(sub, value, &key_path, out);
+                }
+            }
+            if !known {
+                match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        out.push(format!("{}: property {} is not allowed", path, key))
+                    },
+                    Some(additional @ Value::Object(_)) => {
+                        self.check(additional, value, &key_path, out)
+                    },
+                    _ => {},
+                }
+            }
+        }
+    }
+}
+
+/// `min` and `max` bounds on a length
+fn check_bounds(
+    schema: &Map<String, Value>,
+    min: &str,
+    max: &str,
+    len: usize,
+    unit: &str,
+    path: &str,
+    out: &mut Vec<String>,
+) {
+    if let Some(min) = schema.get(min).and_then(Value::as_u64)
+        && (len as u64) < min
+    {
+        out.push(format!("{}: must have at least {} {}", path, min, unit));
+    }
+    if let Some(max) = schema.get(max).and_then(Value::as_u64)
+        && (len as u64) > max
+    {
+        out.push(format!("{}: must have at most {} {}", path, max, unit));
+    }
+}
+
+fn has_type(value: &Value, type_: &str) -> bool {
+    match type_ {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        },
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helm::archive::gzipped_tar;
+
+    const SCHEMA: &str = r##"{
+        "type": "object",
+        "required": ["image"],
+        "properties": {
+            "image": {
+                "type": "object",
+                "required": ["repository"],
+                "properties": {
+                    "repository": { "type": "string" },
+                    "pullPolicy": { "enum": ["Always", "IfNotPresent", "Never"] }
+                }
+            },
+            "replicas": { "type": "integer", "minimum": 1 },
+            "ingress": { "$ref": "#/definitions/ingress" }
+        },
+        "definitions": {
+            "ingress": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": { "enabled": { "type": "boolean" }, "host": { "type": "string" } }
+            }
+        }
+    }"##;
+
+    #[test]
+    fn test_validate() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let valid = json!({
+            "image": { "repository": "nginx", "pullPolicy": "Always" },
+            "replicas": 2,
+            "ingress": { "enabled": true }
+        });
+        assert!(validate(&schema, &valid).is_empty());
+
+        let invalid = json!({
+            "image": { "pullPolicy": "Sometimes" },
+            "replicas": 0,
+            "ingress": { "enabled": "yes", "hosts": [] }
+        });
+        let violations = validate(&schema, &invalid);
+        assert_eq!(
+            violations,
+            vec![
+                "$.image: missing required property repository",
+                "$.image.pullPolicy: must be one of [\"Always\",\"IfNotPresent\",\"Never\"]",
+                "$.ingress.enabled: expected boolean, got string",
+                "$.ingress: property hosts is not allowed",
+                "$.replicas: must be at least 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combinators() {
+        let schema = json!({
+            "oneOf": [{ "type": "string" }, { "type": "integer" }],
+            "not": { "const": "" }
+        });
+        assert!(validate(&schema, &json!("a")).is_empty());
+        assert!(validate(&schema, &json!(3)).is_empty());
+        assert_eq!(validate(&schema, &json!(true)).len(), 1);
+        assert_eq!(validate(&schema, &json!("")).len(), 1);
+
+        let schema = json!({
+            "if": { "properties": { "enabled": { "const": true } } },
+            "then": { "required": ["host"] }
+        });
+        assert!(validate(&schema, &json!({ "enabled": false })).is_empty());
+        assert_eq!(validate(&schema, &json!({ "enabled": true })).len(), 1);
+    }
+
+    #[test]
+    fn test_merge_values() {
+        let mut defaults = json!({
+            "image": { "repository": "nginx", "tag": "1.25" },
+            "resources": { "limits": { "cpu": "1" } },
+            "replicas": 1
+        });
+        merge_values(
+            &mut defaults,
+            &json!({ "image": { "tag": "1.26" }, "resources": null, "replicas": 3 }),
+        );
+        assert_eq!(
+            defaults,
+            json!({ "image": { "repository": "nginx", "tag": "1.26" }, "replicas": 3 })
+        );
+    }
+
+    #[test]
+    fn test_chart_violations() {
+        let archive = gzipped_tar(&[
+            ("web/Chart.yaml", "name: web\nversion: 2.0.0\n"),
+            (
+                "web/values.yaml",
+                "image:\n  repository: nginx\nreplicas: 1\n",
+            ),
+            ("web/values.schema.json", SCHEMA),
+            (
+                "web/charts/redis/values.schema.json",
+                r#"{"required": ["x"]}"#,
+            ),
+        ]);
+
+        // The chart's defaults satisfy required properties the release doesn't set
+        assert!(
+            chart_violations(&archive, Some(&json!({ "replicas": 3 })))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            chart_violations(&archive, Some(&json!({ "replicas": "3" }))).unwrap(),
+            vec!["$.replicas: expected integer, got string"]
+        );
+
+        let without_schema = gzipped_tar(&[("web/values.yaml", "replicas: 1\n")]);
+        assert!(
+            chart_violations(&without_schema, Some(&json!({ "replicas": "3" })))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Chart versions of HelmReleases built from a Flux GitRepository or Bucket.
+//!
+//! Such charts aren't published to a repository: source-controller packs the source into a
+//! tarball artifact and builds the chart from the directory `spec.chart.spec.chart` points at.
+//! With `HEADWIND_HELM_SOURCE_ARTIFACTS=true` the artifact is downloaded from the URL in the
+//! source's status and the chart version is read from that directory's `Chart.yaml`.
+
+use super::{HelmRepositoryClient, RepositoryAuth, archive};
+use crate::models::HelmRelease;
+use anyhow::{Context, Result, anyhow, bail};
+use kube::api::{ApiResource, DynamicObject, GroupVersionKind};
+use kube::{Api, Client, ResourceExt};
+use serde::Deserialize;
+
+/// API group of Flux's source-controller
+const SOURCE_GROUP: &str = "source.toolkit.fluxcd.io";
+
+/// Source kinds whose artifact holds the chart's files
+pub const ARTIFACT_SOURCE_KINDS: &[&str] = &["GitRepository", "Bucket"];
+
+/// Whether chart versions of GitRepository and Bucket sources are tracked
+/// (HEADWIND_HELM_SOURCE_ARTIFACTS, default: false)
+pub fn source_artifacts_enabled() -> bool {
+    std::env::var("HEADWIND_HELM_SOURCE_ARTIFACTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Whether new chart versions of `helm_release` can be discovered: always for HelmRepository
+/// sources, for GitRepository and Bucket sources when source artifacts are tracked
+pub fn chart_versions_tracked(helm_release: &HelmRelease) -> bool {
+    match helm_release.spec.chart.spec.source_ref.kind.as_str() {
+        "HelmRepository" => true,
+        kind => ARTIFACT_SOURCE_KINDS.contains(&kind) && source_artifacts_enabled(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ChartMetadata {
+    version: String,
+}
+
+/// Version of the chart `helm_release` builds from its GitRepository or Bucket, at the
+/// source's current revision
+pub async fn artifact_chart_version(
+    client: &Client,
+    repositories: &HelmRepositoryClient,
+    helm_release: &HelmRelease,
+) -> Result<String> {
+    let chart = &helm_release.spec.chart.spec;
+    let source_ref = &chart.source_ref;
+    if !ARTIFACT_SOURCE_KINDS.contains(&source_ref.kind.as_str()) {
+        bail!("{} sources have no chart artifact", source_ref.kind);
+    }
+
+    let namespace = helm_release.namespace().unwrap_or_default();
+    let source_namespace = source_ref.namespace.as_deref().unwrap_or(&namespace);
+    let resource =
+        ApiResource::from_gvk(&GroupVersionKind::gvk(SOURCE_GROUP, "v1", &source_ref.kind));
+    let source = Api::<DynamicObject>::namespaced_with(client.clone(), source_namespace, &resource)
+        .get(&source_ref.name)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to get {} {}/{}",
+                source_ref.kind, source_namespace, source_ref.name
+            )
+        })?;
+    let url = source.data["status"]["artifact"]["url"]
+        .as_str()
+        .ok_or_else(|| {
+            anyhow!(
+                "{} {}/{} has no artifact yet",
+                source_ref.kind,
+                source_namespace,
+                source_ref.name
+            )
+        })?;
+
+    let artifact = repositories
+        .fetch_chart_archive(url, url, &RepositoryAuth::default())
+        .await?;
+    chart_version(&artifact, &chart.chart)
+}
+
+/// Version in `Chart.yaml` of the chart at `chart_path` in a source artifact
+pub fn chart_version(artifact: &[u8], chart_path: &str) -> Result<String> {
+    let tar = archive::gunzip(artifact).context("Artifact is not valid gzip")?;
+    let chart_dir = normalize(chart_path);
+    let chart_yaml = if chart_dir.is_empty() {
+        "Chart.yaml".to_string()
+    } else {
+        format!("{}/Chart.yaml", chart_dir)
+    };
+
+    let (_, data) = archive::tar_files(&tar)
+        .into_iter()
+        .find(|(path, _)| normalize(path) == chart_yaml)
+        .ok_or_else(|| anyhow!("No {} in the source artifact", chart_yaml))?;
+    let metadata: ChartMetadata =
+        serde_yaml::from_slice(data).with_context(|| format!("Invalid {}", chart_yaml))?;
+    Ok(metadata.version)
+}
+
+/// Path without `./` and surrounding slashes, as charts are referenced as `./charts/app`
+fn normalize(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helm::archive::gzipped_tar;
+
+    #[test]
+    fn test_chart_version() {
+        let artifact = gzipped_tar(&[
+            ("README.md", "# platform\n"),
+            (
+                "charts/podinfo/Chart.yaml",
+                "apiVersion: v2\nname: podinfo\nversion: 6.5.4\n",
+            ),
+            (
+                "charts/podinfo/charts/redis/Chart.yaml",
+                "name: redis\nversion: 18.0.0\n",
+            ),
+            ("./charts/web/Chart.yaml", "name: web\nversion: 1.2.0\n"),
+        ]);
+
+        assert_eq!(
+            chart_version(&artifact, "./charts/podinfo").unwrap(),
+            "6.5.4"
+        );
+        assert_eq!(
+            chart_version(&artifact, "charts/podinfo/").unwrap(),
+            "6.5.4"
+        );
+        assert_eq!(chart_version(&artifact, "./charts/web").unwrap(), "1.2.0");
+        assert!(chart_version(&artifact, "./charts/missing").is_err());
+
+        let root = gzipped_tar(&[("Chart.yaml", "name: app\nversion: 0.1.0\n")]);
+        assert_eq!(chart_version(&root, "./").unwrap(), "0.1.0");
+    }
+}
@@ -0,0 +1,186 @@
+//! Container images pinned in HelmRelease values.
+//!
+//! The `headwind.sh/values-image-paths` annotation lists dotted paths into
+//! `spec.values`, e.g. `.image` or `.image, .worker.image`. Each path points to either an
+//! image string (`ghcr.io/org/app:1.2.3`) or an object in the common chart layout:
+//!
+//! ```yaml
+//! image:
+//!   registry: ghcr.io      # optional
+//!   repository: org/app
+//!   tag: 1.2.3
+//! ```
+//!
+//! Updates replace the string, or the object's `tag`, leaving the rest of the values alone.
+
+use crate::controller::workload::split_image;
+use anyhow::Result;
+use serde_json::{Value, json};
+
+/// An image found at a values path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValuesImage {
+    /// Dotted path without the leading dot, e.g. `worker.image`
+    pub path: String,
+    /// Full image (`repo:tag`)
+    pub image: String,
+}
+
+/// Parse the `headwind.sh/values-image-paths` annotation value
+pub fn parse_paths(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|p| p.trim().trim_start_matches('.').to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Images found at `paths`. Paths that don't resolve to an image with a tag are skipped,
+/// since the tag the chart defaults to isn't known.
+pub fn find_images(values: Option<&Value>, paths: &[String]) -> Vec<ValuesImage> {
+    let Some(values) = values else {
+        return Vec::new();
+    };
+    paths
+        .iter()
+        .filter_map(|path| {
+            Some(ValuesImage {
+                path: path.clone(),
+                image: image_at(values, path)?,
+            })
+        })
+        .collect()
+}
+
+/// Image at a values path
+pub fn image_at(values: &Value, path: &str) -> Option<String> {
+    match values.pointer(&pointer(path))? {
+        Value::String(image) if !image.is_empty() => Some(image.clone()),
+        Value::Object(fields) => {
+            let repository = fields.get("repository")?.as_str()?;
+            let tag = scalar(fields.get("tag")?)?;
+            Some(match fields.get("registry").and_then(Value::as_str) {
+                Some(registry) if !registry.is_empty() => {
+                    format!("{}/{}:{}", registry.trim_end_matches('/'), repository, tag)
+                },
+                _ => format!("{}:{}", repository, tag),
+            })
+        },
+        _ => None,
+    }
+}
+
+/// JSON patch for a HelmRelease that sets the image at a values path to `new_image`
+pub fn image_patch(values: Option<&Value>, path: &str, new_image: &str) -> Result<Value> {
+    let node = values
+        .and_then(|values| values.pointer(&pointer(path)))
+        .ok_or_else(|| anyhow::anyhow!("No image at values path .{}", path))?;
+
+    let op = match node {
+        Value::String(_) => json!({
+            "op": "replace",
+            "path": format!("/spec/values{}", pointer(path)),
+            "value": new_image
+        }),
+        Value::Object(fields) if fields.contains_key("tag") => {
+            let (_, tag) = split_image(new_image)
+                .ok_or_else(|| anyhow::anyhow!("Image {} has no tag", new_image))?;
+            json!({
+                "op": "replace",
+                "path": format!("/spec/values{}/tag", pointer(path)),
+                "value": tag
+            })
+        },
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Values path .{} is neither an image string nor an object with a tag",
+                path
+            ));
+        },
+    };
+
+    Ok(json!([op]))
+}
+
+/// JSON pointer for a dotted values path
+fn pointer(path: &str) -> String {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Tags are sometimes written unquoted, so YAML parses them as numbers
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Value {
+        json!({
+            "image": { "repository": "org/app", "tag": "1.2.3" },
+            "worker": {
+                "image": { "registry": "ghcr.io", "repository": "org/worker", "tag": 2.1 }
+            },
+            "proxy": { "image": "envoyproxy/envoy:v1.30.0" },
+            "untagged": { "repository": "org/untagged" }
+        })
+    }
+
+    #[test]
+    fn test_parse_paths() {
+        assert_eq!(
+            parse_paths(".image, worker.image,,"),
+            vec!["image".to_string(), "worker.image".to_string()]
+        );
+        assert!(parse_paths(" ").is_empty());
+    }
+
+    #[test]
+    fn test_find_images() {
+        let values = values();
+        let paths = parse_paths(".image,.worker.image,.proxy.image,.untagged,.missing");
+        let images = find_images(Some(&values), &paths);
+
+        assert_eq!(
+            images
+                .iter()
+                .map(|i| (i.path.as_str(), i.image.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("image", "org/app:1.2.3"),
+                ("worker.image", "ghcr.io/org/worker:2.1"),
+                ("proxy.image", "envoyproxy/envoy:v1.30.0"),
+            ]
+        );
+        assert!(find_images(None, &paths).is_empty());
+    }
+
+    #[test]
+    fn test_image_patch() {
+        let values = values();
+
+        assert_eq!(
+            image_patch(Some(&values), "image", "org/app:1.3.0").unwrap(),
+            json!([{ "op": "replace", "path": "/spec/values/image/tag", "value": "1.3.0" }])
+        );
+        assert_eq!(
+            image_patch(Some(&values), "proxy.image", "envoyproxy/envoy:v1.31.0").unwrap(),
+            json!([{
+                "op": "replace",
+                "path": "/spec/values/proxy/image",
+                "value": "envoyproxy/envoy:v1.31.0"
+            }])
+        );
+        assert!(image_patch(Some(&values), "untagged", "org/untagged:1.0").is_err());
+        assert!(image_patch(Some(&values), "missing", "org/app:1.0").is_err());
+        assert!(image_patch(None, "image", "org/app:1.0").is_err());
+    }
+}
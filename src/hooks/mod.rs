@@ -0,0 +1,294 @@
+//! Update hooks: HTTP endpoints Headwind calls with an UpdateRequest right before applying
+//! it, and again once it has completed or failed. A pre-update hook vetoes the update by
+//! answering with anything but 2xx; post-update hooks are informational.
+//!
+//! Hook URLs come from the `headwind.sh/pre-update-hook` and `headwind.sh/post-update-hook`
+//! annotations on the UpdateRequest's target, falling back to HEADWIND_PRE_UPDATE_HOOK_URL
+//! and HEADWIND_POST_UPDATE_HOOK_URL.
+
+use crate::metrics::{UPDATE_HOOK_ERRORS, UPDATE_HOOK_VETOES};
+use crate::models::crd::{TargetRef, UpdatePhase, UpdateRequest};
+use crate::models::policy::annotations;
+use anyhow::{Context, Result, anyhow};
+use chrono::Utc;
+use kube::{
+    Api, Client,
+    api::{ApiResource, DynamicObject, GroupVersionKind},
+};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Header naming the hook being called (`pre-update` or `post-update`)
+pub const HOOK_HEADER: &str = "X-Headwind-Hook";
+
+/// Header carrying the payload signature when HEADWIND_HOOK_SECRET is set
+pub const SIGNATURE_HEADER: &str = "X-Headwind-Signature";
+
+/// How long a hook may take when HEADWIND_HOOK_TIMEOUT isn't set
+pub const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPhase {
+    PreUpdate,
+    PostUpdate,
+}
+
+impl HookPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookPhase::PreUpdate => "pre-update",
+            HookPhase::PostUpdate => "post-update",
+        }
+    }
+
+    fn annotation(&self) -> &'static str {
+        match self {
+            HookPhase::PreUpdate => annotations::PRE_UPDATE_HOOK,
+            HookPhase::PostUpdate => annotations::POST_UPDATE_HOOK,
+        }
+    }
+
+    fn env_var(&self) -> &'static str {
+        match self {
+            HookPhase::PreUpdate => "HEADWIND_PRE_UPDATE_HOOK_URL",
+            HookPhase::PostUpdate => "HEADWIND_POST_UPDATE_HOOK_URL",
+        }
+    }
+}
+
+/// URL of a hook: the target's annotation, or else the global setting
+pub fn hook_url(phase: HookPhase, annotations: &BTreeMap<String, String>) -> Option<String> {
+    annotations
+        .get(phase.annotation())
+        .cloned()
+        .or_else(|| std::env::var(phase.env_var()).ok())
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// How long Headwind waits for a hook (HEADWIND_HOOK_TIMEOUT, seconds)
+fn timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("HEADWIND_HOOK_TIMEOUT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs| secs > 0)
+            .unwrap_or(DEFAULT_TIMEOUT_SECONDS),
+    )
+}
+
+/// Signature of a hook payload, in the format of webhook notifications
+fn signature(secret: &str, body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(secret.as_bytes());
+    hasher.update(body.as_bytes());
+    format!("sha256={}", hex::encode(hasher.finalize()))
+}
+
+/// POST the UpdateRequest to a hook. Fails on transport errors and non-2xx answers.
+pub async fn call(url: &str, phase: HookPhase, update_request: &UpdateRequest) -> Result<()> {
+    let body = serde_json::to_string(update_request).context("Failed to serialize payload")?;
+
+    let http = reqwest::Client::builder()
+        .timeout(timeout())
+        .build()
+        .context("Failed to create HTTP client")?;
+    let mut request = http
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header(HOOK_HEADER, phase.as_str());
+    if let Some(secret) = std::env::var("HEADWIND_HOOK_SECRET")
+        .ok()
+        .filter(|s| !s.is_empty())
+    {
+        request = request.header(SIGNATURE_HEADER, signature(&secret, &body));
+    }
+
+    let response = request
+        .body(body)
+        .send()
+        .await
+        .with_context(|| format!("{} hook {} failed", phase.as_str(), url))?;
+
+    let status = response.status();
+    if status.is_success() {
+        debug!("{} hook {} answered {}", phase.as_str(), url, status);
+        return Ok(());
+    }
+
+    let text = response.text().await.unwrap_or_default();
+    let reason = text.trim().chars().take(200).collect::<String>();
+    Err(anyhow!(
+        "{} hook {} answered {}{}",
+        phase.as_str(),
+        url,
+        status,
+        if reason.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", reason)
+        }
+    ))
+}
+
+/// Call the pre-update hook of an UpdateRequest, if any. An error vetoes the update.
+pub async fn pre_update(client: &Client, update_request: &UpdateRequest) -> Result<()> {
+    let annotations = target_annotations(client, &update_request.spec.target_ref).await;
+    let Some(url) = hook_url(HookPhase::PreUpdate, &annotations) else {
+        return Ok(());
+    };
+
+    if let Err(e) = call(&url, HookPhase::PreUpdate, update_request).await {
+        UPDATE_HOOK_VETOES.inc();
+        warn!(
+            "Update of {} {}/{} vetoed: {:#}",
+            update_request.spec.target_ref.kind,
+            update_request.spec.target_ref.namespace,
+            update_request.spec.target_ref.name,
+            e
+        );
+        return Err(anyhow!("Vetoed by {:#}", e));
+    }
+
+    info!(
+        "Pre-update hook allowed update of {} {}/{} to {}",
+        update_request.spec.target_ref.kind,
+        update_request.spec.target_ref.namespace,
+        update_request.spec.target_ref.name,
+        update_request.spec.new_image
+    );
+    Ok(())
+}
+
+/// Call the post-update hook of an UpdateRequest, if any, with the outcome in its status.
+/// Failures are logged; the update has already happened.
+pub async fn post_update(client: &Client, update_request: &UpdateRequest, result: &Result<()>) {
+    let annotations = target_annotations(client, &update_request.spec.target_ref).await;
+    let Some(url) = hook_url(HookPhase::PostUpdate, &annotations) else {
+        return;
+    };
+
+    let payload = with_outcome(update_request, result);
+    if let Err(e) = call(&url, HookPhase::PostUpdate, &payload).await {
+        UPDATE_HOOK_ERRORS.inc();
+        warn!("{:#}", e);
+    }
+}
+
+/// The UpdateRequest with its status set to the outcome of applying it
+fn with_outcome(update_request: &UpdateRequest, result: &Result<()>) -> UpdateRequest {
+    let mut payload = update_request.clone();
+    let mut status = payload.status.take().unwrap_or_default();
+    let (phase, message) = match result {
+        Ok(()) => (
+            UpdatePhase::Completed,
+            "Update applied successfully".to_string(),
+        ),
+        Err(e) => (UpdatePhase::Failed, format!("Update failed: {}", e)),
+    };
+    status.phase = phase;
+    status.message = Some(message);
+    status.last_updated = Some(Utc::now());
+    payload.status = Some(status);
+    payload
+}
+
+/// Annotations of a target resource. Helm CLI releases have no object of their own, so the
+/// annotations configured for them are used. Targets that can't be read have none.
+pub async fn target_annotations(client: &Client, target: &TargetRef) -> BTreeMap<String, String> {
+    if target.kind == crate::controller::plainhelm::KIND {
+        return crate::config::plainhelm::for_release(&target.namespace, &target.name)
+            .map(|release| release.annotations)
+            .unwrap_or_default();
+    }
+
+    let (group, version) = target
+        .api_version
+        .split_once('/')
+        .unwrap_or(("", &target.api_version));
+    let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(group, version, &target.kind));
+    let api: Api<DynamicObject> =
+        Api::namespaced_with(client.clone(), &target.namespace, &resource);
+
+    match api.get(&target.name).await {
+        Ok(object) => object.metadata.annotations.unwrap_or_default(),
+        Err(e) => {
+            debug!(
+                "Failed to read annotations of {} {}/{}: {}",
+                target.kind, target.namespace, target.name, e
+            );
+            BTreeMap::new()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{UpdatePolicyType, UpdateRequestSpec, UpdateType};
+
+    fn update_request() -> UpdateRequest {
+        UpdateRequest::new(
+            "web-1-5-0",
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "web".to_string(),
+                    namespace: "production".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: Some("web".to_string()),
+                image_parameter: None,
+                current_image: "web:1.4.0".to_string(),
+                new_image: "web:1.5.0".to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at: None,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_hook_url_prefers_annotation() {
+        let annotations = BTreeMap::from([(
+            annotations::PRE_UPDATE_HOOK.to_string(),
+            " https://cab.example.com/check ".to_string(),
+        )]);
+
+        assert_eq!(
+            hook_url(HookPhase::PreUpdate, &annotations).as_deref(),
+            Some("https://cab.example.com/check")
+        );
+    }
+
+    #[test]
+    fn test_with_outcome() {
+        let completed = with_outcome(&update_request(), &Ok(()));
+        let status = completed.status.unwrap();
+        assert_eq!(status.phase, UpdatePhase::Completed);
+
+        let failed = with_outcome(&update_request(), &Err(anyhow!("image pull failed")));
+        let status = failed.status.unwrap();
+        assert_eq!(status.phase, UpdatePhase::Failed);
+        assert_eq!(
+            status.message.as_deref(),
+            Some("Update failed: image pull failed")
+        );
+    }
+
+    #[test]
+    fn test_signature_matches_webhook_format() {
+        let signature = signature("secret", "{}");
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+    }
+}
@@ -3,23 +3,42 @@
 // This file exposes internal modules for integration tests while keeping
 // the binary entrypoint in main.rs
 
+pub mod admission;
 pub mod approval;
+pub mod audit;
+pub mod cache;
 pub mod config;
 pub mod controller;
+pub mod eventbus;
+pub mod freeze;
+pub mod gitops;
+pub mod health;
 pub mod helm;
+pub mod hooks;
+pub mod lint;
 pub mod metrics;
 pub mod models;
 pub mod notifications;
+pub mod openapi;
+pub mod ordering;
+pub mod platforms;
 pub mod policy;
 pub mod polling;
+pub mod pubsub;
+pub mod releasenotes;
 pub mod rollback;
+pub mod scanning;
+pub mod schedule;
+pub mod server;
+pub mod telemetry;
+pub mod tickets;
 pub mod ui;
 pub mod webhook;
 
 // Re-export commonly used types for testing
 pub use models::crd::UpdateRequest;
 pub use models::policy::{ResourcePolicy, UpdatePolicy};
-pub use models::webhook::{DockerHubWebhook, ImagePushEvent, RegistryWebhook};
+pub use models::webhook::{DockerHubWebhook, GhcrWebhook, ImagePushEvent, RegistryWebhook};
 
 // Helper functions for testing
 /// Convenience function for testing policy engine
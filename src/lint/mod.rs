@@ -0,0 +1,382 @@
+//! Annotation linter: a report of every workload and HelmRelease whose `headwind.sh/*`
+//! annotations are invalid or contradict each other or the running configuration.
+//!
+//! Served by the approval API at `GET /api/v1/lint` and by `kubectl headwind lint`. Each
+//! resource goes through the same checks as the
+//! [annotation admission webhook](crate::admission::annotations), plus checks that need more
+//! than the annotations themselves:
+//!
+//! - `event-source: webhook` while Headwind has no source of push events
+//! - `event-source: polling` or `both` while registry polling is disabled
+//! - `required-approvals` without `require-approval`
+//! - `canary` or `auto-rollback` on kinds other than Deployment
+//! - only one of `gitops-repository` and `gitops-path`
+//! - `rollout-after` naming a workload that doesn't exist
+
+use crate::admission::annotations::{Finding, Severity, validate_annotations};
+use crate::controller::stores;
+use crate::metrics::{PUBSUB_MESSAGES_RECEIVED, WEBHOOK_EVENTS_TOTAL};
+use crate::models::HelmRelease;
+use crate::models::policy::{EventSource, annotations, parse_required_approvals};
+use crate::ordering::parse_dependencies;
+use anyhow::{Context, Result};
+use k8s_openapi::NamespaceResourceScope;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use k8s_openapi::api::batch::v1::CronJob;
+use kube::{Client, Resource, ResourceExt};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use tracing::debug;
+
+/// Kinds that are linted
+const KINDS: &[&str] = &[
+    "Deployment",
+    "StatefulSet",
+    "DaemonSet",
+    "CronJob",
+    "HelmRelease",
+];
+
+/// Filters for `GET /api/v1/lint`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LintQuery {
+    /// Only resources in this namespace
+    #[serde(default, deserialize_with = "crate::audit::empty_as_none")]
+    pub namespace: Option<String>,
+}
+
+/// A problem with an annotation of one resource
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LintIssue {
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+    /// Annotation key
+    pub annotation: String,
+    pub severity: Severity,
+    /// What is wrong with it
+    pub message: String,
+}
+
+/// Result of linting the cluster
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LintReport {
+    /// Resources with `headwind.sh/` annotations that were checked
+    pub resources: usize,
+    /// Number of issues Headwind can't work with
+    pub errors: usize,
+    /// Number of issues that are probably not what was meant
+    pub warnings: usize,
+    /// Issues, grouped by resource
+    pub issues: Vec<LintIssue>,
+}
+
+/// What the linter knows about the running Headwind. Checks whose facts are unknown (None)
+/// are skipped, as when `kubectl headwind lint` runs without the approval API.
+#[derive(Debug, Clone, Default)]
+pub struct LintContext {
+    /// Whether registry polling is enabled
+    pub polling_enabled: Option<bool>,
+    /// Whether push events can arrive: a registry webhook or Pub/Sub message was received
+    /// since startup, or an event bus is configured
+    pub push_events: Option<bool>,
+}
+
+impl LintContext {
+    /// Facts about this process
+    pub fn current() -> Self {
+        let webhook_events: u64 = ["registry", "dockerhub", "ghcr"]
+            .iter()
+            .map(|endpoint| WEBHOOK_EVENTS_TOTAL.with_label_values(&[endpoint]).get())
+            .sum();
+        let push_events = webhook_events + PUBSUB_MESSAGES_RECEIVED.get() > 0
+            || crate::eventbus::EventBusConfig::from_env().is_some();
+
+        Self {
+            polling_enabled: Some(
+                std::env::var("HEADWIND_POLLING_ENABLED")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(false),
+            ),
+            push_events: Some(push_events),
+        }
+    }
+}
+
+/// `(kind, namespace, name)` of a resource
+type ResourceKey = (String, String, String);
+
+/// Lint every workload and HelmRelease with `headwind.sh/` annotations in the watched
+/// namespaces, or only in `namespace`
+pub async fn lint_cluster(
+    client: &Client,
+    namespace: Option<&str>,
+    context: &LintContext,
+) -> Result<LintReport> {
+    let mut resources: Vec<(ResourceKey, BTreeMap<String, String>)> = Vec::new();
+    collect::<Deployment>(client, "Deployment", &mut resources)
+        .await
+        .context("Failed to list Deployments")?;
+    collect::<StatefulSet>(client, "StatefulSet", &mut resources)
+        .await
+        .context("Failed to list StatefulSets")?;
+    collect::<DaemonSet>(client, "DaemonSet", &mut resources)
+        .await
+        .context("Failed to list DaemonSets")?;
+    collect::<CronJob>(client, "CronJob", &mut resources)
+        .await
+        .context("Failed to list CronJobs")?;
+    match collect::<HelmRelease>(client, "HelmRelease", &mut resources).await {
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            debug!("HelmRelease CRD not installed, skipping HelmReleases");
+        },
+        result => result.context("Failed to list HelmReleases")?,
+    }
+
+    let existing: BTreeSet<ResourceKey> = resources.iter().map(|(key, _)| key.clone()).collect();
+    let mut report = LintReport::default();
+    for ((kind, ns, name), annotations) in &resources {
+        if namespace.is_some_and(|namespace| namespace != ns)
+            || !annotations.keys().any(|k| k.starts_with("headwind.sh/"))
+        {
+            continue;
+        }
+        report.resources += 1;
+        for finding in lint_annotations(kind, ns, annotations, context, Some(&existing)) {
+            report.issues.push(LintIssue {
+                kind: kind.clone(),
+                namespace: ns.clone(),
+                name: name.clone(),
+                annotation: finding.annotation,
+                severity: finding.severity,
+                message: finding.message,
+            });
+        }
+    }
+
+    report.errors = report
+        .issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    report.warnings = report.issues.len() - report.errors;
+    Ok(report)
+}
+
+/// Annotations of every `K` (all of them, not only annotated ones, so that `rollout-after`
+/// targets can be looked up)
+async fn collect<K>(
+    client: &Client,
+    kind: &str,
+    resources: &mut Vec<(ResourceKey, BTreeMap<String, String>)>,
+) -> kube::Result<()>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + Debug + 'static,
+    K::DynamicType: Default + Eq + std::hash::Hash + Clone,
+{
+    let items = stores::list::<K>(client).await?;
+    resources.extend(items.into_iter().map(|resource| {
+        let key = (
+            kind.to_string(),
+            resource.namespace().unwrap_or_default(),
+            resource.name_any(),
+        );
+        (key, resource.annotations().clone())
+    }));
+    Ok(())
+}
+
+/// Problems with the annotations of a `kind` in `namespace`. `existing` holds every resource
+/// of the linted kinds; without it `rollout-after` targets aren't looked up.
+pub fn lint_annotations(
+    kind: &str,
+    namespace: &str,
+    annotations: &BTreeMap<String, String>,
+    context: &LintContext,
+    existing: Option<&BTreeSet<ResourceKey>>,
+) -> Vec<Finding> {
+    let mut findings = validate_annotations(annotations, namespace);
+    let is_true = |key: &str| annotations.get(key).is_some_and(|v| v == "true");
+
+    match annotations
+        .get(annotations::EVENT_SOURCE)
+        .and_then(|v| v.parse::<EventSource>().ok())
+    {
+        Some(EventSource::Webhook) if context.push_events == Some(false) => {
+            findings.push(Finding::warning(
+                annotations::EVENT_SOURCE,
+                "no registry webhook or Pub/Sub message was received since Headwind started and no event bus is configured; use polling or both if the registry can't reach Headwind",
+            ))
+        },
+        Some(source @ (EventSource::Polling | EventSource::Both))
+            if context.polling_enabled == Some(false) =>
+        {
+            findings.push(Finding::warning(
+                annotations::EVENT_SOURCE,
+                format!(
+                    "{} needs registry polling, but HEADWIND_POLLING_ENABLED is not true",
+                    if source == EventSource::Polling {
+                        "polling"
+                    } else {
+                        "both"
+                    }
+                ),
+            ))
+        },
+        _ => {},
+    }
+
+    if !is_true(annotations::REQUIRE_APPROVAL) && parse_required_approvals(annotations).is_some() {
+        findings.push(Finding::warning(
+            annotations::REQUIRED_APPROVALS,
+            "ignored without headwind.sh/require-approval: \"true\"",
+        ));
+    }
+
+    if kind != "Deployment" {
+        for key in [annotations::CANARY, annotations::AUTO_ROLLBACK] {
+            if is_true(key) {
+                findings.push(Finding::warning(
+                    key,
+                    format!("only supported on Deployments, ignored on a {}", kind),
+                ));
+            }
+        }
+    }
+
+    match (
+        annotations.contains_key(annotations::GITOPS_REPOSITORY),
+        annotations.contains_key(annotations::GITOPS_PATH),
+    ) {
+        (true, false) => findings.push(Finding::warning(
+            annotations::GITOPS_REPOSITORY,
+            "git write-back also needs headwind.sh/gitops-path",
+        )),
+        (false, true) => findings.push(Finding::warning(
+            annotations::GITOPS_PATH,
+            "git write-back also needs headwind.sh/gitops-repository",
+        )),
+        _ => {},
+    }
+
+    if let (Some(existing), Ok(dependencies)) =
+        (existing, parse_dependencies(annotations, namespace))
+    {
+        for dependency in dependencies {
+            let key = (
+                dependency.kind.clone(),
+                dependency.namespace.clone(),
+                dependency.name.clone(),
+            );
+            if KINDS.contains(&dependency.kind.as_str()) && !existing.contains(&key) {
+                findings.push(Finding::warning(
+                    annotations::ROLLOUT_AFTER,
+                    format!(
+                        "{} {}/{} doesn't exist",
+                        dependency.kind, dependency.namespace, dependency.name
+                    ),
+                ));
+            }
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotations(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn flagged(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.annotation.as_str()).collect()
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let context = LintContext {
+            polling_enabled: Some(false),
+            push_events: Some(false),
+        };
+        let existing: BTreeSet<ResourceKey> = [(
+            "StatefulSet".to_string(),
+            "prod".to_string(),
+            "postgres".to_string(),
+        )]
+        .into();
+
+        let findings = lint_annotations(
+            "StatefulSet",
+            "prod",
+            &annotations(&[
+                (annotations::POLICY, "glob"),
+                (annotations::EVENT_SOURCE, "webhook"),
+                (annotations::REQUIRED_APPROVALS, "2"),
+                (annotations::CANARY, "true"),
+                (annotations::GITOPS_PATH, "apps/api.yaml"),
+                (
+                    annotations::ROLLOUT_AFTER,
+                    "StatefulSet/prod/postgres,migrations",
+                ),
+            ]),
+            &context,
+            Some(&existing),
+        );
+        assert_eq!(
+            flagged(&findings),
+            [
+                annotations::PATTERN,
+                annotations::EVENT_SOURCE,
+                annotations::REQUIRED_APPROVALS,
+                annotations::CANARY,
+                annotations::GITOPS_PATH,
+                annotations::ROLLOUT_AFTER,
+            ]
+        );
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(
+            findings[1..]
+                .iter()
+                .all(|f| f.severity == Severity::Warning)
+        );
+
+        let polling = annotations(&[
+            (annotations::POLICY, "minor"),
+            (annotations::EVENT_SOURCE, "polling"),
+        ]);
+        assert_eq!(
+            flagged(&lint_annotations(
+                "Deployment",
+                "prod",
+                &polling,
+                &context,
+                None
+            )),
+            [annotations::EVENT_SOURCE]
+        );
+        // Unknown facts skip the checks that need them
+        assert!(
+            lint_annotations(
+                "Deployment",
+                "prod",
+                &polling,
+                &LintContext::default(),
+                None
+            )
+            .is_empty()
+        );
+    }
+}
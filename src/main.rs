@@ -1,23 +1,24 @@
 use anyhow::Result;
-use headwind::{approval, config, controller, metrics, notifications, polling, ui, webhook};
+use headwind::{
+    audit, config, controller, freeze, health, metrics, notifications, ordering, polling, pubsub,
+    schedule, server, telemetry,
+};
 use kube::Client;
 use tracing::info;
-use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| "headwind=info,kube=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer().json())
-        .init();
+    // Initialize logging and trace export
+    let _telemetry = telemetry::init()?;
 
     info!("Starting Headwind - Kubernetes Update Operator");
 
     // Create Kubernetes client
     let client = Client::try_default().await?;
+    health::init(client.clone());
+
+    // Validate configuration (fails fast when HEADWIND_CONFIG_STRICT=true)
+    config::validation::validate_startup(client.clone()).await?;
 
     // Start configuration watcher for hot-reload
     config::start_config_watcher(client.clone()).await;
@@ -25,12 +26,19 @@ async fn main() -> Result<()> {
     // Initialize notification manager
     notifications::init_notifications();
 
-    // Initialize metrics server
-    let metrics_handle = metrics::start_metrics_server().await?;
+    // Initialize the audit trail and prune old records in the background
+    audit::init_audit(client.clone());
+    audit::start_audit_pruner(client.clone());
+
+    // Prune finished UpdateRequests according to the retention policy
+    controller::start_update_request_pruner(client.clone());
 
-    // Initialize webhook server and get event senders (image & chart)
-    let (webhook_handle, event_sender, chart_event_sender) =
-        webhook::start_webhook_server().await?;
+    // Start the webhook, approval API, Web UI and metrics servers (split or single port) and
+    // get the event senders (image & chart)
+    let (servers_handle, event_sender, chart_event_sender) = server::start_servers().await?;
+
+    // Start the Pub/Sub listener for Artifact Registry notifications (idle unless pubsub.enabled)
+    let pubsub_handle = pubsub::start_pubsub_listener(event_sender.clone());
 
     // Initialize registry poller (optional, disabled by default)
     let polling_config = polling::PollingConfig {
@@ -42,38 +50,50 @@ async fn main() -> Result<()> {
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(300),
+        concurrency: std::env::var("HEADWIND_POLLING_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(8),
+        registry_concurrency: std::env::var("HEADWIND_POLLING_REGISTRY_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2),
     };
     let poller =
         polling::RegistryPoller::new(polling_config, event_sender, chart_event_sender).await?;
     let polling_handle = poller.start().await;
 
-    // Initialize approval API server
-    let approval_handle = approval::start_approval_server().await?;
-
-    // Initialize Web UI server
-    let ui_handle = tokio::spawn(async move {
-        if let Err(e) = ui::start_ui_server().await {
-            tracing::error!("Web UI server error: {}", e);
-        }
-    });
-
     // Start Kubernetes controllers
     let controller_handle = controller::start_controllers().await?;
 
     // Start gauge updater to periodically update resource counts
     let gauge_updater_handle = metrics::start_gauge_updater(client.clone());
 
+    // Push metrics to VictoriaMetrics / InfluxDB (idle unless observability.push.enabled)
+    let metrics_pusher_handle = metrics::push::start_metrics_pusher();
+
+    // Start scheduler that applies updates held back until their maintenance window
+    let scheduler_handle = schedule::start_scheduler(client.clone());
+
+    // Start orchestrator that applies updates held back by their rollout order
+    let ordering_handle = ordering::start_orchestrator(client.clone());
+
+    // Start watcher that applies approved updates deferred by the update gate
+    let gate_handle = freeze::start_gate_watcher(client.clone());
+
     info!("Headwind is running");
 
     // Wait for all services
     tokio::select! {
-        _ = metrics_handle => info!("Metrics server stopped"),
-        _ = webhook_handle => info!("Webhook server stopped"),
+        _ = servers_handle => info!("HTTP servers stopped"),
         _ = polling_handle => info!("Registry poller stopped"),
-        _ = approval_handle => info!("Approval server stopped"),
-        _ = ui_handle => info!("Web UI server stopped"),
+        _ = pubsub_handle => info!("Pub/Sub listener stopped"),
         _ = controller_handle => info!("Controllers stopped"),
         _ = gauge_updater_handle => info!("Gauge updater stopped"),
+        _ = metrics_pusher_handle => info!("Metrics pusher stopped"),
+        _ = scheduler_handle => info!("Update scheduler stopped"),
+        _ = ordering_handle => info!("Rollout order orchestrator stopped"),
+        _ = gate_handle => info!("Update gate watcher stopped"),
     }
 
     Ok(())
@@ -1,7 +1,10 @@
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 /// Metrics backend type
@@ -20,6 +23,19 @@ pub struct MetricPoint {
     pub value: f64,
 }
 
+/// How a metric's series are turned into the single series of a chart
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SeriesKind {
+    /// Sum of the values of all series, for gauges
+    #[default]
+    Value,
+    /// Sum of the increase of all series per step, for counters (e.g. updates applied per hour)
+    Increase,
+}
+
 /// Metric value for instant queries
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricValue {
@@ -60,6 +76,28 @@ pub trait MetricsClient: Send + Sync {
     /// Query an instant metric value
     async fn query_instant(&self, query: &str) -> Result<MetricValue>;
 
+    /// Query for the total of a metric across all of its label values
+    fn total_query(&self, metric: &str) -> String {
+        format!("sum({})", metric)
+    }
+
+    /// One series for a chart of `metric` added up across its label values, as PromQL by
+    /// default
+    async fn query_series(
+        &self,
+        metric: &str,
+        kind: SeriesKind,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: &str,
+    ) -> Result<Vec<MetricPoint>> {
+        let query = match kind {
+            SeriesKind::Value => format!("sum({})", metric),
+            SeriesKind::Increase => format!("sum(increase({}[{}]))", metric, step),
+        };
+        self.query_range(&query, start, end, step).await
+    }
+
     /// Get the backend type
     fn backend_type(&self) -> &str;
 }
@@ -269,6 +307,65 @@ impl InfluxDBClient {
             Err(_) => false,
         }
     }
+
+    /// Run a Flux query, returning the CSV response
+    async fn flux(&self, flux_query: String) -> Result<String> {
+        let url = format!("{}/api/v2/query?org={}", self.url, self.org);
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Token {}", self.token))
+            .header("Content-Type", "application/vnd.flux")
+            .body(flux_query)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to query InfluxDB: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow!("InfluxDB query failed: {}", error_text));
+        }
+
+        Ok(response.text().await?)
+    }
+}
+
+/// Points of a Flux CSV response. Every table starts with a header row naming its columns,
+/// whose positions differ between queries.
+fn parse_flux_csv(body: &str) -> Vec<MetricPoint> {
+    let mut columns: Option<(usize, usize)> = None;
+    let mut points = Vec::new();
+    for line in body.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() || line.starts_with('#') {
+            columns = None;
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split(',').collect();
+        let Some((time_column, value_column)) = columns else {
+            let time_column = parts.iter().position(|part| *part == "_time");
+            let value_column = parts.iter().position(|part| *part == "_value");
+            columns = time_column.zip(value_column);
+            continue;
+        };
+
+        if let (Some(time_str), Some(value_str)) = (parts.get(time_column), parts.get(value_column))
+            && let (Ok(timestamp), Ok(value)) = (
+                DateTime::parse_from_rfc3339(time_str),
+                value_str.parse::<f64>(),
+            )
+        {
+            points.push(MetricPoint {
+                timestamp: timestamp.with_timezone(&Utc),
+                value,
+            });
+        }
+    }
+    points
 }
 
 #[async_trait::async_trait]
@@ -303,51 +400,43 @@ impl MetricsClient for InfluxDBClient {
             step_duration
         );
 
-        let url = format!("{}/api/v2/query?org={}", self.url, self.org);
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Token {}", self.token))
-            .header("Content-Type", "application/vnd.flux")
-            .body(flux_query)
-            .send()
-            .await
-            .map_err(|e| anyhow!("Failed to query InfluxDB: {}", e))?;
-
-        if !response.status().is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow!("InfluxDB query failed: {}", error_text));
-        }
-
-        let body = response.text().await?;
-
-        // Parse CSV response from InfluxDB
-        let mut points = Vec::new();
-        for line in body.lines().skip(1) {
-            // Skip header
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+        let body = self.flux(flux_query).await?;
+        Ok(parse_flux_csv(&body))
+    }
 
-            let parts: Vec<&str> = line.split(',').collect();
-            if parts.len() >= 6
-                && let (Some(time_str), Some(value_str)) = (parts.get(5), parts.get(6))
-                && let (Ok(timestamp), Ok(value)) = (
-                    DateTime::parse_from_rfc3339(time_str),
-                    value_str.parse::<f64>(),
-                )
-            {
-                points.push(MetricPoint {
-                    timestamp: timestamp.with_timezone(&Utc),
-                    value,
-                });
-            }
-        }
+    /// Series of a measurement are aligned to the step and added up per point in time.
+    /// Counters become their non-negative difference per step first.
+    async fn query_series(
+        &self,
+        metric: &str,
+        kind: SeriesKind,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: &str,
+    ) -> Result<Vec<MetricPoint>> {
+        let difference = match kind {
+            SeriesKind::Value => "",
+            SeriesKind::Increase => "\n  |> difference(nonNegative: true)",
+        };
+        let flux_query = format!(
+            r#"from(bucket: "{}")
+  |> range(start: {}, stop: {})
+  |> filter(fn: (r) => r["_measurement"] == "{}")
+  |> aggregateWindow(every: {}, fn: last, createEmpty: false){}
+  |> group(columns: ["_time"])
+  |> sum()
+  |> group()
+  |> sort(columns: ["_time"])"#,
+            self.bucket,
+            start.to_rfc3339(),
+            end.to_rfc3339(),
+            metric,
+            step,
+            difference
+        );
 
-        Ok(points)
+        let body = self.flux(flux_query).await?;
+        Ok(parse_flux_csv(&body))
     }
 
     async fn query_instant(&self, query: &str) -> Result<MetricValue> {
@@ -361,52 +450,26 @@ impl MetricsClient for InfluxDBClient {
             self.bucket, query
         );
 
-        let url = format!("{}/api/v2/query?org={}", self.url, self.org);
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Token {}", self.token))
-            .header("Content-Type", "application/vnd.flux")
-            .body(flux_query)
-            .send()
-            .await
-            .map_err(|e| anyhow!("Failed to query InfluxDB: {}", e))?;
-
-        if !response.status().is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow!("InfluxDB query failed: {}", error_text));
-        }
-
-        let body = response.text().await?;
-
-        // Parse CSV response
-        for line in body.lines().skip(1) {
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+        let body = self.flux(flux_query).await?;
+
+        // Add up the last value of each series (one per label set)
+        let total = parse_flux_csv(&body)
+            .into_iter()
+            .map(|point| MetricValue {
+                value: point.value,
+                timestamp: point.timestamp,
+            })
+            .reduce(|total, last| MetricValue {
+                value: total.value + last.value,
+                timestamp: total.timestamp.max(last.timestamp),
+            });
 
-            let parts: Vec<&str> = line.split(',').collect();
-            if parts.len() >= 6
-                && let (Some(time_str), Some(value_str)) = (parts.get(5), parts.get(6))
-                && let (Ok(timestamp), Ok(value)) = (
-                    DateTime::parse_from_rfc3339(time_str),
-                    value_str.parse::<f64>(),
-                )
-            {
-                return Ok(MetricValue {
-                    timestamp: timestamp.with_timezone(&Utc),
-                    value,
-                });
-            }
-        }
+        total.ok_or_else(|| anyhow!("No data returned from InfluxDB for metric {}", query))
+    }
 
-        Err(anyhow!(
-            "No data returned from InfluxDB for metric {}",
-            query
-        ))
+    /// Instant queries already add up the series of a measurement
+    fn total_query(&self, metric: &str) -> String {
+        metric.to_string()
     }
 
     fn backend_type(&self) -> &str {
@@ -424,7 +487,7 @@ impl LiveMetricsClient {
     pub fn new(metrics_url: String) -> Self {
         Self {
             metrics_url,
-            client: Client::new(),
+            client: crate::server::local_client(),
         }
     }
 }
@@ -454,21 +517,17 @@ impl MetricsClient for LiveMetricsClient {
 
         let body = response.text().await?;
 
-        // Simple parsing - look for the metric name
-        for line in body.lines() {
-            if line.starts_with(query)
-                && !line.starts_with('#')
-                && let Some(value_str) = line.split_whitespace().nth(1)
-                && let Ok(value) = value_str.parse::<f64>()
-            {
-                return Ok(MetricValue {
-                    timestamp: Utc::now(),
-                    value,
-                });
-            }
-        }
+        sum_exposition(&body, query)
+            .map(|value| MetricValue {
+                timestamp: Utc::now(),
+                value,
+            })
+            .ok_or_else(|| anyhow!("Metric {} not found", query))
+    }
 
-        Err(anyhow!("Metric {} not found", query))
+    /// Instant queries already add up the series of a metric
+    fn total_query(&self, metric: &str) -> String {
+        metric.to_string()
     }
 
     fn backend_type(&self) -> &str {
@@ -476,6 +535,18 @@ impl MetricsClient for LiveMetricsClient {
     }
 }
 
+/// Sum of every series of `metric` in Prometheus text exposition format, whatever its labels
+fn sum_exposition(body: &str, metric: &str) -> Option<f64> {
+    body.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter(|line| {
+            line.strip_prefix(metric)
+                .is_some_and(|rest| rest.starts_with('{') || rest.starts_with(' '))
+        })
+        .filter_map(|line| line.rsplit_once(' ')?.1.parse::<f64>().ok())
+        .fold(None, |total, value| Some(total.unwrap_or(0.0) + value))
+}
+
 /// Auto-discover and create the appropriate metrics client
 #[allow(clippy::too_many_arguments)]
 pub async fn create_metrics_client(
@@ -531,9 +602,7 @@ pub async fn create_metrics_client(
 
             // Fall back to live metrics
             info!("No metrics backend discovered, falling back to live metrics");
-            Box::new(LiveMetricsClient::new(
-                "http://localhost:9090/metrics".to_string(),
-            ))
+            Box::new(LiveMetricsClient::new(crate::server::metrics_url()))
         },
         "prometheus" => {
             let url = prometheus_url.unwrap_or_else(|| {
@@ -560,18 +629,101 @@ pub async fn create_metrics_client(
         },
         "live" => {
             info!("Using live metrics only");
-            Box::new(LiveMetricsClient::new(
-                "http://localhost:9090/metrics".to_string(),
-            ))
+            Box::new(LiveMetricsClient::new(crate::server::metrics_url()))
         },
         _ => {
             warn!(
                 "Unknown backend type '{}', falling back to live metrics",
                 backend_type
             );
-            Box::new(LiveMetricsClient::new(
-                "http://localhost:9090/metrics".to_string(),
-            ))
+            Box::new(LiveMetricsClient::new(crate::server::metrics_url()))
         },
     }
 }
+
+/// How long the backend `auto` discovered is reused before probing the backends again
+const DISCOVERY_TTL: Duration = Duration::from_secs(300);
+
+/// Backend `auto` last resolved to, as a `metricsBackend` value, and when
+static DISCOVERED_BACKEND: Lazy<Mutex<Option<(Instant, String)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Metrics client for the current `observability` settings. The backend `auto` resolves to is
+/// remembered for a few minutes, so the charts of the observability page don't probe every
+/// backend on each request.
+pub async fn configured_client() -> Box<dyn MetricsClient> {
+    let observability = crate::config::get_cached_config()
+        .unwrap_or_default()
+        .observability;
+
+    let mut backend = observability.metrics_backend.clone();
+    if backend == "auto"
+        && let Some((discovered_at, discovered)) = DISCOVERED_BACKEND.lock().unwrap().as_ref()
+        && discovered_at.elapsed() < DISCOVERY_TTL
+    {
+        backend = discovered.clone();
+    }
+
+    let client = create_metrics_client(
+        &backend,
+        observability.prometheus.url.clone(),
+        observability.prometheus.enabled,
+        observability.victoriametrics.url.clone(),
+        observability.victoriametrics.enabled,
+        observability.influxdb.url.clone(),
+        observability.influxdb.enabled,
+        observability.influxdb.org.clone(),
+        observability.influxdb.bucket.clone(),
+        observability.influxdb.resolved_token(),
+    )
+    .await;
+
+    if backend == "auto" {
+        *DISCOVERED_BACKEND.lock().unwrap() =
+            Some((Instant::now(), client.backend_type().to_lowercase()));
+    }
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flux_csv() {
+        let body = ",result,table,_start,_stop,_time,_value,_field,_measurement\r
+,_result,0,2024-05-01T00:00:00Z,2024-05-01T01:00:00Z,2024-05-01T00:05:00Z,3,value,headwind_updates_applied_total\r
+,_result,0,2024-05-01T00:00:00Z,2024-05-01T01:00:00Z,2024-05-01T00:10:00Z,4.5,value,headwind_updates_applied_total\r
+\r
+,result,table,_time,_value\r
+,_result,0,2024-05-01T00:15:00Z,7\r
+";
+
+        let points = parse_flux_csv(body);
+        assert_eq!(
+            points.iter().map(|p| p.value).collect::<Vec<_>>(),
+            vec![3.0, 4.5, 7.0]
+        );
+        assert_eq!(
+            points[2].timestamp.to_rfc3339(),
+            "2024-05-01T00:15:00+00:00"
+        );
+        assert!(parse_flux_csv("").is_empty());
+    }
+
+    #[test]
+    fn test_sum_exposition() {
+        let body = r#"# HELP headwind_updates_applied_total Total number of updates applied
+# TYPE headwind_updates_applied_total counter
+headwind_updates_applied_total{kind="Deployment",namespace="payments"} 3
+headwind_updates_applied_total{kind="StatefulSet",namespace="search"} 2
+headwind_updates_applied_totals 100
+headwind_updates_pending 4
+"#;
+        assert_eq!(
+            sum_exposition(body, "headwind_updates_applied_total"),
+            Some(5.0)
+        );
+        assert_eq!(sum_exposition(body, "headwind_updates_pending"), Some(4.0));
+        assert_eq!(sum_exposition(body, "headwind_updates_failed_total"), None);
+    }
+}
@@ -1,50 +1,185 @@
 use anyhow::Result;
 use axum::{Router, http::StatusCode, response::IntoResponse, routing::get};
+use kube::ResourceExt;
 use lazy_static::lazy_static;
-use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Registry, TextEncoder};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use tokio::task::JoinHandle;
 use tracing::info;
 
 pub mod client;
+pub mod push;
 
 lazy_static! {
-    pub static ref REGISTRY: Registry = Registry::new();
+    // Labelled with the cluster name (HEADWIND_CLUSTER_NAME), when one is set
+    pub static ref REGISTRY: Registry =
+        Registry::new_custom(None, crate::config::cluster::metric_labels()).unwrap();
 
     // Webhook metrics
-    pub static ref WEBHOOK_EVENTS_TOTAL: IntCounter = IntCounter::new(
-        "headwind_webhook_events_total",
-        "Total number of webhook events received"
+    pub static ref WEBHOOK_EVENTS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_webhook_events_total",
+            "Total number of webhook events received"
+        ),
+        &["endpoint"]
     ).unwrap();
 
-    pub static ref WEBHOOK_EVENTS_PROCESSED: IntCounter = IntCounter::new(
-        "headwind_webhook_events_processed",
-        "Total number of webhook events successfully processed"
+    pub static ref WEBHOOK_EVENTS_PROCESSED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_webhook_events_processed",
+            "Total number of webhook events successfully processed"
+        ),
+        &["registry"]
+    ).unwrap();
+
+    pub static ref WEBHOOK_REQUESTS_REJECTED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_webhook_requests_rejected_total",
+            "Total number of webhook requests rejected by signature or token validation"
+        ),
+        &["endpoint", "reason"]
+    ).unwrap();
+
+    pub static ref HTTP_REQUESTS_LIMITED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_http_requests_limited_total",
+            "Total number of HTTP requests answered with 408 or 413 by the request limits"
+        ),
+        &["limit"]
+    ).unwrap();
+
+    pub static ref WEBHOOK_EVENT_QUEUE_DEPTH: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_webhook_event_queue_depth",
+            "Number of push events waiting for their processor"
+        ),
+        &["queue"]
+    ).unwrap();
+
+    pub static ref WEBHOOK_EVENT_QUEUE_CAPACITY: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_webhook_event_queue_capacity",
+            "Number of push events a processor queue holds before webhooks are rejected"
+        ),
+        &["queue"]
+    ).unwrap();
+
+    pub static ref WEBHOOK_EVENT_RETRIES: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_webhook_event_retries_total",
+            "Total number of retried push events by outcome"
+        ),
+        &["kind", "result"]
+    ).unwrap();
+
+    pub static ref WEBHOOK_EVENTS_DEAD_LETTERED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_webhook_events_dead_lettered_total",
+            "Total number of push events given up on after exhausting their retries"
+        ),
+        &["kind"]
+    ).unwrap();
+
+    pub static ref WEBHOOK_EVENT_RETRY_QUEUE: IntGauge = IntGauge::new(
+        "headwind_webhook_event_retry_queue",
+        "Number of failed push events waiting to be retried"
+    ).unwrap();
+
+    pub static ref ADMISSION_REVIEWS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_admission_reviews_total",
+            "Total number of UpdateRequest admission reviews by result"
+        ),
+        &["result"]
+    ).unwrap();
+
+    pub static ref ANNOTATION_REVIEWS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_admission_annotation_reviews_total",
+            "Total number of workload annotation admission reviews by kind and result"
+        ),
+        &["kind", "result"]
+    ).unwrap();
+
+    // Pub/Sub metrics
+    pub static ref PUBSUB_MESSAGES_RECEIVED: IntCounter = IntCounter::new(
+        "headwind_pubsub_messages_total",
+        "Total number of registry notifications received from Pub/Sub"
+    ).unwrap();
+
+    pub static ref PUBSUB_ERRORS: IntCounter = IntCounter::new(
+        "headwind_pubsub_errors_total",
+        "Total number of failed Pub/Sub pulls and undecodable messages"
+    ).unwrap();
+
+    // Event bus metrics
+    pub static ref EVENT_BUS_MESSAGES: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_event_bus_messages_total",
+            "Total number of push events published to or consumed from the event bus"
+        ),
+        &["queue", "operation", "result"]
     ).unwrap();
 
     // Update metrics
-    pub static ref UPDATES_PENDING: IntGauge = IntGauge::new(
-        "headwind_updates_pending",
-        "Number of updates pending approval"
+    pub static ref UPDATES_PENDING: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_updates_pending",
+            "Number of updates pending approval"
+        ),
+        &["namespace"]
+    ).unwrap();
+
+    pub static ref UPDATES_APPROVED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_approved_total",
+            "Total number of updates approved"
+        ),
+        &["namespace", "kind", "policy"]
+    ).unwrap();
+
+    pub static ref UPDATES_REJECTED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_rejected_total",
+            "Total number of updates rejected"
+        ),
+        &["namespace", "kind", "policy"]
     ).unwrap();
 
-    pub static ref UPDATES_APPROVED: IntCounter = IntCounter::new(
-        "headwind_updates_approved_total",
-        "Total number of updates approved"
+    pub static ref UPDATES_EXPIRED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_expired_total",
+            "Total number of update requests that expired before approval"
+        ),
+        &["namespace", "kind", "policy"]
     ).unwrap();
 
-    pub static ref UPDATES_REJECTED: IntCounter = IntCounter::new(
-        "headwind_updates_rejected_total",
-        "Total number of updates rejected"
+    pub static ref UPDATE_REQUESTS_PRUNED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_update_requests_pruned_total",
+            "Total number of finished update requests deleted by the retention policy"
+        ),
+        &["namespace", "phase"]
     ).unwrap();
 
-    pub static ref UPDATES_APPLIED: IntCounter = IntCounter::new(
-        "headwind_updates_applied_total",
-        "Total number of updates successfully applied"
+    pub static ref UPDATES_APPLIED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_applied_total",
+            "Total number of updates successfully applied"
+        ),
+        &["namespace", "kind"]
     ).unwrap();
 
-    pub static ref UPDATES_FAILED: IntCounter = IntCounter::new(
-        "headwind_updates_failed_total",
-        "Total number of updates that failed to apply"
+    pub static ref UPDATES_FAILED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_failed_total",
+            "Total number of updates that failed to apply"
+        ),
+        &["namespace", "kind"]
     ).unwrap();
 
     // Controller metrics
@@ -55,30 +190,63 @@ lazy_static! {
         ).buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0])
     ).unwrap();
 
-    pub static ref RECONCILE_ERRORS: IntCounter = IntCounter::new(
-        "headwind_reconcile_errors_total",
-        "Total number of reconciliation errors"
+    pub static ref RECONCILE_ERRORS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_reconcile_errors_total",
+            "Total number of reconciliation errors"
+        ),
+        &["kind"]
+    ).unwrap();
+
+    pub static ref INFORMER_CACHE_HITS: IntCounter = IntCounter::new(
+        "headwind_informer_cache_hits_total",
+        "Resource lookups served from a controller's informer cache"
+    ).unwrap();
+
+    pub static ref INFORMER_CACHE_MISSES: IntCounter = IntCounter::new(
+        "headwind_informer_cache_misses_total",
+        "Resource lookups that listed from the API server because no informer cache had synced"
     ).unwrap();
 
     // Resource metrics
-    pub static ref DEPLOYMENTS_WATCHED: IntGauge = IntGauge::new(
-        "headwind_deployments_watched",
-        "Number of Deployments being watched"
+    pub static ref DEPLOYMENTS_WATCHED: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_deployments_watched",
+            "Number of Deployments being watched"
+        ),
+        &["namespace"]
     ).unwrap();
 
-    pub static ref HELM_RELEASES_WATCHED: IntGauge = IntGauge::new(
-        "headwind_helm_releases_watched",
-        "Number of Helm releases being watched"
+    pub static ref HELM_RELEASES_WATCHED: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_helm_releases_watched",
+            "Number of Helm releases being watched"
+        ),
+        &["namespace"]
     ).unwrap();
 
-    pub static ref STATEFULSETS_WATCHED: IntGauge = IntGauge::new(
-        "headwind_statefulsets_watched",
-        "Number of StatefulSets being watched"
+    pub static ref STATEFULSETS_WATCHED: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_statefulsets_watched",
+            "Number of StatefulSets being watched"
+        ),
+        &["namespace"]
     ).unwrap();
 
-    pub static ref DAEMONSETS_WATCHED: IntGauge = IntGauge::new(
-        "headwind_daemonsets_watched",
-        "Number of DaemonSets being watched"
+    pub static ref DAEMONSETS_WATCHED: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_daemonsets_watched",
+            "Number of DaemonSets being watched"
+        ),
+        &["namespace"]
+    ).unwrap();
+
+    pub static ref CRONJOBS_WATCHED: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_cronjobs_watched",
+            "Number of CronJobs being watched"
+        ),
+        &["namespace"]
     ).unwrap();
 
     // Polling metrics
@@ -92,14 +260,30 @@ lazy_static! {
         "Total number of registry polling errors"
     ).unwrap();
 
-    pub static ref POLLING_IMAGES_CHECKED: IntCounter = IntCounter::new(
-        "headwind_polling_images_checked_total",
-        "Total number of images checked during polling"
+    pub static ref POLLING_TAG_LISTS_TRUNCATED: IntCounter = IntCounter::new(
+        "headwind_polling_tag_lists_truncated_total",
+        "Tag listings cut off at HEADWIND_POLLING_MAX_TAGS"
+    ).unwrap();
+
+    pub static ref REGISTRY_PROVIDER_FALLBACKS: IntCounter = IntCounter::new(
+        "headwind_registry_provider_fallbacks_total",
+        "Registry API calls (ECR, Artifact Registry, ACR) that failed and fell back to the OCI distribution API"
+    ).unwrap();
+
+    pub static ref POLLING_IMAGES_CHECKED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_polling_images_checked_total",
+            "Total number of images checked during polling"
+        ),
+        &["registry"]
     ).unwrap();
 
-    pub static ref POLLING_NEW_TAGS_FOUND: IntCounter = IntCounter::new(
-        "headwind_polling_new_tags_found_total",
-        "Total number of new tags discovered via polling"
+    pub static ref POLLING_NEW_TAGS_FOUND: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_polling_new_tags_found_total",
+            "Total number of new tags discovered via polling"
+        ),
+        &["registry"]
     ).unwrap();
 
     pub static ref POLLING_HELM_CHARTS_CHECKED: IntCounter = IntCounter::new(
@@ -117,30 +301,65 @@ lazy_static! {
         "Total number of resources filtered out from polling due to event-source annotation"
     ).unwrap();
 
+    pub static ref POLLING_CYCLE_DURATION: Histogram = Histogram::with_opts(
+        HistogramOpts::new(
+            "headwind_polling_cycle_duration_seconds",
+            "Time spent on a complete registry polling cycle"
+        ).buckets(vec![0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0])
+    ).unwrap();
+
+    // Cache metrics
+    pub static ref CACHE_ENTRIES: IntGauge = IntGauge::new(
+        "headwind_cache_entries",
+        "Number of entries held across all internal caches"
+    ).unwrap();
+
+    pub static ref CACHE_EVICTIONS_TOTAL: IntCounter = IntCounter::new(
+        "headwind_cache_evictions_total",
+        "Total number of cache entries evicted because a cache reached its size limit"
+    ).unwrap();
+
+    pub static ref CACHE_EXPIRATIONS_TOTAL: IntCounter = IntCounter::new(
+        "headwind_cache_expirations_total",
+        "Total number of cache entries purged because their TTL elapsed"
+    ).unwrap();
+
     // Helm metrics
     pub static ref HELM_CHART_VERSIONS_CHECKED: IntCounter = IntCounter::new(
         "headwind_helm_chart_versions_checked_total",
         "Total number of Helm chart version checks performed"
     ).unwrap();
 
-    pub static ref HELM_UPDATES_FOUND: IntCounter = IntCounter::new(
-        "headwind_helm_updates_found_total",
-        "Total number of Helm chart updates discovered"
+    pub static ref HELM_UPDATES_FOUND: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_helm_updates_found_total",
+            "Total number of Helm chart updates discovered"
+        ),
+        &["namespace"]
     ).unwrap();
 
-    pub static ref HELM_UPDATES_APPROVED: IntCounter = IntCounter::new(
-        "headwind_helm_updates_approved_total",
-        "Total number of Helm chart updates approved by policy"
+    pub static ref HELM_UPDATES_APPROVED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_helm_updates_approved_total",
+            "Total number of Helm chart updates approved by policy"
+        ),
+        &["namespace"]
     ).unwrap();
 
-    pub static ref HELM_UPDATES_REJECTED: IntCounter = IntCounter::new(
-        "headwind_helm_updates_rejected_total",
-        "Total number of Helm chart updates rejected by policy"
+    pub static ref HELM_UPDATES_REJECTED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_helm_updates_rejected_total",
+            "Total number of Helm chart updates rejected by policy"
+        ),
+        &["namespace"]
     ).unwrap();
 
-    pub static ref HELM_UPDATES_APPLIED: IntCounter = IntCounter::new(
-        "headwind_helm_updates_applied_total",
-        "Total number of Helm chart updates successfully applied"
+    pub static ref HELM_UPDATES_APPLIED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_helm_updates_applied_total",
+            "Total number of Helm chart updates successfully applied"
+        ),
+        &["namespace"]
     ).unwrap();
 
     pub static ref HELM_REPOSITORY_QUERIES: IntCounter = IntCounter::new(
@@ -159,7 +378,10 @@ lazy_static! {
             "Time spent querying Helm repositories for available versions"
         ).buckets(vec![0.1, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0])
     ).unwrap();
+}
 
+// Split in two: a single lazy_static! block this size exceeds the macro recursion limit
+lazy_static! {
     // Rollback metrics
     pub static ref ROLLBACKS_TOTAL: IntCounter = IntCounter::new(
         "headwind_rollbacks_total",
@@ -181,6 +403,14 @@ lazy_static! {
         "Total number of failed rollback operations"
     ).unwrap();
 
+    pub static ref CANARY_ROLLOUTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_canary_rollouts_total",
+            "Total number of canary rollouts, by result (promoted, failed)"
+        ),
+        &["namespace", "result"]
+    ).unwrap();
+
     pub static ref DEPLOYMENT_HEALTH_CHECKS: IntCounter = IntCounter::new(
         "headwind_deployment_health_checks_total",
         "Total number of deployment health checks performed"
@@ -191,17 +421,169 @@ lazy_static! {
         "Total number of deployment health check failures detected"
     ).unwrap();
 
+    // Git write-back metrics
+    pub static ref GITOPS_COMMITS: IntCounter = IntCounter::new(
+        "headwind_gitops_commits_total",
+        "Total number of image updates committed to Git repositories"
+    ).unwrap();
+
+    pub static ref GITOPS_PULL_REQUESTS: IntCounter = IntCounter::new(
+        "headwind_gitops_pull_requests_total",
+        "Total number of pull requests opened for Git write-backs"
+    ).unwrap();
+
+    pub static ref GITOPS_ERRORS: IntCounter = IntCounter::new(
+        "headwind_gitops_errors_total",
+        "Total number of failed Git write-backs"
+    ).unwrap();
+
+    // Update hook metrics
+    pub static ref UPDATE_HOOK_VETOES: IntCounter = IntCounter::new(
+        "headwind_update_hook_vetoes_total",
+        "Total number of updates vetoed by a pre-update hook"
+    ).unwrap();
+
+    pub static ref UPDATE_HOOK_ERRORS: IntCounter = IntCounter::new(
+        "headwind_update_hook_errors_total",
+        "Total number of failed post-update hook calls"
+    ).unwrap();
+
+    // Change ticket metrics
+    pub static ref CHANGE_TICKETS_OPENED: IntCounter = IntCounter::new(
+        "headwind_change_tickets_opened_total",
+        "Total number of change tickets opened for UpdateRequests"
+    ).unwrap();
+
+    pub static ref CHANGE_TICKET_ERRORS: IntCounter = IntCounter::new(
+        "headwind_change_ticket_errors_total",
+        "Total number of failed attempts to open a change ticket"
+    ).unwrap();
+
+    // Release notes metrics
+    pub static ref RELEASE_NOTES_FOUND: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_release_notes_found_total",
+            "Total number of UpdateRequests release notes were found for"
+        ),
+        &["source"]
+    ).unwrap();
+
+    pub static ref RELEASE_NOTES_ERRORS: IntCounter = IntCounter::new(
+        "headwind_release_notes_errors_total",
+        "Total number of failed release notes lookups"
+    ).unwrap();
+
+    // Vulnerability scan metrics
+    pub static ref VULNERABILITY_SCANS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_vulnerability_scans_total",
+            "Total number of finished vulnerability scans of proposed images"
+        ),
+        &["namespace", "result"]
+    ).unwrap();
+
+    // Platform compatibility metrics
+    pub static ref PLATFORM_CHECKS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_platform_checks_total",
+            "Total number of platform compatibility checks of proposed images"
+        ),
+        &["namespace", "result"]
+    ).unwrap();
+
+    // Minimum tag age metrics
+    pub static ref TAGS_HELD_FOR_AGE: IntCounter = IntCounter::new(
+        "headwind_tags_held_for_age_total",
+        "Total number of new tags not acted on because they are younger than headwind.sh/min-tag-age"
+    ).unwrap();
+
+    // Rollout order metrics
+    pub static ref UPDATES_HELD_FOR_ORDER: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_held_for_order_total",
+            "Total number of automatic updates held until the workloads they are ordered after finished their updates"
+        ),
+        &["namespace", "kind"]
+    ).unwrap();
+
+    pub static ref ORDERED_UPDATES_PENDING: IntGauge = IntGauge::new(
+        "headwind_ordered_updates_pending",
+        "Number of automatic updates waiting for their rollout order"
+    ).unwrap();
+
+    // Update gate metrics
+    pub static ref UPDATE_GATE_CHECKS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_update_gate_checks_total",
+            "Total number of update gate checks by result (open, frozen, unavailable)"
+        ),
+        &["result"]
+    ).unwrap();
+
+    pub static ref UPDATES_DEFERRED_BY_GATE: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_deferred_by_gate_total",
+            "Total number of approved updates deferred while the update gate reported a freeze"
+        ),
+        &["namespace", "kind"]
+    ).unwrap();
+
     // Notification metrics
-    pub static ref NOTIFICATIONS_SENT_TOTAL: IntCounter = IntCounter::new(
-        "headwind_notifications_sent_total",
-        "Total number of notifications sent"
+    pub static ref NOTIFICATIONS_SENT_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_notifications_sent_total",
+            "Total number of notifications sent"
+        ),
+        &["channel", "event"]
+    ).unwrap();
+
+    pub static ref NOTIFICATIONS_FAILED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_notifications_failed_total",
+            "Total number of failed notification attempts"
+        ),
+        &["channel", "event"]
+    ).unwrap();
+
+    pub static ref NOTIFICATIONS_SUPPRESSED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_notifications_suppressed_total",
+            "Total number of notifications suppressed as repeats within the dedup window"
+        ),
+        &["event"]
+    ).unwrap();
+
+    pub static ref NOTIFICATIONS_DROPPED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_notifications_dropped_total",
+            "Total number of notifications not sent because the channel's circuit was open"
+        ),
+        &["channel", "event"]
     ).unwrap();
 
-    pub static ref NOTIFICATIONS_FAILED_TOTAL: IntCounter = IntCounter::new(
-        "headwind_notifications_failed_total",
-        "Total number of failed notification attempts"
+    pub static ref NOTIFICATION_RETRIES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_notification_retries_total",
+            "Total number of retried notification sends"
+        ),
+        &["channel"]
     ).unwrap();
 
+    pub static ref NOTIFICATION_CIRCUIT_OPEN: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_notification_circuit_open",
+            "Destinations of a notification channel whose circuit is open after repeated failures"
+        ),
+        &["channel"]
+    ).unwrap();
+
+    pub static ref NOTIFICATION_QUEUE_DEPTH: IntGauge = IntGauge::new(
+        "headwind_notification_queue_depth",
+        "Number of notifications waiting to be delivered, including those being retried"
+    ).unwrap();
+
+    // Per-channel counters, kept for existing dashboards. Prefer the `channel` label of
+    // headwind_notifications_sent_total.
     pub static ref NOTIFICATIONS_SLACK_SENT: IntCounter = IntCounter::new(
         "headwind_notifications_slack_sent_total",
         "Total number of notifications sent to Slack"
@@ -218,9 +600,64 @@ lazy_static! {
     ).unwrap();
 
     // Update interval metrics
-    pub static ref UPDATES_SKIPPED_INTERVAL: IntCounter = IntCounter::new(
-        "headwind_updates_skipped_interval_total",
-        "Total number of updates skipped due to minimum interval not elapsed"
+    pub static ref UPDATES_SKIPPED_INTERVAL: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_skipped_interval_total",
+            "Total number of updates skipped due to minimum interval not elapsed"
+        ),
+        &["namespace", "kind"]
+    ).unwrap();
+
+    // Maintenance window metrics
+    pub static ref UPDATES_DEFERRED: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_updates_deferred_total",
+            "Total number of updates queued until their maintenance window opens"
+        ),
+        &["namespace", "kind"]
+    ).unwrap();
+
+    pub static ref SCHEDULED_UPDATES_PENDING: IntGauge = IntGauge::new(
+        "headwind_scheduled_updates_pending",
+        "Number of updates waiting for their maintenance window"
+    ).unwrap();
+
+    // Helm values schema metrics
+    pub static ref HELM_VALUES_SCHEMA_VIOLATIONS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_helm_values_schema_violations_total",
+            "Total number of chart upgrades whose release values don't match the new chart's values schema"
+        ),
+        &["namespace"]
+    ).unwrap();
+
+    // Metrics push
+    pub static ref METRICS_PUSHES: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "headwind_metrics_pushes_total",
+            "Total number of metrics pushes to VictoriaMetrics or InfluxDB by outcome"
+        ),
+        &["backend", "result"]
+    ).unwrap();
+
+    // Approval latency metrics
+    pub static ref APPROVAL_LATENCY: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "headwind_approval_latency_seconds",
+            "Time from an UpdateRequest's creation to its approval or rejection"
+        ).buckets(vec![
+            60.0, 300.0, 900.0, 1800.0, 3600.0, 7200.0, 14400.0, 28800.0, 86400.0, 172800.0,
+            604800.0,
+        ]),
+        &["namespace", "kind", "policy", "decision"]
+    ).unwrap();
+
+    pub static ref OLDEST_PENDING_UPDATE_AGE: IntGaugeVec = IntGaugeVec::new(
+        Opts::new(
+            "headwind_oldest_pending_update_age_seconds",
+            "Age of the oldest UpdateRequest waiting for approval"
+        ),
+        &["namespace"]
     ).unwrap();
 }
 
@@ -231,13 +668,51 @@ pub fn register_metrics() {
     REGISTRY
         .register(Box::new(WEBHOOK_EVENTS_PROCESSED.clone()))
         .ok();
+    REGISTRY
+        .register(Box::new(WEBHOOK_REQUESTS_REJECTED.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(HTTP_REQUESTS_LIMITED.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(WEBHOOK_EVENT_QUEUE_DEPTH.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(WEBHOOK_EVENT_QUEUE_CAPACITY.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(WEBHOOK_EVENT_RETRIES.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(WEBHOOK_EVENTS_DEAD_LETTERED.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(WEBHOOK_EVENT_RETRY_QUEUE.clone()))
+        .ok();
+    REGISTRY.register(Box::new(ADMISSION_REVIEWS.clone())).ok();
+    REGISTRY.register(Box::new(ANNOTATION_REVIEWS.clone())).ok();
+    REGISTRY
+        .register(Box::new(PUBSUB_MESSAGES_RECEIVED.clone()))
+        .ok();
+    REGISTRY.register(Box::new(PUBSUB_ERRORS.clone())).ok();
+    REGISTRY.register(Box::new(EVENT_BUS_MESSAGES.clone())).ok();
     REGISTRY.register(Box::new(UPDATES_PENDING.clone())).ok();
     REGISTRY.register(Box::new(UPDATES_APPROVED.clone())).ok();
     REGISTRY.register(Box::new(UPDATES_REJECTED.clone())).ok();
+    REGISTRY.register(Box::new(UPDATES_EXPIRED.clone())).ok();
+    REGISTRY
+        .register(Box::new(UPDATE_REQUESTS_PRUNED.clone()))
+        .ok();
     REGISTRY.register(Box::new(UPDATES_APPLIED.clone())).ok();
     REGISTRY.register(Box::new(UPDATES_FAILED.clone())).ok();
     REGISTRY.register(Box::new(RECONCILE_DURATION.clone())).ok();
     REGISTRY.register(Box::new(RECONCILE_ERRORS.clone())).ok();
+    REGISTRY
+        .register(Box::new(INFORMER_CACHE_HITS.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(INFORMER_CACHE_MISSES.clone()))
+        .ok();
     REGISTRY
         .register(Box::new(DEPLOYMENTS_WATCHED.clone()))
         .ok();
@@ -248,12 +723,19 @@ pub fn register_metrics() {
         .register(Box::new(STATEFULSETS_WATCHED.clone()))
         .ok();
     REGISTRY.register(Box::new(DAEMONSETS_WATCHED.clone())).ok();
+    REGISTRY.register(Box::new(CRONJOBS_WATCHED.clone())).ok();
     REGISTRY
         .register(Box::new(POLLING_CYCLES_TOTAL.clone()))
         .ok();
     REGISTRY
         .register(Box::new(POLLING_ERRORS_TOTAL.clone()))
         .ok();
+    REGISTRY
+        .register(Box::new(POLLING_TAG_LISTS_TRUNCATED.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(REGISTRY_PROVIDER_FALLBACKS.clone()))
+        .ok();
     REGISTRY
         .register(Box::new(POLLING_IMAGES_CHECKED.clone()))
         .ok();
@@ -269,6 +751,16 @@ pub fn register_metrics() {
     REGISTRY
         .register(Box::new(POLLING_RESOURCES_FILTERED.clone()))
         .ok();
+    REGISTRY
+        .register(Box::new(POLLING_CYCLE_DURATION.clone()))
+        .ok();
+    REGISTRY.register(Box::new(CACHE_ENTRIES.clone())).ok();
+    REGISTRY
+        .register(Box::new(CACHE_EVICTIONS_TOTAL.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(CACHE_EXPIRATIONS_TOTAL.clone()))
+        .ok();
     REGISTRY
         .register(Box::new(HELM_CHART_VERSIONS_CHECKED.clone()))
         .ok();
@@ -297,18 +789,68 @@ pub fn register_metrics() {
         .register(Box::new(ROLLBACKS_AUTOMATIC.clone()))
         .ok();
     REGISTRY.register(Box::new(ROLLBACKS_FAILED.clone())).ok();
+    REGISTRY.register(Box::new(CANARY_ROLLOUTS.clone())).ok();
     REGISTRY
         .register(Box::new(DEPLOYMENT_HEALTH_CHECKS.clone()))
         .ok();
     REGISTRY
         .register(Box::new(DEPLOYMENT_HEALTH_FAILURES.clone()))
         .ok();
+    REGISTRY.register(Box::new(GITOPS_COMMITS.clone())).ok();
+    REGISTRY
+        .register(Box::new(GITOPS_PULL_REQUESTS.clone()))
+        .ok();
+    REGISTRY.register(Box::new(GITOPS_ERRORS.clone())).ok();
+    REGISTRY.register(Box::new(UPDATE_HOOK_VETOES.clone())).ok();
+    REGISTRY.register(Box::new(UPDATE_HOOK_ERRORS.clone())).ok();
+    REGISTRY
+        .register(Box::new(CHANGE_TICKETS_OPENED.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(CHANGE_TICKET_ERRORS.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(RELEASE_NOTES_FOUND.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(RELEASE_NOTES_ERRORS.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(VULNERABILITY_SCANS.clone()))
+        .ok();
+    REGISTRY.register(Box::new(PLATFORM_CHECKS.clone())).ok();
+    REGISTRY.register(Box::new(TAGS_HELD_FOR_AGE.clone())).ok();
+    REGISTRY
+        .register(Box::new(UPDATES_HELD_FOR_ORDER.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(ORDERED_UPDATES_PENDING.clone()))
+        .ok();
+    REGISTRY.register(Box::new(UPDATE_GATE_CHECKS.clone())).ok();
+    REGISTRY
+        .register(Box::new(UPDATES_DEFERRED_BY_GATE.clone()))
+        .ok();
     REGISTRY
         .register(Box::new(NOTIFICATIONS_SENT_TOTAL.clone()))
         .ok();
     REGISTRY
         .register(Box::new(NOTIFICATIONS_FAILED_TOTAL.clone()))
         .ok();
+    REGISTRY
+        .register(Box::new(NOTIFICATIONS_SUPPRESSED_TOTAL.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(NOTIFICATIONS_DROPPED_TOTAL.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(NOTIFICATION_RETRIES_TOTAL.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(NOTIFICATION_CIRCUIT_OPEN.clone()))
+        .ok();
+    REGISTRY
+        .register(Box::new(NOTIFICATION_QUEUE_DEPTH.clone()))
+        .ok();
     REGISTRY
         .register(Box::new(NOTIFICATIONS_SLACK_SENT.clone()))
         .ok();
@@ -321,26 +863,36 @@ pub fn register_metrics() {
     REGISTRY
         .register(Box::new(UPDATES_SKIPPED_INTERVAL.clone()))
         .ok();
+    REGISTRY.register(Box::new(UPDATES_DEFERRED.clone())).ok();
+    REGISTRY
+        .register(Box::new(SCHEDULED_UPDATES_PENDING.clone()))
+        .ok();
+    REGISTRY.register(Box::new(METRICS_PUSHES.clone())).ok();
+    REGISTRY.register(Box::new(APPROVAL_LATENCY.clone())).ok();
+    REGISTRY
+        .register(Box::new(OLDEST_PENDING_UPDATE_AGE.clone()))
+        .ok();
 
     info!("Metrics registered");
 }
 
-pub async fn start_metrics_server() -> Result<JoinHandle<()>> {
+/// Metrics and probe routes, without `/health`
+pub fn router() -> Router {
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/livez", get(crate::health::livez))
+        .route("/readyz", get(crate::health::readyz))
+}
+
+pub async fn start_metrics_server(addr: SocketAddr) -> Result<JoinHandle<()>> {
     register_metrics();
 
-    let app = Router::new()
-        .route("/metrics", get(metrics_handler))
-        .route("/health", get(health_check));
+    let app = router().route("/health", get(crate::health::livez));
 
-    let addr = "0.0.0.0:9090";
     info!("Starting metrics server on {}", addr);
 
     let handle = tokio::spawn(async move {
-        let listener = tokio::net::TcpListener::bind(addr)
-            .await
-            .expect("Failed to bind metrics server");
-
-        axum::serve(listener, app)
+        crate::server::serve(addr, app)
             .await
             .expect("Metrics server failed");
     });
@@ -372,85 +924,129 @@ async fn metrics_handler() -> impl IntoResponse {
         .into_response()
 }
 
-async fn health_check() -> impl IntoResponse {
-    (StatusCode::OK, "OK")
-}
-
 /// Update resource gauge metrics by querying Kubernetes
 pub async fn update_resource_gauges(client: kube::Client) -> Result<()> {
+    use crate::config::namespaces;
+    use crate::controller::stores;
+    use crate::models::HelmRelease;
+    use crate::models::crd::{UpdatePhase, UpdateRequest};
     use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
-    use kube::api::{Api, ListParams};
-
-    use crate::models::policy::annotations;
+    use k8s_openapi::api::batch::v1::CronJob;
 
-    // Count Deployments with Headwind annotations
-    let deployments: Api<Deployment> = Api::all(client.clone());
-    let deploy_list = deployments.list(&ListParams::default()).await?;
-    let deploy_count = deploy_list
-        .items
-        .iter()
-        .filter(|d| {
-            d.metadata
-                .annotations
-                .as_ref()
-                .and_then(|a| a.get(annotations::POLICY))
-                .is_some()
-        })
-        .count();
-    DEPLOYMENTS_WATCHED.set(deploy_count as i64);
-
-    // Count StatefulSets with Headwind annotations
-    let statefulsets: Api<StatefulSet> = Api::all(client.clone());
-    let sts_list = statefulsets.list(&ListParams::default()).await?;
-    let sts_count = sts_list
-        .items
-        .iter()
-        .filter(|s| {
-            s.metadata
-                .annotations
-                .as_ref()
-                .and_then(|a| a.get(annotations::POLICY))
-                .is_some()
-        })
-        .count();
-    STATEFULSETS_WATCHED.set(sts_count as i64);
-
-    // Count DaemonSets with Headwind annotations
-    let daemonsets: Api<DaemonSet> = Api::all(client.clone());
-    let ds_list = daemonsets.list(&ListParams::default()).await?;
-    let ds_count = ds_list
-        .items
-        .iter()
-        .filter(|d| {
-            d.metadata
-                .annotations
-                .as_ref()
-                .and_then(|a| a.get(annotations::POLICY))
-                .is_some()
-        })
-        .count();
-    DAEMONSETS_WATCHED.set(ds_count as i64);
+    // Count resources with Headwind annotations, per namespace
+    set_watched(
+        &DEPLOYMENTS_WATCHED,
+        &stores::list::<Deployment>(&client).await?,
+    );
+    set_watched(
+        &STATEFULSETS_WATCHED,
+        &stores::list::<StatefulSet>(&client).await?,
+    );
+    set_watched(
+        &DAEMONSETS_WATCHED,
+        &stores::list::<DaemonSet>(&client).await?,
+    );
+    set_watched(&CRONJOBS_WATCHED, &stores::list::<CronJob>(&client).await?);
+    set_watched(
+        &HELM_RELEASES_WATCHED,
+        &stores::list::<HelmRelease>(&client).await?,
+    );
 
-    // Count HelmReleases with Headwind annotations
-    use crate::models::HelmRelease;
-    let helm_releases: Api<HelmRelease> = Api::all(client);
-    let hr_list = helm_releases.list(&ListParams::default()).await?;
-    let hr_count = hr_list
-        .items
+    // Count UpdateRequests waiting for approval and the age of the oldest, per namespace
+    let update_requests = namespaces::list_watched::<UpdateRequest>(&client).await?;
+    let pending: Vec<&UpdateRequest> = update_requests
         .iter()
-        .filter(|hr| {
-            hr.metadata
-                .annotations
+        .filter(|ur| {
+            ur.status
                 .as_ref()
-                .and_then(|a| a.get(annotations::POLICY))
-                .is_some()
+                .is_none_or(|s| s.phase == UpdatePhase::Pending)
         })
-        .count();
-    HELM_RELEASES_WATCHED.set(hr_count as i64);
+        .collect();
+    set_per_namespace(
+        &UPDATES_PENDING,
+        pending.iter().map(|ur| ur.namespace().unwrap_or_default()),
+    );
+    let now = chrono::Utc::now();
+    set_oldest_per_namespace(
+        &OLDEST_PENDING_UPDATE_AGE,
+        pending.iter().filter_map(|ur| {
+            let created = ur.metadata.creation_timestamp.as_ref()?;
+            Some((
+                ur.namespace().unwrap_or_default(),
+                (now - created.0).num_seconds(),
+            ))
+        }),
+    );
 
     Ok(())
 }
 
+/// Record how long an UpdateRequest waited for its decision (`approved` or `rejected`)
+pub fn observe_approval_latency(
+    update_request: &crate::models::crd::UpdateRequest,
+    decision: &str,
+) {
+    let Some(created) = update_request.metadata.creation_timestamp.as_ref() else {
+        return;
+    };
+    let waited = (chrono::Utc::now() - created.0).num_milliseconds().max(0) as f64 / 1000.0;
+    let [namespace, kind, policy] = update_request_labels(&update_request.spec);
+    APPROVAL_LATENCY
+        .with_label_values(&[namespace, kind, policy, decision])
+        .observe(waited);
+}
+
+/// `namespace`, `kind` and `policy` label values for an UpdateRequest
+pub fn update_request_labels(spec: &crate::models::crd::UpdateRequestSpec) -> [&str; 3] {
+    [
+        spec.target_ref.namespace.as_str(),
+        spec.target_ref.kind.as_str(),
+        spec.policy.as_str(),
+    ]
+}
+
+/// Set a per-namespace gauge to the number of resources carrying a Headwind policy
+fn set_watched<K: kube::Resource>(gauge: &IntGaugeVec, resources: &[K]) {
+    use crate::models::policy::annotations;
+
+    set_per_namespace(
+        gauge,
+        resources
+            .iter()
+            .filter(|r| r.annotations().contains_key(annotations::POLICY))
+            .map(|r| r.namespace().unwrap_or_default()),
+    );
+}
+
+/// Set a per-namespace gauge to the number of times each namespace occurs. Namespaces that
+/// no longer occur are dropped rather than kept at their last value.
+fn set_per_namespace(gauge: &IntGaugeVec, namespaces: impl Iterator<Item = String>) {
+    let mut counts: BTreeMap<String, i64> = BTreeMap::new();
+    for namespace in namespaces {
+        *counts.entry(namespace).or_default() += 1;
+    }
+
+    gauge.reset();
+    for (namespace, count) in counts {
+        gauge.with_label_values(&[&namespace]).set(count);
+    }
+}
+
+/// Set a per-namespace gauge to the largest value of each namespace. Namespaces without values
+/// are dropped.
+fn set_oldest_per_namespace(gauge: &IntGaugeVec, ages: impl Iterator<Item = (String, i64)>) {
+    let mut oldest: BTreeMap<String, i64> = BTreeMap::new();
+    for (namespace, age) in ages {
+        let entry = oldest.entry(namespace).or_insert(age);
+        *entry = (*entry).max(age);
+    }
+
+    gauge.reset();
+    for (namespace, age) in oldest {
+        gauge.with_label_values(&[&namespace]).set(age);
+    }
+}
+
 /// Start a background task to periodically update resource gauges
 pub fn start_gauge_updater(client: kube::Client) -> JoinHandle<()> {
     tokio::spawn(async move {
@@ -463,3 +1059,48 @@ pub fn start_gauge_updater(client: kube::Client) -> JoinHandle<()> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::core::Collector;
+
+    #[test]
+    fn test_set_per_namespace() {
+        let gauge = IntGaugeVec::new(Opts::new("test_watched", "Test"), &["namespace"]).unwrap();
+        set_per_namespace(
+            &gauge,
+            ["payments", "search", "payments"]
+                .into_iter()
+                .map(String::from),
+        );
+        assert_eq!(gauge.with_label_values(&["payments"]).get(), 2);
+        assert_eq!(gauge.with_label_values(&["search"]).get(), 1);
+
+        // Namespaces without watched resources are dropped
+        set_per_namespace(&gauge, ["search"].into_iter().map(String::from));
+        let namespaces: Vec<String> = gauge
+            .collect()
+            .iter()
+            .flat_map(|family| family.get_metric())
+            .map(|metric| metric.get_label()[0].value().to_string())
+            .collect();
+        assert_eq!(namespaces, vec!["search".to_string()]);
+    }
+
+    #[test]
+    fn test_set_oldest_per_namespace() {
+        let gauge = IntGaugeVec::new(Opts::new("test_oldest", "Test"), &["namespace"]).unwrap();
+        set_oldest_per_namespace(
+            &gauge,
+            [("payments", 120), ("search", 30), ("payments", 900)]
+                .into_iter()
+                .map(|(namespace, age)| (namespace.to_string(), age)),
+        );
+        assert_eq!(gauge.with_label_values(&["payments"]).get(), 900);
+        assert_eq!(gauge.with_label_values(&["search"]).get(), 30);
+
+        set_oldest_per_namespace(&gauge, std::iter::empty());
+        assert!(gauge.collect()[0].get_metric().is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+//! Pushes Headwind's metrics to VictoriaMetrics and InfluxDB.
+//!
+//! With `observability.push.enabled`, every `observability.push.interval` seconds the
+//! registry is written to each enabled backend: VictoriaMetrics gets the Prometheus exposition
+//! on `/api/v1/import/prometheus`, InfluxDB the same samples as line protocol on
+//! `/api/v2/write`. Series keep their Prometheus names and labels (one InfluxDB measurement
+//! per metric with a `value` field), so the observability page queries them like scraped ones.
+
+use super::{METRICS_PUSHES, REGISTRY};
+use crate::config::{HeadwindConfig, get_cached_config};
+use anyhow::{Context, Result};
+use prometheus::{Encoder, TextEncoder};
+use reqwest::Client;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Longest wait for a backend to accept a push
+const PUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Start a background task pushing metrics to the configured backends. Settings are read
+/// again before every push, so enabling pushes or changing the interval needs no restart.
+pub fn start_metrics_pusher() -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = Client::builder()
+            .timeout(PUSH_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        loop {
+            let config = get_cached_config().unwrap_or_default();
+            if config.observability.push.enabled {
+                push_metrics(&client, &config).await;
+            }
+            let interval = config.observability.push.interval.max(1);
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    })
+}
+
+/// Push the current metrics to every enabled push backend
+async fn push_metrics(client: &Client, config: &HeadwindConfig) {
+    let exposition = match encode() {
+        Ok(exposition) => exposition,
+        Err(e) => {
+            warn!("Failed to encode metrics for pushing: {:#}", e);
+            return;
+        },
+    };
+    let observability = &config.observability;
+
+    if observability.victoriametrics.enabled
+        && let Some(url) = &observability.victoriametrics.url
+    {
+        let result = push_victoriametrics(client, url, &exposition).await;
+        record("victoriametrics", url, result);
+    }
+
+    if observability.influxdb.enabled
+        && let Some(url) = &observability.influxdb.url
+    {
+        let influxdb = &observability.influxdb;
+        let lines = to_line_protocol(&exposition, chrono::Utc::now().timestamp());
+        let token = influxdb.resolved_token().unwrap_or_default();
+        let result = push_influxdb(
+            client,
+            url,
+            influxdb.org.as_deref().unwrap_or("headwind"),
+            influxdb.bucket.as_deref().unwrap_or("metrics"),
+            &token,
+            lines,
+        )
+        .await;
+        record("influxdb", url, result);
+    }
+}
+
+fn record(backend: &str, url: &str, result: Result<()>) {
+    match result {
+        Ok(()) => {
+            debug!("Pushed metrics to {} at {}", backend, url);
+            METRICS_PUSHES
+                .with_label_values(&[backend, "success"])
+                .inc();
+        },
+        Err(e) => {
+            warn!("Failed to push metrics to {} at {}: {:#}", backend, url, e);
+            METRICS_PUSHES.with_label_values(&[backend, "error"]).inc();
+        },
+    }
+}
+
+/// Current metrics in the Prometheus text format
+fn encode() -> Result<String> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+async fn push_victoriametrics(client: &Client, url: &str, exposition: &str) -> Result<()> {
+    client
+        .post(format!(
+            "{}/api/v1/import/prometheus",
+            url.trim_end_matches('/')
+        ))
+        .query(&[("extra_label", "job=headwind")])
+        .body(exposition.to_string())
+        .send()
+        .await?
+        .error_for_status()
+        .context("VictoriaMetrics rejected the metrics")?;
+    Ok(())
+}
+
+async fn push_influxdb(
+    client: &Client,
+    url: &str,
+    org: &str,
+    bucket: &str,
+    token: &str,
+    lines: String,
+) -> Result<()> {
+    client
+        .post(format!("{}/api/v2/write", url.trim_end_matches('/')))
+        .query(&[("org", org), ("bucket", bucket), ("precision", "s")])
+        .header("Authorization", format!("Token {}", token))
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(lines)
+        .send()
+        .await?
+        .error_for_status()
+        .context("InfluxDB rejected the metrics")?;
+    Ok(())
+}
+
+/// Convert Prometheus exposition samples to InfluxDB line protocol with `timestamp` in
+/// seconds. Labels become tags; infinite and NaN samples, which InfluxDB can't store, are
+/// dropped.
+fn to_line_protocol(exposition: &str, timestamp: i64) -> String {
+    exposition
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (series, value) = line.rsplit_once(' ')?;
+            let value: f64 = value.parse().ok().filter(|v: &f64| v.is_finite())?;
+            let (name, labels) = match series.split_once('{') {
+                Some((name, labels)) => (name, parse_labels(labels.strip_suffix('}')?)),
+                None => (series, Vec::new()),
+            };
+
+            let mut point = escape(name, &[',', ' ']);
+            for (key, value) in labels.iter().filter(|(_, value)| !value.is_empty()) {
+                point.push(',');
+                point.push_str(&escape(key, &[',', ' ', '=']));
+                point.push('=');
+                point.push_str(&escape(value, &[',', ' ', '=']));
+            }
+            Some(format!("{} value={} {}", point, value, timestamp))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse `key="value",...` of an exposition sample, unescaping the values
+fn parse_labels(labels: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut chars = labels.chars();
+    loop {
+        let key: String = chars
+            .by_ref()
+            .take_while(|c| *c != '=')
+            .filter(|c| *c != ',')
+            .collect();
+        if key.trim().is_empty() || chars.next() != Some('"') {
+            break;
+        }
+
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(escaped) => value.push(escaped),
+                    None => break,
+                },
+                '"' => break,
+                c => value.push(c),
+            }
+        }
+        parsed.push((key.trim().to_string(), value));
+    }
+    parsed
+}
+
+/// Backslash-escape the characters line protocol reserves in this position
+fn escape(value: &str, reserved: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '\n' {
+            escaped.push_str("\\n");
+            continue;
+        }
+        if reserved.contains(&c) || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_line_protocol() {
+        let exposition = r#"# HELP headwind_updates_applied_total Total number of updates applied
+# TYPE headwind_updates_applied_total counter
+headwind_updates_applied_total{cluster="prod eu",kind="Deployment",namespace="payments"} 3
+headwind_polling_cycles_total 12
+headwind_reconcile_duration_seconds_bucket{le="+Inf"} 7
+headwind_notifications_failed_total{channel="slack",error="a \"quoted\", b=c"} 1
+headwind_cache_entries NaN
+"#;
+
+        assert_eq!(
+            to_line_protocol(exposition, 1700000000),
+            [
+                r#"headwind_updates_applied_total,cluster=prod\ eu,kind=Deployment,namespace=payments value=3 1700000000"#,
+                "headwind_polling_cycles_total value=12 1700000000",
+                r#"headwind_reconcile_duration_seconds_bucket,le=+Inf value=7 1700000000"#,
+                r#"headwind_notifications_failed_total,channel=slack,error=a\ "quoted"\,\ b\=c value=1 1700000000"#,
+            ]
+            .join("\n")
+        );
+    }
+}
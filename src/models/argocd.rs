@@ -0,0 +1,296 @@
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Application is an ArgoCD custom resource describing what to deploy from where.
+/// Only the fields Headwind reads are modeled; Headwind never replaces the whole object.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[kube(
+    group = "argoproj.io",
+    version = "v1alpha1",
+    kind = "Application",
+    namespaced
+)]
+#[kube(status = "ApplicationStatus")]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSpec {
+    /// Single source of the application
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ApplicationSource>,
+
+    /// Multiple sources (mutually exclusive with `source`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<ApplicationSource>,
+
+    /// ArgoCD project the application belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSource {
+    /// Git or Helm repository URL
+    #[serde(rename = "repoURL")]
+    pub repo_url: String,
+
+    /// Git revision, or chart version for Helm repositories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_revision: Option<String>,
+
+    /// Path within a Git repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Chart name when the source is a Helm repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chart: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub helm: Option<ApplicationSourceHelm>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kustomize: Option<ApplicationSourceKustomize>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSourceHelm {
+    /// Helm parameter overrides (`--set`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<HelmParameter>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HelmParameter {
+    pub name: String,
+
+    #[serde(default)]
+    pub value: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub force_string: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSourceKustomize {
+    /// Image overrides in kustomize format (`name=newName:tag` or `name:tag`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+/// ApplicationSet is an ArgoCD custom resource generating Applications from a template.
+/// Only the template's Application spec is modeled; Headwind patches it when a generated
+/// Application needs a new image or chart version, since the ApplicationSet controller
+/// reverts changes made to the Applications themselves.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[kube(
+    group = "argoproj.io",
+    version = "v1alpha1",
+    kind = "ApplicationSet",
+    namespaced
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSetSpec {
+    pub template: ApplicationSetTemplate,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSetTemplate {
+    /// Spec of every generated Application, with generator parameters (`{{...}}`) unexpanded
+    pub spec: ApplicationSpec,
+}
+
+/// Simplified Application status
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationStatus {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<ApplicationSyncStatus>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<ApplicationHealthStatus>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSyncStatus {
+    /// Synced, OutOfSync or Unknown
+    #[serde(default)]
+    pub status: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationHealthStatus {
+    /// Healthy, Progressing, Degraded, Suspended, Missing or Unknown
+    #[serde(default)]
+    pub status: String,
+}
+
+impl ApplicationSpec {
+    /// Sources with their JSON pointer (`/spec/source` or `/spec/sources/<n>`)
+    pub fn sources_with_paths(&self) -> Vec<(String, &ApplicationSource)> {
+        match &self.source {
+            Some(source) => vec![("/spec/source".to_string(), source)],
+            None => self
+                .sources
+                .iter()
+                .enumerate()
+                .map(|(i, source)| (format!("/spec/sources/{}", i), source))
+                .collect(),
+        }
+    }
+
+    /// The first source that deploys a chart from a Helm repository
+    pub fn chart_source(&self) -> Option<(String, &ApplicationSource)> {
+        self.sources_with_paths()
+            .into_iter()
+            .find(|(_, source)| source.chart.is_some())
+    }
+}
+
+impl ApplicationSource {
+    /// Value of a Helm parameter override, if set
+    pub fn helm_parameter(&self, name: &str) -> Option<&str> {
+        self.helm
+            .as_ref()?
+            .parameters
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.value.as_str())
+    }
+
+    /// Tag of the kustomize image override for `image`, if set
+    pub fn kustomize_image_tag(&self, image: &str) -> Option<&str> {
+        self.kustomize
+            .as_ref()?
+            .images
+            .iter()
+            .map(|entry| parse_kustomize_image(entry))
+            .find(|(name, _)| *name == image)
+            .and_then(|(_, tag)| tag)
+    }
+
+    /// Index of the kustomize image override for `image`, if set
+    pub fn kustomize_image_index(&self, image: &str) -> Option<usize> {
+        self.kustomize
+            .as_ref()?
+            .images
+            .iter()
+            .position(|entry| parse_kustomize_image(entry).0 == image)
+    }
+
+    /// Whether this source renders Helm (a chart, or a Git path with Helm options)
+    pub fn is_helm(&self) -> bool {
+        self.chart.is_some() || self.helm.is_some()
+    }
+}
+
+/// Split a kustomize image override (`name=newName:tag` or `name:tag`) into the image name
+/// it applies to and its tag
+fn parse_kustomize_image(entry: &str) -> (&str, Option<&str>) {
+    let split_tag = |image: &str| -> (usize, Option<usize>) {
+        match image.rsplit_once(':') {
+            Some((name, tag)) if !tag.contains('/') => (name.len(), Some(name.len() + 1)),
+            _ => (image.len(), None),
+        }
+    };
+
+    match entry.split_once('=') {
+        Some((name, target)) => {
+            let (_, tag_start) = split_tag(target);
+            (name, tag_start.map(|start| &target[start..]))
+        },
+        None => {
+            let (name_end, tag_start) = split_tag(entry);
+            (&entry[..name_end], tag_start.map(|start| &entry[start..]))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(chart: Option<&str>) -> ApplicationSource {
+        ApplicationSource {
+            repo_url: "https://charts.example.com".to_string(),
+            target_revision: Some("1.2.0".to_string()),
+            chart: chart.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_deserialize_application() {
+        let app: Application = serde_json::from_value(serde_json::json!({
+            "apiVersion": "argoproj.io/v1alpha1",
+            "kind": "Application",
+            "metadata": { "name": "web", "namespace": "argocd" },
+            "spec": {
+                "project": "default",
+                "source": {
+                    "repoURL": "https://github.com/example/deploy.git",
+                    "path": "charts/web",
+                    "targetRevision": "main",
+                    "helm": { "parameters": [{ "name": "image.tag", "value": "1.0.0" }] }
+                },
+                "destination": { "server": "https://kubernetes.default.svc", "namespace": "web" },
+                "syncPolicy": { "automated": { "selfHeal": true } }
+            }
+        }))
+        .unwrap();
+
+        let source = app.spec.source.as_ref().unwrap();
+        assert_eq!(source.repo_url, "https://github.com/example/deploy.git");
+        assert_eq!(source.helm_parameter("image.tag"), Some("1.0.0"));
+        assert!(source.is_helm());
+    }
+
+    #[test]
+    fn test_sources_with_paths() {
+        let spec = ApplicationSpec {
+            source: None,
+            sources: vec![source(None), source(Some("web"))],
+            project: None,
+        };
+
+        let (path, chart_source) = spec.chart_source().unwrap();
+        assert_eq!(path, "/spec/sources/1");
+        assert_eq!(chart_source.chart.as_deref(), Some("web"));
+
+        let single = ApplicationSpec {
+            source: Some(source(Some("web"))),
+            sources: Vec::new(),
+            project: None,
+        };
+        assert_eq!(single.chart_source().unwrap().0, "/spec/source");
+    }
+
+    #[test]
+    fn test_kustomize_image_tag() {
+        let mut src = source(None);
+        src.kustomize = Some(ApplicationSourceKustomize {
+            images: vec![
+                "nginx:1.25.0".to_string(),
+                "myapp=registry.example.com/myapp:2.0.0".to_string(),
+            ],
+        });
+
+        assert_eq!(src.kustomize_image_tag("nginx"), Some("1.25.0"));
+        assert_eq!(src.kustomize_image_tag("myapp"), Some("2.0.0"));
+        assert_eq!(src.kustomize_image_tag("redis"), None);
+        assert_eq!(src.kustomize_image_index("myapp"), Some(1));
+        assert_eq!(
+            parse_kustomize_image("localhost:5000/app"),
+            ("localhost:5000/app", None)
+        );
+    }
+}
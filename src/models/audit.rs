@@ -0,0 +1,122 @@
+use super::crd::TargetRef;
+use chrono::{DateTime, Utc};
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// UpdateAudit records a single step in the life of an update: detection, approval,
+/// rejection, rollout or rollback. Records are written by Headwind and never modified.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[kube(
+    group = "headwind.sh",
+    version = "v1alpha1",
+    kind = "UpdateAudit",
+    plural = "updateaudits",
+    shortname = "ua",
+    namespaced,
+    printcolumn = r#"{"name":"Action", "type":"string", "jsonPath":".spec.action"}"#,
+    printcolumn = r#"{"name":"Kind", "type":"string", "jsonPath":".spec.targetRef.kind"}"#,
+    printcolumn = r#"{"name":"Target", "type":"string", "jsonPath":".spec.targetRef.name"}"#,
+    printcolumn = r#"{"name":"New", "type":"string", "jsonPath":".spec.newImage"}"#,
+    printcolumn = r#"{"name":"Actor", "type":"string", "jsonPath":".spec.actor"}"#,
+    printcolumn = r#"{"name":"Outcome", "type":"string", "jsonPath":".spec.outcome"}"#,
+    printcolumn = r#"{"name":"Age", "type":"date", "jsonPath":".metadata.creationTimestamp"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAuditSpec {
+    /// What happened
+    pub action: AuditAction,
+
+    /// Resource the update applies to
+    pub target_ref: TargetRef,
+
+    /// Container the update applies to (for image updates)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+
+    /// Image or chart:version before the update
+    pub current_image: String,
+
+    /// Image or chart:version after the update
+    pub new_image: String,
+
+    /// User that acted, or `headwind` for automatic actions
+    pub actor: String,
+
+    /// Component that recorded the event (controller, api, scheduler, gitops, rollback)
+    pub source: String,
+
+    /// Whether the action succeeded
+    pub outcome: AuditOutcome,
+
+    /// UpdateRequest the action belongs to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_request: Option<String>,
+
+    /// Details, such as a rejection reason or error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Trace ID of the update flow, shared with its notifications and exported spans
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+
+    /// When the action happened
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Step in the life of an update
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq)]
+pub enum AuditAction {
+    /// A new version allowed by the policy was found
+    Detected,
+    /// An approver approved the UpdateRequest
+    Approved,
+    /// An approver rejected the UpdateRequest
+    Rejected,
+    /// The update was rolled out (or committed to Git)
+    Applied,
+    /// The UpdateRequest was not approved in time
+    Expired,
+    /// The workload was rolled back to a previous image
+    RolledBack,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Detected => "Detected",
+            AuditAction::Approved => "Approved",
+            AuditAction::Rejected => "Rejected",
+            AuditAction::Applied => "Applied",
+            AuditAction::Expired => "Expired",
+            AuditAction::RolledBack => "RolledBack",
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            AuditAction::Detected,
+            AuditAction::Approved,
+            AuditAction::Rejected,
+            AuditAction::Applied,
+            AuditAction::Expired,
+            AuditAction::RolledBack,
+        ]
+        .into_iter()
+        .find(|action| action.as_str().eq_ignore_ascii_case(s))
+        .ok_or_else(|| format!("unknown audit action: {}", s))
+    }
+}
+
+/// Result of an audited action
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq)]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
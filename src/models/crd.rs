@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use kube::CustomResource;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// UpdateRequest is a CRD that represents a pending update to a Kubernetes resource
 #[allow(dead_code)]
@@ -35,6 +36,11 @@ pub struct UpdateRequestSpec {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub container_name: Option<String>,
 
+    /// Helm parameter holding the image tag, when the target is an ArgoCD Application, or
+    /// the values path holding the image, when the target is a HelmRelease
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_parameter: Option<String>,
+
     /// Current image or chart version
     pub current_image: String,
 
@@ -55,12 +61,61 @@ pub struct UpdateRequestSpec {
     /// Optional expiration time for this update request
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<DateTime<Utc>>,
+
+    /// Number of distinct approvers needed before the update is applied (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_approvals: Option<u32>,
+
+    /// Git location the update is committed to instead of patching the target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_write_back: Option<GitWriteBack>,
+
+    /// Other containers of the update group (`headwind.sh/update-group`) updated together
+    /// with `containerName`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grouped_containers: Vec<ContainerUpdate>,
+
+    /// Event that detected the update (webhook endpoint and sender, poll cycle, digest and
+    /// detection time)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceEvent>,
+
+    /// Version the new image's tag currently stands for, from the image's
+    /// `org.opencontainers.image.version` annotation, when the tag is a channel such as
+    /// `stable` pinned to a digest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_version: Option<String>,
 }
 
 fn default_require_approval() -> bool {
     true
 }
 
+impl UpdateRequestSpec {
+    /// New image of every container the update changes: `containerName` first, then the
+    /// grouped containers
+    pub fn container_images(&self) -> Vec<(String, String)> {
+        self.container_name
+            .iter()
+            .map(|name| (name.clone(), self.new_image.clone()))
+            .chain(
+                self.grouped_containers
+                    .iter()
+                    .map(|c| (c.container_name.clone(), c.new_image.clone())),
+            )
+            .collect()
+    }
+}
+
+/// Image change of one container of a grouped update
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerUpdate {
+    pub container_name: String,
+    pub current_image: String,
+    pub new_image: String,
+}
+
 /// Reference to the target Kubernetes resource
 #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -69,7 +124,7 @@ pub struct TargetRef {
     /// API version of the target resource
     pub api_version: String,
 
-    /// Kind of the target resource (e.g., Deployment, StatefulSet)
+    /// Kind of the target resource (e.g., Deployment, StatefulSet, Application)
     pub kind: String,
 
     /// Name of the target resource
@@ -79,6 +134,49 @@ pub struct TargetRef {
     pub namespace: String,
 }
 
+/// Where an image update is written back to in Git
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitWriteBack {
+    /// Repository, e.g. github.com/org/repo
+    pub repository: String,
+
+    /// Path of the file holding the image reference
+    pub path: String,
+
+    /// How the file is edited
+    #[serde(default)]
+    pub format: GitFileFormat,
+
+    /// Branch to commit to (or to open the pull request against)
+    pub branch: String,
+
+    /// Dotted key of the tag in a Helm values file (helm format only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_path: Option<String>,
+
+    /// Commit to a new branch and open a pull request instead of committing to `branch`
+    #[serde(default)]
+    pub pull_request: bool,
+
+    /// Git hosting provider (github or gitlab). Inferred from the repository host when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+}
+
+/// Format of a file edited by Git write-back
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitFileFormat {
+    /// Kubernetes manifest: `image:` fields are rewritten
+    #[default]
+    Manifest,
+    /// kustomization.yaml: the `images:` entry's `newTag` is set
+    Kustomize,
+    /// Helm values file: the key at `valuePath` is set
+    Helm,
+}
+
 /// Type of update
 #[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -97,9 +195,58 @@ pub enum UpdatePolicyType {
     Minor,
     Patch,
     Glob,
+    Regex,
+    Range,
+    Digest,
     None,
 }
 
+impl UpdatePolicyType {
+    /// The policy as written in the CRD, e.g. `minor`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdatePolicyType::Major => "major",
+            UpdatePolicyType::Minor => "minor",
+            UpdatePolicyType::Patch => "patch",
+            UpdatePolicyType::Glob => "glob",
+            UpdatePolicyType::Regex => "regex",
+            UpdatePolicyType::Range => "range",
+            UpdatePolicyType::Digest => "digest",
+            UpdatePolicyType::None => "none",
+        }
+    }
+}
+
+/// Event an update was detected from
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceEvent {
+    /// How the update was found: `webhook`, `pubsub`, `eventbus`, `polling` or `reconcile`
+    /// (while reconciling the target). UpdateRequests created by hand have no source.
+    pub kind: String,
+
+    /// Webhook endpoint, Pub/Sub subscription, event bus subject or polled registry the event
+    /// came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+
+    /// Address of the webhook sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+
+    /// Start of the poll cycle that found the version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_cycle: Option<DateTime<Utc>>,
+
+    /// Digest reported by the event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+
+    /// When Headwind received the event or detected the update
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received_at: Option<DateTime<Utc>>,
+}
+
 /// Status of the UpdateRequest
 #[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -117,6 +264,10 @@ pub struct UpdateRequestStatus {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub approved_at: Option<DateTime<Utc>>,
 
+    /// Approvals collected so far, when more than one is required
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approvals: Vec<Approval>,
+
     /// User or system that rejected the update
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rejected_by: Option<String>,
@@ -132,21 +283,316 @@ pub struct UpdateRequestStatus {
     /// Last time this status was updated
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_updated: Option<DateTime<Utc>>,
+
+    /// How often the update was detected while this request was open (1 when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_count: Option<u32>,
+
+    /// Last time the update was detected again while this request was open
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_observed_at: Option<DateTime<Utc>>,
+
+    /// Change ticket opened for the update (HEADWIND_CHANGE_TICKETS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_ticket: Option<ChangeTicket>,
+
+    /// Release notes of the new version (HEADWIND_RELEASE_NOTES)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_notes: Option<ReleaseNotes>,
+
+    /// Creation time, size, labels and exposed ports of the new image (HEADWIND_IMAGE_METADATA)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_metadata: Option<ImageMetadata>,
+
+    /// Vulnerability scan of the new image (HEADWIND_VULNERABILITY_SCAN)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vulnerability_scan: Option<VulnerabilityScan>,
+
+    /// Why an approved update is held back: the workloads it is ordered after
+    /// (`headwind.sh/rollout-after`) haven't finished their updates, or the update gate reports
+    /// a freeze
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiting_for: Option<String>,
+
+    /// When a Deferred update waiting for the target's minimum update interval
+    /// (`headwind.sh/min-update-interval`) is released
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deferred_until: Option<DateTime<Utc>>,
+
+    /// Progress of the Deployment rollout the applied update started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<RolloutProgress>,
+
+    /// Standard conditions (Approved, Applied, HealthChecked, RolledBack), so
+    /// `kubectl wait --for=condition=Applied` and other controllers can follow the update
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<Condition>,
+}
+
+impl UpdateRequestStatus {
+    /// The condition of the given type, if it was ever set
+    pub fn condition(&self, type_: &str) -> Option<&Condition> {
+        self.conditions.iter().find(|c| c.type_ == type_)
+    }
+
+    /// Set a condition, keeping its lastTransitionTime when the status did not change
+    pub fn set_condition(
+        &mut self,
+        type_: &str,
+        status: bool,
+        reason: &str,
+        message: Option<String>,
+    ) {
+        let condition = Condition::new(type_, status, reason, message);
+        match self.conditions.iter_mut().find(|c| c.type_ == type_) {
+            Some(existing) if existing.status == condition.status => {
+                existing.reason = condition.reason;
+                existing.message = condition.message;
+            },
+            Some(existing) => *existing = condition,
+            None => self.conditions.push(condition),
+        }
+    }
+
+    /// Start a new status from the conditions of `previous`, since merge patches replace the
+    /// whole conditions array
+    pub fn with_conditions_of(previous: Option<&UpdateRequestStatus>) -> Self {
+        UpdateRequestStatus {
+            conditions: previous.map(|s| s.conditions.clone()).unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Condition types of an UpdateRequest
+pub mod conditions {
+    /// The update was approved (False once rejected or expired)
+    pub const APPROVED: &str = "Approved";
+    /// The update was applied to the target
+    pub const APPLIED: &str = "Applied";
+    /// The target was healthy after the update (auto-rollback and canaries)
+    pub const HEALTH_CHECKED: &str = "HealthChecked";
+    /// The update was rolled back after a failed health check
+    pub const ROLLED_BACK: &str = "RolledBack";
+}
+
+/// A standard Kubernetes condition
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    /// Condition type, see [`conditions`]
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// `True`, `False` or `Unknown`
+    pub status: String,
+
+    /// CamelCase reason for the last transition
+    pub reason: String,
+
+    /// Human readable details
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// When the status last changed
+    pub last_transition_time: DateTime<Utc>,
+}
+
+impl Condition {
+    pub fn new(type_: &str, status: bool, reason: &str, message: Option<String>) -> Self {
+        Condition {
+            type_: type_.to_string(),
+            status: if status { "True" } else { "False" }.to_string(),
+            reason: reason.to_string(),
+            message,
+            last_transition_time: Utc::now(),
+        }
+    }
+
+    pub fn is_true(&self) -> bool {
+        self.status == "True"
+    }
+}
+
+/// A change ticket in an external ticket system
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeTicket {
+    /// Ticket system: `jira` or `servicenow`
+    pub system: String,
+
+    /// Ticket number shown to users (Jira issue key, ServiceNow change number)
+    pub id: String,
+
+    /// Link to the ticket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// ID the ticket system's API addresses the ticket by, when it differs from `id`
+    /// (ServiceNow `sys_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_id: Option<String>,
+}
+
+/// Release notes of the version an UpdateRequest proposes
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseNotes {
+    /// Where the notes were found: `github` or `artifacthub`
+    pub source: String,
+
+    /// Link to the full release notes
+    pub url: String,
+
+    /// Release title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Start of the release notes, shortened for display
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
 }
 
-/// Phase of the UpdateRequest lifecycle
+/// Metadata of the image an UpdateRequest proposes, read from its manifest and config in the
+/// registry
 #[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMetadata {
+    /// When the image was built
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+
+    /// Compressed size in bytes: config and layers, as pulled for one platform
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Labels of the image config, overridden by manifest annotations
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+
+    /// Ports the image exposes, e.g. `8080/tcp`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exposed_ports: Vec<String>,
+}
+
+/// Vulnerabilities found in the image an UpdateRequest proposes, by severity
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VulnerabilityScan {
+    /// Scanner that produced the counts: `trivy`
+    pub scanner: String,
+
+    /// Image that was scanned
+    pub image: String,
+
+    /// When the scan finished
+    pub scanned_at: DateTime<Utc>,
+
+    #[serde(default)]
+    pub critical: u32,
+
+    #[serde(default)]
+    pub high: u32,
+
+    #[serde(default)]
+    pub medium: u32,
+
+    #[serde(default)]
+    pub low: u32,
+
+    #[serde(default)]
+    pub unknown: u32,
+
+    /// Why the scan failed; the counts are zero then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Rollout of a Deployment after an update was applied, as reported by its status
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RolloutProgress {
+    pub state: RolloutState,
+
+    /// Desired replicas
+    #[serde(default)]
+    pub replicas: i32,
+
+    /// Replicas running the new pod template
+    #[serde(default)]
+    pub updated_replicas: i32,
+
+    #[serde(default)]
+    pub ready_replicas: i32,
+
+    #[serde(default)]
+    pub available_replicas: i32,
+
+    /// Why the rollout failed, or what it is waiting for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// When the progress was last read from the Deployment
+    pub last_updated: DateTime<Utc>,
+}
+
+/// State of a rollout, following `kubectl rollout status`
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema, PartialEq, Eq)]
+pub enum RolloutState {
+    /// New pods are still being created or becoming available
+    Progressing,
+    /// Every replica runs the new pod template and is available
+    Complete,
+    /// The Deployment exceeded its progress deadline, or the rollout was not complete in time
+    Failed,
+}
+
+/// A single approval of an UpdateRequest
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Approval {
+    /// Identity of the approver
+    pub approver: String,
+
+    /// When the approval was given
+    pub approved_at: DateTime<Utc>,
+}
+
+/// Phase of the UpdateRequest lifecycle, ordered from pending to terminal
+#[derive(
+    Deserialize, Serialize, Clone, Debug, Default, JsonSchema, PartialEq, Eq, PartialOrd, Ord,
+)]
 #[allow(dead_code)]
 pub enum UpdatePhase {
     #[default]
     Pending,
     Approved,
+    /// Held back while the update gate (HEADWIND_UPDATE_GATE_URL) reports a freeze, or until
+    /// the target's minimum update interval has passed (`status.deferredUntil`)
+    Deferred,
     Rejected,
     Completed,
     Failed,
     Expired,
 }
 
+impl std::str::FromStr for UpdatePhase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pending" => Ok(UpdatePhase::Pending),
+            "approved" => Ok(UpdatePhase::Approved),
+            "deferred" => Ok(UpdatePhase::Deferred),
+            "rejected" => Ok(UpdatePhase::Rejected),
+            "completed" => Ok(UpdatePhase::Completed),
+            "failed" => Ok(UpdatePhase::Failed),
+            "expired" => Ok(UpdatePhase::Expired),
+            _ => Err(format!("unknown phase: {}", s)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,17 +608,39 @@ mod tests {
             },
             update_type: UpdateType::Image,
             container_name: Some("nginx".to_string()),
+            image_parameter: None,
             current_image: "nginx:1.25.0".to_string(),
             new_image: "nginx:1.26.0".to_string(),
             policy: UpdatePolicyType::Minor,
             reason: Some("New minor version available".to_string()),
             require_approval: true,
             expires_at: None,
+            required_approvals: None,
+            git_write_back: None,
+            grouped_containers: Vec::new(),
+            source: None,
+            resolved_version: None,
         };
 
         assert_eq!(spec.target_ref.name, "nginx");
         assert_eq!(spec.update_type, UpdateType::Image);
         assert_eq!(spec.policy, UpdatePolicyType::Minor);
+        assert_eq!(
+            spec.container_images(),
+            vec![("nginx".to_string(), "nginx:1.26.0".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_grouped_containers_serialization() {
+        let grouped = vec![ContainerUpdate {
+            container_name: "sidecar".to_string(),
+            current_image: "sidecar:1.25.0".to_string(),
+            new_image: "sidecar:1.26.0".to_string(),
+        }];
+        let json = serde_json::to_value(&grouped).unwrap();
+        assert_eq!(json[0]["containerName"], "sidecar");
+        assert_eq!(json[0]["newImage"], "sidecar:1.26.0");
     }
 
     #[test]
@@ -185,4 +653,40 @@ mod tests {
     fn test_require_approval_default() {
         assert!(default_require_approval());
     }
+
+    #[test]
+    fn test_set_condition() {
+        let mut status = UpdateRequestStatus::default();
+        status.set_condition(conditions::APPROVED, false, "PendingApproval", None);
+        let pending = status.condition(conditions::APPROVED).unwrap().clone();
+        assert!(!pending.is_true());
+
+        // Same status: the reason changes, the transition time does not
+        status.set_condition(
+            conditions::APPROVED,
+            false,
+            "PendingApproval",
+            Some("1 of 2 approvals".to_string()),
+        );
+        let still_pending = status.condition(conditions::APPROVED).unwrap();
+        assert_eq!(
+            still_pending.last_transition_time,
+            pending.last_transition_time
+        );
+        assert_eq!(still_pending.message.as_deref(), Some("1 of 2 approvals"));
+
+        status.set_condition(conditions::APPROVED, true, "Approved", None);
+        status.set_condition(conditions::APPLIED, true, "UpdateApplied", None);
+        assert_eq!(status.conditions.len(), 2);
+        assert!(status.condition(conditions::APPROVED).unwrap().is_true());
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["conditions"][1]["type"], "Applied");
+        assert_eq!(json["conditions"][1]["status"], "True");
+        assert!(json["conditions"][1]["lastTransitionTime"].is_string());
+
+        let next = UpdateRequestStatus::with_conditions_of(Some(&status));
+        assert_eq!(next.conditions, status.conditions);
+        assert_eq!(next.phase, UpdatePhase::Pending);
+    }
 }
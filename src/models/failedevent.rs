@@ -0,0 +1,64 @@
+use super::webhook::{ChartPushEvent, ImagePushEvent};
+use chrono::{DateTime, Utc};
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// FailedEvent keeps a registry push event whose processing failed. Headwind retries it with
+/// exponential backoff (also after a restart) and deletes it once processing succeeds. When
+/// the retries are exhausted it stays behind as a dead letter for inspection.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[kube(
+    group = "headwind.sh",
+    version = "v1alpha1",
+    kind = "FailedEvent",
+    plural = "failedevents",
+    shortname = "fe",
+    namespaced,
+    status = "FailedEventStatus",
+    printcolumn = r#"{"name":"Image", "type":"string", "jsonPath":".spec.image.repository"}"#,
+    printcolumn = r#"{"name":"Chart", "type":"string", "jsonPath":".spec.chart.repository"}"#,
+    printcolumn = r#"{"name":"Phase", "type":"string", "jsonPath":".status.phase"}"#,
+    printcolumn = r#"{"name":"Attempts", "type":"integer", "jsonPath":".status.attempts"}"#,
+    printcolumn = r#"{"name":"Age", "type":"date", "jsonPath":".metadata.creationTimestamp"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedEventSpec {
+    /// Image push event, for image events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImagePushEvent>,
+
+    /// Chart push event, for chart events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chart: Option<ChartPushEvent>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedEventStatus {
+    pub phase: FailedEventPhase,
+
+    /// Failed processing attempts so far
+    pub attempts: u32,
+
+    /// Error of the last attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+
+    /// When the last attempt failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_attempt_time: Option<DateTime<Utc>>,
+
+    /// When the event is retried next (unset for dead letters)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_attempt_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, JsonSchema, PartialEq, Eq)]
+pub enum FailedEventPhase {
+    /// Waiting for the next attempt
+    #[default]
+    Retrying,
+    /// Retries exhausted; kept for inspection and not retried again
+    DeadLettered,
+}
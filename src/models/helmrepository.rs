@@ -3,7 +3,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// HelmRepository defines a Helm chart repository (Flux CD v2 API)
-#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
 #[kube(
     group = "source.toolkit.fluxcd.io",
     version = "v1",
@@ -36,6 +36,10 @@ pub struct HelmRepositorySpec {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub pass_credentials: Option<bool>,
 
+    /// Allow plain HTTP connections to an OCI registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insecure: Option<bool>,
+
     /// Type of repository (default, oci)
     #[serde(default, skip_serializing_if = "Option::is_none", rename = "type")]
     pub repository_type: Option<String>,
@@ -0,0 +1,103 @@
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// ImageRepository is a Flux image-reflector resource naming an image to scan for tags.
+/// Only the fields Headwind reads are modeled; the CRD is owned by Flux.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[kube(
+    group = "image.toolkit.fluxcd.io",
+    version = "v1beta2",
+    kind = "ImageRepository",
+    namespaced
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageRepositorySpec {
+    /// Image name without tag, e.g. `ghcr.io/org/app`
+    pub image: String,
+
+    /// How often the repository is scanned, e.g. `5m`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+
+    /// Regular expressions for tags that are never considered (Flux default: `^.*\.sig$`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclusion_list: Option<Vec<String>>,
+
+    /// Whether scanning is suspended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspend: Option<bool>,
+}
+
+/// ImagePolicy is a Flux image-reflector resource selecting the latest tag of an
+/// ImageRepository. Only the fields Headwind reads are modeled; the CRD is owned by Flux.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[kube(
+    group = "image.toolkit.fluxcd.io",
+    version = "v1beta2",
+    kind = "ImagePolicy",
+    namespaced
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagePolicySpec {
+    /// ImageRepository whose tags the policy selects from
+    pub image_repository_ref: ImageRepositoryReference,
+
+    /// Ordering rule for the tags
+    pub policy: ImagePolicyChoice,
+
+    /// Tags to consider, and the part of the tag the policy compares
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter_tags: Option<TagFilter>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageRepositoryReference {
+    pub name: String,
+
+    /// Defaults to the ImagePolicy's namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+/// Exactly one of the fields is set
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagePolicyChoice {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semver: Option<SemVerPolicy>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alphabetical: Option<OrderedPolicy>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numerical: Option<OrderedPolicy>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SemVerPolicy {
+    /// Version range, e.g. `>=1.0.0 <2.0.0` or `~1.2`
+    pub range: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderedPolicy {
+    /// `asc` (default) selects the last tag, `desc` the first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagFilter {
+    /// Regular expression tags must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// Replacement built from the pattern's capture groups, e.g. `$ts`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract: Option<String>,
+}
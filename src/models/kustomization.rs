@@ -0,0 +1,101 @@
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Kustomization is a Flux kustomize-controller resource applying a Kustomize overlay from
+/// a source. Only the fields Headwind reads are modeled; the CRD is owned by Flux.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[kube(
+    group = "kustomize.toolkit.fluxcd.io",
+    version = "v1",
+    kind = "Kustomization",
+    namespaced
+)]
+#[serde(rename_all = "camelCase")]
+pub struct KustomizationSpec {
+    /// Path to the overlay within the source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Image overrides applied on top of the overlay (`kustomize edit set image`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<KustomizeImage>,
+
+    /// Whether reconciliation is suspended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspend: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct KustomizeImage {
+    /// Image name as written in the manifests
+    pub name: String,
+
+    /// Replacement image name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_name: Option<String>,
+
+    /// Replacement tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_tag: Option<String>,
+
+    /// Replacement digest, taking precedence over `newTag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+impl KustomizeImage {
+    /// Image name the rendered workloads run: `newName` when set, otherwise `name`
+    pub fn rendered_name(&self) -> &str {
+        self.new_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl KustomizationSpec {
+    /// Index of the image override that renders `repository`
+    pub fn image_index(&self, repository: &str) -> Option<usize> {
+        self.images
+            .iter()
+            .position(|image| image.rendered_name() == repository)
+    }
+
+    /// Tag the override for `repository` sets, if any
+    pub fn image_tag(&self, repository: &str) -> Option<&str> {
+        self.images[self.image_index(repository)?]
+            .new_tag
+            .as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_kustomization() {
+        let kustomization: Kustomization = serde_json::from_value(serde_json::json!({
+            "apiVersion": "kustomize.toolkit.fluxcd.io/v1",
+            "kind": "Kustomization",
+            "metadata": { "name": "apps", "namespace": "flux-system" },
+            "spec": {
+                "interval": "10m",
+                "path": "./apps/production",
+                "prune": true,
+                "sourceRef": { "kind": "GitRepository", "name": "flux-system" },
+                "images": [
+                    { "name": "nginx", "newTag": "1.25.0" },
+                    { "name": "app", "newName": "ghcr.io/org/app", "newTag": "2.0.0" }
+                ]
+            }
+        }))
+        .unwrap();
+
+        let spec = &kustomization.spec;
+        assert_eq!(spec.path.as_deref(), Some("./apps/production"));
+        assert_eq!(spec.image_tag("nginx"), Some("1.25.0"));
+        assert_eq!(spec.image_index("ghcr.io/org/app"), Some(1));
+        assert_eq!(spec.image_tag("app"), None);
+        assert_eq!(spec.image_index("redis"), None);
+    }
+}
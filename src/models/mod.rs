@@ -1,13 +1,26 @@
+pub mod argocd;
+pub mod audit;
 pub mod crd;
+pub mod failedevent;
 pub mod helmrelease;
 pub mod helmrepository;
+pub mod imagepolicy;
+pub mod kustomization;
 pub mod policy;
+pub mod settings;
 pub mod update;
+pub mod updatetarget;
+pub mod v1beta1;
 pub mod webhook;
 
+pub use argocd::{Application, ApplicationSet, ApplicationSource, ApplicationSpec};
+pub use audit::{AuditAction, AuditOutcome, UpdateAudit, UpdateAuditSpec};
 #[allow(unused_imports)]
 pub use crd::*;
+pub use failedevent::{FailedEvent, FailedEventPhase, FailedEventSpec, FailedEventStatus};
 pub use helmrelease::*;
 pub use helmrepository::*;
 pub use policy::*;
+pub use settings::{HeadwindSettings, HeadwindSettingsSpec, HeadwindSettingsStatus};
+pub use updatetarget::{UpdateTarget, UpdateTargetSpec};
 pub use webhook::{ChartPushEvent, ImagePushEvent};
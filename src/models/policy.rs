@@ -1,8 +1,12 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum UpdatePolicy {
     /// Only update patch versions (1.2.3 -> 1.2.4)
@@ -15,8 +19,14 @@ pub enum UpdatePolicy {
     All,
     /// Match glob pattern
     Glob,
+    /// Match regular expression
+    Regex,
+    /// Match a semver range expression such as `>=1.2, <2.0` or `~1.4`
+    Range,
     /// Force update regardless of version
     Force,
+    /// Keep the tag and follow the digest it points to (`latest@sha256:...`)
+    Digest,
     /// Never update automatically
     None,
 }
@@ -35,12 +45,27 @@ pub enum EventSource {
     None,
 }
 
+/// How a workload picks up a new image (`headwind.sh/update-mode`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateMode {
+    /// Patch the container image (default)
+    #[default]
+    Image,
+    /// Keep the image and restart the pods when its tag moves to another digest, as
+    /// `kubectl rollout restart` does. For `:latest`-style tags pulled with
+    /// `imagePullPolicy: Always`.
+    Restart,
+}
+
 #[derive(Debug, Error)]
 pub enum PolicyError {
     #[error("Invalid policy: {0}")]
     InvalidPolicy(String),
     #[error("Invalid event source: {0}")]
     InvalidEventSource(String),
+    #[error("Invalid update mode: {0}")]
+    InvalidUpdateMode(String),
 }
 
 impl FromStr for UpdatePolicy {
@@ -53,13 +78,45 @@ impl FromStr for UpdatePolicy {
             "major" => Ok(UpdatePolicy::Major),
             "all" => Ok(UpdatePolicy::All),
             "glob" => Ok(UpdatePolicy::Glob),
+            "regex" => Ok(UpdatePolicy::Regex),
+            "range" => Ok(UpdatePolicy::Range),
             "force" => Ok(UpdatePolicy::Force),
+            "digest" => Ok(UpdatePolicy::Digest),
             "none" => Ok(UpdatePolicy::None),
             _ => Err(PolicyError::InvalidPolicy(s.to_string())),
         }
     }
 }
 
+impl std::fmt::Display for UpdatePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UpdatePolicy::Patch => "patch",
+            UpdatePolicy::Minor => "minor",
+            UpdatePolicy::Major => "major",
+            UpdatePolicy::All => "all",
+            UpdatePolicy::Glob => "glob",
+            UpdatePolicy::Regex => "regex",
+            UpdatePolicy::Range => "range",
+            UpdatePolicy::Force => "force",
+            UpdatePolicy::Digest => "digest",
+            UpdatePolicy::None => "none",
+        })
+    }
+}
+
+impl FromStr for UpdateMode {
+    type Err = PolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "image" => Ok(UpdateMode::Image),
+            "restart" => Ok(UpdateMode::Restart),
+            _ => Err(PolicyError::InvalidUpdateMode(s.to_string())),
+        }
+    }
+}
+
 impl FromStr for EventSource {
     type Err = PolicyError;
 
@@ -79,7 +136,7 @@ pub struct ResourcePolicy {
     /// Update policy to apply
     pub policy: UpdatePolicy,
 
-    /// Optional glob pattern for matching versions (when policy is Glob)
+    /// Optional pattern for matching versions (glob when policy is Glob, regex when Regex)
     pub pattern: Option<String>,
 
     /// Whether approval is required before updating
@@ -96,6 +153,86 @@ pub struct ResourcePolicy {
 
     /// Per-resource polling interval in seconds (overrides global setting)
     pub polling_interval: Option<u64>,
+
+    /// Glob patterns for tags that are never updated to, whatever the policy
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+
+    /// Which prerelease versions semver policies may update to
+    #[serde(default)]
+    pub prerelease: PrereleasePolicy,
+
+    /// Pin images to the digest of their tag (`repo:tag@sha256:...`)
+    #[serde(default)]
+    pub pin_digest: bool,
+
+    /// Channel tag such as `stable` the image follows, pinned to the digest it points at
+    /// (`headwind.sh/channel-tag`)
+    #[serde(default)]
+    pub channel_tag: Option<String>,
+
+    /// Whether a tag pushed again is picked up by restarting the pods rather than pinning
+    /// the image to its new digest
+    #[serde(default)]
+    pub update_mode: UpdateMode,
+
+    /// Seconds an UpdateRequest waits for approval before it expires (0 = never)
+    #[serde(default = "default_approval_ttl")]
+    pub approval_ttl: u64,
+
+    /// Distinct approvers an UpdateRequest needs, when more than one
+    #[serde(default)]
+    pub required_approvals: Option<u32>,
+
+    /// Highest version updates may go to (`headwind.sh/max-version`), e.g. `1.28.x`, or a
+    /// semver range such as `>=1.2, <2.0`
+    #[serde(default)]
+    pub max_version: Option<String>,
+}
+
+/// Default time an UpdateRequest waits for approval
+pub const DEFAULT_APPROVAL_TTL: u64 = 24 * 60 * 60;
+
+fn default_approval_ttl() -> u64 {
+    DEFAULT_APPROVAL_TTL
+}
+
+impl ResourcePolicy {
+    /// When an UpdateRequest created now expires
+    pub fn approval_expiry(&self) -> Option<DateTime<Utc>> {
+        if self.approval_ttl == 0 {
+            return None;
+        }
+        Some(Utc::now() + chrono::Duration::seconds(self.approval_ttl as i64))
+    }
+}
+
+/// Prerelease handling for the patch, minor and major policies.
+///
+/// By default a prerelease is only proposed while the workload already runs a prerelease
+/// of the same version (`1.3.0-rc.1` -> `1.3.0-rc.2`). Stable releases are always eligible.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrereleasePolicy {
+    /// Allow updating to any prerelease (`headwind.sh/allow-prerelease`)
+    pub allow: bool,
+
+    /// Only allow prereleases of this channel, e.g. `rc` (`headwind.sh/prerelease-channel`)
+    pub channel: Option<String>,
+}
+
+impl PrereleasePolicy {
+    pub fn from_annotations(annotations: &BTreeMap<String, String>) -> Self {
+        Self {
+            allow: annotations
+                .get(annotations::ALLOW_PRERELEASE)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            channel: annotations
+                .get(annotations::PRERELEASE_CHANNEL)
+                .map(|v| v.trim().to_lowercase())
+                .filter(|v| !v.is_empty()),
+        }
+    }
 }
 
 impl Default for ResourcePolicy {
@@ -108,8 +245,217 @@ impl Default for ResourcePolicy {
             images: Vec::new(),
             event_source: EventSource::default(),
             polling_interval: None,
+            ignore_patterns: Vec::new(),
+            prerelease: PrereleasePolicy::default(),
+            pin_digest: false,
+            channel_tag: None,
+            update_mode: UpdateMode::Image,
+            approval_ttl: DEFAULT_APPROVAL_TTL,
+            required_approvals: None,
+            max_version: None,
+        }
+    }
+}
+
+/// Parse the `headwind.sh/pin-digest` annotation
+pub fn parse_pin_digest(annotations: &BTreeMap<String, String>) -> bool {
+    annotations
+        .get(annotations::PIN_DIGEST)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Parse the `headwind.sh/channel-tag` annotation
+pub fn parse_channel_tag(annotations: &BTreeMap<String, String>) -> Option<String> {
+    annotations
+        .get(annotations::CHANNEL_TAG)
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Parse the `headwind.sh/update-mode` annotation (default: image)
+pub fn parse_update_mode(annotations: &BTreeMap<String, String>) -> UpdateMode {
+    annotations
+        .get(annotations::UPDATE_MODE)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Parse the `headwind.sh/approval-ttl` annotation
+pub fn parse_approval_ttl(annotations: &BTreeMap<String, String>) -> u64 {
+    annotations
+        .get(annotations::APPROVAL_TTL)
+        .and_then(|v| parse_duration_secs(v))
+        .unwrap_or(DEFAULT_APPROVAL_TTL)
+}
+
+/// Parse the `headwind.sh/required-approvals` annotation. A single approval is the default,
+/// so values below 2 yield None.
+pub fn parse_required_approvals(annotations: &BTreeMap<String, String>) -> Option<u32> {
+    annotations
+        .get(annotations::REQUIRED_APPROVALS)
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|n: &u32| *n > 1)
+}
+
+/// Parse the `headwind.sh/max-version` annotation
+pub fn parse_max_version(annotations: &BTreeMap<String, String>) -> Option<String> {
+    annotations
+        .get(annotations::MAX_VERSION)
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Parse the `headwind.sh/min-tag-age` annotation: how long a new tag must have existed before
+/// it is acted on, in seconds. Zero and invalid values yield None.
+pub fn parse_min_tag_age(annotations: &BTreeMap<String, String>) -> Option<u64> {
+    annotations
+        .get(annotations::MIN_TAG_AGE)
+        .and_then(|v| parse_duration_secs(v))
+        .filter(|secs| *secs > 0)
+}
+
+/// Whether a resource is in dry-run mode: updates are detected and proposed as UpdateRequests,
+/// but never applied, even when approved. `headwind.sh/dry-run` overrides HEADWIND_DRY_RUN
+/// (default: false).
+pub fn parse_dry_run(annotations: &BTreeMap<String, String>) -> bool {
+    annotations
+        .get(annotations::DRY_RUN)
+        .cloned()
+        .or_else(|| std::env::var("HEADWIND_DRY_RUN").ok())
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(false)
+}
+
+/// Whether a resource with policy `none` is still notified about new releases of its images
+/// (`update.detected`, without an UpdateRequest). `headwind.sh/notify-detected` overrides
+/// HEADWIND_NOTIFY_DETECTED (default: false).
+pub fn parse_notify_detected(annotations: &BTreeMap<String, String>) -> bool {
+    annotations
+        .get(annotations::NOTIFY_DETECTED)
+        .cloned()
+        .or_else(|| std::env::var("HEADWIND_NOTIFY_DETECTED").ok())
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(false)
+}
+
+/// Parse the `headwind.sh/require-approval` annotation (default: true). Resources in dry-run
+/// mode always require approval, so every update becomes an UpdateRequest.
+pub fn parse_require_approval(annotations: &BTreeMap<String, String>) -> bool {
+    parse_dry_run(annotations)
+        || annotations
+            .get(annotations::REQUIRE_APPROVAL)
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(true)
+}
+
+/// Parse a duration in seconds, optionally with an `s`, `m`, `h` or `d` suffix (`90m`, `7d`)
+pub fn parse_duration_secs(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, unit) = match value.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&value[..i], c),
+        _ => (value, 's'),
+    };
+    let multiplier = match unit.to_ascii_lowercase() {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => return None,
+    };
+    number.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Parse the comma-separated `headwind.sh/ignore-pattern` annotation
+pub fn parse_ignore_patterns(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// The update group (`headwind.sh/update-group`) a container belongs to, in annotation order.
+/// Groups are comma-separated container names, separated by `;`
+/// (`app,sidecar;worker,worker-init`). Containers outside any group yield an empty list.
+pub fn parse_update_group(annotations: &BTreeMap<String, String>, container: &str) -> Vec<String> {
+    annotations
+        .get(annotations::UPDATE_GROUP)
+        .into_iter()
+        .flat_map(|value| value.split(';'))
+        .map(parse_ignore_patterns)
+        .find(|group| group.len() > 1 && group.iter().any(|name| name == container))
+        .unwrap_or_default()
+}
+
+/// Annotations a single container can override with `headwind.sh/container.<name>.<key>`,
+/// e.g. `headwind.sh/container.sidecar.policy: none`
+pub const CONTAINER_POLICY_KEYS: &[&str] = &[
+    annotations::POLICY,
+    annotations::PATTERN,
+    annotations::IGNORE_PATTERN,
+    annotations::REQUIRE_APPROVAL,
+    annotations::ALLOW_PRERELEASE,
+    annotations::PRERELEASE_CHANNEL,
+    annotations::PIN_DIGEST,
+    annotations::CHANNEL_TAG,
+    annotations::MAX_VERSION,
+];
+
+/// Split a per-container annotation (`headwind.sh/container.<name>.<key>`) into the container
+/// and the annotation it overrides (`headwind.sh/<key>`). The key need not be overridable.
+pub fn parse_container_key(key: &str) -> Option<(&str, String)> {
+    let (container, setting) = key
+        .strip_prefix(annotations::CONTAINER_PREFIX)?
+        .rsplit_once('.')?;
+    if container.is_empty() || setting.is_empty() {
+        return None;
+    }
+    Some((container, format!("headwind.sh/{}", setting)))
+}
+
+/// Per-container overrides by container name, as the annotations they override
+pub fn parse_container_overrides(
+    annotations: &BTreeMap<String, String>,
+) -> BTreeMap<String, BTreeMap<String, String>> {
+    let mut overrides: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for (key, value) in annotations {
+        let Some((container, overridden)) = parse_container_key(key) else {
+            continue;
+        };
+        if CONTAINER_POLICY_KEYS.contains(&overridden.as_str()) {
+            overrides
+                .entry(container.to_string())
+                .or_default()
+                .insert(overridden, value.clone());
         }
     }
+    overrides
+}
+
+/// A workload's annotations as one of its containers sees them: its per-container overrides
+/// replace the workload-wide values
+pub fn container_annotations<'a>(
+    annotations: &'a BTreeMap<String, String>,
+    container: &str,
+) -> Cow<'a, BTreeMap<String, String>> {
+    match parse_container_overrides(annotations).remove(container) {
+        Some(overrides) => {
+            let mut merged = annotations.clone();
+            merged.extend(overrides);
+            Cow::Owned(merged)
+        },
+        None => Cow::Borrowed(annotations),
+    }
+}
+
+/// Whether a workload is tracked: it has a `headwind.sh/policy`, for the whole workload or
+/// for one of its containers
+pub fn has_policy(annotations: &BTreeMap<String, String>) -> bool {
+    annotations.contains_key(annotations::POLICY)
+        || parse_container_overrides(annotations)
+            .values()
+            .any(|overrides| overrides.contains_key(annotations::POLICY))
 }
 
 /// Annotation keys used on Kubernetes resources
@@ -119,6 +465,22 @@ pub mod annotations {
     pub const REQUIRE_APPROVAL: &str = "headwind.sh/require-approval";
     pub const MIN_UPDATE_INTERVAL: &str = "headwind.sh/min-update-interval";
     pub const IMAGES: &str = "headwind.sh/images";
+    pub const IGNORE_PATTERN: &str = "headwind.sh/ignore-pattern";
+    pub const ALLOW_PRERELEASE: &str = "headwind.sh/allow-prerelease";
+    pub const PRERELEASE_CHANNEL: &str = "headwind.sh/prerelease-channel";
+    pub const PIN_DIGEST: &str = "headwind.sh/pin-digest";
+    pub const CHANNEL_TAG: &str = "headwind.sh/channel-tag";
+    pub const UPDATE_MODE: &str = "headwind.sh/update-mode";
+    pub const SCHEDULE: &str = "headwind.sh/schedule";
+    pub const APPROVAL_TTL: &str = "headwind.sh/approval-ttl";
+    pub const REQUIRED_APPROVALS: &str = "headwind.sh/required-approvals";
+    pub const DRY_RUN: &str = "headwind.sh/dry-run";
+    pub const UPDATE_GROUP: &str = "headwind.sh/update-group";
+    pub const ROLLOUT_AFTER: &str = "headwind.sh/rollout-after";
+    pub const MIN_TAG_AGE: &str = "headwind.sh/min-tag-age";
+    pub const MAX_VERSION: &str = "headwind.sh/max-version";
+    /// Prefix of per-container overrides (`headwind.sh/container.<name>.<key>`)
+    pub const CONTAINER_PREFIX: &str = "headwind.sh/container.";
     #[allow(dead_code)]
     pub const LAST_UPDATE: &str = "headwind.sh/last-update";
 
@@ -130,4 +492,54 @@ pub mod annotations {
     pub const AUTO_ROLLBACK: &str = "headwind.sh/auto-rollback";
     pub const ROLLBACK_TIMEOUT: &str = "headwind.sh/rollback-timeout";
     pub const HEALTH_CHECK_RETRIES: &str = "headwind.sh/health-check-retries";
+    pub const ERROR_RATE_QUERY: &str = "headwind.sh/error-rate-query";
+    pub const MAX_ERROR_RATE: &str = "headwind.sh/max-error-rate";
+    pub const LATENCY_QUERY: &str = "headwind.sh/latency-query";
+    pub const MAX_LATENCY: &str = "headwind.sh/max-latency";
+
+    // Canary rollout annotations (Deployments only)
+    pub const CANARY: &str = "headwind.sh/canary";
+    pub const CANARY_PERCENT: &str = "headwind.sh/canary-percent";
+    pub const CANARY_TIMEOUT: &str = "headwind.sh/canary-timeout";
+
+    // ArgoCD integration annotations (set on workloads managed by an Application)
+    pub const ARGOCD_APPLICATION: &str = "headwind.sh/argocd-application";
+    pub const ARGOCD_IMAGE_PARAMETER: &str = "headwind.sh/argocd-image-parameter";
+
+    // Flux Kustomization integration (set on workloads applied by a Kustomization)
+    pub const FLUX_KUSTOMIZATION: &str = "headwind.sh/flux-kustomization";
+
+    // Git write-back annotations
+    pub const GITOPS_REPOSITORY: &str = "headwind.sh/gitops-repository";
+    pub const GITOPS_PATH: &str = "headwind.sh/gitops-path";
+    pub const GITOPS_FORMAT: &str = "headwind.sh/gitops-format";
+    pub const GITOPS_BRANCH: &str = "headwind.sh/gitops-branch";
+    pub const GITOPS_VALUE_PATH: &str = "headwind.sh/gitops-value-path";
+    pub const GITOPS_PULL_REQUEST: &str = "headwind.sh/gitops-pull-request";
+    pub const GITOPS_PROVIDER: &str = "headwind.sh/gitops-provider";
+
+    // Update hooks (URLs called before and after an UpdateRequest is applied)
+    pub const PRE_UPDATE_HOOK: &str = "headwind.sh/pre-update-hook";
+    pub const POST_UPDATE_HOOK: &str = "headwind.sh/post-update-hook";
+
+    // Vulnerability scan gate (see scanning)
+    pub const MAX_CRITICAL_CVES: &str = "headwind.sh/max-critical-cves";
+
+    // Platform compatibility check (see platforms)
+    pub const PLATFORM_CHECK: &str = "headwind.sh/platform-check";
+
+    // Notification routing (see config::notificationroutes)
+    pub const NOTIFY_ROUTE: &str = "headwind.sh/notify-route";
+    pub const NOTIFY_CHANNEL: &str = "headwind.sh/notify-channel";
+    pub const NOTIFY_DETECTED: &str = "headwind.sh/notify-detected";
+
+    // HelmRelease values paths holding container images
+    pub const VALUES_IMAGE_PATHS: &str = "headwind.sh/values-image-paths";
+
+    // OCI image labels copied to the workload when its image is updated
+    pub const BUILD_METADATA: &str = "headwind.sh/build-metadata";
+
+    // Trace context recorded on UpdateRequests (see telemetry)
+    pub const CORRELATION_ID: &str = "headwind.sh/correlation-id";
+    pub const TRACEPARENT: &str = "headwind.sh/traceparent";
 }
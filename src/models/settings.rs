@@ -0,0 +1,377 @@
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Name of the HeadwindSettings resource Headwind reads. Other instances are ignored.
+pub const SETTINGS_NAME: &str = "headwind";
+
+/// HeadwindSettings declares Headwind configuration as a cluster-scoped resource, mirroring the
+/// `headwind-config` ConfigMap. Values set here take precedence over the ConfigMap.
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[kube(
+    group = "headwind.sh",
+    version = "v1alpha1",
+    kind = "HeadwindSettings",
+    plural = "headwindsettings",
+    shortname = "hws",
+    status = "HeadwindSettingsStatus",
+    printcolumn = r#"{"name":"Valid", "type":"boolean", "jsonPath":".status.valid"}"#,
+    printcolumn = r#"{"name":"Errors", "type":"integer", "jsonPath":".status.errors"}"#,
+    printcolumn = r#"{"name":"Warnings", "type":"integer", "jsonPath":".status.warnings"}"#,
+    printcolumn = r#"{"name":"Age", "type":"date", "jsonPath":".metadata.creationTimestamp"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct HeadwindSettingsSpec {
+    #[serde(default)]
+    pub polling: PollingSettings,
+
+    #[serde(default)]
+    pub pubsub: PubSubSettings,
+
+    #[serde(default)]
+    pub helm: HelmSettings,
+
+    #[serde(default)]
+    pub controllers: ControllersSettings,
+
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+
+    #[serde(default)]
+    pub observability: ObservabilitySettings,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PollingSettings {
+    /// Enable registry polling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// Polling interval in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+
+    /// Images and charts polled at the same time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<u64>,
+
+    /// Concurrent requests to a single registry host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_concurrency: Option<u64>,
+}
+
+/// Google Cloud Pub/Sub subscription for Artifact Registry / GCR push notifications
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PubSubSettings {
+    /// Pull image push notifications from Pub/Sub
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// Project of the subscription
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+
+    /// Subscription name, or projects/<project>/subscriptions/<name>
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscription: Option<String>,
+
+    /// Messages pulled per request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_messages: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HelmSettings {
+    /// Automatically discover HelmReleases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_discovery: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ControllersSettings {
+    /// Enable the workload controllers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+/// Notifier settings. Webhook URLs stay in the `headwind-secrets` Secret.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    #[serde(default)]
+    pub slack: SlackSettings,
+
+    #[serde(default)]
+    pub teams: NotifierToggle,
+
+    #[serde(default)]
+    pub webhook: NotifierToggle,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SlackSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_emoji: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotifierToggle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+/// Metrics backend settings. The InfluxDB token stays in the ConfigMap or environment.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ObservabilitySettings {
+    /// Metrics backend (auto, prometheus, victoriametrics, influxdb, live)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_backend: Option<String>,
+
+    #[serde(default)]
+    pub prometheus: MetricsBackendSettings,
+
+    #[serde(default)]
+    pub victoriametrics: MetricsBackendSettings,
+
+    #[serde(default)]
+    pub influxdb: InfluxDBSettings,
+
+    #[serde(default)]
+    pub push: MetricsPushSettings,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsBackendSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InfluxDBSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+}
+
+/// Metrics pushed to the enabled VictoriaMetrics and InfluxDB backends
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsPushSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// Seconds between pushes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+/// Validation result for the merged configuration, written back by Headwind
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HeadwindSettingsStatus {
+    /// True when the merged configuration has no errors
+    pub valid: bool,
+
+    pub errors: u32,
+
+    pub warnings: u32,
+
+    /// Problems found in the merged configuration (ConfigMap, Secret, environment and this resource)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<SettingsIssue>,
+
+    /// Generation of the spec that was validated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_generation: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsIssue {
+    /// error or warning
+    pub severity: String,
+
+    /// Where the value came from (settings, configMap, secret, env)
+    pub source: String,
+
+    /// unknownKey, invalidValue or conflict
+    pub kind: String,
+
+    /// Field path for settings issues, otherwise the ConfigMap/Secret key or variable name
+    pub key: String,
+
+    pub message: String,
+}
+
+impl HeadwindSettingsSpec {
+    /// Flatten the set fields into the equivalent `headwind-config` ConfigMap keys
+    pub fn to_config_data(&self) -> BTreeMap<String, String> {
+        let slack = &self.notifications.slack;
+        let observability = &self.observability;
+        let pubsub = &self.pubsub;
+        let entries: [(&str, Option<String>); 27] = [
+            ("polling.enabled", to_value(self.polling.enabled)),
+            ("polling.interval", to_value(self.polling.interval)),
+            ("polling.concurrency", to_value(self.polling.concurrency)),
+            (
+                "polling.registryConcurrency",
+                to_value(self.polling.registry_concurrency),
+            ),
+            ("pubsub.enabled", to_value(pubsub.enabled)),
+            ("pubsub.project", pubsub.project.clone()),
+            ("pubsub.subscription", pubsub.subscription.clone()),
+            ("pubsub.maxMessages", to_value(pubsub.max_messages)),
+            ("helm.autoDiscovery", to_value(self.helm.auto_discovery)),
+            ("controllers.enabled", to_value(self.controllers.enabled)),
+            ("slack.enabled", to_value(slack.enabled)),
+            ("slack.channel", slack.channel.clone()),
+            ("slack.username", slack.username.clone()),
+            ("slack.iconEmoji", slack.icon_emoji.clone()),
+            ("teams.enabled", to_value(self.notifications.teams.enabled)),
+            (
+                "webhook.enabled",
+                to_value(self.notifications.webhook.enabled),
+            ),
+            (
+                "observability.metricsBackend",
+                observability.metrics_backend.clone(),
+            ),
+            (
+                "observability.prometheus.enabled",
+                to_value(observability.prometheus.enabled),
+            ),
+            (
+                "observability.prometheus.url",
+                observability.prometheus.url.clone(),
+            ),
+            (
+                "observability.victoriametrics.enabled",
+                to_value(observability.victoriametrics.enabled),
+            ),
+            (
+                "observability.victoriametrics.url",
+                observability.victoriametrics.url.clone(),
+            ),
+            (
+                "observability.influxdb.enabled",
+                to_value(observability.influxdb.enabled),
+            ),
+            (
+                "observability.influxdb.url",
+                observability.influxdb.url.clone(),
+            ),
+            (
+                "observability.influxdb.org",
+                observability.influxdb.org.clone(),
+            ),
+            (
+                "observability.influxdb.bucket",
+                observability.influxdb.bucket.clone(),
+            ),
+            (
+                "observability.push.enabled",
+                to_value(observability.push.enabled),
+            ),
+            (
+                "observability.push.interval",
+                to_value(observability.push.interval),
+            ),
+        ];
+
+        entries
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|v| (key.to_string(), v)))
+            .collect()
+    }
+}
+
+fn to_value<T: ToString>(value: Option<T>) -> Option<String> {
+    value.map(|v| v.to_string())
+}
+
+/// Map a ConfigMap key to its field path in a HeadwindSettings resource
+/// Example: "slack.iconEmoji" -> "spec.notifications.slack.iconEmoji"
+pub fn spec_path(config_key: &str) -> String {
+    if ["slack.", "teams.", "webhook."]
+        .iter()
+        .any(|prefix| config_key.starts_with(prefix))
+    {
+        format!("spec.notifications.{}", config_key)
+    } else {
+        format!("spec.{}", config_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_spec_sets_nothing() {
+        assert!(HeadwindSettingsSpec::default().to_config_data().is_empty());
+    }
+
+    #[test]
+    fn test_to_config_data() {
+        let spec: HeadwindSettingsSpec = serde_json::from_value(serde_json::json!({
+            "polling": { "enabled": true, "interval": 600 },
+            "notifications": { "slack": { "enabled": true, "iconEmoji": ":ship:" } },
+            "observability": { "metricsBackend": "prometheus" }
+        }))
+        .unwrap();
+
+        let data = spec.to_config_data();
+        assert_eq!(data.len(), 5);
+        assert_eq!(data.get("polling.enabled").unwrap(), "true");
+        assert_eq!(data.get("polling.interval").unwrap(), "600");
+        assert_eq!(data.get("slack.enabled").unwrap(), "true");
+        assert_eq!(data.get("slack.iconEmoji").unwrap(), ":ship:");
+        assert_eq!(
+            data.get("observability.metricsBackend").unwrap(),
+            "prometheus"
+        );
+    }
+
+    #[test]
+    fn test_spec_path() {
+        assert_eq!(spec_path("polling.interval"), "spec.polling.interval");
+        assert_eq!(
+            spec_path("slack.iconEmoji"),
+            "spec.notifications.slack.iconEmoji"
+        );
+        assert_eq!(
+            spec_path("observability.influxdb.url"),
+            "spec.observability.influxdb.url"
+        );
+    }
+}
@@ -21,6 +21,7 @@ pub enum ResourceKind {
     Deployment,
     StatefulSet,
     DaemonSet,
+    CronJob,
     HelmRelease,
 }
 
@@ -0,0 +1,337 @@
+use super::policy::annotations;
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// UpdateTarget applies a Headwind policy to every workload matching a label selector,
+/// so workloads don't need to be annotated individually
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[kube(
+    group = "headwind.sh",
+    version = "v1alpha1",
+    kind = "UpdateTarget",
+    plural = "updatetargets",
+    shortname = "ut",
+    printcolumn = r#"{"name":"Policy", "type":"string", "jsonPath":".spec.policy"}"#,
+    printcolumn = r#"{"name":"Event Source", "type":"string", "jsonPath":".spec.eventSource"}"#,
+    printcolumn = r#"{"name":"Age", "type":"date", "jsonPath":".metadata.creationTimestamp"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTargetSpec {
+    /// Label selector for the workloads this target applies to
+    pub selector: LabelSelector,
+
+    /// Workload kinds to select (Deployment, StatefulSet, DaemonSet, CronJob). Empty selects all kinds
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<String>,
+
+    /// Namespaces to select workloads from. Empty selects all namespaces
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub namespaces: Vec<String>,
+
+    /// Update policy (patch, minor, major, all, glob, regex, range, force, digest, none)
+    pub policy: String,
+
+    /// Pattern for the glob or regex policy, or the range expression of the range policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// Glob patterns for tags that are never updated to, e.g. `*-rc*`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_patterns: Vec<String>,
+
+    /// Allow semver policies to update to prereleases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_prerelease: Option<bool>,
+
+    /// Only allow prereleases of this channel, e.g. `rc`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prerelease_channel: Option<String>,
+
+    /// Pin images to the digest of their tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin_digest: Option<bool>,
+
+    /// Whether updates require approval (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_approval: Option<bool>,
+
+    /// How long UpdateRequests wait for approval, e.g. `48h` or `7d`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_ttl: Option<String>,
+
+    /// Number of distinct approvers an UpdateRequest needs (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_approvals: Option<u32>,
+
+    /// Maintenance window for automatic updates, e.g. `Mon-Fri 02:00-05:00 Europe/Berlin`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+
+    /// Minimum time between updates in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_update_interval: Option<u64>,
+
+    /// Container names to track. Empty tracks all containers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+
+    /// Event source (webhook, polling, both, none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_source: Option<String>,
+
+    /// Per-resource polling interval in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polling_interval: Option<u64>,
+}
+
+/// Kubernetes-style label selector
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelSelector {
+    /// Labels that must all be present with the given values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub match_labels: BTreeMap<String, String>,
+
+    /// Set-based requirements that must all be satisfied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_expressions: Vec<LabelSelectorRequirement>,
+}
+
+/// A single set-based label requirement
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelSelectorRequirement {
+    pub key: String,
+
+    /// One of In, NotIn, Exists, DoesNotExist
+    pub operator: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+impl LabelSelector {
+    /// Check whether a set of labels satisfies this selector.
+    /// An empty selector matches nothing, so a target never selects the whole cluster by accident.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        if self.match_labels.is_empty() && self.match_expressions.is_empty() {
+            return false;
+        }
+
+        self.match_labels
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+            && self
+                .match_expressions
+                .iter()
+                .all(|requirement| requirement.matches(labels))
+    }
+}
+
+impl LabelSelectorRequirement {
+    fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        let value = labels.get(&self.key);
+        match self.operator.as_str() {
+            "In" => value.is_some_and(|v| self.values.contains(v)),
+            "NotIn" => value.is_none_or(|v| !self.values.contains(v)),
+            "Exists" => value.is_some(),
+            "DoesNotExist" => value.is_none(),
+            _ => false,
+        }
+    }
+}
+
+impl UpdateTargetSpec {
+    /// Check whether this target selects a workload
+    pub fn selects(&self, kind: &str, namespace: &str, labels: &BTreeMap<String, String>) -> bool {
+        let kind_matches =
+            self.kinds.is_empty() || self.kinds.iter().any(|k| k.eq_ignore_ascii_case(kind));
+        let namespace_matches =
+            self.namespaces.is_empty() || self.namespaces.iter().any(|ns| ns == namespace);
+
+        kind_matches && namespace_matches && self.selector.matches(labels)
+    }
+
+    /// Render this target's policy as the equivalent `headwind.sh/*` annotations
+    pub fn to_annotations(&self) -> BTreeMap<String, String> {
+        let mut result = BTreeMap::new();
+        result.insert(annotations::POLICY.to_string(), self.policy.clone());
+
+        if let Some(pattern) = &self.pattern {
+            result.insert(annotations::PATTERN.to_string(), pattern.clone());
+        }
+        if !self.ignore_patterns.is_empty() {
+            result.insert(
+                annotations::IGNORE_PATTERN.to_string(),
+                self.ignore_patterns.join(","),
+            );
+        }
+        if let Some(allow_prerelease) = self.allow_prerelease {
+            result.insert(
+                annotations::ALLOW_PRERELEASE.to_string(),
+                allow_prerelease.to_string(),
+            );
+        }
+        if let Some(channel) = &self.prerelease_channel {
+            result.insert(annotations::PRERELEASE_CHANNEL.to_string(), channel.clone());
+        }
+        if let Some(pin_digest) = self.pin_digest {
+            result.insert(annotations::PIN_DIGEST.to_string(), pin_digest.to_string());
+        }
+        if let Some(require_approval) = self.require_approval {
+            result.insert(
+                annotations::REQUIRE_APPROVAL.to_string(),
+                require_approval.to_string(),
+            );
+        }
+        if let Some(ttl) = &self.approval_ttl {
+            result.insert(annotations::APPROVAL_TTL.to_string(), ttl.clone());
+        }
+        if let Some(required) = self.required_approvals {
+            result.insert(
+                annotations::REQUIRED_APPROVALS.to_string(),
+                required.to_string(),
+            );
+        }
+        if let Some(schedule) = &self.schedule {
+            result.insert(annotations::SCHEDULE.to_string(), schedule.clone());
+        }
+        if let Some(interval) = self.min_update_interval {
+            result.insert(
+                annotations::MIN_UPDATE_INTERVAL.to_string(),
+                interval.to_string(),
+            );
+        }
+        if !self.images.is_empty() {
+            result.insert(annotations::IMAGES.to_string(), self.images.join(","));
+        }
+        if let Some(event_source) = &self.event_source {
+            result.insert(annotations::EVENT_SOURCE.to_string(), event_source.clone());
+        }
+        if let Some(interval) = self.polling_interval {
+            result.insert(
+                annotations::POLLING_INTERVAL.to_string(),
+                interval.to_string(),
+            );
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn spec(selector: LabelSelector) -> UpdateTargetSpec {
+        UpdateTargetSpec {
+            selector,
+            kinds: Vec::new(),
+            namespaces: Vec::new(),
+            policy: "minor".to_string(),
+            pattern: None,
+            ignore_patterns: Vec::new(),
+            allow_prerelease: None,
+            prerelease_channel: None,
+            pin_digest: None,
+            require_approval: None,
+            approval_ttl: None,
+            required_approvals: None,
+            schedule: None,
+            min_update_interval: None,
+            images: Vec::new(),
+            event_source: None,
+            polling_interval: None,
+        }
+    }
+
+    #[test]
+    fn test_match_labels() {
+        let selector = LabelSelector {
+            match_labels: labels(&[("team", "payments")]),
+            match_expressions: Vec::new(),
+        };
+
+        assert!(selector.matches(&labels(&[("team", "payments"), ("app", "api")])));
+        assert!(!selector.matches(&labels(&[("team", "search")])));
+        assert!(!selector.matches(&labels(&[])));
+    }
+
+    #[test]
+    fn test_match_expressions() {
+        let selector = LabelSelector {
+            match_labels: BTreeMap::new(),
+            match_expressions: vec![
+                LabelSelectorRequirement {
+                    key: "tier".to_string(),
+                    operator: "In".to_string(),
+                    values: vec!["backend".to_string(), "worker".to_string()],
+                },
+                LabelSelectorRequirement {
+                    key: "headwind-opt-out".to_string(),
+                    operator: "DoesNotExist".to_string(),
+                    values: Vec::new(),
+                },
+            ],
+        };
+
+        assert!(selector.matches(&labels(&[("tier", "worker")])));
+        assert!(!selector.matches(&labels(&[("tier", "frontend")])));
+        assert!(!selector.matches(&labels(&[
+            ("tier", "backend"),
+            ("headwind-opt-out", "true")
+        ])));
+    }
+
+    #[test]
+    fn test_empty_selector_matches_nothing() {
+        assert!(!LabelSelector::default().matches(&labels(&[("app", "nginx")])));
+    }
+
+    #[test]
+    fn test_selects_kind_and_namespace() {
+        let mut target = spec(LabelSelector {
+            match_labels: labels(&[("app", "nginx")]),
+            match_expressions: Vec::new(),
+        });
+        target.kinds = vec!["Deployment".to_string()];
+        target.namespaces = vec!["production".to_string()];
+
+        let app = labels(&[("app", "nginx")]);
+        assert!(target.selects("Deployment", "production", &app));
+        assert!(!target.selects("StatefulSet", "production", &app));
+        assert!(!target.selects("Deployment", "staging", &app));
+    }
+
+    #[test]
+    fn test_to_annotations() {
+        let mut target = spec(LabelSelector::default());
+        target.require_approval = Some(false);
+        target.images = vec!["app".to_string(), "sidecar".to_string()];
+        target.event_source = Some("both".to_string());
+        target.ignore_patterns = vec!["*-rc*".to_string(), "nightly".to_string()];
+
+        let rendered = target.to_annotations();
+        assert_eq!(rendered.get(annotations::POLICY).unwrap(), "minor");
+        assert_eq!(
+            rendered.get(annotations::REQUIRE_APPROVAL).unwrap(),
+            "false"
+        );
+        assert_eq!(rendered.get(annotations::IMAGES).unwrap(), "app,sidecar");
+        assert_eq!(rendered.get(annotations::EVENT_SOURCE).unwrap(), "both");
+        assert_eq!(
+            rendered.get(annotations::IGNORE_PATTERN).unwrap(),
+            "*-rc*,nightly"
+        );
+        assert!(!rendered.contains_key(annotations::PATTERN));
+    }
+}
@@ -0,0 +1,468 @@
+//! `headwind.sh/v1beta1` UpdateRequest.
+//!
+//! v1alpha1 stays the storage version; the API server converts between the two through the
+//! conversion webhook (see [`crate::admission::conversion`]). Compared to v1alpha1:
+//! - `currentImage`/`newImage` become `current`/`target` with the repository (or chart) and
+//!   version split
+//! - `status.approvals` holds every approval, replacing `approvedBy`/`approvedAt`
+
+use super::crd::{
+    self, Approval, GitWriteBack, SourceEvent, TargetRef, UpdatePolicyType, UpdateType,
+};
+use crate::controller::workload::split_image;
+use chrono::{DateTime, Utc};
+use kube::CustomResource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// UpdateRequest is a CRD that represents a pending update to a Kubernetes resource
+#[derive(CustomResource, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[kube(
+    group = "headwind.sh",
+    version = "v1beta1",
+    kind = "UpdateRequest",
+    plural = "updaterequests",
+    shortname = "ur",
+    shortname = "upd",
+    namespaced,
+    status = "UpdateRequestStatus",
+    printcolumn = r#"{"name":"Target", "type":"string", "jsonPath":".spec.targetRef.name"}"#,
+    printcolumn = r#"{"name":"Container", "type":"string", "jsonPath":".spec.containerName"}"#,
+    printcolumn = r#"{"name":"Current", "type":"string", "jsonPath":".spec.current.version"}"#,
+    printcolumn = r#"{"name":"New", "type":"string", "jsonPath":".spec.target.version"}"#,
+    printcolumn = r#"{"name":"Policy", "type":"string", "jsonPath":".spec.policy"}"#,
+    printcolumn = r#"{"name":"Phase", "type":"string", "jsonPath":".status.phase"}"#,
+    printcolumn = r#"{"name":"Age", "type":"date", "jsonPath":".metadata.creationTimestamp"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRequestSpec {
+    /// Reference to the target resource to update
+    pub target_ref: TargetRef,
+
+    /// Type of update (image or helmChart)
+    pub update_type: UpdateType,
+
+    /// Name of the container to update (for image updates)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+
+    /// Helm parameter holding the image tag, when the target is an ArgoCD Application, or
+    /// the values path holding the image, when the target is a HelmRelease
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_parameter: Option<String>,
+
+    /// Image or chart running now
+    pub current: ArtifactVersion,
+
+    /// Image or chart the update moves to
+    pub target: ArtifactVersion,
+
+    /// Policy that triggered this update
+    pub policy: UpdatePolicyType,
+
+    /// Human-readable reason for the update
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// Whether this update requires manual approval
+    #[serde(default = "default_require_approval")]
+    pub require_approval: bool,
+
+    /// Optional expiration time for this update request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Number of distinct approvers needed before the update is applied (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_approvals: Option<u32>,
+
+    /// Git location the update is committed to instead of patching the target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_write_back: Option<GitWriteBack>,
+
+    /// Other containers of the update group (`headwind.sh/update-group`) updated together
+    /// with `containerName`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grouped_containers: Vec<ContainerUpdate>,
+
+    /// Event that detected the update
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceEvent>,
+
+    /// Version a channel tag of the target stands for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_version: Option<String>,
+}
+
+fn default_require_approval() -> bool {
+    true
+}
+
+/// An image (`repository` and tag, optionally followed by `@digest`) or a chart (`chart`
+/// name and version)
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactVersion {
+    /// Image repository or chart name
+    pub name: String,
+
+    /// Tag or chart version; unset for references without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl ArtifactVersion {
+    /// Split a v1alpha1 `image:tag` or `chart:version` reference
+    pub fn parse(reference: &str, update_type: &UpdateType) -> Self {
+        let split = match update_type {
+            UpdateType::Image => split_image(reference),
+            UpdateType::HelmChart => reference.rsplit_once(':'),
+        };
+        match split {
+            Some((name, version)) => Self {
+                name: name.to_string(),
+                version: Some(version.to_string()),
+            },
+            None => Self {
+                name: reference.to_string(),
+                version: None,
+            },
+        }
+    }
+
+    /// The v1alpha1 reference: `name:version`, or `name` without a version
+    pub fn reference(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}:{}", self.name, version),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Image change of one container of a grouped update
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerUpdate {
+    pub container_name: String,
+    pub current: ArtifactVersion,
+    pub target: ArtifactVersion,
+}
+
+/// Status of the UpdateRequest. Same as v1alpha1, except that `approvals` holds every
+/// approval, also when a single one is required.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRequestStatus {
+    /// Current phase of the update request
+    #[serde(default)]
+    pub phase: crd::UpdatePhase,
+
+    /// Approvals given so far, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approvals: Vec<Approval>,
+
+    /// User or system that rejected the update
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejected_by: Option<String>,
+
+    /// When the update was rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejected_at: Option<DateTime<Utc>>,
+
+    /// Status message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Last time this status was updated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<DateTime<Utc>>,
+
+    /// How often the update was detected while this request was open (1 when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_count: Option<u32>,
+
+    /// Last time the update was detected again while this request was open
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_observed_at: Option<DateTime<Utc>>,
+
+    /// Change ticket opened for the update (HEADWIND_CHANGE_TICKETS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_ticket: Option<crd::ChangeTicket>,
+
+    /// Release notes of the new version (HEADWIND_RELEASE_NOTES)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_notes: Option<crd::ReleaseNotes>,
+
+    /// Creation time, size, labels and exposed ports of the new image (HEADWIND_IMAGE_METADATA)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_metadata: Option<crd::ImageMetadata>,
+
+    /// Vulnerability scan of the new image (HEADWIND_VULNERABILITY_SCAN)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vulnerability_scan: Option<crd::VulnerabilityScan>,
+
+    /// Why an approved update is held back: the workloads it is ordered after
+    /// (`headwind.sh/rollout-after`) haven't finished their updates, or the update gate reports
+    /// a freeze
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiting_for: Option<String>,
+
+    /// When a Deferred update waiting for the target's minimum update interval
+    /// (`headwind.sh/min-update-interval`) is released
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deferred_until: Option<DateTime<Utc>>,
+
+    /// Progress of the Deployment rollout the applied update started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<crd::RolloutProgress>,
+
+    /// Standard conditions (Approved, Applied, HealthChecked, RolledBack)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<crd::Condition>,
+}
+
+impl From<crd::UpdateRequest> for UpdateRequest {
+    fn from(old: crd::UpdateRequest) -> Self {
+        let metadata = old.metadata;
+        let spec = old.spec;
+        let update_type = spec.update_type;
+        let spec = UpdateRequestSpec {
+            current: ArtifactVersion::parse(&spec.current_image, &update_type),
+            target: ArtifactVersion::parse(&spec.new_image, &update_type),
+            grouped_containers: spec
+                .grouped_containers
+                .into_iter()
+                .map(|c| ContainerUpdate {
+                    current: ArtifactVersion::parse(&c.current_image, &UpdateType::Image),
+                    target: ArtifactVersion::parse(&c.new_image, &UpdateType::Image),
+                    container_name: c.container_name,
+                })
+                .collect(),
+            target_ref: spec.target_ref,
+            update_type,
+            container_name: spec.container_name,
+            image_parameter: spec.image_parameter,
+            policy: spec.policy,
+            reason: spec.reason,
+            require_approval: spec.require_approval,
+            expires_at: spec.expires_at,
+            required_approvals: spec.required_approvals,
+            git_write_back: spec.git_write_back,
+            source: spec.source,
+            resolved_version: spec.resolved_version,
+        };
+
+        let status = old.status.map(|status| {
+            let approvals = if status.approvals.is_empty() {
+                status
+                    .approved_by
+                    .zip(status.approved_at.or(status.last_updated))
+                    .map(|(approver, approved_at)| Approval {
+                        approver,
+                        approved_at,
+                    })
+                    .into_iter()
+                    .collect()
+            } else {
+                status.approvals
+            };
+            UpdateRequestStatus {
+                phase: status.phase,
+                approvals,
+                rejected_by: status.rejected_by,
+                rejected_at: status.rejected_at,
+                message: status.message,
+                last_updated: status.last_updated,
+                observed_count: status.observed_count,
+                last_observed_at: status.last_observed_at,
+                change_ticket: status.change_ticket,
+                release_notes: status.release_notes,
+                image_metadata: status.image_metadata,
+                vulnerability_scan: status.vulnerability_scan,
+                waiting_for: status.waiting_for,
+                deferred_until: status.deferred_until,
+                rollout: status.rollout,
+                conditions: status.conditions,
+            }
+        });
+
+        UpdateRequest {
+            metadata,
+            spec,
+            status,
+        }
+    }
+}
+
+impl From<UpdateRequest> for crd::UpdateRequest {
+    fn from(new: UpdateRequest) -> Self {
+        let metadata = new.metadata;
+        let spec = new.spec;
+
+        let spec = crd::UpdateRequestSpec {
+            target_ref: spec.target_ref,
+            update_type: spec.update_type,
+            container_name: spec.container_name,
+            image_parameter: spec.image_parameter,
+            current_image: spec.current.reference(),
+            new_image: spec.target.reference(),
+            policy: spec.policy,
+            reason: spec.reason,
+            require_approval: spec.require_approval,
+            expires_at: spec.expires_at,
+            required_approvals: spec.required_approvals,
+            git_write_back: spec.git_write_back,
+            grouped_containers: spec
+                .grouped_containers
+                .into_iter()
+                .map(|c| crd::ContainerUpdate {
+                    container_name: c.container_name,
+                    current_image: c.current.reference(),
+                    new_image: c.target.reference(),
+                })
+                .collect(),
+            source: spec.source,
+            resolved_version: spec.resolved_version,
+        };
+
+        // v1alpha1 lists approvals only when more than one was given
+        let status = new.status.map(|status| {
+            let last = status.approvals.last().cloned();
+            crd::UpdateRequestStatus {
+                phase: status.phase,
+                approved_by: last.as_ref().map(|a| a.approver.clone()),
+                approved_at: last.map(|a| a.approved_at),
+                approvals: if status.approvals.len() > 1 {
+                    status.approvals
+                } else {
+                    Vec::new()
+                },
+                rejected_by: status.rejected_by,
+                rejected_at: status.rejected_at,
+                message: status.message,
+                last_updated: status.last_updated,
+                observed_count: status.observed_count,
+                last_observed_at: status.last_observed_at,
+                change_ticket: status.change_ticket,
+                release_notes: status.release_notes,
+                image_metadata: status.image_metadata,
+                vulnerability_scan: status.vulnerability_scan,
+                waiting_for: status.waiting_for,
+                deferred_until: status.deferred_until,
+                rollout: status.rollout,
+                conditions: status.conditions,
+            }
+        });
+
+        crd::UpdateRequest {
+            metadata,
+            spec,
+            status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::api::ObjectMeta;
+
+    fn v1alpha1(current: &str, new: &str, update_type: UpdateType) -> crd::UpdateRequest {
+        crd::UpdateRequest {
+            metadata: ObjectMeta {
+                name: Some("web-1-26".to_string()),
+                namespace: Some("default".to_string()),
+                ..Default::default()
+            },
+            spec: crd::UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "web".to_string(),
+                    namespace: "default".to_string(),
+                },
+                update_type,
+                container_name: Some("app".to_string()),
+                image_parameter: None,
+                current_image: current.to_string(),
+                new_image: new.to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at: None,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: vec![crd::ContainerUpdate {
+                    container_name: "sidecar".to_string(),
+                    current_image: "envoy:1.0".to_string(),
+                    new_image: "envoy:1.1".to_string(),
+                }],
+                source: None,
+                resolved_version: None,
+            },
+            status: None,
+        }
+    }
+
+    #[test]
+    fn test_artifact_version_parse() {
+        let image = ArtifactVersion::parse("registry:5000/app:1.2@sha256:abc", &UpdateType::Image);
+        assert_eq!(image.name, "registry:5000/app");
+        assert_eq!(image.version.as_deref(), Some("1.2@sha256:abc"));
+
+        let untagged = ArtifactVersion::parse("registry:5000/app", &UpdateType::Image);
+        assert_eq!(untagged.version, None);
+        assert_eq!(untagged.reference(), "registry:5000/app");
+
+        let chart = ArtifactVersion::parse("podinfo:6.5.4", &UpdateType::HelmChart);
+        assert_eq!(chart.name, "podinfo");
+        assert_eq!(chart.version.as_deref(), Some("6.5.4"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut old = v1alpha1("nginx:1.25", "nginx:1.26", UpdateType::Image);
+        let approved_at = Utc::now();
+        old.status = Some(crd::UpdateRequestStatus {
+            approved_by: Some("alice".to_string()),
+            approved_at: Some(approved_at),
+            ..Default::default()
+        });
+
+        let new = UpdateRequest::from(old.clone());
+        assert_eq!(new.spec.target.version.as_deref(), Some("1.26"));
+        assert_eq!(new.spec.grouped_containers[0].target.name, "envoy");
+        let status = new.status.as_ref().unwrap();
+        assert_eq!(status.approvals.len(), 1);
+        assert_eq!(status.approvals[0].approver, "alice");
+
+        let back = crd::UpdateRequest::from(new);
+        assert_eq!(back.spec.current_image, "nginx:1.25");
+        assert_eq!(back.spec.new_image, "nginx:1.26");
+        assert_eq!(back.spec.grouped_containers, old.spec.grouped_containers);
+        let status = back.status.unwrap();
+        assert_eq!(status.approved_by.as_deref(), Some("alice"));
+        assert_eq!(status.approved_at, Some(approved_at));
+        assert!(status.approvals.is_empty());
+        assert_eq!(back.metadata.annotations, None);
+    }
+
+    #[test]
+    fn test_source_converted() {
+        let mut old = v1alpha1("podinfo:6.5.3", "podinfo:6.5.4", UpdateType::HelmChart);
+        let source = SourceEvent {
+            kind: "webhook".to_string(),
+            origin: Some("registry".to_string()),
+            sender: Some("10.0.0.7:51234".to_string()),
+            ..Default::default()
+        };
+        old.spec.source = Some(source.clone());
+
+        let new = UpdateRequest::from(old);
+        assert_eq!(new.spec.source, Some(source.clone()));
+
+        let back = crd::UpdateRequest::from(new);
+        assert_eq!(back.spec.source, Some(source));
+        assert_eq!(back.metadata.annotations, None);
+    }
+}
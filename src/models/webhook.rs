@@ -1,3 +1,5 @@
+use super::crd::SourceEvent;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Generic webhook payload for container registry notifications
@@ -51,14 +53,68 @@ pub struct Repository {
     pub name: String,
 }
 
+/// GitHub `package` (or legacy `registry_package`) webhook, sent for GHCR pushes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhcrWebhook {
+    pub action: String,
+    #[serde(alias = "registry_package")]
+    pub package: GhcrPackage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhcrPackage {
+    pub name: String,
+    /// "container" or "CONTAINER" for OCI images and charts
+    pub package_type: String,
+    pub owner: GhcrOwner,
+    pub package_version: Option<GhcrPackageVersion>,
+    pub registry: Option<GhcrRegistry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhcrOwner {
+    pub login: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhcrPackageVersion {
+    /// Manifest digest for container packages
+    pub version: Option<String>,
+    /// Pull reference, e.g. "ghcr.io/org/app:1.2.3"
+    pub package_url: Option<String>,
+    pub container_metadata: Option<GhcrContainerMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhcrContainerMetadata {
+    pub tag: Option<GhcrTag>,
+    /// Image manifest, used to tell Helm charts from images
+    pub manifest: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhcrTag {
+    pub name: String,
+    pub digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhcrRegistry {
+    /// e.g. "https://ghcr.io"
+    pub url: Option<String>,
+}
+
 /// Normalized webhook event after parsing (for container images)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ImagePushEvent {
     pub registry: String,
     pub repository: String,
     pub tag: String,
     #[allow(dead_code)]
     pub digest: Option<String>,
+    /// Where the event came from, recorded on the UpdateRequests it leads to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceEvent>,
 }
 
 impl ImagePushEvent {
@@ -72,7 +128,7 @@ impl ImagePushEvent {
 }
 
 /// Normalized webhook event for Helm chart pushes
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ChartPushEvent {
     /// OCI registry (e.g., "ghcr.io", "registry.example.com")
     pub registry: String,
@@ -82,6 +138,9 @@ pub struct ChartPushEvent {
     pub version: String,
     /// SHA256 digest
     pub digest: Option<String>,
+    /// Where the event came from, recorded on the UpdateRequests it leads to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceEvent>,
 }
 
 impl ChartPushEvent {
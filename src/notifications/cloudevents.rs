@@ -0,0 +1,248 @@
+//! CloudEvents sink.
+//!
+//! Publishes every notification as a [CloudEvents 1.0](https://cloudevents.io) event in
+//! structured JSON mode, to an HTTP endpoint (`CLOUDEVENTS_URL`, POSTed as
+//! `application/cloudevents+json`) and/or a NATS server (`CLOUDEVENTS_NATS_URL`, on the subject
+//! `<CLOUDEVENTS_NATS_SUBJECT>.<event>`). The event `data` is the notification payload sent to
+//! generic webhooks, so automation can subscribe to Headwind's activity without parsing chat
+//! messages. Routes don't apply: every event goes to the configured sinks.
+
+use super::{
+    CloudEventsConfig, NotificationEvent, NotificationPayload, NotificationRoute, Notifier,
+};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tracing::{debug, info};
+
+/// Version of the CloudEvents specification the events follow
+pub const SPEC_VERSION: &str = "1.0";
+
+/// Content type of structured-mode events
+pub const CONTENT_TYPE: &str = "application/cloudevents+json";
+
+/// Events published by this process, to make event IDs unique
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// A CloudEvent in structured JSON mode
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub struct CloudEvent<'a> {
+    pub specversion: &'static str,
+    pub id: String,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub subject: String,
+    pub time: DateTime<Utc>,
+    pub datacontenttype: &'static str,
+    /// Trace ID of the update flow (extension attribute)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlationid: Option<&'a str>,
+    pub data: &'a NotificationPayload,
+}
+
+impl<'a> CloudEvent<'a> {
+    pub fn new(source: &str, payload: &'a NotificationPayload) -> Self {
+        let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let deployment = &payload.deployment;
+        Self {
+            specversion: SPEC_VERSION,
+            id: format!(
+                "{}-{}",
+                payload.timestamp.timestamp_nanos_opt().unwrap_or_default(),
+                sequence
+            ),
+            source: source.to_string(),
+            type_: format!("sh.headwind.{}", event_name(payload.event)),
+            subject: format!(
+                "namespaces/{}/{}/{}",
+                deployment.namespace,
+                deployment
+                    .resource_kind
+                    .as_deref()
+                    .unwrap_or("Deployment")
+                    .to_lowercase(),
+                deployment.name
+            ),
+            time: payload.timestamp,
+            datacontenttype: "application/json",
+            correlationid: payload.correlation_id.as_deref(),
+            data: payload,
+        }
+    }
+}
+
+/// Name of a notification event in CloudEvent types and NATS subjects
+pub fn event_name(event: NotificationEvent) -> &'static str {
+    match event {
+        NotificationEvent::UpdateDetected => "update.detected",
+        NotificationEvent::UpdateRequestCreated => "update.requested",
+        NotificationEvent::UpdateApproved => "update.approved",
+        NotificationEvent::UpdateRejected => "update.rejected",
+        NotificationEvent::UpdateExpired => "update.expired",
+        NotificationEvent::UpdateCompleted => "update.applied",
+        NotificationEvent::UpdateFailed => "update.failed",
+        NotificationEvent::RollbackTriggered => "update.rollback.started",
+        NotificationEvent::RollbackCompleted => "update.rolledback",
+        NotificationEvent::RollbackFailed => "update.rollback.failed",
+    }
+}
+
+/// Default `source` of the events: `/headwind`, followed by the cluster name when it is set
+pub fn default_source() -> String {
+    match crate::config::cluster::name() {
+        Some(cluster) => format!("/headwind/{}", cluster),
+        None => "/headwind".to_string(),
+    }
+}
+
+pub struct CloudEventsNotifier {
+    config: CloudEventsConfig,
+    client: Client,
+    nats: OnceCell<async_nats::Client>,
+}
+
+impl CloudEventsNotifier {
+    pub fn new(config: CloudEventsConfig) -> Result<Self> {
+        if !config.enabled {
+            return Err(anyhow!("CloudEvents sink is disabled"));
+        }
+
+        if config.url.is_none() && config.nats_url.is_none() {
+            return Err(anyhow!(
+                "CloudEvents sink needs CLOUDEVENTS_URL or CLOUDEVENTS_NATS_URL"
+            ));
+        }
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            config,
+            client,
+            nats: OnceCell::new(),
+        })
+    }
+
+    async fn send_http(&self, url: &str, body: &str) -> Result<()> {
+        self.client
+            .post(url)
+            .header("Content-Type", CONTENT_TYPE)
+            .body(body.to_string())
+            .send()
+            .await
+            .context("HTTP request failed")?
+            .error_for_status()
+            .context("CloudEvents sink rejected the event")?;
+        debug!("Sent CloudEvent to {}", url);
+        Ok(())
+    }
+
+    async fn send_nats(&self, url: &str, event: NotificationEvent, body: &str) -> Result<()> {
+        let client = self
+            .nats
+            .get_or_try_init(|| async {
+                info!("Connecting to NATS at {} for CloudEvents", url);
+                async_nats::connect(url).await
+            })
+            .await
+            .context("Failed to connect to NATS")?;
+
+        let subject = format!("{}.{}", self.config.nats_subject, event_name(event));
+        client
+            .publish(subject.clone(), body.to_string().into())
+            .await
+            .context("Failed to publish to NATS")?;
+        client.flush().await.context("Failed to flush NATS")?;
+        debug!("Published CloudEvent on NATS subject {}", subject);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for CloudEventsNotifier {
+    async fn send_routed(
+        &self,
+        payload: &NotificationPayload,
+        _route: &NotificationRoute,
+    ) -> Result<()> {
+        let event = CloudEvent::new(&self.config.source, payload);
+        let body = serde_json::to_string(&event).context("Failed to serialize CloudEvent")?;
+
+        if let Some(url) = &self.config.url {
+            self.send_http(url, &body).await?;
+        }
+        if let Some(url) = &self.config.nats_url {
+            self.send_nats(url, payload.event, &body).await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "CloudEvents"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.config.enabled && (self.config.url.is_some() || self.config.nats_url.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::DeploymentInfo;
+
+    #[test]
+    fn test_cloud_event() {
+        let mut payload = NotificationPayload::new(
+            NotificationEvent::UpdateCompleted,
+            DeploymentInfo {
+                name: "redis".to_string(),
+                namespace: "cache".to_string(),
+                current_image: "redis:7.2.4".to_string(),
+                new_image: "redis:7.2.5".to_string(),
+                container: Some("redis".to_string()),
+                resource_kind: Some("StatefulSet".to_string()),
+            },
+        );
+        payload.correlation_id = Some("4bf92f3577b34da6a3ce929d0e0e4736".to_string());
+
+        let first = CloudEvent::new("/headwind/prod-eu", &payload);
+        let second = CloudEvent::new("/headwind/prod-eu", &payload);
+        assert_ne!(first.id, second.id);
+
+        let value = serde_json::to_value(&first).unwrap();
+        assert_eq!(value["specversion"], "1.0");
+        assert_eq!(value["type"], "sh.headwind.update.applied");
+        assert_eq!(value["source"], "/headwind/prod-eu");
+        assert_eq!(value["subject"], "namespaces/cache/statefulset/redis");
+        assert_eq!(value["datacontenttype"], "application/json");
+        assert_eq!(value["correlationid"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(value["data"]["event"], "update_completed");
+        assert_eq!(value["data"]["deployment"]["newImage"], "redis:7.2.5");
+    }
+
+    #[test]
+    fn test_notifier_needs_a_sink() {
+        let config = CloudEventsConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(CloudEventsNotifier::new(config.clone()).is_err());
+
+        let notifier = CloudEventsNotifier::new(CloudEventsConfig {
+            nats_url: Some("nats://nats.messaging:4222".to_string()),
+            ..config
+        })
+        .unwrap();
+        assert!(notifier.is_enabled());
+        assert_eq!(notifier.name(), "CloudEvents");
+    }
+}
@@ -0,0 +1,199 @@
+//! Suppression of repeated notifications.
+//!
+//! The same update is often detected several times in a row: a registry delivers its
+//! webhook twice, or a push is seen by the webhook and then again by polling. Each detection
+//! would send the same notification. A detection or UpdateRequest notification is suppressed
+//! when one with the same event, resource, container and new image was delivered within the
+//! window (HEADWIND_NOTIFICATION_DEDUP_WINDOW, seconds, 0 turns suppression off).
+//! Notifications about failures, rollbacks and decisions are always sent, and a notification
+//! no channel could deliver is not remembered, so its next detection tries again. Delivered
+//! notifications are kept in the bounded `notification_dedup` cache (see [`crate::cache`]).
+
+use super::{NotificationEvent, NotificationPayload};
+use crate::cache::{CacheLimits, SharedCache, new_shared_cache};
+use std::time::{Duration, Instant};
+
+/// Suppression window when HEADWIND_NOTIFICATION_DEDUP_WINDOW isn't set
+pub const DEFAULT_WINDOW_SECONDS: u64 = 300;
+
+/// HEADWIND_NOTIFICATION_DEDUP_WINDOW, seconds
+pub fn window_from_env() -> u64 {
+    std::env::var("HEADWIND_NOTIFICATION_DEDUP_WINDOW")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_WINDOW_SECONDS)
+}
+
+/// When notifications were delivered, by what they are about
+pub struct Deduplicator {
+    window: Duration,
+    sent: SharedCache<Instant>,
+}
+
+impl Deduplicator {
+    pub fn new(window_seconds: u64) -> Self {
+        Self {
+            window: Duration::from_secs(window_seconds),
+            sent: new_shared_cache("notification_dedup", CacheLimits::from_env()),
+        }
+    }
+
+    /// Whether the same notification as `payload` was delivered within the window
+    pub async fn is_repeat(&self, payload: &NotificationPayload) -> bool {
+        self.is_repeat_at(payload, Instant::now()).await
+    }
+
+    /// Remember that `payload` was delivered
+    pub async fn record(&self, payload: &NotificationPayload) {
+        self.record_at(payload, Instant::now()).await;
+    }
+
+    async fn is_repeat_at(&self, payload: &NotificationPayload, now: Instant) -> bool {
+        if self.window.is_zero() || !deduplicated(payload.event) {
+            return false;
+        }
+
+        self.sent
+            .read()
+            .await
+            .get(&key(payload))
+            .is_some_and(|at| now.saturating_duration_since(*at) < self.window)
+    }
+
+    async fn record_at(&self, payload: &NotificationPayload, now: Instant) {
+        if self.window.is_zero() || !deduplicated(payload.event) {
+            return;
+        }
+
+        self.sent.write().await.insert(key(payload), now);
+    }
+}
+
+/// Events repeated by detecting the same update again. Failures, rollbacks and decisions
+/// happen once and must never be swallowed.
+fn deduplicated(event: NotificationEvent) -> bool {
+    matches!(
+        event,
+        NotificationEvent::UpdateDetected | NotificationEvent::UpdateRequestCreated
+    )
+}
+
+/// What a notification is about: event, resource, container and new image
+fn key(payload: &NotificationPayload) -> String {
+    let resource = &payload.deployment;
+    format!(
+        "{}|{}|{}/{}|{}|{}",
+        payload.event.as_str(),
+        resource.resource_kind.as_deref().unwrap_or("Deployment"),
+        resource.namespace,
+        resource.name,
+        resource.container.as_deref().unwrap_or_default(),
+        resource.new_image
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::DeploymentInfo;
+
+    fn payload(event: NotificationEvent, new_image: &str) -> NotificationPayload {
+        NotificationPayload::new(
+            event,
+            DeploymentInfo {
+                name: "web".to_string(),
+                namespace: "default".to_string(),
+                current_image: "nginx:1.26.0".to_string(),
+                new_image: new_image.to_string(),
+                container: Some("nginx".to_string()),
+                resource_kind: None,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_suppresses_within_window() {
+        let dedup = Deduplicator::new(300);
+        let now = Instant::now();
+        let detected = payload(NotificationEvent::UpdateDetected, "nginx:1.27.0");
+
+        assert!(!dedup.is_repeat_at(&detected, now).await);
+        dedup.record_at(&detected, now).await;
+        assert!(
+            dedup
+                .is_repeat_at(&detected, now + Duration::from_secs(60))
+                .await
+        );
+
+        // Another event or another image is a different notification
+        let created = payload(NotificationEvent::UpdateRequestCreated, "nginx:1.27.0");
+        assert!(
+            !dedup
+                .is_repeat_at(&created, now + Duration::from_secs(60))
+                .await
+        );
+        let newer = payload(NotificationEvent::UpdateDetected, "nginx:1.27.1");
+        assert!(
+            !dedup
+                .is_repeat_at(&newer, now + Duration::from_secs(60))
+                .await
+        );
+
+        // Once the window has passed, it is sent again
+        assert!(
+            !dedup
+                .is_repeat_at(&detected, now + Duration::from_secs(300))
+                .await
+        );
+
+        let disabled = Deduplicator::new(0);
+        disabled.record_at(&detected, now).await;
+        assert!(!disabled.is_repeat_at(&detected, now).await);
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_notification_is_not_suppressed() {
+        let dedup = Deduplicator::new(300);
+        let now = Instant::now();
+        let created = payload(NotificationEvent::UpdateRequestCreated, "nginx:1.27.0");
+
+        // Checking alone doesn't remember the notification, only a delivery does
+        assert!(!dedup.is_repeat_at(&created, now).await);
+        assert!(
+            !dedup
+                .is_repeat_at(&created, now + Duration::from_secs(10))
+                .await
+        );
+        dedup
+            .record_at(&created, now + Duration::from_secs(10))
+            .await;
+        assert!(
+            dedup
+                .is_repeat_at(&created, now + Duration::from_secs(20))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failures_and_rollbacks_are_never_suppressed() {
+        let dedup = Deduplicator::new(300);
+        let now = Instant::now();
+
+        for event in [
+            NotificationEvent::UpdateFailed,
+            NotificationEvent::RollbackTriggered,
+            NotificationEvent::RollbackCompleted,
+            NotificationEvent::RollbackFailed,
+            NotificationEvent::UpdateCompleted,
+            NotificationEvent::UpdateApproved,
+        ] {
+            let notification = payload(event, "nginx:1.27.0");
+            dedup.record_at(&notification, now).await;
+            assert!(
+                !dedup
+                    .is_repeat_at(&notification, now + Duration::from_secs(1))
+                    .await
+            );
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! Delivery of notifications to one channel, with retries and circuit breakers.
+//!
+//! A send failing with a server error (5xx), a rate limit (429) or without a response
+//! (connection errors, timeouts) is retried up to HEADWIND_NOTIFICATION_MAX_RETRIES times
+//! (default 3; the generic webhook keeps WEBHOOK_MAX_RETRIES), waiting one second doubled
+//! after every attempt up to a minute. Each wait is shortened by a random part of up to half,
+//! so channels coming back from an outage aren't retried in lockstep. Other client errors
+//! (4xx) won't succeed on a retry and fail right away.
+//!
+//! Every destination of a channel (the webhook URL a route resolves to) has its own circuit.
+//! After HEADWIND_NOTIFICATION_CIRCUIT_THRESHOLD notifications in a row failed on it
+//! (default 5), the circuit opens: notifications for the destination are dropped without
+//! trying for HEADWIND_NOTIFICATION_CIRCUIT_COOLDOWN seconds (default 300). After that one
+//! notification is let through as a trial while the others are still dropped; success
+//! closes the circuit, failure opens it for another cooldown.
+
+use super::{NotificationPayload, NotificationRoute, Notifier, channel_label};
+use crate::metrics::{NOTIFICATION_CIRCUIT_OPEN, NOTIFICATION_RETRIES_TOTAL};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::warn;
+
+/// Wait before the first retry
+const BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between two attempts
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Retry and circuit breaker settings, shared by all channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliverySettings {
+    /// Retries after a failed send (HEADWIND_NOTIFICATION_MAX_RETRIES)
+    pub max_retries: u32,
+    /// Failed notifications in a row that open a channel's circuit
+    /// (HEADWIND_NOTIFICATION_CIRCUIT_THRESHOLD)
+    pub circuit_threshold: u32,
+    /// How long an open circuit drops notifications (HEADWIND_NOTIFICATION_CIRCUIT_COOLDOWN)
+    pub circuit_cooldown: Duration,
+}
+
+impl Default for DeliverySettings {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            circuit_threshold: 5,
+            circuit_cooldown: Duration::from_secs(300),
+        }
+    }
+}
+
+impl DeliverySettings {
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let defaults = Self::default();
+        Self {
+            max_retries: env_u64("HEADWIND_NOTIFICATION_MAX_RETRIES")
+                .map_or(defaults.max_retries, |v| v as u32),
+            circuit_threshold: env_u64("HEADWIND_NOTIFICATION_CIRCUIT_THRESHOLD")
+                .filter(|v| *v > 0)
+                .map_or(defaults.circuit_threshold, |v| v as u32),
+            circuit_cooldown: env_u64("HEADWIND_NOTIFICATION_CIRCUIT_COOLDOWN")
+                .filter(|v| *v > 0)
+                .map_or(defaults.circuit_cooldown, Duration::from_secs),
+        }
+    }
+}
+
+/// Wait before retry number `retry` (1 for the first), without jitter
+fn backoff(retry: u32) -> Duration {
+    let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+    BASE_DELAY.saturating_mul(factor).min(MAX_DELAY)
+}
+
+/// `delay` shortened by a part of up to half of it, picked by `sample`
+fn with_jitter(delay: Duration, sample: u64) -> Duration {
+    let fraction = sample as f64 / u64::MAX as f64;
+    delay.mul_f64(1.0 - fraction / 2.0)
+}
+
+/// A random number for jitter
+fn random_sample() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// A destination answered a notification with an unsuccessful status
+#[derive(Debug, Error)]
+#[error("{channel} returned error {status}: {body}")]
+pub struct StatusError {
+    pub channel: &'static str,
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl StatusError {
+    /// Error for an unsuccessful `response`, with its body
+    pub async fn from_response(channel: &'static str, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unable to read response".to_string());
+        Self {
+            channel,
+            status,
+            body,
+        }
+    }
+}
+
+/// Whether a failed send may succeed when retried: the destination answered with a server
+/// error or a rate limit, or didn't answer at all
+fn is_retryable(error: &anyhow::Error) -> bool {
+    let status = error.chain().find_map(|cause| {
+        cause
+            .downcast_ref::<StatusError>()
+            .map(|e| e.status)
+            .or_else(|| cause.downcast_ref::<reqwest::Error>()?.status())
+    });
+    status.is_none_or(|status| status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS)
+}
+
+/// Failures in a row of a destination, until when its circuit is open, and whether the trial
+/// after the cooldown is in flight
+#[derive(Debug, Default)]
+struct BreakerState {
+    failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+/// Circuit breaker of one destination
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(settings: &DeliverySettings) -> Self {
+        Self {
+            threshold: settings.circuit_threshold,
+            cooldown: settings.circuit_cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether a notification may be sent at `now`: the circuit is closed, or its cooldown is
+    /// over and this notification claims the trial. Until the trial's result is recorded,
+    /// other notifications are dropped.
+    fn allows(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            None => true,
+            Some(until) if now >= until && !state.probing => {
+                state.probing = true;
+                true
+            },
+            Some(_) => false,
+        }
+    }
+
+    fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::default();
+    }
+
+    /// Count a failed notification. Returns true when it opens the circuit, or opens it again
+    /// after a failed trial.
+    fn record_failure(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.failures = state.failures.saturating_add(1);
+        let reopen = std::mem::take(&mut state.probing);
+        if !reopen && (state.open_until.is_some() || state.failures < self.threshold) {
+            return false;
+        }
+        state.open_until = Some(now + self.cooldown);
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        self.state.lock().unwrap().open_until.is_some()
+    }
+}
+
+/// What happened to a notification on one channel
+pub enum Outcome {
+    Sent,
+    Failed(anyhow::Error),
+    /// Not tried, the channel's circuit is open
+    Dropped,
+}
+
+/// A notifier with the circuit breakers of its destinations
+pub struct Channel {
+    pub notifier: Box<dyn Notifier>,
+    breakers: Mutex<HashMap<String, Arc<CircuitBreaker>>>,
+}
+
+impl Channel {
+    pub fn new(notifier: Box<dyn Notifier>) -> Self {
+        Self {
+            notifier,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Circuit breaker of the destination notifications for `route` are sent to
+    fn breaker(
+        &self,
+        route: &NotificationRoute,
+        settings: &DeliverySettings,
+    ) -> Arc<CircuitBreaker> {
+        let destination = self.notifier.destination(route).unwrap_or_default();
+        self.breakers
+            .lock()
+            .unwrap()
+            .entry(destination)
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(settings)))
+            .clone()
+    }
+
+    /// Destinations of the channel whose circuit is open
+    fn open_circuits(&self) -> usize {
+        let breakers = self.breakers.lock().unwrap();
+        breakers
+            .values()
+            .filter(|breaker| breaker.is_open())
+            .count()
+    }
+
+    /// Send a notification, retrying failed attempts unless the circuit is open
+    pub async fn deliver(
+        &self,
+        payload: &NotificationPayload,
+        route: &NotificationRoute,
+        settings: &DeliverySettings,
+    ) -> Outcome {
+        let name = self.notifier.name();
+        let channel = channel_label(name);
+        let breaker = self.breaker(route, settings);
+        if !breaker.allows(Instant::now()) {
+            return Outcome::Dropped;
+        }
+
+        let max_retries = self.notifier.max_retries().unwrap_or(settings.max_retries);
+        let mut retry = 0;
+        let result = loop {
+            match self.notifier.send_routed(payload, route).await {
+                Ok(()) => break Ok(()),
+                Err(e) if retry < max_retries && is_retryable(&e) => {
+                    retry += 1;
+                    let delay = with_jitter(backoff(retry), random_sample());
+                    warn!(
+                        "Failed to send notification via {}, retry {}/{} in {:.1}s: {}",
+                        name,
+                        retry,
+                        max_retries,
+                        delay.as_secs_f64(),
+                        e
+                    );
+                    NOTIFICATION_RETRIES_TOTAL
+                        .with_label_values(&[channel])
+                        .inc();
+                    tokio::time::sleep(delay).await;
+                },
+                Err(e) => break Err(e),
+            }
+        };
+
+        let outcome = match result {
+            Ok(()) => {
+                breaker.record_success();
+                Outcome::Sent
+            },
+            Err(e) => {
+                if breaker.record_failure(Instant::now()) {
+                    warn!(
+                        "Opening the circuit of a {} destination for {}s after repeated failures",
+                        name,
+                        breaker.cooldown.as_secs()
+                    );
+                }
+                Outcome::Failed(e)
+            },
+        };
+        NOTIFICATION_CIRCUIT_OPEN
+            .with_label_values(&[channel])
+            .set(self.open_circuits() as i64);
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::{DeploymentInfo, NotificationEvent};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(2), Duration::from_secs(2));
+        assert_eq!(backoff(4), Duration::from_secs(8));
+        assert_eq!(backoff(7), MAX_DELAY);
+        assert_eq!(backoff(100), MAX_DELAY);
+
+        let delay = Duration::from_secs(8);
+        assert_eq!(with_jitter(delay, 0), delay);
+        assert_eq!(with_jitter(delay, u64::MAX), Duration::from_secs(4));
+        let jittered = with_jitter(delay, random_sample());
+        assert!(jittered >= Duration::from_secs(4) && jittered <= delay);
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let breaker = CircuitBreaker::new(&DeliverySettings {
+            max_retries: 0,
+            circuit_threshold: 2,
+            circuit_cooldown: Duration::from_secs(300),
+        });
+        let now = Instant::now();
+
+        assert!(breaker.allows(now));
+        assert!(!breaker.record_failure(now));
+        assert!(breaker.allows(now));
+        assert!(breaker.record_failure(now));
+        assert!(!breaker.allows(now + Duration::from_secs(299)));
+
+        // After the cooldown one trial is let through, and its failure reopens the circuit
+        let later = now + Duration::from_secs(300);
+        assert!(breaker.allows(later));
+        assert!(!breaker.allows(later));
+        assert!(breaker.record_failure(later));
+        assert!(!breaker.allows(later + Duration::from_secs(1)));
+
+        // A successful trial closes it
+        let trial = later + Duration::from_secs(300);
+        assert!(breaker.allows(trial));
+        assert!(!breaker.allows(trial));
+        breaker.record_success();
+        assert!(breaker.allows(trial));
+        assert!(breaker.allows(trial));
+        assert!(!breaker.record_failure(trial));
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let status = |status: StatusCode| {
+            anyhow::Error::new(StatusError {
+                channel: "Webhook",
+                status,
+                body: String::new(),
+            })
+        };
+
+        assert!(is_retryable(&status(StatusCode::SERVICE_UNAVAILABLE)));
+        assert!(is_retryable(&status(StatusCode::TOO_MANY_REQUESTS)));
+        assert!(!is_retryable(&status(StatusCode::NOT_FOUND)));
+        assert!(!is_retryable(
+            &status(StatusCode::BAD_REQUEST).context("Failed to send")
+        ));
+        assert!(is_retryable(&anyhow::anyhow!("connection refused")));
+    }
+
+    /// Fails with `status` for the webhook URL `failing`, counting attempts
+    struct FakeNotifier {
+        failing: &'static str,
+        status: StatusCode,
+        attempts: Arc<AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl Notifier for FakeNotifier {
+        async fn send_routed(
+            &self,
+            _payload: &NotificationPayload,
+            route: &NotificationRoute,
+        ) -> anyhow::Result<()> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            if route.webhook_url.as_deref() == Some(self.failing) {
+                return Err(StatusError {
+                    channel: "Fake",
+                    status: self.status,
+                    body: String::new(),
+                }
+                .into());
+            }
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            "Fake"
+        }
+
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn destination(&self, route: &NotificationRoute) -> Option<String> {
+            route.webhook_url.clone()
+        }
+    }
+
+    fn route(url: &str) -> NotificationRoute {
+        NotificationRoute {
+            webhook_url: Some(url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_circuit_per_destination() {
+        let settings = DeliverySettings {
+            max_retries: 3,
+            circuit_threshold: 1,
+            circuit_cooldown: Duration::from_secs(300),
+        };
+        let attempts = Arc::new(AtomicU32::new(0));
+        let notifier = FakeNotifier {
+            failing: "https://a.example.com",
+            status: StatusCode::NOT_FOUND,
+            attempts: attempts.clone(),
+        };
+        let channel = Channel::new(Box::new(notifier));
+        let payload = NotificationPayload::new(
+            NotificationEvent::UpdateCompleted,
+            DeploymentInfo {
+                name: "web".to_string(),
+                namespace: "default".to_string(),
+                current_image: "nginx:1.26.0".to_string(),
+                new_image: "nginx:1.27.0".to_string(),
+                container: Some("nginx".to_string()),
+                resource_kind: None,
+            },
+        );
+        let failing = route("https://a.example.com");
+
+        // Client errors aren't retried
+        assert!(matches!(
+            channel.deliver(&payload, &failing, &settings).await,
+            Outcome::Failed(_)
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            channel.deliver(&payload, &failing, &settings).await,
+            Outcome::Dropped
+        ));
+        // Another destination of the same channel is still sent to
+        assert!(matches!(
+            channel
+                .deliver(&payload, &route("https://b.example.com"), &settings)
+                .await,
+            Outcome::Sent
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(channel.open_circuits(), 1);
+    }
+}
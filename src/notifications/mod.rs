@@ -1,18 +1,26 @@
+use crate::config::notificationroutes::NotificationRoute;
 use crate::metrics;
+use crate::models::crd::ReleaseNotes;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
 
+mod cloudevents;
+pub mod dedup;
+pub mod delivery;
+pub mod routing;
 mod slack;
 mod teams;
 mod webhook;
 
+pub use cloudevents::CloudEventsNotifier;
+use delivery::{Channel, DeliverySettings, Outcome};
 pub use slack::SlackNotifier;
-pub use teams::TeamsNotifier;
+pub use teams::{ACTIONS_PATH, CARD_ACTION_STATUS_HEADER, TeamsNotifier, verify_action_token};
 pub use webhook::WebhookNotifier;
 
 /// Notification event types
@@ -27,6 +35,8 @@ pub enum NotificationEvent {
     UpdateApproved,
     /// Update rejected by user
     UpdateRejected,
+    /// UpdateRequest not approved before it expired
+    UpdateExpired,
     /// Update successfully applied
     UpdateCompleted,
     /// Update failed to apply
@@ -46,6 +56,7 @@ impl NotificationEvent {
             Self::UpdateRequestCreated => "update.request.created",
             Self::UpdateApproved => "update.approved",
             Self::UpdateRejected => "update.rejected",
+            Self::UpdateExpired => "update.expired",
             Self::UpdateCompleted => "update.completed",
             Self::UpdateFailed => "update.failed",
             Self::RollbackTriggered => "rollback.triggered",
@@ -60,6 +71,7 @@ impl NotificationEvent {
             Self::UpdateRequestCreated => "📦",
             Self::UpdateApproved => "✅",
             Self::UpdateRejected => "❌",
+            Self::UpdateExpired => "⌛",
             Self::UpdateCompleted => "🎉",
             Self::UpdateFailed => "⚠️",
             Self::RollbackTriggered => "🔄",
@@ -74,6 +86,7 @@ impl NotificationEvent {
             Self::UpdateRequestCreated => "#9C27B0", // Purple
             Self::UpdateApproved => "#4CAF50",       // Green
             Self::UpdateRejected => "#F44336",       // Red
+            Self::UpdateExpired => "#9E9E9E",        // Grey
             Self::UpdateCompleted => "#4CAF50",      // Green
             Self::UpdateFailed => "#FF9800",         // Orange
             Self::RollbackTriggered => "#FF9800",    // Orange
@@ -90,6 +103,9 @@ pub struct NotificationPayload {
     pub event: NotificationEvent,
     pub timestamp: DateTime<Utc>,
     pub deployment: DeploymentInfo,
+    /// Cluster the resource runs in (HEADWIND_CLUSTER_NAME)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub policy: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -106,6 +122,12 @@ pub struct NotificationPayload {
     pub error_message: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub update_request_name: Option<String>,
+    /// Release notes of the new version (HEADWIND_RELEASE_NOTES)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_notes: Option<ReleaseNotes>,
+    /// Trace ID of the update flow, shared with its audit records and exported spans
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<HashMap<String, String>>,
 }
@@ -129,6 +151,11 @@ pub struct NotificationConfig {
     pub slack: SlackConfig,
     pub teams: TeamsConfig,
     pub webhook: WebhookConfig,
+    pub cloudevents: CloudEventsConfig,
+    /// Seconds repeats of a notification are suppressed for (HEADWIND_NOTIFICATION_DEDUP_WINDOW)
+    pub dedup_window_seconds: u64,
+    /// Retries and circuit breaking of failing channels
+    pub delivery: DeliverySettings,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -144,6 +171,9 @@ pub struct SlackConfig {
 pub struct TeamsConfig {
     pub enabled: bool,
     pub webhook_url: Option<String>,
+    /// Secret signing the Approve/Reject buttons of Adaptive Cards. Without it, cards only
+    /// link to the approval API.
+    pub action_secret: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -155,6 +185,34 @@ pub struct WebhookConfig {
     pub max_retries: u32,
 }
 
+/// CloudEvents sink (see [`CloudEventsNotifier`])
+#[derive(Debug, Clone)]
+pub struct CloudEventsConfig {
+    pub enabled: bool,
+    /// HTTP endpoint receiving the events
+    pub url: Option<String>,
+    /// NATS server the events are published to
+    pub nats_url: Option<String>,
+    /// Prefix of the NATS subjects, followed by the event name
+    pub nats_subject: String,
+    /// `source` attribute of the events
+    pub source: String,
+    pub timeout_seconds: u64,
+}
+
+impl Default for CloudEventsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: None,
+            nats_url: None,
+            nats_subject: "headwind.events".to_string(),
+            source: cloudevents::default_source(),
+            timeout_seconds: 10,
+        }
+    }
+}
+
 impl Default for NotificationConfig {
     fn default() -> Self {
         Self {
@@ -167,6 +225,9 @@ impl Default for NotificationConfig {
                 timeout_seconds: 10,
                 max_retries: 3,
             },
+            cloudevents: CloudEventsConfig::default(),
+            dedup_window_seconds: dedup::DEFAULT_WINDOW_SECONDS,
+            delivery: DeliverySettings::default(),
         }
     }
 }
@@ -178,6 +239,9 @@ impl NotificationConfig {
             slack: SlackConfig::from_env(),
             teams: TeamsConfig::from_env(),
             webhook: WebhookConfig::from_env(),
+            cloudevents: CloudEventsConfig::from_env(),
+            dedup_window_seconds: dedup::window_from_env(),
+            delivery: DeliverySettings::from_env(),
         }
     }
 
@@ -235,12 +299,17 @@ impl NotificationConfig {
             slack: SlackConfig::from_configmap_config(cm_config.slack),
             teams: TeamsConfig::from_configmap_config(cm_config.teams),
             webhook: WebhookConfig::from_configmap_config(cm_config.webhook),
+            cloudevents: CloudEventsConfig::from_configmap_config(cm_config.cloudevents),
+            dedup_window_seconds: cm_config
+                .dedup_window_seconds
+                .unwrap_or_else(dedup::window_from_env),
+            delivery: DeliverySettings::from_env(),
         }
     }
 
     /// Check if any notification channels are enabled
     pub fn has_enabled_channels(&self) -> bool {
-        self.slack.enabled || self.teams.enabled || self.webhook.enabled
+        self.slack.enabled || self.teams.enabled || self.webhook.enabled || self.cloudevents.enabled
     }
 }
 
@@ -253,6 +322,10 @@ struct ConfigMapNotificationConfig {
     teams: Option<ConfigMapTeamsConfig>,
     #[serde(default)]
     webhook: Option<ConfigMapWebhookConfig>,
+    #[serde(default)]
+    cloudevents: Option<ConfigMapCloudEventsConfig>,
+    #[serde(default)]
+    dedup_window_seconds: Option<u64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -268,6 +341,7 @@ struct ConfigMapSlackConfig {
 struct ConfigMapTeamsConfig {
     enabled: Option<bool>,
     webhook_url: Option<String>,
+    action_secret: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -279,6 +353,16 @@ struct ConfigMapWebhookConfig {
     max_retries: Option<u32>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct ConfigMapCloudEventsConfig {
+    enabled: Option<bool>,
+    url: Option<String>,
+    nats_url: Option<String>,
+    nats_subject: Option<String>,
+    source: Option<String>,
+    timeout_seconds: Option<u64>,
+}
+
 impl SlackConfig {
     /// Load Slack configuration from environment variables
     pub fn from_env() -> Self {
@@ -319,6 +403,21 @@ impl SlackConfig {
     }
 }
 
+/// Secret signing Teams card actions (TEAMS_ACTION_SECRET)
+pub fn teams_action_secret() -> Option<String> {
+    std::env::var("TEAMS_ACTION_SECRET")
+        .ok()
+        .filter(|s| !s.is_empty())
+}
+
+/// Base URL of the approval API as reachable from notification recipients
+/// (HEADWIND_API_URL, default: the local approval API)
+pub fn api_url() -> String {
+    std::env::var("HEADWIND_API_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| crate::server::approval_api_url())
+}
+
 impl TeamsConfig {
     /// Load Teams configuration from environment variables
     pub fn from_env() -> Self {
@@ -328,6 +427,7 @@ impl TeamsConfig {
                 .parse()
                 .unwrap_or(false),
             webhook_url: std::env::var("TEAMS_WEBHOOK_URL").ok(),
+            action_secret: teams_action_secret(),
         }
     }
 
@@ -344,6 +444,7 @@ impl TeamsConfig {
                 webhook_url: cm
                     .webhook_url
                     .or_else(|| std::env::var("TEAMS_WEBHOOK_URL").ok()),
+                action_secret: cm.action_secret.or_else(teams_action_secret),
             }
         } else {
             Self::from_env()
@@ -403,17 +504,95 @@ impl WebhookConfig {
     }
 }
 
+impl CloudEventsConfig {
+    /// Load CloudEvents configuration from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("CLOUDEVENTS_ENABLED")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(false),
+            url: non_empty_env("CLOUDEVENTS_URL"),
+            nats_url: non_empty_env("CLOUDEVENTS_NATS_URL"),
+            nats_subject: non_empty_env("CLOUDEVENTS_NATS_SUBJECT")
+                .unwrap_or(defaults.nats_subject),
+            source: non_empty_env("CLOUDEVENTS_SOURCE").unwrap_or(defaults.source),
+            timeout_seconds: std::env::var("CLOUDEVENTS_TIMEOUT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.timeout_seconds),
+        }
+    }
+
+    /// Load CloudEvents configuration from ConfigMap, falling back to environment variables
+    fn from_configmap_config(cm_config: Option<ConfigMapCloudEventsConfig>) -> Self {
+        let env = Self::from_env();
+        match cm_config {
+            Some(cm) => Self {
+                enabled: cm.enabled.unwrap_or(env.enabled),
+                url: cm.url.or(env.url),
+                nats_url: cm.nats_url.or(env.nats_url),
+                nats_subject: cm.nats_subject.unwrap_or(env.nats_subject),
+                source: cm.source.unwrap_or(env.source),
+                timeout_seconds: cm.timeout_seconds.unwrap_or(env.timeout_seconds),
+            },
+            None => env,
+        }
+    }
+}
+
+fn non_empty_env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
 /// Notifier trait for different notification backends
 #[async_trait::async_trait]
 pub trait Notifier: Send + Sync {
-    async fn send(&self, payload: &NotificationPayload) -> Result<()>;
+    /// Send a notification to the globally configured destination
+    async fn send(&self, payload: &NotificationPayload) -> Result<()> {
+        self.send_routed(payload, &NotificationRoute::default())
+            .await
+    }
+
+    /// Send a notification to the destination of a route, falling back to the global one
+    async fn send_routed(
+        &self,
+        payload: &NotificationPayload,
+        route: &NotificationRoute,
+    ) -> Result<()>;
     fn name(&self) -> &'static str;
     fn is_enabled(&self) -> bool;
+
+    /// Retries after a failed send, when the channel has its own setting rather than
+    /// HEADWIND_NOTIFICATION_MAX_RETRIES
+    fn max_retries(&self) -> Option<u32> {
+        None
+    }
+
+    /// Where a notification for `route` is sent, so failing destinations are circuit broken
+    /// separately. None for channels that always send to the same place.
+    fn destination(&self, _route: &NotificationRoute) -> Option<String> {
+        None
+    }
+}
+
+/// Value of the `channel` metric label for a notifier
+fn channel_label(name: &str) -> &str {
+    match name {
+        "Slack" => "slack",
+        "Microsoft Teams" => "teams",
+        "Webhook" => "webhook",
+        "CloudEvents" => "cloudevents",
+        other => other,
+    }
 }
 
 /// Main notification manager that coordinates all notifiers
 pub struct NotificationManager {
-    notifiers: Vec<Box<dyn Notifier>>,
+    channels: Vec<Channel>,
+    dedup: dedup::Deduplicator,
+    delivery: DeliverySettings,
 }
 
 impl NotificationManager {
@@ -444,17 +623,42 @@ impl NotificationManager {
             }
         }
 
+        // Add CloudEvents sink if enabled
+        if config.cloudevents.enabled {
+            match CloudEventsNotifier::new(config.cloudevents.clone()) {
+                Ok(notifier) => notifiers.push(Box::new(notifier)),
+                Err(e) => error!("Failed to create CloudEvents notifier: {}", e),
+            }
+        }
+
         info!(
             "Notification manager initialized with {} notifiers",
             notifiers.len()
         );
 
-        Self { notifiers }
+        Self {
+            channels: notifiers.into_iter().map(Channel::new).collect(),
+            dedup: dedup::Deduplicator::new(config.dedup_window_seconds),
+            delivery: config.delivery,
+        }
     }
 
     /// Send notification to all enabled notifiers
     pub async fn notify(&self, payload: &NotificationPayload) {
-        if self.notifiers.is_empty() {
+        if self.channels.is_empty() {
+            return;
+        }
+
+        if self.dedup.is_repeat(payload).await {
+            debug!(
+                "Suppressing repeated notification: {} for {}/{}",
+                payload.event.as_str(),
+                payload.deployment.namespace,
+                payload.deployment.name
+            );
+            metrics::NOTIFICATIONS_SUPPRESSED_TOTAL
+                .with_label_values(&[payload.event.as_str()])
+                .inc();
             return;
         }
 
@@ -465,27 +669,59 @@ impl NotificationManager {
             payload.deployment.name
         );
 
-        for notifier in &self.notifiers {
-            if !notifier.is_enabled() {
-                continue;
-            }
+        let route = &routing::resolve(payload).await;
+
+        // Channels are delivered to side by side, so one retrying doesn't hold the others up
+        let channels = self
+            .channels
+            .iter()
+            .filter(|channel| channel.notifier.is_enabled());
+        let outcomes = futures::future::join_all(channels.map(|channel| async move {
+            let outcome = channel.deliver(payload, route, &self.delivery).await;
+            (channel.notifier.name(), outcome)
+        }))
+        .await;
+
+        // Only delivered notifications suppress their repeats
+        if outcomes
+            .iter()
+            .any(|(_, outcome)| matches!(outcome, Outcome::Sent))
+        {
+            self.dedup.record(payload).await;
+        }
 
-            match notifier.send(payload).await {
-                Ok(()) => {
-                    info!("Notification sent successfully via {}", notifier.name());
-                    metrics::NOTIFICATIONS_SENT_TOTAL.inc();
+        for (name, outcome) in outcomes {
+            let labels = [channel_label(name), payload.event.as_str()];
+            match outcome {
+                Outcome::Sent => {
+                    info!("Notification sent successfully via {}", name);
+                    metrics::NOTIFICATIONS_SENT_TOTAL
+                        .with_label_values(&labels)
+                        .inc();
 
                     // Increment per-channel metrics
-                    match notifier.name() {
+                    match name {
                         "Slack" => metrics::NOTIFICATIONS_SLACK_SENT.inc(),
                         "Microsoft Teams" => metrics::NOTIFICATIONS_TEAMS_SENT.inc(),
                         "Webhook" => metrics::NOTIFICATIONS_WEBHOOK_SENT.inc(),
                         _ => {},
                     }
                 },
-                Err(e) => {
-                    error!("Failed to send notification via {}: {}", notifier.name(), e);
-                    metrics::NOTIFICATIONS_FAILED_TOTAL.inc();
+                Outcome::Failed(e) => {
+                    error!("Failed to send notification via {}: {}", name, e);
+                    metrics::NOTIFICATIONS_FAILED_TOTAL
+                        .with_label_values(&labels)
+                        .inc();
+                },
+                Outcome::Dropped => {
+                    warn!(
+                        "Dropped notification {} via {}: the destination's circuit is open",
+                        payload.event.as_str(),
+                        name
+                    );
+                    metrics::NOTIFICATIONS_DROPPED_TOTAL
+                        .with_label_values(&labels)
+                        .inc();
                 },
             }
         }
@@ -493,12 +729,15 @@ impl NotificationManager {
 
     /// Check if any notifiers are enabled
     pub fn has_enabled_notifiers(&self) -> bool {
-        self.notifiers.iter().any(|n| n.is_enabled())
+        self.channels.iter().any(|c| c.notifier.is_enabled())
     }
 
     /// Get count of enabled notifiers
     pub fn enabled_count(&self) -> usize {
-        self.notifiers.iter().filter(|n| n.is_enabled()).count()
+        self.channels
+            .iter()
+            .filter(|c| c.notifier.is_enabled())
+            .count()
     }
 }
 
@@ -508,6 +747,7 @@ impl NotificationPayload {
             event,
             timestamp: Utc::now(),
             deployment,
+            cluster: crate::config::cluster::name().map(str::to_string),
             policy: None,
             requires_approval: None,
             approval_url: None,
@@ -516,6 +756,8 @@ impl NotificationPayload {
             rejection_reason: None,
             error_message: None,
             update_request_name: None,
+            release_notes: None,
+            correlation_id: crate::telemetry::correlation_id(),
             metadata: None,
         }
     }
@@ -560,6 +802,11 @@ impl NotificationPayload {
         self
     }
 
+    pub fn with_release_notes(mut self, release_notes: ReleaseNotes) -> Self {
+        self.release_notes = Some(release_notes);
+        self
+    }
+
     pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
         self.metadata = Some(metadata);
         self
@@ -578,10 +825,13 @@ impl NotificationPayload {
         } else {
             resource_kind
         };
-        let resource_ref = format!(
+        let mut resource_ref = format!(
             "{} {}/{}",
             formatted_kind, self.deployment.namespace, self.deployment.name
         );
+        if let Some(cluster) = &self.cluster {
+            resource_ref.push_str(&format!(" in {}", cluster));
+        }
 
         match self.event {
             NotificationEvent::UpdateDetected => {
@@ -596,6 +846,9 @@ impl NotificationPayload {
             NotificationEvent::UpdateRejected => {
                 format!("Update rejected: {}", resource_ref)
             },
+            NotificationEvent::UpdateExpired => {
+                format!("Update request expired: {}", resource_ref)
+            },
             NotificationEvent::UpdateCompleted => {
                 format!("Update completed: {}", resource_ref)
             },
@@ -637,6 +890,10 @@ impl NotificationPayload {
             desc.push_str(&format!("\nError: {}", error));
         }
 
+        if let Some(notes) = &self.release_notes {
+            desc.push_str(&format!("\nRelease notes: {}", notes.url));
+        }
+
         desc
     }
 }
@@ -662,8 +919,10 @@ pub fn notify(payload: NotificationPayload) {
 
     if let Some(manager) = notifier {
         // Spawn a background task to send notifications asynchronously
+        metrics::NOTIFICATION_QUEUE_DEPTH.inc();
         tokio::spawn(async move {
             manager.notify(&payload).await;
+            metrics::NOTIFICATION_QUEUE_DEPTH.dec();
         });
     }
 }
@@ -674,12 +933,21 @@ pub fn notify_update_detected(deployment: DeploymentInfo) {
     notify(payload);
 }
 
+/// Helper function to send update detected notification for a resource with policy `none`,
+/// which is never updated (`headwind.sh/notify-detected`)
+pub fn notify_update_detected_only(deployment: DeploymentInfo) {
+    let payload =
+        NotificationPayload::new(NotificationEvent::UpdateDetected, deployment).with_policy("none");
+    notify(payload);
+}
+
 /// Helper function to send UpdateRequest created notification
 pub fn notify_update_request_created(
     deployment: DeploymentInfo,
     policy: String,
     requires_approval: bool,
     update_request_name: String,
+    release_notes: Option<ReleaseNotes>,
 ) {
     let mut payload =
         NotificationPayload::new(NotificationEvent::UpdateRequestCreated, deployment.clone())
@@ -687,14 +955,15 @@ pub fn notify_update_request_created(
             .with_requires_approval(requires_approval)
             .with_update_request(update_request_name);
 
+    if let Some(release_notes) = release_notes {
+        payload = payload.with_release_notes(release_notes);
+    }
+
     // Add approval URL if requires_approval is true
     if requires_approval {
-        // Use environment variable or default to localhost for testing
-        let base_url = std::env::var("HEADWIND_API_URL")
-            .unwrap_or_else(|_| "http://localhost:8081".to_string());
         let approval_url = format!(
             "{}/api/v1/updates/{}/{}/approve",
-            base_url,
+            api_url(),
             deployment.namespace,
             payload
                 .update_request_name
@@ -749,6 +1018,13 @@ pub fn notify_update_rejected(
     notify(payload);
 }
 
+/// Helper function to send expiry notification
+pub fn notify_update_expired(deployment: DeploymentInfo, update_request_name: String) {
+    let payload = NotificationPayload::new(NotificationEvent::UpdateExpired, deployment)
+        .with_update_request(update_request_name);
+    notify(payload);
+}
+
 /// Helper function to send update completed notification
 pub fn notify_update_completed(deployment: DeploymentInfo) {
     let payload = NotificationPayload::new(NotificationEvent::UpdateCompleted, deployment);
@@ -829,11 +1105,18 @@ mod tests {
             resource_kind: None,
         };
 
-        let payload = NotificationPayload::new(NotificationEvent::UpdateApproved, deployment);
+        let mut payload = NotificationPayload::new(NotificationEvent::UpdateApproved, deployment);
+        payload.cluster = None;
         assert_eq!(
             payload.title(),
             "Update approved: Deployment production/nginx"
         );
+
+        payload.cluster = Some("staging".to_string());
+        assert_eq!(
+            payload.title(),
+            "Update approved: Deployment production/nginx in staging"
+        );
     }
 
     #[test]
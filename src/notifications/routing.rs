@@ -0,0 +1,124 @@
+//! Per-resource and per-namespace notification routing.
+//!
+//! Before a notification is sent, the resource it is about is looked up. The route named by
+//! its `headwind.sh/notify-route` annotation, or else the route named after its namespace
+//! (see `config::notificationroutes`), decides where it goes. `headwind.sh/notify-channel`
+//! on the resource picks the Slack channel. Anything a route leaves unset goes to the global
+//! destination.
+
+use super::NotificationPayload;
+use crate::config::notificationroutes::{self, NotificationRoute};
+use crate::models::crd::TargetRef;
+use crate::models::policy::annotations;
+use kube::Client;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// Route for a notification about a resource in `namespace`. `lookup` returns configured
+/// routes by name.
+pub fn select(
+    namespace: &str,
+    resource_annotations: &BTreeMap<String, String>,
+    lookup: impl Fn(&str) -> Option<NotificationRoute>,
+) -> NotificationRoute {
+    let named = resource_annotations
+        .get(annotations::NOTIFY_ROUTE)
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty());
+    let base = match named {
+        Some(name) => lookup(name).unwrap_or_else(|| {
+            debug!("Notification route {} is not configured", name);
+            NotificationRoute::default()
+        }),
+        None => lookup(namespace).unwrap_or_default(),
+    };
+
+    let channel = resource_annotations
+        .get(annotations::NOTIFY_CHANNEL)
+        .map(|channel| channel.trim().to_string())
+        .filter(|channel| !channel.is_empty());
+    base.overridden_by(NotificationRoute {
+        slack_channel: channel,
+        ..Default::default()
+    })
+}
+
+/// Route for a notification, from the annotations of the resource it is about
+pub async fn resolve(payload: &NotificationPayload) -> NotificationRoute {
+    let resource = &payload.deployment;
+    let annotations = match Client::try_default().await {
+        Ok(client) => {
+            let kind = resource.resource_kind.as_deref().unwrap_or("Deployment");
+            let target = TargetRef {
+                api_version: crate::audit::api_version(kind).to_string(),
+                kind: kind.to_string(),
+                name: resource.name.clone(),
+                namespace: resource.namespace.clone(),
+            };
+            crate::hooks::target_annotations(&client, &target).await
+        },
+        Err(e) => {
+            debug!("Routing notification without resource annotations: {}", e);
+            BTreeMap::new()
+        },
+    };
+
+    select(&resource.namespace, &annotations, notificationroutes::route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(name: &str) -> Option<NotificationRoute> {
+        match name {
+            "payments" => Some(NotificationRoute {
+                slack_channel: Some("#team-payments".to_string()),
+                webhook_url: Some("https://payments.example.com/hook".to_string()),
+                ..Default::default()
+            }),
+            "platform" => Some(NotificationRoute {
+                slack_channel: Some("#platform".to_string()),
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_select_by_namespace() {
+        let route = select("payments", &BTreeMap::new(), routes);
+        assert_eq!(route.slack_channel.as_deref(), Some("#team-payments"));
+
+        assert_eq!(
+            select("default", &BTreeMap::new(), routes),
+            NotificationRoute::default()
+        );
+    }
+
+    #[test]
+    fn test_select_by_annotation() {
+        let mut resource = BTreeMap::from([(
+            annotations::NOTIFY_ROUTE.to_string(),
+            "platform".to_string(),
+        )]);
+        let route = select("payments", &resource, routes);
+        assert_eq!(route.slack_channel.as_deref(), Some("#platform"));
+        assert_eq!(route.webhook_url, None);
+
+        resource.insert(
+            annotations::NOTIFY_CHANNEL.to_string(),
+            "#payments-api".to_string(),
+        );
+        let route = select("payments", &resource, routes);
+        assert_eq!(route.slack_channel.as_deref(), Some("#payments-api"));
+
+        resource.remove(annotations::NOTIFY_ROUTE);
+        let route = select("payments", &resource, routes);
+        assert_eq!(route.slack_channel.as_deref(), Some("#payments-api"));
+        assert_eq!(
+            route.webhook_url.as_deref(),
+            Some("https://payments.example.com/hook")
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use super::{NotificationPayload, Notifier, SlackConfig};
+use super::delivery::StatusError;
+use super::{NotificationPayload, NotificationRoute, Notifier, SlackConfig};
 use anyhow::{Context, Result, anyhow};
 use reqwest::Client;
 use serde_json::json;
@@ -36,6 +37,14 @@ impl SlackNotifier {
 
         let mut fields = Vec::new();
 
+        // Add cluster field if present
+        if let Some(cluster) = &payload.cluster {
+            fields.push(json!({
+                "type": "mrkdwn",
+                "text": format!("*Cluster:*\n{}", cluster)
+            }));
+        }
+
         // Add policy field if present
         if let Some(policy) = &payload.policy {
             fields.push(json!({
@@ -120,6 +129,25 @@ impl SlackNotifier {
             }));
         }
 
+        // Add release notes if present
+        if let Some(notes) = &payload.release_notes {
+            let mut text = format!(
+                "*Release notes:* <{}|{}>",
+                notes.url,
+                notes.title.as_deref().unwrap_or(&notes.url)
+            );
+            if let Some(summary) = &notes.summary {
+                text.push_str(&format!("\n{}", summary));
+            }
+            blocks.push(json!({
+                "type": "section",
+                "text": {
+                    "type": "mrkdwn",
+                    "text": text
+                }
+            }));
+        }
+
         // Add action buttons if URLs are present
         let mut action_elements = Vec::new();
 
@@ -184,7 +212,8 @@ impl SlackNotifier {
 
         // Note: For Incoming Webhooks, the channel is pre-configured in the webhook URL
         // and cannot be overridden in the payload. The channel config is ignored for
-        // Incoming Webhooks but may be used in the future for other Slack integration methods.
+        // Incoming Webhooks; routed channels are sent (see send_routed) for legacy webhooks
+        // and workflows that honor them.
 
         // Add username if configured
         if let Some(username) = &self.config.username {
@@ -206,14 +235,19 @@ impl SlackNotifier {
 
 #[async_trait::async_trait]
 impl Notifier for SlackNotifier {
-    async fn send(&self, payload: &NotificationPayload) -> Result<()> {
+    async fn send_routed(
+        &self,
+        payload: &NotificationPayload,
+        route: &NotificationRoute,
+    ) -> Result<()> {
         let webhook_url = self
-            .config
-            .webhook_url
-            .as_ref()
+            .destination(route)
             .ok_or_else(|| anyhow!("Slack webhook URL not configured"))?;
 
-        let message = self.build_message(payload);
+        let mut message = self.build_message(payload);
+        if let Some(channel) = &route.slack_channel {
+            message["channel"] = json!(channel);
+        }
 
         debug!("Sending Slack notification to: {}", webhook_url);
         debug!("Webhook URL length: {} chars", webhook_url.len());
@@ -230,7 +264,7 @@ impl Notifier for SlackNotifier {
 
         let response = self
             .client
-            .post(webhook_url)
+            .post(&webhook_url)
             .header("Content-Type", "application/json")
             .body(json_str)
             .send()
@@ -248,12 +282,9 @@ impl Notifier for SlackNotifier {
             })?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response
-                .text()
+            return Err(StatusError::from_response("Slack API", response)
                 .await
-                .unwrap_or_else(|_| "Unable to read response".to_string());
-            return Err(anyhow!("Slack API returned error {}: {}", status, body));
+                .into());
         }
 
         debug!("Slack notification sent successfully");
@@ -264,6 +295,13 @@ impl Notifier for SlackNotifier {
         "Slack"
     }
 
+    fn destination(&self, route: &NotificationRoute) -> Option<String> {
+        route
+            .slack_webhook_url
+            .clone()
+            .or_else(|| self.config.webhook_url.clone())
+    }
+
     fn is_enabled(&self) -> bool {
         self.config.enabled && self.config.webhook_url.is_some()
     }
@@ -272,6 +310,7 @@ impl Notifier for SlackNotifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::crd::ReleaseNotes;
     use crate::notifications::{DeploymentInfo, NotificationEvent};
 
     #[test]
@@ -382,4 +421,42 @@ mod tests {
         let message_str = serde_json::to_string(&message).unwrap();
         assert!(message_str.contains("Failed to pull image"));
     }
+
+    #[test]
+    fn test_build_message_with_release_notes() {
+        let config = SlackConfig {
+            enabled: true,
+            webhook_url: Some("https://hooks.slack.com/services/TEST".to_string()),
+            channel: None,
+            username: None,
+            icon_emoji: None,
+        };
+
+        let notifier = SlackNotifier::new(config).unwrap();
+
+        let deployment = DeploymentInfo {
+            name: "nginx".to_string(),
+            namespace: "production".to_string(),
+            current_image: "nginx:1.25.0".to_string(),
+            new_image: "nginx:1.26.0".to_string(),
+            container: None,
+            resource_kind: None,
+        };
+
+        let payload = NotificationPayload::new(NotificationEvent::UpdateRequestCreated, deployment)
+            .with_release_notes(ReleaseNotes {
+                source: "github".to_string(),
+                url: "https://github.com/nginx/nginx/releases/tag/release-1.26.0".to_string(),
+                title: Some("release-1.26.0".to_string()),
+                summary: Some("- HTTP/3 enabled by default".to_string()),
+            });
+
+        let message = notifier.build_message(&payload);
+
+        let message_str = serde_json::to_string(&message).unwrap();
+        assert!(message_str.contains(
+            "<https://github.com/nginx/nginx/releases/tag/release-1.26.0|release-1.26.0>"
+        ));
+        assert!(message_str.contains("HTTP/3 enabled by default"));
+    }
 }
@@ -1,10 +1,37 @@
-use super::{NotificationPayload, Notifier, TeamsConfig};
+use super::delivery::StatusError;
+use super::{NotificationEvent, NotificationPayload, NotificationRoute, Notifier, TeamsConfig};
+use crate::webhook::auth::{constant_time_eq, hmac_sha256};
 use anyhow::{Context, Result, anyhow};
 use reqwest::Client;
 use serde_json::json;
 use std::time::Duration;
 use tracing::debug;
 
+/// Path of the approval API endpoint Teams posts card actions to
+pub const ACTIONS_PATH: &str = "/api/v1/teams/actions";
+
+/// Response header whose value Teams shows under the card after an action
+pub const CARD_ACTION_STATUS_HEADER: &str = "CARD-ACTION-STATUS";
+
+/// Token authorizing one action (`approve` or `reject`) on one UpdateRequest: the hex
+/// HMAC-SHA256 of `action:namespace/name` with the action secret
+pub fn action_token(secret: &str, action: &str, namespace: &str, name: &str) -> String {
+    let message = format!("{}:{}/{}", action, namespace, name);
+    hex::encode(hmac_sha256(secret.as_bytes(), message.as_bytes()))
+}
+
+/// Check a token posted by a card action
+pub fn verify_action_token(
+    secret: &str,
+    action: &str,
+    namespace: &str,
+    name: &str,
+    token: &str,
+) -> bool {
+    let expected = action_token(secret, action, namespace, name);
+    constant_time_eq(expected.as_bytes(), token.trim().as_bytes())
+}
+
 pub struct TeamsNotifier {
     config: TeamsConfig,
     client: Client,
@@ -28,12 +55,17 @@ impl TeamsNotifier {
         Ok(Self { config, client })
     }
 
-    /// Build Microsoft Teams Adaptive Card
-    fn build_adaptive_card(&self, payload: &NotificationPayload) -> serde_json::Value {
-        let emoji = payload.event.emoji();
-        let color = payload.event.color();
-        let title = payload.title();
+    /// Build the message for a notification: an Adaptive Card with Approve/Reject buttons
+    /// when card actions are configured, otherwise a MessageCard linking to the approval API
+    fn build_card(&self, payload: &NotificationPayload) -> serde_json::Value {
+        match &self.config.action_secret {
+            Some(secret) => self.build_actionable_card(payload, secret),
+            None => self.build_adaptive_card(payload),
+        }
+    }
 
+    /// Facts listed on every card
+    fn facts(payload: &NotificationPayload) -> Vec<serde_json::Value> {
         let mut facts = vec![
             json!({
                 "title": "Namespace",
@@ -53,6 +85,14 @@ impl TeamsNotifier {
             }),
         ];
 
+        // Add cluster if present
+        if let Some(cluster) = &payload.cluster {
+            facts.push(json!({
+                "title": "Cluster",
+                "value": cluster
+            }));
+        }
+
         // Add policy if present
         if let Some(policy) = &payload.policy {
             facts.push(json!({
@@ -85,6 +125,28 @@ impl TeamsNotifier {
             }));
         }
 
+        // Add release notes link if present
+        if let Some(notes) = &payload.release_notes {
+            facts.push(json!({
+                "title": "Release Notes",
+                "value": format!(
+                    "[{}]({})",
+                    notes.title.as_deref().unwrap_or(&notes.url),
+                    notes.url
+                )
+            }));
+        }
+
+        facts
+    }
+
+    /// Build Microsoft Teams Adaptive Card
+    fn build_adaptive_card(&self, payload: &NotificationPayload) -> serde_json::Value {
+        let emoji = payload.event.emoji();
+        let color = payload.event.color();
+        let title = payload.title();
+        let facts = Self::facts(payload);
+
         let sections = vec![json!({
             "activityTitle": format!("{} {}", emoji, title),
             "activitySubtitle": format!("Event: {}", payload.event.as_str()),
@@ -134,34 +196,113 @@ impl TeamsNotifier {
 
         card
     }
+
+    /// Build an Adaptive Card whose Approve and Reject buttons post straight to the approval
+    /// API (`Action.Http`), each carrying a token for that one action
+    fn build_actionable_card(
+        &self,
+        payload: &NotificationPayload,
+        secret: &str,
+    ) -> serde_json::Value {
+        let mut body = vec![
+            json!({
+                "type": "TextBlock",
+                "text": format!("{} {}", payload.event.emoji(), payload.title()),
+                "weight": "Bolder",
+                "size": "Medium",
+                "wrap": true
+            }),
+            json!({
+                "type": "TextBlock",
+                "text": format!("Event: {}", payload.event.as_str()),
+                "isSubtle": true,
+                "spacing": "None"
+            }),
+            json!({
+                "type": "FactSet",
+                "facts": Self::facts(payload)
+            }),
+        ];
+
+        let mut actions = Vec::new();
+        if let Some(ui_url) = &payload.ui_url {
+            actions.push(json!({
+                "type": "Action.OpenUrl",
+                "title": "View in Dashboard",
+                "url": ui_url
+            }));
+        }
+
+        let pending_approval = payload.event == NotificationEvent::UpdateRequestCreated
+            && payload.requires_approval == Some(true);
+        if let (true, Some(name)) = (pending_approval, &payload.update_request_name) {
+            let namespace = &payload.deployment.namespace;
+            let url = format!("{}{}", super::api_url(), ACTIONS_PATH);
+            for (action, title) in [("approve", "Approve"), ("reject", "Reject")] {
+                let action_body = json!({
+                    "namespace": namespace,
+                    "name": name,
+                    "action": action,
+                    "token": action_token(secret, action, namespace, name),
+                });
+                actions.push(json!({
+                    "type": "Action.Http",
+                    "title": title,
+                    "method": "POST",
+                    "url": url,
+                    "headers": [{ "name": "Content-Type", "value": "application/json" }],
+                    "body": action_body.to_string()
+                }));
+            }
+        } else if let Some(approval_url) = &payload.approval_url {
+            body.push(json!({
+                "type": "TextBlock",
+                "text": format!("[Approve Update]({})", approval_url),
+                "wrap": true
+            }));
+        }
+
+        json!({
+            "type": "message",
+            "attachments": [{
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": {
+                    "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                    "type": "AdaptiveCard",
+                    "version": "1.4",
+                    "body": body,
+                    "actions": actions
+                }
+            }]
+        })
+    }
 }
 
 #[async_trait::async_trait]
 impl Notifier for TeamsNotifier {
-    async fn send(&self, payload: &NotificationPayload) -> Result<()> {
+    async fn send_routed(
+        &self,
+        payload: &NotificationPayload,
+        route: &NotificationRoute,
+    ) -> Result<()> {
         let webhook_url = self
-            .config
-            .webhook_url
-            .as_ref()
+            .destination(route)
             .ok_or_else(|| anyhow!("Teams webhook URL not configured"))?;
 
-        let card = self.build_adaptive_card(payload);
+        let card = self.build_card(payload);
 
         let response = self
             .client
-            .post(webhook_url)
+            .post(&webhook_url)
             .json(&card)
             .send()
             .await
             .context("Failed to send Teams notification")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response
-                .text()
+            return Err(StatusError::from_response("Teams API", response)
                 .await
-                .unwrap_or_else(|_| "Unable to read response".to_string());
-            return Err(anyhow!("Teams API returned error {}: {}", status, body));
+                .into());
         }
 
         debug!("Teams notification sent successfully");
@@ -172,6 +313,13 @@ impl Notifier for TeamsNotifier {
         "Microsoft Teams"
     }
 
+    fn destination(&self, route: &NotificationRoute) -> Option<String> {
+        route
+            .teams_webhook_url
+            .clone()
+            .or_else(|| self.config.webhook_url.clone())
+    }
+
     fn is_enabled(&self) -> bool {
         self.config.enabled && self.config.webhook_url.is_some()
     }
@@ -187,6 +335,7 @@ mod tests {
         let config = TeamsConfig {
             enabled: true,
             webhook_url: Some("https://outlook.office.com/webhook/test".to_string()),
+            action_secret: None,
         };
 
         let notifier = TeamsNotifier::new(config);
@@ -199,6 +348,7 @@ mod tests {
         let config = TeamsConfig {
             enabled: false,
             webhook_url: Some("https://outlook.office.com/webhook/test".to_string()),
+            action_secret: None,
         };
 
         let notifier = TeamsNotifier::new(config);
@@ -210,6 +360,7 @@ mod tests {
         let config = TeamsConfig {
             enabled: true,
             webhook_url: None,
+            action_secret: None,
         };
 
         let notifier = TeamsNotifier::new(config);
@@ -221,6 +372,7 @@ mod tests {
         let config = TeamsConfig {
             enabled: true,
             webhook_url: Some("https://outlook.office.com/webhook/test".to_string()),
+            action_secret: None,
         };
 
         let notifier = TeamsNotifier::new(config).unwrap();
@@ -252,6 +404,7 @@ mod tests {
         let config = TeamsConfig {
             enabled: true,
             webhook_url: Some("https://outlook.office.com/webhook/test".to_string()),
+            action_secret: None,
         };
 
         let notifier = TeamsNotifier::new(config).unwrap();
@@ -279,6 +432,7 @@ mod tests {
         let config = TeamsConfig {
             enabled: true,
             webhook_url: Some("https://outlook.office.com/webhook/test".to_string()),
+            action_secret: None,
         };
 
         let notifier = TeamsNotifier::new(config).unwrap();
@@ -301,4 +455,82 @@ mod tests {
         assert!(card_str.contains("admin@example.com"));
         assert!(card_str.contains("Approved By"));
     }
+
+    #[test]
+    fn test_build_actionable_card() {
+        let config = TeamsConfig {
+            enabled: true,
+            webhook_url: Some("https://outlook.office.com/webhook/test".to_string()),
+            action_secret: Some("s3cret".to_string()),
+        };
+
+        let notifier = TeamsNotifier::new(config).unwrap();
+
+        let deployment = DeploymentInfo {
+            name: "nginx".to_string(),
+            namespace: "production".to_string(),
+            current_image: "nginx:1.25.0".to_string(),
+            new_image: "nginx:1.26.0".to_string(),
+            container: None,
+            resource_kind: None,
+        };
+
+        let payload = NotificationPayload::new(NotificationEvent::UpdateRequestCreated, deployment)
+            .with_requires_approval(true)
+            .with_update_request("nginx-1-26-0");
+
+        let card = notifier.build_card(&payload);
+        let content = &card["attachments"][0]["content"];
+        assert_eq!(content["type"], "AdaptiveCard");
+
+        let actions = content["actions"].as_array().unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["type"], "Action.Http");
+        assert!(actions[0]["url"].as_str().unwrap().ends_with(ACTIONS_PATH));
+
+        let body: serde_json::Value =
+            serde_json::from_str(actions[1]["body"].as_str().unwrap()).unwrap();
+        assert_eq!(body["action"], "reject");
+        assert!(verify_action_token(
+            "s3cret",
+            "reject",
+            "production",
+            "nginx-1-26-0",
+            body["token"].as_str().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_action_token_is_bound_to_action_and_request() {
+        let token = action_token("s3cret", "reject", "production", "nginx-1-26-0");
+
+        assert!(verify_action_token(
+            "s3cret",
+            "reject",
+            "production",
+            "nginx-1-26-0",
+            &token
+        ));
+        assert!(!verify_action_token(
+            "s3cret",
+            "approve",
+            "production",
+            "nginx-1-26-0",
+            &token
+        ));
+        assert!(!verify_action_token(
+            "s3cret",
+            "reject",
+            "production",
+            "redis-7-4-0",
+            &token
+        ));
+        assert!(!verify_action_token(
+            "other",
+            "reject",
+            "production",
+            "nginx-1-26-0",
+            &token
+        ));
+    }
 }
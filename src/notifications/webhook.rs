@@ -1,9 +1,10 @@
-use super::{NotificationPayload, Notifier, WebhookConfig};
+use super::delivery::StatusError;
+use super::{NotificationPayload, NotificationRoute, Notifier, WebhookConfig};
 use anyhow::{Context, Result, anyhow};
 use reqwest::Client;
 use sha2::{Digest, Sha256};
 use std::time::Duration;
-use tracing::{debug, warn};
+use tracing::debug;
 
 pub struct WebhookNotifier {
     config: WebhookConfig,
@@ -39,74 +40,47 @@ impl WebhookNotifier {
         })
     }
 
-    /// Send webhook with retry logic
-    async fn send_with_retry(&self, payload: &NotificationPayload) -> Result<()> {
-        let url = self
-            .config
-            .url
-            .as_ref()
+    /// Send the webhook once. Failed sends are retried by the notification manager, up to
+    /// WEBHOOK_MAX_RETRIES times.
+    async fn send_to(&self, payload: &NotificationPayload, url: Option<&String>) -> Result<()> {
+        let url = url
+            .or(self.config.url.as_ref())
             .ok_or_else(|| anyhow!("Webhook URL not configured"))?;
 
         let body = serde_json::to_string(payload).context("Failed to serialize payload")?;
 
-        let mut last_error = None;
-        let mut backoff_ms = 1000u64; // Start with 1 second
-
-        for attempt in 0..=self.config.max_retries {
-            if attempt > 0 {
-                debug!(
-                    "Retrying webhook notification (attempt {}/{})",
-                    attempt, self.config.max_retries
-                );
-                tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
-                backoff_ms *= 2; // Exponential backoff
-            }
-
-            let mut request = self
-                .client
-                .post(url)
-                .header("Content-Type", "application/json");
-
-            // Add signature if secret is configured
-            if let Some(signature) = self.generate_signature(&body) {
-                request = request.header("X-Headwind-Signature", signature);
-            }
-
-            match request.body(body.clone()).send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        debug!("Webhook notification sent successfully to {}", url);
-                        return Ok(());
-                    } else {
-                        last_error = Some(anyhow!(
-                            "Webhook returned non-success status: {}",
-                            response.status()
-                        ));
-                        warn!(
-                            "Webhook returned status {}: {}",
-                            response.status(),
-                            response
-                                .text()
-                                .await
-                                .unwrap_or_else(|_| "Unable to read response".to_string())
-                        );
-                    }
-                },
-                Err(e) => {
-                    last_error = Some(anyhow!("HTTP request failed: {}", e));
-                    warn!("Failed to send webhook notification: {}", e);
-                },
-            }
+        let mut request = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json");
+
+        // Add signature if secret is configured
+        if let Some(signature) = self.generate_signature(&body) {
+            request = request.header("X-Headwind-Signature", signature);
+        }
+
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| anyhow!("HTTP request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(StatusError::from_response("Webhook", response).await.into());
         }
 
-        Err(last_error.unwrap_or_else(|| anyhow!("Webhook notification failed after all retries")))
+        debug!("Webhook notification sent successfully to {}", url);
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
 impl Notifier for WebhookNotifier {
-    async fn send(&self, payload: &NotificationPayload) -> Result<()> {
-        self.send_with_retry(payload).await
+    async fn send_routed(
+        &self,
+        payload: &NotificationPayload,
+        route: &NotificationRoute,
+    ) -> Result<()> {
+        self.send_to(payload, route.webhook_url.as_ref()).await
     }
 
     fn name(&self) -> &'static str {
@@ -116,6 +90,17 @@ impl Notifier for WebhookNotifier {
     fn is_enabled(&self) -> bool {
         self.config.enabled && self.config.url.is_some()
     }
+
+    fn max_retries(&self) -> Option<u32> {
+        Some(self.config.max_retries)
+    }
+
+    fn destination(&self, route: &NotificationRoute) -> Option<String> {
+        route
+            .webhook_url
+            .clone()
+            .or_else(|| self.config.url.clone())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,882 @@
+//! OpenAPI 3 documents of the approval API (port 8081) and the Web UI API (port 8082), served
+//! by both servers at `/api/v1/openapi.json`.
+//!
+//! Schemas are generated with schemars from the types the handlers read and return, so they
+//! follow the code. The operations themselves are listed here and have to be kept in step
+//! with the routers in `approval::router()` and `ui::create_router()`.
+
+use crate::approval::{
+    BatchRequest, BatchResponse, LastGoodRollbackRequest, PolicyEvaluationRequest,
+    PolicyEvaluationResponse, RollbackQuery, RollbackRequest, RollbackResponse,
+    SimpleApprovalRequest, SimpleRejectionRequest, TeamsAction, UpdatesQuery,
+};
+use crate::audit::AuditQuery;
+use crate::config::HeadwindConfig;
+use crate::lint::{LintQuery, LintReport};
+use crate::models::audit::UpdateAudit;
+use crate::models::crd::UpdateRequest;
+use crate::polling::PollCycleSummary;
+use crate::rollback::{UpdateHistory, UpdateHistoryEntry};
+use crate::ui::graphql::{GraphQLRequest, GraphQLResponse};
+use crate::ui::hub::ClusterQuery;
+use crate::ui::routes::{
+    ApprovalForm, BulkApproveRequest, BulkOperationResponse, BulkRejectRequest,
+    LastGoodRollbackForm, RejectionForm, TimeseriesQuery,
+};
+use axum::Json;
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, SchemaGenerator};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// Path both servers serve their document at
+pub const OPENAPI_PATH: &str = "/api/v1/openapi.json";
+
+/// Error body of failed requests. Some endpoints add details next to `error`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+/// OpenAPI document under construction
+struct Document {
+    generator: SchemaGenerator,
+    paths: Map<String, Value>,
+}
+
+impl Document {
+    fn new() -> Self {
+        Self {
+            generator: SchemaSettings::openapi3().into_generator(),
+            paths: Map::new(),
+        }
+    }
+
+    /// Reference to the schema of `T`, registered under `components.schemas`
+    fn schema<T: JsonSchema>(&mut self) -> Value {
+        self.generator.subschema_for::<T>().to_value()
+    }
+
+    /// Query parameters for the fields of `T`, all optional
+    fn query<T: JsonSchema>(&mut self) -> Vec<Value> {
+        let mut generator = SchemaSettings::openapi3()
+            .with(|s| s.inline_subschemas = true)
+            .into_generator();
+        let schema = generator.root_schema_for::<T>().to_value();
+        let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+            return Vec::new();
+        };
+
+        properties
+            .iter()
+            .map(|(name, property)| {
+                let mut property = property.clone();
+                let description = property
+                    .as_object_mut()
+                    .and_then(|p| p.remove("description"))
+                    .unwrap_or(Value::Null);
+                let mut parameter = json!({
+                    "name": name,
+                    "in": "query",
+                    "required": false,
+                    "schema": property,
+                });
+                if !description.is_null() {
+                    parameter["description"] = description;
+                }
+                parameter
+            })
+            .collect()
+    }
+
+    /// JSON response with the schema of `T`
+    fn json<T: JsonSchema>(&mut self, description: &str) -> Value {
+        let schema = self.schema::<T>();
+        json_content(description, schema)
+    }
+
+    /// JSON response with a list of `T`
+    fn json_list<T: JsonSchema>(&mut self, description: &str) -> Value {
+        let schema = self.schema::<T>();
+        json_content(description, json!({ "type": "array", "items": schema }))
+    }
+
+    /// Error response
+    fn error(&mut self, description: &str) -> Value {
+        self.json::<ErrorBody>(description)
+    }
+
+    /// JSON request body with the schema of `T`
+    fn body<T: JsonSchema>(&mut self) -> Value {
+        let schema = self.schema::<T>();
+        json!({
+            "required": true,
+            "content": { "application/json": { "schema": schema } }
+        })
+    }
+
+    /// Form request body with the schema of `T`, as sent by the Web UI
+    fn form<T: JsonSchema>(&mut self) -> Value {
+        let schema = self.schema::<T>();
+        json!({
+            "required": true,
+            "content": { "application/x-www-form-urlencoded": { "schema": schema } }
+        })
+    }
+
+    fn add(&mut self, method: &str, path: &str, operation: Value) {
+        let item = self
+            .paths
+            .entry(path.to_string())
+            .or_insert_with(|| json!({}));
+        item[method] = operation;
+    }
+
+    /// The common `/health` and OpenAPI operations
+    fn add_common(&mut self) {
+        self.add(
+            "get",
+            "/health",
+            json!({
+                "operationId": "health",
+                "summary": "Liveness check",
+                "responses": { "200": { "description": "The server is running" } }
+            }),
+        );
+        self.add(
+            "get",
+            OPENAPI_PATH,
+            json!({
+                "operationId": "openapi",
+                "summary": "This OpenAPI document",
+                "responses": { "200": json_content("OpenAPI 3 document", json!({ "type": "object" })) }
+            }),
+        );
+    }
+
+    fn finish(mut self, title: &str, description: &str) -> Value {
+        let schemas = self.generator.take_definitions(true);
+        json!({
+            "openapi": "3.0.3",
+            "info": {
+                "title": title,
+                "description": description,
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "paths": self.paths,
+            "components": { "schemas": schemas },
+        })
+    }
+}
+
+fn json_content(description: &str, schema: Value) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema } }
+    })
+}
+
+fn path_params(names: &[&str]) -> Vec<Value> {
+    names
+        .iter()
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect()
+}
+
+/// Operations of the namespaced approval API, authorized per namespace with the caller's
+/// Kubernetes token
+fn add_namespaced_updates(doc: &mut Document) {
+    let namespace_path = path_params(&["namespace"]);
+    let update_path = path_params(&["namespace", "name"]);
+    let unauthorized = doc.error("Missing or invalid bearer token");
+    let forbidden = doc.error("RBAC doesn't allow the operation in the namespace");
+
+    let mut parameters = namespace_path;
+    parameters.extend(
+        doc.query::<UpdatesQuery>()
+            .into_iter()
+            .filter(|p| p["name"] != "namespace"),
+    );
+    let ok = doc.json_list::<UpdateRequest>(
+        "UpdateRequests of the namespace. `X-Total-Count` and `X-Continue-Token` as for listUpdates.",
+    );
+    doc.add(
+        "get",
+        "/api/v1/namespaces/{namespace}/updates",
+        json!({
+            "operationId": "listNamespacedUpdates",
+            "summary": "List UpdateRequests of a namespace (needs `list` on `updaterequests`)",
+            "tags": ["updates"],
+            "parameters": parameters,
+            "responses": { "200": ok, "401": unauthorized, "403": forbidden }
+        }),
+    );
+
+    let ok = doc.json::<UpdateRequest>("The UpdateRequest");
+    doc.add(
+        "get",
+        "/api/v1/namespaces/{namespace}/updates/{name}",
+        json!({
+            "operationId": "getNamespacedUpdate",
+            "summary": "Get an UpdateRequest (needs `get` on `updaterequests`)",
+            "tags": ["updates"],
+            "parameters": update_path,
+            "responses": {
+                "200": ok,
+                "401": unauthorized,
+                "403": forbidden,
+                "404": { "description": "UpdateRequest not found" },
+            }
+        }),
+    );
+
+    for (action, operation_id, summary) in [
+        (
+            "approve",
+            "approveNamespacedUpdate",
+            "Approve an UpdateRequest",
+        ),
+        (
+            "reject",
+            "rejectNamespacedUpdate",
+            "Reject an UpdateRequest",
+        ),
+    ] {
+        let body = if action == "approve" {
+            doc.body::<SimpleApprovalRequest>()
+        } else {
+            doc.body::<SimpleRejectionRequest>()
+        };
+        let ok = doc.json::<UpdateRequest>("As for the cluster-wide operation");
+        let not_found = doc.error("UpdateRequest not found");
+        let conflict =
+            doc.error("The UpdateRequest can't be decided on (see the cluster-wide operation)");
+        doc.add(
+            "post",
+            &format!("/api/v1/namespaces/{{namespace}}/updates/{{name}}/{}", action),
+            json!({
+                "operationId": operation_id,
+                "summary": format!("{} as the authenticated user (needs `update` on `updaterequests/approval`)", summary),
+                "tags": ["updates"],
+                "parameters": update_path,
+                "requestBody": body,
+                "responses": {
+                    "200": ok,
+                    "401": unauthorized,
+                    "403": forbidden,
+                    "404": not_found,
+                    "409": conflict,
+                }
+            }),
+        );
+    }
+}
+
+/// OpenAPI document of the approval API
+pub fn approval_api() -> Value {
+    let mut doc = Document::new();
+    let update_path = path_params(&["namespace", "name"]);
+    let rollback_path = path_params(&["namespace", "deployment"]);
+
+    let parameters = doc.query::<UpdatesQuery>();
+    let ok = doc.json_list::<UpdateRequest>(
+        "UpdateRequests of the page. `X-Total-Count` holds the number of matching requests, `X-Continue-Token` the token of the next page.",
+    );
+    doc.add(
+        "get",
+        "/api/v1/updates",
+        json!({
+            "operationId": "listUpdates",
+            "summary": "List UpdateRequests",
+            "tags": ["updates"],
+            "parameters": parameters,
+            "responses": {
+                "200": ok,
+                "400": { "description": "Invalid label selector" },
+            }
+        }),
+    );
+
+    let body = doc.body::<BatchRequest>();
+    let ok = doc.json::<BatchResponse>("Outcome of every UpdateRequest of the batch");
+    let bad_request = doc.error("Neither or both of `items` and `selector` were given");
+    doc.add(
+        "post",
+        "/api/v1/updates/batch",
+        json!({
+            "operationId": "batchUpdates",
+            "summary": "Approve or reject several UpdateRequests",
+            "tags": ["updates"],
+            "requestBody": body,
+            "responses": { "200": ok, "400": bad_request }
+        }),
+    );
+
+    let ok = doc.json::<UpdateRequest>("The UpdateRequest");
+    doc.add(
+        "get",
+        "/api/v1/updates/{namespace}/{name}",
+        json!({
+            "operationId": "getUpdate",
+            "summary": "Get an UpdateRequest",
+            "tags": ["updates"],
+            "parameters": update_path,
+            "responses": {
+                "200": ok,
+                "404": { "description": "UpdateRequest not found" },
+            }
+        }),
+    );
+
+    let body = doc.body::<SimpleApprovalRequest>();
+    let ok = doc.json::<UpdateRequest>("Approved and applied (or dry run completed)");
+    let accepted = doc.json::<UpdateRequest>(
+        "Approval recorded: more approvals are required, a canary is running or the update waits for its rollout order",
+    );
+    let not_found = doc.error("UpdateRequest not found");
+    let conflict = doc.error("Not Pending, expired, gated, or already approved by this approver");
+    let precondition = doc.error("`expectedNewImage` or `expectedCurrentImage` no longer match");
+    doc.add(
+        "post",
+        "/api/v1/updates/{namespace}/{name}/approve",
+        json!({
+            "operationId": "approveUpdate",
+            "summary": "Approve an UpdateRequest",
+            "tags": ["updates"],
+            "parameters": update_path,
+            "requestBody": body,
+            "responses": {
+                "200": ok,
+                "202": accepted,
+                "404": not_found,
+                "409": conflict,
+                "412": precondition,
+            }
+        }),
+    );
+
+    let body = doc.body::<SimpleRejectionRequest>();
+    let ok = doc.json::<UpdateRequest>("Rejected");
+    let not_found = doc.error("UpdateRequest not found");
+    let conflict = doc.error("UpdateRequest is not Pending");
+    doc.add(
+        "post",
+        "/api/v1/updates/{namespace}/{name}/reject",
+        json!({
+            "operationId": "rejectUpdate",
+            "summary": "Reject an UpdateRequest",
+            "tags": ["updates"],
+            "parameters": update_path,
+            "requestBody": body,
+            "responses": { "200": ok, "404": not_found, "409": conflict }
+        }),
+    );
+
+    add_namespaced_updates(&mut doc);
+
+    let mut parameters = rollback_path.clone();
+    parameters.extend(doc.query::<RollbackQuery>());
+    let ok = doc.json::<UpdateHistory>("Update history of the Deployment, newest first");
+    doc.add(
+        "get",
+        "/api/v1/rollback/{namespace}/{deployment}",
+        json!({
+            "operationId": "getRollbackHistory",
+            "summary": "Update history of a Deployment",
+            "tags": ["rollback"],
+            "parameters": parameters,
+            "responses": {
+                "200": ok,
+                "500": { "description": "Deployment or history couldn't be read" },
+            }
+        }),
+    );
+
+    let body = doc.body::<RollbackRequest>();
+    let ok = doc.json::<RollbackResponse>("Rolled back");
+    let not_found = doc.error("No history entry at the index");
+    let failed = doc.error("Rollback failed");
+    doc.add(
+        "post",
+        "/api/v1/rollback/{namespace}/{deployment}",
+        json!({
+            "operationId": "rollbackDeployment",
+            "summary": "Roll a Deployment container back to an earlier image",
+            "tags": ["rollback"],
+            "parameters": rollback_path.clone(),
+            "requestBody": body,
+            "responses": { "200": ok, "404": not_found, "500": failed }
+        }),
+    );
+
+    let mut parameters = rollback_path.clone();
+    parameters.extend(doc.query::<RollbackQuery>());
+    let ok = doc.json_list::<UpdateHistoryEntry>(
+        "Newest entry of each container that passed health monitoring",
+    );
+    doc.add(
+        "get",
+        "/api/v1/rollback/{namespace}/{deployment}/last-good",
+        json!({
+            "operationId": "getLastKnownGood",
+            "summary": "Last known good images of a Deployment",
+            "tags": ["rollback"],
+            "parameters": parameters,
+            "responses": {
+                "200": ok,
+                "500": { "description": "Deployment or history couldn't be read" },
+            }
+        }),
+    );
+
+    let body = doc.body::<LastGoodRollbackRequest>();
+    let ok = doc.json::<RollbackResponse>("Rolled back");
+    let not_found = doc.error("No last known good image recorded for the container");
+    let failed = doc.error("Rollback failed");
+    doc.add(
+        "post",
+        "/api/v1/rollback/{namespace}/{deployment}/last-good",
+        json!({
+            "operationId": "rollbackToLastKnownGood",
+            "summary": "Roll a Deployment container back to its last known good image",
+            "tags": ["rollback"],
+            "parameters": rollback_path,
+            "requestBody": body,
+            "responses": { "200": ok, "404": not_found, "500": failed }
+        }),
+    );
+
+    let parameters = doc.query::<AuditQuery>();
+    let ok = doc.json_list::<UpdateAudit>("Audit records, newest first");
+    doc.add(
+        "get",
+        "/api/v1/audit",
+        json!({
+            "operationId": "listAudit",
+            "summary": "List audit records",
+            "tags": ["audit"],
+            "parameters": parameters,
+            "responses": { "200": ok }
+        }),
+    );
+
+    let body = doc.body::<PolicyEvaluationRequest>();
+    let ok = doc.json::<PolicyEvaluationResponse>("Decision and the rule that made it");
+    let bad_request = doc.error("Invalid policy annotations, or versions the policy can't compare");
+    doc.add(
+        "post",
+        "/api/v1/policy/evaluate",
+        json!({
+            "operationId": "evaluatePolicy",
+            "summary": "Explain whether a policy would update from one version to another",
+            "tags": ["policy"],
+            "requestBody": body,
+            "responses": { "200": ok, "400": bad_request }
+        }),
+    );
+
+    let parameters = doc.query::<LintQuery>();
+    let ok = doc.json::<LintReport>("Invalid and conflicting annotations");
+    let failed = doc.error("Listing the resources failed");
+    doc.add(
+        "get",
+        "/api/v1/lint",
+        json!({
+            "operationId": "lintAnnotations",
+            "summary": "Check the headwind.sh annotations of workloads and HelmReleases",
+            "tags": ["policy"],
+            "parameters": parameters,
+            "responses": { "200": ok, "500": failed }
+        }),
+    );
+
+    let body = doc.body::<TeamsAction>();
+    doc.add(
+        "post",
+        crate::notifications::ACTIONS_PATH,
+        json!({
+            "operationId": "teamsAction",
+            "summary": "Approve or reject from a Microsoft Teams card",
+            "tags": ["integrations"],
+            "requestBody": body,
+            "responses": {
+                "200": { "description": "Adaptive Card with the outcome" },
+                "401": { "description": "Invalid action token" },
+            }
+        }),
+    );
+
+    doc.add_common();
+    doc.finish(
+        "Headwind approval API",
+        "Approve, reject and inspect UpdateRequests, roll Deployments back, read the audit trail, evaluate policies and lint annotations",
+    )
+}
+
+/// OpenAPI document of the Web UI API. The HTML pages are not part of it.
+pub fn ui_api() -> Value {
+    let mut doc = Document::new();
+    let update_path = path_params(&["namespace", "name"]);
+    let any = json!({ "type": "object" });
+
+    let ok = doc.json::<HeadwindConfig>("Current settings");
+    doc.add(
+        "get",
+        "/api/v1/settings",
+        json!({
+            "operationId": "getSettings",
+            "summary": "Read the settings from the headwind-config ConfigMap and Secret",
+            "tags": ["settings"],
+            "responses": { "200": ok }
+        }),
+    );
+
+    let body = doc.body::<HeadwindConfig>();
+    let failed = doc.error("Settings couldn't be saved");
+    doc.add(
+        "put",
+        "/api/v1/settings",
+        json!({
+            "operationId": "updateSettings",
+            "summary": "Save the settings",
+            "tags": ["settings"],
+            "requestBody": body,
+            "responses": {
+                "200": json_content("Saved", any.clone()),
+                "500": failed,
+            }
+        }),
+    );
+
+    doc.add(
+        "get",
+        "/api/v1/settings/validate",
+        json!({
+            "operationId": "validateSettings",
+            "summary": "Validate the ConfigMap, Secret and environment configuration",
+            "tags": ["settings"],
+            "responses": { "200": json_content("Validation report", any.clone()) }
+        }),
+    );
+
+    doc.add(
+        "post",
+        "/api/v1/settings/test-notification",
+        json!({
+            "operationId": "testNotification",
+            "summary": "Send a test notification",
+            "tags": ["settings"],
+            "requestBody": {
+                "required": true,
+                "content": { "application/json": { "schema": any.clone() } }
+            },
+            "responses": { "200": json_content("Notification sent", any.clone()) }
+        }),
+    );
+
+    doc.add(
+        "get",
+        "/api/v1/metrics",
+        json!({
+            "operationId": "getMetrics",
+            "summary": "Current values of Headwind's metrics",
+            "tags": ["observability"],
+            "responses": { "200": json_content("Metric values", any.clone()) }
+        }),
+    );
+
+    let mut parameters = path_params(&["metric_name"]);
+    parameters.extend(doc.query::<TimeseriesQuery>());
+    doc.add(
+        "get",
+        "/api/v1/metrics/timeseries/{metric_name}",
+        json!({
+            "operationId": "getMetricsTimeseries",
+            "summary": "Time series of a Headwind metric from the metrics backend, added up across its labels",
+            "tags": ["observability"],
+            "parameters": parameters,
+            "responses": {
+                "200": json_content("Data points", any.clone()),
+                "400": { "description": "Not a Headwind metric" }
+            }
+        }),
+    );
+
+    let ok = doc.json::<PollCycleSummary>("Summary of the last poll cycle");
+    let not_found = doc.error("No poll cycle has completed yet");
+    doc.add(
+        "get",
+        "/api/v1/polling/last-cycle",
+        json!({
+            "operationId": "getLastPollCycle",
+            "summary": "Summary of the last registry poll cycle",
+            "tags": ["observability"],
+            "responses": { "200": ok, "404": not_found }
+        }),
+    );
+
+    doc.add(
+        "get",
+        "/api/v1/debug/caches",
+        json!({
+            "operationId": "getCacheStats",
+            "summary": "Entry counts, limits and evictions of the internal caches",
+            "tags": ["observability"],
+            "responses": { "200": json_content("Cache statistics", any.clone()) }
+        }),
+    );
+
+    doc.add(
+        "get",
+        "/api/v1/updates",
+        json!({
+            "operationId": "listUpdateSummaries",
+            "summary": "Namespace, name and phase of every UpdateRequest",
+            "tags": ["updates"],
+            "responses": {
+                "200": json_content("UpdateRequest summaries", json!({
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "namespace": { "type": "string" },
+                            "name": { "type": "string" },
+                            "status": {
+                                "type": "object",
+                                "nullable": true,
+                                "properties": {
+                                    "phase": { "type": "string" },
+                                    "approvedBy": { "type": "string", "nullable": true },
+                                },
+                            },
+                        },
+                    },
+                })),
+            }
+        }),
+    );
+
+    let mut update_params = update_path;
+    update_params.extend(doc.query::<ClusterQuery>());
+    let body = doc.form::<ApprovalForm>();
+    doc.add(
+        "post",
+        "/api/v1/updates/{namespace}/{name}/approve",
+        json!({
+            "operationId": "approveUpdate",
+            "summary": "Approve an UpdateRequest as the signed-in user (forwarded to the approval API)",
+            "tags": ["updates"],
+            "parameters": update_params,
+            "requestBody": body,
+            "responses": { "200": { "description": "HTML fragment with the outcome" } }
+        }),
+    );
+
+    let body = doc.form::<RejectionForm>();
+    doc.add(
+        "post",
+        "/api/v1/updates/{namespace}/{name}/reject",
+        json!({
+            "operationId": "rejectUpdate",
+            "summary": "Reject an UpdateRequest as the signed-in user (forwarded to the approval API)",
+            "tags": ["updates"],
+            "parameters": update_params,
+            "requestBody": body,
+            "responses": { "200": { "description": "HTML fragment with the outcome" } }
+        }),
+    );
+
+    let body = doc.form::<LastGoodRollbackForm>();
+    let ok = doc.json::<RollbackResponse>("Rolled back");
+    let not_found = doc.error("No last known good image recorded for the container");
+    doc.add(
+        "post",
+        "/api/v1/rollback/{namespace}/{deployment}/last-good",
+        json!({
+            "operationId": "rollbackToLastKnownGood",
+            "summary": "Roll a Deployment container back to its last known good image as the signed-in user (forwarded to the approval API)",
+            "tags": ["rollback"],
+            "parameters": path_params(&["namespace", "deployment"]),
+            "requestBody": body,
+            "responses": { "200": ok, "404": not_found }
+        }),
+    );
+
+    let body = doc.body::<BulkApproveRequest>();
+    let ok = doc.json::<BulkOperationResponse>("Number of approved and failed UpdateRequests");
+    doc.add(
+        "post",
+        "/api/v1/updates/bulk/approve",
+        json!({
+            "operationId": "bulkApprove",
+            "summary": "Approve several UpdateRequests",
+            "tags": ["updates"],
+            "requestBody": body,
+            "responses": { "200": ok }
+        }),
+    );
+
+    let body = doc.body::<BulkRejectRequest>();
+    let ok = doc.json::<BulkOperationResponse>("Number of rejected and failed UpdateRequests");
+    doc.add(
+        "post",
+        "/api/v1/updates/bulk/reject",
+        json!({
+            "operationId": "bulkReject",
+            "summary": "Reject several UpdateRequests",
+            "tags": ["updates"],
+            "requestBody": body,
+            "responses": { "200": ok }
+        }),
+    );
+
+    doc.add(
+        "get",
+        "/api/v1/events/updates",
+        json!({
+            "operationId": "updatesStream",
+            "summary": "Server-Sent Events announcing UpdateRequest changes",
+            "tags": ["updates"],
+            "responses": {
+                "200": {
+                    "description": "Event stream",
+                    "content": { "text/event-stream": { "schema": { "type": "string" } } }
+                }
+            }
+        }),
+    );
+
+    let body = doc.body::<GraphQLRequest>();
+    let ok = doc.json::<GraphQLResponse>(
+        "Query result. Failing fields are null with an entry in `errors`.",
+    );
+    let bad_request = doc.json::<GraphQLResponse>("The query couldn't be parsed");
+    doc.add(
+        "post",
+        "/api/v1/graphql",
+        json!({
+            "operationId": "graphql",
+            "summary": "Query UpdateRequests, rollback history and tracked resources (only with HEADWIND_GRAPHQL_ENABLED=true)",
+            "tags": ["graphql"],
+            "requestBody": body,
+            "responses": { "200": ok, "400": bad_request }
+        }),
+    );
+    let parameters = doc.query::<GraphQLRequest>();
+    let ok = doc.json::<GraphQLResponse>("Query result");
+    let bad_request = doc.json::<GraphQLResponse>("The query couldn't be parsed");
+    doc.add(
+        "get",
+        "/api/v1/graphql",
+        json!({
+            "operationId": "graphqlGet",
+            "summary": "GraphQL query in query parameters (only with HEADWIND_GRAPHQL_ENABLED=true)",
+            "tags": ["graphql"],
+            "parameters": parameters,
+            "responses": { "200": ok, "400": bad_request }
+        }),
+    );
+
+    doc.add_common();
+    doc.finish(
+        "Headwind Web UI API",
+        "Settings, observability data and UpdateRequest actions used by the Web UI",
+    )
+}
+
+/// Serve the approval API document
+pub async fn serve_approval_api() -> Json<Value> {
+    Json(approval_api())
+}
+
+/// Serve the Web UI API document
+pub async fn serve_ui_api() -> Json<Value> {
+    Json(ui_api())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every `$ref` of a document
+    fn refs(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(reference)) => found.push(reference.clone()),
+                        _ => refs(value, found),
+                    }
+                }
+            },
+            Value::Array(items) => items.iter().for_each(|item| refs(item, found)),
+            _ => {},
+        }
+    }
+
+    fn assert_refs_resolve(doc: &Value) {
+        let mut found = Vec::new();
+        refs(doc, &mut found);
+        assert!(!found.is_empty());
+        for reference in found {
+            let name = reference
+                .strip_prefix("#/components/schemas/")
+                .unwrap_or_else(|| panic!("unexpected reference {}", reference));
+            assert!(
+                doc["components"]["schemas"].get(name).is_some(),
+                "missing schema {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_approval_api_document() {
+        let doc = approval_api();
+        assert_eq!(doc["openapi"], "3.0.3");
+        assert_eq!(
+            doc["paths"]["/api/v1/updates/{namespace}/{name}/approve"]["post"]["operationId"],
+            "approveUpdate"
+        );
+        assert!(doc["paths"][OPENAPI_PATH]["get"].is_object());
+        assert!(doc["components"]["schemas"]["UpdateRequest"].is_object());
+        assert!(doc["paths"]["/api/v1/policy/evaluate"]["post"].is_object());
+        assert!(doc["paths"]["/api/v1/lint"]["get"].is_object());
+        assert_eq!(
+            doc["paths"]["/api/v1/namespaces/{namespace}/updates/{name}/approve"]["post"]["operationId"],
+            "approveNamespacedUpdate"
+        );
+        assert_refs_resolve(&doc);
+    }
+
+    #[test]
+    fn test_query_parameters() {
+        let doc = approval_api();
+        let parameters = doc["paths"]["/api/v1/updates"]["get"]["parameters"]
+            .as_array()
+            .unwrap();
+        let names: Vec<&str> = parameters
+            .iter()
+            .filter_map(|p| p["name"].as_str())
+            .collect();
+        assert!(names.contains(&"namespace"));
+        assert!(names.contains(&"labelSelector"));
+        assert!(names.contains(&"continue"));
+        assert!(parameters.iter().all(|p| p["in"] == "query"));
+    }
+
+    #[test]
+    fn test_ui_api_document() {
+        let doc = ui_api();
+        assert!(doc["paths"]["/api/v1/settings"]["put"].is_object());
+        assert!(doc["components"]["schemas"]["HeadwindConfig"].is_object());
+        assert_refs_resolve(&doc);
+    }
+}
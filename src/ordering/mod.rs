@@ -0,0 +1,469 @@
+//! Rollout ordering between workloads.
+//!
+//! `headwind.sh/rollout-after` lists the workloads (`[Kind/][namespace/]name`, comma-separated)
+//! a workload is only updated after. While one of them has an UpdateRequest waiting for
+//! approval or being rolled out, or hasn't finished rolling out, updates of the workload are
+//! held back:
+//!
+//! - Approved UpdateRequests stay Approved with `status.waitingFor` set and are applied by the
+//!   ordering loop once their dependencies settled. They survive restarts.
+//! - Automatic updates are queued in memory, like updates waiting for a maintenance window;
+//!   after a restart they are queued again when they are next detected.
+
+use crate::audit;
+use crate::config::namespaces;
+use crate::controller::tracked::fetch_rolled_out;
+use crate::controller::workload::WorkloadImageUpdate;
+use crate::metrics::{ORDERED_UPDATES_PENDING, UPDATES_HELD_FOR_ORDER};
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
+use crate::models::crd::{UpdatePhase, UpdateRequest, UpdateRequestSpec};
+use crate::models::policy::annotations;
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use kube::api::{Api, ListParams};
+use kube::{Client, ResourceExt};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// How often held updates are checked against their dependencies
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A workload another workload is rolled out after
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+}
+
+impl Dependency {
+    /// Parse `[Kind/][namespace/]name`. The kind defaults to Deployment and the namespace to
+    /// the namespace of the annotated workload.
+    pub fn parse(value: &str, namespace: &str) -> Result<Self> {
+        let parts: Vec<&str> = value.trim().split('/').map(str::trim).collect();
+        let (kind, namespace, name) = match parts.as_slice() {
+            [name] => ("Deployment", namespace, *name),
+            [namespace, name] => ("Deployment", *namespace, *name),
+            [kind, namespace, name] => (*kind, *namespace, *name),
+            _ => bail!("expected [Kind/][namespace/]name, got '{}'", value),
+        };
+        if namespace.is_empty() || name.is_empty() {
+            return Err(anyhow!("expected [Kind/][namespace/]name, got '{}'", value));
+        }
+
+        Ok(Self {
+            kind: normalize_kind(kind)?,
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    fn is_target_of(&self, spec: &UpdateRequestSpec) -> bool {
+        spec.target_ref.kind == self.kind
+            && spec.target_ref.namespace == self.namespace
+            && spec.target_ref.name == self.name
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}/{}", self.kind, self.namespace, self.name)
+    }
+}
+
+/// Accept kinds in any case (`deployment`, `StatefulSet`)
+fn normalize_kind(kind: &str) -> Result<String> {
+    const KINDS: [&str; 8] = [
+        "Deployment",
+        "StatefulSet",
+        "DaemonSet",
+        "CronJob",
+        "HelmRelease",
+        "Application",
+        "Kustomization",
+        "Release",
+    ];
+    KINDS
+        .iter()
+        .find(|k| k.eq_ignore_ascii_case(kind))
+        .map(|k| k.to_string())
+        .ok_or_else(|| anyhow!("unsupported kind '{}'", kind))
+}
+
+/// Parse the `headwind.sh/rollout-after` annotation of a workload in `namespace`
+pub fn parse_dependencies(
+    annotations: &BTreeMap<String, String>,
+    namespace: &str,
+) -> Result<Vec<Dependency>> {
+    annotations
+        .get(annotations::ROLLOUT_AFTER)
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| Dependency::parse(entry, namespace))
+        .collect()
+}
+
+/// Why an update of a workload in `namespace` with `annotations` can't be applied yet: the
+/// first workload of its rollout order that has an open UpdateRequest or is still rolling
+/// out. None when the update may proceed.
+pub async fn blocking_dependency(
+    client: &Client,
+    namespace: &str,
+    annotations: &BTreeMap<String, String>,
+) -> Option<String> {
+    let dependencies = match parse_dependencies(annotations, namespace) {
+        Ok(dependencies) => dependencies,
+        Err(e) => {
+            return Some(format!(
+                "invalid {} annotation: {}",
+                annotations::ROLLOUT_AFTER,
+                e
+            ));
+        },
+    };
+
+    for dependency in &dependencies {
+        match check_dependency(client, dependency).await {
+            Ok(None) => {},
+            Ok(Some(reason)) => return Some(reason),
+            Err(e) => return Some(format!("failed to check {}: {}", dependency, e)),
+        }
+    }
+    None
+}
+
+async fn check_dependency(client: &Client, dependency: &Dependency) -> Result<Option<String>> {
+    let update_requests: Api<UpdateRequest> =
+        Api::namespaced(client.clone(), &dependency.namespace);
+    let open = update_requests
+        .list(&ListParams::default())
+        .await?
+        .items
+        .into_iter()
+        .find(|ur| dependency.is_target_of(&ur.spec) && is_open(ur, Utc::now()));
+    if let Some(ur) = open {
+        return Ok(Some(format!(
+            "waiting for {} to finish its update ({})",
+            dependency,
+            ur.name_any()
+        )));
+    }
+
+    let (namespace, name) = (&dependency.namespace, &dependency.name);
+    let rolled_out = match dependency.kind.as_str() {
+        "Deployment" => fetch_rolled_out::<Deployment>(client, namespace, name).await?,
+        "StatefulSet" => fetch_rolled_out::<StatefulSet>(client, namespace, name).await?,
+        "DaemonSet" => fetch_rolled_out::<DaemonSet>(client, namespace, name).await?,
+        // Other kinds are only ordered by their UpdateRequests
+        _ => true,
+    };
+    Ok((!rolled_out).then(|| format!("waiting for {} to finish rolling out", dependency)))
+}
+
+/// Whether an UpdateRequest still waits for approval or is being applied
+fn is_open(update_request: &UpdateRequest, now: DateTime<Utc>) -> bool {
+    let phase = update_request
+        .status
+        .as_ref()
+        .map(|s| s.phase.clone())
+        .unwrap_or_default();
+    match phase {
+        UpdatePhase::Pending => update_request
+            .spec
+            .expires_at
+            .is_none_or(|expires_at| expires_at > now),
+        UpdatePhase::Approved | UpdatePhase::Deferred => true,
+        _ => false,
+    }
+}
+
+/// An automatic update waiting for the workloads it is ordered after
+#[derive(Debug, Clone)]
+pub struct OrderedUpdate {
+    pub spec: UpdateRequestSpec,
+    pub annotations: BTreeMap<String, String>,
+    pub reason: String,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// Queued automatic updates keyed by target and container, so a newer detection replaces an
+/// older one
+static PENDING: once_cell::sync::Lazy<Mutex<BTreeMap<String, OrderedUpdate>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Check a workload's rollout order before applying an automatic update. Returns true when
+/// the update was queued until the workloads it is ordered after finished their updates.
+pub async fn defer_until_ordered(
+    client: &Client,
+    annotations: &BTreeMap<String, String>,
+    update: &WorkloadImageUpdate<'_>,
+) -> bool {
+    if !annotations.contains_key(annotations::ROLLOUT_AFTER) {
+        return false;
+    }
+    let Some(reason) = blocking_dependency(client, update.namespace, annotations).await else {
+        return false;
+    };
+
+    info!(
+        "Holding update of {} {}/{} to {}: {}",
+        update.kind, update.namespace, update.name, update.new_image, reason
+    );
+    let mut spec = crate::schedule::scheduled_spec(update);
+    spec.reason = Some("Released in rollout order".to_string());
+    queue(OrderedUpdate {
+        spec,
+        annotations: annotations.clone(),
+        reason,
+        queued_at: Utc::now(),
+    });
+    true
+}
+
+fn queue(update: OrderedUpdate) {
+    let target = &update.spec.target_ref;
+    let key = format!(
+        "{}/{}/{}/{}",
+        target.kind,
+        target.namespace,
+        target.name,
+        update.spec.container_name.as_deref().unwrap_or_default()
+    );
+
+    UPDATES_HELD_FOR_ORDER
+        .with_label_values(&[target.namespace.as_str(), target.kind.as_str()])
+        .inc();
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    pending.insert(key, update);
+    ORDERED_UPDATES_PENDING.set(pending.len() as i64);
+}
+
+/// Automatic updates currently waiting for their rollout order
+pub fn pending_updates() -> Vec<OrderedUpdate> {
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect()
+}
+
+fn take(key: &str) -> Option<OrderedUpdate> {
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    let update = pending.remove(key);
+    ORDERED_UPDATES_PENDING.set(pending.len() as i64);
+    update
+}
+
+/// Start the loop that applies held updates once the workloads they are ordered after
+/// finished their updates
+pub fn start_orchestrator(client: Client) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Rollout order orchestrator started");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            release_queued_updates(&client).await;
+            if let Err(e) = release_held_requests(&client).await {
+                warn!(
+                    "Failed to check UpdateRequests held for rollout order: {}",
+                    e
+                );
+            }
+        }
+    })
+}
+
+async fn release_queued_updates(client: &Client) {
+    let queued: Vec<(String, OrderedUpdate)> = PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(key, update)| (key.clone(), update.clone()))
+        .collect();
+
+    for (key, update) in queued {
+        let target = &update.spec.target_ref;
+        if let Some(reason) =
+            blocking_dependency(client, &target.namespace, &update.annotations).await
+        {
+            debug!(
+                "{} {}/{} still held: {}",
+                target.kind, target.namespace, target.name, reason
+            );
+            continue;
+        }
+        let Some(update) = take(&key) else {
+            continue;
+        };
+        let target = &update.spec.target_ref;
+
+        info!(
+            "Rollout order reached, applying {} to {} {}/{}",
+            update.spec.new_image, target.kind, target.namespace, target.name
+        );
+        let result = crate::approval::apply_scheduled_update(client, &update.spec).await;
+        audit::record(
+            UpdateAuditSpec::for_update_request(AuditAction::Applied, &update.spec)
+                .with_source("rollout-order")
+                .with_message(format!(
+                    "Held since {}: {}",
+                    update.queued_at, update.reason
+                ))
+                .with_result(&result),
+        );
+        if let Err(e) = result {
+            warn!(
+                "Failed to apply ordered update of {} {}/{}: {}",
+                target.kind, target.namespace, target.name, e
+            );
+        }
+    }
+}
+
+async fn release_held_requests(client: &Client) -> Result<()> {
+    let update_requests: Api<UpdateRequest> = Api::all(client.clone());
+    let held: Vec<UpdateRequest> = update_requests
+        .list(&ListParams::default())
+        .await?
+        .items
+        .into_iter()
+        .filter(|ur| {
+            ur.status
+                .as_ref()
+                .is_some_and(|s| s.phase == UpdatePhase::Approved && s.waiting_for.is_some())
+        })
+        .filter(|ur| namespaces::is_watched(&ur.namespace().unwrap_or_default()))
+        .collect();
+
+    for update_request in held {
+        let target = &update_request.spec.target_ref;
+        let annotations = crate::hooks::target_annotations(client, target).await;
+        if let Some(reason) = blocking_dependency(client, &target.namespace, &annotations).await {
+            debug!(
+                "UpdateRequest {}/{} still held: {}",
+                update_request.namespace().unwrap_or_default(),
+                update_request.name_any(),
+                reason
+            );
+            continue;
+        }
+        crate::approval::release_held_update(client, update_request).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{TargetRef, UpdatePolicyType, UpdateRequestStatus, UpdateType};
+
+    #[test]
+    fn test_parse_dependency() {
+        assert_eq!(
+            Dependency::parse("api", "shop").unwrap(),
+            Dependency {
+                kind: "Deployment".to_string(),
+                namespace: "shop".to_string(),
+                name: "api".to_string(),
+            }
+        );
+        assert_eq!(
+            Dependency::parse(" statefulset/data/postgres ", "shop").unwrap(),
+            Dependency {
+                kind: "StatefulSet".to_string(),
+                namespace: "data".to_string(),
+                name: "postgres".to_string(),
+            }
+        );
+        assert_eq!(
+            Dependency::parse("payments/api", "shop").unwrap().namespace,
+            "payments"
+        );
+        assert!(Dependency::parse("Pod/shop/api", "shop").is_err());
+        assert!(Dependency::parse("a/b/c/d", "shop").is_err());
+    }
+
+    #[test]
+    fn test_parse_dependencies() {
+        let annotations = BTreeMap::from([(
+            annotations::ROLLOUT_AFTER.to_string(),
+            "payments/api, StatefulSet/data/postgres,".to_string(),
+        )]);
+        let dependencies = parse_dependencies(&annotations, "shop").unwrap();
+        assert_eq!(dependencies.len(), 2);
+        assert_eq!(dependencies[1].to_string(), "StatefulSet data/postgres");
+
+        assert!(
+            parse_dependencies(&BTreeMap::new(), "shop")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    fn update_request(
+        phase: Option<UpdatePhase>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> UpdateRequest {
+        let mut ur = UpdateRequest::new(
+            "api-app-1-2-0",
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "api".to_string(),
+                    namespace: "payments".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: Some("app".to_string()),
+                image_parameter: None,
+                current_image: "api:1.1.0".to_string(),
+                new_image: "api:1.2.0".to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        );
+        ur.status = phase.map(|phase| UpdateRequestStatus {
+            phase,
+            ..Default::default()
+        });
+        ur
+    }
+
+    #[test]
+    fn test_open_update_requests() {
+        let now = Utc::now();
+        assert!(is_open(&update_request(None, None), now));
+        assert!(is_open(
+            &update_request(Some(UpdatePhase::Approved), None),
+            now
+        ));
+        assert!(!is_open(
+            &update_request(Some(UpdatePhase::Completed), None),
+            now
+        ));
+        assert!(!is_open(
+            &update_request(
+                Some(UpdatePhase::Pending),
+                Some(now - chrono::Duration::hours(1))
+            ),
+            now
+        ));
+
+        let dependency = Dependency::parse("payments/api", "shop").unwrap();
+        assert!(dependency.is_target_of(&update_request(None, None).spec));
+    }
+}
@@ -0,0 +1,397 @@
+//! Platform compatibility of proposed images.
+//!
+//! A tag built only for amd64 breaks a workload scheduled on arm64 nodes. With the check
+//! enabled (HEADWIND_PLATFORM_CHECK, or per workload with `headwind.sh/platform-check`), the
+//! platforms (`os/architecture`) of the nodes a workload can be scheduled on are compared with
+//! the platforms of the candidate image's manifest list before an update is proposed. In
+//! `skip` mode incompatible updates are dropped; in `flag` mode they always become an
+//! UpdateRequest, with the missing platforms in its reason, even for workloads that update
+//! without approval.
+
+use crate::metrics::PLATFORM_CHECKS;
+use crate::models::policy::annotations;
+use crate::polling;
+use anyhow::Result;
+use k8s_openapi::api::core::v1::{Node, NodeSelectorRequirement, PodSpec};
+use kube::api::{Api, ListParams};
+use kube::{Client, ResourceExt};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use tracing::{debug, info, warn};
+
+const OS_LABEL: &str = "kubernetes.io/os";
+const ARCH_LABEL: &str = "kubernetes.io/arch";
+
+/// What happens to updates whose image isn't built for every platform of a workload's nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlatformCheck {
+    /// Platforms aren't checked
+    #[default]
+    Off,
+    /// Incompatible updates are not proposed
+    Skip,
+    /// Incompatible updates are proposed as UpdateRequests that need approval
+    Flag,
+}
+
+impl FromStr for PlatformCheck {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "" => Ok(Self::Off),
+            "skip" => Ok(Self::Skip),
+            "flag" => Ok(Self::Flag),
+            other => Err(format!(
+                "Invalid platform check '{}', expected off, skip or flag",
+                other
+            )),
+        }
+    }
+}
+
+impl PlatformCheck {
+    /// Mode for a workload: its `headwind.sh/platform-check` annotation, or
+    /// HEADWIND_PLATFORM_CHECK (default: off)
+    pub fn for_workload(workload_annotations: &BTreeMap<String, String>) -> Self {
+        workload_annotations
+            .get(annotations::PLATFORM_CHECK)
+            .cloned()
+            .or_else(|| std::env::var("HEADWIND_PLATFORM_CHECK").ok())
+            .and_then(|mode| mode.parse().map_err(|e| warn!("{}", e)).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Outcome of a platform check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// The image runs on the workload's nodes, or wasn't checked
+    Compatible,
+    /// Don't propose the update, because of the given reason
+    Skip(String),
+    /// Propose the update for approval, with the given warning
+    Flag(String),
+}
+
+/// Check whether `new_image` is built for the platforms of the nodes a workload's pods can be
+/// scheduled on. Failing to read the nodes or the image's manifest doesn't hold the update
+/// back.
+pub async fn check(
+    client: &Client,
+    namespace: &str,
+    workload_annotations: &BTreeMap<String, String>,
+    pod_spec: &PodSpec,
+    new_image: &str,
+) -> Verdict {
+    let mode = PlatformCheck::for_workload(workload_annotations);
+    if mode == PlatformCheck::Off {
+        return Verdict::Compatible;
+    }
+
+    let missing = match missing_platforms(client, namespace, pod_spec, new_image).await {
+        Ok(missing) => missing,
+        Err(e) => {
+            PLATFORM_CHECKS
+                .with_label_values(&[namespace, "error"])
+                .inc();
+            warn!(
+                "Failed to check the platforms of {}, not holding the update back: {:#}",
+                new_image, e
+            );
+            return Verdict::Compatible;
+        },
+    };
+    if missing.is_empty() {
+        PLATFORM_CHECKS
+            .with_label_values(&[namespace, "compatible"])
+            .inc();
+        debug!("{} is built for all platforms of its nodes", new_image);
+        return Verdict::Compatible;
+    }
+
+    let reason = format!(
+        "{} is not built for {}, which nodes of the workload run on",
+        new_image,
+        missing.join(", ")
+    );
+    info!("{}", reason);
+    match mode {
+        PlatformCheck::Flag => {
+            PLATFORM_CHECKS
+                .with_label_values(&[namespace, "flagged"])
+                .inc();
+            Verdict::Flag(reason)
+        },
+        _ => {
+            PLATFORM_CHECKS
+                .with_label_values(&[namespace, "skipped"])
+                .inc();
+            Verdict::Skip(reason)
+        },
+    }
+}
+
+/// UpdateRequest reason, followed by the warning of a flagged platform check
+pub fn with_warning(reason: String, warning: Option<&str>) -> String {
+    match warning {
+        Some(warning) => format!("{}. Warning: {}", reason, warning),
+        None => reason,
+    }
+}
+
+/// Platforms of the workload's nodes that `image` isn't built for
+async fn missing_platforms(
+    client: &Client,
+    namespace: &str,
+    pod_spec: &PodSpec,
+    image: &str,
+) -> Result<Vec<String>> {
+    let nodes: Api<Node> = Api::all(client.clone());
+    let nodes = nodes.list(&ListParams::default()).await?.items;
+    let required = node_platforms(&nodes, pod_spec);
+    if required.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let available = polling::image_platforms(client, image, namespace).await?;
+    debug!(
+        "{} is built for {:?}, nodes run {:?}",
+        image, available, required
+    );
+    Ok(required
+        .into_iter()
+        .filter(|platform| !available.contains(platform))
+        .collect())
+}
+
+/// Platforms (`os/architecture`) of the nodes a pod can be scheduled on, going by its
+/// `nodeSelector` and required node affinity
+pub fn node_platforms(nodes: &[Node], pod_spec: &PodSpec) -> BTreeSet<String> {
+    nodes
+        .iter()
+        .filter(|node| schedulable(node.labels(), pod_spec))
+        .filter_map(|node| {
+            let info = node.status.as_ref().and_then(|s| s.node_info.as_ref());
+            let os = node
+                .labels()
+                .get(OS_LABEL)
+                .cloned()
+                .or_else(|| info.map(|i| i.operating_system.clone()))?;
+            let arch = node
+                .labels()
+                .get(ARCH_LABEL)
+                .cloned()
+                .or_else(|| info.map(|i| i.architecture.clone()))?;
+            Some(format!("{}/{}", os, arch))
+        })
+        .collect()
+}
+
+/// Whether a node with `labels` satisfies a pod's `nodeSelector` and required node affinity
+fn schedulable(labels: &BTreeMap<String, String>, pod_spec: &PodSpec) -> bool {
+    let selected = pod_spec
+        .node_selector
+        .iter()
+        .flatten()
+        .all(|(key, value)| labels.get(key) == Some(value));
+
+    let terms = pod_spec
+        .affinity
+        .as_ref()
+        .and_then(|affinity| affinity.node_affinity.as_ref())
+        .and_then(|affinity| {
+            affinity
+                .required_during_scheduling_ignored_during_execution
+                .as_ref()
+        })
+        .map(|selector| selector.node_selector_terms.as_slice())
+        .unwrap_or_default();
+    // Terms are ORed, the expressions of a term ANDed
+    let affine = terms.is_empty()
+        || terms.iter().any(|term| {
+            term.match_expressions
+                .iter()
+                .flatten()
+                .all(|requirement| matches(labels, requirement))
+        });
+
+    selected && affine
+}
+
+/// Whether node labels satisfy a node selector requirement
+fn matches(labels: &BTreeMap<String, String>, requirement: &NodeSelectorRequirement) -> bool {
+    let value = labels.get(&requirement.key);
+    let values = requirement.values.as_deref().unwrap_or_default();
+    let compare = |ordering: std::cmp::Ordering| {
+        let label = value.and_then(|v| v.parse::<i64>().ok());
+        let bound = values.first().and_then(|v| v.parse::<i64>().ok());
+        matches!((label, bound), (Some(label), Some(bound)) if label.cmp(&bound) == ordering)
+    };
+    match requirement.operator.as_str() {
+        "In" => value.is_some_and(|v| values.contains(v)),
+        "NotIn" => !value.is_some_and(|v| values.contains(v)),
+        "Exists" => value.is_some(),
+        "DoesNotExist" => value.is_none(),
+        "Gt" => compare(std::cmp::Ordering::Greater),
+        "Lt" => compare(std::cmp::Ordering::Less),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{
+        Affinity, NodeAffinity, NodeSelector, NodeSelectorTerm, NodeStatus, NodeSystemInfo,
+    };
+    use kube::api::ObjectMeta;
+
+    fn node(name: &str, labels: &[(&str, &str)]) -> Node {
+        Node {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                labels: Some(
+                    labels
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn nodes() -> Vec<Node> {
+        vec![
+            node(
+                "amd",
+                &[
+                    (OS_LABEL, "linux"),
+                    (ARCH_LABEL, "amd64"),
+                    ("pool", "general"),
+                ],
+            ),
+            node(
+                "arm",
+                &[
+                    (OS_LABEL, "linux"),
+                    (ARCH_LABEL, "arm64"),
+                    ("pool", "general"),
+                ],
+            ),
+            node(
+                "gpu",
+                &[(OS_LABEL, "linux"), (ARCH_LABEL, "amd64"), ("pool", "gpu")],
+            ),
+        ]
+    }
+
+    fn platforms(pod_spec: &PodSpec) -> Vec<String> {
+        node_platforms(&nodes(), pod_spec).into_iter().collect()
+    }
+
+    #[test]
+    fn test_node_platforms_without_constraints() {
+        assert_eq!(
+            platforms(&PodSpec::default()),
+            vec!["linux/amd64", "linux/arm64"]
+        );
+    }
+
+    #[test]
+    fn test_node_platforms_with_node_selector() {
+        let pod_spec = PodSpec {
+            node_selector: Some(BTreeMap::from([("pool".to_string(), "gpu".to_string())])),
+            ..Default::default()
+        };
+        assert_eq!(platforms(&pod_spec), vec!["linux/amd64"]);
+    }
+
+    #[test]
+    fn test_node_platforms_with_required_affinity() {
+        let requirement = |key: &str, operator: &str, values: &[&str]| NodeSelectorRequirement {
+            key: key.to_string(),
+            operator: operator.to_string(),
+            values: Some(values.iter().map(|v| v.to_string()).collect()),
+        };
+        let pod_spec = |expressions: Vec<NodeSelectorRequirement>| PodSpec {
+            affinity: Some(Affinity {
+                node_affinity: Some(NodeAffinity {
+                    required_during_scheduling_ignored_during_execution: Some(NodeSelector {
+                        node_selector_terms: vec![NodeSelectorTerm {
+                            match_expressions: Some(expressions),
+                            match_fields: None,
+                        }],
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            platforms(&pod_spec(vec![requirement(ARCH_LABEL, "In", &["arm64"])])),
+            vec!["linux/arm64"]
+        );
+        assert_eq!(
+            platforms(&pod_spec(vec![
+                requirement("pool", "NotIn", &["gpu"]),
+                requirement(ARCH_LABEL, "Exists", &[]),
+            ])),
+            vec!["linux/amd64", "linux/arm64"]
+        );
+        assert!(platforms(&pod_spec(vec![requirement("zone", "Exists", &[])])).is_empty());
+    }
+
+    #[test]
+    fn test_node_platforms_from_node_info() {
+        let mut unlabeled = node("old", &[]);
+        unlabeled.status = Some(NodeStatus {
+            node_info: Some(NodeSystemInfo {
+                architecture: "arm64".to_string(),
+                operating_system: "linux".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert_eq!(
+            node_platforms(&[unlabeled], &PodSpec::default())
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["linux/arm64"]
+        );
+    }
+
+    #[test]
+    fn test_with_warning() {
+        assert_eq!(
+            with_warning("Update from 1.0 to 1.1".to_string(), None),
+            "Update from 1.0 to 1.1"
+        );
+        assert_eq!(
+            with_warning(
+                "Update from 1.0 to 1.1".to_string(),
+                Some("app:1.1 is not built for linux/arm64")
+            ),
+            "Update from 1.0 to 1.1. Warning: app:1.1 is not built for linux/arm64"
+        );
+    }
+
+    #[test]
+    fn test_platform_check_modes() {
+        assert_eq!("skip".parse(), Ok(PlatformCheck::Skip));
+        assert_eq!("Flag".parse(), Ok(PlatformCheck::Flag));
+        assert_eq!("off".parse(), Ok(PlatformCheck::Off));
+        assert!("warn".parse::<PlatformCheck>().is_err());
+
+        let annotations =
+            BTreeMap::from([(annotations::PLATFORM_CHECK.to_string(), "flag".to_string())]);
+        assert_eq!(
+            PlatformCheck::for_workload(&annotations),
+            PlatformCheck::Flag
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Cluster and namespace policy defaults from the `policyDefaults` key of `headwind-config`
+//!
+//! Defaults are the lowest layer of a workload's effective annotations: UpdateTargets, Flux
+//! ImagePolicies and the workload's own annotations all take precedence.
+
+use crate::config::policydefaults;
+use kube::{Resource, ResourceExt};
+use std::collections::BTreeMap;
+
+/// A workload's annotations with `defaults` filled in for the keys it doesn't set
+pub fn with_defaults(
+    annotations: Option<&BTreeMap<String, String>>,
+    defaults: BTreeMap<String, String>,
+) -> Option<BTreeMap<String, String>> {
+    if defaults.is_empty() {
+        return annotations.cloned();
+    }
+
+    let mut merged = defaults;
+    if let Some(annotations) = annotations {
+        merged.extend(annotations.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    Some(merged)
+}
+
+/// Fill in the policy defaults of the resource's namespace (in memory only), after
+/// UpdateTargets and ImagePolicies have been applied
+pub fn apply_policy_defaults<K: Resource>(resource: &mut K) {
+    let defaults = policydefaults::annotations_for(&resource.namespace().unwrap_or_default());
+    if defaults.is_empty() {
+        return;
+    }
+    let merged = with_defaults(resource.meta().annotations.as_ref(), defaults);
+    resource.meta_mut().annotations = merged;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotations;
+
+    #[test]
+    fn test_annotations_override_defaults() {
+        let defaults: BTreeMap<String, String> = [
+            (annotations::POLICY.to_string(), "minor".to_string()),
+            (
+                annotations::REQUIRE_APPROVAL.to_string(),
+                "false".to_string(),
+            ),
+        ]
+        .into();
+
+        let merged = with_defaults(None, defaults.clone()).unwrap();
+        assert_eq!(merged[annotations::POLICY], "minor");
+
+        let own: BTreeMap<String, String> =
+            [(annotations::POLICY.to_string(), "none".to_string())].into();
+        let merged = with_defaults(Some(&own), defaults).unwrap();
+        assert_eq!(merged[annotations::POLICY], "none");
+        assert_eq!(merged[annotations::REQUIRE_APPROVAL], "false");
+
+        assert_eq!(with_defaults(Some(&own), BTreeMap::new()), Some(own));
+    }
+}
@@ -0,0 +1,558 @@
+//! Flux image automation compatibility
+//!
+//! With `HEADWIND_FLUX_IMAGE_POLICIES_ENABLED=true`, Flux `ImagePolicy` resources (together
+//! with the `ImageRepository` each one references) become a policy source: every workload
+//! container running an image of the ImageRepository is tracked with the ImagePolicy's rules,
+//! as if the workload carried the equivalent `headwind.sh/*` annotations. Annotations set on
+//! the workload and UpdateTargets take precedence. Headwind then proposes updates through its
+//! own approval workflow instead of Flux committing them to Git.
+
+use crate::controller::stores;
+use crate::models::annotations;
+use crate::models::imagepolicy::{ImagePolicy, ImagePolicySpec, ImageRepository};
+use crate::models::{UpdatePolicy, parse_duration_secs};
+use anyhow::{Result, anyhow};
+use k8s_openapi::api::core::v1::PodSpec;
+use kube::{Api, Client, Resource, ResourceExt, api::ListParams};
+use oci_distribution::Reference;
+use std::collections::BTreeMap;
+use tracing::{debug, warn};
+
+/// Flux's default `exclusionList`
+const DEFAULT_EXCLUSIONS: &[&str] = &[r"^.*\.sig$"];
+
+/// Whether Flux ImagePolicies are used as a policy source
+/// (HEADWIND_FLUX_IMAGE_POLICIES_ENABLED, default: false)
+pub fn flux_image_policies_enabled() -> bool {
+    std::env::var("HEADWIND_FLUX_IMAGE_POLICIES_ENABLED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// An ImagePolicy resolved against its ImageRepository
+#[derive(Debug, Clone, PartialEq)]
+pub struct FluxImagePolicy {
+    /// `namespace/name` of the ImagePolicy
+    pub name: String,
+    /// Repository the policy tracks, as `registry/repository`
+    pub repository: String,
+    /// Headwind annotations equivalent to the policy
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl FluxImagePolicy {
+    /// Translate an ImagePolicy and the ImageRepository it references
+    pub fn resolve(policy: &ImagePolicy, repository: &ImageRepository) -> Result<Self> {
+        let image = &repository.spec.image;
+        let repository_name = image_repository(image)
+            .ok_or_else(|| anyhow!("invalid ImageRepository image '{}'", image))?;
+
+        let mut annotations = policy_annotations(&policy.spec)?;
+        let exclusions = match &repository.spec.exclusion_list {
+            Some(list) => list.iter().map(String::as_str).collect(),
+            None => DEFAULT_EXCLUSIONS.to_vec(),
+        };
+        let ignore_patterns: Vec<String> = exclusions
+            .into_iter()
+            .filter_map(|pattern| {
+                let glob = exclusion_glob(pattern);
+                if glob.is_none() {
+                    debug!(
+                        "Exclusion {} of ImageRepository {} has no glob equivalent, ignoring it",
+                        pattern,
+                        repository.name_any()
+                    );
+                }
+                glob
+            })
+            .collect();
+        if !ignore_patterns.is_empty() {
+            annotations.insert(
+                annotations::IGNORE_PATTERN.to_string(),
+                ignore_patterns.join(","),
+            );
+        }
+        if let Some(interval) = repository
+            .spec
+            .interval
+            .as_deref()
+            .and_then(go_duration_secs)
+        {
+            annotations.insert(
+                annotations::POLLING_INTERVAL.to_string(),
+                interval.to_string(),
+            );
+        }
+
+        Ok(Self {
+            name: format!(
+                "{}/{}",
+                policy.namespace().unwrap_or_default(),
+                policy.name_any()
+            ),
+            repository: repository_name,
+            annotations,
+        })
+    }
+
+    /// Whether a container image belongs to the tracked repository
+    pub fn tracks(&self, image: &str) -> bool {
+        image_repository(image).is_some_and(|repository| repository == self.repository)
+    }
+}
+
+/// Headwind annotations equivalent to an ImagePolicy's rules. Polling drives the updates
+/// like Flux's image scanning, and registry webhooks are accepted as well.
+pub fn policy_annotations(spec: &ImagePolicySpec) -> Result<BTreeMap<String, String>> {
+    let filter = spec
+        .filter_tags
+        .as_ref()
+        .and_then(|filter| filter.pattern.clone());
+    let choice = &spec.policy;
+
+    let mut result = BTreeMap::new();
+    let policy = if let Some(semver) = &choice.semver {
+        if filter.is_some() {
+            return Err(anyhow!(
+                "filterTags can't be combined with a semver policy in Headwind"
+            ));
+        }
+        // Ranges with prerelease versions (`>=1.0.0-0`) admit prereleases, as in Flux
+        if semver.range.contains('-') {
+            result.insert(
+                annotations::ALLOW_PRERELEASE.to_string(),
+                "true".to_string(),
+            );
+        }
+        semver_range_policy(&semver.range)
+    } else if let Some(ordered) = choice.alphabetical.as_ref().or(choice.numerical.as_ref()) {
+        if ordered
+            .order
+            .as_deref()
+            .is_some_and(|order| order.eq_ignore_ascii_case("desc"))
+        {
+            return Err(anyhow!("descending order is not supported"));
+        }
+        if spec
+            .filter_tags
+            .as_ref()
+            .is_some_and(|filter| filter.extract.is_some())
+        {
+            debug!("filterTags.extract is not supported, comparing whole tags");
+        }
+        result.insert(
+            annotations::PATTERN.to_string(),
+            filter.unwrap_or_else(|| ".*".to_string()),
+        );
+        UpdatePolicy::Regex
+    } else {
+        return Err(anyhow!("no semver, alphabetical or numerical policy set"));
+    };
+
+    result.insert(annotations::POLICY.to_string(), policy.to_string());
+    result.insert(annotations::EVENT_SOURCE.to_string(), "both".to_string());
+    Ok(result)
+}
+
+/// Closest Headwind policy to a semver range: `~1.2` and `1.2.x` follow patches, `^1.2`,
+/// `1.x` and ranges with an upper bound follow minor versions, anything else any newer
+/// version. Bounds themselves are not enforced.
+pub fn semver_range_policy(range: &str) -> UpdatePolicy {
+    let range = range.trim();
+    let wildcards = range
+        .split('.')
+        .skip(1)
+        .filter(|part| matches!(*part, "x" | "X" | "*"))
+        .count();
+    let parts = range.split('.').count();
+
+    if range.starts_with('~') || (parts == 3 && wildcards == 1) {
+        UpdatePolicy::Patch
+    } else if range.starts_with('^') || wildcards > 0 || range.contains('<') {
+        UpdatePolicy::Minor
+    } else {
+        UpdatePolicy::Major
+    }
+}
+
+/// Glob for a Flux exclusion regex of the form `^.*<literal>$` (or any literal with `.*`
+/// wildcards), the only shape Headwind's ignore patterns can express
+fn exclusion_glob(pattern: &str) -> Option<String> {
+    let anchored_start = pattern.starts_with('^');
+    let anchored_end = pattern.ends_with('$') && !pattern.ends_with(r"\$");
+    let body = pattern.trim_start_matches('^');
+    let body = if anchored_end {
+        &body[..body.len() - 1]
+    } else {
+        body
+    };
+
+    let mut glob = String::new();
+    if !anchored_start {
+        glob.push('*');
+    }
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' if chars.peek() == Some(&'*') => {
+                chars.next();
+                glob.push('*');
+            },
+            // Escaped punctuation is a literal, `\d` and friends are classes
+            '\\' => match chars.next()? {
+                c if c.is_ascii_alphanumeric() => return None,
+                c => glob.push(c),
+            },
+            '.' | '[' | ']' | '(' | ')' | '+' | '?' | '{' | '}' | '|' | '*' | '^' | '$' => {
+                return None;
+            },
+            c => glob.push(c),
+        }
+    }
+    if !anchored_end {
+        glob.push('*');
+    }
+    Some(glob)
+}
+
+/// Seconds in a Go duration such as `5m` or `1h30m`
+fn go_duration_secs(value: &str) -> Option<u64> {
+    let mut total = 0u64;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        if c.is_ascii_alphabetic() {
+            total = total.checked_add(parse_duration_secs(&value[start..=i])?)?;
+            start = i + 1;
+        }
+    }
+    (start == value.len() && total > 0).then_some(total)
+}
+
+/// `registry/repository` of an image reference, with Docker Hub defaults applied
+fn image_repository(image: &str) -> Option<String> {
+    let reference = Reference::try_from(image).ok()?;
+    Some(format!(
+        "{}/{}",
+        reference.resolve_registry(),
+        reference.repository()
+    ))
+}
+
+/// List the ImagePolicies in watched namespaces and resolve them, sorted by namespace and
+/// name so overlapping policies resolve deterministically.
+///
+/// Returns an empty list when the compatibility mode is disabled, the Flux CRDs are not
+/// installed, or they cannot be listed.
+pub async fn list_image_policies(client: &Client) -> Vec<FluxImagePolicy> {
+    if !flux_image_policies_enabled() {
+        return Vec::new();
+    }
+
+    let policies = match stores::list::<ImagePolicy>(client).await {
+        Ok(policies) => policies,
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            debug!("Flux ImagePolicy CRD not installed");
+            return Vec::new();
+        },
+        Err(e) => {
+            warn!("Failed to list Flux ImagePolicies: {}", e);
+            return Vec::new();
+        },
+    };
+    if policies.is_empty() {
+        return Vec::new();
+    }
+
+    let repositories = match Api::<ImageRepository>::all(client.clone())
+        .list(&ListParams::default())
+        .await
+    {
+        Ok(list) => list.items,
+        Err(e) => {
+            warn!("Failed to list Flux ImageRepositories: {}", e);
+            return Vec::new();
+        },
+    };
+
+    resolve_all(&policies, &repositories)
+}
+
+/// Resolve every ImagePolicy whose ImageRepository exists and isn't suspended
+pub fn resolve_all(
+    policies: &[ImagePolicy],
+    repositories: &[ImageRepository],
+) -> Vec<FluxImagePolicy> {
+    policies
+        .iter()
+        .filter_map(|policy| {
+            let repository = referenced_repository(policy, repositories)?;
+            if repository.spec.suspend == Some(true) {
+                debug!(
+                    "ImageRepository {} is suspended, skipping ImagePolicy {}",
+                    repository.name_any(),
+                    policy.name_any()
+                );
+                return None;
+            }
+            FluxImagePolicy::resolve(policy, repository)
+                .inspect_err(|e| debug!("Skipping ImagePolicy {}: {}", policy.name_any(), e))
+                .ok()
+        })
+        .collect()
+}
+
+/// The ImageRepository an ImagePolicy references
+pub fn referenced_repository<'a>(
+    policy: &ImagePolicy,
+    repositories: &'a [ImageRepository],
+) -> Option<&'a ImageRepository> {
+    let reference = &policy.spec.image_repository_ref;
+    let namespace = reference
+        .namespace
+        .clone()
+        .or_else(|| policy.namespace())
+        .unwrap_or_default();
+    repositories.iter().find(|repository| {
+        repository.name_any() == reference.name
+            && repository.namespace().unwrap_or_default() == namespace
+    })
+}
+
+/// Merge the first ImagePolicy tracking one of a workload's containers into its annotations
+/// (in memory only). Only the matching containers are tracked; existing annotations win.
+pub fn apply_image_policies<K>(
+    resource: &mut K,
+    pod_spec: impl Fn(&K) -> Option<&PodSpec>,
+    policies: &[FluxImagePolicy],
+) where
+    K: Resource,
+{
+    let Some(base) = policies.iter().find_map(|policy| {
+        let containers: Vec<&str> = pod_spec(resource)?
+            .containers
+            .iter()
+            .filter(|c| c.image.as_deref().is_some_and(|image| policy.tracks(image)))
+            .map(|c| c.name.as_str())
+            .collect();
+        if containers.is_empty() {
+            return None;
+        }
+        let mut annotations = policy.annotations.clone();
+        annotations.insert(annotations::IMAGES.to_string(), containers.join(","));
+        debug!(
+            "{} tracked by ImagePolicy {}",
+            resource.meta().name.as_deref().unwrap_or_default(),
+            policy.name
+        );
+        Some(annotations)
+    }) else {
+        return;
+    };
+
+    let meta = resource.meta_mut();
+    let mut merged = base;
+    if let Some(existing) = meta.annotations.take() {
+        merged.extend(existing);
+    }
+    meta.annotations = Some(merged);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::imagepolicy::{
+        ImagePolicyChoice, ImageRepositoryReference, ImageRepositorySpec, OrderedPolicy,
+        SemVerPolicy, TagFilter,
+    };
+    use k8s_openapi::api::apps::v1::Deployment;
+    use k8s_openapi::api::core::v1::Container;
+
+    fn repository(namespace: &str, name: &str, image: &str) -> ImageRepository {
+        let mut repository = ImageRepository::new(
+            name,
+            ImageRepositorySpec {
+                image: image.to_string(),
+                interval: Some("1h30m".to_string()),
+                exclusion_list: None,
+                suspend: None,
+            },
+        );
+        repository.metadata.namespace = Some(namespace.to_string());
+        repository
+    }
+
+    fn semver_policy(namespace: &str, name: &str, repository: &str, range: &str) -> ImagePolicy {
+        let mut policy = ImagePolicy::new(
+            name,
+            ImagePolicySpec {
+                image_repository_ref: ImageRepositoryReference {
+                    name: repository.to_string(),
+                    namespace: None,
+                },
+                policy: ImagePolicyChoice {
+                    semver: Some(SemVerPolicy {
+                        range: range.to_string(),
+                    }),
+                    ..Default::default()
+                },
+                filter_tags: None,
+            },
+        );
+        policy.metadata.namespace = Some(namespace.to_string());
+        policy
+    }
+
+    fn deployment(containers: &[(&str, &str)], annotations: &[(&str, &str)]) -> Deployment {
+        let mut d: Deployment = serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "web", "namespace": "default"},
+            "spec": {"selector": {}, "template": {"spec": {"containers": []}}}
+        }))
+        .unwrap();
+        let pod_spec = d.spec.as_mut().unwrap().template.spec.as_mut().unwrap();
+        pod_spec.containers = containers
+            .iter()
+            .map(|(name, image)| Container {
+                name: name.to_string(),
+                image: Some(image.to_string()),
+                ..Default::default()
+            })
+            .collect();
+        if !annotations.is_empty() {
+            d.metadata.annotations = Some(
+                annotations
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
+        }
+        d
+    }
+
+    fn pod_spec(d: &Deployment) -> Option<&PodSpec> {
+        d.spec.as_ref()?.template.spec.as_ref()
+    }
+
+    #[test]
+    fn test_semver_range_policy() {
+        assert_eq!(semver_range_policy("~1.2"), UpdatePolicy::Patch);
+        assert_eq!(semver_range_policy("1.2.x"), UpdatePolicy::Patch);
+        assert_eq!(semver_range_policy("^1.2.0"), UpdatePolicy::Minor);
+        assert_eq!(semver_range_policy("1.x"), UpdatePolicy::Minor);
+        assert_eq!(semver_range_policy(">=1.0.0 <2.0.0"), UpdatePolicy::Minor);
+        assert_eq!(semver_range_policy(">=1.0.0"), UpdatePolicy::Major);
+        assert_eq!(semver_range_policy("*"), UpdatePolicy::Major);
+    }
+
+    #[test]
+    fn test_policy_annotations() {
+        let policy = semver_policy("flux-system", "web", "web", ">=1.0.0-0");
+        let annotations = policy_annotations(&policy.spec).unwrap();
+        assert_eq!(annotations[annotations::POLICY], "major");
+        assert_eq!(annotations[annotations::ALLOW_PRERELEASE], "true");
+        assert_eq!(annotations[annotations::EVENT_SOURCE], "both");
+
+        let mut spec = policy.spec.clone();
+        spec.policy = ImagePolicyChoice {
+            numerical: Some(OrderedPolicy::default()),
+            ..Default::default()
+        };
+        spec.filter_tags = Some(TagFilter {
+            pattern: Some(r"^main-[a-f0-9]+-\d+$".to_string()),
+            extract: None,
+        });
+        let annotations = policy_annotations(&spec).unwrap();
+        assert_eq!(annotations[annotations::POLICY], "regex");
+        assert_eq!(annotations[annotations::PATTERN], r"^main-[a-f0-9]+-\d+$");
+
+        spec.policy.numerical = Some(OrderedPolicy {
+            order: Some("desc".to_string()),
+        });
+        assert!(policy_annotations(&spec).is_err());
+    }
+
+    #[test]
+    fn test_exclusion_glob() {
+        assert_eq!(exclusion_glob(r"^.*\.sig$").as_deref(), Some("*.sig"));
+        assert_eq!(exclusion_glob("^dev-").as_deref(), Some("dev-*"));
+        assert_eq!(exclusion_glob("rc").as_deref(), Some("*rc*"));
+        assert_eq!(exclusion_glob(r"^v\d+$"), None);
+    }
+
+    #[test]
+    fn test_go_duration_secs() {
+        assert_eq!(go_duration_secs("5m"), Some(300));
+        assert_eq!(go_duration_secs("1h30m"), Some(5400));
+        assert_eq!(go_duration_secs("10"), None);
+        assert_eq!(go_duration_secs("500ms"), None);
+    }
+
+    #[test]
+    fn test_resolve_all() {
+        let repositories = vec![
+            repository("flux-system", "web", "ghcr.io/acme/web"),
+            repository("flux-system", "nginx", "nginx"),
+        ];
+        let mut suspended = repository("flux-system", "paused", "ghcr.io/acme/paused");
+        suspended.spec.suspend = Some(true);
+        let repositories = [repositories, vec![suspended]].concat();
+
+        let policies = vec![
+            semver_policy("flux-system", "web", "web", "~1.2"),
+            semver_policy("flux-system", "nginx", "nginx", "^1.25"),
+            semver_policy("flux-system", "paused", "paused", "*"),
+            semver_policy("flux-system", "orphan", "missing", "*"),
+        ];
+
+        let resolved = resolve_all(&policies, &repositories);
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].name, "flux-system/web");
+        assert_eq!(resolved[0].repository, "ghcr.io/acme/web");
+        assert_eq!(
+            resolved[0].annotations[annotations::POLLING_INTERVAL],
+            "5400"
+        );
+        assert_eq!(
+            resolved[0].annotations[annotations::IGNORE_PATTERN],
+            "*.sig"
+        );
+        assert!(resolved[1].tracks("nginx:1.25.3"));
+        assert!(resolved[1].tracks("docker.io/library/nginx:1.25.3"));
+        assert!(!resolved[1].tracks("ghcr.io/acme/nginx:1.25.3"));
+    }
+
+    #[test]
+    fn test_apply_image_policies() {
+        let repositories = vec![repository("flux-system", "web", "ghcr.io/acme/web")];
+        let policies = resolve_all(
+            &[semver_policy("flux-system", "web", "web", "~1.2")],
+            &repositories,
+        );
+
+        let mut d = deployment(
+            &[
+                ("web", "ghcr.io/acme/web:1.2.0"),
+                ("proxy", "envoyproxy/envoy:v1.30.0"),
+            ],
+            &[(annotations::REQUIRE_APPROVAL, "false")],
+        );
+        apply_image_policies(&mut d, pod_spec, &policies);
+        let ann = d.metadata.annotations.unwrap();
+        assert_eq!(ann[annotations::POLICY], "patch");
+        assert_eq!(ann[annotations::IMAGES], "web");
+        assert_eq!(ann[annotations::REQUIRE_APPROVAL], "false");
+
+        // Workload annotations win
+        let mut d = deployment(
+            &[("web", "ghcr.io/acme/web:1.2.0")],
+            &[(annotations::POLICY, "none")],
+        );
+        apply_image_policies(&mut d, pod_spec, &policies);
+        assert_eq!(d.metadata.annotations.unwrap()[annotations::POLICY], "none");
+
+        // Workloads without a tracked image are untouched
+        let mut d = deployment(&[("api", "ghcr.io/acme/api:2.0.0")], &[]);
+        apply_image_policies(&mut d, pod_spec, &policies);
+        assert!(d.metadata.annotations.is_none());
+    }
+}
@@ -0,0 +1,89 @@
+//! Minimum update interval (`headwind.sh/min-update-interval`).
+//!
+//! The last update of a workload comes from its persisted update history: the
+//! `headwind.sh/update-history` annotation kept for rollbacks and the `headwind.sh/last-update`
+//! annotation written with every update (`<RFC 3339> (approved by ...)` for approved ones).
+//! Updates detected before the interval has passed are not dropped: they become a Deferred
+//! UpdateRequest with `status.deferredUntil`, which the UpdateRequest controller releases
+//! once the interval is over.
+
+use crate::models::policy::annotations;
+use crate::rollback::{HISTORY_ANNOTATION, UpdateHistoryEntry};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+/// When the workload was last updated by Headwind, from its update history
+pub fn last_update(annotations: &BTreeMap<String, String>) -> Option<DateTime<Utc>> {
+    let from_annotation = annotations
+        .get(annotations::LAST_UPDATE)
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+        .map(|timestamp| timestamp.with_timezone(&Utc));
+    let from_history = annotations
+        .get(HISTORY_ANNOTATION)
+        .and_then(|json| serde_json::from_str::<Vec<UpdateHistoryEntry>>(json).ok())
+        .and_then(|entries| entries.iter().map(|entry| entry.timestamp).max());
+
+    from_annotation.max(from_history)
+}
+
+/// Until when updates of the workload are held back by its minimum interval, or None when
+/// they may be applied at `now`
+pub fn deferred_until(
+    annotations: &BTreeMap<String, String>,
+    min_interval_seconds: Option<u64>,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let min_interval = chrono::Duration::seconds(min_interval_seconds? as i64);
+    let until = last_update(annotations)? + min_interval;
+    (until > now).then_some(until)
+}
+
+/// Why an update waits until `until`, for `status.waitingFor`
+pub fn describe(until: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    format!(
+        "minimum update interval not reached, {}s remaining",
+        (until - now).num_seconds().max(0)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_deferred_until_uses_history() {
+        let now = Utc::now();
+        let approved = format!(
+            "{} (approved by admin@example.com)",
+            (now - Duration::seconds(600)).to_rfc3339()
+        );
+        let history = serde_json::to_string(&[UpdateHistoryEntry {
+            container: "app".to_string(),
+            image: "nginx:1.26.0".to_string(),
+            timestamp: now - Duration::seconds(60),
+            update_request_name: None,
+            approved_by: None,
+            healthy: false,
+        }])
+        .unwrap();
+        let annotations: BTreeMap<String, String> = [
+            (annotations::LAST_UPDATE.to_string(), approved),
+            (HISTORY_ANNOTATION.to_string(), history),
+        ]
+        .into();
+
+        // The newest of both counts
+        let until = deferred_until(&annotations, Some(300), now).unwrap();
+        assert_eq!(until, now + Duration::seconds(240));
+        assert_eq!(
+            describe(until, now),
+            "minimum update interval not reached, 240s remaining"
+        );
+
+        assert_eq!(deferred_until(&annotations, Some(30), now), None);
+        assert_eq!(deferred_until(&annotations, None, now), None);
+        assert_eq!(deferred_until(&BTreeMap::new(), Some(300), now), None);
+    }
+}
@@ -1,10 +1,82 @@
-use crate::models::{ResourcePolicy, UpdatePolicy};
+pub mod defaults;
+pub mod flux;
+pub mod interval;
+pub mod targets;
+
+use crate::models::{PrereleasePolicy, ResourcePolicy, UpdatePolicy};
 use anyhow::{Context, Result};
-use semver::Version;
+use regex::Regex;
+use schemars::JsonSchema;
+use semver::{BuildMetadata, Prerelease, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use tracing::{debug, info};
 
 pub struct PolicyEngine;
 
+/// Rule of a policy that decided whether a version is proposed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum PolicyRule {
+    /// A digest-pinned tag was compared by its digest
+    Digest,
+    /// The image follows the channel tag of `headwind.sh/channel-tag`
+    Channel,
+    /// The version matches `headwind.sh/ignore-pattern`
+    IgnorePattern,
+    /// The version is above `headwind.sh/max-version`
+    MaxVersion,
+    /// The policy is `none`
+    PolicyNone,
+    /// The policy is `force`
+    Force,
+    /// The policy is `all`
+    All,
+    /// The glob pattern of the `glob` policy
+    Glob,
+    /// The regular expression of the `regex` policy
+    Regex,
+    /// The `glob`, `regex` or `range` policy has no pattern
+    MissingPattern,
+    /// The version is a prerelease the policy doesn't allow
+    Prerelease,
+    /// The version is not newer than the current one
+    NotNewer,
+    /// The version level of the `patch`, `minor` or `major` policy
+    Semver,
+    /// The range of the `range` policy
+    Range,
+}
+
+/// Decision of a policy for one version, with the rule that made it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Evaluation {
+    /// Whether the version would be proposed
+    pub update: bool,
+    /// Rule that decided
+    pub rule: PolicyRule,
+    /// Why the rule decided the way it did
+    pub explanation: String,
+}
+
+impl Evaluation {
+    fn update(rule: PolicyRule, explanation: String) -> Self {
+        Self {
+            update: true,
+            rule,
+            explanation,
+        }
+    }
+
+    fn skip(rule: PolicyRule, explanation: String) -> Self {
+        Self {
+            update: false,
+            rule,
+            explanation,
+        }
+    }
+}
+
 impl PolicyEngine {
     #[allow(dead_code)]
     pub fn should_update(
@@ -13,72 +85,429 @@ impl PolicyEngine {
         current_version: &str,
         new_version: &str,
     ) -> Result<bool> {
+        let evaluation = self.evaluate(policy, current_version, new_version)?;
+        if evaluation.update {
+            info!(
+                "Policy {}: {} -> {}: {}",
+                policy.policy, current_version, new_version, evaluation.explanation
+            );
+        } else {
+            debug!(
+                "Policy {}: {} -> {}: {}",
+                policy.policy, current_version, new_version, evaluation.explanation
+            );
+        }
+        Ok(evaluation.update)
+    }
+
+    /// Decide whether `policy` proposes `new_version` over `current_version`, and explain
+    /// which rule decided. Versions that can't be compared at all are an error.
+    pub fn evaluate(
+        &self,
+        policy: &ResourcePolicy,
+        current_version: &str,
+        new_version: &str,
+    ) -> Result<Evaluation> {
+        // Digest-pinned versions (`1.2.3@sha256:...`) are judged by their tag. The same tag
+        // with another digest means the tag was pushed again, which pinned workloads follow.
+        let (current_version, current_digest) = split_digest(current_version);
+        let (new_version, new_digest) = split_digest(new_version);
+
+        // Images following a channel tag move with whatever digest the channel points at,
+        // whichever version that is
+        if let Some(channel) = &policy.channel_tag
+            && policy.policy != UpdatePolicy::None
+        {
+            return Ok(if new_version != channel {
+                Evaluation::skip(
+                    PolicyRule::Channel,
+                    format!(
+                        "Only channel tag {} is followed, not {}",
+                        channel, new_version
+                    ),
+                )
+            } else if current_version == channel && current_digest == new_digest {
+                Evaluation::skip(
+                    PolicyRule::Channel,
+                    format!("Channel tag {} still points to the running image", channel),
+                )
+            } else {
+                Evaluation::update(
+                    PolicyRule::Channel,
+                    format!(
+                        "Channel tag {} now points to {}",
+                        channel,
+                        new_digest.unwrap_or("a new image")
+                    ),
+                )
+            });
+        }
+
+        if let Some(new_digest) = new_digest
+            && current_version == new_version
+        {
+            return Ok(if policy.policy == UpdatePolicy::None {
+                Evaluation::skip(
+                    PolicyRule::PolicyNone,
+                    format!(
+                        "Policy is 'none', so tag {} is not followed to digest {}",
+                        new_version, new_digest
+                    ),
+                )
+            } else if current_digest != Some(new_digest) {
+                Evaluation::update(
+                    PolicyRule::Digest,
+                    format!(
+                        "Tag {} was pushed again with digest {}",
+                        new_version, new_digest
+                    ),
+                )
+            } else {
+                Evaluation::skip(
+                    PolicyRule::Digest,
+                    format!("Tag {} still has digest {}", new_version, new_digest),
+                )
+            });
+        }
+
+        if let Some(pattern) = policy
+            .ignore_patterns
+            .iter()
+            .find(|pattern| glob_match(pattern, new_version))
+        {
+            return Ok(Evaluation::skip(
+                PolicyRule::IgnorePattern,
+                format!(
+                    "Version {} matches the ignore pattern '{}'",
+                    new_version, pattern
+                ),
+            ));
+        }
+
+        if policy.policy != UpdatePolicy::None
+            && let Some(max_version) = &policy.max_version
+            && !Self::within_ceiling(max_version, new_version)?
+        {
+            return Ok(Evaluation::skip(
+                PolicyRule::MaxVersion,
+                format!(
+                    "Version {} is above the version ceiling {}",
+                    new_version, max_version
+                ),
+            ));
+        }
+
+        let missing_pattern = || {
+            Evaluation::skip(
+                PolicyRule::MissingPattern,
+                format!(
+                    "Policy is '{}' but no pattern is set (headwind.sh/pattern)",
+                    policy.policy
+                ),
+            )
+        };
+
         match policy.policy {
-            UpdatePolicy::None => {
-                debug!("Policy is 'none', skipping update");
-                Ok(false)
-            },
-            UpdatePolicy::Force => {
-                info!("Policy is 'force', allowing update");
-                Ok(true)
-            },
-            UpdatePolicy::All => {
-                info!("Policy is 'all', allowing update");
-                Ok(current_version != new_version)
-            },
+            UpdatePolicy::None => Ok(Evaluation::skip(
+                PolicyRule::PolicyNone,
+                "Policy is 'none', updates are never proposed".to_string(),
+            )),
+            UpdatePolicy::Force => Ok(Evaluation::update(
+                PolicyRule::Force,
+                "Policy is 'force', every version is proposed".to_string(),
+            )),
+            // Same-tag digest changes were decided above
+            UpdatePolicy::Digest => Ok(Evaluation::skip(
+                PolicyRule::Digest,
+                format!(
+                    "Policy is 'digest', so tag {} is only followed by digest, never changed to {}",
+                    current_version, new_version
+                ),
+            )),
+            UpdatePolicy::All => Ok(if current_version != new_version {
+                Evaluation::update(
+                    PolicyRule::All,
+                    format!(
+                        "Policy is 'all', every version other than {} is proposed",
+                        current_version
+                    ),
+                )
+            } else {
+                Evaluation::skip(
+                    PolicyRule::All,
+                    format!("Version {} is already running", new_version),
+                )
+            }),
             UpdatePolicy::Glob => {
-                if let Some(pattern) = &policy.pattern {
-                    let matches = glob_match(pattern, new_version);
-                    debug!("Glob pattern '{}' match: {}", pattern, matches);
-                    Ok(matches)
+                let Some(pattern) = &policy.pattern else {
+                    return Ok(missing_pattern());
+                };
+                Ok(if glob_match(pattern, new_version) {
+                    Evaluation::update(
+                        PolicyRule::Glob,
+                        format!("Version {} matches the glob '{}'", new_version, pattern),
+                    )
                 } else {
-                    Ok(false)
-                }
+                    Evaluation::skip(
+                        PolicyRule::Glob,
+                        format!(
+                            "Version {} doesn't match the glob '{}'",
+                            new_version, pattern
+                        ),
+                    )
+                })
+            },
+            UpdatePolicy::Regex => {
+                let Some(pattern) = &policy.pattern else {
+                    return Ok(missing_pattern());
+                };
+                Ok(if regex_match(pattern, new_version)? {
+                    Evaluation::update(
+                        PolicyRule::Regex,
+                        format!("Version {} matches the regex '{}'", new_version, pattern),
+                    )
+                } else {
+                    Evaluation::skip(
+                        PolicyRule::Regex,
+                        format!(
+                            "Version {} doesn't match the regex '{}'",
+                            new_version, pattern
+                        ),
+                    )
+                })
             },
             UpdatePolicy::Patch | UpdatePolicy::Minor | UpdatePolicy::Major => {
-                self.check_semver_policy(policy.policy, current_version, new_version)
+                self.check_semver_policy(policy, current_version, new_version)
+            },
+            UpdatePolicy::Range => match &policy.pattern {
+                Some(range) => self.check_range_policy(policy, range, current_version, new_version),
+                None => Ok(missing_pattern()),
             },
         }
     }
 
-    fn check_semver_policy(&self, policy: UpdatePolicy, current: &str, new: &str) -> Result<bool> {
+    /// Policy a resource with policy `none` and `headwind.sh/notify-detected` is watched with:
+    /// any newer semver release, keeping its ignore patterns and prerelease settings
+    pub fn detection_policy(policy: &ResourcePolicy) -> ResourcePolicy {
+        ResourcePolicy {
+            policy: UpdatePolicy::Major,
+            pin_digest: false,
+            channel_tag: None,
+            max_version: None,
+            ..policy.clone()
+        }
+    }
+
+    /// Whether `new_version` is a newer release than `current_version`, for detection-only
+    /// notifications (see [`Self::detection_policy`])
+    pub fn is_newer_release(
+        &self,
+        policy: &ResourcePolicy,
+        current_version: &str,
+        new_version: &str,
+    ) -> bool {
+        self.evaluate(
+            &Self::detection_policy(policy),
+            current_version,
+            new_version,
+        )
+        .is_ok_and(|evaluation| evaluation.update)
+    }
+
+    /// Whether a tag is excluded by the policy's ignore patterns
+    pub fn is_ignored(policy: &ResourcePolicy, version: &str) -> bool {
+        policy
+            .ignore_patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, version))
+    }
+
+    /// Whether `version` is allowed by a `headwind.sh/max-version` ceiling. Tags that aren't
+    /// semver can't be placed below the ceiling, so they're held back.
+    fn within_ceiling(max_version: &str, version: &str) -> Result<bool> {
+        let ceiling = version_ceiling(max_version)?;
+        let Ok(mut version) = Self::parse_version(version) else {
+            return Ok(false);
+        };
+        // Compare the release itself, so 1.28.4-rc.1 is within 1.28.x
+        version.pre = Prerelease::EMPTY;
+        version.build = BuildMetadata::EMPTY;
+        Ok(ceiling.matches(&version))
+    }
+
+    fn check_semver_policy(
+        &self,
+        resource_policy: &ResourcePolicy,
+        current: &str,
+        new: &str,
+    ) -> Result<Evaluation> {
+        let policy = resource_policy.policy;
+
         // Try to parse as semver, stripping common prefixes
         let current_version = Self::parse_version(current)
             .context(format!("Failed to parse current version: {}", current))?;
         let new_version =
             Self::parse_version(new).context(format!("Failed to parse new version: {}", new))?;
 
-        if new_version <= current_version {
-            debug!(
-                "New version {} is not greater than current version {}",
-                new, current
-            );
-            return Ok(false);
+        if !Self::prerelease_allowed(&resource_policy.prerelease, &current_version, &new_version) {
+            return Ok(Self::prerelease_denied(
+                &resource_policy.prerelease,
+                current,
+                new,
+            ));
+        }
+
+        // Semver precedence ignores build metadata, so 1.0.0+2 is not newer than 1.0.0+1
+        if new_version.cmp_precedence(&current_version) != Ordering::Greater {
+            return Ok(Self::not_newer(current, new));
         }
 
-        let should_update = match policy {
+        let evaluation = match policy {
             UpdatePolicy::Patch => {
                 // Only update if major and minor are the same
-                new_version.major == current_version.major
+                if new_version.major == current_version.major
                     && new_version.minor == current_version.minor
+                {
+                    Evaluation::update(
+                        PolicyRule::Semver,
+                        format!("Policy is 'patch' and {} is a patch of {}", new, current),
+                    )
+                } else {
+                    Evaluation::skip(
+                        PolicyRule::Semver,
+                        format!(
+                            "Policy is 'patch' but {} changes the minor or major version of {}",
+                            new, current
+                        ),
+                    )
+                }
             },
             UpdatePolicy::Minor => {
                 // Update if major is the same
-                new_version.major == current_version.major
-            },
-            UpdatePolicy::Major => {
-                // Update to any newer version
-                true
+                if new_version.major == current_version.major {
+                    Evaluation::update(
+                        PolicyRule::Semver,
+                        format!(
+                            "Policy is 'minor' and {} keeps the major version of {}",
+                            new, current
+                        ),
+                    )
+                } else {
+                    Evaluation::skip(
+                        PolicyRule::Semver,
+                        format!(
+                            "Policy is 'minor' but {} changes the major version of {}",
+                            new, current
+                        ),
+                    )
+                }
             },
-            _ => false,
+            // Update to any newer version
+            _ => Evaluation::update(
+                PolicyRule::Semver,
+                format!(
+                    "Policy is '{}' and {} is newer than {}",
+                    policy, new, current
+                ),
+            ),
         };
 
-        info!(
-            "Semver policy {:?}: current={}, new={}, should_update={}",
-            policy, current, new, should_update
-        );
+        Ok(evaluation)
+    }
 
-        Ok(should_update)
+    /// Range policy: `new` must be newer than `current` and satisfy the semver range
+    /// expression, e.g. `>=1.2, <2.0` or `~1.4`
+    fn check_range_policy(
+        &self,
+        resource_policy: &ResourcePolicy,
+        range: &str,
+        current: &str,
+        new: &str,
+    ) -> Result<Evaluation> {
+        let req =
+            VersionReq::parse(range.trim()).context(format!("Invalid version range: {}", range))?;
+        let current_version = Self::parse_version(current)
+            .context(format!("Failed to parse current version: {}", current))?;
+        let Ok(new_version) = Self::parse_version(new) else {
+            return Ok(Evaluation::skip(
+                PolicyRule::Range,
+                format!(
+                    "Version {} is not semver, so it can't be in range '{}'",
+                    new, range
+                ),
+            ));
+        };
+
+        if !Self::prerelease_allowed(&resource_policy.prerelease, &current_version, &new_version) {
+            return Ok(Self::prerelease_denied(
+                &resource_policy.prerelease,
+                current,
+                new,
+            ));
+        }
+
+        if new_version.cmp_precedence(&current_version) != Ordering::Greater {
+            return Ok(Self::not_newer(current, new));
+        }
+
+        // Prereleases were admitted above, so match them by their release
+        let mut release = new_version;
+        release.pre = Prerelease::EMPTY;
+        release.build = BuildMetadata::EMPTY;
+        Ok(if req.matches(&release) {
+            Evaluation::update(
+                PolicyRule::Range,
+                format!("Version {} is in range '{}'", new, range),
+            )
+        } else {
+            Evaluation::skip(
+                PolicyRule::Range,
+                format!("Version {} is outside range '{}'", new, range),
+            )
+        })
+    }
+
+    fn not_newer(current: &str, new: &str) -> Evaluation {
+        Evaluation::skip(
+            PolicyRule::NotNewer,
+            format!(
+                "Version {} is not newer than the current version {}",
+                new, current
+            ),
+        )
+    }
+
+    fn prerelease_denied(prerelease: &PrereleasePolicy, current: &str, new: &str) -> Evaluation {
+        let explanation = match &prerelease.channel {
+            Some(channel) => format!(
+                "Prerelease {} is not in the '{}' channel (headwind.sh/prerelease-channel)",
+                new, channel
+            ),
+            None => format!(
+                "Prerelease {} is not allowed: {} isn't a prerelease of the same version and \
+                 headwind.sh/allow-prerelease is not set",
+                new, current
+            ),
+        };
+        Evaluation::skip(PolicyRule::Prerelease, explanation)
+    }
+
+    /// Whether a semver policy may update to `new`, given how prereleases are configured
+    fn prerelease_allowed(prerelease: &PrereleasePolicy, current: &Version, new: &Version) -> bool {
+        if new.pre.is_empty() {
+            return true;
+        }
+
+        if let Some(channel) = &prerelease.channel {
+            return prerelease_channel(&new.pre).eq_ignore_ascii_case(channel);
+        }
+
+        // Moving along the prerelease series already running (1.3.0-rc.1 -> 1.3.0-rc.2)
+        // is allowed even without opting in
+        prerelease.allow
+            || (!current.pre.is_empty()
+                && (current.major, current.minor, current.patch)
+                    == (new.major, new.minor, new.patch))
     }
 
     fn parse_version(version: &str) -> Result<Version> {
@@ -92,23 +521,79 @@ impl PolicyEngine {
     }
 }
 
-#[allow(dead_code)]
-fn glob_match(pattern: &str, text: &str) -> bool {
-    // Simple glob matching - can be enhanced with a proper glob library
-    if pattern == "*" {
-        return true;
+/// Split a version into its tag and, for digest-pinned images, the digest:
+/// `1.2.3@sha256:...` -> (`1.2.3`, Some(`sha256:...`))
+pub fn split_digest(version: &str) -> (&str, Option<&str>) {
+    match version.split_once('@') {
+        Some((tag, digest)) => (tag, Some(digest)),
+        None => (version, None),
     }
+}
+
+/// Parse a `headwind.sh/max-version` value. A bare version is a ceiling: `1.28.x` (or `1.28`)
+/// allows anything up to the last 1.28 release, `1` anything below 2.0.0 and `1.28.3` up to
+/// and including 1.28.3. Anything with an operator or a comma is a semver range such as
+/// `>=1.2, <2.0`.
+pub fn version_ceiling(value: &str) -> Result<VersionReq> {
+    let value = value.trim();
+    if value.contains(',') || value.starts_with(['<', '>', '=', '~', '^']) {
+        return VersionReq::parse(value).context(format!("Invalid version range: {}", value));
+    }
+
+    let invalid = || anyhow::anyhow!("Invalid max version: {}", value);
+    let parts: Vec<&str> = value.trim_start_matches('v').split('.').collect();
+    let numbers: Vec<u64> = parts
+        .iter()
+        .take_while(|p| !matches!(**p, "x" | "X" | "*"))
+        .map(|p| p.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<_>>()?;
+    if numbers.is_empty()
+        || parts.len() > 3
+        || !parts[numbers.len()..]
+            .iter()
+            .all(|p| matches!(*p, "x" | "X" | "*"))
+    {
+        return Err(invalid());
+    }
+
+    let req = match numbers[..] {
+        [major] => format!("<{}.0.0", major + 1),
+        [major, minor] => format!("<{}.{}.0", major, minor + 1),
+        [major, minor, patch] => format!("<={}.{}.{}", major, minor, patch),
+        _ => return Err(invalid()),
+    };
+    VersionReq::parse(&req).context(format!("Invalid max version: {}", value))
+}
+
+/// Channel of a prerelease: its first identifier without a trailing number,
+/// so `rc.1`, `rc1` and `rc-1` are all in channel `rc`
+fn prerelease_channel(pre: &Prerelease) -> &str {
+    let first = pre.as_str().split('.').next().unwrap_or_default();
+    first.trim_end_matches(|c: char| c.is_ascii_digit() || c == '-')
+}
 
-    if pattern.contains('*') {
-        let parts: Vec<&str> = pattern.split('*').collect();
-        if parts.len() == 2 {
-            let prefix = parts[0];
-            let suffix = parts[1];
-            return text.starts_with(prefix) && text.ends_with(suffix);
+/// Glob matching: `*` matches any run of characters and `?` a single character.
+/// The glob is translated into an anchored regex, so every other character is literal.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut expr = String::with_capacity(pattern.len() + 2);
+    expr.push('^');
+    for c in pattern.chars() {
+        match c {
+            '*' => expr.push_str(".*"),
+            '?' => expr.push('.'),
+            c => expr.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
         }
     }
+    expr.push('$');
 
-    pattern == text
+    Regex::new(&expr).is_ok_and(|re| re.is_match(text))
+}
+
+/// Match a version against a regular expression. The expression is not anchored
+/// implicitly, so use `^...$` to match the whole tag.
+pub fn regex_match(pattern: &str, text: &str) -> Result<bool> {
+    let re = Regex::new(pattern).context(format!("Invalid regex pattern: {}", pattern))?;
+    Ok(re.is_match(text))
 }
 
 #[cfg(test)]
@@ -177,11 +662,485 @@ mod tests {
         assert!(engine.should_update(&policy, "v1.2.3", "v1.2.4").unwrap());
     }
 
+    #[test]
+    fn test_prerelease_excluded_by_default() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Minor,
+            ..Default::default()
+        };
+
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.3", "1.3.0-rc.1")
+                .unwrap()
+        );
+        assert!(engine.should_update(&policy, "1.2.3", "1.3.0").unwrap());
+
+        // Already on a prerelease: follow its series and move on to the release
+        assert!(
+            engine
+                .should_update(&policy, "1.3.0-rc.1", "1.3.0-rc.2")
+                .unwrap()
+        );
+        assert!(
+            engine
+                .should_update(&policy, "1.3.0-rc.2", "1.3.0")
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .should_update(&policy, "1.3.0-rc.2", "1.4.0-rc.1")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_allow_prerelease() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Minor,
+            prerelease: PrereleasePolicy {
+                allow: true,
+                channel: None,
+            },
+            ..Default::default()
+        };
+
+        assert!(
+            engine
+                .should_update(&policy, "1.2.3", "1.3.0-alpha.1")
+                .unwrap()
+        );
+        // Semver precedence: numeric identifiers compare numerically
+        assert!(
+            engine
+                .should_update(&policy, "1.3.0-rc.2", "1.3.0-rc.10")
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .should_update(&policy, "1.3.0-rc", "1.3.0-beta")
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .should_update(&policy, "1.3.0", "1.3.0-rc.1")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_prerelease_channel() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Major,
+            prerelease: PrereleasePolicy {
+                allow: false,
+                channel: Some("rc".to_string()),
+            },
+            ..Default::default()
+        };
+
+        assert!(
+            engine
+                .should_update(&policy, "1.2.3", "2.0.0-rc.1")
+                .unwrap()
+        );
+        assert!(engine.should_update(&policy, "1.2.3", "2.0.0-rc1").unwrap());
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.3", "2.0.0-beta.1")
+                .unwrap()
+        );
+        assert!(
+            engine
+                .should_update(&policy, "2.0.0-rc.1", "2.0.0")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_build_metadata_ignored() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Patch,
+            ..Default::default()
+        };
+
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.3+build.1", "1.2.3+build.2")
+                .unwrap()
+        );
+        assert!(
+            engine
+                .should_update(&policy, "1.2.3+build.9", "1.2.4+build.1")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pinned_digest_versions() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Minor,
+            pin_digest: true,
+            ..Default::default()
+        };
+
+        // The tag was pushed again
+        assert!(
+            engine
+                .should_update(&policy, "1.2.3@sha256:aaa", "1.2.3@sha256:bbb")
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.3@sha256:aaa", "1.2.3@sha256:aaa")
+                .unwrap()
+        );
+        // Pinning an unpinned tag
+        assert!(
+            engine
+                .should_update(&policy, "1.2.3", "1.2.3@sha256:aaa")
+                .unwrap()
+        );
+        // New tags still go through the policy
+        assert!(
+            engine
+                .should_update(&policy, "1.2.3@sha256:aaa", "1.3.0@sha256:bbb")
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.3@sha256:aaa", "2.0.0@sha256:bbb")
+                .unwrap()
+        );
+
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::None,
+            ..Default::default()
+        };
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.3@sha256:aaa", "1.2.3@sha256:bbb")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_digest_policy() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Digest,
+            ..Default::default()
+        };
+
+        assert!(
+            engine
+                .should_update(&policy, "latest", "latest@sha256:aaa")
+                .unwrap()
+        );
+        assert!(
+            engine
+                .should_update(&policy, "latest@sha256:aaa", "latest@sha256:bbb")
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .should_update(&policy, "latest@sha256:aaa", "latest@sha256:aaa")
+                .unwrap()
+        );
+        // The tag never changes, not even to a newer version
+        let evaluation = engine
+            .evaluate(&policy, "1.2.3@sha256:aaa", "1.3.0@sha256:bbb")
+            .unwrap();
+        assert!(!evaluation.update);
+        assert_eq!(evaluation.rule, PolicyRule::Digest);
+    }
+
+    #[test]
+    fn test_channel_tag() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::All,
+            channel_tag: Some("stable".to_string()),
+            ..Default::default()
+        };
+
+        // Moving onto the channel from a version tag, and following it
+        let evaluation = engine
+            .evaluate(&policy, "1.4.2", "stable@sha256:aaa")
+            .unwrap();
+        assert!(evaluation.update);
+        assert_eq!(evaluation.rule, PolicyRule::Channel);
+        assert!(
+            engine
+                .should_update(&policy, "stable@sha256:aaa", "stable@sha256:bbb")
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .should_update(&policy, "stable@sha256:aaa", "stable@sha256:aaa")
+                .unwrap()
+        );
+        // Other tags are ignored, even newer versions
+        let evaluation = engine
+            .evaluate(&policy, "stable@sha256:aaa", "1.5.0@sha256:ccc")
+            .unwrap();
+        assert!(!evaluation.update);
+        assert_eq!(evaluation.rule, PolicyRule::Channel);
+    }
+
     #[test]
     fn test_glob_matching() {
         assert!(glob_match("*", "anything"));
         assert!(glob_match("v1.*", "v1.2.3"));
         assert!(glob_match("*-beta", "v1.0.0-beta"));
         assert!(!glob_match("v1.*", "v2.0.0"));
+        assert!(glob_match("*-rc*", "1.3.0-rc.1"));
+        assert!(glob_match("v?.*", "v1.2.3"));
+        assert!(!glob_match("*-rc*", "1.3.0"));
+        assert!(!glob_match("nightly", "nightly-2024"));
+        // Regex metacharacters are literal in globs
+        assert!(glob_match("v1.2.*", "v1.2.9"));
+        assert!(!glob_match("v1.2.*", "v1x2.9"));
+        assert!(glob_match("build+*", "build+123"));
+    }
+
+    #[test]
+    fn test_regex_policy() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Regex,
+            pattern: Some(r"^v1\.2[0-9]\.\d+$".to_string()),
+            ..Default::default()
+        };
+
+        assert!(engine.should_update(&policy, "v1.20.0", "v1.24.3").unwrap());
+        assert!(!engine.should_update(&policy, "v1.20.0", "v1.30.0").unwrap());
+        assert!(
+            !engine
+                .should_update(&policy, "v1.20.0", "v1.24.3-rc.1")
+                .unwrap()
+        );
+
+        // No pattern never updates, an invalid one is an error
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Regex,
+            ..Default::default()
+        };
+        assert!(!engine.should_update(&policy, "v1.20.0", "v1.24.3").unwrap());
+
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Regex,
+            pattern: Some("v1.(".to_string()),
+            ..Default::default()
+        };
+        assert!(engine.should_update(&policy, "v1.20.0", "v1.24.3").is_err());
+    }
+
+    #[test]
+    fn test_ignore_patterns() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Major,
+            ignore_patterns: vec!["*-rc*".to_string(), "*-alpha*".to_string()],
+            ..Default::default()
+        };
+
+        // Valid semver prereleases are excluded
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.3", "1.3.0-rc.1")
+                .unwrap()
+        );
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.3", "2.0.0-alpha.2")
+                .unwrap()
+        );
+        assert!(engine.should_update(&policy, "1.2.3", "1.3.0").unwrap());
+
+        // Ignore patterns win over force and glob policies
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Force,
+            ignore_patterns: vec!["nightly".to_string()],
+            ..Default::default()
+        };
+        assert!(!engine.should_update(&policy, "latest", "nightly").unwrap());
+        assert!(engine.should_update(&policy, "latest", "stable").unwrap());
+    }
+
+    #[test]
+    fn test_version_ceiling() {
+        let matches = |ceiling: &str, version: &str| {
+            version_ceiling(ceiling)
+                .unwrap()
+                .matches(&Version::parse(version).unwrap())
+        };
+        assert!(matches("1.28.x", "1.28.9"));
+        assert!(!matches("1.28.x", "1.29.0"));
+        assert!(matches("1.28", "1.28.0"));
+        assert!(!matches("1.28", "1.29.0"));
+        assert!(matches("1", "1.99.0"));
+        assert!(!matches("1.x", "2.0.0"));
+        assert!(matches("1.28.3", "1.28.3"));
+        assert!(!matches("1.28.3", "1.28.4"));
+        assert!(matches(">=1.2, <2.0", "1.5.0"));
+        assert!(!matches(">=1.2, <2.0", "2.0.0"));
+        assert!(!matches("<1.28", "1.28.0"));
+
+        assert!(version_ceiling("latest").is_err());
+        assert!(version_ceiling("1.x.3").is_err());
+        assert!(version_ceiling("1.2.3.4").is_err());
+        assert!(version_ceiling(">=1.2, nope").is_err());
+    }
+
+    #[test]
+    fn test_max_version() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Major,
+            max_version: Some("1.28.x".to_string()),
+            ..Default::default()
+        };
+        assert!(engine.should_update(&policy, "1.27.4", "1.28.2").unwrap());
+        assert!(!engine.should_update(&policy, "1.27.4", "1.29.0").unwrap());
+        assert!(!engine.should_update(&policy, "1.27.4", "2.0.0").unwrap());
+        assert!(engine.should_update(&policy, "v1.27.4", "v1.28.2").unwrap());
+
+        // The ceiling applies to non-semver policies too; tags it can't place are held back
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::All,
+            max_version: Some(">=1.2, <2.0".to_string()),
+            ..Default::default()
+        };
+        assert!(engine.should_update(&policy, "1.2.0", "1.9.0").unwrap());
+        assert!(!engine.should_update(&policy, "1.2.0", "2.1.0").unwrap());
+        assert!(!engine.should_update(&policy, "1.2.0", "latest").unwrap());
+
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Minor,
+            max_version: Some("newest".to_string()),
+            ..Default::default()
+        };
+        assert!(engine.should_update(&policy, "1.2.0", "1.3.0").is_err());
+    }
+
+    #[test]
+    fn test_range_policy() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Range,
+            pattern: Some(">=1.2, <2.0".to_string()),
+            ..Default::default()
+        };
+        assert!(engine.should_update(&policy, "1.2.0", "1.9.3").unwrap());
+        assert!(engine.should_update(&policy, "v1.2.0", "v1.3.0").unwrap());
+        assert!(!engine.should_update(&policy, "1.2.0", "2.0.0").unwrap());
+        assert!(!engine.should_update(&policy, "1.5.0", "1.4.0").unwrap());
+        assert!(!engine.should_update(&policy, "1.2.0", "latest").unwrap());
+        assert!(
+            !engine
+                .should_update(&policy, "1.2.0", "1.3.0-rc.1")
+                .unwrap()
+        );
+
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Range,
+            pattern: Some("~1.4".to_string()),
+            prerelease: PrereleasePolicy {
+                allow: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(engine.should_update(&policy, "1.4.0", "1.4.7").unwrap());
+        assert!(
+            engine
+                .should_update(&policy, "1.4.0", "1.4.8-rc.1")
+                .unwrap()
+        );
+        assert!(!engine.should_update(&policy, "1.4.0", "1.5.0").unwrap());
+
+        // Without an expression nothing matches; an invalid one is an error
+        let mut policy = ResourcePolicy {
+            policy: UpdatePolicy::Range,
+            ..Default::default()
+        };
+        assert!(!engine.should_update(&policy, "1.2.0", "1.3.0").unwrap());
+        policy.pattern = Some("between 1 and 2".to_string());
+        assert!(engine.should_update(&policy, "1.2.0", "1.3.0").is_err());
+        assert_eq!(
+            "range".parse::<UpdatePolicy>().unwrap(),
+            UpdatePolicy::Range
+        );
+        assert_eq!(UpdatePolicy::Range.to_string(), "range");
+    }
+
+    #[test]
+    fn test_evaluate_explains_rule() {
+        let engine = PolicyEngine;
+        let mut policy = ResourcePolicy {
+            policy: UpdatePolicy::Patch,
+            ignore_patterns: vec!["*-debug".to_string()],
+            max_version: Some("1.2.x".to_string()),
+            ..Default::default()
+        };
+
+        let evaluation = engine.evaluate(&policy, "1.2.3", "1.2.4").unwrap();
+        assert!(evaluation.update);
+        assert_eq!(evaluation.rule, PolicyRule::Semver);
+
+        let evaluation = engine.evaluate(&policy, "1.2.3", "1.2.4-debug").unwrap();
+        assert!(!evaluation.update);
+        assert_eq!(evaluation.rule, PolicyRule::IgnorePattern);
+        assert!(evaluation.explanation.contains("*-debug"));
+
+        let evaluation = engine.evaluate(&policy, "1.2.3", "1.3.0").unwrap();
+        assert_eq!(evaluation.rule, PolicyRule::MaxVersion);
+
+        let evaluation = engine.evaluate(&policy, "1.2.3", "1.2.5-rc.1").unwrap();
+        assert_eq!(evaluation.rule, PolicyRule::Prerelease);
+
+        let evaluation = engine.evaluate(&policy, "1.2.4", "1.2.3").unwrap();
+        assert_eq!(evaluation.rule, PolicyRule::NotNewer);
+
+        policy.policy = UpdatePolicy::Glob;
+        let evaluation = engine.evaluate(&policy, "1.2.3", "1.2.4").unwrap();
+        assert!(!evaluation.update);
+        assert_eq!(evaluation.rule, PolicyRule::MissingPattern);
+
+        policy.policy = UpdatePolicy::None;
+        let evaluation = engine
+            .evaluate(&policy, "1.2.3@sha256:aaa", "1.2.3@sha256:bbb")
+            .unwrap();
+        assert_eq!(evaluation.rule, PolicyRule::PolicyNone);
+
+        assert!(engine.evaluate(&policy, "latest", "1.2.4").is_ok());
+        policy.policy = UpdatePolicy::Minor;
+        assert!(engine.evaluate(&policy, "latest", "1.2.4").is_err());
+    }
+
+    #[test]
+    fn test_is_newer_release() {
+        let engine = PolicyEngine;
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::None,
+            ignore_patterns: vec!["*-debug".to_string()],
+            max_version: Some("1.2.x".to_string()),
+            ..Default::default()
+        };
+
+        // Any newer release, above the ceiling too
+        assert!(engine.is_newer_release(&policy, "1.2.3", "1.2.4"));
+        assert!(engine.is_newer_release(&policy, "1.2.3", "2.0.0"));
+        assert!(!engine.is_newer_release(&policy, "1.2.3", "1.2.3"));
+        assert!(!engine.is_newer_release(&policy, "1.2.3", "1.2.2"));
+        assert!(!engine.is_newer_release(&policy, "1.2.3", "1.2.4-debug"));
+        assert!(!engine.is_newer_release(&policy, "1.2.3", "1.3.0-rc.1"));
+        assert!(!engine.is_newer_release(&policy, "latest", "1.2.4"));
     }
 }
@@ -0,0 +1,209 @@
+//! Label-selector based tracking via `UpdateTarget` resources
+//!
+//! An `UpdateTarget` contributes default `headwind.sh/*` annotations to every workload it
+//! selects. Annotations set directly on a workload always take precedence, so a workload can
+//! opt out of a target with `headwind.sh/policy: none`.
+
+use crate::models::UpdateTarget;
+use kube::{Api, Client, Resource, ResourceExt, api::ListParams};
+use std::collections::BTreeMap;
+use tracing::{debug, warn};
+
+/// List all UpdateTargets, sorted by name so overlapping targets resolve deterministically.
+///
+/// Returns an empty list if the CRD is not installed or cannot be listed.
+pub async fn list_update_targets(client: &Client) -> Vec<UpdateTarget> {
+    let api: Api<UpdateTarget> = Api::all(client.clone());
+    match api.list(&ListParams::default()).await {
+        Ok(list) => {
+            let mut targets = list.items;
+            targets.sort_by_key(|t| t.name_any());
+            targets
+        },
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            debug!("UpdateTarget CRD not installed, using annotations only");
+            Vec::new()
+        },
+        Err(e) => {
+            warn!(
+                "Failed to list UpdateTargets, using annotations only: {}",
+                e
+            );
+            Vec::new()
+        },
+    }
+}
+
+/// Compute the effective annotations for a workload.
+///
+/// The first target (by name) selecting the workload provides the base annotations and the
+/// workload's own annotations are layered on top. Without a matching target the workload's
+/// annotations are returned unchanged.
+pub fn merged_annotations(
+    kind: &str,
+    namespace: &str,
+    labels: &BTreeMap<String, String>,
+    annotations: Option<&BTreeMap<String, String>>,
+    targets: &[UpdateTarget],
+) -> Option<BTreeMap<String, String>> {
+    let Some(target) = targets
+        .iter()
+        .find(|t| t.spec.selects(kind, namespace, labels))
+    else {
+        return annotations.cloned();
+    };
+
+    debug!(
+        "{} {} selected by UpdateTarget {}",
+        kind,
+        namespace,
+        target.name_any()
+    );
+
+    let mut merged = target.spec.to_annotations();
+    if let Some(annotations) = annotations {
+        merged.extend(annotations.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    Some(merged)
+}
+
+/// Replace a workload's annotations with its effective annotations (in memory only), so
+/// code that reads `headwind.sh/*` annotations sees policies contributed by UpdateTargets.
+pub fn apply_update_targets<K>(resource: &mut K, targets: &[UpdateTarget])
+where
+    K: Resource<DynamicType = ()>,
+{
+    if targets.is_empty() {
+        return;
+    }
+
+    let kind = K::kind(&());
+    let namespace = resource.namespace().unwrap_or_default();
+    let merged = merged_annotations(
+        &kind,
+        &namespace,
+        resource.labels(),
+        resource.meta().annotations.as_ref(),
+        targets,
+    );
+    resource.meta_mut().annotations = merged;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotations;
+    use crate::models::updatetarget::{LabelSelector, UpdateTargetSpec};
+    use k8s_openapi::api::apps::v1::Deployment;
+
+    fn target(name: &str, team: &str, policy: &str) -> UpdateTarget {
+        let mut t = UpdateTarget::new(
+            name,
+            UpdateTargetSpec {
+                selector: LabelSelector {
+                    match_labels: [("team".to_string(), team.to_string())].into(),
+                    match_expressions: Vec::new(),
+                },
+                kinds: Vec::new(),
+                namespaces: Vec::new(),
+                policy: policy.to_string(),
+                pattern: None,
+                ignore_patterns: Vec::new(),
+                allow_prerelease: None,
+                prerelease_channel: None,
+                pin_digest: None,
+                require_approval: Some(false),
+                approval_ttl: None,
+                required_approvals: None,
+                schedule: None,
+                min_update_interval: None,
+                images: Vec::new(),
+                event_source: Some("polling".to_string()),
+                polling_interval: None,
+            },
+        );
+        t.metadata.name = Some(name.to_string());
+        t
+    }
+
+    fn deployment(labels: &[(&str, &str)], annotations: &[(&str, &str)]) -> Deployment {
+        let mut d = Deployment::default();
+        d.metadata.name = Some("api".to_string());
+        d.metadata.namespace = Some("default".to_string());
+        d.metadata.labels = Some(
+            labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        );
+        if !annotations.is_empty() {
+            d.metadata.annotations = Some(
+                annotations
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
+        }
+        d
+    }
+
+    #[test]
+    fn test_target_supplies_policy_for_unannotated_workload() {
+        let targets = vec![target("payments", "payments", "minor")];
+        let mut d = deployment(&[("team", "payments")], &[]);
+
+        apply_update_targets(&mut d, &targets);
+
+        let ann = d.metadata.annotations.unwrap();
+        assert_eq!(ann.get(annotations::POLICY).unwrap(), "minor");
+        assert_eq!(ann.get(annotations::REQUIRE_APPROVAL).unwrap(), "false");
+    }
+
+    #[test]
+    fn test_annotations_take_precedence() {
+        let targets = vec![target("payments", "payments", "minor")];
+        let mut d = deployment(
+            &[("team", "payments")],
+            &[
+                (annotations::POLICY, "patch"),
+                (annotations::EVENT_SOURCE, "webhook"),
+            ],
+        );
+
+        apply_update_targets(&mut d, &targets);
+
+        let ann = d.metadata.annotations.unwrap();
+        assert_eq!(ann.get(annotations::POLICY).unwrap(), "patch");
+        assert_eq!(ann.get(annotations::EVENT_SOURCE).unwrap(), "webhook");
+        // Keys not set on the workload still come from the target
+        assert_eq!(ann.get(annotations::REQUIRE_APPROVAL).unwrap(), "false");
+    }
+
+    #[test]
+    fn test_unselected_workload_is_unchanged() {
+        let targets = vec![target("payments", "payments", "minor")];
+        let mut d = deployment(&[("team", "search")], &[]);
+
+        apply_update_targets(&mut d, &targets);
+
+        assert!(d.metadata.annotations.is_none());
+    }
+
+    #[test]
+    fn test_first_target_by_name_wins() {
+        let targets = vec![
+            target("a-payments", "payments", "patch"),
+            target("b-payments", "payments", "major"),
+        ];
+        let merged = merged_annotations(
+            "Deployment",
+            "default",
+            &[("team".to_string(), "payments".to_string())].into(),
+            None,
+            &targets,
+        )
+        .unwrap();
+
+        assert_eq!(merged.get(annotations::POLICY).unwrap(), "patch");
+    }
+}
@@ -1,12 +1,44 @@
-use anyhow::{Context, Result};
+//! Registry credentials for polling: the imagePullSecrets of the namespace's default service
+//! account, or for cloud registries without one, short-lived tokens from the pod's workload
+//! identity (IRSA for ECR, GKE Workload Identity for GCR and Artifact Registry, AKS workload
+//! identity or a managed identity for ACR).
+
+use super::providers::{ProviderKind, providers};
+use crate::cache::{CacheLimits, SharedCache, new_shared_cache};
+use anyhow::{Context, Result, anyhow};
 use base64::prelude::*;
+use chrono::Utc;
 use k8s_openapi::api::core::v1::{Secret, ServiceAccount};
 use kube::{Api, Client};
 use oci_distribution::secrets::RegistryAuth;
+use once_cell::sync::Lazy;
+use reqwest::Url;
 use serde::Deserialize;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 use tracing::{debug, warn};
 
+/// Username for Google registries authenticated with an OAuth access token
+const GOOGLE_TOKEN_USERNAME: &str = "oauth2accesstoken";
+/// Username for ACR refresh tokens
+const ACR_TOKEN_USERNAME: &str = "00000000-0000-0000-0000-000000000000";
+/// Azure Resource Manager, the audience ACR accepts Azure AD tokens for
+const AZURE_ARM_RESOURCE: &str = "https://management.azure.com/";
+const AZURE_IMDS_TOKEN_URL: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+const AZURE_DEFAULT_AUTHORITY: &str = "https://login.microsoftonline.com/";
+/// ACR refresh tokens are valid for three hours
+const ACR_REFRESH_TOKEN_LIFETIME: Duration = Duration::from_secs(3 * 60 * 60);
+/// ECR authorization tokens are valid for twelve hours
+const ECR_TOKEN_LIFETIME: Duration = Duration::from_secs(12 * 60 * 60);
+/// Refresh workload identity tokens this long before they expire
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+/// Wait this long before asking a cloud for a token again after it failed
+const TOKEN_FAILURE_BACKOFF: Duration = Duration::from_secs(300);
+
+static WORKLOAD_IDENTITY: Lazy<WorkloadIdentity> = Lazy::new(WorkloadIdentity::new);
+
 /// Docker config.json structure
 #[derive(Debug, Deserialize)]
 struct DockerConfig {
@@ -37,14 +69,14 @@ pub struct RegistryCredentials {
 pub struct AuthManager {
     client: Client,
     /// Cache of registry -> credentials
-    credentials_cache: HashMap<String, RegistryCredentials>,
+    credentials_cache: SharedCache<RegistryCredentials>,
 }
 
 impl AuthManager {
     pub fn new(client: Client) -> Self {
         Self {
             client,
-            credentials_cache: HashMap::new(),
+            credentials_cache: new_shared_cache("credentials", CacheLimits::from_env()),
         }
     }
 
@@ -61,7 +93,7 @@ impl AuthManager {
         debug!("Getting auth for registry: {} (image: {})", registry, image);
 
         // Check cache first
-        if let Some(creds) = self.credentials_cache.get(&registry) {
+        if let Some(creds) = self.credentials_cache.read().await.get(&registry) {
             debug!("Using cached credentials for {}", registry);
             return Ok(RegistryAuth::Basic(
                 creds.username.clone(),
@@ -76,19 +108,35 @@ impl AuthManager {
                 let auth = RegistryAuth::Basic(creds.username.clone(), creds.password.clone());
 
                 // Cache credentials
-                self.credentials_cache.insert(registry.clone(), creds);
+                self.credentials_cache
+                    .write()
+                    .await
+                    .insert(registry.clone(), creds);
 
-                Ok(auth)
-            },
-            Ok(None) => {
-                debug!("No credentials found for {}, using anonymous", registry);
-                Ok(RegistryAuth::Anonymous)
-            },
-            Err(e) => {
-                warn!("Error fetching credentials for {}: {}", registry, e);
-                Ok(RegistryAuth::Anonymous)
+                return Ok(auth);
             },
+            Ok(None) => debug!("No pull secret credentials for {}", registry),
+            Err(e) => warn!("Error fetching credentials for {}: {}", registry, e),
         }
+
+        // Cloud registries without a pull secret use the pod's workload identity. Its tokens
+        // expire, so they're cached by WORKLOAD_IDENTITY rather than the credentials cache.
+        if workload_identity_enabled() {
+            match WORKLOAD_IDENTITY.credentials(&registry).await {
+                Ok(Some(creds)) => {
+                    debug!("Using workload identity credentials for {}", registry);
+                    return Ok(RegistryAuth::Basic(creds.username, creds.password));
+                },
+                Ok(None) => {},
+                Err(e) => warn!(
+                    "Workload identity authentication for {} failed: {:#}",
+                    registry, e
+                ),
+            }
+        }
+
+        debug!("No credentials found for {}, using anonymous", registry);
+        Ok(RegistryAuth::Anonymous)
     }
 
     /// Fetch credentials from Kubernetes secrets
@@ -239,11 +287,305 @@ impl AuthManager {
 
     /// Clear the credentials cache (useful for testing or credential rotation)
     #[allow(dead_code)] // Available for future credential rotation feature
-    pub fn clear_cache(&mut self) {
-        self.credentials_cache.clear();
+    pub async fn clear_cache(&mut self) {
+        self.credentials_cache.write().await.clear();
+    }
+}
+
+/// Whether cloud registries may be authenticated with the pod's workload identity
+/// (`HEADWIND_REGISTRY_WORKLOAD_IDENTITY`, default true)
+fn workload_identity_enabled() -> bool {
+    std::env::var("HEADWIND_REGISTRY_WORKLOAD_IDENTITY")
+        .map(|v| !v.eq_ignore_ascii_case("false"))
+        .unwrap_or(true)
+}
+
+/// Cloud whose workload identity can authenticate a registry host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloudRegistry {
+    Ecr,
+    Google,
+    Acr,
+}
+
+impl CloudRegistry {
+    /// Cloud of a registry host. `HEADWIND_REGISTRY_PROVIDERS` overrides count, so ACR
+    /// behind a custom domain is authenticated too.
+    fn detect(host: &str) -> Option<Self> {
+        let host = host.to_lowercase();
+        let kind = match providers().kind_for(&host) {
+            ProviderKind::Oci => ProviderKind::detect(&host),
+            kind => kind,
+        };
+        match kind {
+            ProviderKind::Ecr => Some(Self::Ecr),
+            ProviderKind::Gar => Some(Self::Google),
+            ProviderKind::Acr => Some(Self::Acr),
+            ProviderKind::Oci if host == "gcr.io" || host.ends_with(".gcr.io") => {
+                Some(Self::Google)
+            },
+            ProviderKind::Oci => None,
+        }
+    }
+}
+
+/// Registry token from a workload identity
+#[derive(Debug, Clone)]
+struct CloudToken {
+    username: String,
+    password: String,
+    refresh_at: Instant,
+}
+
+impl CloudToken {
+    /// Token valid for `lifetime`, refreshed a little before it runs out
+    fn new(username: &str, password: String, lifetime: Duration) -> Self {
+        Self {
+            username: username.to_string(),
+            password,
+            refresh_at: Instant::now() + lifetime.saturating_sub(TOKEN_REFRESH_MARGIN),
+        }
+    }
+
+    fn credentials(&self, registry: &str) -> RegistryCredentials {
+        RegistryCredentials {
+            registry: registry.to_string(),
+            username: self.username.clone(),
+            password: self.password.clone(),
+        }
+    }
+}
+
+/// Azure AD token response of both the token endpoint and IMDS, which sends `expires_in`
+/// as a string
+#[derive(Debug, Deserialize)]
+struct AzureToken {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Value,
+}
+
+impl AzureToken {
+    fn lifetime(&self) -> Duration {
+        let seconds = self
+            .expires_in
+            .as_u64()
+            .or_else(|| self.expires_in.as_str()?.parse().ok())
+            .unwrap_or(0);
+        Duration::from_secs(seconds)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AcrExchange {
+    refresh_token: String,
+}
+
+/// Short-lived registry credentials from the pod's cloud identity, cached per registry until
+/// shortly before they expire
+struct WorkloadIdentity {
+    http: reqwest::Client,
+    tokens: Mutex<HashMap<String, CloudToken>>,
+    /// Registries whose token request failed, and when to try again
+    failures: Mutex<HashMap<String, Instant>>,
+}
+
+impl WorkloadIdentity {
+    fn new() -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .user_agent("headwind")
+                .build()
+                .unwrap_or_default(),
+            tokens: Mutex::new(HashMap::new()),
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Credentials for `registry`, or None when it isn't a cloud registry or its last token
+    /// request failed recently
+    async fn credentials(&self, registry: &str) -> Result<Option<RegistryCredentials>> {
+        let Some(cloud) = CloudRegistry::detect(registry) else {
+            return Ok(None);
+        };
+        if self
+            .failures
+            .lock()
+            .await
+            .get(registry)
+            .is_some_and(|retry_at| Instant::now() < *retry_at)
+        {
+            return Ok(None);
+        }
+
+        let mut tokens = self.tokens.lock().await;
+        if let Some(token) = tokens.get(registry)
+            && Instant::now() < token.refresh_at
+        {
+            return Ok(Some(token.credentials(registry)));
+        }
+
+        let fetched = match cloud {
+            CloudRegistry::Ecr => self.ecr_token(registry).await,
+            CloudRegistry::Google => self.google_token().await,
+            CloudRegistry::Acr => self.acr_token(registry).await,
+        };
+        let token = match fetched {
+            Ok(token) => token,
+            Err(e) => {
+                tokens.remove(registry);
+                self.failures
+                    .lock()
+                    .await
+                    .insert(registry.to_string(), Instant::now() + TOKEN_FAILURE_BACKOFF);
+                return Err(e);
+            },
+        };
+        debug!(
+            "Refreshed {:?} workload identity token for {}",
+            cloud, registry
+        );
+        self.failures.lock().await.remove(registry);
+        let credentials = token.credentials(registry);
+        tokens.insert(registry.to_string(), token);
+        Ok(Some(credentials))
+    }
+
+    /// ECR `GetAuthorizationToken`, signed with the IRSA (or static) AWS credentials
+    async fn ecr_token(&self, registry: &str) -> Result<CloudToken> {
+        let authorization = providers().ecr_authorization(registry).await?;
+        let lifetime = authorization
+            .expires_at
+            .and_then(|expires_at| (expires_at - Utc::now()).to_std().ok())
+            .unwrap_or(ECR_TOKEN_LIFETIME);
+        Ok(CloudToken::new(
+            &authorization.username,
+            authorization.password,
+            lifetime,
+        ))
+    }
+
+    /// Access token of the pod's Google service account, accepted by GCR and Artifact Registry
+    async fn google_token(&self) -> Result<CloudToken> {
+        let (token, refresh_at) = crate::pubsub::metadata_access_token(&self.http).await?;
+        Ok(CloudToken {
+            username: GOOGLE_TOKEN_USERNAME.to_string(),
+            password: token,
+            refresh_at,
+        })
+    }
+
+    /// ACR refresh token, exchanged for an Azure AD token of the pod's identity
+    async fn acr_token(&self, registry: &str) -> Result<CloudToken> {
+        let aad = self.azure_token().await?;
+        let tenant = std::env::var("AZURE_TENANT_ID").ok();
+        let mut form = vec![
+            ("grant_type", "access_token"),
+            ("service", registry),
+            ("access_token", aad.access_token.as_str()),
+        ];
+        if let Some(tenant) = &tenant {
+            form.push(("tenant", tenant.as_str()));
+        }
+
+        let exchange: AcrExchange = self
+            .http
+            .post(format!("https://{}/oauth2/exchange", registry))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(form_body(&form))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", registry))?
+            .error_for_status()
+            .context("ACR token exchange failed")?
+            .json()
+            .await?;
+
+        let lifetime = match aad.lifetime() {
+            lifetime if lifetime.is_zero() => ACR_REFRESH_TOKEN_LIFETIME,
+            lifetime => lifetime.min(ACR_REFRESH_TOKEN_LIFETIME),
+        };
+        Ok(CloudToken::new(
+            ACR_TOKEN_USERNAME,
+            exchange.refresh_token,
+            lifetime,
+        ))
+    }
+
+    /// Azure AD token for Resource Manager: AKS workload identity when its federated token is
+    /// mounted (`AZURE_FEDERATED_TOKEN_FILE`), otherwise the managed identity from IMDS
+    async fn azure_token(&self) -> Result<AzureToken> {
+        let response = match std::env::var("AZURE_FEDERATED_TOKEN_FILE") {
+            Ok(token_file) => {
+                let client_id = std::env::var("AZURE_CLIENT_ID")
+                    .context("AZURE_FEDERATED_TOKEN_FILE is set but AZURE_CLIENT_ID is not")?;
+                let tenant = std::env::var("AZURE_TENANT_ID")
+                    .context("AZURE_FEDERATED_TOKEN_FILE is set but AZURE_TENANT_ID is not")?;
+                let assertion = tokio::fs::read_to_string(&token_file)
+                    .await
+                    .with_context(|| format!("Failed to read {}", token_file))?;
+                let authority = std::env::var("AZURE_AUTHORITY_HOST")
+                    .unwrap_or_else(|_| AZURE_DEFAULT_AUTHORITY.to_string());
+                let scope = format!("{}.default", AZURE_ARM_RESOURCE);
+
+                self.http
+                    .post(format!(
+                        "{}/{}/oauth2/v2.0/token",
+                        authority.trim_end_matches('/'),
+                        tenant
+                    ))
+                    .header("Content-Type", "application/x-www-form-urlencoded")
+                    .body(form_body(&[
+                        ("client_id", client_id.as_str()),
+                        ("scope", scope.as_str()),
+                        ("grant_type", "client_credentials"),
+                        (
+                            "client_assertion_type",
+                            "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+                        ),
+                        ("client_assertion", assertion.trim()),
+                    ]))
+                    .send()
+                    .await
+                    .context("Failed to reach Azure AD")?
+            },
+            Err(_) => {
+                let mut url = Url::parse_with_params(
+                    AZURE_IMDS_TOKEN_URL,
+                    &[
+                        ("api-version", "2018-02-01"),
+                        ("resource", AZURE_ARM_RESOURCE),
+                    ],
+                )?;
+                if let Ok(client_id) = std::env::var("AZURE_CLIENT_ID") {
+                    url.query_pairs_mut().append_pair("client_id", &client_id);
+                }
+                self.http
+                    .get(url)
+                    .header("Metadata", "true")
+                    .send()
+                    .await
+                    .context("Failed to reach the Azure instance metadata service")?
+            },
+        };
+
+        let response = response.error_for_status()?;
+        let token: AzureToken = response.json().await?;
+        if token.access_token.is_empty() {
+            return Err(anyhow!("Azure AD returned an empty access token"));
+        }
+        Ok(token)
     }
 }
 
+/// `application/x-www-form-urlencoded` body of `pairs`
+fn form_body(pairs: &[(&str, &str)]) -> String {
+    let mut url = Url::parse("http://localhost/").expect("valid URL");
+    url.query_pairs_mut().extend_pairs(pairs);
+    url.query().unwrap_or_default().to_string()
+}
+
 /// Extract registry hostname from image reference
 fn extract_registry_from_image(image: &str) -> String {
     // Parse image reference: [registry/]repository[:tag][@digest]
@@ -330,6 +672,49 @@ mod tests {
         assert!(!registry_matches("other.io", "docker.io"));
     }
 
+    #[test]
+    fn test_cloud_registry_detect() {
+        assert_eq!(
+            CloudRegistry::detect("123456789012.dkr.ecr.us-east-1.amazonaws.com"),
+            Some(CloudRegistry::Ecr)
+        );
+        assert_eq!(
+            CloudRegistry::detect("europe-west1-docker.pkg.dev"),
+            Some(CloudRegistry::Google)
+        );
+        assert_eq!(CloudRegistry::detect("gcr.io"), Some(CloudRegistry::Google));
+        assert_eq!(
+            CloudRegistry::detect("eu.gcr.io"),
+            Some(CloudRegistry::Google)
+        );
+        assert_eq!(
+            CloudRegistry::detect("myregistry.azurecr.io"),
+            Some(CloudRegistry::Acr)
+        );
+        assert_eq!(CloudRegistry::detect("docker.io"), None);
+        assert_eq!(CloudRegistry::detect("ghcr.io"), None);
+    }
+
+    #[test]
+    fn test_azure_token_lifetime() {
+        let imds: AzureToken =
+            serde_json::from_str(r#"{"access_token": "t", "expires_in": "3599"}"#).unwrap();
+        assert_eq!(imds.lifetime(), Duration::from_secs(3599));
+        let aad: AzureToken =
+            serde_json::from_str(r#"{"access_token": "t", "expires_in": 3600}"#).unwrap();
+        assert_eq!(aad.lifetime(), Duration::from_secs(3600));
+        let unknown: AzureToken = serde_json::from_str(r#"{"access_token": "t"}"#).unwrap();
+        assert_eq!(unknown.lifetime(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_form_body() {
+        assert_eq!(
+            form_body(&[("grant_type", "access_token"), ("service", "a b&c")]),
+            "grant_type=access_token&service=a+b%26c"
+        );
+    }
+
     #[test]
     fn test_parse_auth_entry() {
         // This test would require a k8s client, skip for now
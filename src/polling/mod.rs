@@ -1,23 +1,45 @@
 mod auth;
+mod providers;
 
 use self::auth::AuthManager;
+use self::providers::providers;
+use crate::cache::{CacheLimits, SharedCache, new_shared_cache};
+use crate::config::registries;
+use crate::controller::stores;
+use crate::controller::tracked::TrackedWorkload;
+use crate::helm::{HelmRepositoryClient, values};
 use crate::metrics::{
-    POLLING_CYCLES_TOTAL, POLLING_HELM_CHARTS_CHECKED, POLLING_HELM_NEW_VERSIONS_FOUND,
-    POLLING_IMAGES_CHECKED, POLLING_NEW_TAGS_FOUND, POLLING_RESOURCES_FILTERED,
+    POLLING_CYCLE_DURATION, POLLING_CYCLES_TOTAL, POLLING_ERRORS_TOTAL,
+    POLLING_HELM_CHARTS_CHECKED, POLLING_HELM_NEW_VERSIONS_FOUND, POLLING_IMAGES_CHECKED,
+    POLLING_NEW_TAGS_FOUND, POLLING_RESOURCES_FILTERED, TAGS_HELD_FOR_AGE,
+};
+use crate::models::crd::ImageMetadata;
+use crate::models::policy::{
+    DEFAULT_APPROVAL_TTL, EventSource, PrereleasePolicy, ResourcePolicy, UpdateMode, UpdatePolicy,
+    annotations, container_annotations, has_policy, parse_container_overrides,
+    parse_ignore_patterns, parse_max_version, parse_min_tag_age, parse_notify_detected,
 };
-use crate::models::policy::{EventSource, ResourcePolicy, UpdatePolicy, annotations};
 use crate::models::webhook::{ChartPushEvent, ImagePushEvent};
-use crate::models::{HelmRelease, HelmRepository};
+use crate::models::{HelmRelease, HelmRepository, HelmRepositorySpec, UpdateTarget};
 use crate::policy::PolicyEngine;
+use crate::policy::defaults::apply_policy_defaults;
+use crate::policy::flux::{FluxImagePolicy, apply_image_policies, list_image_policies};
+use crate::policy::targets::{apply_update_targets, list_update_targets};
+use crate::webhook::{parse_policy_from_annotations, provenance};
 use anyhow::Result;
-use k8s_openapi::api::apps::v1::Deployment;
-use kube::{Api, Client};
-use oci_distribution::{Client as OciClient, Reference, secrets::RegistryAuth};
-use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use k8s_openapi::api::batch::v1::CronJob;
+use kube::{Api, Client, ResourceExt};
+use oci_distribution::{Reference, secrets::RegistryAuth};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, RwLock, Semaphore};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
@@ -28,6 +50,10 @@ pub struct PollingConfig {
     pub interval: u64,
     /// Enable/disable polling
     pub enabled: bool,
+    /// Maximum number of images and charts polled at the same time
+    pub concurrency: usize,
+    /// Maximum number of concurrent requests to a single registry host
+    pub registry_concurrency: usize,
 }
 
 impl Default for PollingConfig {
@@ -35,8 +61,41 @@ impl Default for PollingConfig {
         Self {
             interval: 300,  // 5 minutes
             enabled: false, // Disabled by default, webhooks preferred
+            concurrency: 8,
+            registry_concurrency: 2,
+        }
+    }
+}
+
+/// Caps the number of in-flight polls per registry host so a cycle with many images from
+/// the same registry does not trip its rate limits
+struct RegistryLimiter {
+    permits: usize,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl RegistryLimiter {
+    fn new(permits: usize) -> Self {
+        Self {
+            permits: permits.max(1),
+            hosts: Mutex::new(HashMap::new()),
         }
     }
+
+    /// Wait for a free slot for `host`; the slot is released when the permit is dropped
+    async fn acquire(&self, host: &str) -> OwnedSemaphorePermit {
+        let semaphore = self
+            .hosts
+            .lock()
+            .await
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.permits)))
+            .clone();
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("registry semaphore is never closed")
+    }
 }
 
 /// Metadata for an image to track
@@ -46,6 +105,19 @@ pub(crate) struct ImageToTrack {
     policy: UpdatePolicy,
     #[allow(dead_code)] // Will be used for semver/glob matching in future
     pattern: Option<String>,
+    /// Tags excluded by `headwind.sh/ignore-pattern`
+    ignore_patterns: Vec<String>,
+    prerelease: PrereleasePolicy,
+    /// Whether the workload pins the image to its tag's digest
+    pin_digest: bool,
+    /// Channel tag the workload follows instead of its own tag (`headwind.sh/channel-tag`)
+    channel_tag: Option<String>,
+    /// Whether digest changes restart the workload rather than pin it (`headwind.sh/update-mode`)
+    update_mode: UpdateMode,
+    /// Seconds a new tag must have existed before it is acted on (`headwind.sh/min-tag-age`)
+    min_tag_age: Option<u64>,
+    /// Highest version updates may go to (`headwind.sh/max-version`)
+    max_version: Option<String>,
     namespace: String,
     /// Per-resource polling interval in seconds (overrides global interval)
     polling_interval: Option<u64>,
@@ -60,9 +132,18 @@ pub(crate) struct HelmChartToTrack {
     current_version: String,
     policy: UpdatePolicy,
     pattern: Option<String>,
+    /// Versions excluded by `headwind.sh/ignore-pattern`
+    ignore_patterns: Vec<String>,
+    prerelease: PrereleasePolicy,
+    /// Highest version updates may go to (`headwind.sh/max-version`)
+    max_version: Option<String>,
     namespace: String,
     #[allow(dead_code)] // May be used for correlation in future
     release_name: String, // HelmRelease name for correlation
+    /// Namespace of the HelmRepository, where its Secrets live
+    repository_namespace: String,
+    /// HelmRepository spec, for credentials and TLS settings
+    repository: HelmRepositorySpec,
     /// Per-resource polling interval in seconds (overrides global interval)
     polling_interval: Option<u64>,
 }
@@ -88,13 +169,199 @@ struct CachedChartInfo {
 }
 
 /// Tracks the last seen tag and digest for each image
-type ImageCache = Arc<RwLock<HashMap<String, CachedImageInfo>>>;
+type ImageCache = SharedCache<CachedImageInfo>;
 
 /// Tracks the last seen version for each Helm chart
-type ChartCache = Arc<RwLock<HashMap<String, CachedChartInfo>>>;
+type ChartCache = SharedCache<CachedChartInfo>;
 
 /// Tracks the last poll time for each resource (by unique key)
-type LastPollCache = Arc<RwLock<HashMap<String, std::time::Instant>>>;
+type LastPollCache = SharedCache<std::time::Instant>;
+
+/// Category of an error encountered during a poll cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PollErrorCategory {
+    /// Listing Deployments or HelmReleases from the Kubernetes API failed
+    Discovery,
+    /// Resolving registry credentials failed
+    Auth,
+    /// Fetching a manifest digest from the registry failed
+    Manifest,
+    /// Listing tags from the registry failed
+    TagList,
+    /// Fetching or parsing a Helm repository index.yaml failed
+    ChartIndex,
+    /// Handing a new version to the event processor failed
+    Event,
+    /// Invalid image references, malformed URLs and other failures
+    Other,
+}
+
+/// Summary of a single registry poll cycle
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PollCycleSummary {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub duration_ms: u64,
+    /// Images queried against their registry this cycle
+    pub images_checked: u64,
+    /// Images skipped because their polling interval had not elapsed
+    pub images_skipped: u64,
+    /// Helm charts queried against their repository this cycle
+    pub charts_checked: u64,
+    /// Helm charts skipped because their polling interval had not elapsed
+    pub charts_skipped: u64,
+    /// New image tags or digests found
+    pub new_image_versions: u64,
+    /// New Helm chart versions found
+    pub new_chart_versions: u64,
+    /// Error counts keyed by category
+    pub errors: BTreeMap<PollErrorCategory, u64>,
+}
+
+impl PollCycleSummary {
+    fn new() -> Self {
+        Self {
+            started_at: Utc::now(),
+            finished_at: None,
+            duration_ms: 0,
+            images_checked: 0,
+            images_skipped: 0,
+            charts_checked: 0,
+            charts_skipped: 0,
+            new_image_versions: 0,
+            new_chart_versions: 0,
+            errors: BTreeMap::new(),
+        }
+    }
+
+    fn record_error(&mut self, category: PollErrorCategory) {
+        *self.errors.entry(category).or_insert(0) += 1;
+        POLLING_ERRORS_TOTAL.inc();
+    }
+
+    /// Add the counts of a summary collected by a single poll task
+    fn merge(&mut self, other: PollCycleSummary) {
+        self.images_checked += other.images_checked;
+        self.images_skipped += other.images_skipped;
+        self.charts_checked += other.charts_checked;
+        self.charts_skipped += other.charts_skipped;
+        self.new_image_versions += other.new_image_versions;
+        self.new_chart_versions += other.new_chart_versions;
+        for (category, count) in other.errors {
+            *self.errors.entry(category).or_insert(0) += count;
+        }
+    }
+
+    /// Total number of errors across all categories
+    pub fn total_errors(&self) -> u64 {
+        self.errors.values().sum()
+    }
+
+    fn finish(&mut self, elapsed: Duration) {
+        self.finished_at = Some(Utc::now());
+        self.duration_ms = elapsed.as_millis() as u64;
+        POLLING_CYCLE_DURATION.observe(elapsed.as_secs_f64());
+    }
+
+    /// Emit the summary as a structured log event
+    fn log(&self) {
+        info!(
+            target: "headwind::polling",
+            images_checked = self.images_checked,
+            images_skipped = self.images_skipped,
+            charts_checked = self.charts_checked,
+            charts_skipped = self.charts_skipped,
+            new_image_versions = self.new_image_versions,
+            new_chart_versions = self.new_chart_versions,
+            errors = self.total_errors(),
+            error_categories = ?self.errors,
+            duration_ms = self.duration_ms,
+            "Registry poll cycle completed"
+        );
+    }
+}
+
+/// Summary of the most recently completed poll cycle
+static LAST_CYCLE: once_cell::sync::Lazy<std::sync::RwLock<Option<PollCycleSummary>>> =
+    once_cell::sync::Lazy::new(|| std::sync::RwLock::new(None));
+
+/// Get the summary of the most recently completed poll cycle, if any
+pub fn last_cycle_summary() -> Option<PollCycleSummary> {
+    LAST_CYCLE.read().ok().and_then(|guard| guard.clone())
+}
+
+fn store_cycle_summary(summary: PollCycleSummary) {
+    if let Ok(mut guard) = LAST_CYCLE.write() {
+        *guard = Some(summary);
+    }
+}
+
+/// When images (`repo:tag`) were first seen, for `headwind.sh/min-tag-age` checks of images
+/// without a usable creation time. Shared by the poller and the controllers, and never
+/// expired, so the age keeps growing across poll cycles.
+static TAG_FIRST_SEEN: once_cell::sync::Lazy<SharedCache<DateTime<Utc>>> =
+    once_cell::sync::Lazy::new(|| {
+        new_shared_cache(
+            "tagFirstSeen",
+            CacheLimits {
+                ttl: None,
+                ..CacheLimits::from_env()
+            },
+        )
+    });
+
+/// Whether `image` has existed for at least `min_age` seconds: since `created`, or when that
+/// is unknown, since Headwind first saw it
+async fn old_enough(image: &str, created: Option<DateTime<Utc>>, min_age: u64) -> bool {
+    let published = match created {
+        Some(created) => created,
+        None => {
+            let mut first_seen = TAG_FIRST_SEEN.write().await;
+            match first_seen.get(image) {
+                Some(seen) => *seen,
+                None => {
+                    let now = Utc::now();
+                    first_seen.insert(image.to_string(), now);
+                    now
+                },
+            }
+        },
+    };
+
+    let age = (Utc::now() - published).num_seconds().max(0) as u64;
+    if age >= min_age {
+        return true;
+    }
+    info!(
+        "Holding back {}: published {}s ago, minimum tag age is {}s",
+        image, age, min_age
+    );
+    TAGS_HELD_FOR_AGE.inc();
+    false
+}
+
+/// Whether `image` has existed for at least `min_age` seconds (`headwind.sh/min-tag-age`),
+/// authenticating with the pull secrets available in the namespace. The age is taken from the
+/// image's creation time, or from when Headwind first saw the image when the registry doesn't
+/// tell.
+pub async fn tag_old_enough(client: &Client, image: &str, namespace: &str, min_age: u64) -> bool {
+    let created = async {
+        let reference = Reference::try_from(image)?;
+        let auth = AuthManager::new(client.clone())
+            .get_auth_for_image(image, namespace)
+            .await?;
+        let tls = registries::for_host(reference.registry());
+        providers::fetch_created(&reference, &auth, &tls).await
+    }
+    .await
+    .unwrap_or_else(|e: anyhow::Error| {
+        debug!("No creation time for {}: {:#}", image, e);
+        None
+    });
+    old_enough(image, created, min_age).await
+}
 
 pub struct RegistryPoller {
     config: PollingConfig,
@@ -105,6 +372,8 @@ pub struct RegistryPoller {
     chart_event_sender: crate::webhook::ChartEventSender,
     client: Client,
     auth_manager: Arc<RwLock<AuthManager>>,
+    helm_repo_client: HelmRepositoryClient,
+    registry_limiter: RegistryLimiter,
 }
 
 impl RegistryPoller {
@@ -115,22 +384,30 @@ impl RegistryPoller {
     ) -> Result<Self> {
         let client = Client::try_default().await?;
         let auth_manager = AuthManager::new(client.clone());
+        let limits = CacheLimits::from_env();
+        let registry_limiter = RegistryLimiter::new(config.registry_concurrency);
+        let helm_repo_client = HelmRepositoryClient::with_client(client.clone())?;
         Ok(Self {
             config,
-            cache: Arc::new(RwLock::new(HashMap::new())),
-            chart_cache: Arc::new(RwLock::new(HashMap::new())),
-            last_poll_cache: Arc::new(RwLock::new(HashMap::new())),
+            cache: new_shared_cache("images", limits),
+            chart_cache: new_shared_cache("charts", limits),
+            last_poll_cache: new_shared_cache("lastPoll", limits),
             event_sender,
             chart_event_sender,
             client,
             auth_manager: Arc::new(RwLock::new(auth_manager)),
+            helm_repo_client,
+            registry_limiter,
         })
     }
 
     pub async fn start(self) -> JoinHandle<()> {
         info!(
-            "Starting registry poller (enabled: {}, interval: {}s)",
-            self.config.enabled, self.config.interval
+            "Starting registry poller (enabled: {}, interval: {}s, concurrency: {}, per registry: {})",
+            self.config.enabled,
+            self.config.interval,
+            self.config.concurrency,
+            self.config.registry_concurrency
         );
 
         tokio::spawn(async move {
@@ -160,177 +437,255 @@ impl RegistryPoller {
         POLLING_CYCLES_TOTAL.inc();
 
         let now = std::time::Instant::now();
+        let mut summary = PollCycleSummary::new();
+
+        // Drop expired entries so caches of removed resources don't linger
+        self.cache.write().await.purge_expired();
+        self.chart_cache.write().await.purge_expired();
+        self.last_poll_cache.write().await.purge_expired();
+
+        let result = self.poll_cycle(now, &mut summary).await;
+
+        summary.finish(now.elapsed());
+        summary.log();
+        store_cycle_summary(summary);
 
+        result
+    }
+
+    /// Run a single poll cycle over all tracked images and charts, recording into `summary`
+    async fn poll_cycle(
+        &self,
+        now: std::time::Instant,
+        summary: &mut PollCycleSummary,
+    ) -> Result<()> {
         // Get list of images to track from Kubernetes
-        let images = self.get_tracked_images().await?;
+        let images = self
+            .get_tracked_images()
+            .await
+            .inspect_err(|_| summary.record_error(PollErrorCategory::Discovery))?;
         info!("Found {} images to track", images.len());
 
-        // Poll each image for updates, respecting per-resource intervals
+        // Task summaries share the cycle's start, which provenance records as the poll cycle
+        let cycle_started_at = summary.started_at;
+
+        // Poll images that are due, up to `concurrency` at a time
+        let mut due_images = Vec::new();
         for image_info in images {
             let key = format!("image::{}", image_info.image);
             let interval = image_info.polling_interval.unwrap_or(self.config.interval);
-
-            // Check if enough time has elapsed since last poll
-            let should_poll = {
-                let last_poll_cache = self.last_poll_cache.read().await;
-                match last_poll_cache.get(&key) {
-                    Some(last_poll) => now.duration_since(*last_poll).as_secs() >= interval,
-                    None => true, // Never polled before
-                }
-            };
-
-            if !should_poll {
+            if self.is_due(&key, interval, now).await {
+                due_images.push((key, image_info));
+            } else {
                 debug!(
                     "Skipping image {} - interval {}s not elapsed",
                     image_info.image, interval
                 );
-                continue;
-            }
-
-            if let Err(e) = self.poll_image(&image_info).await {
-                error!("Failed to poll image {}: {}", image_info.image, e);
+                summary.images_skipped += 1;
             }
+        }
 
-            // Update last poll time
-            let mut last_poll_cache = self.last_poll_cache.write().await;
-            last_poll_cache.insert(key, now);
+        let mut results = futures::stream::iter(due_images)
+            .map(|(key, image_info)| async move {
+                let mut task_summary = PollCycleSummary {
+                    started_at: cycle_started_at,
+                    ..PollCycleSummary::new()
+                };
+                let _permit = self
+                    .registry_limiter
+                    .acquire(&image_registry(&image_info.image))
+                    .await;
+                if let Err(e) = self.poll_image(&image_info, &mut task_summary).await {
+                    error!("Failed to poll image {}: {}", image_info.image, e);
+                }
+                (key, task_summary)
+            })
+            .buffer_unordered(self.config.concurrency.max(1));
+        while let Some((key, task_summary)) = results.next().await {
+            summary.merge(task_summary);
+            self.last_poll_cache.write().await.insert(key, now);
         }
 
         // Get list of Helm charts to track from Kubernetes
-        let charts = self.get_tracked_helm_releases().await?;
+        let charts = self
+            .get_tracked_helm_releases()
+            .await
+            .inspect_err(|_| summary.record_error(PollErrorCategory::Discovery))?;
         info!("Found {} Helm charts to track", charts.len());
 
-        // Poll each chart for updates based on repository type, respecting per-resource intervals
+        // Poll charts that are due based on repository type, up to `concurrency` at a time
+        let mut due_charts = Vec::new();
         for chart_info in charts {
             let key = format!("chart::{}", chart_info.repository_url);
             let interval = chart_info.polling_interval.unwrap_or(self.config.interval);
-
-            // Check if enough time has elapsed since last poll
-            let should_poll = {
-                let last_poll_cache = self.last_poll_cache.read().await;
-                match last_poll_cache.get(&key) {
-                    Some(last_poll) => now.duration_since(*last_poll).as_secs() >= interval,
-                    None => true, // Never polled before
-                }
-            };
-
-            if !should_poll {
+            if self.is_due(&key, interval, now).await {
+                due_charts.push((key, chart_info));
+            } else {
                 debug!(
                     "Skipping chart {} - interval {}s not elapsed",
                     chart_info.chart_name, interval
                 );
-                continue;
-            }
-
-            let result = match chart_info.repository_type {
-                HelmRepositoryType::Oci => self.poll_oci_helm_chart(&chart_info).await,
-                HelmRepositoryType::Http => self.poll_http_helm_chart(&chart_info).await,
-            };
-
-            if let Err(e) = result {
-                error!(
-                    "Failed to poll {:?} Helm chart {}: {}",
-                    chart_info.repository_type, chart_info.chart_name, e
-                );
+                summary.charts_skipped += 1;
             }
+        }
 
-            // Update last poll time
-            let mut last_poll_cache = self.last_poll_cache.write().await;
-            last_poll_cache.insert(key, now);
+        let mut results = futures::stream::iter(due_charts)
+            .map(|(key, chart_info)| async move {
+                let mut task_summary = PollCycleSummary {
+                    started_at: cycle_started_at,
+                    ..PollCycleSummary::new()
+                };
+                let _permit = self
+                    .registry_limiter
+                    .acquire(&chart_registry(&chart_info.repository_url))
+                    .await;
+                let result = match chart_info.repository_type {
+                    HelmRepositoryType::Oci => {
+                        self.poll_oci_helm_chart(&chart_info, &mut task_summary)
+                            .await
+                    },
+                    HelmRepositoryType::Http => {
+                        self.poll_http_helm_chart(&chart_info, &mut task_summary)
+                            .await
+                    },
+                };
+                if let Err(e) = result {
+                    error!(
+                        "Failed to poll {:?} Helm chart {}: {}",
+                        chart_info.repository_type, chart_info.chart_name, e
+                    );
+                }
+                (key, task_summary)
+            })
+            .buffer_unordered(self.config.concurrency.max(1));
+        while let Some((key, task_summary)) = results.next().await {
+            summary.merge(task_summary);
+            self.last_poll_cache.write().await.insert(key, now);
         }
 
-        info!("Registry poll cycle completed");
         Ok(())
     }
 
-    /// Get the list of images to track from Kubernetes Deployments
+    /// Whether the resource behind `key` has not been polled within `interval` seconds
+    async fn is_due(&self, key: &str, interval: u64, now: std::time::Instant) -> bool {
+        match self.last_poll_cache.read().await.get(key) {
+            Some(last_poll) => now.duration_since(*last_poll).as_secs() >= interval,
+            None => true, // Never polled before
+        }
+    }
+
+    /// Get the list of images to track from Kubernetes workloads (Deployments, StatefulSets,
+    /// DaemonSets and CronJobs)
     async fn get_tracked_images(&self) -> Result<Vec<ImageToTrack>> {
-        let deployments: Api<Deployment> = Api::all(self.client.clone());
-        let deployment_list = deployments.list(&Default::default()).await?;
+        let targets = list_update_targets(&self.client).await;
+        let image_policies = list_image_policies(&self.client).await;
 
         let mut images = Vec::new();
         let mut seen = HashSet::new(); // Track unique image+policy combinations
 
-        for deployment in deployment_list.items {
-            let metadata = &deployment.metadata;
-            let annotations = match &metadata.annotations {
-                Some(ann) => ann,
-                None => continue,
-            };
+        track_workload_images(
+            stores::list::<Deployment>(&self.client).await?,
+            &targets,
+            &image_policies,
+            &mut seen,
+            &mut images,
+        );
+        track_workload_images(
+            stores::list::<StatefulSet>(&self.client).await?,
+            &targets,
+            &image_policies,
+            &mut seen,
+            &mut images,
+        );
+        track_workload_images(
+            stores::list::<DaemonSet>(&self.client).await?,
+            &targets,
+            &image_policies,
+            &mut seen,
+            &mut images,
+        );
+        track_workload_images(
+            stores::list::<CronJob>(&self.client).await?,
+            &targets,
+            &image_policies,
+            &mut seen,
+            &mut images,
+        );
 
-            // Skip deployments without headwind policy annotation
-            let policy_str = match annotations.get(annotations::POLICY) {
-                Some(p) if p != "none" => p,
-                _ => continue,
+        // Images pinned in HelmRelease values. HelmReleases are optional, so failing to list
+        // them doesn't stop deployment images from being polled.
+        let release_list = stores::list::<HelmRelease>(&self.client)
+            .await
+            .unwrap_or_else(|e| {
+                debug!("Not polling HelmRelease values images: {}", e);
+                Vec::new()
+            });
+        for helm_release in release_list {
+            let Some(annotations) = &helm_release.metadata.annotations else {
+                continue;
             };
-
-            let policy = match UpdatePolicy::from_str(policy_str) {
-                Ok(p) => p,
-                Err(e) => {
-                    warn!("Invalid policy '{}': {}", policy_str, e);
-                    continue;
-                },
+            let Some(paths) = annotations.get(annotations::VALUES_IMAGE_PATHS) else {
+                continue;
+            };
+            let policy = match annotations.get(annotations::POLICY).map(|p| p.parse()) {
+                Some(Ok(policy)) if policy != UpdatePolicy::None => policy,
+                _ => continue,
             };
-
-            // Check event source - only poll if event_source is "polling" or "both"
             let event_source = annotations
                 .get(annotations::EVENT_SOURCE)
                 .and_then(|v| v.parse::<EventSource>().ok())
-                .unwrap_or_default(); // defaults to Webhook
-
+                .unwrap_or_default();
             if event_source != EventSource::Polling && event_source != EventSource::Both {
-                debug!(
-                    "Skipping deployment {}/{} - event source is {:?}, not polling",
-                    metadata
-                        .namespace
-                        .as_ref()
-                        .unwrap_or(&"default".to_string()),
-                    metadata.name.as_ref().unwrap_or(&"unknown".to_string()),
-                    event_source
-                );
                 POLLING_RESOURCES_FILTERED.inc();
                 continue;
             }
 
-            let pattern = annotations.get(annotations::PATTERN).cloned();
-
-            // Parse per-resource polling interval (overrides global interval)
-            let polling_interval = annotations
-                .get(annotations::POLLING_INTERVAL)
-                .and_then(|v| v.parse::<u64>().ok());
-
-            debug!(
-                "Processing deployment {}/{} with policy {:?}",
-                metadata
-                    .namespace
-                    .as_ref()
-                    .unwrap_or(&"default".to_string()),
-                metadata.name.as_ref().unwrap_or(&"unknown".to_string()),
-                policy
-            );
-
-            // Extract images from pod template
-            if let Some(spec) = &deployment.spec
-                && let Some(template) = &spec.template.spec
-            {
-                for container in &template.containers {
-                    if let Some(image) = &container.image {
-                        // Create unique key for deduplication
-                        let key = format!("{}::{:?}", image, policy);
-                        if seen.insert(key) {
-                            debug!("  Adding image to track: {} (policy: {:?})", image, policy);
-                            images.push(ImageToTrack {
-                                image: image.clone(),
-                                policy,
-                                pattern: pattern.clone(),
-                                namespace: metadata
-                                    .namespace
-                                    .clone()
-                                    .unwrap_or_else(|| "default".to_string()),
-                                polling_interval,
-                            });
-                        }
-                    }
+            let ignore_patterns = annotations
+                .get(annotations::IGNORE_PATTERN)
+                .map(|v| parse_ignore_patterns(v))
+                .unwrap_or_default();
+            let prerelease = PrereleasePolicy::from_annotations(annotations);
+            let min_tag_age = parse_min_tag_age(annotations);
+            let max_version = parse_max_version(annotations);
+
+            for values_image in values::find_images(
+                helm_release.spec.values.as_ref(),
+                &values::parse_paths(paths),
+            ) {
+                let key = format!(
+                    "{}::{:?}::{:?}::{:?}::false::{:?}::{:?}",
+                    values_image.image,
+                    policy,
+                    ignore_patterns,
+                    prerelease,
+                    min_tag_age,
+                    max_version
+                );
+                if seen.insert(key) {
+                    debug!(
+                        "  Adding HelmRelease values image to track: {} (policy: {:?})",
+                        values_image.image, policy
+                    );
+                    images.push(ImageToTrack {
+                        image: values_image.image,
+                        policy,
+                        pattern: annotations.get(annotations::PATTERN).cloned(),
+                        ignore_patterns: ignore_patterns.clone(),
+                        prerelease: prerelease.clone(),
+                        pin_digest: false,
+                        channel_tag: None,
+                        update_mode: UpdateMode::Image,
+                        min_tag_age,
+                        max_version: max_version.clone(),
+                        namespace: helm_release
+                            .metadata
+                            .namespace
+                            .clone()
+                            .unwrap_or_else(|| "default".to_string()),
+                        polling_interval: annotations
+                            .get(annotations::POLLING_INTERVAL)
+                            .and_then(|v| v.parse::<u64>().ok()),
+                    });
                 }
             }
         }
@@ -342,32 +697,69 @@ impl RegistryPoller {
     /// Checks both for digest changes (same-tag updates) and new tags (new versions)
     #[allow(dead_code)]
     #[allow(private_interfaces)]
-    pub async fn poll_image(&self, image_info: &ImageToTrack) -> Result<Option<String>> {
+    pub async fn poll_image(
+        &self,
+        image_info: &ImageToTrack,
+        summary: &mut PollCycleSummary,
+    ) -> Result<Option<String>> {
         let image = &image_info.image;
-        let reference = Reference::try_from(image.as_str())?;
-        let current_tag = reference.tag().unwrap_or("latest");
+        let reference = match Reference::try_from(image.as_str()) {
+            Ok(r) => r,
+            Err(e) => {
+                summary.record_error(PollErrorCategory::Other);
+                return Err(e.into());
+            },
+        };
+        // Images following a channel tag are checked against the channel, not their own tag
+        let current_tag = image_info
+            .channel_tag
+            .as_deref()
+            .unwrap_or(reference.tag().unwrap_or("latest"));
 
         debug!(
             "Polling image: {} (tag: {}, policy: {:?})",
             image, current_tag, image_info.policy
         );
-        POLLING_IMAGES_CHECKED.inc();
-
-        // Create OCI client
-        let client = OciClient::new(Default::default());
+        POLLING_IMAGES_CHECKED
+            .with_label_values(&[reference.registry()])
+            .inc();
+        summary.images_checked += 1;
 
         // Get authentication for this image
         let mut auth_manager = self.auth_manager.write().await;
-        let auth = auth_manager
+        let auth = match auth_manager
             .get_auth_for_image(&image_info.image, &image_info.namespace)
-            .await?;
+            .await
+        {
+            Ok(auth) => auth,
+            Err(e) => {
+                summary.record_error(PollErrorCategory::Auth);
+                return Err(e);
+            },
+        };
         drop(auth_manager);
 
+        // Pinned images (`repo:tag@sha256:...`) are resolved through their tag, so the digest
+        // the tag points to now can be compared with the pinned one
+        let tag_reference = if reference.digest().is_some() || reference.tag() != Some(current_tag)
+        {
+            Reference::with_tag(
+                reference.registry().to_string(),
+                reference.repository().to_string(),
+                current_tag.to_string(),
+            )
+        } else {
+            reference.clone()
+        };
+
+        let tls = registries::for_host(reference.registry());
+
         // Step 1: Check if the current tag's digest has changed
-        let current_digest = match client.fetch_manifest_digest(&reference, &auth).await {
+        let current_digest = match providers().fetch_digest(&tag_reference, &auth, &tls).await {
             Ok(d) => d,
             Err(e) => {
                 warn!("Failed to fetch digest for {}: {}", image, e);
+                summary.record_error(PollErrorCategory::Manifest);
                 return Ok(None);
             },
         };
@@ -379,6 +771,37 @@ impl RegistryPoller {
             &current_digest[..16]
         );
 
+        // Pinned workloads follow their tag: re-pin as soon as the tag moves (or pin it for
+        // the first time) rather than waiting for a cached digest to compare with. Workloads
+        // following a channel tag also move onto the channel from any other tag. Restarted
+        // workloads keep an unpinned image, so their digest changes are found through the cache.
+        let digest_only =
+            image_info.policy == UpdatePolicy::Digest || image_info.channel_tag.is_some();
+        let restarted = image_info.update_mode == UpdateMode::Restart;
+        let following = (image_info.pin_digest || digest_only) && !restarted;
+        if following
+            && (reference.digest() != Some(current_digest.as_str())
+                || reference.tag() != Some(current_tag))
+        {
+            info!(
+                "Tag {}:{} now points to {}, updating pinned digest",
+                reference.repository(),
+                current_tag,
+                current_digest
+            );
+            self.send_update_event(&reference, current_tag, &current_digest, summary)
+                .await?;
+            POLLING_NEW_TAGS_FOUND
+                .with_label_values(&[reference.registry()])
+                .inc();
+            summary.new_image_versions += 1;
+            return Ok(Some(current_digest));
+        }
+        // Other tags don't matter to an image following a channel or the digest of its tag
+        if digest_only && !restarted {
+            return Ok(None);
+        }
+
         // Check cache
         let cache = self.cache.read().await;
         let cached_info = cache.get(image).cloned(); // Clone to avoid borrow issues
@@ -408,8 +831,12 @@ impl RegistryPoller {
                 drop(cache);
 
                 // Send event for digest change
-                self.send_update_event(&reference, current_tag, &current_digest)?;
-                POLLING_NEW_TAGS_FOUND.inc();
+                self.send_update_event(&reference, current_tag, &current_digest, summary)
+                    .await?;
+                POLLING_NEW_TAGS_FOUND
+                    .with_label_values(&[reference.registry()])
+                    .inc();
+                summary.new_image_versions += 1;
                 return Ok(Some(current_digest));
             }
         } else {
@@ -426,11 +853,15 @@ impl RegistryPoller {
             drop(cache);
         }
 
+        if digest_only {
+            return Ok(None);
+        }
+
         // Step 2: Check for new tags (if policy allows)
         if image_info.policy != UpdatePolicy::None
             && image_info.policy != UpdatePolicy::Force
             && let Some(new_tag) = self
-                .check_for_new_tags(&client, &reference, &auth, image_info)
+                .check_for_new_tags(&reference, &auth, image_info, summary)
                 .await?
         {
             info!(
@@ -439,10 +870,13 @@ impl RegistryPoller {
             );
 
             // Fetch digest for the new tag
-            let new_ref_str = format!("{}:{}", reference.repository(), new_tag);
-            let new_ref = Reference::try_from(new_ref_str.as_str())?;
+            let new_ref = Reference::with_tag(
+                reference.registry().to_string(),
+                reference.repository().to_string(),
+                new_tag.clone(),
+            );
 
-            if let Ok(new_digest) = client.fetch_manifest_digest(&new_ref, &auth).await {
+            if let Ok(new_digest) = providers().fetch_digest(&new_ref, &auth, &tls).await {
                 // Update cache to new tag
                 let mut cache = self.cache.write().await;
                 cache.insert(
@@ -455,8 +889,12 @@ impl RegistryPoller {
                 drop(cache);
 
                 // Send event for new tag
-                self.send_update_event(&reference, &new_tag, &new_digest)?;
-                POLLING_NEW_TAGS_FOUND.inc();
+                self.send_update_event(&reference, &new_tag, &new_digest, summary)
+                    .await?;
+                POLLING_NEW_TAGS_FOUND
+                    .with_label_values(&[reference.registry()])
+                    .inc();
+                summary.new_image_versions += 1;
                 return Ok(Some(new_digest));
             }
         }
@@ -467,20 +905,22 @@ impl RegistryPoller {
     /// Check for new tags that match the policy
     async fn check_for_new_tags(
         &self,
-        client: &OciClient,
         reference: &Reference,
         auth: &RegistryAuth,
         image_info: &ImageToTrack,
+        summary: &mut PollCycleSummary,
     ) -> Result<Option<String>> {
         // List available tags
-        let tag_response = match client.list_tags(reference, auth, None, None).await {
-            Ok(resp) => resp,
+        let tls = registries::for_host(reference.registry());
+        let tags = match providers().list_tags(reference, auth, &tls).await {
+            Ok(tags) => tags,
             Err(e) => {
                 debug!(
                     "Failed to list tags for {}: {} (registry may not support listing)",
                     reference.repository(),
                     e
                 );
+                summary.record_error(PollErrorCategory::TagList);
                 return Ok(None);
             },
         };
@@ -489,7 +929,7 @@ impl RegistryPoller {
 
         debug!(
             "Found {} tags for {} (current: {}, policy: {:?})",
-            tag_response.tags.len(),
+            tags.len(),
             reference.repository(),
             current_tag,
             image_info.policy
@@ -504,62 +944,30 @@ impl RegistryPoller {
             images: vec![],
             event_source: Default::default(),
             polling_interval: None,
+            ignore_patterns: image_info.ignore_patterns.clone(),
+            prerelease: image_info.prerelease.clone(),
+            pin_digest: image_info.pin_digest,
+            channel_tag: image_info.channel_tag.clone(),
+            update_mode: image_info.update_mode,
+            approval_ttl: DEFAULT_APPROVAL_TTL,
+            required_approvals: None,
+            max_version: image_info.max_version.clone(),
         };
 
-        let policy_engine = PolicyEngine;
-        let mut best_version: Option<String> = None;
-
-        // Find the best matching tag according to policy
-        for tag in &tag_response.tags {
-            // Skip non-version-looking tags for semver policies
-            if matches!(
-                image_info.policy,
-                UpdatePolicy::Patch | UpdatePolicy::Minor | UpdatePolicy::Major
-            ) {
-                // Quick sanity check: does it look like a version?
-                // Must start with digit or 'v'
-                if !tag
-                    .chars()
-                    .next()
-                    .is_some_and(|c| c.is_ascii_digit() || c == 'v')
-                {
-                    debug!("Skipping non-version tag: {}", tag);
-                    continue;
-                }
-            }
-
-            // Check if this tag should be considered for update
-            match policy_engine.should_update(&resource_policy, current_tag, tag) {
-                Ok(true) => {
-                    debug!("Tag {} matches policy {:?}", tag, image_info.policy);
-
-                    // If we don't have a best version yet, or this one is better
-                    if best_version.is_none() {
-                        best_version = Some(tag.clone());
-                    } else if let Some(ref current_best) = best_version {
-                        // Check if new tag is better than current best
-                        match policy_engine.should_update(&resource_policy, current_best, tag) {
-                            Ok(true) => {
-                                debug!("Tag {} is better than current best {}", tag, current_best);
-                                best_version = Some(tag.clone());
-                            },
-                            Ok(false) => {
-                                debug!("Tag {} is not better than {}", tag, current_best);
-                            },
-                            Err(e) => {
-                                debug!("Failed to compare {} with {}: {}", tag, current_best, e);
-                            },
-                        }
-                    }
-                },
-                Ok(false) => {
-                    debug!("Tag {} does not match policy", tag);
-                },
-                Err(e) => {
-                    debug!("Failed to check if tag {} matches policy: {}", tag, e);
+        // The newest tags may be too young (`headwind.sh/min-tag-age`): fall back to the best
+        // tag old enough, checking candidates from the best down
+        let mut candidates = tags;
+        let best_version = loop {
+            let Some(best) = best_tag(&resource_policy, current_tag, &candidates) else {
+                break None;
+            };
+            match image_info.min_tag_age {
+                Some(min_age) if !self.tag_old_enough(reference, &best, auth, min_age).await => {
+                    candidates.retain(|tag| *tag != best);
                 },
+                _ => break Some(best),
             }
-        }
+        };
 
         if let Some(ref best) = best_version {
             info!(
@@ -574,17 +982,54 @@ impl RegistryPoller {
         Ok(best_version)
     }
 
-    /// Send an update event for a new image version
-    fn send_update_event(&self, reference: &Reference, tag: &str, digest: &str) -> Result<()> {
+    /// Whether `tag` of the image `reference` has existed for at least `min_age` seconds
+    async fn tag_old_enough(
+        &self,
+        reference: &Reference,
+        tag: &str,
+        auth: &RegistryAuth,
+        min_age: u64,
+    ) -> bool {
+        let tag_reference = Reference::with_tag(
+            reference.registry().to_string(),
+            reference.repository().to_string(),
+            tag.to_string(),
+        );
+        let tls = registries::for_host(reference.registry());
+        let created = providers::fetch_created(&tag_reference, auth, &tls)
+            .await
+            .unwrap_or_else(|e| {
+                debug!("No creation time for {}: {:#}", tag_reference, e);
+                None
+            });
+        old_enough(&tag_reference.whole(), created, min_age).await
+    }
+
+    /// Send an update event for a new image version found by the poll cycle of `summary`,
+    /// waiting while the event queue is full
+    async fn send_update_event(
+        &self,
+        reference: &Reference,
+        tag: &str,
+        digest: &str,
+        summary: &mut PollCycleSummary,
+    ) -> Result<()> {
+        let registry = extract_registry(reference.registry());
         let event = ImagePushEvent {
-            registry: extract_registry(reference.registry()),
+            source: Some(provenance::polling(
+                &registry,
+                summary.started_at,
+                Some(digest),
+            )),
+            registry,
             repository: reference.repository().to_string(),
             tag: tag.to_string(),
             digest: Some(digest.to_string()),
         };
 
-        if let Err(e) = self.event_sender.send(event) {
+        if let Err(e) = self.event_sender.send(event).await {
             error!("Failed to send polling event: {}", e);
+            summary.record_error(PollErrorCategory::Event);
         }
 
         Ok(())
@@ -592,13 +1037,12 @@ impl RegistryPoller {
 
     /// Get the list of Helm charts to track from Kubernetes HelmReleases
     async fn get_tracked_helm_releases(&self) -> Result<Vec<HelmChartToTrack>> {
-        let helm_releases: Api<HelmRelease> = Api::all(self.client.clone());
-        let release_list = helm_releases.list(&Default::default()).await?;
+        let release_list = stores::list::<HelmRelease>(&self.client).await?;
 
         let mut charts = Vec::new();
         let mut seen = HashSet::new(); // Track unique chart+policy combinations
 
-        for helm_release in release_list.items {
+        for helm_release in release_list {
             let metadata = &helm_release.metadata;
             let annotations = match &metadata.annotations {
                 Some(ann) => ann,
@@ -644,6 +1088,11 @@ impl RegistryPoller {
             }
 
             let pattern = annotations.get(annotations::PATTERN).cloned();
+            let ignore_patterns = annotations
+                .get(annotations::IGNORE_PATTERN)
+                .map(|v| parse_ignore_patterns(v))
+                .unwrap_or_default();
+            let prerelease = PrereleasePolicy::from_annotations(annotations);
 
             // Parse per-resource polling interval (overrides global interval)
             let polling_interval = annotations
@@ -711,12 +1160,14 @@ impl RegistryPoller {
             };
 
             // Create unique key for deduplication
-            let key = format!("{}::{:?}", repository_url, policy);
+            let max_version = parse_max_version(annotations);
+            let key = format!("{}::{:?}::{:?}", repository_url, policy, max_version);
             if seen.insert(key) {
                 debug!(
                     "  Adding Helm chart to track: {} (type: {:?}, version: {}, policy: {:?})",
                     repository_url, repository_type, current_version, policy
                 );
+                let repository_namespace = repo_namespace.clone();
                 charts.push(HelmChartToTrack {
                     chart_name: chart_name.clone(),
                     repository_url,
@@ -724,8 +1175,13 @@ impl RegistryPoller {
                     current_version,
                     policy,
                     pattern,
+                    ignore_patterns,
+                    prerelease,
+                    max_version,
                     namespace,
                     release_name,
+                    repository_namespace,
+                    repository: helm_repo.spec.clone(),
                     polling_interval,
                 });
             }
@@ -735,42 +1191,88 @@ impl RegistryPoller {
     }
 
     /// Poll a specific OCI Helm chart for updates
-    async fn poll_oci_helm_chart(&self, chart_info: &HelmChartToTrack) -> Result<()> {
+    async fn poll_oci_helm_chart(
+        &self,
+        chart_info: &HelmChartToTrack,
+        summary: &mut PollCycleSummary,
+    ) -> Result<()> {
         debug!(
             "Polling OCI Helm chart: {} (version: {}, policy: {:?})",
             chart_info.repository_url, chart_info.current_version, chart_info.policy
         );
         POLLING_HELM_CHARTS_CHECKED.inc();
+        summary.charts_checked += 1;
 
         // Parse OCI URL to get registry and repository
         // Format: oci://registry.io/path/to/chart
-        let url_without_scheme = chart_info
-            .repository_url
-            .strip_prefix("oci://")
-            .ok_or_else(|| anyhow::anyhow!("Invalid OCI URL: {}", chart_info.repository_url))?;
+        let Some(url_without_scheme) = chart_info.repository_url.strip_prefix("oci://") else {
+            summary.record_error(PollErrorCategory::Other);
+            return Err(anyhow::anyhow!(
+                "Invalid OCI URL: {}",
+                chart_info.repository_url
+            ));
+        };
 
         let reference_str = format!("{}:{}", url_without_scheme, chart_info.current_version);
-        let reference = Reference::try_from(reference_str.as_str())?;
-
-        // Create OCI client
-        let client = OciClient::new(Default::default());
+        let reference = match Reference::try_from(reference_str.as_str()) {
+            Ok(r) => r,
+            Err(e) => {
+                summary.record_error(PollErrorCategory::Other);
+                return Err(e.into());
+            },
+        };
 
         // Get authentication for this chart (charts use same auth as images)
         let mut auth_manager = self.auth_manager.write().await;
-        let auth = auth_manager
+        let auth = match auth_manager
             .get_auth_for_image(&chart_info.repository_url, &chart_info.namespace)
-            .await?;
+            .await
+        {
+            Ok(auth) => auth,
+            Err(e) => {
+                summary.record_error(PollErrorCategory::Auth);
+                return Err(e);
+            },
+        };
         drop(auth_manager);
 
+        // The HelmRepository can allow plain HTTP and add a CA through its certSecretRef
+        let mut tls = registries::for_host(reference.registry());
+        if chart_info.repository.insecure == Some(true) {
+            tls.plain_http = true;
+        }
+        if chart_info.repository.cert_secret_ref.is_some() {
+            match self
+                .helm_repo_client
+                .read_repository_auth(&chart_info.repository_namespace, &chart_info.repository)
+                .await
+            {
+                Ok(repository_auth) => {
+                    if let Some(ca) = repository_auth.ca_bundle {
+                        let ca = String::from_utf8_lossy(&ca);
+                        tls.ca = Some(match tls.ca.take() {
+                            Some(existing) => format!("{}\n{}", existing, ca),
+                            None => ca.into_owned(),
+                        });
+                    }
+                },
+                Err(e) => {
+                    summary.record_error(PollErrorCategory::Auth);
+                    return Err(e);
+                },
+            }
+        }
+
         // List available versions (tags)
-        let tag_response = match client.list_tags(&reference, &auth, None, None).await {
-            Ok(resp) => resp,
+        let tags = match providers().list_tags(&reference, &auth, &tls).await {
+            Ok(tags) => tags,
             Err(e) => {
                 debug!(
                     "Failed to list tags for {}: {} (registry may not support listing)",
                     reference.repository(),
                     e
                 );
+                summary.record_error(PollErrorCategory::TagList);
                 return Ok(());
             },
         };
@@ -787,16 +1289,32 @@ impl RegistryPoller {
             images: Vec::new(),
             event_source: Default::default(),
             polling_interval: None,
+            ignore_patterns: chart_info.ignore_patterns.clone(),
+            prerelease: chart_info.prerelease.clone(),
+            pin_digest: false,
+            channel_tag: None,
+            update_mode: UpdateMode::Image,
+            approval_ttl: DEFAULT_APPROVAL_TTL,
+            required_approvals: None,
+            max_version: chart_info.max_version.clone(),
         };
 
         let mut best_version: Option<String> = None;
 
         // Check each tag to find the best match
-        for tag in &tag_response.tags {
+        for tag in &tags {
+            if PolicyEngine::is_ignored(&resource_policy, tag) {
+                debug!("Skipping ignored version: {}", tag);
+                continue;
+            }
+
             // Skip non-semantic version tags for semver policies
             if matches!(
                 chart_info.policy,
-                UpdatePolicy::Patch | UpdatePolicy::Minor | UpdatePolicy::Major
+                UpdatePolicy::Patch
+                    | UpdatePolicy::Minor
+                    | UpdatePolicy::Major
+                    | UpdatePolicy::Range
             ) {
                 if tag.starts_with('v') {
                     // semver crate handles v prefix
@@ -876,8 +1394,11 @@ impl RegistryPoller {
                     &chart_info.repository_url,
                     &chart_info.chart_name,
                     &new_version,
-                )?;
+                    summary,
+                )
+                .await?;
                 POLLING_HELM_NEW_VERSIONS_FOUND.inc();
+                summary.new_chart_versions += 1;
             }
         }
 
@@ -885,7 +1406,11 @@ impl RegistryPoller {
     }
 
     /// Poll a specific HTTP/HTTPS Helm chart for updates
-    async fn poll_http_helm_chart(&self, chart_info: &HelmChartToTrack) -> Result<()> {
+    async fn poll_http_helm_chart(
+        &self,
+        chart_info: &HelmChartToTrack,
+        summary: &mut PollCycleSummary,
+    ) -> Result<()> {
         debug!(
             "Polling HTTP Helm chart: {} (chart: {}, version: {}, policy: {:?})",
             chart_info.repository_url,
@@ -894,26 +1419,35 @@ impl RegistryPoller {
             chart_info.policy
         );
         POLLING_HELM_CHARTS_CHECKED.inc();
+        summary.charts_checked += 1;
 
-        // Fetch index.yaml from HTTP repository
-        let index_url = format!(
-            "{}/index.yaml",
-            chart_info.repository_url.trim_end_matches('/')
-        );
-        debug!("Fetching Helm repository index from: {}", index_url);
-
-        let response = match reqwest::get(&index_url).await {
-            Ok(resp) => resp,
+        // Credentials and TLS settings from the HelmRepository's secretRef / certSecretRef
+        let auth = match self
+            .helm_repo_client
+            .read_repository_auth(&chart_info.repository_namespace, &chart_info.repository)
+            .await
+        {
+            Ok(auth) => auth,
             Err(e) => {
-                debug!("Failed to fetch Helm repository index: {}", e);
+                warn!(
+                    "Failed to read credentials for Helm repository {}: {:#}",
+                    chart_info.repository_url, e
+                );
+                summary.record_error(PollErrorCategory::Auth);
                 return Ok(());
             },
         };
 
-        let index_yaml = match response.text().await {
+        // Fetch index.yaml from HTTP repository
+        let index_yaml = match self
+            .helm_repo_client
+            .fetch_index_body(&chart_info.repository_url, &auth)
+            .await
+        {
             Ok(text) => text,
             Err(e) => {
-                debug!("Failed to read Helm repository index response: {}", e);
+                debug!("Failed to fetch Helm repository index: {:#}", e);
+                summary.record_error(PollErrorCategory::ChartIndex);
                 return Ok(());
             },
         };
@@ -923,6 +1457,7 @@ impl RegistryPoller {
             Ok(idx) => idx,
             Err(e) => {
                 debug!("Failed to parse Helm repository index YAML: {}", e);
+                summary.record_error(PollErrorCategory::ChartIndex);
                 return Ok(());
             },
         };
@@ -932,6 +1467,7 @@ impl RegistryPoller {
             Some(serde_yaml::Value::Mapping(map)) => map,
             _ => {
                 debug!("Helm index.yaml missing 'entries' field");
+                summary.record_error(PollErrorCategory::ChartIndex);
                 return Ok(());
             },
         };
@@ -960,6 +1496,14 @@ impl RegistryPoller {
             images: Vec::new(),
             event_source: Default::default(),
             polling_interval: None,
+            ignore_patterns: chart_info.ignore_patterns.clone(),
+            prerelease: chart_info.prerelease.clone(),
+            pin_digest: false,
+            channel_tag: None,
+            update_mode: UpdateMode::Image,
+            approval_ttl: DEFAULT_APPROVAL_TTL,
+            required_approvals: None,
+            max_version: chart_info.max_version.clone(),
         };
 
         let mut best_version: Option<String> = None;
@@ -972,10 +1516,18 @@ impl RegistryPoller {
                 _ => continue,
             };
 
+            if PolicyEngine::is_ignored(&resource_policy, version) {
+                debug!("Skipping ignored version: {}", version);
+                continue;
+            }
+
             // Skip non-semantic version tags for semver policies
             if matches!(
                 chart_info.policy,
-                UpdatePolicy::Patch | UpdatePolicy::Minor | UpdatePolicy::Major
+                UpdatePolicy::Patch
+                    | UpdatePolicy::Minor
+                    | UpdatePolicy::Major
+                    | UpdatePolicy::Range
             ) {
                 if version.starts_with('v') {
                     // semver crate handles v prefix
@@ -1061,8 +1613,11 @@ impl RegistryPoller {
                     &chart_info.repository_url,
                     &chart_info.chart_name,
                     &new_version,
-                )?;
+                    summary,
+                )
+                .await?;
                 POLLING_HELM_NEW_VERSIONS_FOUND.inc();
+                summary.new_chart_versions += 1;
             }
         }
 
@@ -1071,11 +1626,12 @@ impl RegistryPoller {
 
     /// Send a chart update event
     /// Supports both OCI (oci://registry.io/path/to/chart) and HTTP (<https://charts.example.com>) URLs
-    fn send_chart_event(
+    async fn send_chart_event(
         &self,
         repository_url: &str,
         chart_name: &str,
         version: &str,
+        summary: &mut PollCycleSummary,
     ) -> Result<()> {
         let (registry, repository) = if repository_url.starts_with("oci://") {
             // Parse OCI URL: oci://registry.io/path/to/chart
@@ -1106,20 +1662,315 @@ impl RegistryPoller {
         };
 
         let event = ChartPushEvent {
+            source: Some(provenance::polling(&registry, summary.started_at, None)),
             registry,
             repository,
             version: version.to_string(),
             digest: None,
         };
 
-        if let Err(e) = self.chart_event_sender.send(event) {
+        if let Err(e) = self.chart_event_sender.send(event).await {
             error!("Failed to send chart polling event: {}", e);
+            summary.record_error(PollErrorCategory::Event);
         }
 
         Ok(())
     }
 }
 
+/// Add the images of `workloads` whose policy is polled (`headwind.sh/event-source` of
+/// `polling` or `both`) to `images`. Policies are parsed like for webhook events, including
+/// per-container overrides.
+fn track_workload_images<W: TrackedWorkload>(
+    workloads: Vec<W>,
+    targets: &[UpdateTarget],
+    image_policies: &[FluxImagePolicy],
+    seen: &mut HashSet<String>,
+    images: &mut Vec<ImageToTrack>,
+) {
+    let kind = W::kind(&());
+
+    for mut workload in workloads {
+        // Merge in policies from UpdateTargets selecting this workload
+        apply_update_targets(&mut workload, targets);
+        // Then in policies from Flux ImagePolicies tracking its images
+        apply_image_policies(&mut workload, W::pod_spec, image_policies);
+        // And the policy defaults of its namespace
+        apply_policy_defaults(&mut workload);
+
+        let namespace = workload
+            .namespace()
+            .unwrap_or_else(|| "default".to_string());
+        let name = workload.name_any();
+        let Some(annotations) = &workload.meta().annotations else {
+            continue;
+        };
+
+        // Skip workloads without headwind policy annotation, for the workload or one of its
+        // containers
+        if !has_policy(annotations) {
+            continue;
+        }
+
+        // Check event source - only poll if event_source is "polling" or "both"
+        let event_source = annotations
+            .get(annotations::EVENT_SOURCE)
+            .and_then(|v| v.parse::<EventSource>().ok())
+            .unwrap_or_default(); // defaults to Webhook
+
+        if event_source != EventSource::Polling && event_source != EventSource::Both {
+            debug!(
+                "Skipping {} {}/{} - event source is {:?}, not polling",
+                kind, namespace, name, event_source
+            );
+            POLLING_RESOURCES_FILTERED.inc();
+            continue;
+        }
+
+        let policy = match parse_policy_from_annotations(annotations) {
+            Ok(policy) => policy,
+            Err(e) => {
+                warn!("Invalid policy for {} {}/{}: {}", kind, namespace, name, e);
+                continue;
+            },
+        };
+        let overridden = parse_container_overrides(annotations);
+        let min_tag_age = parse_min_tag_age(annotations);
+        let notify_detected = parse_notify_detected(annotations);
+        // Parse per-resource polling interval (overrides global interval)
+        let polling_interval = annotations
+            .get(annotations::POLLING_INTERVAL)
+            .and_then(|v| v.parse::<u64>().ok());
+
+        debug!("Processing {} {}/{}", kind, namespace, name);
+
+        let Some(template) = workload.pod_spec() else {
+            continue;
+        };
+        for container in &template.containers {
+            // Skip containers not in the tracked images list (if specified)
+            if !policy.images.is_empty() && !policy.images.contains(&container.name) {
+                continue;
+            }
+            let Some(image) = &container.image else {
+                continue;
+            };
+            // The container's own overrides (headwind.sh/container.<name>.*) take precedence.
+            // Invalid ones only take that container out of polling.
+            let container_policy;
+            let policy = if overridden.contains_key(&container.name) {
+                match parse_policy_from_annotations(&container_annotations(
+                    annotations,
+                    &container.name,
+                )) {
+                    Ok(parsed) => {
+                        container_policy = parsed;
+                        &container_policy
+                    },
+                    Err(e) => {
+                        warn!(
+                            "Invalid policy for container {} of {} {}/{}: {}",
+                            container.name, kind, namespace, name, e
+                        );
+                        continue;
+                    },
+                }
+            } else {
+                &policy
+            };
+            // Images of containers with policy 'none' are only polled for new releases to
+            // notify about (headwind.sh/notify-detected)
+            let detection_policy;
+            let policy = match policy.policy {
+                UpdatePolicy::None if notify_detected => {
+                    detection_policy = PolicyEngine::detection_policy(policy);
+                    &detection_policy
+                },
+                UpdatePolicy::None => continue,
+                _ => policy,
+            };
+
+            // Create unique key for deduplication
+            let key = format!(
+                "{}::{:?}::{:?}::{:?}::{}::{:?}::{:?}::{:?}::{:?}",
+                image,
+                policy.policy,
+                policy.ignore_patterns,
+                policy.prerelease,
+                policy.pin_digest,
+                policy.channel_tag,
+                policy.update_mode,
+                min_tag_age,
+                policy.max_version
+            );
+            if seen.insert(key) {
+                debug!(
+                    "  Adding image to track: {} (policy: {:?})",
+                    image, policy.policy
+                );
+                images.push(ImageToTrack {
+                    image: image.clone(),
+                    policy: policy.policy,
+                    pattern: policy.pattern.clone(),
+                    ignore_patterns: policy.ignore_patterns.clone(),
+                    prerelease: policy.prerelease.clone(),
+                    pin_digest: policy.pin_digest,
+                    channel_tag: policy.channel_tag.clone(),
+                    update_mode: policy.update_mode,
+                    min_tag_age,
+                    max_version: policy.max_version.clone(),
+                    namespace: namespace.clone(),
+                    polling_interval,
+                });
+            }
+        }
+    }
+}
+
+/// The best tag of `tags` to update `current_tag` to according to the policy
+fn best_tag(
+    resource_policy: &ResourcePolicy,
+    current_tag: &str,
+    tags: &[String],
+) -> Option<String> {
+    let policy_engine = PolicyEngine;
+    let mut best_version: Option<String> = None;
+
+    // Find the best matching tag according to policy
+    for tag in tags {
+        if PolicyEngine::is_ignored(resource_policy, tag) {
+            debug!("Skipping ignored tag: {}", tag);
+            continue;
+        }
+
+        // Skip non-version-looking tags for semver policies
+        if matches!(
+            resource_policy.policy,
+            UpdatePolicy::Patch | UpdatePolicy::Minor | UpdatePolicy::Major | UpdatePolicy::Range
+        ) {
+            // Quick sanity check: does it look like a version?
+            // Must start with digit or 'v'
+            if !tag
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_digit() || c == 'v')
+            {
+                debug!("Skipping non-version tag: {}", tag);
+                continue;
+            }
+        }
+
+        // Check if this tag should be considered for update
+        match policy_engine.should_update(resource_policy, current_tag, tag) {
+            Ok(true) => {
+                debug!("Tag {} matches policy {:?}", tag, resource_policy.policy);
+
+                // If we don't have a best version yet, or this one is better
+                if best_version.is_none() {
+                    best_version = Some(tag.clone());
+                } else if let Some(ref current_best) = best_version {
+                    // Check if new tag is better than current best
+                    match policy_engine.should_update(resource_policy, current_best, tag) {
+                        Ok(true) => {
+                            debug!("Tag {} is better than current best {}", tag, current_best);
+                            best_version = Some(tag.clone());
+                        },
+                        Ok(false) => {
+                            debug!("Tag {} is not better than {}", tag, current_best);
+                        },
+                        Err(e) => {
+                            debug!("Failed to compare {} with {}: {}", tag, current_best, e);
+                        },
+                    }
+                }
+            },
+            Ok(false) => {
+                debug!("Tag {} does not match policy", tag);
+            },
+            Err(e) => {
+                debug!("Failed to check if tag {} matches policy: {}", tag, e);
+            },
+        }
+    }
+
+    best_version
+}
+
+/// Resolve the digest an image's tag currently points to, authenticating with the pull
+/// secrets available in the namespace. Used for webhook events that carry no digest.
+pub async fn resolve_digest(client: &Client, image: &str, namespace: &str) -> Result<String> {
+    let reference = Reference::try_from(image)?;
+    let auth = AuthManager::new(client.clone())
+        .get_auth_for_image(image, namespace)
+        .await?;
+
+    let tls = registries::for_host(reference.registry());
+    providers().fetch_digest(&reference, &auth, &tls).await
+}
+
+/// Labels and annotations of an image, e.g. `org.opencontainers.image.source`, authenticating
+/// with the pull secrets available in the namespace
+pub async fn image_labels(
+    client: &Client,
+    image: &str,
+    namespace: &str,
+) -> Result<HashMap<String, String>> {
+    let reference = Reference::try_from(image)?;
+    let auth = AuthManager::new(client.clone())
+        .get_auth_for_image(image, namespace)
+        .await?;
+
+    let tls = registries::for_host(reference.registry());
+    providers::fetch_labels(&reference, &auth, &tls).await
+}
+
+/// Creation time, size, labels and exposed ports of an image, authenticating with the pull
+/// secrets available in the namespace
+pub async fn image_metadata(
+    client: &Client,
+    image: &str,
+    namespace: &str,
+) -> Result<ImageMetadata> {
+    let reference = Reference::try_from(image)?;
+    let auth = AuthManager::new(client.clone())
+        .get_auth_for_image(image, namespace)
+        .await?;
+
+    let tls = registries::for_host(reference.registry());
+    providers::fetch_metadata(&reference, &auth, &tls).await
+}
+
+/// Platforms (`os/architecture`) an image is built for, authenticating with the pull secrets
+/// available in the namespace
+pub async fn image_platforms(client: &Client, image: &str, namespace: &str) -> Result<Vec<String>> {
+    let reference = Reference::try_from(image)?;
+    let auth = AuthManager::new(client.clone())
+        .get_auth_for_image(image, namespace)
+        .await?;
+
+    let tls = registries::for_host(reference.registry());
+    providers::fetch_platforms(&reference, &auth, &tls).await
+}
+
+/// Registry host an image is pulled from, used to group polls for rate limiting
+fn image_registry(image: &str) -> String {
+    match Reference::try_from(image) {
+        Ok(reference) => extract_registry(reference.resolve_registry()),
+        Err(_) => image.split('/').next().unwrap_or_default().to_string(),
+    }
+}
+
+/// Host of an `oci://` or `http(s)://` Helm repository URL
+fn chart_registry(repository_url: &str) -> String {
+    repository_url
+        .split_once("://")
+        .map_or(repository_url, |(_, rest)| rest)
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
 fn extract_registry(registry: &str) -> String {
     if registry.is_empty() {
         "docker.io".to_string()
@@ -1137,6 +1988,8 @@ mod tests {
         let config = PollingConfig::default();
         assert_eq!(config.interval, 300);
         assert!(!config.enabled);
+        assert_eq!(config.concurrency, 8);
+        assert_eq!(config.registry_concurrency, 2);
     }
 
     #[test]
@@ -1148,4 +2001,222 @@ mod tests {
             "registry.example.com"
         );
     }
+
+    #[test]
+    fn test_registry_hosts() {
+        assert_eq!(image_registry("nginx:1.25"), "index.docker.io");
+        assert_eq!(image_registry("ghcr.io/org/app:v1"), "ghcr.io");
+        assert_eq!(
+            chart_registry("oci://registry.example.com/charts/app"),
+            "registry.example.com"
+        );
+        assert_eq!(
+            chart_registry("https://charts.example.com:8443/stable"),
+            "charts.example.com:8443"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_old_enough() {
+        let day = 24 * 60 * 60;
+        let two_days_ago = Utc::now() - chrono::Duration::days(2);
+        assert!(old_enough("registry.test/app:1.0.0", Some(two_days_ago), day).await);
+        assert!(!old_enough("registry.test/app:1.0.0", Some(Utc::now()), day).await);
+
+        // Without a creation time the age counts from the first time the tag was seen
+        assert!(!old_enough("registry.test/first-seen:2.0.0", None, day).await);
+        TAG_FIRST_SEEN
+            .write()
+            .await
+            .insert("registry.test/first-seen:2.0.0".to_string(), two_days_ago);
+        assert!(old_enough("registry.test/first-seen:2.0.0", None, day).await);
+    }
+
+    #[test]
+    fn test_best_tag() {
+        let policy = ResourcePolicy {
+            policy: UpdatePolicy::Minor,
+            ..Default::default()
+        };
+        let tags: Vec<String> = ["1.2.0", "1.3.0", "1.4.0", "2.0.0", "latest"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(best_tag(&policy, "1.2.0", &tags).as_deref(), Some("1.4.0"));
+
+        // Candidates held back by their age are removed and the next best is picked
+        let tags: Vec<String> = tags.into_iter().filter(|t| t != "1.4.0").collect();
+        assert_eq!(best_tag(&policy, "1.2.0", &tags).as_deref(), Some("1.3.0"));
+        assert_eq!(best_tag(&policy, "1.3.0", &tags), None);
+    }
+
+    #[test]
+    fn test_track_workload_images() {
+        let statefulset: StatefulSet = serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": "postgres",
+                "namespace": "db",
+                "annotations": {
+                    "headwind.sh/policy": "minor",
+                    "headwind.sh/event-source": "polling",
+                    "headwind.sh/container.exporter.policy": "none",
+                },
+            },
+            "spec": {
+                "selector": {},
+                "serviceName": "postgres",
+                "template": {"spec": {"containers": [
+                    {"name": "postgres", "image": "postgres:16.1"},
+                    {"name": "exporter", "image": "prom/postgres-exporter:0.15.0"},
+                ]}},
+            },
+        }))
+        .unwrap();
+        let mut webhook_only = statefulset.clone();
+        webhook_only.metadata.name = Some("webhook-only".to_string());
+        webhook_only
+            .metadata
+            .annotations
+            .as_mut()
+            .unwrap()
+            .insert(annotations::EVENT_SOURCE.to_string(), "webhook".to_string());
+
+        let mut seen = HashSet::new();
+        let mut images = Vec::new();
+        track_workload_images(
+            vec![statefulset.clone(), webhook_only],
+            &[],
+            &[],
+            &mut seen,
+            &mut images,
+        );
+
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].image, "postgres:16.1");
+        assert_eq!(images[0].policy, UpdatePolicy::Minor);
+        assert_eq!(images[0].namespace, "db");
+
+        // Containers with policy 'none' are polled for any newer release when their owners
+        // asked to be notified
+        let mut notified = statefulset;
+        notified
+            .metadata
+            .annotations
+            .as_mut()
+            .unwrap()
+            .insert(annotations::NOTIFY_DETECTED.to_string(), "true".to_string());
+        track_workload_images(vec![notified], &[], &[], &mut seen, &mut images);
+
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[1].image, "prom/postgres-exporter:0.15.0");
+        assert_eq!(images[1].policy, UpdatePolicy::Major);
+    }
+
+    #[test]
+    fn test_track_workload_images_skips_invalid_container_policy() {
+        let daemonset: DaemonSet = serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": "node-agent",
+                "namespace": "monitoring",
+                "annotations": {
+                    "headwind.sh/policy": "patch",
+                    "headwind.sh/event-source": "polling",
+                    "headwind.sh/container.sidecar.policy": "sometimes",
+                },
+            },
+            "spec": {
+                "selector": {},
+                "template": {"spec": {"containers": [
+                    {"name": "agent", "image": "grafana/agent:0.40.1"},
+                    {"name": "sidecar", "image": "busybox:1.36.0"},
+                ]}},
+            },
+        }))
+        .unwrap();
+
+        let mut seen = HashSet::new();
+        let mut images = Vec::new();
+        track_workload_images(vec![daemonset], &[], &[], &mut seen, &mut images);
+
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].image, "grafana/agent:0.40.1");
+        assert_eq!(images[0].policy, UpdatePolicy::Patch);
+    }
+
+    #[test]
+    fn test_parse_min_tag_age() {
+        let mut workload_annotations = BTreeMap::new();
+        assert_eq!(parse_min_tag_age(&workload_annotations), None);
+        workload_annotations.insert(annotations::MIN_TAG_AGE.to_string(), "24h".to_string());
+        assert_eq!(parse_min_tag_age(&workload_annotations), Some(86400));
+        workload_annotations.insert(annotations::MIN_TAG_AGE.to_string(), "0".to_string());
+        assert_eq!(parse_min_tag_age(&workload_annotations), None);
+        workload_annotations.insert(annotations::MIN_TAG_AGE.to_string(), "soon".to_string());
+        assert_eq!(parse_min_tag_age(&workload_annotations), None);
+    }
+
+    #[tokio::test]
+    async fn test_registry_limiter_caps_per_host() {
+        let limiter = RegistryLimiter::new(1);
+        let permit = limiter.acquire("ghcr.io").await;
+
+        // Another host is unaffected
+        let _other = limiter.acquire("docker.io").await;
+
+        // The same host waits until the first permit is released
+        let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("ghcr.io"));
+        assert!(blocked.await.is_err());
+        drop(permit);
+        let _permit = limiter.acquire("ghcr.io").await;
+    }
+
+    #[test]
+    fn test_poll_cycle_summary_merge() {
+        let mut summary = PollCycleSummary::new();
+        summary.images_skipped = 1;
+        summary.record_error(PollErrorCategory::Auth);
+
+        let mut task = PollCycleSummary::new();
+        task.images_checked = 1;
+        task.new_image_versions = 1;
+        task.record_error(PollErrorCategory::Auth);
+        task.record_error(PollErrorCategory::TagList);
+        summary.merge(task);
+
+        assert_eq!(summary.images_checked, 1);
+        assert_eq!(summary.images_skipped, 1);
+        assert_eq!(summary.new_image_versions, 1);
+        assert_eq!(summary.errors.get(&PollErrorCategory::Auth), Some(&2));
+        assert_eq!(summary.total_errors(), 3);
+    }
+
+    #[test]
+    fn test_poll_cycle_summary_errors_by_category() {
+        let mut summary = PollCycleSummary::new();
+        summary.record_error(PollErrorCategory::TagList);
+        summary.record_error(PollErrorCategory::TagList);
+        summary.record_error(PollErrorCategory::Auth);
+
+        assert_eq!(summary.total_errors(), 3);
+        assert_eq!(summary.errors.get(&PollErrorCategory::TagList), Some(&2));
+        assert_eq!(summary.errors.get(&PollErrorCategory::Auth), Some(&1));
+        assert!(summary.finished_at.is_none());
+
+        summary.finish(Duration::from_millis(1500));
+        assert_eq!(summary.duration_ms, 1500);
+        assert!(summary.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_poll_cycle_summary_serialization() {
+        let mut summary = PollCycleSummary::new();
+        summary.images_checked = 4;
+        summary.new_chart_versions = 1;
+        summary.record_error(PollErrorCategory::ChartIndex);
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["imagesChecked"], 4);
+        assert_eq!(json["newChartVersions"], 1);
+        assert_eq!(json["errors"]["chartIndex"], 1);
+    }
 }
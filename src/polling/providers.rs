@@ -0,0 +1,1399 @@
+//! Registry-specific backends for tag discovery and digest lookups.
+//!
+//! The OCI distribution `tags/list` endpoint is unreliable on some managed registries (ECR
+//! caps and paginates it, Artifact Registry and ACR expose richer native APIs). Each registry
+//! host is served by a [`RegistryProvider`]: ECR (`DescribeImages`), Google Artifact Registry,
+//! Azure Container Registry, or the generic OCI API. The provider is detected from the host
+//! and can be overridden with `HEADWIND_REGISTRY_PROVIDERS`, e.g.
+//! `registry.internal=oci,*.example.com=acr`. When a native API call fails (missing cloud
+//! credentials, permissions) the generic OCI API is used instead.
+//!
+//! Tag listings are paginated and capped at `HEADWIND_POLLING_MAX_TAGS` tags (default
+//! 10000); repositories with more tags are truncated to the first tags the registry returns.
+
+use crate::config::registries::RegistryTls;
+use crate::metrics::{POLLING_TAG_LISTS_TRUNCATED, REGISTRY_PROVIDER_FALLBACKS};
+use crate::models::crd::ImageMetadata;
+use crate::webhook::auth::hmac_sha256;
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use base64::prelude::*;
+use chrono::{DateTime, Datelike, Utc};
+use oci_distribution::manifest::OciManifest;
+use oci_distribution::{Client as OciClient, Reference, secrets::RegistryAuth};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+const ECR_DESCRIBE_IMAGES: &str = "AmazonEC2ContainerRegistry_V20150921.DescribeImages";
+const ECR_GET_AUTHORIZATION_TOKEN: &str =
+    "AmazonEC2ContainerRegistry_V20150921.GetAuthorizationToken";
+const ARTIFACT_REGISTRY_API: &str = "https://artifactregistry.googleapis.com/v1/";
+/// Page size for native tag listing APIs
+const PAGE_SIZE: usize = 1000;
+/// Page size for the OCI distribution `tags/list` API (Docker Hub caps pages at 100)
+const OCI_PAGE_SIZE: usize = 100;
+/// Default for `HEADWIND_POLLING_MAX_TAGS`
+const DEFAULT_MAX_TAGS: usize = 10_000;
+/// Refresh temporary AWS credentials this long before they expire
+const CREDENTIAL_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+static PROVIDERS: once_cell::sync::Lazy<RegistryProviders> = once_cell::sync::Lazy::new(|| {
+    RegistryProviders::new(
+        parse_overrides(&std::env::var("HEADWIND_REGISTRY_PROVIDERS").unwrap_or_default()),
+        std::env::var("HEADWIND_POLLING_MAX_TAGS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&max| max > 0)
+            .unwrap_or(DEFAULT_MAX_TAGS),
+    )
+});
+
+/// Registry backends shared by polling and webhook digest resolution
+pub(crate) fn providers() -> &'static RegistryProviders {
+    &PROVIDERS
+}
+
+/// Kind of registry API used for a host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProviderKind {
+    /// OCI distribution API (`/v2/<name>/tags/list`)
+    Oci,
+    /// Amazon ECR `DescribeImages`
+    Ecr,
+    /// Google Artifact Registry REST API
+    Gar,
+    /// Azure Container Registry `/acr/v1` API
+    Acr,
+}
+
+impl ProviderKind {
+    /// Infer the provider from a registry host
+    pub fn detect(host: &str) -> Self {
+        if EcrRepository::parse_host(host).is_some() {
+            Self::Ecr
+        } else if host.ends_with("-docker.pkg.dev") {
+            Self::Gar
+        } else if host.ends_with(".azurecr.io") {
+            Self::Acr
+        } else {
+            Self::Oci
+        }
+    }
+}
+
+impl FromStr for ProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "oci" | "generic" => Ok(Self::Oci),
+            "ecr" => Ok(Self::Ecr),
+            "gar" | "artifactregistry" => Ok(Self::Gar),
+            "acr" => Ok(Self::Acr),
+            other => Err(format!("unknown registry provider: {}", other)),
+        }
+    }
+}
+
+/// Parse `host=provider` pairs. Hosts starting with `*.` match any subdomain.
+fn parse_overrides(value: &str) -> Vec<(String, ProviderKind)> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected host=provider, got '{}'", entry.trim()))
+                .and_then(|(host, kind)| Ok((host.trim().to_lowercase(), kind.parse()?)));
+            parsed
+                .map_err(|e| warn!("Ignoring HEADWIND_REGISTRY_PROVIDERS entry: {}", e))
+                .ok()
+        })
+        .collect()
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.ends_with(&format!(".{}", domain)),
+        None => pattern == host,
+    }
+}
+
+/// Tag discovery and digest lookups for one kind of registry
+#[async_trait]
+pub(crate) trait RegistryProvider: Send + Sync {
+    /// Tags of the repository `reference` points to. Implementations may stop paging once
+    /// more than `max_tags` tags were collected.
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+        max_tags: usize,
+    ) -> Result<Vec<String>>;
+
+    /// Manifest digest the tag of `reference` currently points to
+    async fn fetch_digest(&self, reference: &Reference, auth: &RegistryAuth) -> Result<String>;
+}
+
+/// Selects the provider for each registry host
+pub(crate) struct RegistryProviders {
+    overrides: Vec<(String, ProviderKind)>,
+    max_tags: usize,
+    ecr: EcrProvider,
+    gar: GarProvider,
+    acr: AcrProvider,
+}
+
+impl RegistryProviders {
+    fn new(overrides: Vec<(String, ProviderKind)>, max_tags: usize) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("headwind")
+            .build()
+            .unwrap_or_default();
+        Self {
+            overrides,
+            max_tags,
+            ecr: EcrProvider {
+                http: http.clone(),
+                credentials: Mutex::new(None),
+            },
+            gar: GarProvider {
+                http: http.clone(),
+                token: Mutex::new(None),
+            },
+            acr: AcrProvider { http },
+        }
+    }
+
+    /// Provider used for `host`: the first matching override, otherwise detected
+    pub fn kind_for(&self, host: &str) -> ProviderKind {
+        let host = host.to_lowercase();
+        self.overrides
+            .iter()
+            .find(|(pattern, _)| host_matches(pattern, &host))
+            .map(|(_, kind)| *kind)
+            .unwrap_or_else(|| ProviderKind::detect(&host))
+    }
+
+    fn native(&self, reference: &Reference) -> Option<&dyn RegistryProvider> {
+        match self.kind_for(reference.registry()) {
+            ProviderKind::Oci => None,
+            ProviderKind::Ecr => Some(&self.ecr),
+            ProviderKind::Gar => Some(&self.gar),
+            ProviderKind::Acr => Some(&self.acr),
+        }
+    }
+
+    /// Tags of `reference`'s repository. `tls` applies to the OCI distribution API; the
+    /// cloud registry APIs always use their public endpoints.
+    pub async fn list_tags(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+        tls: &RegistryTls,
+    ) -> Result<Vec<String>> {
+        let native = match self.native(reference) {
+            Some(provider) => match provider.list_tags(reference, auth, self.max_tags).await {
+                Ok(tags) => Some(tags),
+                Err(e) => {
+                    fall_back(reference, "list tags", &e);
+                    None
+                },
+            },
+            None => None,
+        };
+        let mut tags = match native {
+            Some(tags) => tags,
+            None => {
+                OciProvider::new(reference, tls)
+                    .list_tags(reference, auth, self.max_tags)
+                    .await?
+            },
+        };
+
+        if tags.len() > self.max_tags {
+            POLLING_TAG_LISTS_TRUNCATED.inc();
+            warn!(
+                "{}/{} has more than {} tags, only the first {} are considered \
+                 (raise HEADWIND_POLLING_MAX_TAGS to see all)",
+                reference.registry(),
+                reference.repository(),
+                self.max_tags,
+                self.max_tags
+            );
+            tags.truncate(self.max_tags);
+        }
+        Ok(tags)
+    }
+
+    /// Basic credentials for the OCI API of the ECR registry `host`, with the pod's AWS
+    /// credentials (IRSA or static)
+    pub async fn ecr_authorization(&self, host: &str) -> Result<EcrAuthorization> {
+        self.ecr.authorization(host).await
+    }
+
+    pub async fn fetch_digest(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+        tls: &RegistryTls,
+    ) -> Result<String> {
+        if let Some(provider) = self.native(reference) {
+            match provider.fetch_digest(reference, auth).await {
+                Ok(digest) => return Ok(digest),
+                Err(e) => fall_back(reference, "fetch digest", &e),
+            }
+        }
+        OciProvider::new(reference, tls)
+            .fetch_digest(reference, auth)
+            .await
+    }
+}
+
+/// Labels of the image `reference` points to: its config labels, overridden by the
+/// manifest's annotations. Always read through the OCI distribution API.
+pub async fn fetch_labels(
+    reference: &Reference,
+    auth: &RegistryAuth,
+    tls: &RegistryTls,
+) -> Result<HashMap<String, String>> {
+    let client = OciClient::new(tls.oci_client_config(reference.registry()));
+    let (manifest, _, config) = client
+        .pull_manifest_and_config(reference, auth)
+        .await
+        .context("Failed to fetch image config")?;
+
+    let mut labels = config_labels(&config)?;
+    labels.extend(manifest.annotations.unwrap_or_default());
+    Ok(labels)
+}
+
+/// Creation time of the image `reference` points to: the `created` time of its config, or the
+/// manifest's `org.opencontainers.image.created` annotation. Always read through the OCI
+/// distribution API.
+pub async fn fetch_created(
+    reference: &Reference,
+    auth: &RegistryAuth,
+    tls: &RegistryTls,
+) -> Result<Option<DateTime<Utc>>> {
+    let client = OciClient::new(tls.oci_client_config(reference.registry()));
+    let (manifest, _, config) = client
+        .pull_manifest_and_config(reference, auth)
+        .await
+        .context("Failed to fetch image config")?;
+
+    let annotation = manifest
+        .annotations
+        .unwrap_or_default()
+        .remove("org.opencontainers.image.created");
+    Ok(config_created(&config)?.or_else(|| parse_created(annotation.as_deref()?)))
+}
+
+/// Creation time, size, labels and exposed ports of the image `reference` points to, for the
+/// platform the registry serves by default. Always read through the OCI distribution API.
+pub async fn fetch_metadata(
+    reference: &Reference,
+    auth: &RegistryAuth,
+    tls: &RegistryTls,
+) -> Result<ImageMetadata> {
+    let client = OciClient::new(tls.oci_client_config(reference.registry()));
+    let (manifest, _, config) = client
+        .pull_manifest_and_config(reference, auth)
+        .await
+        .context("Failed to fetch image config")?;
+
+    let mut metadata = config_metadata(&config)?;
+    let size = manifest.config.size + manifest.layers.iter().map(|l| l.size).sum::<i64>();
+    metadata.size = u64::try_from(size).ok().filter(|&size| size > 0);
+    let annotations = manifest.annotations.unwrap_or_default();
+    if metadata.created.is_none() {
+        metadata.created = annotations
+            .get("org.opencontainers.image.created")
+            .and_then(|created| parse_created(created));
+    }
+    metadata.labels.extend(annotations);
+    Ok(metadata)
+}
+
+/// Creation time, labels and exposed ports of an image config
+fn config_metadata(config: &str) -> Result<ImageMetadata> {
+    let labels = config_labels(config)?.into_iter().collect();
+    let config: Value = serde_json::from_str(config).context("Invalid image config")?;
+    let exposed_ports = config
+        .pointer("/config/ExposedPorts")
+        .and_then(Value::as_object)
+        .map(|ports| ports.keys().cloned().collect())
+        .unwrap_or_default();
+    Ok(ImageMetadata {
+        created: config["created"].as_str().and_then(parse_created),
+        size: None,
+        labels,
+        exposed_ports,
+    })
+}
+
+/// `created` of an image config
+fn config_created(config: &str) -> Result<Option<DateTime<Utc>>> {
+    let config: Value = serde_json::from_str(config).context("Invalid image config")?;
+    Ok(config["created"].as_str().and_then(parse_created))
+}
+
+/// RFC 3339 creation time. Reproducible builds often use the Unix epoch, which says nothing
+/// about when the image was published, so times before 2000 are ignored.
+fn parse_created(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+        .filter(|t| t.year() >= 2000)
+}
+
+/// Platforms (`os/architecture`) the image `reference` points to is built for: every
+/// platform of a multi-platform image index, or the platform in a single image's config.
+/// Always read through the OCI distribution API.
+pub async fn fetch_platforms(
+    reference: &Reference,
+    auth: &RegistryAuth,
+    tls: &RegistryTls,
+) -> Result<Vec<String>> {
+    let client = OciClient::new(tls.oci_client_config(reference.registry()));
+    let (manifest, _) = client
+        .pull_manifest(reference, auth)
+        .await
+        .context("Failed to fetch image manifest")?;
+
+    let platforms = match manifest {
+        OciManifest::ImageIndex(index) => index
+            .manifests
+            .iter()
+            .filter_map(|entry| entry.platform.as_ref())
+            .filter_map(|platform| platform_name(&platform.os, &platform.architecture))
+            .collect(),
+        OciManifest::Image(_) => {
+            let (_, _, config) = client
+                .pull_manifest_and_config(reference, auth)
+                .await
+                .context("Failed to fetch image config")?;
+            config_platform(&config)?.into_iter().collect()
+        },
+    };
+    Ok(platforms)
+}
+
+/// Platform of an image config (`{"os": "linux", "architecture": "arm64", ...}`)
+fn config_platform(config: &str) -> Result<Option<String>> {
+    let config: Value = serde_json::from_str(config).context("Invalid image config")?;
+    Ok(platform_name(
+        config["os"].as_str().unwrap_or_default(),
+        config["architecture"].as_str().unwrap_or_default(),
+    ))
+}
+
+/// `os/architecture`, or None for unknown platforms (e.g. attestation manifests in an index)
+fn platform_name(os: &str, architecture: &str) -> Option<String> {
+    (!os.is_empty() && !architecture.is_empty() && os != "unknown" && architecture != "unknown")
+        .then(|| format!("{}/{}", os, architecture))
+}
+
+/// `config.Labels` of an image config
+fn config_labels(config: &str) -> Result<HashMap<String, String>> {
+    let config: Value = serde_json::from_str(config).context("Invalid image config")?;
+    Ok(config
+        .pointer("/config/Labels")
+        .and_then(Value::as_object)
+        .map(|labels| {
+            labels
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn fall_back(reference: &Reference, operation: &str, error: &anyhow::Error) {
+    REGISTRY_PROVIDER_FALLBACKS.inc();
+    debug!(
+        "Registry API failed to {} for {}/{}, falling back to the OCI API: {:#}",
+        operation,
+        reference.registry(),
+        reference.repository(),
+        error
+    );
+}
+
+/// Collect tags from a listing paginated with `n` and `last` (OCI distribution and ACR APIs).
+/// Stops at a short page, when the registry ignores `last` and repeats tags, or once more
+/// than `max_tags` tags were collected.
+async fn paginate_by_last<F, Fut>(
+    page_size: usize,
+    max_tags: usize,
+    mut fetch_page: F,
+) -> Result<Vec<String>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Vec<String>>>,
+{
+    let mut tags: Vec<String> = Vec::new();
+    loop {
+        let page = fetch_page(tags.last().cloned()).await?;
+        if page.first().is_some_and(|tag| tags.contains(tag)) {
+            return Ok(tags);
+        }
+        let count = page.len();
+        tags.extend(page);
+        if count < page_size || tags.len() > max_tags {
+            return Ok(tags);
+        }
+    }
+}
+
+/// The OCI distribution API, supported by every registry
+struct OciProvider {
+    client: OciClient,
+}
+
+impl OciProvider {
+    fn new(reference: &Reference, tls: &RegistryTls) -> Self {
+        Self {
+            client: OciClient::new(tls.oci_client_config(reference.registry())),
+        }
+    }
+}
+
+#[async_trait]
+impl RegistryProvider for OciProvider {
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+        max_tags: usize,
+    ) -> Result<Vec<String>> {
+        let client = &self.client;
+        paginate_by_last(OCI_PAGE_SIZE, max_tags, |last| async move {
+            let page = client
+                .list_tags(reference, auth, Some(OCI_PAGE_SIZE), last.as_deref())
+                .await?;
+            Ok(page.tags)
+        })
+        .await
+    }
+
+    async fn fetch_digest(&self, reference: &Reference, auth: &RegistryAuth) -> Result<String> {
+        Ok(self.client.fetch_manifest_digest(reference, auth).await?)
+    }
+}
+
+/// An ECR repository, from `<account>.dkr.ecr.<region>.amazonaws.com/<name>`
+#[derive(Debug, PartialEq, Eq)]
+struct EcrRepository {
+    registry_id: String,
+    region: String,
+    /// `.amazonaws.com` or `.amazonaws.com.cn`
+    domain: String,
+    name: String,
+}
+
+impl EcrRepository {
+    /// Account, region and domain of an ECR registry host
+    fn parse_host(host: &str) -> Option<(String, String, String)> {
+        static HOST: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+            regex::Regex::new(
+                r"^(\d{12})\.dkr\.ecr(?:-fips)?\.([a-z0-9-]+)\.(amazonaws\.com(?:\.cn)?)$",
+            )
+            .expect("valid ECR host pattern")
+        });
+        let captures = HOST.captures(host)?;
+        Some((
+            captures[1].to_string(),
+            captures[2].to_string(),
+            captures[3].to_string(),
+        ))
+    }
+
+    fn parse(reference: &Reference) -> Result<Self> {
+        let (registry_id, region, domain) = Self::parse_host(reference.registry())
+            .ok_or_else(|| anyhow!("{} is not an ECR registry", reference.registry()))?;
+        Ok(Self {
+            registry_id,
+            region,
+            domain,
+            name: reference.repository().to_string(),
+        })
+    }
+
+    fn api_host(&self) -> String {
+        format!("api.ecr.{}.{}", self.region, self.domain)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    /// When temporary credentials must be refreshed
+    refresh_at: Option<Instant>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DescribeImagesResponse {
+    #[serde(default)]
+    image_details: Vec<EcrImageDetail>,
+    next_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EcrImageDetail {
+    image_digest: String,
+    #[serde(default)]
+    image_tags: Vec<String>,
+}
+
+/// Amazon ECR through `DescribeImages`, signed with SigV4. Credentials come from
+/// `AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY` or IRSA (`AWS_ROLE_ARN` and
+/// `AWS_WEB_IDENTITY_TOKEN_FILE`).
+struct EcrProvider {
+    http: reqwest::Client,
+    credentials: Mutex<Option<AwsCredentials>>,
+}
+
+impl EcrProvider {
+    async fn credentials(&self, region: &str) -> Result<AwsCredentials> {
+        let mut cached = self.credentials.lock().await;
+        if let Some(credentials) = cached.as_ref()
+            && credentials
+                .refresh_at
+                .is_none_or(|refresh_at| Instant::now() < refresh_at)
+        {
+            return Ok(credentials.clone());
+        }
+
+        let credentials = match (
+            std::env::var("AWS_ACCESS_KEY_ID"),
+            std::env::var("AWS_SECRET_ACCESS_KEY"),
+        ) {
+            (Ok(access_key_id), Ok(secret_access_key)) => AwsCredentials {
+                access_key_id,
+                secret_access_key,
+                session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+                refresh_at: None,
+            },
+            _ => self.assume_role_with_web_identity(region).await?,
+        };
+        *cached = Some(credentials.clone());
+        Ok(credentials)
+    }
+
+    async fn assume_role_with_web_identity(&self, region: &str) -> Result<AwsCredentials> {
+        let role_arn = std::env::var("AWS_ROLE_ARN")
+            .context("No AWS credentials (set AWS_ACCESS_KEY_ID or use IRSA)")?;
+        let token_file = std::env::var("AWS_WEB_IDENTITY_TOKEN_FILE")
+            .context("AWS_ROLE_ARN is set but AWS_WEB_IDENTITY_TOKEN_FILE is not")?;
+        let token = tokio::fs::read_to_string(&token_file)
+            .await
+            .with_context(|| format!("Failed to read {}", token_file))?;
+        let region = std::env::var("AWS_REGION").unwrap_or_else(|_| region.to_string());
+
+        let url = Url::parse_with_params(
+            &format!("https://sts.{}.amazonaws.com/", region),
+            &[
+                ("Action", "AssumeRoleWithWebIdentity"),
+                ("Version", "2011-06-15"),
+                ("RoleArn", role_arn.as_str()),
+                ("RoleSessionName", "headwind"),
+                ("WebIdentityToken", token.trim()),
+            ],
+        )?;
+        let body = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .context("Failed to reach AWS STS")?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let field = |name: &str| {
+            xml_value(&body, name).ok_or_else(|| anyhow!("STS response has no {}", name))
+        };
+        let refresh_at = xml_value(&body, "Expiration")
+            .and_then(|expiration| expiration.parse::<DateTime<Utc>>().ok())
+            .and_then(|expiration| (expiration - Utc::now()).to_std().ok())
+            .map(|lifetime| Instant::now() + lifetime.saturating_sub(CREDENTIAL_REFRESH_MARGIN));
+
+        Ok(AwsCredentials {
+            access_key_id: field("AccessKeyId")?,
+            secret_access_key: field("SecretAccessKey")?,
+            session_token: Some(field("SessionToken")?),
+            refresh_at,
+        })
+    }
+
+    async fn describe_images(
+        &self,
+        repository: &EcrRepository,
+        request: Value,
+    ) -> Result<DescribeImagesResponse> {
+        self.call(repository, ECR_DESCRIBE_IMAGES, request).await
+    }
+
+    /// SigV4-signed call of an ECR API action
+    async fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        repository: &EcrRepository,
+        target: &str,
+        request: Value,
+    ) -> Result<T> {
+        let credentials = self.credentials(&repository.region).await?;
+        let host = repository.api_host();
+        let payload = request.to_string();
+        let headers = sigv4_headers(
+            &credentials,
+            &repository.region,
+            "ecr",
+            &host,
+            target,
+            &payload,
+            Utc::now(),
+        );
+
+        let mut builder = self.http.post(format!("https://{}/", host)).body(payload);
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        let response = builder.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let action = target.rsplit('.').next().unwrap_or(target);
+            return Err(anyhow!("ECR {} failed ({}): {}", action, status, body));
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Basic credentials for the OCI API of an ECR registry, from `GetAuthorizationToken`
+    async fn authorization(&self, host: &str) -> Result<EcrAuthorization> {
+        let (registry_id, region, domain) = EcrRepository::parse_host(host)
+            .ok_or_else(|| anyhow!("{} is not an ECR registry", host))?;
+        let registry = EcrRepository {
+            registry_id,
+            region,
+            domain,
+            name: String::new(),
+        };
+        let response: GetAuthorizationTokenResponse = self
+            .call(
+                &registry,
+                ECR_GET_AUTHORIZATION_TOKEN,
+                json!({ "registryIds": [registry.registry_id] }),
+            )
+            .await?;
+        let data = response
+            .authorization_data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("ECR returned no authorization data"))?;
+        EcrAuthorization::decode(&data.authorization_token, data.expires_at)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetAuthorizationTokenResponse {
+    #[serde(default)]
+    authorization_data: Vec<EcrAuthorizationData>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EcrAuthorizationData {
+    authorization_token: String,
+    /// Epoch seconds
+    expires_at: Option<f64>,
+}
+
+/// Credentials for the OCI API of an ECR registry
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EcrAuthorization {
+    pub username: String,
+    pub password: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl EcrAuthorization {
+    /// Decode a base64 `user:password` authorization token
+    fn decode(token: &str, expires_at: Option<f64>) -> Result<Self> {
+        let decoded = String::from_utf8(
+            BASE64_STANDARD
+                .decode(token)
+                .context("Invalid ECR authorization token")?,
+        )?;
+        let (username, password) = decoded
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid ECR authorization token"))?;
+        Ok(Self {
+            username: username.to_string(),
+            password: password.to_string(),
+            expires_at: expires_at.and_then(|secs| DateTime::from_timestamp(secs as i64, 0)),
+        })
+    }
+}
+
+#[async_trait]
+impl RegistryProvider for EcrProvider {
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        _auth: &RegistryAuth,
+        max_tags: usize,
+    ) -> Result<Vec<String>> {
+        let repository = EcrRepository::parse(reference)?;
+        let mut tags = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut request = json!({
+                "registryId": repository.registry_id,
+                "repositoryName": repository.name,
+                "maxResults": PAGE_SIZE,
+                "filter": { "tagStatus": "TAGGED" },
+            });
+            if let Some(token) = &next_token {
+                request["nextToken"] = json!(token);
+            }
+            let page = self.describe_images(&repository, request).await?;
+            tags.extend(
+                page.image_details
+                    .into_iter()
+                    .flat_map(|detail| detail.image_tags),
+            );
+            match page.next_token {
+                Some(token) if tags.len() <= max_tags => next_token = Some(token),
+                _ => return Ok(tags),
+            }
+        }
+    }
+
+    async fn fetch_digest(&self, reference: &Reference, _auth: &RegistryAuth) -> Result<String> {
+        let repository = EcrRepository::parse(reference)?;
+        let tag = reference.tag().unwrap_or("latest");
+        let request = json!({
+            "registryId": repository.registry_id,
+            "repositoryName": repository.name,
+            "imageIds": [{ "imageTag": tag }],
+        });
+        self.describe_images(&repository, request)
+            .await?
+            .image_details
+            .into_iter()
+            .next()
+            .map(|detail| detail.image_digest)
+            .ok_or_else(|| anyhow!("ECR has no image tagged {}", tag))
+    }
+}
+
+/// Headers for a SigV4-signed AWS JSON 1.1 `POST /` request (everything but `host`, which
+/// the HTTP client sets)
+fn sigv4_headers(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    host: &str,
+    target: &str,
+    payload: &str,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = &amz_date[..8];
+
+    // Sorted by name, as SigV4 requires
+    let mut headers = vec![
+        ("content-type", "application/x-amz-json-1.1".to_string()),
+        ("host", host.to_string()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    headers.push(("x-amz-target", target.to_string()));
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{}\n{}\n{}",
+        canonical_headers,
+        signed_headers,
+        hex::encode(Sha256::digest(payload.as_bytes()))
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = signing_key(&credentials.secret_access_key, date, region, service);
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    headers.retain(|(name, _)| *name != "host");
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    headers
+}
+
+/// SigV4 signing key for a date (`YYYYMMDD`), region and service
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+/// Text of the first `<name>` element in an XML document
+fn xml_value(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    Some(xml[start..end].trim().to_string())
+}
+
+/// An Artifact Registry package, from `<location>-docker.pkg.dev/<project>/<repository>/<image>`
+#[derive(Debug, PartialEq, Eq)]
+struct GarPackage {
+    project: String,
+    location: String,
+    repository: String,
+    package: String,
+}
+
+impl GarPackage {
+    fn parse(reference: &Reference) -> Result<Self> {
+        let location = reference
+            .registry()
+            .strip_suffix("-docker.pkg.dev")
+            .ok_or_else(|| anyhow!("{} is not an Artifact Registry host", reference.registry()))?;
+        let mut segments = reference.repository().splitn(3, '/');
+        match (segments.next(), segments.next(), segments.next()) {
+            (Some(project), Some(repository), Some(package)) if !package.is_empty() => Ok(Self {
+                project: project.to_string(),
+                location: location.to_string(),
+                repository: repository.to_string(),
+                package: package.to_string(),
+            }),
+            _ => Err(anyhow!(
+                "Expected <project>/<repository>/<image>, got {}",
+                reference.repository()
+            )),
+        }
+    }
+
+    /// URL of the package's tags collection (the package name is a single, encoded segment)
+    fn tags_url(&self) -> Result<Url> {
+        let mut url = Url::parse(ARTIFACT_REGISTRY_API)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("Invalid Artifact Registry URL"))?
+            .pop_if_empty()
+            .extend([
+                "projects",
+                &self.project,
+                "locations",
+                &self.location,
+                "repositories",
+                &self.repository,
+                "packages",
+                &self.package,
+                "tags",
+            ]);
+        Ok(url)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GarTagsResponse {
+    #[serde(default)]
+    tags: Vec<GarTag>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GarTag {
+    /// `projects/.../tags/<tag>`
+    name: String,
+    /// `projects/.../versions/sha256:<digest>`
+    #[serde(default)]
+    version: String,
+}
+
+impl GarTag {
+    fn tag(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or_default()
+    }
+
+    fn digest(&self) -> String {
+        self.version
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .replace("%3A", ":")
+    }
+}
+
+/// Google Artifact Registry REST API, authenticated with the pod's Google service account
+/// (Workload Identity)
+struct GarProvider {
+    http: reqwest::Client,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl GarProvider {
+    async fn access_token(&self) -> Result<String> {
+        let mut cached = self.token.lock().await;
+        if let Some((token, refresh_at)) = cached.as_ref()
+            && Instant::now() < *refresh_at
+        {
+            return Ok(token.clone());
+        }
+        let (token, refresh_at) = crate::pubsub::metadata_access_token(&self.http).await?;
+        *cached = Some((token.clone(), refresh_at));
+        Ok(token)
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: Url) -> Result<T> {
+        Ok(self
+            .http
+            .get(url)
+            .bearer_auth(self.access_token().await?)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[async_trait]
+impl RegistryProvider for GarProvider {
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        _auth: &RegistryAuth,
+        max_tags: usize,
+    ) -> Result<Vec<String>> {
+        let base = GarPackage::parse(reference)?.tags_url()?;
+        let mut tags = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = base.clone();
+            url.query_pairs_mut()
+                .append_pair("pageSize", &PAGE_SIZE.to_string());
+            if let Some(token) = &page_token {
+                url.query_pairs_mut().append_pair("pageToken", token);
+            }
+            let page: GarTagsResponse = self.get(url).await?;
+            tags.extend(page.tags.iter().map(|tag| tag.tag().to_string()));
+            match page.next_page_token.filter(|token| !token.is_empty()) {
+                Some(token) if tags.len() <= max_tags => page_token = Some(token),
+                _ => return Ok(tags),
+            }
+        }
+    }
+
+    async fn fetch_digest(&self, reference: &Reference, _auth: &RegistryAuth) -> Result<String> {
+        let mut url = GarPackage::parse(reference)?.tags_url()?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("Invalid Artifact Registry URL"))?
+            .push(reference.tag().unwrap_or("latest"));
+        let tag: GarTag = self.get(url).await?;
+        Ok(tag.digest())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AcrTagsResponse {
+    #[serde(default)]
+    tags: Vec<AcrTag>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcrTagResponse {
+    tag: AcrTag,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcrTag {
+    name: String,
+    digest: String,
+}
+
+/// Azure Container Registry `/acr/v1` API, authenticated with the pull secret's credentials
+struct AcrProvider {
+    http: reqwest::Client,
+}
+
+impl AcrProvider {
+    async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        url: Url,
+        auth: &RegistryAuth,
+    ) -> Result<T> {
+        let mut request = self.http.get(url);
+        if let RegistryAuth::Basic(username, password) = auth {
+            request = request.basic_auth(username, Some(password));
+        }
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+}
+
+#[async_trait]
+impl RegistryProvider for AcrProvider {
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+        max_tags: usize,
+    ) -> Result<Vec<String>> {
+        let base = format!(
+            "https://{}/acr/v1/{}/_tags",
+            reference.registry(),
+            reference.repository()
+        );
+        paginate_by_last(PAGE_SIZE, max_tags, |last| {
+            let base = &base;
+            async move {
+                let page_size = PAGE_SIZE.to_string();
+                let mut params = vec![("n", page_size.as_str())];
+                if let Some(last) = &last {
+                    params.push(("last", last.as_str()));
+                }
+                let url = Url::parse_with_params(base, &params)?;
+                let page: AcrTagsResponse = self.get(url, auth).await?;
+                Ok(page.tags.into_iter().map(|tag| tag.name).collect())
+            }
+        })
+        .await
+    }
+
+    async fn fetch_digest(&self, reference: &Reference, auth: &RegistryAuth) -> Result<String> {
+        let url = Url::parse(&format!(
+            "https://{}/acr/v1/{}/_tags/{}",
+            reference.registry(),
+            reference.repository(),
+            reference.tag().unwrap_or("latest")
+        ))?;
+        let response: AcrTagResponse = self.get(url, auth).await?;
+        Ok(response.tag.digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(image: &str) -> Reference {
+        Reference::try_from(image).unwrap()
+    }
+
+    #[test]
+    fn test_detect_provider() {
+        assert_eq!(
+            ProviderKind::detect("123456789012.dkr.ecr.eu-west-1.amazonaws.com"),
+            ProviderKind::Ecr
+        );
+        assert_eq!(
+            ProviderKind::detect("123456789012.dkr.ecr.cn-north-1.amazonaws.com.cn"),
+            ProviderKind::Ecr
+        );
+        assert_eq!(
+            ProviderKind::detect("us-east1-docker.pkg.dev"),
+            ProviderKind::Gar
+        );
+        assert_eq!(
+            ProviderKind::detect("myregistry.azurecr.io"),
+            ProviderKind::Acr
+        );
+        assert_eq!(ProviderKind::detect("ghcr.io"), ProviderKind::Oci);
+        assert_eq!(ProviderKind::detect("public.ecr.aws"), ProviderKind::Oci);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let providers = RegistryProviders::new(
+            parse_overrides(
+                "myregistry.azurecr.io=oci, *.internal.example.com=acr, bogus, x=unknown",
+            ),
+            DEFAULT_MAX_TAGS,
+        );
+        assert_eq!(providers.overrides.len(), 2);
+        assert_eq!(
+            providers.kind_for("myregistry.azurecr.io"),
+            ProviderKind::Oci
+        );
+        assert_eq!(
+            providers.kind_for("registry.internal.example.com"),
+            ProviderKind::Acr
+        );
+        assert_eq!(
+            providers.kind_for("internal.example.com"),
+            ProviderKind::Oci
+        );
+        assert_eq!(providers.kind_for("other.azurecr.io"), ProviderKind::Acr);
+    }
+
+    /// Serve `available` tags in order, `page_size` at a time after `last`
+    fn page_of(available: &[String], page_size: usize, last: Option<String>) -> Vec<String> {
+        let start = last
+            .and_then(|last| available.iter().position(|tag| *tag == last))
+            .map_or(0, |index| index + 1);
+        available
+            .iter()
+            .skip(start)
+            .take(page_size)
+            .cloned()
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_paginate_by_last() {
+        let available: Vec<String> = (0..250).map(|i| format!("1.0.{}", i)).collect();
+
+        let mut requests = 0;
+        let tags = paginate_by_last(100, DEFAULT_MAX_TAGS, |last| {
+            requests += 1;
+            let page = page_of(&available, 100, last);
+            async move { Ok(page) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(tags, available);
+        assert_eq!(requests, 3);
+
+        // Stops once the cap is exceeded
+        let tags = paginate_by_last(100, 150, |last| {
+            let page = page_of(&available, 100, last);
+            async move { Ok(page) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(tags.len(), 200);
+
+        // A registry that ignores `last` returns the first page again
+        let tags = paginate_by_last(100, DEFAULT_MAX_TAGS, |_| {
+            let page = page_of(&available, 100, None);
+            async move { Ok(page) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(tags.len(), 100);
+    }
+
+    #[test]
+    fn test_parse_ecr_repository() {
+        let repository = EcrRepository::parse(&reference(
+            "123456789012.dkr.ecr.eu-west-1.amazonaws.com/team/web:1.2.0",
+        ))
+        .unwrap();
+        assert_eq!(repository.registry_id, "123456789012");
+        assert_eq!(repository.region, "eu-west-1");
+        assert_eq!(repository.name, "team/web");
+        assert_eq!(repository.api_host(), "api.ecr.eu-west-1.amazonaws.com");
+
+        assert!(EcrRepository::parse(&reference("ghcr.io/org/web:1.0")).is_err());
+    }
+
+    #[test]
+    fn test_decode_ecr_authorization() {
+        let token = BASE64_STANDARD.encode("AWS:eyJwYXlsb2FkIjoi");
+        let authorization = EcrAuthorization::decode(&token, Some(1_700_000_000.5)).unwrap();
+        assert_eq!(authorization.username, "AWS");
+        assert_eq!(authorization.password, "eyJwYXlsb2FkIjoi");
+        assert_eq!(
+            authorization.expires_at,
+            DateTime::from_timestamp(1_700_000_000, 0)
+        );
+
+        let invalid = BASE64_STANDARD.encode("no separator");
+        assert!(EcrAuthorization::decode(&invalid, None).is_err());
+    }
+
+    #[test]
+    fn test_sigv4_signing_key() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_sigv4_headers() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+            session_token: Some("session".to_string()),
+            refresh_at: None,
+        };
+        let now = "2024-03-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let headers = sigv4_headers(
+            &credentials,
+            "eu-west-1",
+            "ecr",
+            "api.ecr.eu-west-1.amazonaws.com",
+            ECR_DESCRIBE_IMAGES,
+            "{}",
+            now,
+        );
+
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(header("host"), None);
+        assert_eq!(header("x-amz-date"), Some("20240301T120000Z"));
+        assert_eq!(header("x-amz-security-token"), Some("session"));
+        let authorization = header("authorization").unwrap();
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240301/eu-west-1/ecr/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date;x-amz-security-token;x-amz-target, \
+             Signature="
+        ));
+    }
+
+    #[test]
+    fn test_xml_value() {
+        let xml = "<Credentials><AccessKeyId>ASIA123</AccessKeyId>\
+                   <SessionToken> token </SessionToken></Credentials>";
+        assert_eq!(xml_value(xml, "AccessKeyId").as_deref(), Some("ASIA123"));
+        assert_eq!(xml_value(xml, "SessionToken").as_deref(), Some("token"));
+        assert_eq!(xml_value(xml, "Expiration"), None);
+    }
+
+    #[test]
+    fn test_gar_package() {
+        let package = GarPackage::parse(&reference(
+            "us-east1-docker.pkg.dev/my-project/apps/team/web:1.0",
+        ))
+        .unwrap();
+        assert_eq!(package.location, "us-east1");
+        assert_eq!(package.project, "my-project");
+        assert_eq!(package.repository, "apps");
+        assert_eq!(package.package, "team/web");
+        assert_eq!(
+            package.tags_url().unwrap().as_str(),
+            "https://artifactregistry.googleapis.com/v1/projects/my-project/locations/us-east1/repositories/apps/packages/team%2Fweb/tags"
+        );
+
+        assert!(GarPackage::parse(&reference("us-east1-docker.pkg.dev/my-project/web:1")).is_err());
+
+        let tag: GarTag = serde_json::from_value(json!({
+            "name": "projects/p/locations/l/repositories/r/packages/web/tags/1.2.0",
+            "version": "projects/p/locations/l/repositories/r/packages/web/versions/sha256%3Aabc"
+        }))
+        .unwrap();
+        assert_eq!(tag.tag(), "1.2.0");
+        assert_eq!(tag.digest(), "sha256:abc");
+    }
+
+    #[test]
+    fn test_config_labels() {
+        let config = json!({
+            "architecture": "amd64",
+            "config": {
+                "Labels": {
+                    "org.opencontainers.image.source": "https://github.com/nginx/nginx",
+                    "maintainer": "NGINX"
+                }
+            }
+        });
+        let labels = config_labels(&config.to_string()).unwrap();
+        assert_eq!(
+            labels.get("org.opencontainers.image.source").unwrap(),
+            "https://github.com/nginx/nginx"
+        );
+        assert_eq!(labels.len(), 2);
+
+        assert!(config_labels(r#"{"config":{}}"#).unwrap().is_empty());
+        assert!(config_labels("not json").is_err());
+    }
+
+    #[test]
+    fn test_config_platform() {
+        let config = json!({ "architecture": "arm64", "os": "linux", "variant": "v8" });
+        assert_eq!(
+            config_platform(&config.to_string()).unwrap().as_deref(),
+            Some("linux/arm64")
+        );
+        assert_eq!(config_platform(r#"{"os":"linux"}"#).unwrap(), None);
+        assert_eq!(platform_name("unknown", "unknown"), None);
+    }
+
+    #[test]
+    fn test_config_metadata() {
+        let config = json!({
+            "created": "2026-03-01T12:00:00Z",
+            "config": {
+                "ExposedPorts": { "8080/tcp": {}, "9090/tcp": {} },
+                "Labels": { "org.opencontainers.image.version": "1.27.0" }
+            }
+        });
+        let metadata = config_metadata(&config.to_string()).unwrap();
+        assert_eq!(
+            metadata.created,
+            Some("2026-03-01T12:00:00Z".parse().unwrap())
+        );
+        assert_eq!(metadata.exposed_ports, vec!["8080/tcp", "9090/tcp"]);
+        assert_eq!(
+            metadata.labels["org.opencontainers.image.version"],
+            "1.27.0"
+        );
+
+        assert_eq!(config_metadata("{}").unwrap(), ImageMetadata::default());
+    }
+
+    #[test]
+    fn test_config_created() {
+        let config = json!({ "created": "2026-03-01T12:00:00.123456789Z", "os": "linux" });
+        assert_eq!(
+            config_created(&config.to_string()).unwrap(),
+            Some("2026-03-01T12:00:00.123456789Z".parse().unwrap())
+        );
+        // Reproducible builds
+        let config = json!({ "created": "1970-01-01T00:00:00Z" });
+        assert_eq!(config_created(&config.to_string()).unwrap(), None);
+        assert_eq!(config_created("{}").unwrap(), None);
+        assert_eq!(
+            parse_created("2026-03-01T14:00:00+02:00"),
+            Some("2026-03-01T12:00:00Z".parse().unwrap())
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Google Cloud Pub/Sub listener for Artifact Registry and Container Registry push
+//! notifications.
+//!
+//! Both registries publish to the `gcr` topic in their project. Headwind pulls from a
+//! subscription on that topic and turns image pushes into `ImagePushEvent`s, so GCP clusters
+//! get near-instant updates without exposing a webhook endpoint or polling.
+//!
+//! Requests are authenticated with the pod's Google service account through the GKE
+//! metadata server (Workload Identity). When `PUBSUB_EMULATOR_HOST` is set, the emulator is
+//! used without authentication.
+
+use crate::config::{self, PubSubConfig};
+use crate::metrics::{PUBSUB_ERRORS, PUBSUB_MESSAGES_RECEIVED};
+use crate::models::webhook::ImagePushEvent;
+use crate::webhook::{EventSender, provenance};
+use anyhow::{Context, Result, anyhow};
+use base64::prelude::*;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+const PUBSUB_ENDPOINT: &str = "https://pubsub.googleapis.com";
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// How often the configuration is re-checked while the listener is disabled
+const DISABLED_RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Delay before retrying after a failed pull
+const ERROR_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Registry notification published to the `gcr` topic
+#[derive(Debug, Deserialize)]
+struct RegistryNotification {
+    /// INSERT or DELETE
+    action: String,
+    /// `host/path@sha256:...`
+    #[serde(default)]
+    digest: Option<String>,
+    /// `host/path:tag`, absent for untagged pushes
+    #[serde(default)]
+    tag: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullResponse {
+    #[serde(default)]
+    received_messages: Vec<ReceivedMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReceivedMessage {
+    ack_id: String,
+    message: PubSubMessage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PubSubMessage {
+    #[serde(default)]
+    data: String,
+    #[serde(default)]
+    message_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Start the listener. It follows the `pubsub.*` configuration, so enabling or changing the
+/// subscription takes effect without a restart.
+pub fn start_pubsub_listener(event_tx: EventSender) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut listener = PubSubListener::new(event_tx);
+        loop {
+            let config = config::get_cached_config()
+                .map(|c| c.pubsub)
+                .unwrap_or_default();
+            if !config.enabled {
+                tokio::time::sleep(DISABLED_RECHECK_INTERVAL).await;
+                continue;
+            }
+
+            let Some(subscription) = subscription_path(&config) else {
+                warn!("Pub/Sub is enabled but no subscription (and project) is configured");
+                tokio::time::sleep(DISABLED_RECHECK_INTERVAL).await;
+                continue;
+            };
+
+            if let Err(e) = listener
+                .pull_once(&subscription, config.max_messages.max(1))
+                .await
+            {
+                PUBSUB_ERRORS.inc();
+                error!(
+                    "Failed to pull from Pub/Sub subscription {}: {}",
+                    subscription, e
+                );
+                tokio::time::sleep(ERROR_BACKOFF).await;
+            }
+        }
+    })
+}
+
+/// Full resource name of the configured subscription
+pub fn subscription_path(config: &PubSubConfig) -> Option<String> {
+    let subscription = config.subscription.as_deref()?.trim();
+    if subscription.is_empty() {
+        return None;
+    }
+    if subscription.starts_with("projects/") {
+        return Some(subscription.to_string());
+    }
+    let project = config.project.as_deref()?.trim();
+    if project.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "projects/{}/subscriptions/{}",
+        project, subscription
+    ))
+}
+
+/// Convert a registry notification into a push event. Deletions and untagged pushes are ignored.
+pub fn parse_notification(data: &[u8]) -> Result<Option<ImagePushEvent>> {
+    let notification: RegistryNotification =
+        serde_json::from_slice(data).context("Invalid registry notification")?;
+
+    if notification.action != "INSERT" {
+        return Ok(None);
+    }
+    let Some(image) = notification.tag else {
+        return Ok(None);
+    };
+
+    let (name, tag) = image
+        .rsplit_once(':')
+        .filter(|(_, tag)| !tag.contains('/'))
+        .ok_or_else(|| anyhow!("Notification tag {} has no tag", image))?;
+    let (registry, repository) = name
+        .split_once('/')
+        .ok_or_else(|| anyhow!("Notification image {} has no registry", name))?;
+
+    Ok(Some(ImagePushEvent {
+        registry: registry.to_string(),
+        repository: repository.to_string(),
+        tag: tag.to_string(),
+        digest: notification
+            .digest
+            .and_then(|d| d.split_once('@').map(|(_, digest)| digest.to_string())),
+        source: None,
+    }))
+}
+
+struct PubSubListener {
+    http: reqwest::Client,
+    event_tx: EventSender,
+    endpoint: String,
+    /// Emulators don't authenticate
+    emulator: bool,
+    token: Option<(String, Instant)>,
+}
+
+impl PubSubListener {
+    fn new(event_tx: EventSender) -> Self {
+        let emulator_host = std::env::var("PUBSUB_EMULATOR_HOST")
+            .ok()
+            .filter(|host| !host.is_empty());
+        let endpoint = emulator_host
+            .as_ref()
+            .map(|host| format!("http://{}", host))
+            .unwrap_or_else(|| PUBSUB_ENDPOINT.to_string());
+
+        Self {
+            // Pulls are long-polled by Pub/Sub, so allow well over its hold time
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(120))
+                .build()
+                .unwrap_or_default(),
+            event_tx,
+            endpoint,
+            emulator: emulator_host.is_some(),
+            token: None,
+        }
+    }
+
+    /// Pull one batch, forward image pushes and acknowledge every message
+    async fn pull_once(&mut self, subscription: &str, max_messages: u32) -> Result<()> {
+        let url = format!("{}/v1/{}:pull", self.endpoint, subscription);
+        let response: PullResponse = self
+            .authorized(self.http.post(&url))
+            .await?
+            .json(&serde_json::json!({ "maxMessages": max_messages }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if response.received_messages.is_empty() {
+            return Ok(());
+        }
+
+        let mut ack_ids = Vec::with_capacity(response.received_messages.len());
+        for received in response.received_messages {
+            PUBSUB_MESSAGES_RECEIVED.inc();
+            ack_ids.push(received.ack_id);
+
+            let event = BASE64_STANDARD
+                .decode(received.message.data.as_bytes())
+                .map_err(anyhow::Error::from)
+                .and_then(|data| parse_notification(&data));
+            match event {
+                Ok(Some(mut event)) => {
+                    info!(
+                        "Received Pub/Sub push notification for {}",
+                        event.full_image()
+                    );
+                    event.source = Some(provenance::pubsub(subscription, event.digest.as_deref()));
+                    // Waits while the event queue is full, slowing down the pull loop
+                    if let Err(e) = self.event_tx.send(event).await {
+                        error!("Failed to send push event: {}", e);
+                    }
+                },
+                Ok(None) => debug!(
+                    "Ignoring Pub/Sub message {} (not a tagged push)",
+                    received.message.message_id
+                ),
+                Err(e) => {
+                    PUBSUB_ERRORS.inc();
+                    warn!(
+                        "Dropping Pub/Sub message {}: {}",
+                        received.message.message_id, e
+                    );
+                },
+            }
+        }
+
+        let url = format!("{}/v1/{}:acknowledge", self.endpoint, subscription);
+        self.authorized(self.http.post(&url))
+            .await?
+            .json(&serde_json::json!({ "ackIds": ack_ids }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn authorized(
+        &mut self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::RequestBuilder> {
+        if self.emulator {
+            return Ok(request);
+        }
+        Ok(request.bearer_auth(self.access_token().await?))
+    }
+
+    /// Access token for the pod's service account, refreshed a minute before it expires
+    async fn access_token(&mut self) -> Result<String> {
+        if let Some((token, expires_at)) = &self.token
+            && Instant::now() < *expires_at
+        {
+            return Ok(token.clone());
+        }
+
+        let (token, expires_at) = metadata_access_token(&self.http).await?;
+        self.token = Some((token.clone(), expires_at));
+        Ok(token)
+    }
+}
+
+/// Fetch an access token for the pod's Google service account from the GKE metadata server.
+/// Returns the token and when to refresh it (a minute before it expires).
+pub(crate) async fn metadata_access_token(http: &reqwest::Client) -> Result<(String, Instant)> {
+    let token: MetadataToken = http
+        .get(METADATA_TOKEN_URL)
+        .header("Metadata-Flavor", "Google")
+        .send()
+        .await
+        .context("Failed to reach the GKE metadata server")?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let lifetime = Duration::from_secs(token.expires_in.saturating_sub(60));
+    Ok((token.access_token, Instant::now() + lifetime))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_artifact_registry_notification() {
+        let data = br#"{
+            "action": "INSERT",
+            "digest": "us-east1-docker.pkg.dev/my-project/my-repo/web@sha256:6ec128e26cd5",
+            "tag": "us-east1-docker.pkg.dev/my-project/my-repo/web:1.5.0"
+        }"#;
+
+        let event = parse_notification(data).unwrap().unwrap();
+        assert_eq!(event.registry, "us-east1-docker.pkg.dev");
+        assert_eq!(event.repository, "my-project/my-repo/web");
+        assert_eq!(event.tag, "1.5.0");
+        assert_eq!(event.digest.as_deref(), Some("sha256:6ec128e26cd5"));
+    }
+
+    #[test]
+    fn test_ignored_notifications() {
+        // Untagged push
+        let data = br#"{"action": "INSERT", "digest": "gcr.io/my-project/web@sha256:abc"}"#;
+        assert!(parse_notification(data).unwrap().is_none());
+
+        // Deletion
+        let data = br#"{"action": "DELETE", "tag": "gcr.io/my-project/web:1.0.0"}"#;
+        assert!(parse_notification(data).unwrap().is_none());
+
+        assert!(parse_notification(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_subscription_path() {
+        let mut config = PubSubConfig {
+            enabled: true,
+            project: Some("my-project".to_string()),
+            subscription: Some("headwind".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            subscription_path(&config).as_deref(),
+            Some("projects/my-project/subscriptions/headwind")
+        );
+
+        config.project = None;
+        assert_eq!(subscription_path(&config), None);
+
+        config.subscription = Some("projects/other/subscriptions/gcr".to_string());
+        assert_eq!(
+            subscription_path(&config).as_deref(),
+            Some("projects/other/subscriptions/gcr")
+        );
+    }
+}
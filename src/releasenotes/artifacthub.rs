@@ -0,0 +1,157 @@
+use super::{error_for_status, http_client, summarize};
+use crate::models::crd::ReleaseNotes;
+use anyhow::Result;
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use tracing::debug;
+
+/// Artifact Hub API client (HEADWIND_ARTIFACT_HUB_URL, default: https://artifacthub.io)
+pub struct ArtifactHub {
+    http: Client,
+    url: String,
+}
+
+impl ArtifactHub {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            http: http_client()?,
+            url: std::env::var("HEADWIND_ARTIFACT_HUB_URL")
+                .ok()
+                .map(|url| url.trim().trim_end_matches('/').to_string())
+                .filter(|url| !url.is_empty())
+                .unwrap_or_else(|| "https://artifacthub.io".to_string()),
+        })
+    }
+
+    /// The changes listed for `version` of the Helm chart `chart`. Charts are found by name;
+    /// when several repositories publish a chart with that name, none is picked.
+    pub async fn release(&self, chart: &str, version: &str) -> Result<Option<ReleaseNotes>> {
+        let response = self
+            .http
+            .get(format!("{}/api/v1/packages/search", self.url))
+            .query(&[("ts_query_web", chart), ("kind", "0"), ("limit", "60")])
+            .send()
+            .await?;
+        let search: Value = error_for_status(response, "search Artifact Hub")
+            .await?
+            .json()
+            .await?;
+        let Some(repository) = repository_for(&search, chart) else {
+            debug!(
+                "No single Artifact Hub repository publishes chart {}",
+                chart
+            );
+            return Ok(None);
+        };
+
+        let response = self
+            .http
+            .get(format!(
+                "{}/api/v1/packages/helm/{}/{}/{}",
+                self.url, repository, chart, version
+            ))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let package: Value = error_for_status(
+            response,
+            &format!(
+                "read {}/{} {} from Artifact Hub",
+                repository, chart, version
+            ),
+        )
+        .await?
+        .json()
+        .await?;
+
+        Ok(Some(ReleaseNotes {
+            source: "artifacthub".to_string(),
+            url: format!(
+                "{}/packages/helm/{}/{}/{}",
+                self.url, repository, chart, version
+            ),
+            title: Some(format!("{} {}", chart, version)),
+            summary: changes(&package).as_deref().and_then(summarize),
+        }))
+    }
+}
+
+/// Name of the only repository in search results that publishes a chart named `chart`
+fn repository_for(search: &Value, chart: &str) -> Option<String> {
+    let mut repositories = search["packages"]
+        .as_array()?
+        .iter()
+        .filter(|package| package["name"].as_str() == Some(chart))
+        .filter_map(|package| package["repository"]["name"].as_str());
+    let repository = repositories.next()?;
+    if repositories.any(|other| other != repository) {
+        return None;
+    }
+    Some(repository.to_string())
+}
+
+/// The `changes` of a package version as a Markdown list. Artifact Hub returns them as
+/// objects (`{"kind": "added", "description": ...}`), older packages as plain strings.
+fn changes(package: &Value) -> Option<String> {
+    let lines: Vec<String> = package["changes"]
+        .as_array()?
+        .iter()
+        .filter_map(|change| match change {
+            Value::String(description) => Some(format!("- {}", description)),
+            Value::Object(_) => {
+                let description = change["description"].as_str()?;
+                Some(match change["kind"].as_str() {
+                    Some(kind) => format!("- {}: {}", kind, description),
+                    None => format!("- {}", description),
+                })
+            },
+            _ => None,
+        })
+        .collect();
+    Some(lines.join("\n")).filter(|lines| !lines.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_repository_for() {
+        let search = json!({
+            "packages": [
+                { "name": "nginx-ingress", "repository": { "name": "nginx" } },
+                { "name": "nginx", "repository": { "name": "bitnami" } }
+            ]
+        });
+        assert_eq!(repository_for(&search, "nginx").as_deref(), Some("bitnami"));
+        assert_eq!(repository_for(&search, "redis"), None);
+
+        let ambiguous = json!({
+            "packages": [
+                { "name": "redis", "repository": { "name": "bitnami" } },
+                { "name": "redis", "repository": { "name": "dandydev" } }
+            ]
+        });
+        assert_eq!(repository_for(&ambiguous, "redis"), None);
+    }
+
+    #[test]
+    fn test_changes() {
+        let package = json!({
+            "changes": [
+                { "kind": "added", "description": "Support for PDBs" },
+                { "description": "Bump app to 1.27" },
+                "Legacy change"
+            ]
+        });
+        assert_eq!(
+            changes(&package).as_deref(),
+            Some("- added: Support for PDBs\n- Bump app to 1.27\n- Legacy change")
+        );
+        assert_eq!(changes(&json!({ "changes": null })), None);
+        assert_eq!(changes(&json!({ "changes": [] })), None);
+    }
+}
@@ -0,0 +1,181 @@
+use super::{error_for_status, http_client, summarize};
+use crate::models::crd::ReleaseNotes;
+use anyhow::Result;
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+
+const API_URL: &str = "https://api.github.com";
+
+/// A repository on github.com
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubRepository {
+    pub owner: String,
+    pub name: String,
+}
+
+impl GitHubRepository {
+    /// The repository an `org.opencontainers.image.source` label points to, e.g.
+    /// `https://github.com/owner/repo`, `https://github.com/owner/repo.git`,
+    /// `https://github.com/owner/repo/tree/main/images/app` or `git@github.com:owner/repo.git`.
+    /// None for other hosts.
+    pub fn parse(source: &str) -> Option<Self> {
+        let source = source.trim();
+        let path = source.strip_prefix("git@github.com:").or_else(|| {
+            let rest = source.split_once("://").map_or(source, |(_, rest)| rest);
+            let rest = rest.rsplit_once('@').map_or(rest, |(_, host)| host);
+            rest.strip_prefix("github.com/")
+                .or_else(|| rest.strip_prefix("www.github.com/"))
+        })?;
+
+        let mut segments = path.split('/');
+        let owner = segments.next().filter(|owner| !owner.is_empty())?;
+        let name = segments.next()?.trim_end_matches(".git");
+        if name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            owner: owner.to_string(),
+            name: name.to_string(),
+        })
+    }
+}
+
+/// GitHub Releases API client. HEADWIND_GITHUB_TOKEN raises the rate limit and gives access to
+/// private repositories.
+pub struct GitHub {
+    http: Client,
+    token: Option<String>,
+}
+
+impl GitHub {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            http: http_client()?,
+            token: std::env::var("HEADWIND_GITHUB_TOKEN")
+                .ok()
+                .map(|token| token.trim().to_string())
+                .filter(|token| !token.is_empty()),
+        })
+    }
+
+    /// The release for `version`, tagged either as the version or with a `v` prefix added or
+    /// removed
+    pub async fn release(
+        &self,
+        repository: &GitHubRepository,
+        version: &str,
+    ) -> Result<Option<ReleaseNotes>> {
+        for tag in tag_candidates(version) {
+            let mut request = self
+                .http
+                .get(format!(
+                    "{}/repos/{}/{}/releases/tags/{}",
+                    API_URL, repository.owner, repository.name, tag
+                ))
+                .header("Accept", "application/vnd.github+json");
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+            let response = request.send().await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                continue;
+            }
+            let body: Value = error_for_status(
+                response,
+                &format!(
+                    "read GitHub release {} of {}/{}",
+                    tag, repository.owner, repository.name
+                ),
+            )
+            .await?
+            .json()
+            .await?;
+            return Ok(notes_from_release(&body));
+        }
+        Ok(None)
+    }
+}
+
+/// Tags a release of `version` may be published under
+fn tag_candidates(version: &str) -> Vec<String> {
+    match version.strip_prefix('v') {
+        Some(bare) => vec![version.to_string(), bare.to_string()],
+        None => vec![version.to_string(), format!("v{}", version)],
+    }
+}
+
+/// Release notes from a GitHub release (`{"html_url": ..., "name": ..., "body": ...}`)
+fn notes_from_release(release: &Value) -> Option<ReleaseNotes> {
+    Some(ReleaseNotes {
+        source: "github".to_string(),
+        url: release["html_url"].as_str()?.to_string(),
+        title: release["name"]
+            .as_str()
+            .filter(|name| !name.trim().is_empty())
+            .or_else(|| release["tag_name"].as_str())
+            .map(str::to_string),
+        summary: release["body"].as_str().and_then(summarize),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn repository(owner: &str, name: &str) -> Option<GitHubRepository> {
+        Some(GitHubRepository {
+            owner: owner.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_parse_repository() {
+        assert_eq!(
+            GitHubRepository::parse("https://github.com/headwind-sh/headwind"),
+            repository("headwind-sh", "headwind")
+        );
+        assert_eq!(
+            GitHubRepository::parse("https://github.com/nginx/nginx.git"),
+            repository("nginx", "nginx")
+        );
+        assert_eq!(
+            GitHubRepository::parse("https://github.com/org/mono/tree/main/images/app"),
+            repository("org", "mono")
+        );
+        assert_eq!(
+            GitHubRepository::parse("git@github.com:org/app.git"),
+            repository("org", "app")
+        );
+        assert_eq!(
+            GitHubRepository::parse("git+https://git@github.com/org/app"),
+            repository("org", "app")
+        );
+        assert_eq!(GitHubRepository::parse("https://gitlab.com/org/app"), None);
+        assert_eq!(GitHubRepository::parse("https://github.com/org"), None);
+    }
+
+    #[test]
+    fn test_tag_candidates() {
+        assert_eq!(tag_candidates("1.2.3"), vec!["1.2.3", "v1.2.3"]);
+        assert_eq!(tag_candidates("v1.2.3"), vec!["v1.2.3", "1.2.3"]);
+    }
+
+    #[test]
+    fn test_notes_from_release() {
+        let notes = notes_from_release(&json!({
+            "html_url": "https://github.com/org/app/releases/tag/v1.2.3",
+            "tag_name": "v1.2.3",
+            "name": "",
+            "body": "## Fixes\n- crash on start"
+        }))
+        .unwrap();
+        assert_eq!(notes.source, "github");
+        assert_eq!(notes.url, "https://github.com/org/app/releases/tag/v1.2.3");
+        assert_eq!(notes.title.as_deref(), Some("v1.2.3"));
+        assert_eq!(notes.summary.as_deref(), Some("## Fixes\n- crash on start"));
+
+        assert!(notes_from_release(&json!({"message": "Not Found"})).is_none());
+    }
+}
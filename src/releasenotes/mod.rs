@@ -0,0 +1,191 @@
+//! Release notes for proposed updates, so approvers can see what changed before approving.
+//!
+//! With HEADWIND_RELEASE_NOTES enabled, the release notes of the new version are looked up when
+//! an UpdateRequest is created and recorded in its `status.releaseNotes`, and included in the
+//! "update request created" notification. Images are looked up as GitHub Releases of the
+//! repository in their `org.opencontainers.image.source` label, Helm charts on Artifact Hub.
+
+mod artifacthub;
+mod github;
+
+use crate::controller::workload::split_image;
+use crate::metrics::{RELEASE_NOTES_ERRORS, RELEASE_NOTES_FOUND};
+use crate::models::crd::{ReleaseNotes, UpdateRequest, UpdateType};
+use crate::polling;
+use anyhow::{Context, Result, anyhow};
+use kube::api::{Api, Patch, PatchParams};
+use kube::{Client, ResourceExt};
+use reqwest::Response;
+use serde_json::json;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+pub use artifacthub::ArtifactHub;
+pub use github::{GitHub, GitHubRepository};
+
+/// Image label naming the source repository of an image
+pub const SOURCE_LABEL: &str = "org.opencontainers.image.source";
+
+/// How long a lookup may take before the UpdateRequest is left without release notes
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Longest summary recorded in the UpdateRequest status
+const MAX_SUMMARY_CHARS: usize = 500;
+
+/// Whether release notes are looked up (HEADWIND_RELEASE_NOTES, default: false)
+pub fn enabled() -> bool {
+    std::env::var("HEADWIND_RELEASE_NOTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Look up the release notes of a newly created UpdateRequest and record them in its status.
+/// Returns the notes, or None when the lookup is disabled, found nothing or failed.
+pub async fn attach(
+    api: &Api<UpdateRequest>,
+    update_request: &UpdateRequest,
+) -> Option<ReleaseNotes> {
+    if !enabled() {
+        return None;
+    }
+    let name = update_request.name_any();
+    let client = api.clone().into_client();
+
+    let lookup = tokio::time::timeout(FETCH_TIMEOUT, fetch(&client, update_request))
+        .await
+        .map_err(|_| anyhow!("timed out after {}s", FETCH_TIMEOUT.as_secs()))
+        .and_then(|result| result);
+    let notes = match lookup {
+        Ok(Some(notes)) => notes,
+        Ok(None) => {
+            debug!(
+                "No release notes found for {} (UpdateRequest {})",
+                update_request.spec.new_image, name
+            );
+            return None;
+        },
+        Err(e) => {
+            RELEASE_NOTES_ERRORS.inc();
+            warn!(
+                "Failed to look up release notes for UpdateRequest {}: {:#}",
+                name, e
+            );
+            return None;
+        },
+    };
+    RELEASE_NOTES_FOUND
+        .with_label_values(&[notes.source.as_str()])
+        .inc();
+    info!(
+        "Found release notes for UpdateRequest {}: {}",
+        name, notes.url
+    );
+
+    let status_patch = json!({
+        "apiVersion": "headwind.sh/v1alpha1",
+        "kind": "UpdateRequest",
+        "status": { "releaseNotes": notes }
+    });
+    if let Err(e) = api
+        .patch_status(&name, &PatchParams::default(), &Patch::Merge(status_patch))
+        .await
+    {
+        warn!(
+            "Failed to record release notes on UpdateRequest {}: {}",
+            name, e
+        );
+    }
+    Some(notes)
+}
+
+/// Release notes of the version an UpdateRequest proposes
+pub async fn fetch(
+    client: &Client,
+    update_request: &UpdateRequest,
+) -> Result<Option<ReleaseNotes>> {
+    let spec = &update_request.spec;
+    let Some((name, version)) = split_image(&spec.new_image) else {
+        return Ok(None);
+    };
+    let version = version.split('@').next().unwrap_or(version);
+
+    match spec.update_type {
+        UpdateType::Image => {
+            let labels =
+                polling::image_labels(client, &spec.new_image, &spec.target_ref.namespace).await?;
+            let Some(repository) = labels
+                .get(SOURCE_LABEL)
+                .and_then(|source| GitHubRepository::parse(source))
+            else {
+                debug!("{} has no GitHub {} label", spec.new_image, SOURCE_LABEL);
+                return Ok(None);
+            };
+            GitHub::from_env()?.release(&repository, version).await
+        },
+        UpdateType::HelmChart => {
+            let chart = name.rsplit('/').next().unwrap_or(name);
+            ArtifactHub::from_env()?.release(chart, version).await
+        },
+    }
+}
+
+/// Start of release notes for display: at most MAX_SUMMARY_CHARS, cut at a line break where
+/// possible. None for empty notes.
+fn summarize(text: &str) -> Option<String> {
+    let text = text.replace("\r\n", "\n");
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= MAX_SUMMARY_CHARS {
+        return Some(text.to_string());
+    }
+
+    let cut: String = text.chars().take(MAX_SUMMARY_CHARS).collect();
+    let cut = match cut.rfind('\n') {
+        Some(line_end) if line_end > 0 => &cut[..line_end],
+        _ => cut.as_str(),
+    };
+    Some(format!("{}\n…", cut.trim_end()))
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent("headwind")
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+async fn error_for_status(response: Response, action: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(anyhow!("Failed to {}: {} {}", action, status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(summarize("  \r\n "), None);
+        assert_eq!(
+            summarize("## Fixes\r\n- crash on start\r\n").as_deref(),
+            Some("## Fixes\n- crash on start")
+        );
+
+        let long = format!("## Changes\n{}\n{}", "a".repeat(300), "b".repeat(300));
+        let summary = summarize(&long).unwrap();
+        assert_eq!(summary, format!("## Changes\n{}\n…", "a".repeat(300)));
+
+        let single_line = "x".repeat(600);
+        let summary = summarize(&single_line).unwrap();
+        assert_eq!(summary.chars().count(), MAX_SUMMARY_CHARS + 2);
+        assert!(summary.ends_with('…'));
+    }
+}
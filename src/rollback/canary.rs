@@ -0,0 +1,475 @@
+// Canary rollouts for approved Deployment updates
+//
+// With `headwind.sh/canary: "true"`, an approved update first runs the new image in a
+// temporary `<name>-canary` Deployment sized to `headwind.sh/canary-percent` of the replicas.
+// Its pods carry the Deployment's pod labels, so Services send them their share of traffic.
+// The canary has to stay healthy for `headwind.sh/canary-timeout` seconds; the Deployment is
+// then updated as usual. Otherwise the canary is removed and the Deployment is left untouched.
+
+use super::{HealthChecker, HealthStatus};
+use crate::models::policy::annotations;
+use anyhow::{Result, anyhow};
+use k8s_openapi::api::apps::v1::Deployment;
+use kube::api::{DeleteParams, Patch, PatchParams};
+use kube::{Api, Client, Resource, ResourceExt};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::{debug, info, instrument, warn};
+
+/// Label on canary pods (and the canary's selector) keeping them apart from the stable pods
+pub const CANARY_LABEL: &str = "headwind.sh/canary";
+
+/// Label on canary Deployments naming the Deployment they are a canary of
+pub const CANARY_OF_LABEL: &str = "headwind.sh/canary-of";
+
+/// How often the canary's health is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Configuration for canary rollouts
+#[derive(Debug, Clone)]
+pub struct CanaryConfig {
+    /// Roll approved updates out through a canary first
+    pub enabled: bool,
+    /// Share of the Deployment's replicas run by the canary (percent)
+    pub percent: u32,
+    /// How long the canary has to stay healthy before it is promoted (seconds)
+    pub timeout: u64,
+    /// Number of consecutive failed health checks before the canary is abandoned
+    pub retries: u32,
+}
+
+impl Default for CanaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            percent: 10,
+            timeout: 300, // 5 minutes
+            retries: 3,
+        }
+    }
+}
+
+impl CanaryConfig {
+    /// Parse config from deployment annotations
+    pub fn from_annotations(annotations: &BTreeMap<String, String>) -> Self {
+        let defaults = Self::default();
+
+        let enabled = annotations
+            .get(annotations::CANARY)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.enabled);
+
+        let percent = annotations
+            .get(annotations::CANARY_PERCENT)
+            .and_then(|v| v.trim().trim_end_matches('%').parse().ok())
+            .filter(|percent| (1..=100).contains(percent))
+            .unwrap_or(defaults.percent);
+
+        let timeout = annotations
+            .get(annotations::CANARY_TIMEOUT)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.timeout);
+
+        let retries = annotations
+            .get(annotations::HEALTH_CHECK_RETRIES)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.retries);
+
+        Self {
+            enabled,
+            percent,
+            timeout,
+            retries,
+        }
+    }
+}
+
+/// Name of the canary Deployment of a Deployment
+pub fn canary_name(deployment_name: &str) -> String {
+    format!("{}-canary", deployment_name)
+}
+
+/// Number of canary replicas for a Deployment: the configured share, rounded up, and at least one
+pub fn canary_replicas(replicas: i32, percent: u32) -> i32 {
+    let replicas = replicas.max(0) as u64;
+    let canary = (replicas * u64::from(percent)).div_ceil(100);
+    canary.clamp(1, replicas.max(1)) as i32
+}
+
+/// Build the canary Deployment running the new images of `images` (container name, image)
+pub fn build_canary(
+    deployment: &Deployment,
+    images: &[(String, String)],
+    percent: u32,
+) -> Result<Deployment> {
+    let name = deployment.name_any();
+    let mut spec = deployment
+        .spec
+        .clone()
+        .ok_or_else(|| anyhow!("Deployment {} has no spec", name))?;
+
+    spec.replicas = Some(canary_replicas(spec.replicas.unwrap_or(1), percent));
+    spec.paused = None;
+    spec.selector
+        .match_labels
+        .get_or_insert_with(BTreeMap::new)
+        .insert(CANARY_LABEL.to_string(), "true".to_string());
+    spec.template
+        .metadata
+        .get_or_insert_with(Default::default)
+        .labels
+        .get_or_insert_with(BTreeMap::new)
+        .insert(CANARY_LABEL.to_string(), "true".to_string());
+
+    for (container_name, new_image) in images {
+        let container = spec
+            .template
+            .spec
+            .as_mut()
+            .and_then(|pod_spec| {
+                pod_spec
+                    .containers
+                    .iter_mut()
+                    .find(|c| c.name == *container_name)
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "Container '{}' not found in deployment {}",
+                    container_name,
+                    name
+                )
+            })?;
+        container.image = Some(new_image.clone());
+    }
+
+    let mut canary = Deployment {
+        spec: Some(spec),
+        ..Default::default()
+    };
+    canary.metadata.name = Some(canary_name(&name));
+    canary.metadata.namespace = deployment.namespace();
+    canary.metadata.labels = Some(BTreeMap::from([(
+        CANARY_OF_LABEL.to_string(),
+        name.clone(),
+    )]));
+    // Deleting the Deployment also deletes its canary
+    canary.metadata.owner_references = deployment.owner_ref(&()).map(|owner| vec![owner]);
+    Ok(canary)
+}
+
+/// Tracks consecutive health check results of a canary
+#[derive(Debug, Default)]
+struct Observation {
+    healthy: bool,
+    consecutive_failures: u32,
+}
+
+impl Observation {
+    /// Record a health check, returning the final verdict once the canary has failed
+    fn record(&mut self, status: HealthStatus, retries: u32) -> Option<HealthStatus> {
+        match status {
+            HealthStatus::Healthy => {
+                self.healthy = true;
+                self.consecutive_failures = 0;
+            },
+            HealthStatus::Failed(reason) => {
+                self.healthy = false;
+                self.consecutive_failures += 1;
+                if self.consecutive_failures >= retries {
+                    return Some(HealthStatus::Failed(reason));
+                }
+            },
+            HealthStatus::Progressing | HealthStatus::Timeout => {
+                self.healthy = false;
+                self.consecutive_failures = 0;
+            },
+        }
+        None
+    }
+
+    /// Verdict at the end of the observation window
+    fn verdict(&self) -> HealthStatus {
+        if self.healthy {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Timeout
+        }
+    }
+}
+
+/// A running canary Deployment
+pub struct CanaryRollout {
+    client: Client,
+    namespace: String,
+    deployment_name: String,
+    config: CanaryConfig,
+    /// Number of replicas of the canary
+    pub replicas: i32,
+    /// Number of replicas of the Deployment
+    pub total_replicas: i32,
+}
+
+impl CanaryRollout {
+    /// Create (or replace) the canary Deployment for an update
+    pub async fn start(
+        client: Client,
+        deployment: &Deployment,
+        images: &[(String, String)],
+        config: CanaryConfig,
+    ) -> Result<Self> {
+        let namespace = deployment.namespace().unwrap_or_default();
+        let deployment_name = deployment.name_any();
+        let canary = build_canary(deployment, images, config.percent)?;
+        let replicas = canary.spec.as_ref().and_then(|s| s.replicas).unwrap_or(1);
+
+        let deployments: Api<Deployment> = Api::namespaced(client.clone(), &namespace);
+        deployments
+            .patch(
+                &canary_name(&deployment_name),
+                &PatchParams::apply("headwind").force(),
+                &Patch::Apply(&canary),
+            )
+            .await?;
+
+        info!(
+            "Started canary for {}/{}: {} replica(s) running {}",
+            namespace,
+            deployment_name,
+            replicas,
+            images
+                .iter()
+                .map(|(_, image)| image.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        Ok(Self {
+            client,
+            namespace,
+            deployment_name,
+            config,
+            replicas,
+            total_replicas: deployment
+                .spec
+                .as_ref()
+                .and_then(|s| s.replicas)
+                .unwrap_or(1),
+        })
+    }
+
+    /// Watch the canary until it fails or the observation window ends. Healthy means the canary
+    /// was healthy at the end of the window without failing in between.
+    #[instrument(name = "canary_monitoring", skip(self), fields(namespace = %self.namespace, deployment = %self.deployment_name))]
+    pub async fn observe(&self) -> HealthStatus {
+        let health_checker = HealthChecker::new(self.client.clone());
+        let canary = canary_name(&self.deployment_name);
+        let deadline = std::time::Instant::now() + Duration::from_secs(self.config.timeout);
+        let mut observation = Observation::default();
+
+        info!(
+            "Observing canary {}/{} for {}s (retries: {})",
+            self.namespace, canary, self.config.timeout, self.config.retries
+        );
+
+        loop {
+            match health_checker
+                .check_deployment_health(&canary, &self.namespace)
+                .await
+            {
+                Ok(status) => {
+                    debug!("Canary {}/{}: {:?}", self.namespace, canary, status);
+                    if let Some(verdict) = observation.record(status, self.config.retries) {
+                        return verdict;
+                    }
+                },
+                // Don't count API errors as health failures
+                Err(e) => warn!(
+                    "Error checking health of canary {}/{}: {}",
+                    self.namespace, canary, e
+                ),
+            }
+
+            if std::time::Instant::now() >= deadline {
+                return observation.verdict();
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    }
+
+    /// Delete the canary Deployment and its pods
+    pub async fn remove(&self) -> Result<()> {
+        let deployments: Api<Deployment> = Api::namespaced(self.client.clone(), &self.namespace);
+        match deployments
+            .delete(
+                &canary_name(&self.deployment_name),
+                &DeleteParams::foreground(),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(kube::Error::Api(e)) if e.code == 404 => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::apps::v1::DeploymentSpec;
+    use k8s_openapi::api::core::v1::{Container, PodSpec, PodTemplateSpec};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta};
+
+    fn deployment(replicas: i32) -> Deployment {
+        let labels = BTreeMap::from([("app".to_string(), "web".to_string())]);
+        Deployment {
+            metadata: ObjectMeta {
+                name: Some("web".to_string()),
+                namespace: Some("production".to_string()),
+                uid: Some("0b7c6a1e".to_string()),
+                annotations: Some(BTreeMap::from([(
+                    annotations::POLICY.to_string(),
+                    "minor".to_string(),
+                )])),
+                ..Default::default()
+            },
+            spec: Some(DeploymentSpec {
+                replicas: Some(replicas),
+                selector: LabelSelector {
+                    match_labels: Some(labels.clone()),
+                    ..Default::default()
+                },
+                template: PodTemplateSpec {
+                    metadata: Some(ObjectMeta {
+                        labels: Some(labels),
+                        ..Default::default()
+                    }),
+                    spec: Some(PodSpec {
+                        containers: vec![
+                            Container {
+                                name: "app".to_string(),
+                                image: Some("web:1.0.0".to_string()),
+                                ..Default::default()
+                            },
+                            Container {
+                                name: "proxy".to_string(),
+                                image: Some("envoy:1.30.0".to_string()),
+                                ..Default::default()
+                            },
+                        ],
+                        ..Default::default()
+                    }),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_canary_config_from_annotations() {
+        let config = CanaryConfig::from_annotations(&BTreeMap::from([
+            (annotations::CANARY.to_string(), "true".to_string()),
+            (annotations::CANARY_PERCENT.to_string(), "25%".to_string()),
+            (annotations::CANARY_TIMEOUT.to_string(), "600".to_string()),
+            (
+                annotations::HEALTH_CHECK_RETRIES.to_string(),
+                "2".to_string(),
+            ),
+        ]));
+        assert!(config.enabled);
+        assert_eq!(config.percent, 25);
+        assert_eq!(config.timeout, 600);
+        assert_eq!(config.retries, 2);
+
+        let config = CanaryConfig::from_annotations(&BTreeMap::from([(
+            annotations::CANARY_PERCENT.to_string(),
+            "150".to_string(),
+        )]));
+        assert!(!config.enabled);
+        assert_eq!(config.percent, 10);
+        assert_eq!(config.timeout, 300);
+    }
+
+    #[test]
+    fn test_canary_replicas() {
+        assert_eq!(canary_replicas(10, 10), 1);
+        assert_eq!(canary_replicas(10, 25), 3);
+        assert_eq!(canary_replicas(3, 10), 1);
+        assert_eq!(canary_replicas(4, 100), 4);
+        assert_eq!(canary_replicas(0, 50), 1);
+    }
+
+    #[test]
+    fn test_build_canary() {
+        let canary = build_canary(
+            &deployment(10),
+            &[("app".to_string(), "web:1.1.0".to_string())],
+            20,
+        )
+        .unwrap();
+
+        assert_eq!(canary.metadata.name.as_deref(), Some("web-canary"));
+        assert_eq!(canary.metadata.namespace.as_deref(), Some("production"));
+        assert_eq!(canary.labels()[CANARY_OF_LABEL], "web");
+        // Not tracked by Headwind itself
+        assert!(canary.metadata.annotations.is_none());
+        assert_eq!(canary.owner_references()[0].name, "web");
+
+        let spec = canary.spec.unwrap();
+        assert_eq!(spec.replicas, Some(2));
+        let selector = spec.selector.match_labels.unwrap();
+        assert_eq!(selector["app"], "web");
+        assert_eq!(selector[CANARY_LABEL], "true");
+        let pod_labels = spec.template.metadata.unwrap().labels.unwrap();
+        assert_eq!(pod_labels["app"], "web");
+        assert_eq!(pod_labels[CANARY_LABEL], "true");
+
+        let images: Vec<_> = spec
+            .template
+            .spec
+            .unwrap()
+            .containers
+            .into_iter()
+            .map(|c| c.image.unwrap())
+            .collect();
+        assert_eq!(images, vec!["web:1.1.0", "envoy:1.30.0"]);
+    }
+
+    #[test]
+    fn test_build_canary_unknown_container() {
+        assert!(
+            build_canary(
+                &deployment(3),
+                &[("missing".to_string(), "web:1.1.0".to_string())],
+                10
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_observation() {
+        // Healthy at the end of the window
+        let mut observation = Observation::default();
+        assert!(observation.record(HealthStatus::Progressing, 3).is_none());
+        assert!(observation.record(HealthStatus::Healthy, 3).is_none());
+        assert_eq!(observation.verdict(), HealthStatus::Healthy);
+
+        // Never became healthy
+        let mut observation = Observation::default();
+        assert!(observation.record(HealthStatus::Progressing, 3).is_none());
+        assert_eq!(observation.verdict(), HealthStatus::Timeout);
+
+        // Fails after the configured number of consecutive failures
+        let mut observation = Observation::default();
+        let crash = || HealthStatus::Failed("CrashLoopBackOff".to_string());
+        assert!(observation.record(crash(), 2).is_none());
+        assert!(observation.record(HealthStatus::Progressing, 2).is_none());
+        assert!(observation.record(crash(), 2).is_none());
+        assert_eq!(
+            observation.record(crash(), 2),
+            Some(HealthStatus::Failed("CrashLoopBackOff".to_string()))
+        );
+    }
+}
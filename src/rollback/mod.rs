@@ -5,15 +5,22 @@
 // 2. Allowing manual rollback to previous image versions
 // 3. Creating UpdateRequests for rollback operations
 
+pub mod canary;
+
+use crate::config::{HeadwindConfig, ObservabilityConfig};
+use crate::metrics::client::{
+    MetricsBackend, MetricsClient, PrometheusClient, VictoriaMetricsClient,
+};
 use anyhow::{Context, Result, anyhow};
 use chrono::{DateTime, Utc};
 use k8s_openapi::api::apps::v1::Deployment;
 use k8s_openapi::api::core::v1::Pod;
 use kube::{Api, Client};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::Duration;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, instrument, warn};
 
 /// Annotation key for storing update history
 pub const HISTORY_ANNOTATION: &str = "headwind.sh/update-history";
@@ -22,7 +29,7 @@ pub const HISTORY_ANNOTATION: &str = "headwind.sh/update-history";
 pub const MAX_HISTORY_ENTRIES: usize = 10;
 
 /// Represents a single update in the history
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateHistoryEntry {
     /// Container name that was updated
@@ -41,10 +48,14 @@ pub struct UpdateHistoryEntry {
     /// User or system that approved the update
     #[serde(skip_serializing_if = "Option::is_none")]
     pub approved_by: Option<String>,
+
+    /// Whether the image passed health monitoring after the update
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub healthy: bool,
 }
 
 /// Update history for a deployment
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct UpdateHistory {
     /// List of updates, newest first
     entries: Vec<UpdateHistoryEntry>,
@@ -86,14 +97,19 @@ impl UpdateHistory {
         self.trim_history();
     }
 
-    /// Trim history to keep only MAX_HISTORY_ENTRIES per container
+    /// Trim history to keep only MAX_HISTORY_ENTRIES per container. The last known good
+    /// entry of a container is kept beyond the limit.
     fn trim_history(&mut self) {
         let mut container_counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut good_seen: BTreeSet<String> = BTreeSet::new();
 
+        // Entries are newest first, so the first healthy entry of a container is its last
+        // known good
         self.entries.retain(|entry| {
             let count = container_counts.entry(entry.container.clone()).or_insert(0);
             *count += 1;
-            *count <= MAX_HISTORY_ENTRIES
+            let last_good = entry.healthy && good_seen.insert(entry.container.clone());
+            *count <= MAX_HISTORY_ENTRIES || last_good
         });
     }
 
@@ -115,6 +131,25 @@ impl UpdateHistory {
         self.get_container_history(container).get(index).copied()
     }
 
+    /// The newest entry of a container whose image passed health monitoring
+    pub fn last_known_good(&self, container: &str) -> Option<&UpdateHistoryEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.container == container && e.healthy)
+    }
+
+    /// Mark the newest entry of a container as healthy if it is for `image`. Returns
+    /// whether an entry was marked.
+    pub fn mark_healthy(&mut self, container: &str, image: &str) -> bool {
+        match self.entries.iter_mut().find(|e| e.container == container) {
+            Some(entry) if entry.image == image => {
+                entry.healthy = true;
+                true
+            },
+            _ => false,
+        }
+    }
+
     /// Get all entries
     pub fn entries(&self) -> &[UpdateHistoryEntry] {
         &self.entries
@@ -162,6 +197,7 @@ impl RollbackManager {
             timestamp: Utc::now(),
             update_request_name,
             approved_by,
+            healthy: false,
         };
 
         history.add_entry(entry);
@@ -213,6 +249,71 @@ impl RollbackManager {
         UpdateHistory::from_deployment(&deployment).or_else(|_| Ok(UpdateHistory::new()))
     }
 
+    /// Record that the images a deployment was updated to passed health monitoring, making
+    /// them the last known good of their containers
+    pub async fn mark_healthy(
+        &self,
+        deployment_name: &str,
+        namespace: &str,
+        images: &[(String, String)],
+    ) -> Result<()> {
+        let deployments: Api<Deployment> = Api::namespaced(self.client.clone(), namespace);
+        let deployment = deployments
+            .get(deployment_name)
+            .await
+            .context("Failed to get deployment")?;
+
+        let mut history =
+            UpdateHistory::from_deployment(&deployment).unwrap_or_else(|_| UpdateHistory::new());
+        let mut marked = false;
+        for (container, image) in images {
+            marked |= history.mark_healthy(container, image);
+        }
+        if !marked {
+            debug!(
+                "No history entry of {}/{} to mark healthy",
+                namespace, deployment_name
+            );
+            return Ok(());
+        }
+
+        let patch = serde_json::json!({
+            "metadata": {
+                "annotations": {
+                    HISTORY_ANNOTATION: history.to_json()?
+                }
+            }
+        });
+        deployments
+            .patch(
+                deployment_name,
+                &kube::api::PatchParams::default(),
+                &kube::api::Patch::Merge(patch),
+            )
+            .await
+            .context("Failed to update deployment annotations")?;
+
+        info!(
+            deployment = deployment_name,
+            namespace = namespace,
+            "Marked updated images as last known good"
+        );
+
+        Ok(())
+    }
+
+    /// Get the last known good entry for a container in a deployment
+    pub async fn get_last_known_good(
+        &self,
+        deployment_name: &str,
+        namespace: &str,
+        container: &str,
+    ) -> Result<Option<UpdateHistoryEntry>> {
+        let history = self.get_history(deployment_name, namespace).await?;
+
+        Ok(history.last_known_good(container).cloned())
+    }
+
     /// Get the previous image for a container in a deployment
     pub async fn get_previous_image(
         &self,
@@ -256,6 +357,24 @@ pub enum HealthStatus {
     Timeout,
 }
 
+/// A PromQL query whose value must stay at or below a threshold after an update
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricGate {
+    /// What the query measures, for logs and rollback reasons
+    pub name: String,
+    /// PromQL query returning a single value
+    pub query: String,
+    /// Highest acceptable value
+    pub max: f64,
+}
+
+impl MetricGate {
+    /// Reason to roll back when `value` violates the gate
+    pub fn violation(&self, value: f64) -> Option<String> {
+        (value > self.max).then(|| format!("{} is {} (max {})", self.name, value, self.max))
+    }
+}
+
 /// Configuration for automatic rollback
 #[derive(Debug, Clone)]
 pub struct AutoRollbackConfig {
@@ -265,6 +384,8 @@ pub struct AutoRollbackConfig {
     pub timeout: u64,
     /// Number of health check retries before rolling back
     pub retries: u32,
+    /// Metric thresholds checked until the timeout once the deployment is healthy
+    pub metric_gates: Vec<MetricGate>,
 }
 
 impl Default for AutoRollbackConfig {
@@ -273,6 +394,7 @@ impl Default for AutoRollbackConfig {
             enabled: false,
             timeout: 300, // 5 minutes
             retries: 3,
+            metric_gates: Vec::new(),
         }
     }
 }
@@ -297,23 +419,127 @@ impl AutoRollbackConfig {
             .and_then(|v| v.parse().ok())
             .unwrap_or(3);
 
+        let metric_gates = [
+            (
+                "error rate",
+                annotations::ERROR_RATE_QUERY,
+                annotations::MAX_ERROR_RATE,
+            ),
+            (
+                "latency",
+                annotations::LATENCY_QUERY,
+                annotations::MAX_LATENCY,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, query_key, max_key)| {
+            let query = annotations.get(query_key)?.trim();
+            if query.is_empty() {
+                return None;
+            }
+            match annotations.get(max_key).map(|v| v.trim().parse::<f64>()) {
+                Some(Ok(max)) => Some(MetricGate {
+                    name: name.to_string(),
+                    query: query.to_string(),
+                    max,
+                }),
+                _ => {
+                    warn!(
+                        "Ignoring {}: {} must be set to a number",
+                        query_key, max_key
+                    );
+                    None
+                },
+            }
+        })
+        .collect();
+
         Self {
             enabled,
             timeout,
             retries,
+            metric_gates,
         }
     }
 }
 
+/// The configured backend that can answer PromQL queries for metric gates
+pub fn promql_backend(config: &ObservabilityConfig) -> Option<MetricsBackend> {
+    let prometheus = config
+        .prometheus
+        .url
+        .clone()
+        .filter(|_| config.prometheus.enabled)
+        .map(|url| MetricsBackend::Prometheus { url });
+    let victoriametrics = config
+        .victoriametrics
+        .url
+        .clone()
+        .filter(|_| config.victoriametrics.enabled)
+        .map(|url| MetricsBackend::VictoriaMetrics { url });
+
+    match config.metrics_backend.as_str() {
+        "prometheus" => prometheus,
+        "victoriametrics" => victoriametrics,
+        "auto" => prometheus.or(victoriametrics),
+        _ => None,
+    }
+}
+
 /// Health checker for deployments
 pub struct HealthChecker {
     client: Client,
+    metrics: Option<Box<dyn MetricsClient>>,
 }
 
 impl HealthChecker {
     /// Create a new health checker
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            metrics: None,
+        }
+    }
+
+    /// Create a health checker that evaluates metric gates against the configured Prometheus
+    /// or VictoriaMetrics backend
+    pub async fn with_metrics_backend(client: Client) -> Self {
+        let backend = match HeadwindConfig::load(client.clone()).await {
+            Ok(config) => promql_backend(&config.observability),
+            Err(e) => {
+                warn!("Failed to load configuration for metric gates: {}", e);
+                None
+            },
+        };
+        let metrics: Option<Box<dyn MetricsClient>> = match backend {
+            Some(MetricsBackend::Prometheus { url }) => Some(Box::new(PrometheusClient::new(url))),
+            Some(MetricsBackend::VictoriaMetrics { url }) => {
+                Some(Box::new(VictoriaMetricsClient::new(url)))
+            },
+            _ => None,
+        };
+        Self { client, metrics }
+    }
+
+    /// Evaluate metric gates. Queries that fail or return no data are skipped, like API errors
+    /// during pod checks.
+    pub async fn check_metric_gates(&self, gates: &[MetricGate]) -> HealthStatus {
+        let Some(metrics) = &self.metrics else {
+            return HealthStatus::Healthy;
+        };
+
+        for gate in gates {
+            match metrics.query_instant(&gate.query).await {
+                Ok(result) => {
+                    debug!("Metric gate {}: {}", gate.name, result.value);
+                    if let Some(reason) = gate.violation(result.value) {
+                        return HealthStatus::Failed(reason);
+                    }
+                },
+                Err(e) => warn!("Could not evaluate {} query: {}", gate.name, e),
+            }
+        }
+        HealthStatus::Healthy
     }
 
     /// Check the health of a deployment
@@ -458,6 +684,7 @@ impl HealthChecker {
     }
 
     /// Monitor deployment health with timeout and retries
+    #[instrument(name = "health_monitoring", skip(self, config))]
     pub async fn monitor_deployment_health(
         &self,
         deployment_name: &str,
@@ -468,15 +695,39 @@ impl HealthChecker {
         let check_interval = Duration::from_secs(10); // Check every 10 seconds
         let start = std::time::Instant::now();
         let mut consecutive_failures = 0;
+        // With metric gates, a healthy deployment is watched until the timeout
+        let metric_gates = if self.metrics.is_some() {
+            config.metric_gates.as_slice()
+        } else {
+            if !config.metric_gates.is_empty() {
+                warn!(
+                    "Metric gates for {}/{} need a Prometheus or VictoriaMetrics backend, skipping them",
+                    namespace, deployment_name
+                );
+            }
+            &[]
+        };
+        let mut healthy = false;
 
         info!(
-            "Monitoring health of {}/{} (timeout: {}s, retries: {})",
-            namespace, deployment_name, config.timeout, config.retries
+            "Monitoring health of {}/{} (timeout: {}s, retries: {}, metric gates: {})",
+            namespace,
+            deployment_name,
+            config.timeout,
+            config.retries,
+            metric_gates.len()
         );
 
         loop {
             // Check if timeout exceeded
             if start.elapsed() > timeout_duration {
+                if healthy {
+                    info!(
+                        "Deployment {}/{} stayed within its metric gates",
+                        namespace, deployment_name
+                    );
+                    return Ok(HealthStatus::Healthy);
+                }
                 warn!(
                     "Health check timeout for {}/{} after {}s",
                     namespace, deployment_name, config.timeout
@@ -485,10 +736,26 @@ impl HealthChecker {
             }
 
             // Check health
-            match self
+            let status = match self
                 .check_deployment_health(deployment_name, namespace)
                 .await
             {
+                Ok(HealthStatus::Healthy) if !metric_gates.is_empty() => {
+                    Ok(self.check_metric_gates(metric_gates).await)
+                },
+                status => status,
+            };
+            if let Ok(status) = &status {
+                healthy = *status == HealthStatus::Healthy;
+            }
+            match status {
+                Ok(HealthStatus::Healthy) if !metric_gates.is_empty() => {
+                    consecutive_failures = 0;
+                    debug!(
+                        "Deployment {}/{} is healthy and within its metric gates",
+                        namespace, deployment_name
+                    );
+                },
                 Ok(HealthStatus::Healthy) => {
                     info!("Deployment {}/{} is healthy", namespace, deployment_name);
                     return Ok(HealthStatus::Healthy);
@@ -549,6 +816,7 @@ mod tests {
             timestamp: Utc::now(),
             update_request_name: Some("nginx-update".to_string()),
             approved_by: Some("admin".to_string()),
+            healthy: false,
         };
 
         history.add_entry(entry.clone());
@@ -567,6 +835,7 @@ mod tests {
             timestamp: Utc::now(),
             update_request_name: None,
             approved_by: None,
+            healthy: false,
         };
 
         let entry2 = UpdateHistoryEntry {
@@ -575,6 +844,7 @@ mod tests {
             timestamp: Utc::now(),
             update_request_name: None,
             approved_by: None,
+            healthy: false,
         };
 
         history.add_entry(entry1);
@@ -594,6 +864,7 @@ mod tests {
             timestamp: Utc::now(),
             update_request_name: None,
             approved_by: None,
+            healthy: false,
         });
 
         history.add_entry(UpdateHistoryEntry {
@@ -602,6 +873,7 @@ mod tests {
             timestamp: Utc::now(),
             update_request_name: None,
             approved_by: None,
+            healthy: false,
         });
 
         let previous = history.get_previous_image("nginx");
@@ -621,6 +893,7 @@ mod tests {
                 timestamp: Utc::now(),
                 update_request_name: None,
                 approved_by: None,
+                healthy: false,
             });
         }
 
@@ -628,6 +901,55 @@ mod tests {
         assert_eq!(nginx_history.len(), MAX_HISTORY_ENTRIES);
     }
 
+    #[test]
+    fn test_last_known_good() {
+        let mut history = UpdateHistory::new();
+        let entry = |image: &str| UpdateHistoryEntry {
+            container: "nginx".to_string(),
+            image: image.to_string(),
+            timestamp: Utc::now(),
+            update_request_name: None,
+            approved_by: None,
+            healthy: false,
+        };
+
+        history.add_entry(entry("nginx:1.24.0"));
+        assert!(history.mark_healthy("nginx", "nginx:1.24.0"));
+        history.add_entry(entry("nginx:1.25.0"));
+        assert_eq!(
+            history.last_known_good("nginx").unwrap().image,
+            "nginx:1.24.0"
+        );
+
+        // Only the newest entry can be marked
+        assert!(!history.mark_healthy("nginx", "nginx:1.24.0"));
+        assert!(!history.mark_healthy("redis", "redis:7.0"));
+        assert!(history.last_known_good("redis").is_none());
+
+        // The last known good survives trimming
+        for i in 0..15 {
+            history.add_entry(entry(&format!("nginx:2.{}.0", i)));
+        }
+        assert_eq!(
+            history.get_container_history("nginx").len(),
+            MAX_HISTORY_ENTRIES + 1
+        );
+        assert_eq!(
+            history.last_known_good("nginx").unwrap().image,
+            "nginx:1.24.0"
+        );
+
+        // Unset flags are left out of the annotation, and older annotations still parse
+        let json = history.to_json().unwrap();
+        assert_eq!(json.matches("\"healthy\":true").count(), 1);
+        assert!(!json.contains("\"healthy\":false"));
+        let entries: Vec<UpdateHistoryEntry> = serde_json::from_str(
+            r#"[{"container":"nginx","image":"nginx:1.0","timestamp":"2025-01-01T00:00:00Z"}]"#,
+        )
+        .unwrap();
+        assert!(!entries[0].healthy);
+    }
+
     #[test]
     fn test_json_serialization() {
         let mut history = UpdateHistory::new();
@@ -638,10 +960,59 @@ mod tests {
             timestamp: Utc::now(),
             update_request_name: Some("nginx-update".to_string()),
             approved_by: Some("admin".to_string()),
+            healthy: false,
         });
 
         let json = history.to_json().unwrap();
         assert!(json.contains("nginx:1.26.0"));
         assert!(json.contains("nginx-update"));
     }
+
+    #[test]
+    fn test_metric_gate_violation() {
+        let gate = MetricGate {
+            name: "error rate".to_string(),
+            query: "sum(rate(http_requests_total{code=~\"5..\"}[2m]))".to_string(),
+            max: 0.05,
+        };
+        assert_eq!(gate.violation(0.01), None);
+        assert_eq!(gate.violation(0.05), None);
+        assert_eq!(
+            gate.violation(0.2).as_deref(),
+            Some("error rate is 0.2 (max 0.05)")
+        );
+    }
+
+    #[test]
+    fn test_promql_backend() {
+        let mut config = HeadwindConfig::default().observability;
+        config.prometheus.enabled = true;
+        config.prometheus.url = Some("http://prometheus:9090".to_string());
+        config.victoriametrics.enabled = true;
+        config.victoriametrics.url = Some("http://victoria-metrics:8428".to_string());
+
+        config.metrics_backend = "auto".to_string();
+        assert_eq!(
+            promql_backend(&config),
+            Some(MetricsBackend::Prometheus {
+                url: "http://prometheus:9090".to_string()
+            })
+        );
+
+        config.metrics_backend = "victoriametrics".to_string();
+        assert_eq!(
+            promql_backend(&config),
+            Some(MetricsBackend::VictoriaMetrics {
+                url: "http://victoria-metrics:8428".to_string()
+            })
+        );
+
+        // Disabled backends and backends without PromQL can't evaluate gates
+        config.metrics_backend = "auto".to_string();
+        config.prometheus.enabled = false;
+        config.victoriametrics.enabled = false;
+        assert_eq!(promql_backend(&config), None);
+        config.metrics_backend = "influxdb".to_string();
+        assert_eq!(promql_backend(&config), None);
+    }
 }
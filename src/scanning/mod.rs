@@ -0,0 +1,173 @@
+//! Vulnerability scans of proposed images, gating their approval.
+//!
+//! With HEADWIND_VULNERABILITY_SCAN enabled, the UpdateRequest controller scans the new image of
+//! every image UpdateRequest that waits for approval, running the trivy CLI in a Job in the
+//! request's namespace (as a client of a Trivy server when HEADWIND_TRIVY_SERVER is set). The
+//! counts are recorded in `status.vulnerabilityScan`. Approval waits for the scan, and a request
+//! whose image has more critical vulnerabilities than its target's `headwind.sh/max-critical-cves`
+//! allows is rejected.
+
+mod trivy;
+
+use crate::models::crd::{UpdatePhase, UpdateRequest, UpdateType, VulnerabilityScan};
+use crate::models::policy::annotations;
+use std::collections::BTreeMap;
+
+pub use trivy::{ScanProgress, scan};
+
+/// Who rejects UpdateRequests whose image has too many critical vulnerabilities
+pub const REJECTED_BY: &str = "headwind-vulnerability-scan";
+
+/// Whether proposed images are scanned (HEADWIND_VULNERABILITY_SCAN, default: false)
+pub fn enabled() -> bool {
+    std::env::var("HEADWIND_VULNERABILITY_SCAN")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Whether an UpdateRequest's image should be scanned: an image update that waits for
+/// approval and has not been scanned yet
+pub fn needs_scan(update_request: &UpdateRequest) -> bool {
+    let status = update_request.status.as_ref();
+    update_request.spec.update_type == UpdateType::Image
+        && update_request.spec.require_approval
+        && status.map(|s| &s.phase).unwrap_or(&UpdatePhase::Pending) == &UpdatePhase::Pending
+        && status.and_then(|s| s.vulnerability_scan.as_ref()).is_none()
+}
+
+/// Check the scan gate before approving an UpdateRequest. Returns why approval isn't
+/// possible yet.
+pub fn check_gate(update_request: &UpdateRequest) -> Result<(), String> {
+    if enabled() && needs_scan(update_request) {
+        return Err(format!(
+            "Vulnerability scan of {} has not finished yet",
+            update_request.spec.new_image
+        ));
+    }
+    Ok(())
+}
+
+/// Most critical vulnerabilities a target accepts (`headwind.sh/max-critical-cves`)
+pub fn max_critical(target_annotations: &BTreeMap<String, String>) -> Option<u32> {
+    target_annotations
+        .get(annotations::MAX_CRITICAL_CVES)
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Why a scan blocks the update, when it found more critical vulnerabilities than allowed
+pub fn violation(scan: &VulnerabilityScan, max_critical: Option<u32>) -> Option<String> {
+    let max = max_critical?;
+    (scan.error.is_none() && scan.critical > max).then(|| {
+        format!(
+            "{} has {} critical vulnerabilities (max {})",
+            scan.image, scan.critical, max
+        )
+    })
+}
+
+/// Short description of a scan's result, e.g. `2 critical, 5 high, 10 medium, 3 low`
+pub fn summary(scan: &VulnerabilityScan) -> String {
+    match &scan.error {
+        Some(error) => format!("scan failed: {}", error),
+        None => format!(
+            "{} critical, {} high, {} medium, {} low",
+            scan.critical, scan.high, scan.medium, scan.low
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{TargetRef, UpdatePolicyType, UpdateRequestSpec, UpdateRequestStatus};
+    use chrono::Utc;
+
+    fn update_request() -> UpdateRequest {
+        UpdateRequest::new(
+            "web-1-5-0",
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "web".to_string(),
+                    namespace: "production".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: Some("web".to_string()),
+                image_parameter: None,
+                current_image: "web:1.4.0".to_string(),
+                new_image: "web:1.5.0".to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at: None,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        )
+    }
+
+    fn scan(critical: u32) -> VulnerabilityScan {
+        VulnerabilityScan {
+            scanner: "trivy".to_string(),
+            image: "web:1.5.0".to_string(),
+            scanned_at: Utc::now(),
+            critical,
+            high: 5,
+            medium: 0,
+            low: 1,
+            unknown: 0,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_needs_scan() {
+        let mut request = update_request();
+        assert!(needs_scan(&request));
+
+        request.status = Some(UpdateRequestStatus {
+            vulnerability_scan: Some(scan(0)),
+            ..Default::default()
+        });
+        assert!(!needs_scan(&request));
+
+        let mut chart = update_request();
+        chart.spec.update_type = UpdateType::HelmChart;
+        assert!(!needs_scan(&chart));
+
+        let mut rejected = update_request();
+        rejected.status = Some(UpdateRequestStatus {
+            phase: UpdatePhase::Rejected,
+            ..Default::default()
+        });
+        assert!(!needs_scan(&rejected));
+    }
+
+    #[test]
+    fn test_violation() {
+        let annotations =
+            BTreeMap::from([(annotations::MAX_CRITICAL_CVES.to_string(), "0".to_string())]);
+        let max = max_critical(&annotations);
+        assert_eq!(max, Some(0));
+
+        assert_eq!(
+            violation(&scan(2), max).as_deref(),
+            Some("web:1.5.0 has 2 critical vulnerabilities (max 0)")
+        );
+        assert_eq!(violation(&scan(0), max), None);
+        assert_eq!(violation(&scan(2), None), None);
+
+        let failed = VulnerabilityScan {
+            error: Some("image not found".to_string()),
+            ..scan(0)
+        };
+        assert_eq!(violation(&failed, max), None);
+        assert_eq!(summary(&failed), "scan failed: image not found");
+        assert_eq!(summary(&scan(2)), "2 critical, 5 high, 0 medium, 1 low");
+    }
+}
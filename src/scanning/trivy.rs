@@ -0,0 +1,355 @@
+use crate::models::crd::{UpdateRequest, VulnerabilityScan};
+use anyhow::{Context, Result, anyhow};
+use chrono::Utc;
+use k8s_openapi::api::batch::v1::{Job, JobSpec};
+use k8s_openapi::api::core::v1::{
+    Container, EnvVar, EnvVarSource, Pod, PodSpec, PodTemplateSpec, SecretKeySelector,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::api::{Api, DeleteParams, ListParams, LogParams, PostParams, PropagationPolicy};
+use kube::{Client, ResourceExt};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::{debug, info};
+
+/// Image scan Jobs run when HEADWIND_TRIVY_IMAGE is unset
+pub const DEFAULT_IMAGE: &str = "aquasec/trivy:0.57.1";
+
+/// Longest a scan Job may run
+const JOB_DEADLINE_SECONDS: i64 = 600;
+
+/// Where the scan of an UpdateRequest's image stands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanProgress {
+    /// The scan Job was started or is still running
+    Running,
+    /// The scan finished (or failed, see `VulnerabilityScan::error`)
+    Finished(VulnerabilityScan),
+}
+
+/// Advance the scan of an UpdateRequest's new image: start its Job, or collect the result of
+/// a finished Job and delete it
+pub async fn scan(client: &Client, update_request: &UpdateRequest) -> Result<ScanProgress> {
+    let namespace = update_request.namespace().unwrap_or_default();
+    let image = &update_request.spec.new_image;
+    let jobs: Api<Job> = Api::namespaced(client.clone(), &namespace);
+    let name = job_name(&update_request.name_any());
+
+    let Some(job) = jobs.get_opt(&name).await? else {
+        match jobs
+            .create(&PostParams::default(), &scan_job(update_request))
+            .await
+        {
+            Ok(_) => info!("Started Job {}/{} scanning {}", namespace, name, image),
+            Err(kube::Error::Api(err)) if err.code == 409 => {},
+            Err(e) => return Err(e.into()),
+        }
+        return Ok(ScanProgress::Running);
+    };
+
+    let status = job.status.unwrap_or_default();
+    let succeeded = status.succeeded.unwrap_or(0) > 0;
+    if !succeeded && status.failed.unwrap_or(0) == 0 {
+        debug!("Scan Job {}/{} is still running", namespace, name);
+        return Ok(ScanProgress::Running);
+    }
+
+    let logs = job_logs(client, &namespace, &name).await;
+    let scan = match (succeeded, logs) {
+        (true, Ok(logs)) => parse_report(&logs, image)?,
+        (true, Err(e)) => return Err(e),
+        (false, logs) => {
+            let reason = logs
+                .ok()
+                .and_then(|logs| logs.lines().last().map(|line| line.trim().to_string()))
+                .filter(|line| !line.is_empty())
+                .unwrap_or_else(|| "the Trivy Job failed".to_string());
+            VulnerabilityScan {
+                error: Some(reason),
+                ..empty_scan(image)
+            }
+        },
+    };
+
+    let delete = DeleteParams {
+        propagation_policy: Some(PropagationPolicy::Background),
+        ..Default::default()
+    };
+    if let Err(e) = jobs.delete(&name, &delete).await {
+        debug!("Failed to delete scan Job {}/{}: {}", namespace, name, e);
+    }
+    Ok(ScanProgress::Finished(scan))
+}
+
+/// Output of the trivy container of a scan Job
+async fn job_logs(client: &Client, namespace: &str, job: &str) -> Result<String> {
+    let pods: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pod = pods
+        .list(&ListParams::default().labels(&format!("job-name={}", job)))
+        .await?
+        .items
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Scan Job {}/{} has no pod", namespace, job))?;
+    Ok(pods
+        .logs(
+            &pod.name_any(),
+            &LogParams {
+                container: Some("trivy".to_string()),
+                ..Default::default()
+            },
+        )
+        .await?)
+}
+
+/// Image scan Jobs run (HEADWIND_TRIVY_IMAGE)
+fn trivy_image() -> String {
+    std::env::var("HEADWIND_TRIVY_IMAGE")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_IMAGE.to_string())
+}
+
+/// Deterministic Job name, so an image is only scanned once per UpdateRequest
+fn job_name(update_request: &str) -> String {
+    let name: String = format!("trivy-{}", update_request)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let name = &name[..name.len().min(63)];
+    name.trim_end_matches('-').to_string()
+}
+
+/// Arguments of `trivy image`, as a client of `server` when set
+fn scan_args(image: &str, server: Option<&str>) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "image",
+        "--quiet",
+        "--format",
+        "json",
+        "--scanners",
+        "vuln",
+        "--timeout",
+        "9m",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    if let Some(server) = server {
+        args.extend(["--server".to_string(), server.to_string()]);
+    }
+    args.push(image.to_string());
+    args
+}
+
+/// Job running `trivy image` for an UpdateRequest's new image, in the request's namespace.
+/// Registry credentials come from the Secret named by HEADWIND_TRIVY_CREDENTIALS_SECRET
+/// (`username` and `password` keys) in that namespace.
+fn scan_job(update_request: &UpdateRequest) -> Job {
+    let server = std::env::var("HEADWIND_TRIVY_SERVER")
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    let env = std::env::var("HEADWIND_TRIVY_CREDENTIALS_SECRET")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(|secret| {
+            ["username", "password"]
+                .into_iter()
+                .map(|key| EnvVar {
+                    name: format!("TRIVY_{}", key.to_uppercase()),
+                    value_from: Some(EnvVarSource {
+                        secret_key_ref: Some(SecretKeySelector {
+                            name: secret.clone(),
+                            key: key.to_string(),
+                            optional: Some(true),
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+                .collect()
+        });
+
+    let labels = BTreeMap::from([(
+        "app.kubernetes.io/managed-by".to_string(),
+        "headwind".to_string(),
+    )]);
+
+    Job {
+        metadata: ObjectMeta {
+            name: Some(job_name(&update_request.name_any())),
+            namespace: update_request.namespace(),
+            labels: Some(labels.clone()),
+            ..Default::default()
+        },
+        spec: Some(JobSpec {
+            backoff_limit: Some(0),
+            active_deadline_seconds: Some(JOB_DEADLINE_SECONDS),
+            ttl_seconds_after_finished: Some(60 * 60),
+            template: PodTemplateSpec {
+                metadata: Some(ObjectMeta {
+                    labels: Some(labels),
+                    ..Default::default()
+                }),
+                spec: Some(PodSpec {
+                    restart_policy: Some("Never".to_string()),
+                    automount_service_account_token: Some(false),
+                    containers: vec![Container {
+                        name: "trivy".to_string(),
+                        image: Some(trivy_image()),
+                        args: Some(scan_args(&update_request.spec.new_image, server.as_deref())),
+                        env,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+            },
+            ..Default::default()
+        }),
+        status: None,
+    }
+}
+
+fn empty_scan(image: &str) -> VulnerabilityScan {
+    VulnerabilityScan {
+        scanner: "trivy".to_string(),
+        image: image.to_string(),
+        scanned_at: Utc::now(),
+        critical: 0,
+        high: 0,
+        medium: 0,
+        low: 0,
+        unknown: 0,
+        error: None,
+    }
+}
+
+/// Vulnerabilities by severity in a `trivy image --format json` report
+fn parse_report(output: &str, image: &str) -> Result<VulnerabilityScan> {
+    // Anything trivy logs before the report is skipped
+    let start = output
+        .find('{')
+        .ok_or_else(|| anyhow!("Trivy printed no report"))?;
+    let report: Value =
+        serde_json::from_str(output[start..].trim()).context("Invalid Trivy report")?;
+
+    let mut scan = empty_scan(image);
+    let vulnerabilities = report["Results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|result| result["Vulnerabilities"].as_array())
+        .flatten();
+    for vulnerability in vulnerabilities {
+        let count = match vulnerability["Severity"].as_str().unwrap_or_default() {
+            "CRITICAL" => &mut scan.critical,
+            "HIGH" => &mut scan.high,
+            "MEDIUM" => &mut scan.medium,
+            "LOW" => &mut scan.low,
+            _ => &mut scan.unknown,
+        };
+        *count += 1;
+    }
+    Ok(scan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{TargetRef, UpdatePolicyType, UpdateRequestSpec, UpdateType};
+
+    #[test]
+    fn test_parse_report() {
+        let output = r#"{
+            "SchemaVersion": 2,
+            "ArtifactName": "web:1.5.0",
+            "Results": [
+                {
+                    "Target": "web:1.5.0 (debian 12.5)",
+                    "Vulnerabilities": [
+                        { "VulnerabilityID": "CVE-2024-0001", "Severity": "CRITICAL" },
+                        { "VulnerabilityID": "CVE-2024-0002", "Severity": "HIGH" },
+                        { "VulnerabilityID": "CVE-2024-0003", "Severity": "HIGH" }
+                    ]
+                },
+                { "Target": "app/go.mod" },
+                {
+                    "Target": "app/package-lock.json",
+                    "Vulnerabilities": [
+                        { "VulnerabilityID": "CVE-2024-0004", "Severity": "LOW" },
+                        { "VulnerabilityID": "CVE-2024-0005", "Severity": "UNKNOWN" }
+                    ]
+                }
+            ]
+        }"#;
+
+        let scan = parse_report(output, "web:1.5.0").unwrap();
+        assert_eq!(scan.scanner, "trivy");
+        assert_eq!(
+            (
+                scan.critical,
+                scan.high,
+                scan.medium,
+                scan.low,
+                scan.unknown
+            ),
+            (1, 2, 0, 1, 1)
+        );
+        assert_eq!(scan.error, None);
+
+        let clean = parse_report(
+            "2024-01-01T00:00:00Z INFO Vulnerability scanning is enabled\n{\"SchemaVersion\": 2}",
+            "web:1.5.0",
+        )
+        .unwrap();
+        assert_eq!(clean.critical + clean.high + clean.unknown, 0);
+
+        assert!(parse_report("FATAL image not found", "web:1.5.0").is_err());
+    }
+
+    #[test]
+    fn test_scan_job() {
+        let mut request = UpdateRequest::new(
+            "web-1-5-0",
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "web".to_string(),
+                    namespace: "production".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: Some("web".to_string()),
+                image_parameter: None,
+                current_image: "web:1.4.0".to_string(),
+                new_image: "registry.example.com/web:1.5.0".to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at: None,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        );
+        request.metadata.namespace = Some("production".to_string());
+
+        let job = scan_job(&request);
+        assert_eq!(job.metadata.name.as_deref(), Some("trivy-web-1-5-0"));
+        assert_eq!(job.metadata.namespace.as_deref(), Some("production"));
+        let container = &job.spec.unwrap().template.spec.unwrap().containers[0];
+        assert_eq!(
+            container.args.as_ref().unwrap().last().map(String::as_str),
+            Some("registry.example.com/web:1.5.0")
+        );
+
+        assert_eq!(
+            scan_args("web:1.5.0", Some("http://trivy.trivy:4954"))[8..],
+            ["--server", "http://trivy.trivy:4954", "web:1.5.0"]
+        );
+        assert_eq!(job_name(&"x".repeat(80)).len(), 63);
+    }
+}
@@ -0,0 +1,568 @@
+//! Maintenance windows for automatic updates.
+//!
+//! `headwind.sh/schedule` restricts when updates that don't need approval are applied. It is
+//! either a time window (`Mon-Fri 02:00-05:00 Europe/Berlin`) or a five-field cron expression
+//! matching the minutes in which updates may be applied (`* 2-4 * * 1-5 Europe/Berlin`).
+//! Updates detected outside the window are queued and applied by the scheduler once the
+//! window opens. The queue is kept in memory; after a restart, updates are queued again
+//! when they are next detected.
+
+use crate::audit;
+use crate::controller::workload::{WorkloadImageUpdate, map_policy_to_crd};
+use crate::metrics::{SCHEDULED_UPDATES_PENDING, UPDATES_DEFERRED};
+use crate::models::audit::{AuditAction, UpdateAuditSpec};
+use crate::models::crd::{TargetRef, UpdateRequestSpec, UpdateType};
+use crate::models::policy::annotations;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use jiff::tz::TimeZone;
+use kube::Client;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// How often queued updates are checked against their windows
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// When automatic updates may be applied
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    Window(TimeWindow),
+    Cron(CronSchedule),
+}
+
+/// Daily window on selected weekdays. A window ending before it starts crosses midnight and
+/// belongs to the day it starts on.
+#[derive(Debug, Clone)]
+pub struct TimeWindow {
+    /// Indexed from Monday
+    days: [bool; 7],
+    /// Minutes after midnight
+    start: u32,
+    end: u32,
+    tz: TimeZone,
+}
+
+/// Cron expression matching the minutes in which updates may be applied
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    minutes: CronField,
+    hours: CronField,
+    days_of_month: CronField,
+    months: CronField,
+    /// Indexed from Sunday
+    days_of_week: CronField,
+    tz: TimeZone,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CronField {
+    values: u64,
+    /// Whether the field was anything other than `*`
+    restricted: bool,
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        match fields.len() {
+            2 | 3 => Ok(Schedule::Window(TimeWindow::parse(&fields)?)),
+            5 | 6 => Ok(Schedule::Cron(CronSchedule::parse(&fields)?)),
+            _ => bail!(
+                "Invalid schedule '{}': expected a time window (Mon-Fri 02:00-05:00 [timezone]) or a cron expression",
+                s
+            ),
+        }
+    }
+}
+
+impl Schedule {
+    /// Whether updates may be applied at `now`
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        match self {
+            Schedule::Window(window) => window.is_open(now),
+            Schedule::Cron(cron) => cron.is_open(now),
+        }
+    }
+}
+
+impl TimeWindow {
+    fn parse(fields: &[&str]) -> Result<Self> {
+        let days = parse_days(fields[0])?;
+        let (start, end) = fields[1]
+            .split_once('-')
+            .ok_or_else(|| anyhow!("Invalid time range '{}', expected HH:MM-HH:MM", fields[1]))?;
+        let start = parse_time(start)?;
+        let end = parse_time(end)?;
+        if start == end {
+            bail!("Time range '{}' is empty", fields[1]);
+        }
+
+        Ok(Self {
+            days,
+            start,
+            end,
+            tz: parse_tz(fields.get(2).copied())?,
+        })
+    }
+
+    fn is_open(&self, now: DateTime<Utc>) -> bool {
+        let local = local_time(&self.tz, now);
+        let day = local.weekday().to_monday_zero_offset() as usize;
+        let minute = local.hour() as u32 * 60 + local.minute() as u32;
+
+        if self.start < self.end {
+            self.days[day] && (self.start..self.end).contains(&minute)
+        } else {
+            // Crosses midnight: the late part belongs to today, the early part to yesterday
+            (self.days[day] && minute >= self.start)
+                || (self.days[(day + 6) % 7] && minute < self.end)
+        }
+    }
+}
+
+impl CronSchedule {
+    fn parse(fields: &[&str]) -> Result<Self> {
+        Ok(Self {
+            minutes: CronField::parse(fields[0], 0, 59)?,
+            hours: CronField::parse(fields[1], 0, 23)?,
+            days_of_month: CronField::parse(fields[2], 1, 31)?,
+            months: CronField::parse(fields[3], 1, 12)?,
+            days_of_week: CronField::parse_days_of_week(fields[4])?,
+            tz: parse_tz(fields.get(5).copied())?,
+        })
+    }
+
+    fn is_open(&self, now: DateTime<Utc>) -> bool {
+        let local = local_time(&self.tz, now);
+        let day_of_month = self.days_of_month.contains(local.day() as u32);
+        let day_of_week = self
+            .days_of_week
+            .contains(local.weekday().to_sunday_zero_offset() as u32);
+
+        // As in cron, a day matches either field when both are restricted
+        let day = match (self.days_of_month.restricted, self.days_of_week.restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+
+        day && self.minutes.contains(local.minute() as u32)
+            && self.hours.contains(local.hour() as u32)
+            && self.months.contains(local.month() as u32)
+    }
+}
+
+impl CronField {
+    /// Parse `*`, `n`, `a-b`, `*/s`, `a-b/s` and comma-separated lists of them
+    fn parse(field: &str, min: u32, max: u32) -> Result<Self> {
+        Self::parse_with(field, min, max, |v| {
+            v.parse().map_err(|_| anyhow!("Invalid cron value '{}'", v))
+        })
+    }
+
+    /// Days of the week accept names (`mon`) and both 0 and 7 for Sunday
+    fn parse_days_of_week(field: &str) -> Result<Self> {
+        let mut parsed = Self::parse_with(field, 0, 7, |v| {
+            match DAY_NAMES.iter().position(|d| v.eq_ignore_ascii_case(d)) {
+                Some(index) => Ok((index as u32 + 1) % 7),
+                None => v
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid day of week '{}'", v)),
+            }
+        })?;
+        if parsed.values & (1 << 7) != 0 {
+            parsed.values |= 1;
+        }
+        Ok(parsed)
+    }
+
+    fn parse_with(
+        field: &str,
+        min: u32,
+        max: u32,
+        value: impl Fn(&str) -> Result<u32>,
+    ) -> Result<Self> {
+        let mut values = 0u64;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (
+                    range,
+                    step.parse::<u32>()
+                        .ok()
+                        .filter(|s| *s > 0)
+                        .ok_or_else(|| anyhow!("Invalid cron step '{}'", step))?,
+                ),
+                None => (part, 1),
+            };
+            let (first, last) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((first, last)) => (value(first)?, value(last)?),
+                    None => {
+                        let single = value(range)?;
+                        (single, if step > 1 { max } else { single })
+                    },
+                },
+            };
+            if first < min || last > max || first > last {
+                bail!("Cron range '{}' is outside {}-{}", part, min, max);
+            }
+            for v in (first..=last).step_by(step as usize) {
+                values |= 1 << v;
+            }
+        }
+
+        Ok(Self {
+            values,
+            restricted: field != "*",
+        })
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.values & (1 << value) != 0
+    }
+}
+
+/// Parse `Mon-Fri`, `Sat,Sun`, `Fri-Mon` (wrapping), `daily` or `*`
+fn parse_days(field: &str) -> Result<[bool; 7]> {
+    if field == "*" || field.eq_ignore_ascii_case("daily") {
+        return Ok([true; 7]);
+    }
+
+    let day = |name: &str| {
+        let prefix = name.get(..3).unwrap_or(name);
+        DAY_NAMES
+            .iter()
+            .position(|d| prefix.eq_ignore_ascii_case(d))
+            .ok_or_else(|| anyhow!("Invalid day '{}'", name))
+    };
+
+    let mut days = [false; 7];
+    for part in field.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                let mut d = first;
+                loop {
+                    days[d] = true;
+                    if d == last {
+                        break;
+                    }
+                    d = (d + 1) % 7;
+                }
+            },
+            None => days[day(part)?] = true,
+        }
+    }
+    Ok(days)
+}
+
+/// Parse `HH:MM` into minutes after midnight. `24:00` ends a window at midnight.
+fn parse_time(s: &str) -> Result<u32> {
+    let (hour, minute) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Invalid time '{}', expected HH:MM", s))?;
+    let hour: u32 = hour
+        .parse()
+        .with_context(|| format!("Invalid hour in '{}'", s))?;
+    let minute: u32 = minute
+        .parse()
+        .with_context(|| format!("Invalid minute in '{}'", s))?;
+    if minute > 59 || hour > 24 || (hour == 24 && minute != 0) {
+        bail!("Invalid time '{}'", s);
+    }
+    Ok(hour * 60 + minute)
+}
+
+fn parse_tz(name: Option<&str>) -> Result<TimeZone> {
+    match name {
+        None => Ok(TimeZone::UTC),
+        Some(name) => TimeZone::get(name).with_context(|| format!("Unknown time zone '{}'", name)),
+    }
+}
+
+fn local_time(tz: &TimeZone, now: DateTime<Utc>) -> jiff::civil::DateTime {
+    let timestamp =
+        jiff::Timestamp::from_second(now.timestamp()).unwrap_or(jiff::Timestamp::UNIX_EPOCH);
+    tz.to_datetime(timestamp)
+}
+
+/// An automatic update waiting for its maintenance window
+#[derive(Debug, Clone)]
+pub struct ScheduledUpdate {
+    pub spec: UpdateRequestSpec,
+    pub schedule: String,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// Queued updates keyed by target and container, so a newer detection replaces an older one
+static PENDING: once_cell::sync::Lazy<Mutex<BTreeMap<String, ScheduledUpdate>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Check a workload's maintenance window before applying an automatic update. Returns true
+/// when the update must not be applied now: it was queued for the window, or the schedule is
+/// invalid.
+pub fn defer_outside_window(
+    annotations: &BTreeMap<String, String>,
+    update: &WorkloadImageUpdate<'_>,
+) -> bool {
+    let Some(raw) = annotations.get(annotations::SCHEDULE) else {
+        return false;
+    };
+
+    let schedule = match raw.parse::<Schedule>() {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            error!(
+                "Not updating {} {}/{}: invalid {} annotation: {}",
+                update.kind,
+                update.namespace,
+                update.name,
+                annotations::SCHEDULE,
+                e
+            );
+            return true;
+        },
+    };
+
+    if schedule.is_open(Utc::now()) {
+        return false;
+    }
+
+    info!(
+        "Outside the maintenance window '{}' of {} {}/{}, queueing update to {}",
+        raw, update.kind, update.namespace, update.name, update.new_image
+    );
+    defer(ScheduledUpdate {
+        spec: scheduled_spec(update),
+        schedule: raw.clone(),
+        queued_at: Utc::now(),
+    });
+    true
+}
+
+pub(crate) fn scheduled_spec(update: &WorkloadImageUpdate<'_>) -> UpdateRequestSpec {
+    let (api_version, update_type) = match update.kind {
+        "HelmRelease" if update.values_path.is_some() => {
+            ("helm.toolkit.fluxcd.io/v2", UpdateType::Image)
+        },
+        "HelmRelease" => ("helm.toolkit.fluxcd.io/v2", UpdateType::HelmChart),
+        "Release" => ("helm.sh/v3", UpdateType::HelmChart),
+        "CronJob" => ("batch/v1", UpdateType::Image),
+        _ => ("apps/v1", UpdateType::Image),
+    };
+
+    UpdateRequestSpec {
+        target_ref: TargetRef {
+            api_version: api_version.to_string(),
+            kind: update.kind.to_string(),
+            name: update.name.to_string(),
+            namespace: update.namespace.to_string(),
+        },
+        update_type,
+        container_name: update.container_name.map(str::to_string),
+        image_parameter: update.values_path.map(str::to_string),
+        current_image: update.current_image.to_string(),
+        new_image: update.new_image.to_string(),
+        policy: map_policy_to_crd(&update.policy.policy),
+        reason: Some("Released in maintenance window".to_string()),
+        require_approval: false,
+        expires_at: None,
+        required_approvals: None,
+        git_write_back: None,
+        grouped_containers: Vec::new(),
+        source: None,
+        resolved_version: None,
+    }
+}
+
+/// Queue an update for its window
+pub fn defer(update: ScheduledUpdate) {
+    let target = &update.spec.target_ref;
+    let key = format!(
+        "{}/{}/{}/{}",
+        target.kind,
+        target.namespace,
+        target.name,
+        update
+            .spec
+            .container_name
+            .as_deref()
+            .or(update.spec.image_parameter.as_deref())
+            .unwrap_or_default()
+    );
+
+    UPDATES_DEFERRED
+        .with_label_values(&[target.namespace.as_str(), target.kind.as_str()])
+        .inc();
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    pending.insert(key, update);
+    SCHEDULED_UPDATES_PENDING.set(pending.len() as i64);
+}
+
+/// Updates currently waiting for their window
+pub fn pending_updates() -> Vec<ScheduledUpdate> {
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// Remove and return the queued updates whose window is open at `now`
+fn take_due(now: DateTime<Utc>) -> Vec<ScheduledUpdate> {
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    let due: Vec<String> = pending
+        .iter()
+        .filter(|(_, update)| {
+            // A schedule that stopped parsing can't open; it stays queued until replaced
+            update
+                .schedule
+                .parse::<Schedule>()
+                .is_ok_and(|schedule| schedule.is_open(now))
+        })
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    let released = due.iter().filter_map(|key| pending.remove(key)).collect();
+    SCHEDULED_UPDATES_PENDING.set(pending.len() as i64);
+    released
+}
+
+/// Start the scheduler that applies queued updates when their window opens
+pub fn start_scheduler(client: Client) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Update scheduler started");
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            for update in take_due(Utc::now()) {
+                let target = &update.spec.target_ref;
+                info!(
+                    "Maintenance window '{}' open, applying {} to {} {}/{}",
+                    update.schedule,
+                    update.spec.new_image,
+                    target.kind,
+                    target.namespace,
+                    target.name
+                );
+                let result = crate::approval::apply_scheduled_update(&client, &update.spec).await;
+                audit::record(
+                    UpdateAuditSpec::for_update_request(AuditAction::Applied, &update.spec)
+                        .with_source("scheduler")
+                        .with_message(format!("Maintenance window '{}'", update.schedule))
+                        .with_result(&result),
+                );
+                if let Err(e) = result {
+                    warn!(
+                        "Failed to apply scheduled update of {} {}/{}: {}",
+                        target.kind, target.namespace, target.name, e
+                    );
+                }
+            }
+            debug!(
+                "{} update(s) waiting for a maintenance window",
+                pending_updates().len()
+            );
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_time_window() {
+        let schedule: Schedule = "Mon-Fri 02:00-05:00".parse().unwrap();
+
+        // 2025-01-06 is a Monday
+        assert!(schedule.is_open(at(2025, 1, 6, 2, 0)));
+        assert!(schedule.is_open(at(2025, 1, 6, 4, 59)));
+        assert!(!schedule.is_open(at(2025, 1, 6, 5, 0)));
+        assert!(!schedule.is_open(at(2025, 1, 6, 1, 59)));
+        // Saturday
+        assert!(!schedule.is_open(at(2025, 1, 11, 3, 0)));
+    }
+
+    #[test]
+    fn test_time_window_across_midnight() {
+        let schedule: Schedule = "Fri 22:00-02:00".parse().unwrap();
+
+        // Friday night and early Saturday, but not early Friday
+        assert!(schedule.is_open(at(2025, 1, 10, 23, 0)));
+        assert!(schedule.is_open(at(2025, 1, 11, 1, 30)));
+        assert!(!schedule.is_open(at(2025, 1, 10, 1, 30)));
+        assert!(!schedule.is_open(at(2025, 1, 11, 22, 30)));
+    }
+
+    #[test]
+    fn test_time_window_days() {
+        assert_eq!(
+            parse_days("Sat,Sun").unwrap(),
+            [false, false, false, false, false, true, true]
+        );
+        assert_eq!(
+            parse_days("Fri-Mon").unwrap(),
+            [true, false, false, false, true, true, true]
+        );
+        assert_eq!(parse_days("daily").unwrap(), [true; 7]);
+        assert!(parse_days("Funday").is_err());
+    }
+
+    #[test]
+    fn test_cron_schedule() {
+        // Any minute from 02:00 to 04:59 on weekdays
+        let schedule: Schedule = "* 2-4 * * mon-fri".parse().unwrap();
+        assert!(schedule.is_open(at(2025, 1, 6, 3, 17)));
+        assert!(!schedule.is_open(at(2025, 1, 6, 5, 0)));
+        assert!(!schedule.is_open(at(2025, 1, 12, 3, 17)));
+
+        // Steps, lists and Sunday as 7
+        let schedule: Schedule = "*/15 0,12 * * 7".parse().unwrap();
+        assert!(schedule.is_open(at(2025, 1, 12, 12, 45)));
+        assert!(!schedule.is_open(at(2025, 1, 12, 12, 46)));
+
+        // Day of month or day of week when both are restricted
+        let schedule: Schedule = "* * 1 * mon".parse().unwrap();
+        assert!(schedule.is_open(at(2025, 1, 1, 9, 0)));
+        assert!(schedule.is_open(at(2025, 1, 6, 9, 0)));
+        assert!(!schedule.is_open(at(2025, 1, 7, 9, 0)));
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        assert!("Mon-Fri".parse::<Schedule>().is_err());
+        assert!("Mon-Fri 02:00-02:00".parse::<Schedule>().is_err());
+        assert!("Mon-Fri 25:00-26:00".parse::<Schedule>().is_err());
+        assert!("* 24 * * *".parse::<Schedule>().is_err());
+        assert!("*/0 * * * *".parse::<Schedule>().is_err());
+        assert!("Mon 02:00-05:00 Mars/Olympus".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn test_time_zone() {
+        // Without a zone database the named zone can't be resolved; nothing to check then
+        let Ok(schedule) = "Mon-Fri 02:00-05:00 Europe/Berlin".parse::<Schedule>() else {
+            return;
+        };
+
+        // 01:30 UTC is 02:30 in Berlin in winter
+        assert!(schedule.is_open(at(2025, 1, 6, 1, 30)));
+        assert!(!schedule.is_open(at(2025, 1, 6, 4, 30)));
+    }
+}
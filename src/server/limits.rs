@@ -0,0 +1,138 @@
+//! Request limits of the HTTP servers.
+//!
+//! [`serve`](super::serve) applies them to every server so that malformed or abusive callers
+//! can't tie up the operator:
+//!
+//! | Limit | Environment variable | Default | Answer |
+//! |-------|----------------------|---------|--------|
+//! | Request body size | `HEADWIND_MAX_BODY_BYTES` | 2 MiB | `413 Payload Too Large` |
+//! | Time to answer a request | `HEADWIND_REQUEST_TIMEOUT` | 60 s | `408 Request Timeout` |
+//! | Requests handled at once, per server | `HEADWIND_MAX_CONCURRENT_REQUESTS` | 256 | waits, then 408 |
+//!
+//! The timeout covers reading the body and running the handler, not streaming the response,
+//! so Server-Sent Events keep flowing. The limits are read once at startup.
+
+use crate::metrics::HTTP_REQUESTS_LIMITED;
+use axum::Router;
+use axum::extract::DefaultBodyLimit;
+use axum::http::{Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use std::time::Duration;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+
+const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+const DEFAULT_REQUEST_TIMEOUT: u64 = 60;
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 256;
+
+/// Limits applied to the requests of a server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_body_bytes: usize,
+    pub request_timeout: Duration,
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            request_timeout: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT),
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+        }
+    }
+}
+
+impl Limits {
+    /// Limits from the environment; unset, invalid and zero values keep the default
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_body_bytes: env_value("HEADWIND_MAX_BODY_BYTES")
+                .unwrap_or(defaults.max_body_bytes as u64) as usize,
+            request_timeout: env_value("HEADWIND_REQUEST_TIMEOUT")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.request_timeout),
+            max_concurrent_requests: env_value("HEADWIND_MAX_CONCURRENT_REQUESTS")
+                .unwrap_or(defaults.max_concurrent_requests as u64)
+                as usize,
+        }
+    }
+}
+
+fn env_value(name: &str) -> Option<u64> {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|v| *v > 0)
+}
+
+/// Apply `limits` to every route of `app`
+pub fn apply(app: Router, limits: &Limits) -> Router {
+    app.layer(DefaultBodyLimit::max(limits.max_body_bytes))
+        .layer(GlobalConcurrencyLimitLayer::new(
+            limits.max_concurrent_requests,
+        ))
+        .layer(TimeoutLayer::with_status_code(
+            StatusCode::REQUEST_TIMEOUT,
+            limits.request_timeout,
+        ))
+        .layer(middleware::from_fn(count_limited))
+}
+
+/// Count requests answered with 408 or 413
+async fn count_limited(request: Request<axum::body::Body>, next: Next) -> Response {
+    let response = next.run(request).await;
+    let limit = match response.status() {
+        StatusCode::REQUEST_TIMEOUT => "timeout",
+        StatusCode::PAYLOAD_TOO_LARGE => "body_size",
+        _ => return response,
+    };
+    HTTP_REQUESTS_LIMITED.with_label_values(&[limit]).inc();
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, Bytes};
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    async fn status(app: &Router, body: &'static str) -> StatusCode {
+        app.clone()
+            .oneshot(Request::post("/").body(Body::from(body)).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let limits = Limits {
+            max_body_bytes: 8,
+            request_timeout: Duration::from_millis(50),
+            max_concurrent_requests: 4,
+        };
+        let app = apply(
+            Router::new().route(
+                "/",
+                post(|body: Bytes| async move {
+                    if body.as_ref() == b"slow" {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                    "done"
+                }),
+            ),
+            &limits,
+        );
+
+        assert_eq!(status(&app, "ok").await, StatusCode::OK);
+        assert_eq!(
+            status(&app, "longer than eight bytes").await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert_eq!(status(&app, "slow").await, StatusCode::REQUEST_TIMEOUT);
+    }
+}
@@ -0,0 +1,378 @@
+//! HTTP listeners of the operator.
+//!
+//! In the default `split` mode the webhook (8080), approval API (8081), Web UI (8082) and metrics
+//! (9090) servers each have their own port, configurable with the `listen.*` keys (see
+//! [`crate::config::listen`]). `HEADWIND_SERVER_MODE=single` serves them all on one port
+//! (`HEADWIND_SERVER_PORT`, default 8080) so that a single Service port and ingress rule is enough.
+//! That listener binds the host of the `listen.webhook` address. The Web UI, webhooks and metrics
+//! keep their paths there, and the approval API moves below `/approval` since its paths overlap
+//! with the Web UI's. With `HEADWIND_BASE_PATH` (e.g. `/headwind`) everything is served below that
+//! prefix.
+//!
+//! All servers use TLS when it is configured (see [`tls`]) and enforce the request
+//! [`limits`].
+
+pub mod limits;
+pub mod tls;
+
+use crate::config::listen;
+use crate::webhook::auth::constant_time_eq;
+use crate::webhook::{ChartEventSender, EventSender};
+use crate::{approval, metrics, ui, webhook};
+use anyhow::Result;
+use axum::Router;
+use axum::http::HeaderMap;
+use axum::response::Redirect;
+use axum::routing::get;
+use axum::serve::ListenerExt;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::net::SocketAddr;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+/// Prefix of the approval API in single mode
+pub const APPROVAL_PREFIX: &str = "/approval";
+
+/// Port of the single listener unless `HEADWIND_SERVER_PORT` is set
+const DEFAULT_SINGLE_PORT: u16 = 8080;
+
+/// How the servers are exposed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerMode {
+    /// One port per server
+    Split,
+    /// All servers on one port
+    Single,
+}
+
+/// Server mode from `HEADWIND_SERVER_MODE` (default: split)
+pub fn mode() -> ServerMode {
+    match std::env::var("HEADWIND_SERVER_MODE").as_deref() {
+        Ok("single") => ServerMode::Single,
+        _ => ServerMode::Split,
+    }
+}
+
+/// Port of the single listener (`HEADWIND_SERVER_PORT`, default: 8080)
+pub fn port() -> u16 {
+    std::env::var("HEADWIND_SERVER_PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_SINGLE_PORT)
+}
+
+/// Address of the single listener: the host of `listen.webhook` with [`port`]
+fn single_addr(listen: &listen::ListenConfig) -> SocketAddr {
+    SocketAddr::new(listen.webhook.ip(), port())
+}
+
+/// Prefix of every path in single mode (`HEADWIND_BASE_PATH`), without a trailing slash.
+/// Empty in split mode and when unset.
+pub fn base_path() -> String {
+    match mode() {
+        ServerMode::Split => String::new(),
+        ServerMode::Single => {
+            normalize_base_path(&std::env::var("HEADWIND_BASE_PATH").unwrap_or_default())
+        },
+    }
+}
+
+/// `headwind/`, `/headwind/` and `/headwind` all become `/headwind`; `/` becomes empty
+fn normalize_base_path(value: &str) -> String {
+    let trimmed = value.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", trimmed)
+    }
+}
+
+fn local_scheme() -> &'static str {
+    if tls::enabled() { "https" } else { "http" }
+}
+
+/// `host:port` to reach a server bound to `addr` from within the operator
+fn local_authority(addr: SocketAddr) -> String {
+    if addr.ip().is_unspecified() {
+        format!("localhost:{}", addr.port())
+    } else {
+        addr.to_string()
+    }
+}
+
+/// Base URL of the approval API for requests from within the operator
+pub fn approval_api_url() -> String {
+    match mode() {
+        ServerMode::Split => format!(
+            "{}://{}",
+            local_scheme(),
+            local_authority(listen::current().api)
+        ),
+        ServerMode::Single => format!(
+            "{}://{}{}{}",
+            local_scheme(),
+            local_authority(single_addr(&listen::current())),
+            base_path(),
+            APPROVAL_PREFIX
+        ),
+    }
+}
+
+/// URL of the Prometheus metrics endpoint for requests from within the operator
+pub fn metrics_url() -> String {
+    match mode() {
+        ServerMode::Split => format!(
+            "{}://{}/metrics",
+            local_scheme(),
+            local_authority(listen::current().metrics)
+        ),
+        ServerMode::Single => format!(
+            "{}://{}{}/metrics",
+            local_scheme(),
+            local_authority(single_addr(&listen::current())),
+            base_path()
+        ),
+    }
+}
+
+/// Header of the operator's requests to its own servers carrying the process's secret
+pub const LOCAL_TOKEN_HEADER: &str = "x-headwind-local-token";
+
+/// Secret of this process, sent with its requests to its own servers. It never leaves the
+/// process, so requests carrying it were sent by the operator itself.
+static LOCAL_TOKEN: once_cell::sync::Lazy<String> = once_cell::sync::Lazy::new(|| {
+    (0..4)
+        .map(|_| format!("{:016x}", RandomState::new().build_hasher().finish()))
+        .collect()
+});
+
+/// Whether a request was sent by the operator itself through [`local_client`]
+pub fn is_local(headers: &HeaderMap) -> bool {
+    headers
+        .get(LOCAL_TOKEN_HEADER)
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), LOCAL_TOKEN.as_bytes()))
+}
+
+/// HTTP client for requests from within the operator to its own servers. With TLS the
+/// certificate is usually not issued for `localhost`, so it isn't verified on these loopback
+/// requests.
+pub fn local_client() -> reqwest::Client {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Ok(token) = reqwest::header::HeaderValue::from_str(&LOCAL_TOKEN) {
+        headers.insert(LOCAL_TOKEN_HEADER, token);
+    }
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .danger_accept_invalid_certs(tls::enabled())
+        .build()
+        .unwrap_or_default()
+}
+
+/// Serve `app` on `addr` with the request limits, over TLS when it is configured
+pub async fn serve(addr: impl ToSocketAddrs, app: Router) -> io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let app = limits::apply(app, &limits::Limits::from_env())
+        .into_make_service_with_connect_info::<SocketAddr>();
+
+    match tls::acceptor().map_err(io::Error::other)? {
+        // `tap_io` provides `ConnectInfo<SocketAddr>` for listeners other than TcpListener
+        Some(acceptor) => {
+            axum::serve(
+                tls::TlsListener::new(listener, acceptor)?.tap_io(|_| {}),
+                app,
+            )
+            .await
+        },
+        None => axum::serve(listener, app).await,
+    }
+}
+
+/// Combine the server routers for single mode
+pub fn single_router(
+    base_path: &str,
+    webhook: Router,
+    approval: Router,
+    ui: Router,
+    metrics: Router,
+) -> Router {
+    let app = ui
+        .merge(webhook)
+        .merge(metrics)
+        .nest(APPROVAL_PREFIX, approval);
+
+    if base_path.is_empty() {
+        app
+    } else {
+        // The dashboard is at `{base}`; links to `{base}/` are redirected there
+        let dashboard = base_path.to_string();
+        Router::new().nest(base_path, app).route(
+            &format!("{}/", base_path),
+            get(move || async move { Redirect::permanent(&dashboard) }),
+        )
+    }
+}
+
+/// Start the HTTP servers and the webhook event processors. The returned handle completes
+/// when a server stops.
+pub async fn start_servers() -> Result<(JoinHandle<()>, EventSender, ChartEventSender)> {
+    // Fail at startup rather than in the listeners when the certificate can't be loaded
+    tls::acceptor()?;
+
+    match mode() {
+        ServerMode::Split => start_split_servers().await,
+        ServerMode::Single => start_single_server().await,
+    }
+}
+
+async fn start_split_servers() -> Result<(JoinHandle<()>, EventSender, ChartEventSender)> {
+    let listen = listen::current();
+    let metrics_handle = metrics::start_metrics_server(listen.metrics).await?;
+    let (webhook_handle, event_tx, chart_event_tx) =
+        webhook::start_webhook_server(listen.webhook).await?;
+    let approval_handle = approval::start_approval_server(listen.api).await?;
+    let ui_handle = tokio::spawn(async move {
+        if let Err(e) = ui::start_ui_server(listen.ui).await {
+            error!("Web UI server error: {}", e);
+        }
+    });
+
+    let handle = tokio::spawn(async move {
+        tokio::select! {
+            _ = metrics_handle => info!("Metrics server stopped"),
+            _ = webhook_handle => info!("Webhook server stopped"),
+            _ = approval_handle => info!("Approval server stopped"),
+            _ = ui_handle => info!("Web UI server stopped"),
+        }
+    });
+
+    Ok((handle, event_tx, chart_event_tx))
+}
+
+async fn start_single_server() -> Result<(JoinHandle<()>, EventSender, ChartEventSender)> {
+    metrics::register_metrics();
+    let (event_tx, chart_event_tx) = webhook::start_event_processors();
+
+    let base_path = base_path();
+    let app = single_router(
+        &base_path,
+        webhook::router(event_tx.clone(), chart_event_tx.clone()),
+        approval::router().await?,
+        ui::create_router(),
+        metrics::router(),
+    );
+
+    let addr = single_addr(&listen::current());
+    info!(
+        "Starting single-port server on {} (base path: {:?})",
+        addr, base_path
+    );
+
+    let handle = tokio::spawn(async move {
+        serve(addr, app).await.expect("Server failed");
+    });
+
+    Ok((handle, event_tx, chart_event_tx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_normalize_base_path() {
+        assert_eq!(normalize_base_path(""), "");
+        assert_eq!(normalize_base_path("/"), "");
+        assert_eq!(normalize_base_path("headwind"), "/headwind");
+        assert_eq!(normalize_base_path("/headwind/"), "/headwind");
+        assert_eq!(normalize_base_path(" /tools/headwind "), "/tools/headwind");
+    }
+
+    #[test]
+    fn test_local_authority() {
+        assert_eq!(
+            local_authority("0.0.0.0:8081".parse().unwrap()),
+            "localhost:8081"
+        );
+        assert_eq!(
+            local_authority("[::]:9090".parse().unwrap()),
+            "localhost:9090"
+        );
+        assert_eq!(
+            local_authority("127.0.0.1:18081".parse().unwrap()),
+            "127.0.0.1:18081"
+        );
+        assert_eq!(local_authority("[::1]:8082".parse().unwrap()), "[::1]:8082");
+    }
+
+    #[test]
+    fn test_single_addr_keeps_listen_host() {
+        let listen = listen::ListenConfig {
+            webhook: "127.0.0.1:18080".parse().unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(single_addr(&listen).ip().to_string(), "127.0.0.1");
+        assert_eq!(single_addr(&listen).port(), port());
+        assert!(
+            single_addr(&listen::ListenConfig::default())
+                .ip()
+                .is_unspecified()
+        );
+    }
+
+    #[test]
+    fn test_is_local() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_local(&headers));
+        headers.insert(LOCAL_TOKEN_HEADER, "guessed".parse().unwrap());
+        assert!(!is_local(&headers));
+        headers.insert(LOCAL_TOKEN_HEADER, LOCAL_TOKEN.parse().unwrap());
+        assert!(is_local(&headers));
+    }
+
+    async fn status(app: &Router, path: &str) -> StatusCode {
+        app.clone()
+            .oneshot(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_single_router() {
+        let app = single_router(
+            "/headwind",
+            Router::new().route("/webhook/registry", get(|| async { "webhook" })),
+            Router::new().route("/api/v1/updates", get(|| async { "approval" })),
+            Router::new()
+                .route("/", get(|| async { "dashboard" }))
+                .route("/api/v1/updates", get(|| async { "ui" })),
+            Router::new().route("/metrics", get(|| async { "metrics" })),
+        );
+
+        assert_eq!(status(&app, "/headwind").await, StatusCode::OK);
+        assert_eq!(
+            status(&app, "/headwind/").await,
+            StatusCode::PERMANENT_REDIRECT
+        );
+        assert_eq!(
+            status(&app, "/headwind/api/v1/updates").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, "/headwind/approval/api/v1/updates").await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, "/headwind/webhook/registry").await,
+            StatusCode::OK
+        );
+        assert_eq!(status(&app, "/headwind/metrics").await, StatusCode::OK);
+        assert_eq!(status(&app, "/metrics").await, StatusCode::NOT_FOUND);
+    }
+}
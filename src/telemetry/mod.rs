@@ -0,0 +1,212 @@
+//! Logging and OpenTelemetry tracing.
+//!
+//! Every span carries an OpenTelemetry trace context, so an update flow (webhook or polling
+//! event → policy evaluation → UpdateRequest creation → apply → health monitoring) shares one
+//! trace ID. The trace ID doubles as the correlation ID recorded in audit records and
+//! notifications. Flows that pause at an UpdateRequest are picked up again from its
+//! `headwind.sh/traceparent` annotation when the request is approved, rejected or expires.
+//!
+//! Spans are exported over OTLP/HTTP when HEADWIND_OTLP_ENDPOINT is set (e.g.
+//! `http://otel-collector.monitoring:4318`); otherwise trace IDs are only used for
+//! correlation.
+
+use crate::models::policy::annotations;
+use anyhow::{Context as _, Result};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::collections::{BTreeMap, HashMap};
+use tracing::{Level, Span, debug, warn};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Service name reported with exported spans when OTEL_SERVICE_NAME isn't set
+const SERVICE_NAME: &str = "headwind";
+
+/// W3C trace context header name, used as the key when (de)serializing trace contexts
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Flushes exported spans when dropped at shutdown
+pub struct TelemetryGuard {
+    provider: SdkTracerProvider,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to flush traces: {}", e);
+        }
+    }
+}
+
+/// Install the global tracing subscriber: JSON logs filtered by RUST_LOG, plus OpenTelemetry
+/// spans exported to HEADWIND_OTLP_ENDPOINT when it is set.
+pub fn init() -> Result<TelemetryGuard> {
+    let endpoint = std::env::var("HEADWIND_OTLP_ENDPOINT")
+        .ok()
+        .filter(|e| !e.trim().is_empty());
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| SERVICE_NAME.to_string());
+
+    let mut provider = SdkTracerProvider::builder()
+        .with_resource(Resource::builder().with_service_name(service_name).build());
+    if let Some(endpoint) = &endpoint {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpBinary)
+            .with_endpoint(traces_endpoint(endpoint))
+            .build()
+            .context("Failed to create OTLP span exporter")?;
+        provider = provider.with_batch_exporter(exporter);
+    }
+    let provider = provider.build();
+
+    tracing_subscriber::registry()
+        .with(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| "headwind=info,kube=info".into()),
+        )
+        .with(tracing_subscriber::fmt::layer().json())
+        .with(otel_layer(provider.tracer(SERVICE_NAME)))
+        .init();
+
+    if let Some(endpoint) = endpoint {
+        tracing::info!("Exporting traces to {}", traces_endpoint(&endpoint));
+    }
+
+    Ok(TelemetryGuard { provider })
+}
+
+/// Layer recording Headwind's spans as OpenTelemetry spans. Spans start lazily (on their
+/// first child or trace ID lookup) rather than when entered, so an instrumented handler can
+/// still switch to the trace of the UpdateRequest it loaded. Spans of dependencies such as the
+/// kube client are left out so they can't start a handler's span early.
+fn otel_layer<S>(tracer: opentelemetry_sdk::trace::Tracer) -> impl Layer<S>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_context_activation(false)
+        .with_filter(Targets::new().with_target(env!("CARGO_CRATE_NAME"), Level::TRACE))
+}
+
+/// OTLP/HTTP traces URL for a collector base URL
+fn traces_endpoint(endpoint: &str) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    }
+}
+
+/// Correlation ID of the current span: its trace ID, when there is one
+pub fn correlation_id() -> Option<String> {
+    let context = Span::current().context();
+    let span_context = context.span().span_context().clone();
+    span_context
+        .is_valid()
+        .then(|| span_context.trace_id().to_string())
+}
+
+/// Annotations recording the current trace on an UpdateRequest, so the spans of its approval
+/// and rollout join the trace it was created in
+pub fn trace_annotations() -> BTreeMap<String, String> {
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(&Span::current().context(), &mut carrier);
+
+    let mut trace_annotations = BTreeMap::new();
+    if let Some(traceparent) = carrier.remove(TRACEPARENT_HEADER) {
+        trace_annotations.insert(annotations::TRACEPARENT.to_string(), traceparent);
+    }
+    if let Some(correlation_id) = correlation_id() {
+        trace_annotations.insert(annotations::CORRELATION_ID.to_string(), correlation_id);
+    }
+    trace_annotations
+}
+
+/// Make the current span continue the trace recorded in an UpdateRequest's annotations.
+/// Call this before the span starts any child spans; requests created before tracing was
+/// enabled keep a trace of their own.
+pub fn continue_trace(resource_annotations: &BTreeMap<String, String>) {
+    let Some(traceparent) = resource_annotations.get(annotations::TRACEPARENT) else {
+        return;
+    };
+    let carrier = HashMap::from([(TRACEPARENT_HEADER.to_string(), traceparent.clone())]);
+    let parent = TraceContextPropagator::new().extract(&carrier);
+    if !parent.span().span_context().is_valid() {
+        warn!(
+            "Ignoring invalid {}: {}",
+            annotations::TRACEPARENT,
+            traceparent
+        );
+        return;
+    }
+    if let Err(e) = Span::current().set_parent(parent) {
+        debug!("Could not continue trace {}: {}", traceparent, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traces_endpoint() {
+        assert_eq!(
+            traces_endpoint("http://otel-collector:4318"),
+            "http://otel-collector:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_endpoint("http://otel-collector:4318/"),
+            "http://otel-collector:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_endpoint("https://otlp.example.com/v1/traces"),
+            "https://otlp.example.com/v1/traces"
+        );
+    }
+
+    #[test]
+    fn test_no_trace_outside_spans() {
+        assert_eq!(correlation_id(), None);
+        assert!(trace_annotations().is_empty());
+    }
+
+    #[test]
+    fn test_update_request_continues_trace() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry().with(otel_layer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let (recorded, correlation) = tracing::info_span!("create_update_request")
+                .in_scope(|| (trace_annotations(), correlation_id()));
+            let correlation = correlation.expect("span has a trace ID");
+            assert_eq!(
+                recorded.get(annotations::CORRELATION_ID),
+                Some(&correlation)
+            );
+            assert!(recorded[annotations::TRACEPARENT].contains(&correlation));
+
+            // A later approval joins the trace the UpdateRequest was created in
+            let continued = tracing::info_span!("approve_update").in_scope(|| {
+                continue_trace(&recorded);
+                correlation_id()
+            });
+            assert_eq!(continued, Some(correlation.clone()));
+
+            // Requests without trace annotations start a trace of their own
+            let fresh = tracing::info_span!("approve_update").in_scope(|| {
+                continue_trace(&BTreeMap::new());
+                correlation_id()
+            });
+            assert!(fresh.is_some());
+            assert_ne!(fresh, Some(correlation));
+        });
+    }
+}
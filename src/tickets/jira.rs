@@ -0,0 +1,176 @@
+use super::{ChangeTickets, description, error_for_status, http_client, required_env, summary};
+use crate::models::crd::{ChangeTicket, UpdateRequest};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{Value, json};
+use tracing::info;
+
+/// Jira settings, read from JIRA_* environment variables
+#[derive(Debug, Clone)]
+pub struct JiraConfig {
+    /// Base URL, e.g. `https://example.atlassian.net`
+    pub url: String,
+    pub user: String,
+    pub api_token: String,
+    /// Project key issues are created in
+    pub project: String,
+    /// Issue type (default: Task)
+    pub issue_type: String,
+    /// Statuses that count as approved (default: Approved)
+    pub approved_statuses: Vec<String>,
+}
+
+impl JiraConfig {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            url: required_env("JIRA_URL")?.trim_end_matches('/').to_string(),
+            user: required_env("JIRA_USER")?,
+            api_token: required_env("JIRA_API_TOKEN")?,
+            project: required_env("JIRA_PROJECT")?,
+            issue_type: required_env("JIRA_ISSUE_TYPE").unwrap_or_else(|_| "Task".to_string()),
+            approved_statuses: required_env("JIRA_APPROVED_STATUSES")
+                .unwrap_or_else(|_| "Approved".to_string())
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+        })
+    }
+}
+
+pub struct JiraTickets {
+    config: JiraConfig,
+    http: Client,
+}
+
+impl JiraTickets {
+    pub fn new(config: JiraConfig) -> Result<Self> {
+        Ok(Self {
+            config,
+            http: http_client()?,
+        })
+    }
+}
+
+/// Request body creating an issue for an UpdateRequest
+fn issue_body(config: &JiraConfig, update_request: &UpdateRequest) -> Value {
+    json!({
+        "fields": {
+            "project": { "key": config.project },
+            "issuetype": { "name": config.issue_type },
+            "summary": summary(update_request),
+            "description": description(update_request),
+            "labels": ["headwind"]
+        }
+    })
+}
+
+/// The ticket for a created issue (`{"id": ..., "key": "OPS-123", ...}`)
+fn ticket_from_response(config: &JiraConfig, body: &Value) -> Result<ChangeTicket> {
+    let key = body["key"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Jira response has no issue key"))?;
+    Ok(ChangeTicket {
+        system: "jira".to_string(),
+        id: key.to_string(),
+        url: Some(format!("{}/browse/{}", config.url, key)),
+        record_id: None,
+    })
+}
+
+/// Whether an issue status counts as approved
+fn is_approved_status(config: &JiraConfig, status: &str) -> bool {
+    config
+        .approved_statuses
+        .iter()
+        .any(|approved| approved.eq_ignore_ascii_case(status))
+}
+
+#[async_trait]
+impl ChangeTickets for JiraTickets {
+    async fn open(&self, update_request: &UpdateRequest) -> Result<ChangeTicket> {
+        let response = self
+            .http
+            .post(format!("{}/rest/api/2/issue", self.config.url))
+            .basic_auth(&self.config.user, Some(&self.config.api_token))
+            .json(&issue_body(&self.config, update_request))
+            .send()
+            .await?;
+        let body: Value = error_for_status(response, "create Jira issue")
+            .await?
+            .json()
+            .await?;
+
+        let ticket = ticket_from_response(&self.config, &body)?;
+        info!("Opened Jira issue {}", ticket.id);
+        Ok(ticket)
+    }
+
+    async fn is_approved(&self, ticket: &ChangeTicket) -> Result<bool> {
+        let response = self
+            .http
+            .get(format!(
+                "{}/rest/api/2/issue/{}?fields=status",
+                self.config.url, ticket.id
+            ))
+            .basic_auth(&self.config.user, Some(&self.config.api_token))
+            .send()
+            .await?;
+        let body: Value = error_for_status(response, &format!("read Jira issue {}", ticket.id))
+            .await?
+            .json()
+            .await?;
+
+        let status = body["fields"]["status"]["name"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Jira issue {} has no status", ticket.id))?;
+        Ok(is_approved_status(&self.config, status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> JiraConfig {
+        JiraConfig {
+            url: "https://example.atlassian.net".to_string(),
+            user: "headwind@example.com".to_string(),
+            api_token: "token".to_string(),
+            project: "OPS".to_string(),
+            issue_type: "Change".to_string(),
+            approved_statuses: vec!["Approved".to_string(), "Ready to Deploy".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_issue_body() {
+        let body = issue_body(&config(), &super::super::tests::update_request());
+        assert_eq!(body["fields"]["project"]["key"], "OPS");
+        assert_eq!(body["fields"]["issuetype"]["name"], "Change");
+        assert_eq!(
+            body["fields"]["summary"],
+            "Update Deployment production/web to web:1.5.0"
+        );
+    }
+
+    #[test]
+    fn test_ticket_from_response() {
+        let ticket =
+            ticket_from_response(&config(), &json!({ "id": "10001", "key": "OPS-42" })).unwrap();
+        assert_eq!(ticket.id, "OPS-42");
+        assert_eq!(
+            ticket.url.as_deref(),
+            Some("https://example.atlassian.net/browse/OPS-42")
+        );
+        assert!(ticket_from_response(&config(), &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_is_approved_status() {
+        assert!(is_approved_status(&config(), "approved"));
+        assert!(is_approved_status(&config(), "Ready to Deploy"));
+        assert!(!is_approved_status(&config(), "In Review"));
+    }
+}
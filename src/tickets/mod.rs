@@ -0,0 +1,296 @@
+//! Change tickets: a Jira issue or ServiceNow change request opened for every UpdateRequest
+//! that waits for approval, so updates show up in existing change-management processes.
+//!
+//! The ticket is recorded in the UpdateRequest's `status.changeTicket`. With
+//! HEADWIND_CHANGE_TICKET_GATE enabled, an UpdateRequest can only be approved once its ticket
+//! has been approved in the ticket system.
+
+mod jira;
+mod servicenow;
+
+use crate::models::crd::{ChangeTicket, UpdatePhase, UpdateRequest};
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use reqwest::{Client, Response};
+use std::time::Duration;
+
+pub use jira::{JiraConfig, JiraTickets};
+pub use servicenow::{ServiceNowConfig, ServiceNowTickets};
+
+/// Supported ticket systems
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TicketSystem {
+    Jira,
+    ServiceNow,
+}
+
+impl TicketSystem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TicketSystem::Jira => "jira",
+            TicketSystem::ServiceNow => "servicenow",
+        }
+    }
+}
+
+impl std::str::FromStr for TicketSystem {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "jira" => Ok(TicketSystem::Jira),
+            "servicenow" => Ok(TicketSystem::ServiceNow),
+            other => Err(anyhow!(
+                "Unsupported ticket system: {} (expected jira or servicenow)",
+                other
+            )),
+        }
+    }
+}
+
+/// Ticket system change tickets are opened in (HEADWIND_CHANGE_TICKETS, default: none)
+pub fn ticket_system() -> Option<TicketSystem> {
+    std::env::var("HEADWIND_CHANGE_TICKETS")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .and_then(|v| v.parse().ok())
+}
+
+/// Whether approval waits for the change ticket to be approved
+/// (HEADWIND_CHANGE_TICKET_GATE, default: false)
+pub fn gate_enabled() -> bool {
+    std::env::var("HEADWIND_CHANGE_TICKET_GATE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Operations Headwind needs from a ticket system
+#[async_trait]
+pub trait ChangeTickets: Send + Sync {
+    /// Open a ticket describing an UpdateRequest
+    async fn open(&self, update_request: &UpdateRequest) -> Result<ChangeTicket>;
+
+    /// Whether the ticket has been approved
+    async fn is_approved(&self, ticket: &ChangeTicket) -> Result<bool>;
+}
+
+/// Client for the configured ticket system, or None when change tickets are disabled
+pub fn connect() -> Result<Option<Box<dyn ChangeTickets>>> {
+    let Some(system) = ticket_system() else {
+        return Ok(None);
+    };
+
+    Ok(Some(match system {
+        TicketSystem::Jira => Box::new(JiraTickets::new(JiraConfig::from_env()?)?),
+        TicketSystem::ServiceNow => {
+            Box::new(ServiceNowTickets::new(ServiceNowConfig::from_env()?)?)
+        },
+    }))
+}
+
+/// Whether a ticket should be opened for an UpdateRequest: it waits for approval and has
+/// none yet
+pub fn needs_ticket(update_request: &UpdateRequest) -> bool {
+    let status = update_request.status.as_ref();
+    update_request.spec.require_approval
+        && status.map(|s| &s.phase).unwrap_or(&UpdatePhase::Pending) == &UpdatePhase::Pending
+        && status.and_then(|s| s.change_ticket.as_ref()).is_none()
+}
+
+/// Check the change ticket gate before approving an UpdateRequest. Returns why approval
+/// isn't possible yet.
+pub async fn check_gate(update_request: &UpdateRequest) -> std::result::Result<(), String> {
+    if !gate_enabled() {
+        return Ok(());
+    }
+    let tickets = match connect() {
+        Ok(Some(tickets)) => tickets,
+        Ok(None) => return Ok(()),
+        Err(e) => return Err(format!("Failed to check change ticket: {:#}", e)),
+    };
+
+    let Some(ticket) = update_request
+        .status
+        .as_ref()
+        .and_then(|s| s.change_ticket.as_ref())
+    else {
+        return Err("No change ticket has been opened for this update yet".to_string());
+    };
+
+    match tickets.is_approved(ticket).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("Change ticket {} is not approved yet", ticket.id)),
+        Err(e) => Err(format!(
+            "Failed to check change ticket {}: {:#}",
+            ticket.id, e
+        )),
+    }
+}
+
+/// Ticket title for an UpdateRequest
+fn summary(update_request: &UpdateRequest) -> String {
+    let target = &update_request.spec.target_ref;
+    format!(
+        "Update {} {}/{} to {}",
+        target.kind, target.namespace, target.name, update_request.spec.new_image
+    )
+}
+
+/// Ticket body for an UpdateRequest
+fn description(update_request: &UpdateRequest) -> String {
+    let spec = &update_request.spec;
+    let target = &spec.target_ref;
+    let name = update_request.metadata.name.as_deref().unwrap_or_default();
+
+    let mut lines = vec![
+        format!(
+            "Headwind found a new version for {} {}/{}.",
+            target.kind, target.namespace, target.name
+        ),
+        String::new(),
+        format!("Current: {}", spec.current_image),
+        format!("New: {}", spec.new_image),
+        format!("Policy: {:?}", spec.policy),
+    ];
+    if let Some(container) = &spec.container_name {
+        lines.push(format!("Container: {}", container));
+    }
+    if let Some(reason) = &spec.reason {
+        lines.push(format!("Reason: {}", reason));
+    }
+    lines.push(format!(
+        "UpdateRequest: {}/{}",
+        update_request
+            .metadata
+            .namespace
+            .as_deref()
+            .unwrap_or_default(),
+        name
+    ));
+    if let Ok(ui_url) = std::env::var("HEADWIND_UI_URL") {
+        lines.push(format!(
+            "Review: {}/updates/{}/{}",
+            ui_url.trim_end_matches('/'),
+            update_request
+                .metadata
+                .namespace
+                .as_deref()
+                .unwrap_or_default(),
+            name
+        ));
+    }
+    lines.join("\n")
+}
+
+fn http_client() -> Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .user_agent("headwind")
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+/// Read a required setting
+fn required_env(name: &str) -> Result<String> {
+    std::env::var(name)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| anyhow!("{} is required for change tickets", name))
+}
+
+async fn error_for_status(response: Response, action: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(anyhow!("Failed to {}: {} {}", action, status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::crd::{
+        TargetRef, UpdatePolicyType, UpdateRequestSpec, UpdateRequestStatus, UpdateType,
+    };
+
+    pub(super) fn update_request() -> UpdateRequest {
+        let mut update_request = UpdateRequest::new(
+            "web-1-5-0",
+            UpdateRequestSpec {
+                target_ref: TargetRef {
+                    api_version: "apps/v1".to_string(),
+                    kind: "Deployment".to_string(),
+                    name: "web".to_string(),
+                    namespace: "production".to_string(),
+                },
+                update_type: UpdateType::Image,
+                container_name: Some("web".to_string()),
+                image_parameter: None,
+                current_image: "web:1.4.0".to_string(),
+                new_image: "web:1.5.0".to_string(),
+                policy: UpdatePolicyType::Minor,
+                reason: None,
+                require_approval: true,
+                expires_at: None,
+                required_approvals: None,
+                git_write_back: None,
+                grouped_containers: Vec::new(),
+                source: None,
+                resolved_version: None,
+            },
+        );
+        update_request.metadata.namespace = Some("production".to_string());
+        update_request
+    }
+
+    #[test]
+    fn test_parse_ticket_system() {
+        assert_eq!("Jira".parse::<TicketSystem>().unwrap(), TicketSystem::Jira);
+        assert_eq!(
+            "servicenow".parse::<TicketSystem>().unwrap(),
+            TicketSystem::ServiceNow
+        );
+        assert!("remedy".parse::<TicketSystem>().is_err());
+    }
+
+    #[test]
+    fn test_needs_ticket() {
+        let mut request = update_request();
+        assert!(needs_ticket(&request));
+
+        request.status = Some(UpdateRequestStatus {
+            change_ticket: Some(ChangeTicket {
+                system: "jira".to_string(),
+                id: "OPS-1".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert!(!needs_ticket(&request));
+
+        request.status = Some(UpdateRequestStatus {
+            phase: UpdatePhase::Completed,
+            ..Default::default()
+        });
+        assert!(!needs_ticket(&request));
+
+        let mut automatic = update_request();
+        automatic.spec.require_approval = false;
+        assert!(!needs_ticket(&automatic));
+    }
+
+    #[test]
+    fn test_ticket_text() {
+        let request = update_request();
+        assert_eq!(
+            summary(&request),
+            "Update Deployment production/web to web:1.5.0"
+        );
+        let description = description(&request);
+        assert!(description.contains("Current: web:1.4.0"));
+        assert!(description.contains("UpdateRequest: production/web-1-5-0"));
+    }
+}
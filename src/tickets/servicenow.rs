@@ -0,0 +1,172 @@
+use super::{ChangeTickets, description, error_for_status, http_client, required_env, summary};
+use crate::models::crd::{ChangeTicket, UpdateRequest};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{Value, json};
+use tracing::info;
+
+/// ServiceNow settings, read from SERVICENOW_* environment variables
+#[derive(Debug, Clone)]
+pub struct ServiceNowConfig {
+    /// Instance URL, e.g. `https://example.service-now.com`
+    pub url: String,
+    pub user: String,
+    pub password: String,
+    /// Change request type (default: normal)
+    pub change_type: String,
+    /// Assignment group of new change requests
+    pub assignment_group: Option<String>,
+}
+
+impl ServiceNowConfig {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            url: required_env("SERVICENOW_URL")?
+                .trim_end_matches('/')
+                .to_string(),
+            user: required_env("SERVICENOW_USER")?,
+            password: required_env("SERVICENOW_PASSWORD")?,
+            change_type: required_env("SERVICENOW_CHANGE_TYPE")
+                .unwrap_or_else(|_| "normal".to_string()),
+            assignment_group: required_env("SERVICENOW_ASSIGNMENT_GROUP").ok(),
+        })
+    }
+}
+
+pub struct ServiceNowTickets {
+    config: ServiceNowConfig,
+    http: Client,
+}
+
+impl ServiceNowTickets {
+    pub fn new(config: ServiceNowConfig) -> Result<Self> {
+        Ok(Self {
+            config,
+            http: http_client()?,
+        })
+    }
+}
+
+/// Request body creating a change request for an UpdateRequest
+fn change_request_body(config: &ServiceNowConfig, update_request: &UpdateRequest) -> Value {
+    let mut body = json!({
+        "short_description": summary(update_request),
+        "description": description(update_request),
+        "type": config.change_type,
+    });
+    if let Some(group) = &config.assignment_group {
+        body["assignment_group"] = json!(group);
+    }
+    body
+}
+
+/// The ticket for a created change request (`{"result": {"sys_id": ..., "number": "CHG0030001"}}`)
+fn ticket_from_response(config: &ServiceNowConfig, body: &Value) -> Result<ChangeTicket> {
+    let result = &body["result"];
+    let (Some(number), Some(sys_id)) = (result["number"].as_str(), result["sys_id"].as_str())
+    else {
+        return Err(anyhow!(
+            "ServiceNow response has no change request number or sys_id"
+        ));
+    };
+    Ok(ChangeTicket {
+        system: "servicenow".to_string(),
+        id: number.to_string(),
+        url: Some(format!(
+            "{}/change_request.do?sys_id={}",
+            config.url, sys_id
+        )),
+        record_id: Some(sys_id.to_string()),
+    })
+}
+
+#[async_trait]
+impl ChangeTickets for ServiceNowTickets {
+    async fn open(&self, update_request: &UpdateRequest) -> Result<ChangeTicket> {
+        let response = self
+            .http
+            .post(format!("{}/api/now/table/change_request", self.config.url))
+            .basic_auth(&self.config.user, Some(&self.config.password))
+            .header("Accept", "application/json")
+            .json(&change_request_body(&self.config, update_request))
+            .send()
+            .await?;
+        let body: Value = error_for_status(response, "create ServiceNow change request")
+            .await?
+            .json()
+            .await?;
+
+        let ticket = ticket_from_response(&self.config, &body)?;
+        info!("Opened ServiceNow change request {}", ticket.id);
+        Ok(ticket)
+    }
+
+    async fn is_approved(&self, ticket: &ChangeTicket) -> Result<bool> {
+        let sys_id = ticket
+            .record_id
+            .as_deref()
+            .ok_or_else(|| anyhow!("Change request {} has no sys_id", ticket.id))?;
+        let response = self
+            .http
+            .get(format!(
+                "{}/api/now/table/change_request/{}?sysparm_fields=approval",
+                self.config.url, sys_id
+            ))
+            .basic_auth(&self.config.user, Some(&self.config.password))
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+        let body: Value = error_for_status(
+            response,
+            &format!("read ServiceNow change request {}", ticket.id),
+        )
+        .await?
+        .json()
+        .await?;
+
+        Ok(body["result"]["approval"].as_str() == Some("approved"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ServiceNowConfig {
+        ServiceNowConfig {
+            url: "https://example.service-now.com".to_string(),
+            user: "headwind".to_string(),
+            password: "secret".to_string(),
+            change_type: "standard".to_string(),
+            assignment_group: Some("Platform".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_change_request_body() {
+        let body = change_request_body(&config(), &super::super::tests::update_request());
+        assert_eq!(body["type"], "standard");
+        assert_eq!(body["assignment_group"], "Platform");
+        assert_eq!(
+            body["short_description"],
+            "Update Deployment production/web to web:1.5.0"
+        );
+    }
+
+    #[test]
+    fn test_ticket_from_response() {
+        let ticket = ticket_from_response(
+            &config(),
+            &json!({ "result": { "sys_id": "abc123", "number": "CHG0030001" } }),
+        )
+        .unwrap();
+        assert_eq!(ticket.id, "CHG0030001");
+        assert_eq!(ticket.record_id.as_deref(), Some("abc123"));
+        assert_eq!(
+            ticket.url.as_deref(),
+            Some("https://example.service-now.com/change_request.do?sys_id=abc123")
+        );
+        assert!(ticket_from_response(&config(), &json!({ "result": {} })).is_err());
+    }
+}
@@ -1,7 +1,8 @@
+use crate::config::uiroles::{self, UiRole};
 use axum::{
     Json,
     extract::FromRequestParts,
-    http::{StatusCode, header::AUTHORIZATION, request::Parts},
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION, request::Parts},
     response::{IntoResponse, Response},
 };
 use k8s_openapi::api::authentication::v1::{TokenReview, TokenReviewSpec};
@@ -42,6 +43,12 @@ impl AuthMode {
     pub fn proxy_header() -> String {
         env::var("HEADWIND_UI_PROXY_HEADER").unwrap_or_else(|_| "X-Forwarded-User".to_string())
     }
+
+    /// Get the header listing the user's groups (comma-separated) for proxy mode
+    pub fn proxy_groups_header() -> String {
+        env::var("HEADWIND_UI_PROXY_GROUPS_HEADER")
+            .unwrap_or_else(|_| "X-Forwarded-Groups".to_string())
+    }
 }
 
 /// User identity extracted from the request
@@ -49,17 +56,71 @@ impl AuthMode {
 pub struct UserIdentity {
     pub username: String,
     pub auth_mode: AuthMode,
+    /// Groups of the user, from the TokenReview or the proxy's groups header
+    pub groups: Vec<String>,
+    /// Role from the `uiRoles` mapping
+    pub role: UiRole,
+}
+
+impl UserIdentity {
+    fn new(username: String, auth_mode: AuthMode, groups: Vec<String>) -> Self {
+        let role = uiroles::role_for(&username, &groups);
+        Self {
+            username,
+            auth_mode,
+            groups,
+            role,
+        }
+    }
+
+    /// Whether the user may approve, reject and roll back
+    pub fn can_approve(&self) -> bool {
+        self.role == UiRole::Approver
+    }
+
+    /// Error for viewers attempting an approver action
+    pub fn require_approver(&self) -> Result<(), AuthError> {
+        if self.can_approve() {
+            Ok(())
+        } else {
+            Err(AuthError {
+                status: StatusCode::FORBIDDEN,
+                error: format!("User {} has the viewer role", self.username),
+            })
+        }
+    }
+}
+
+/// Whether the user of a page may approve, for showing or hiding actions. Pages are also
+/// served to browsers that don't send credentials (in `token` mode); those get the default
+/// role, and the actions are checked again when used.
+pub async fn page_can_approve(headers: &HeaderMap) -> bool {
+    match identify(headers).await {
+        Ok(user) => user.can_approve(),
+        Err(_) => uiroles::role_for("", &[]) == UiRole::Approver,
+    }
 }
 
 /// Authentication error response
 #[derive(Serialize)]
 pub struct AuthError {
+    #[serde(skip)]
+    pub status: StatusCode,
     pub error: String,
 }
 
+impl AuthError {
+    fn unauthorized(error: String) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            error,
+        }
+    }
+}
+
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
-        (StatusCode::UNAUTHORIZED, Json(self)).into_response()
+        (self.status, Json(self)).into_response()
     }
 }
 
@@ -71,91 +132,96 @@ where
     type Rejection = AuthError;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let auth_mode = AuthMode::from_env();
-
-        match auth_mode {
-            AuthMode::None => {
-                // No authentication - use default username
-                Ok(UserIdentity {
-                    username: "web-ui-user".to_string(),
-                    auth_mode,
-                })
-            },
-
-            AuthMode::Simple => {
-                // Simple mode - read username from X-User header (set by frontend)
-                let username = parts
-                    .headers
-                    .get("X-User")
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| "anonymous".to_string());
-
-                debug!("Simple auth: username={}", username);
-
-                Ok(UserIdentity {
-                    username,
-                    auth_mode,
-                })
-            },
-
-            AuthMode::Token => {
-                // Token mode - validate Kubernetes token
-                let auth_header = parts
-                    .headers
-                    .get(AUTHORIZATION)
-                    .and_then(|value| value.to_str().ok())
-                    .ok_or_else(|| AuthError {
-                        error: "Missing Authorization header".to_string(),
-                    })?;
-
-                let token = auth_header
-                    .strip_prefix("Bearer ")
-                    .ok_or_else(|| AuthError {
-                        error: "Invalid Authorization header format. Expected: Bearer <token>"
-                            .to_string(),
-                    })?;
-
-                let username = validate_token_and_get_username(token).await.map_err(|e| {
-                    error!("Token validation failed: {}", e);
-                    AuthError {
-                        error: format!("Token validation failed: {}", e),
-                    }
+        identify(&parts.headers).await
+    }
+}
+
+/// Identify the user of a request based on the configured auth mode
+pub async fn identify(headers: &HeaderMap) -> Result<UserIdentity, AuthError> {
+    let auth_mode = AuthMode::from_env();
+
+    match auth_mode {
+        AuthMode::None => {
+            // No authentication - use default username
+            Ok(UserIdentity::new(
+                "web-ui-user".to_string(),
+                auth_mode,
+                Vec::new(),
+            ))
+        },
+
+        AuthMode::Simple => {
+            // Simple mode - read username from X-User header (set by frontend)
+            let username = headers
+                .get("X-User")
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "anonymous".to_string());
+
+            debug!("Simple auth: username={}", username);
+            let groups = Vec::new();
+
+            Ok(UserIdentity::new(username, auth_mode, groups))
+        },
+
+        AuthMode::Token => {
+            // Token mode - validate Kubernetes token
+            let auth_header = headers
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| {
+                    AuthError::unauthorized("Missing Authorization header".to_string())
                 })?;
 
-                debug!("Token auth: username={}", username);
-
-                Ok(UserIdentity {
-                    username,
-                    auth_mode,
-                })
-            },
-
-            AuthMode::Proxy => {
-                // Proxy mode - read username from configured header
-                let header_name = AuthMode::proxy_header();
-                let username = parts
-                    .headers
-                    .get(&header_name)
-                    .and_then(|v| v.to_str().ok())
-                    .ok_or_else(|| AuthError {
-                        error: format!("Missing {} header", header_name),
-                    })?
-                    .to_string();
-
-                debug!("Proxy auth: username={}", username);
-
-                Ok(UserIdentity {
-                    username,
-                    auth_mode,
-                })
-            },
-        }
+            let token = auth_header.strip_prefix("Bearer ").ok_or_else(|| {
+                AuthError::unauthorized(
+                    "Invalid Authorization header format. Expected: Bearer <token>".to_string(),
+                )
+            })?;
+
+            let (username, groups) = validate_token(token).await.map_err(|e| {
+                error!("Token validation failed: {}", e);
+                AuthError::unauthorized(format!("Token validation failed: {}", e))
+            })?;
+
+            debug!("Token auth: username={}, groups={:?}", username, groups);
+
+            Ok(UserIdentity::new(username, auth_mode, groups))
+        },
+
+        AuthMode::Proxy => {
+            // Proxy mode - read username from configured header
+            let header_name = AuthMode::proxy_header();
+            let username = headers
+                .get(&header_name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| AuthError::unauthorized(format!("Missing {} header", header_name)))?
+                .to_string();
+            let groups = headers
+                .get(AuthMode::proxy_groups_header())
+                .and_then(|v| v.to_str().ok())
+                .map(parse_groups)
+                .unwrap_or_default();
+
+            debug!("Proxy auth: username={}, groups={:?}", username, groups);
+
+            Ok(UserIdentity::new(username, auth_mode, groups))
+        },
     }
 }
 
-/// Validate a Kubernetes token and extract the username
-async fn validate_token_and_get_username(token: &str) -> Result<String, String> {
+/// Groups from a comma-separated header value
+fn parse_groups(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|g| !g.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Validate a Kubernetes token and extract the username and groups
+pub(crate) async fn validate_token(token: &str) -> Result<(String, Vec<String>), String> {
     // Create a Kubernetes client using the operator's service account
     let client = Client::try_default()
         .await
@@ -189,7 +255,7 @@ async fn validate_token_and_get_username(token: &str) -> Result<String, String>
     let user_info = status.user.ok_or("TokenReview returned no user info")?;
     let username = user_info.username.ok_or("Username not found in token")?;
 
-    Ok(username)
+    Ok((username, user_info.groups.unwrap_or_default()))
 }
 
 /// Audit log entry for tracking user actions in the Web UI
@@ -0,0 +1,946 @@
+//! Read-only GraphQL endpoint for dashboards (`/api/v1/graphql`, enabled with
+//! HEADWIND_GRAPHQL_ENABLED).
+//!
+//! One query can fetch UpdateRequests, rollback history and the tracked resources with their
+//! policy, selecting only the fields a page needs. Queries are executed against the JSON form
+//! of the resources: a selection picks fields by their JSON name and unknown fields resolve to
+//! null. Supported are queries with aliases, arguments and variables; fragments, directives,
+//! mutations and subscriptions are not.
+
+use crate::controller::stores;
+use crate::helm::source;
+use crate::models::HelmRelease;
+use crate::models::crd::UpdateRequest;
+use crate::models::policy::annotations;
+use crate::policy::defaults::apply_policy_defaults;
+use crate::policy::flux::{FluxImagePolicy, apply_image_policies, list_image_policies};
+use crate::policy::targets::{apply_update_targets, list_update_targets};
+use crate::rollback::RollbackManager;
+use anyhow::{Context, Result, anyhow, bail};
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use k8s_openapi::NamespaceResourceScope;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use k8s_openapi::api::batch::v1::CronJob;
+use k8s_openapi::api::core::v1::PodSpec;
+use kube::api::ListParams;
+use kube::{Api, Client, Resource, ResourceExt};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use tracing::error;
+
+/// Longest query accepted, in bytes
+const MAX_QUERY_LENGTH: usize = 16 * 1024;
+
+/// Whether the GraphQL endpoint is served (HEADWIND_GRAPHQL_ENABLED, default: false)
+pub fn graphql_enabled() -> bool {
+    std::env::var("HEADWIND_GRAPHQL_ENABLED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// GraphQL request, as a JSON body or query parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQLRequest {
+    pub query: String,
+    #[serde(default)]
+    pub operation_name: Option<String>,
+    /// Variable values, a JSON object (a JSON-encoded string in query parameters)
+    #[serde(default)]
+    pub variables: Option<Value>,
+}
+
+/// GraphQL response
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct GraphQLResponse {
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<GraphQLError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GraphQLError {
+    pub message: String,
+    /// Response key of the root field that failed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<String>,
+}
+
+impl GraphQLResponse {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            data: None,
+            errors: vec![GraphQLError {
+                message: message.into(),
+                path: Vec::new(),
+            }],
+        }
+    }
+}
+
+/// `POST /api/v1/graphql`
+pub async fn graphql_post(Json(request): Json<GraphQLRequest>) -> Response {
+    respond(request).await
+}
+
+/// `GET /api/v1/graphql?query=...`
+pub async fn graphql_get(Query(params): Query<BTreeMap<String, String>>) -> Response {
+    let variables = match params.get("variables").filter(|v| !v.is_empty()) {
+        Some(v) => match serde_json::from_str(v) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(GraphQLResponse::error(format!("Invalid variables: {}", e))),
+                )
+                    .into_response();
+            },
+        },
+        None => None,
+    };
+    respond(GraphQLRequest {
+        query: params.get("query").cloned().unwrap_or_default(),
+        operation_name: params.get("operationName").cloned(),
+        variables,
+    })
+    .await
+}
+
+async fn respond(request: GraphQLRequest) -> Response {
+    let operation = match parse_request(&request) {
+        Ok(operation) => operation,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(GraphQLResponse::error(e.to_string())),
+            )
+                .into_response();
+        },
+    };
+
+    let client = match Client::try_default().await {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Failed to create Kubernetes client: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(GraphQLResponse::error(
+                    "Failed to connect to Kubernetes API",
+                )),
+            )
+                .into_response();
+        },
+    };
+
+    Json(execute(&client, &operation).await).into_response()
+}
+
+/// Field of a selection set
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    alias: Option<String>,
+    name: String,
+    arguments: BTreeMap<String, Value>,
+    selection: Vec<Field>,
+}
+
+impl Field {
+    /// Key of the field in the response
+    fn key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+
+    fn string_argument(&self, name: &str) -> Result<Option<String>> {
+        match self.arguments.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(other) => bail!(
+                "Argument '{}' of '{}' must be a string, got {}",
+                name,
+                self.name,
+                other
+            ),
+        }
+    }
+
+    fn required_argument(&self, name: &str) -> Result<String> {
+        self.string_argument(name)?
+            .ok_or_else(|| anyhow!("Argument '{}' of '{}' is required", name, self.name))
+    }
+}
+
+/// Parse the query of `request`, selecting its operation and substituting variables
+fn parse_request(request: &GraphQLRequest) -> Result<Vec<Field>> {
+    if request.query.trim().is_empty() {
+        bail!("No query given");
+    }
+    if request.query.len() > MAX_QUERY_LENGTH {
+        bail!("Query exceeds {} bytes", MAX_QUERY_LENGTH);
+    }
+    let variables = match &request.variables {
+        None | Some(Value::Null) => Map::new(),
+        Some(Value::Object(map)) => map.clone(),
+        Some(_) => bail!("Variables must be a JSON object"),
+    };
+
+    let operations = Parser::new(&request.query)?.document()?;
+    let operation = match &request.operation_name {
+        Some(name) => operations
+            .into_iter()
+            .find(|op| op.name.as_deref() == Some(name.as_str()))
+            .ok_or_else(|| anyhow!("Unknown operation '{}'", name))?,
+        None => {
+            let mut operations = operations;
+            if operations.len() != 1 {
+                bail!("The query has several operations, set operationName");
+            }
+            operations.remove(0)
+        },
+    };
+
+    let mut values = operation.defaults.clone();
+    values.extend(variables);
+    let mut selection = operation.selection;
+    for field in &mut selection {
+        substitute(field, &values)?;
+    }
+    Ok(selection)
+}
+
+/// Replace variable references in the arguments of `field` and its selection
+fn substitute(field: &mut Field, variables: &Map<String, Value>) -> Result<()> {
+    for value in field.arguments.values_mut() {
+        resolve_variables(value, variables)?;
+    }
+    for child in &mut field.selection {
+        substitute(child, variables)?;
+    }
+    Ok(())
+}
+
+/// Variables are parsed as `{"$var": name}` placeholders
+fn resolve_variables(value: &mut Value, variables: &Map<String, Value>) -> Result<()> {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(name)) = map.get("$var")
+                && map.len() == 1
+            {
+                *value = variables.get(name).cloned().unwrap_or(Value::Null);
+                return Ok(());
+            }
+            for v in map.values_mut() {
+                resolve_variables(v, variables)?;
+            }
+        },
+        Value::Array(items) => {
+            for v in items {
+                resolve_variables(v, variables)?;
+            }
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+/// Execute the root fields of an operation. Failing fields are null with an error.
+async fn execute(client: &Client, selection: &[Field]) -> GraphQLResponse {
+    let mut data = Map::new();
+    let mut errors = Vec::new();
+
+    for field in selection {
+        match resolve(client, field).await {
+            Ok(value) => {
+                data.insert(field.key().to_string(), project(value, &field.selection));
+            },
+            Err(e) => {
+                data.insert(field.key().to_string(), Value::Null);
+                errors.push(GraphQLError {
+                    message: format!("{:#}", e),
+                    path: vec![field.key().to_string()],
+                });
+            },
+        }
+    }
+
+    GraphQLResponse {
+        data: Some(Value::Object(data)),
+        errors,
+    }
+}
+
+/// Resolve a root field
+async fn resolve(client: &Client, field: &Field) -> Result<Value> {
+    match field.name.as_str() {
+        "__typename" => Ok(json!("Query")),
+        "updateRequests" => {
+            let namespace = field.string_argument("namespace")?;
+            let phase = field.string_argument("phase")?;
+            let kind = field.string_argument("kind")?;
+            let api: Api<UpdateRequest> = match &namespace {
+                Some(ns) => Api::namespaced(client.clone(), ns),
+                None => Api::all(client.clone()),
+            };
+            let list = api
+                .list(&ListParams::default())
+                .await
+                .context("Failed to list UpdateRequests")?;
+
+            let mut items = Vec::new();
+            for ur in list.items {
+                let matches_phase = phase.as_ref().is_none_or(|phase| {
+                    ur.status
+                        .as_ref()
+                        .is_some_and(|s| format!("{:?}", s.phase).eq_ignore_ascii_case(phase))
+                });
+                let matches_kind = kind
+                    .as_ref()
+                    .is_none_or(|kind| ur.spec.target_ref.kind.eq_ignore_ascii_case(kind));
+                if matches_phase && matches_kind {
+                    items.push(update_request_value(&ur)?);
+                }
+            }
+            Ok(Value::Array(items))
+        },
+        "updateRequest" => {
+            let namespace = field.required_argument("namespace")?;
+            let name = field.required_argument("name")?;
+            let api: Api<UpdateRequest> = Api::namespaced(client.clone(), &namespace);
+            match api
+                .get_opt(&name)
+                .await
+                .context("Failed to get UpdateRequest")?
+            {
+                Some(ur) => update_request_value(&ur),
+                None => Ok(Value::Null),
+            }
+        },
+        "rollbackHistory" => {
+            let namespace = field.required_argument("namespace")?;
+            let deployment = field.required_argument("deployment")?;
+            let container = field.string_argument("container")?;
+            let history = RollbackManager::new(client.clone())
+                .get_history(&deployment, &namespace)
+                .await?;
+            let entries: Vec<_> = history
+                .entries()
+                .iter()
+                .filter(|e| container.as_ref().is_none_or(|c| &e.container == c))
+                .collect();
+            Ok(serde_json::to_value(entries)?)
+        },
+        "trackedResources" => {
+            let namespace = field.string_argument("namespace")?;
+            let kind = field.string_argument("kind")?;
+            let mut resources = tracked_resources(client).await?;
+            resources.retain(|r| {
+                namespace.as_ref().is_none_or(|ns| r["namespace"] == *ns)
+                    && kind.as_ref().is_none_or(|kind| {
+                        r["kind"]
+                            .as_str()
+                            .is_some_and(|k| k.eq_ignore_ascii_case(kind))
+                    })
+            });
+            Ok(Value::Array(resources))
+        },
+        other => bail!("Unknown field '{}' on type Query", other),
+    }
+}
+
+/// JSON of an UpdateRequest, with `name` and `namespace` next to `metadata`
+fn update_request_value(ur: &UpdateRequest) -> Result<Value> {
+    let mut value = serde_json::to_value(ur)?;
+    value["name"] = json!(ur.name_any());
+    value["namespace"] = json!(ur.namespace().unwrap_or_default());
+    Ok(value)
+}
+
+/// Workloads and HelmReleases with a `headwind.sh/policy` other than none, with the policy
+/// from their annotations, UpdateTargets and Flux ImagePolicies
+async fn tracked_resources(client: &Client) -> Result<Vec<Value>> {
+    let targets = list_update_targets(client).await;
+    let image_policies = list_image_policies(client).await;
+
+    let mut resources = Vec::new();
+    tracked::<Deployment>(client, &targets, &image_policies, &mut resources, |d| {
+        d.spec.as_ref()?.template.spec.as_ref()
+    })
+    .await?;
+    tracked::<StatefulSet>(client, &targets, &image_policies, &mut resources, |s| {
+        s.spec.as_ref()?.template.spec.as_ref()
+    })
+    .await?;
+    tracked::<DaemonSet>(client, &targets, &image_policies, &mut resources, |d| {
+        d.spec.as_ref()?.template.spec.as_ref()
+    })
+    .await?;
+    tracked::<CronJob>(client, &targets, &image_policies, &mut resources, |c| {
+        c.spec
+            .as_ref()?
+            .job_template
+            .spec
+            .as_ref()?
+            .template
+            .spec
+            .as_ref()
+    })
+    .await?;
+
+    // HelmReleases need the Flux CRDs, which may not be installed
+    if let Ok(releases) = stores::list::<HelmRelease>(client).await {
+        for release in releases {
+            let Some(policy) = policy_value(release.annotations()) else {
+                continue;
+            };
+            let chart = &release.spec.chart.spec;
+            resources.push(json!({
+                "kind": "HelmRelease",
+                "namespace": release.namespace().unwrap_or_default(),
+                "name": release.name_any(),
+                "containers": [],
+                "chart": {
+                    "name": chart.chart,
+                    "version": chart.version,
+                    "source": {
+                        "kind": chart.source_ref.kind,
+                        "name": chart.source_ref.name,
+                        "namespace": chart.source_ref.namespace,
+                    },
+                    "versionsTracked": source::chart_versions_tracked(&release),
+                },
+                "policy": policy,
+            }));
+        }
+    }
+
+    Ok(resources)
+}
+
+async fn tracked<K>(
+    client: &Client,
+    targets: &[crate::models::updatetarget::UpdateTarget],
+    image_policies: &[FluxImagePolicy],
+    resources: &mut Vec<Value>,
+    pod_spec: impl Fn(&K) -> Option<&PodSpec> + Copy,
+) -> Result<()>
+where
+    K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+        + Clone
+        + DeserializeOwned
+        + Debug
+        + 'static,
+{
+    let kind = K::kind(&()).to_string();
+    let items = stores::list::<K>(client)
+        .await
+        .with_context(|| format!("Failed to list {}s", kind))?;
+
+    for mut resource in items {
+        apply_update_targets(&mut resource, targets);
+        apply_image_policies(&mut resource, pod_spec, image_policies);
+        apply_policy_defaults(&mut resource);
+        let Some(policy) = policy_value(resource.annotations()) else {
+            continue;
+        };
+        let containers: Vec<Value> = pod_spec(&resource)
+            .map(|spec| {
+                spec.containers
+                    .iter()
+                    .map(|c| json!({ "name": c.name, "image": c.image }))
+                    .collect()
+            })
+            .unwrap_or_default();
+        resources.push(json!({
+            "kind": kind,
+            "namespace": resource.namespace().unwrap_or_default(),
+            "name": resource.name_any(),
+            "containers": containers,
+            "chart": null,
+            "policy": policy,
+        }));
+    }
+    Ok(())
+}
+
+/// `headwind.sh/` annotations keyed by their camelCased name (`require-approval` becomes
+/// `requireApproval`), or None when the resource isn't tracked
+fn policy_value(resource_annotations: &BTreeMap<String, String>) -> Option<Value> {
+    match resource_annotations.get(annotations::POLICY) {
+        Some(p) if p != "none" => {},
+        _ => return None,
+    }
+    let policy: Map<String, Value> = resource_annotations
+        .iter()
+        .filter_map(|(key, value)| {
+            let name = key.strip_prefix("headwind.sh/")?;
+            Some((camel_case(name), json!(value)))
+        })
+        .collect();
+    Some(Value::Object(policy))
+}
+
+fn camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '-' || c == '.' || c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Keep the selected fields of `value`
+fn project(value: Value, selection: &[Field]) -> Value {
+    if selection.is_empty() {
+        return value;
+    }
+    match value {
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| project(item, selection))
+                .collect(),
+        ),
+        Value::Object(mut map) => {
+            let mut out = Map::new();
+            for field in selection {
+                let value = if field.name == "__typename" {
+                    json!("Object")
+                } else {
+                    let value = map.get(&field.name).cloned().unwrap_or(Value::Null);
+                    project(value, &field.selection)
+                };
+                out.insert(field.key().to_string(), value);
+            }
+            map.clear();
+            Value::Object(out)
+        },
+        scalar => scalar,
+    }
+}
+
+/// Operation of a GraphQL document
+#[derive(Debug)]
+struct Operation {
+    name: Option<String>,
+    /// Default values of the variables
+    defaults: Map<String, Value>,
+    selection: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Punct(char),
+    Str(String),
+    Number(String),
+    Spread,
+}
+
+/// Recursive-descent parser for executable GraphQL documents
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            pos: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unexpected end of query"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.next()? {
+            Token::Punct(p) if p == c => Ok(()),
+            other => bail!("Expected '{}', found {}", c, describe(&other)),
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Name(name) => Ok(name),
+            other => bail!("Expected a name, found {}", describe(&other)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Vec<Operation>> {
+        let mut operations = Vec::new();
+        while self.peek().is_some() {
+            operations.push(self.operation()?);
+        }
+        Ok(operations)
+    }
+
+    fn operation(&mut self) -> Result<Operation> {
+        let mut name = None;
+        let mut defaults = Map::new();
+
+        if let Some(Token::Name(keyword)) = self.peek().cloned() {
+            match keyword.as_str() {
+                "query" => self.pos += 1,
+                "mutation" | "subscription" => bail!("Only queries are supported"),
+                "fragment" => bail!("Fragments are not supported"),
+                other => bail!("Unexpected '{}'", other),
+            }
+            if let Some(Token::Name(_)) = self.peek() {
+                name = Some(self.name()?);
+            }
+            if self.eat('(') {
+                while !self.eat(')') {
+                    self.expect('$')?;
+                    let variable = self.name()?;
+                    self.expect(':')?;
+                    self.skip_type()?;
+                    if self.eat('=') {
+                        defaults.insert(variable, self.value()?);
+                    }
+                }
+            }
+        }
+
+        if self.peek() == Some(&Token::Punct('@')) {
+            bail!("Directives are not supported");
+        }
+        let selection = self.selection_set()?;
+        Ok(Operation {
+            name,
+            defaults,
+            selection,
+        })
+    }
+
+    /// Variable types are not checked
+    fn skip_type(&mut self) -> Result<()> {
+        if self.eat('[') {
+            self.skip_type()?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        self.eat('!');
+        Ok(())
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Field>> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        while !self.eat('}') {
+            if self.peek() == Some(&Token::Spread) {
+                bail!("Fragments are not supported");
+            }
+            fields.push(self.field()?);
+        }
+        if fields.is_empty() {
+            bail!("Empty selection set");
+        }
+        Ok(fields)
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        let first = self.name()?;
+        let (alias, name) = if self.eat(':') {
+            (Some(first), self.name()?)
+        } else {
+            (None, first)
+        };
+
+        let mut arguments = BTreeMap::new();
+        if self.eat('(') {
+            while !self.eat(')') {
+                let argument = self.name()?;
+                self.expect(':')?;
+                arguments.insert(argument, self.value()?);
+            }
+        }
+        if self.peek() == Some(&Token::Punct('@')) {
+            bail!("Directives are not supported");
+        }
+        let selection = if self.peek() == Some(&Token::Punct('{')) {
+            self.selection_set()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Field {
+            alias,
+            name,
+            arguments,
+            selection,
+        })
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.next()? {
+            Token::Punct('$') => Ok(json!({ "$var": self.name()? })),
+            Token::Str(s) => Ok(Value::String(s)),
+            Token::Number(n) => {
+                serde_json::from_str(&n).map_err(|_| anyhow!("Invalid number '{}'", n))
+            },
+            Token::Name(name) => Ok(match name.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                // Enum values are passed as strings
+                _ => Value::String(name),
+            }),
+            Token::Punct('[') => {
+                let mut items = Vec::new();
+                while !self.eat(']') {
+                    items.push(self.value()?);
+                }
+                Ok(Value::Array(items))
+            },
+            Token::Punct('{') => {
+                let mut map = Map::new();
+                while !self.eat('}') {
+                    let key = self.name()?;
+                    self.expect(':')?;
+                    map.insert(key, self.value()?);
+                }
+                Ok(Value::Object(map))
+            },
+            other => bail!("Expected a value, found {}", describe(&other)),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Name(name) => format!("'{}'", name),
+        Token::Punct(c) => format!("'{}'", c),
+        Token::Str(s) => format!("string \"{}\"", s),
+        Token::Number(n) => format!("number {}", n),
+        Token::Spread => "'...'".to_string(),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            // Commas are insignificant in GraphQL
+            c if c.is_whitespace() || c == ',' || c == '\u{feff}' => {},
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' || c == '\r' {
+                        break;
+                    }
+                }
+            },
+            '{' | '}' | '(' | ')' | '[' | ']' | ':' | '$' | '!' | '=' | '@' => {
+                tokens.push(Token::Punct(c))
+            },
+            '.' => {
+                if chars.next() == Some('.') && chars.next() == Some('.') {
+                    tokens.push(Token::Spread);
+                } else {
+                    bail!("Unexpected '.'");
+                }
+            },
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        None | Some('\n') => bail!("Unterminated string"),
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some('r') => s.push('\r'),
+                            Some('u') => {
+                                let hex: String = chars.by_ref().take(4).collect();
+                                let code = u32::from_str_radix(&hex, 16)
+                                    .ok()
+                                    .and_then(char::from_u32)
+                                    .ok_or_else(|| anyhow!("Invalid escape \\u{}", hex))?;
+                                s.push(code);
+                            },
+                            Some(c @ ('"' | '\\' | '/')) => s.push(c),
+                            Some('b') => s.push('\u{8}'),
+                            Some('f') => s.push('\u{c}'),
+                            other => bail!("Invalid escape \\{}", other.unwrap_or(' ')),
+                        },
+                        Some(c) => s.push(c),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            },
+            c if c == '-' || c.is_ascii_digit() => {
+                let mut n = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-') {
+                        n.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Number(n));
+            },
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let mut name = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c == '_' || c.is_ascii_alphanumeric() {
+                        name.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Name(name));
+            },
+            other => bail!("Unexpected character '{}'", other),
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str, variables: Option<Value>) -> Result<Vec<Field>> {
+        parse_request(&GraphQLRequest {
+            query: query.to_string(),
+            operation_name: None,
+            variables,
+        })
+    }
+
+    #[test]
+    fn test_parse_fields_aliases_and_arguments() {
+        let fields = parse(
+            r#"{
+                pending: updateRequests(phase: Pending, namespace: "prod") { name spec { newImage } }
+                # comment
+                trackedResources { kind, name }
+            }"#,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].key(), "pending");
+        assert_eq!(fields[0].name, "updateRequests");
+        assert_eq!(fields[0].arguments["phase"], json!("Pending"));
+        assert_eq!(fields[0].arguments["namespace"], json!("prod"));
+        assert_eq!(fields[0].selection[1].name, "spec");
+        assert_eq!(fields[0].selection[1].selection[0].name, "newImage");
+        assert_eq!(fields[1].key(), "trackedResources");
+    }
+
+    #[test]
+    fn test_variables_and_defaults() {
+        let fields = parse(
+            r#"query History($ns: String!, $deployment: String = "web") {
+                rollbackHistory(namespace: $ns, deployment: $deployment) { image }
+            }"#,
+            Some(json!({ "ns": "prod" })),
+        )
+        .unwrap();
+        assert_eq!(fields[0].arguments["namespace"], json!("prod"));
+        assert_eq!(fields[0].arguments["deployment"], json!("web"));
+    }
+
+    #[test]
+    fn test_operation_name_selects_operation() {
+        let request = GraphQLRequest {
+            query: "query A { a } query B { b }".to_string(),
+            operation_name: Some("B".to_string()),
+            variables: None,
+        };
+        assert_eq!(parse_request(&request).unwrap()[0].name, "b");
+
+        let err = parse("query A { a } query B { b }", None).unwrap_err();
+        assert!(err.to_string().contains("operationName"));
+    }
+
+    #[test]
+    fn test_unsupported_syntax() {
+        assert!(parse("mutation { approve }", None).is_err());
+        assert!(parse("{ ...F } fragment F on Query { a }", None).is_err());
+        assert!(parse("{ a @skip(if: true) }", None).is_err());
+        assert!(parse("{ a ", None).is_err());
+        assert!(parse("{ }", None).is_err());
+        assert!(parse("", None).is_err());
+        assert!(parse("{ a(x: \"unterminated) }", None).is_err());
+    }
+
+    #[test]
+    fn test_project() {
+        let fields = parse("{ items { name spec { newImage } missing } }", None).unwrap();
+        let value = json!([
+            { "name": "a", "namespace": "x", "spec": { "newImage": "nginx:1.2", "currentImage": "nginx:1.1" } },
+            { "name": "b", "spec": null },
+        ]);
+        assert_eq!(
+            project(value, &fields[0].selection),
+            json!([
+                { "name": "a", "spec": { "newImage": "nginx:1.2" }, "missing": null },
+                { "name": "b", "spec": null, "missing": null },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_policy_value() {
+        let mut resource_annotations = BTreeMap::new();
+        resource_annotations.insert(annotations::POLICY.to_string(), "minor".to_string());
+        resource_annotations.insert(
+            annotations::REQUIRE_APPROVAL.to_string(),
+            "false".to_string(),
+        );
+        resource_annotations.insert("app.kubernetes.io/name".to_string(), "web".to_string());
+
+        assert_eq!(
+            policy_value(&resource_annotations),
+            Some(json!({ "policy": "minor", "requireApproval": "false" }))
+        );
+
+        resource_annotations.insert(annotations::POLICY.to_string(), "none".to_string());
+        assert_eq!(policy_value(&resource_annotations), None);
+    }
+}
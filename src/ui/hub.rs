@@ -0,0 +1,102 @@
+//! Hub mode of the Web UI.
+//!
+//! A hub lists the Headwind deployments of other clusters in `HEADWIND_HUB_AGENTS`. Their
+//! UpdateRequests are fetched from each agent's approval API and shown on the dashboard next
+//! to the hub's own, labelled with the cluster; approvals, rejections and detail pages of them
+//! take a `cluster` query parameter and are forwarded to that agent.
+
+use crate::config::cluster::{self, Agent};
+use crate::models::crd::UpdateRequest;
+use anyhow::{Context, Result};
+use futures::future::join_all;
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::warn;
+
+/// Longest wait for an agent's approval API
+const AGENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `cluster` query parameter of UI routes acting on an UpdateRequest
+#[derive(Debug, Default, Deserialize, schemars::JsonSchema)]
+pub struct ClusterQuery {
+    /// Cluster of the UpdateRequest, for UpdateRequests of a hub's agents
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+/// Base URL of the approval API managing UpdateRequests of `cluster`: the agent's for a
+/// cluster this hub manages, otherwise this instance's own
+pub fn approval_api_url(cluster: Option<&str>) -> String {
+    match cluster.and_then(cluster::agent) {
+        Some(agent) => agent.api_url.clone(),
+        None => crate::server::approval_api_url(),
+    }
+}
+
+/// HTTP client for approval API requests about UpdateRequests of `cluster`
+pub fn client(cluster: Option<&str>) -> reqwest::Client {
+    match cluster.and_then(cluster::agent) {
+        Some(_) => reqwest::Client::builder()
+            .timeout(AGENT_TIMEOUT)
+            .build()
+            .unwrap_or_default(),
+        None => crate::server::local_client(),
+    }
+}
+
+/// UpdateRequests of an agent's cluster
+pub async fn list_update_requests(agent: &Agent) -> Result<Vec<UpdateRequest>> {
+    client(Some(&agent.name))
+        .get(format!("{}/api/v1/updates", agent.api_url))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("Invalid UpdateRequest list from cluster {}", agent.name))
+}
+
+/// An UpdateRequest of an agent's cluster
+pub async fn get_update_request(
+    agent: &Agent,
+    namespace: &str,
+    name: &str,
+) -> Result<UpdateRequest> {
+    client(Some(&agent.name))
+        .get(format!(
+            "{}/api/v1/updates/{}/{}",
+            agent.api_url, namespace, name
+        ))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("Invalid UpdateRequest from cluster {}", agent.name))
+}
+
+/// UpdateRequests of every agent with their cluster, and the clusters that couldn't be reached
+pub async fn agent_update_requests() -> (Vec<(String, UpdateRequest)>, Vec<String>) {
+    let agents = cluster::agents();
+    let results = join_all(agents.iter().map(list_update_requests)).await;
+
+    let mut update_requests = Vec::new();
+    let mut unreachable = Vec::new();
+    for (agent, result) in agents.iter().zip(results) {
+        match result {
+            Ok(items) => update_requests.extend(
+                items
+                    .into_iter()
+                    .map(|update_request| (agent.name.clone(), update_request)),
+            ),
+            Err(e) => {
+                warn!(
+                    "Failed to list UpdateRequests of cluster {}: {:#}",
+                    agent.name, e
+                );
+                unreachable.push(agent.name.clone());
+            },
+        }
+    }
+    (update_requests, unreachable)
+}
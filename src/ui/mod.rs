@@ -6,26 +6,26 @@ use std::net::SocketAddr;
 use tracing::info;
 
 pub mod auth;
+pub mod graphql;
+pub mod hub;
 pub mod routes;
 pub mod static_files;
 pub mod templates;
 
 /// Start the Web UI server
-pub async fn start_ui_server() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn start_ui_server(addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
     let app = create_router();
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8082));
     info!("Starting Web UI server on {}", addr);
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    crate::server::serve(addr, app).await?;
 
     Ok(())
 }
 
 /// Create the Axum router for the Web UI
-fn create_router() -> Router {
-    Router::new()
+pub fn create_router() -> Router {
+    let router = Router::new()
         // Serve embedded static files (CSS, JS, images)
         .route("/static/{*path}", get(static_files::serve_static))
         // Health check endpoint
@@ -36,11 +36,14 @@ fn create_router() -> Router {
         .route("/settings", get(routes::settings_page))
         // Observability page
         .route("/observability", get(routes::observability_page))
+        // Audit trail page
+        .route("/audit", get(routes::audit_page))
         // Individual update request detail view
         .route("/updates/{namespace}/{name}", get(routes::update_detail))
         // Settings API endpoints
         .route("/api/v1/settings", get(routes::get_settings))
         .route("/api/v1/settings", put(routes::update_settings))
+        .route("/api/v1/settings/validate", get(routes::validate_settings))
         .route(
             "/api/v1/settings/test-notification",
             post(routes::test_notification),
@@ -51,6 +54,12 @@ fn create_router() -> Router {
             "/api/v1/metrics/timeseries/{metric_name}",
             get(routes::get_metrics_timeseries),
         )
+        .route(
+            "/api/v1/polling/last-cycle",
+            get(routes::get_last_poll_cycle),
+        )
+        // Debug endpoints
+        .route("/api/v1/debug/caches", get(routes::get_cache_stats))
         // UpdateRequest API endpoint for counts
         .route("/api/v1/updates", get(routes::list_update_requests))
         // Individual approve/reject operations
@@ -62,9 +71,29 @@ fn create_router() -> Router {
             "/api/v1/updates/{namespace}/{name}/reject",
             post(routes::reject_update),
         )
+        // Revert a Deployment container to its last known good image
+        .route(
+            "/api/v1/rollback/{namespace}/{deployment}/last-good",
+            post(routes::rollback_to_last_good),
+        )
         // Bulk operations
         .route("/api/v1/updates/bulk/approve", post(routes::bulk_approve))
         .route("/api/v1/updates/bulk/reject", post(routes::bulk_reject))
         // Real-time updates via Server-Sent Events
         .route("/api/v1/events/updates", get(routes::updates_stream))
+        // API description
+        .route(
+            crate::openapi::OPENAPI_PATH,
+            get(crate::openapi::serve_ui_api),
+        );
+
+    // Optional GraphQL endpoint for dashboards
+    if graphql::graphql_enabled() {
+        router.route(
+            "/api/v1/graphql",
+            get(graphql::graphql_get).post(graphql::graphql_post),
+        )
+    } else {
+        router
+    }
 }
@@ -1,7 +1,7 @@
 use axum::{
     Form,
     extract::{Path, Query},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{
         IntoResponse, Json,
         sse::{Event, Sse},
@@ -10,15 +10,21 @@ use axum::{
 use chrono::{DateTime, Duration, Utc};
 use futures::StreamExt as FuturesStreamExt;
 use futures::stream::Stream;
-use kube::{Api, Client};
+use kube::{Api, Client, ResourceExt};
 use serde::Deserialize;
 use tracing::{error, info};
 
+use crate::audit::AuditQuery;
 use crate::config::HeadwindConfig;
+use crate::config::cluster;
+use crate::metrics::client::SeriesKind;
+use crate::models::audit::AuditOutcome;
 use crate::models::crd::UpdateRequest;
-use crate::ui::auth::{AuditLogEntry, UserIdentity};
+use crate::server;
+use crate::ui::auth::{self, AuditLogEntry, AuthMode, UserIdentity};
 
-use super::templates::{self, UpdateRequestView};
+use super::hub::{self, ClusterQuery};
+use super::templates::{self, AuditEntryView, UpdateRequestView};
 
 /// Health check endpoint for the Web UI
 /// Returns 200 OK if the UI server is running and can connect to Kubernetes API
@@ -37,7 +43,7 @@ pub async fn health_check() -> impl IntoResponse {
 }
 
 /// Dashboard route - main page showing all update requests
-pub async fn dashboard() -> impl IntoResponse {
+pub async fn dashboard(headers: HeaderMap) -> impl IntoResponse {
     info!("Rendering dashboard");
 
     // Get Kubernetes client
@@ -56,27 +62,65 @@ pub async fn dashboard() -> impl IntoResponse {
             Vec::new()
         });
 
+    // A hub also shows the UpdateRequests of its agents' clusters
+    let (agent_update_requests, unreachable_clusters) = hub::agent_update_requests().await;
+    let views =
+        update_requests
+            .iter()
+            .map(convert_to_view)
+            .chain(agent_update_requests.iter().map(|(cluster, ur)| {
+                let mut view = convert_to_view(ur);
+                view.cluster = Some(cluster.clone());
+                view
+            }));
+
     // Convert UpdateRequests to view models
     let mut pending_updates = Vec::new();
     let mut completed_updates = Vec::new();
 
-    for ur in update_requests {
-        let view = convert_to_view(&ur);
-
+    for view in views {
         match view.status.as_str() {
             "Pending" => pending_updates.push(view),
-            "Completed" | "Rejected" | "Failed" => completed_updates.push(view),
+            "Completed" | "Rejected" | "Failed" | "Expired" => completed_updates.push(view),
             _ => pending_updates.push(view), // Default to pending
         }
     }
 
-    templates::dashboard(&pending_updates, &completed_updates)
+    let can_approve = auth::page_can_approve(&headers).await;
+    templates::dashboard(
+        &pending_updates,
+        &completed_updates,
+        &unreachable_clusters,
+        can_approve,
+    )
 }
 
 /// Update detail route - show individual update request
-pub async fn update_detail(Path((namespace, name)): Path<(String, String)>) -> impl IntoResponse {
+pub async fn update_detail(
+    headers: HeaderMap,
+    Path((namespace, name)): Path<(String, String)>,
+    Query(target): Query<ClusterQuery>,
+) -> impl IntoResponse {
     info!("Rendering detail view for {}/{}", namespace, name);
 
+    // UpdateRequests of a hub's agents are fetched from the agent
+    if let Some(agent) = target.cluster.as_deref().and_then(cluster::agent) {
+        let update_request = hub::get_update_request(agent, &namespace, &name)
+            .await
+            .unwrap_or_else(|e| {
+                error!(
+                    "Failed to get UpdateRequest {}/{} of cluster {}: {:#}",
+                    namespace, name, agent.name, e
+                );
+                panic!("UpdateRequest not found");
+            });
+        let mut view = convert_to_view(&update_request);
+        view.cluster = Some(agent.name.clone());
+
+        let can_approve = auth::page_can_approve(&headers).await;
+        return templates::detail(&view, can_approve);
+    }
+
     // Get Kubernetes client
     let client = Client::try_default()
         .await
@@ -91,7 +135,8 @@ pub async fn update_detail(Path((namespace, name)): Path<(String, String)>) -> i
 
     let view = convert_to_view(&update_request);
 
-    templates::detail(&view)
+    let can_approve = auth::page_can_approve(&headers).await;
+    templates::detail(&view, can_approve)
 }
 
 /// Convert UpdateRequest CRD to view model
@@ -101,7 +146,11 @@ fn convert_to_view(ur: &UpdateRequest) -> UpdateRequestView {
     let status = ur.status.as_ref();
 
     // Extract current and new versions from images
-    let (current_version, new_version) = extract_versions(&spec.current_image, &spec.new_image);
+    let (current_version, mut new_version) = extract_versions(&spec.current_image, &spec.new_image);
+    // Channel tags read better with the version they stand for: `stable@sha256:... (1.4.2)`
+    if let Some(resolved) = &spec.resolved_version {
+        new_version = format!("{} ({})", new_version, resolved);
+    }
 
     UpdateRequestView {
         name: metadata.name.clone().unwrap_or_default(),
@@ -124,6 +173,24 @@ fn convert_to_view(ur: &UpdateRequest) -> UpdateRequestView {
         approved_by: status.and_then(|s| s.approved_by.clone()),
         rejected_by: status.and_then(|s| s.rejected_by.clone()),
         rejection_reason: status.and_then(|s| s.message.clone()),
+        approval_progress: spec
+            .required_approvals
+            .filter(|required| *required > 1)
+            .map(|required| {
+                let approvals = status.map(|s| s.approvals.len()).unwrap_or(0);
+                format!("{}/{} approvals", approvals, required)
+            }),
+        release_notes: status.and_then(|s| s.release_notes.clone()),
+        image_metadata: status.and_then(|s| s.image_metadata.clone()),
+        vulnerability_scan: status.and_then(|s| s.vulnerability_scan.clone()),
+        rollout: status.and_then(|s| s.rollout.clone()),
+        container: spec.container_name.clone(),
+        source: spec.source.clone(),
+        cluster: ur
+            .labels()
+            .get(cluster::CLUSTER_LABEL)
+            .cloned()
+            .or_else(|| cluster::name().map(str::to_string)),
     }
 }
 
@@ -183,6 +250,37 @@ pub async fn get_settings() -> impl IntoResponse {
     }
 }
 
+/// Validate the current ConfigMap, Secret and environment configuration
+pub async fn validate_settings() -> impl IntoResponse {
+    let client = match Client::try_default().await {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Failed to create Kubernetes client: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Failed to connect to Kubernetes API"
+                })),
+            )
+                .into_response();
+        },
+    };
+
+    match crate::config::validation::validate_current(client).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            error!("Failed to load configuration: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": format!("Failed to load configuration: {}", e)
+                })),
+            )
+                .into_response()
+        },
+    }
+}
+
 /// Update settings in ConfigMap and Secret
 pub async fn update_settings(Json(config): Json<HeadwindConfig>) -> impl IntoResponse {
     info!("Updating Headwind settings");
@@ -328,6 +426,7 @@ pub async fn test_notification(Json(payload): Json<serde_json::Value>) -> impl I
             let teams_config = TeamsConfig {
                 enabled: config.notifications.teams.enabled,
                 webhook_url: config.notifications.teams.webhook_url.clone(),
+                action_secret: crate::notifications::teams_action_secret(),
             };
 
             match TeamsNotifier::new(teams_config) {
@@ -419,54 +518,54 @@ pub async fn observability_page() -> impl IntoResponse {
     templates::observability()
 }
 
-/// Get metrics data for dashboard
-pub async fn get_metrics_data() -> impl IntoResponse {
-    use crate::metrics::client::create_metrics_client;
+/// Audit trail page, filtered by query parameters
+pub async fn audit_page(Query(query): Query<AuditQuery>) -> impl IntoResponse {
+    info!("Rendering audit page");
 
-    info!("Fetching metrics data");
+    let client = Client::try_default()
+        .await
+        .expect("Failed to create Kubernetes client");
 
-    let client = match Client::try_default().await {
-        Ok(c) => c,
-        Err(e) => {
-            error!("Failed to create Kubernetes client: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Failed to connect to Kubernetes API"
-                })),
-            )
-                .into_response();
-        },
-    };
+    let records = crate::audit::list_audit_records(&client, &query)
+        .await
+        .unwrap_or_else(|e| {
+            error!("Failed to list audit records: {}", e);
+            Vec::new()
+        });
+    let entries: Vec<AuditEntryView> = records
+        .iter()
+        .map(|record| {
+            let spec = &record.spec;
+            AuditEntryView {
+                timestamp: spec.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+                action: spec.action.as_str().to_string(),
+                resource_kind: spec.target_ref.kind.clone(),
+                resource_name: spec.target_ref.name.clone(),
+                namespace: spec.target_ref.namespace.clone(),
+                current_image: spec.current_image.clone(),
+                new_image: spec.new_image.clone(),
+                actor: spec.actor.clone(),
+                source: spec.source.clone(),
+                success: spec.outcome == AuditOutcome::Success,
+                update_request: spec.update_request.clone(),
+                message: spec.message.clone(),
+            }
+        })
+        .collect();
+
+    templates::audit(
+        &entries,
+        query.namespace.as_deref(),
+        query.action.map(|a| a.as_str()),
+        query.target.as_deref(),
+    )
+}
 
-    let config = match HeadwindConfig::load(client).await {
-        Ok(c) => c,
-        Err(e) => {
-            error!("Failed to load configuration: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": format!("Failed to load configuration: {}", e)
-                })),
-            )
-                .into_response();
-        },
-    };
+/// Get metrics data for dashboard
+pub async fn get_metrics_data() -> impl IntoResponse {
+    info!("Fetching metrics data");
 
-    // Create metrics client
-    let metrics_client = create_metrics_client(
-        &config.observability.metrics_backend,
-        config.observability.prometheus.url.clone(),
-        config.observability.prometheus.enabled,
-        config.observability.victoriametrics.url.clone(),
-        config.observability.victoriametrics.enabled,
-        config.observability.influxdb.url.clone(),
-        config.observability.influxdb.enabled,
-        config.observability.influxdb.org.clone(),
-        config.observability.influxdb.bucket.clone(),
-        config.observability.influxdb.token.clone(),
-    )
-    .await;
+    let metrics_client = crate::metrics::client::configured_client().await;
 
     // Query key metrics
     let mut metrics = serde_json::Map::new();
@@ -485,11 +584,13 @@ pub async fn get_metrics_data() -> impl IntoResponse {
         ("deployments_watched", "headwind_deployments_watched"),
         ("statefulsets_watched", "headwind_statefulsets_watched"),
         ("daemonsets_watched", "headwind_daemonsets_watched"),
+        ("cronjobs_watched", "headwind_cronjobs_watched"),
         ("helm_releases_watched", "headwind_helm_releases_watched"),
     ];
 
-    for (key, query) in metric_queries {
-        match metrics_client.query_instant(query).await {
+    for (key, metric) in metric_queries {
+        let query = metrics_client.total_query(metric);
+        match metrics_client.query_instant(&query).await {
             Ok(value) => {
                 metrics.insert(key.to_string(), serde_json::json!(value.value));
             },
@@ -503,60 +604,39 @@ pub async fn get_metrics_data() -> impl IntoResponse {
     (StatusCode::OK, Json(metrics)).into_response()
 }
 
+/// Query parameters of metric time series
+#[derive(Debug, Default, Deserialize, schemars::JsonSchema)]
+pub struct TimeseriesQuery {
+    /// Time range: `1h`, `6h` (default), `24h`, `7d` or `30d`
+    #[serde(default)]
+    pub range: Option<String>,
+    /// `value` (default) adds up the metric's series, `increase` their increase per step
+    #[serde(default)]
+    pub series: SeriesKind,
+}
+
 /// Get metrics time series for charts
 pub async fn get_metrics_timeseries(
     Path(metric_name): Path<String>,
-    Query(params): Query<std::collections::HashMap<String, String>>,
+    Query(params): Query<TimeseriesQuery>,
 ) -> impl IntoResponse {
-    use crate::metrics::client::create_metrics_client;
-
     info!("Fetching time series for metric: {}", metric_name);
 
-    let client = match Client::try_default().await {
-        Ok(c) => c,
-        Err(e) => {
-            error!("Failed to create Kubernetes client: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": "Failed to connect to Kubernetes API"
-                })),
-            )
-                .into_response();
-        },
-    };
-
-    let config = match HeadwindConfig::load(client).await {
-        Ok(c) => c,
-        Err(e) => {
-            error!("Failed to load configuration: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({
-                    "error": format!("Failed to load configuration: {}", e)
-                })),
-            )
-                .into_response();
-        },
-    };
+    // The name ends up in a PromQL or Flux query, so only Headwind's metrics are allowed
+    if !is_headwind_metric(&metric_name) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("Unknown metric: {}", metric_name)
+            })),
+        )
+            .into_response();
+    }
 
-    // Create metrics client
-    let metrics_client = create_metrics_client(
-        &config.observability.metrics_backend,
-        config.observability.prometheus.url.clone(),
-        config.observability.prometheus.enabled,
-        config.observability.victoriametrics.url.clone(),
-        config.observability.victoriametrics.enabled,
-        config.observability.influxdb.url.clone(),
-        config.observability.influxdb.enabled,
-        config.observability.influxdb.org.clone(),
-        config.observability.influxdb.bucket.clone(),
-        config.observability.influxdb.token.clone(),
-    )
-    .await;
+    let metrics_client = crate::metrics::client::configured_client().await;
 
     // Parse time range from query parameter (default: 6h)
-    let time_range = params.get("range").map(|s| s.as_str()).unwrap_or("6h");
+    let time_range = params.range.as_deref().unwrap_or("6h");
     let end = Utc::now();
     let (start, step) = match time_range {
         "1h" => (end - Duration::hours(1), "1m"),
@@ -568,12 +648,13 @@ pub async fn get_metrics_timeseries(
     };
 
     match metrics_client
-        .query_range(&metric_name, start, end, step)
+        .query_series(&metric_name, params.series, start, end, step)
         .await
     {
         Ok(points) => {
             // Fill in missing time intervals with zeros for better visualization
-            let filled_points = fill_missing_intervals(points, start, end, time_range);
+            let filled_points =
+                fill_missing_intervals(points, start, end, time_range, params.series);
             (StatusCode::OK, Json(filled_points)).into_response()
         },
         Err(e) => {
@@ -589,12 +670,52 @@ pub async fn get_metrics_timeseries(
     }
 }
 
+/// Whether `name` is a Headwind metric name (`headwind_` followed by `[a-z0-9_]`)
+fn is_headwind_metric(name: &str) -> bool {
+    name.strip_prefix("headwind_").is_some_and(|rest| {
+        !rest.is_empty()
+            && rest
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    })
+}
+
+/// Get the summary of the most recently completed registry poll cycle
+pub async fn get_last_poll_cycle() -> impl IntoResponse {
+    match crate::polling::last_cycle_summary() {
+        Some(summary) => (StatusCode::OK, Json(summary)).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "No poll cycle has completed yet (polling may be disabled)"
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Summarize the internal caches (entry counts, limits, evictions)
+pub async fn get_cache_stats() -> impl IntoResponse {
+    let caches = crate::cache::all_cache_stats().await;
+    let total_entries: usize = caches.iter().map(|c| c.entries).sum();
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "totalEntries": total_entries,
+            "caches": caches,
+        })),
+    )
+        .into_response()
+}
+
 /// Fill in missing time intervals with zero values for better chart visualization
 fn fill_missing_intervals(
     points: Vec<crate::metrics::client::MetricPoint>,
     start: DateTime<Utc>,
     end: DateTime<Utc>,
     time_range: &str,
+    series: SeriesKind,
 ) -> Vec<crate::metrics::client::MetricPoint> {
     use crate::metrics::client::MetricPoint;
     use std::collections::HashMap;
@@ -611,10 +732,13 @@ fn fill_missing_intervals(
     let mut data_map: HashMap<String, f64> = HashMap::new();
     for point in &points {
         let date_key = point.timestamp.format("%Y-%m-%d").to_string();
-        // Sum values for the same day (or take max, depending on metric type)
+        // Increases add up to the day's total, values keep the day's peak
         data_map
             .entry(date_key)
-            .and_modify(|v| *v = v.max(point.value))
+            .and_modify(|v| match series {
+                SeriesKind::Increase => *v += point.value,
+                SeriesKind::Value => *v = v.max(point.value),
+            })
             .or_insert(point.value);
     }
 
@@ -690,19 +814,22 @@ pub async fn list_update_requests() -> impl IntoResponse {
 }
 
 /// Bulk approve updates
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, schemars::JsonSchema)]
 pub struct BulkApproveRequest {
     updates: Vec<UpdateIdentifier>,
     approver: String,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, schemars::JsonSchema)]
 pub struct UpdateIdentifier {
     namespace: String,
     name: String,
+    /// Cluster of the UpdateRequest, for UpdateRequests of a hub's agents
+    #[serde(default)]
+    cluster: Option<String>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, schemars::JsonSchema)]
 pub struct BulkOperationResponse {
     success_count: usize,
     failed_count: usize,
@@ -713,6 +840,17 @@ pub async fn bulk_approve(
     user: UserIdentity,
     Json(request): Json<BulkApproveRequest>,
 ) -> impl IntoResponse {
+    for update in &request.updates {
+        if let Err(denied) = require_approver(
+            &user,
+            "bulk_approve",
+            "UpdateRequest",
+            &update.namespace,
+            &update.name,
+        ) {
+            return denied.into_response();
+        }
+    }
     info!(
         "Bulk approving {} updates by {}",
         request.updates.len(),
@@ -725,16 +863,19 @@ pub async fn bulk_approve(
 
     for update in &request.updates {
         // Call the approval API endpoint for each update
+        let cluster = update.cluster.as_deref();
         let approval_url = format!(
-            "http://localhost:8081/api/v1/updates/{}/{}/approve",
-            update.namespace, update.name
+            "{}/api/v1/updates/{}/{}/approve",
+            hub::approval_api_url(cluster),
+            update.namespace,
+            update.name
         );
 
         let approve_payload = serde_json::json!({
-            "approver": request.approver
+            "approver": pinned_approver(&user, Some(request.approver.clone()))
         });
 
-        match reqwest::Client::new()
+        match hub::client(cluster)
             .post(&approval_url)
             .json(&approve_payload)
             .send()
@@ -817,7 +958,7 @@ pub async fn bulk_approve(
 }
 
 /// Bulk reject updates
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, schemars::JsonSchema)]
 pub struct BulkRejectRequest {
     updates: Vec<UpdateIdentifier>,
     reason: String,
@@ -827,6 +968,17 @@ pub async fn bulk_reject(
     user: UserIdentity,
     Json(request): Json<BulkRejectRequest>,
 ) -> impl IntoResponse {
+    for update in &request.updates {
+        if let Err(denied) = require_approver(
+            &user,
+            "bulk_reject",
+            "UpdateRequest",
+            &update.namespace,
+            &update.name,
+        ) {
+            return denied.into_response();
+        }
+    }
     info!(
         "Bulk rejecting {} updates by {} with reason: {}",
         request.updates.len(),
@@ -840,16 +992,20 @@ pub async fn bulk_reject(
 
     for update in &request.updates {
         // Call the approval API endpoint for each update
+        let cluster = update.cluster.as_deref();
         let reject_url = format!(
-            "http://localhost:8081/api/v1/updates/{}/{}/reject",
-            update.namespace, update.name
+            "{}/api/v1/updates/{}/{}/reject",
+            hub::approval_api_url(cluster),
+            update.namespace,
+            update.name
         );
 
         let reject_payload = serde_json::json!({
+            "approver": pinned_approver(&user, None),
             "reason": request.reason
         });
 
-        match reqwest::Client::new()
+        match hub::client(cluster)
             .post(&reject_url)
             .json(&reject_payload)
             .send()
@@ -1042,30 +1198,75 @@ pub async fn updates_stream() -> Sse<impl Stream<Item = Result<Event, anyhow::Er
     )
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, schemars::JsonSchema)]
 pub struct ApprovalForm {
     approver: Option<String>,
+    expected_new_image: Option<String>,
+    expected_current_image: Option<String>,
+}
+
+/// Reject approver actions of viewers, logging the denied attempt
+fn require_approver(
+    user: &UserIdentity,
+    action: &str,
+    resource_type: &str,
+    namespace: &str,
+    name: &str,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    user.require_approver().map_err(|e| {
+        AuditLogEntry::new(
+            user.username.clone(),
+            action.to_string(),
+            resource_type.to_string(),
+            namespace.to_string(),
+            name.to_string(),
+            "failed".to_string(),
+            Some(e.error.clone()),
+        )
+        .log();
+        (e.status, Json(serde_json::json!({ "error": e.error })))
+    })
+}
+
+/// Approver to send to the approval API. Authenticated identities can't be overridden by the
+/// client, since updates that need several approvers count distinct approvers.
+fn pinned_approver(user: &UserIdentity, claimed: Option<String>) -> Option<String> {
+    match user.auth_mode {
+        AuthMode::Token | AuthMode::Proxy => Some(user.username.clone()),
+        AuthMode::None | AuthMode::Simple => claimed,
+    }
 }
 
 /// Approve an individual update request (proxy to approval API)
 pub async fn approve_update(
     user: UserIdentity,
     Path((namespace, name)): Path<(String, String)>,
+    Query(target): Query<ClusterQuery>,
     Form(form): Form<ApprovalForm>,
 ) -> impl IntoResponse {
+    if let Err(denied) = require_approver(&user, "approve", "UpdateRequest", &namespace, &name) {
+        return denied;
+    }
     let approver = user.username.clone();
 
+    let cluster = target.cluster.as_deref();
     let approval_url = format!(
-        "http://localhost:8081/api/v1/updates/{}/{}/approve",
-        namespace, name
+        "{}/api/v1/updates/{}/{}/approve",
+        hub::approval_api_url(cluster),
+        namespace,
+        name
     );
 
+    let form_approver = pinned_approver(&user, form.approver);
+
     // Convert form to JSON for the approval API
     let json_body = serde_json::json!({
-        "approver": form.approver
+        "approver": form_approver,
+        "expected_new_image": form.expected_new_image,
+        "expected_current_image": form.expected_current_image
     });
 
-    match reqwest::Client::new()
+    match hub::client(cluster)
         .post(&approval_url)
         .json(&json_body)
         .send()
@@ -1130,7 +1331,7 @@ pub async fn approve_update(
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, schemars::JsonSchema)]
 pub struct RejectionForm {
     approver: Option<String>,
     reason: Option<String>,
@@ -1140,23 +1341,30 @@ pub struct RejectionForm {
 pub async fn reject_update(
     user: UserIdentity,
     Path((namespace, name)): Path<(String, String)>,
+    Query(target): Query<ClusterQuery>,
     Form(form): Form<RejectionForm>,
 ) -> impl IntoResponse {
+    if let Err(denied) = require_approver(&user, "reject", "UpdateRequest", &namespace, &name) {
+        return denied;
+    }
     let approver = user.username.clone();
     let rejection_reason = form.reason.clone();
 
+    let cluster = target.cluster.as_deref();
     let approval_url = format!(
-        "http://localhost:8081/api/v1/updates/{}/{}/reject",
-        namespace, name
+        "{}/api/v1/updates/{}/{}/reject",
+        hub::approval_api_url(cluster),
+        namespace,
+        name
     );
 
     // Convert form to JSON for the approval API
     let json_body = serde_json::json!({
-        "approver": form.approver,
+        "approver": pinned_approver(&user, form.approver),
         "reason": form.reason
     });
 
-    match reqwest::Client::new()
+    match hub::client(cluster)
         .post(&approval_url)
         .json(&json_body)
         .send()
@@ -1220,3 +1428,78 @@ pub async fn reject_update(
         },
     }
 }
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct LastGoodRollbackForm {
+    container: String,
+    reason: Option<String>,
+}
+
+/// Roll a Deployment container back to its last known good image (proxy to approval API)
+pub async fn rollback_to_last_good(
+    user: UserIdentity,
+    Path((namespace, deployment)): Path<(String, String)>,
+    Form(form): Form<LastGoodRollbackForm>,
+) -> impl IntoResponse {
+    if let Err(denied) = require_approver(&user, "rollback", "Deployment", &namespace, &deployment)
+    {
+        return denied;
+    }
+    let rollback_url = format!(
+        "{}/api/v1/rollback/{}/{}/last-good",
+        server::approval_api_url(),
+        namespace,
+        deployment
+    );
+
+    let json_body = serde_json::json!({
+        "container": form.container,
+        "user": user.username,
+        "reason": form.reason
+    });
+
+    let (status, body, error) = match server::local_client()
+        .post(&rollback_url)
+        .json(&json_body)
+        .send()
+        .await
+    {
+        Ok(response) => {
+            let status = response.status();
+            match response.json::<serde_json::Value>().await {
+                Ok(body) => {
+                    let error = (!status.is_success()).then(|| {
+                        body.get("error")
+                            .and_then(|e| e.as_str())
+                            .unwrap_or("Rollback failed")
+                            .to_string()
+                    });
+                    (status, body, error)
+                },
+                Err(_) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    serde_json::json!({"error": "Failed to parse response"}),
+                    Some("Failed to parse approval API response".to_string()),
+                ),
+            }
+        },
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            serde_json::json!({"error": e.to_string()}),
+            Some(format!("Failed to call approval API: {}", e)),
+        ),
+    };
+
+    AuditLogEntry::new(
+        user.username,
+        "rollback".to_string(),
+        "Deployment".to_string(),
+        namespace,
+        deployment,
+        if error.is_none() { "success" } else { "failed" }.to_string(),
+        error,
+    )
+    .log();
+
+    (status, Json(body))
+}
@@ -1,3 +1,7 @@
+use crate::models::crd::{
+    ImageMetadata, ReleaseNotes, RolloutProgress, RolloutState, SourceEvent, VulnerabilityScan,
+};
+use crate::server;
 use maud::{DOCTYPE, Markup, html};
 use serde::{Deserialize, Serialize};
 
@@ -18,9 +22,50 @@ pub struct UpdateRequestView {
     pub approved_by: Option<String>,
     pub rejected_by: Option<String>,
     pub rejection_reason: Option<String>,
+    /// e.g. `1/2 approvals` when more than one approver is required
+    pub approval_progress: Option<String>,
+    /// Release notes of the new version, when they were looked up
+    pub release_notes: Option<ReleaseNotes>,
+    /// Metadata of the new image read from the registry
+    pub image_metadata: Option<ImageMetadata>,
+    /// Vulnerability scan of the new image, when it was scanned
+    pub vulnerability_scan: Option<VulnerabilityScan>,
+    /// Rollout of the Deployment after the update was applied
+    #[serde(default)]
+    pub rollout: Option<RolloutProgress>,
+    /// Container the update targets, for image updates
+    pub container: Option<String>,
+    /// Event that detected the update
+    #[serde(default)]
+    pub source: Option<SourceEvent>,
+    /// Cluster the update belongs to, when clusters are named or this is a hub
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+/// Represents an audit record for display in the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntryView {
+    pub timestamp: String,
+    pub action: String,
+    pub resource_kind: String,
+    pub resource_name: String,
+    pub namespace: String,
+    pub current_image: String,
+    pub new_image: String,
+    pub actor: String,
+    pub source: String,
+    pub success: bool,
+    pub update_request: Option<String>,
+    pub message: Option<String>,
 }
 
 /// Base layout template - shared layout for all pages
+/// Path of a Web UI page or endpoint, below the base path in single-port mode
+fn url(path: &str) -> String {
+    format!("{}{}", server::base_path(), path)
+}
+
 pub fn base_layout(title: &str, content: Markup) -> Markup {
     html! {
         (DOCTYPE)
@@ -31,8 +76,8 @@ pub fn base_layout(title: &str, content: Markup) -> Markup {
                 title { (title) }
 
                 // Favicon
-                link rel="icon" type="image/x-icon" href="/static/img/favicon.ico";
-                link rel="icon" type="image/png" sizes="32x32" href="/static/img/logo.png";
+                link rel="icon" type="image/x-icon" href=(url("/static/img/favicon.ico"));
+                link rel="icon" type="image/png" sizes="32x32" href=(url("/static/img/logo.png"));
 
                 // DaisyUI + Tailwind CSS
                 link href="https://cdn.jsdelivr.net/npm/daisyui@4.4.19/dist/full.min.css" rel="stylesheet" type="text/css";
@@ -44,8 +89,58 @@ pub fn base_layout(title: &str, content: Markup) -> Markup {
                 // Chart.js
                 script src="https://cdn.jsdelivr.net/npm/chart.js@4.4.0/dist/chart.umd.min.js" {}
 
+                // Path prefix for scripts, set when served below HEADWIND_BASE_PATH
+                script {
+                    (maud::PreEscaped(format!(
+                        "const BASE_PATH = {};",
+                        serde_json::to_string(&server::base_path()).unwrap_or_default()
+                    )))
+                }
+
+                // Theme (light/dark/system, kept in localStorage). Applied in the head so the
+                // page doesn't flash the light theme first.
+                script {
+                    (maud::PreEscaped(r#"
+                    const THEME_KEY = 'headwind-theme';
+                    const darkScheme = window.matchMedia('(prefers-color-scheme: dark)');
+
+                    function themePreference() {
+                        return localStorage.getItem(THEME_KEY) || 'light';
+                    }
+
+                    function applyTheme() {
+                        const preference = themePreference();
+                        const theme = preference === 'system'
+                            ? (darkScheme.matches ? 'dark' : 'light')
+                            : (preference === 'dark' ? 'dark' : 'light');
+                        document.documentElement.setAttribute('data-theme', theme);
+                        if (window.Chart) {
+                            Chart.defaults.color = theme === 'dark' ? '#d1d5db' : '#374151';
+                            Chart.defaults.borderColor = theme === 'dark' ? 'rgba(255, 255, 255, 0.1)' : 'rgba(0, 0, 0, 0.1)';
+                        }
+                        // Pages with charts redraw them on this event
+                        document.dispatchEvent(new CustomEvent('headwind:themechange', { detail: { theme } }));
+                    }
+
+                    function setTheme(preference) {
+                        localStorage.setItem(THEME_KEY, preference);
+                        applyTheme();
+                    }
+
+                    applyTheme();
+                    darkScheme.addEventListener('change', function() {
+                        if (themePreference() === 'system') {
+                            applyTheme();
+                        }
+                    });
+                    document.addEventListener('DOMContentLoaded', function() {
+                        document.getElementById('theme-select').value = themePreference();
+                    });
+                    "#))
+                }
+
                 // Custom styles
-                link rel="stylesheet" href="/static/css/custom.css";
+                link rel="stylesheet" href=(url("/static/css/custom.css"));
             }
             body class="bg-base-200 min-h-screen flex flex-col" hx-ext="response-targets" {
                 // Notification Toast Container (for success/error messages)
@@ -54,17 +149,23 @@ pub fn base_layout(title: &str, content: Markup) -> Markup {
                 // Navigation Bar
                 div class="navbar bg-base-100 shadow-lg sticky top-0 z-40" {
                     div class="flex-1" {
-                        a href="/" class="btn btn-ghost normal-case text-xl gap-2" {
-                            img src="/static/img/logo.png" alt="Headwind Logo" class="w-8 h-8";
+                        a href=(url("/")) class="btn btn-ghost normal-case text-xl gap-2" {
+                            img src=(url("/static/img/logo.png")) alt="Headwind Logo" class="w-8 h-8";
                             span class="headwind-logo" { "Headwind" }
                         }
                     }
                     div class="flex-none" {
                         ul class="menu menu-horizontal px-1" {
-                            li { a href="/" { "Dashboard" } }
-                            li { a href="/observability" { "Observability" } }
-                            li { a href="/settings" { "Settings" } }
-                            li { a href="/health" { "Health" } }
+                            li { a href=(url("/")) { "Dashboard" } }
+                            li { a href=(url("/observability")) { "Observability" } }
+                            li { a href=(url("/audit")) { "Audit" } }
+                            li { a href=(url("/settings")) { "Settings" } }
+                            li { a href=(url("/health")) { "Health" } }
+                        }
+                        select id="theme-select" class="select select-sm select-bordered ml-2" aria-label="Theme" onchange="setTheme(this.value)" {
+                            option value="light" { "Light" }
+                            option value="dark" { "Dark" }
+                            option value="system" { "System" }
                         }
                     }
                 }
@@ -301,10 +402,11 @@ pub fn base_layout(title: &str, content: Markup) -> Markup {
 
                         const updates = Array.from(checkedBoxes).map(cb => ({
                             namespace: cb.dataset.namespace,
-                            name: cb.dataset.name
+                            name: cb.dataset.name,
+                            cluster: cb.dataset.cluster
                         }));
 
-                        fetch('/api/v1/updates/bulk/approve', {
+                        fetch(BASE_PATH + '/api/v1/updates/bulk/approve', {
                             method: 'POST',
                             headers: {
                                 'Content-Type': 'application/json'
@@ -341,10 +443,11 @@ pub fn base_layout(title: &str, content: Markup) -> Markup {
                         const checkedBoxes = document.querySelectorAll('.update-checkbox:checked');
                         const updates = Array.from(checkedBoxes).map(cb => ({
                             namespace: cb.dataset.namespace,
-                            name: cb.dataset.name
+                            name: cb.dataset.name,
+                            cluster: cb.dataset.cluster
                         }));
 
-                        fetch('/api/v1/updates/bulk/reject', {
+                        fetch(BASE_PATH + '/api/v1/updates/bulk/reject', {
                             method: 'POST',
                             headers: {
                                 'Content-Type': 'application/json'
@@ -389,7 +492,7 @@ pub fn base_layout(title: &str, content: Markup) -> Markup {
 
                         isInitialLoad = true;
                         console.log('Connecting to SSE endpoint...');
-                        eventSource = new EventSource('/api/v1/events/updates');
+                        eventSource = new EventSource(BASE_PATH + '/api/v1/events/updates');
 
                         eventSource.addEventListener('updated', function(e) {
                             console.log('SSE updated event:', e.data);
@@ -485,15 +588,25 @@ pub fn base_layout(title: &str, content: Markup) -> Markup {
     }
 }
 
-/// Dashboard template - main view showing all pending updates
+/// Dashboard template - main view showing all pending updates. Approve and reject actions
+/// are only shown when `can_approve` is set.
 pub fn dashboard(
     pending_updates: &[UpdateRequestView],
     completed_updates: &[UpdateRequestView],
+    unreachable_clusters: &[String],
+    can_approve: bool,
 ) -> Markup {
     let total_pending = pending_updates.len();
     let total_completed = completed_updates.len();
 
     let content = html! {
+        // Agents of a hub whose updates couldn't be listed
+        @if !unreachable_clusters.is_empty() {
+            div class="alert alert-warning mb-6" {
+                span { "Updates of cluster(s) " (unreachable_clusters.join(", ")) " could not be loaded." }
+            }
+        }
+
         // Stats
         div class="stats shadow mb-6 w-full" {
             div class="stat" {
@@ -604,15 +717,17 @@ pub fn dashboard(
                     }
 
                     // Bulk action buttons
-                    div class="flex justify-between items-center mb-4" {
-                        div class="flex gap-2" {
-                            button id="bulk-approve-btn" class="btn btn-success btn-sm" onclick="bulkApprove()" disabled {
-                                "✓ Approve Selected"
-                                span id="selected-count-approve" class="badge badge-sm ml-2" { "0" }
-                            }
-                            button id="bulk-reject-btn" class="btn btn-error btn-sm" onclick="showBulkRejectModal()" disabled {
-                                "✗ Reject Selected"
-                                span id="selected-count-reject" class="badge badge-sm ml-2" { "0" }
+                    @if can_approve {
+                        div class="flex justify-between items-center mb-4" {
+                            div class="flex gap-2" {
+                                button id="bulk-approve-btn" class="btn btn-success btn-sm" onclick="bulkApprove()" disabled {
+                                    "✓ Approve Selected"
+                                    span id="selected-count-approve" class="badge badge-sm ml-2" { "0" }
+                                }
+                                button id="bulk-reject-btn" class="btn btn-error btn-sm" onclick="showBulkRejectModal()" disabled {
+                                    "✗ Reject Selected"
+                                    span id="selected-count-reject" class="badge badge-sm ml-2" { "0" }
+                                }
                             }
                         }
                     }
@@ -660,7 +775,7 @@ pub fn dashboard(
                             }
                             tbody id="pending-updates-tbody" {
                                 @for update in pending_updates {
-                                    tr id=(format!("update-row-{}-{}", update.namespace, update.name))
+                                    tr id=(format!("update-row-{}", dom_id(update, "-")))
                                         class="pending-update-row"
                                         data-namespace=(update.namespace)
                                         data-kind=(update.resource_kind)
@@ -673,34 +788,45 @@ pub fn dashboard(
                                             input type="checkbox" class="checkbox checkbox-sm update-checkbox"
                                                 data-namespace=(update.namespace)
                                                 data-name=(update.name)
+                                                data-cluster=[update.cluster.as_deref()]
                                                 onchange="updateBulkActionButtons()" {}
                                         }
                                         td {
                                             div class="flex flex-col" {
                                                 span class="badge badge-outline badge-sm mb-1" { (update.resource_kind) }
                                                 span class="font-semibold" { (update.resource_name) }
+                                                @if let Some(ref cluster) = update.cluster {
+                                                    span class="badge badge-primary badge-outline badge-sm mt-1" { (cluster) }
+                                                }
                                             }
                                         }
                                         td { span class="badge badge-ghost" { (update.namespace) } }
                                         td { code class="version-display" { (update.current_version) } }
                                         td { code class="version-display text-success font-bold" { (update.new_version) } }
-                                        td { span class="badge badge-info" { (update.policy) } }
+                                        td {
+                                            span class="badge badge-info" { (update.policy) }
+                                            @if let Some(ref progress) = update.approval_progress {
+                                                span class="badge badge-outline badge-sm ml-1" { (progress) }
+                                            }
+                                        }
                                         td class="text-sm opacity-70" { (update.created_at) }
                                         td {
                                             div class="flex gap-2" {
-                                                button class="btn btn-success btn-sm"
-                                                    hx-post=(format!("/api/v1/updates/{}/{}/approve", update.namespace, update.name))
-                                                    hx-vals=r#"{"approver": "web-ui"}"#
-                                                    hx-confirm="Are you sure you want to approve this update?"
-                                                    hx-swap="none"
-                                                    hx-on--after-request="window.location.reload()" {
-                                                    "✓ Approve"
+                                                @if can_approve {
+                                                    button class="btn btn-success btn-sm"
+                                                        hx-post=(update_url(update, &format!("/api/v1/updates/{}/{}/approve", update.namespace, update.name)))
+                                                        hx-vals=(approval_vals(update))
+                                                        hx-confirm="Are you sure you want to approve this update?"
+                                                        hx-swap="none"
+                                                        hx-on--after-request="window.location.reload()" {
+                                                        "✓ Approve"
+                                                    }
+                                                    button class="btn btn-error btn-sm"
+                                                        onclick=(format!("document.getElementById('reject_modal_{}').showModal()", dom_id(update, "_"))) {
+                                                        "✗ Reject"
+                                                    }
                                                 }
-                                                button class="btn btn-error btn-sm"
-                                                    onclick=(format!("document.getElementById('reject_modal_{}_{}').showModal()", update.namespace, update.name)) {
-                                                    "✗ Reject"
-                                                }
-                                                a href=(format!("/updates/{}/{}", update.namespace, update.name)) class="btn btn-ghost btn-sm" {
+                                                a href=(update_url(update, &format!("/updates/{}/{}", update.namespace, update.name))) class="btn btn-ghost btn-sm" {
                                                     "Details"
                                                 }
                                             }
@@ -743,6 +869,10 @@ pub fn dashboard(
                                             span class="badge badge-outline badge-sm" { (update.resource_kind) }
                                             " "
                                             (update.resource_name)
+                                            @if let Some(ref cluster) = update.cluster {
+                                                " "
+                                                span class="badge badge-primary badge-outline badge-sm" { (cluster) }
+                                            }
                                         }
                                         td { (update.namespace) }
                                         td {
@@ -755,9 +885,15 @@ pub fn dashboard(
                                                 span class="badge badge-success" { (update.status) }
                                             } @else if update.status == "Rejected" {
                                                 span class="badge badge-error" { (update.status) }
+                                            } @else if update.status == "Expired" {
+                                                span class="badge badge-ghost" { (update.status) }
                                             } @else {
                                                 span class="badge badge-warning" { (update.status) }
                                             }
+                                            @if let Some(ref rollout) = update.rollout {
+                                                " "
+                                                (rollout_badge(rollout))
+                                            }
                                         }
                                         td {
                                             @if let Some(ref approver) = update.approved_by {
@@ -779,19 +915,19 @@ pub fn dashboard(
 
         // Individual Reject Modals for Pending Updates
         @for update in pending_updates {
-            dialog id=(format!("reject_modal_{}_{}", update.namespace, update.name)) class="modal" {
+            dialog id=(format!("reject_modal_{}", dom_id(update, "_"))) class="modal" {
                 div class="modal-box" {
                     h3 class="font-bold text-lg" { "Reject Update" }
                     p class="py-4" { "Please provide a reason for rejecting this update:" }
-                    textarea id=(format!("reject_reason_{}_{}", update.namespace, update.name))
+                    textarea id=(format!("reject_reason_{}", dom_id(update, "_")))
                         class="textarea textarea-bordered w-full"
                         placeholder="Reason for rejection..."
                         rows="3" {}
                     div class="modal-action" {
-                        button class="btn" onclick=(format!("document.getElementById('reject_modal_{}_{}').close()", update.namespace, update.name)) { "Cancel" }
+                        button class="btn" onclick=(format!("document.getElementById('reject_modal_{}').close()", dom_id(update, "_"))) { "Cancel" }
                         button type="button" class="btn btn-error"
-                            hx-post=(format!("/api/v1/updates/{}/{}/reject", update.namespace, update.name))
-                            hx-vals=(format!(r#"js:{{approver: "web-ui", reason: document.getElementById("reject_reason_{}_{}").value}}"#, update.namespace, update.name))
+                            hx-post=(update_url(update, &format!("/api/v1/updates/{}/{}/reject", update.namespace, update.name)))
+                            hx-vals=(format!(r#"js:{{approver: "web-ui", reason: document.getElementById("reject_reason_{}").value}}"#, dom_id(update, "_")))
                             hx-swap="none"
                             hx-on--after-request="window.location.reload()" {
                             "Reject Update"
@@ -834,13 +970,42 @@ pub fn dashboard(
     base_layout("Headwind Dashboard", content)
 }
 
-/// Detail template - individual update request view
-pub fn detail(update: &UpdateRequestView) -> Markup {
+/// Badge with the state of a rollout, and its progress while it runs
+fn rollout_badge(rollout: &RolloutProgress) -> Markup {
+    html! {
+        @match rollout.state {
+            RolloutState::Progressing => span class="badge badge-info badge-sm" {
+                "Rolling out " (rollout.updated_replicas) "/" (rollout.replicas)
+            },
+            RolloutState::Complete => span class="badge badge-success badge-outline badge-sm" { "Rolled out" },
+            RolloutState::Failed => span class="badge badge-error badge-sm" { "Rollout failed" },
+        }
+    }
+}
+
+/// Image size in human-readable units
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Detail template - individual update request view. Approve, reject and revert actions are
+/// only shown when `can_approve` is set.
+pub fn detail(update: &UpdateRequestView, can_approve: bool) -> Markup {
     let content = html! {
         // Breadcrumbs
         div class="text-sm breadcrumbs mb-4" {
             ul {
-                li { a href="/" { "Dashboard" } }
+                li { a href=(url("/")) { "Dashboard" } }
                 li { (update.namespace) }
                 li { (update.name) }
             }
@@ -854,8 +1019,14 @@ pub fn detail(update: &UpdateRequestView) -> Markup {
                         h2 class="card-title text-3xl" { (update.resource_name) }
                         div class="flex gap-2 mt-2" {
                             span class="badge badge-outline" { (update.resource_kind) }
+                            @if let Some(ref cluster) = update.cluster {
+                                span class="badge badge-primary badge-outline" { (cluster) }
+                            }
                             span class="badge badge-ghost" { (update.namespace) }
                             span class="badge badge-info" { (update.policy) }
+                            @if let Some(ref progress) = update.approval_progress {
+                                span class="badge badge-outline" { (progress) }
+                            }
                             @if update.status == "Pending" {
                                 span class="badge badge-warning" { (update.status) }
                             } @else if update.status == "Completed" {
@@ -896,6 +1067,105 @@ pub fn detail(update: &UpdateRequestView) -> Markup {
                     }
                 }
 
+                @if let Some(ref metadata) = update.image_metadata {
+                    div class="divider" {}
+
+                    // New Image Metadata
+                    div {
+                        h3 class="text-lg font-semibold mb-2" { "Image Details" }
+                        div class="flex flex-wrap gap-2" {
+                            @if let Some(created) = metadata.created {
+                                span class="badge badge-outline" { "Built " (created.format("%Y-%m-%d %H:%M UTC")) }
+                            }
+                            @if let Some(size) = metadata.size {
+                                span class="badge badge-outline" { (format_size(size)) }
+                            }
+                            @for port in &metadata.exposed_ports {
+                                span class="badge badge-outline" { "Port " (port) }
+                            }
+                        }
+                        @if !metadata.labels.is_empty() {
+                            div class="overflow-x-auto mt-2" {
+                                table class="table table-xs" {
+                                    tbody {
+                                        @for (label, value) in &metadata.labels {
+                                            tr {
+                                                td class="opacity-70" { code { (label) } }
+                                                td class="break-all" { (value) }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                @if let Some(ref scan) = update.vulnerability_scan {
+                    div class="divider" {}
+
+                    // Vulnerability Scan
+                    div {
+                        h3 class="text-lg font-semibold mb-2" { "Vulnerabilities" }
+                        @if let Some(ref error) = scan.error {
+                            div class="alert alert-warning" { span { "Scan failed: " (error) } }
+                        } @else {
+                            div class="flex gap-2" {
+                                span class=(if scan.critical > 0 { "badge badge-error" } else { "badge badge-outline" }) { (scan.critical) " critical" }
+                                span class=(if scan.high > 0 { "badge badge-warning" } else { "badge badge-outline" }) { (scan.high) " high" }
+                                span class="badge badge-outline" { (scan.medium) " medium" }
+                                span class="badge badge-outline" { (scan.low) " low" }
+                            }
+                        }
+                        p class="text-sm opacity-70 mt-2" {
+                            "Scanned by " (scan.scanner) " at " (scan.scanned_at.format("%Y-%m-%d %H:%M:%S UTC"))
+                        }
+                    }
+                }
+
+                @if let Some(ref rollout) = update.rollout {
+                    div class="divider" {}
+
+                    // Rollout Progress
+                    div {
+                        h3 class="text-lg font-semibold mb-2" { "Rollout " (rollout_badge(rollout)) }
+                        progress
+                            class=(match rollout.state {
+                                RolloutState::Progressing => "progress progress-info w-full",
+                                RolloutState::Complete => "progress progress-success w-full",
+                                RolloutState::Failed => "progress progress-error w-full",
+                            })
+                            value=(rollout.updated_replicas)
+                            max=(rollout.replicas.max(1)) {}
+                        div class="flex gap-2 mt-2" {
+                            span class="badge badge-outline" { (rollout.updated_replicas) "/" (rollout.replicas) " updated" }
+                            span class="badge badge-outline" { (rollout.ready_replicas) " ready" }
+                            span class="badge badge-outline" { (rollout.available_replicas) " available" }
+                        }
+                        @if let Some(ref message) = rollout.message {
+                            p class="text-sm mt-2" { (message) }
+                        }
+                        p class="text-sm opacity-70 mt-2" {
+                            "Last checked at " (rollout.last_updated.format("%Y-%m-%d %H:%M:%S UTC"))
+                        }
+                    }
+                }
+
+                @if let Some(ref notes) = update.release_notes {
+                    div class="divider" {}
+
+                    // Release Notes
+                    div {
+                        h3 class="text-lg font-semibold mb-2" { "Release Notes" }
+                        a href=(notes.url) class="link link-primary" target="_blank" rel="noopener noreferrer" {
+                            (notes.title.as_deref().unwrap_or(&notes.url))
+                        }
+                        @if let Some(ref summary) = notes.summary {
+                            pre class="mt-2 p-4 bg-base-200 rounded-box text-sm whitespace-pre-wrap" { (summary) }
+                        }
+                    }
+                }
+
                 div class="divider" {}
 
                 // Metadata
@@ -919,6 +1189,25 @@ pub fn detail(update: &UpdateRequestView) -> Markup {
                         }
                     }
 
+                    @if let Some(ref source) = update.source {
+                        div {
+                            p class="text-sm opacity-70" { "Detected By" }
+                            p class="font-semibold" {
+                                (source.kind)
+                                @if let Some(ref origin) = source.origin { " (" (origin) ")" }
+                            }
+                            p class="text-sm opacity-70" {
+                                @if let Some(ref sender) = source.sender { "from " (sender) " " }
+                                @if let Some(received_at) = source.received_at {
+                                    "at " (received_at.format("%Y-%m-%d %H:%M:%S UTC"))
+                                }
+                            }
+                            @if let Some(ref digest) = source.digest {
+                                p class="text-xs font-mono opacity-70 break-all" { (digest) }
+                            }
+                        }
+                    }
+
                     @if let Some(ref reason) = update.rejection_reason {
                         div class="col-span-2" {
                             p class="text-sm opacity-70" { "Rejection Reason" }
@@ -932,20 +1221,20 @@ pub fn detail(update: &UpdateRequestView) -> Markup {
                     }
                 }
 
-                @if update.status == "Pending" {
+                @if update.status == "Pending" && can_approve {
                     div class="divider" {}
 
                     // Action Buttons
                     div class="card-actions justify-end" {
-                        a href="/" class="btn btn-ghost" { "Back to Dashboard" }
+                        a href=(url("/")) class="btn btn-ghost" { "Back to Dashboard" }
                         button class="btn btn-error" onclick="reject_modal.showModal()" {
                             "✗ Reject"
                         }
                         button class="btn btn-success"
-                            hx-post=(format!("/api/v1/updates/{}/{}/approve", update.namespace, update.name))
-                            hx-vals=r#"{"approver": "web-ui"}"#
+                            hx-post=(update_url(update, &format!("/api/v1/updates/{}/{}/approve", update.namespace, update.name)))
+                            hx-vals=(approval_vals(update))
                             hx-confirm="Are you sure you want to approve this update?"
-                            hx-on--after-request="window.location.href='/'" {
+                            hx-on--after-request="window.location.href=BASE_PATH + '/'" {
                             "✓ Approve Update"
                         }
                     }
@@ -960,9 +1249,9 @@ pub fn detail(update: &UpdateRequestView) -> Markup {
                                 div class="modal-action" {
                                     button class="btn" { "Cancel" }
                                     button type="button" class="btn btn-error"
-                                        hx-post=(format!("/api/v1/updates/{}/{}/reject", update.namespace, update.name))
+                                        hx-post=(update_url(update, &format!("/api/v1/updates/{}/{}/reject", update.namespace, update.name)))
                                         hx-vals=r#"js:{approver: "web-ui", reason: document.getElementById("reject_reason").value}"#
-                                        hx-on--after-request="window.location.href='/'"
+                                        hx-on--after-request="window.location.href=BASE_PATH + '/'"
                                         onclick="reject_modal.close()" {
                                         "Reject Update"
                                     }
@@ -976,7 +1265,17 @@ pub fn detail(update: &UpdateRequestView) -> Markup {
                 } @else {
                     div class="divider" {}
                     div class="card-actions justify-end" {
-                        a href="/" class="btn btn-primary" { "Back to Dashboard" }
+                        @if can_approve && update.resource_kind == "Deployment" && update.status == "Completed" {
+                            @if let Some(ref container) = update.container {
+                                button class="btn btn-warning"
+                                    hx-post=(url(&format!("/api/v1/rollback/{}/{}/last-good", update.namespace, update.resource_name)))
+                                    hx-vals=(serde_json::json!({ "container": container }).to_string())
+                                    hx-confirm=(format!("Revert container {} to its last known good image?", container)) {
+                                    "↺ Revert to Last Known Good"
+                                }
+                            }
+                        }
+                        a href=(url("/")) class="btn btn-primary" { "Back to Dashboard" }
                     }
                 }
             }
@@ -986,6 +1285,34 @@ pub fn detail(update: &UpdateRequestView) -> Markup {
     base_layout(&format!("Update Request - {}", update.name), content)
 }
 
+/// Path of an UpdateRequest's page or action. Updates of a cluster carry it as the `cluster`
+/// query parameter, which a hub routes to the cluster's agent.
+fn update_url(update: &UpdateRequestView, path: &str) -> String {
+    match &update.cluster {
+        Some(cluster) => url(&format!("{}?cluster={}", path, cluster)),
+        None => url(path),
+    }
+}
+
+/// Element ID suffix unique to an update across clusters
+fn dom_id(update: &UpdateRequestView, separator: &str) -> String {
+    let mut parts = vec![update.namespace.as_str(), update.name.as_str()];
+    if let Some(cluster) = &update.cluster {
+        parts.insert(0, cluster);
+    }
+    parts.join(separator)
+}
+
+/// Approval form values, pinning the images shown on the page so a stale approval is refused
+fn approval_vals(update: &UpdateRequestView) -> String {
+    serde_json::json!({
+        "approver": "web-ui",
+        "expected_new_image": update.new_image,
+        "expected_current_image": update.current_image,
+    })
+    .to_string()
+}
+
 /// Helper function to get unique namespaces from updates
 fn get_unique_namespaces(updates: &[UpdateRequestView]) -> Vec<String> {
     let mut namespaces: Vec<String> = updates.iter().map(|u| u.namespace.clone()).collect();
@@ -1043,6 +1370,41 @@ pub fn settings() -> Markup {
                 }
             }
 
+            // Google Cloud Pub/Sub
+            div class="card bg-base-100 shadow-xl mb-6" {
+                div class="card-body" {
+                    h2 class="card-title text-2xl mb-4" { "Google Cloud Pub/Sub" }
+
+                    div class="form-control mb-4" {
+                        label class="label cursor-pointer" {
+                            span class="label-text" { "Receive Artifact Registry / GCR Push Notifications" }
+                            input type="checkbox" id="pubsub-enabled" class="checkbox checkbox-primary";
+                        }
+                    }
+
+                    div class="form-control mb-4" {
+                        label class="label" {
+                            span class="label-text" { "Project" }
+                        }
+                        input type="text" id="pubsub-project" class="input input-bordered" placeholder="my-project";
+                    }
+
+                    div class="form-control mb-4" {
+                        label class="label" {
+                            span class="label-text" { "Subscription" }
+                        }
+                        input type="text" id="pubsub-subscription" class="input input-bordered" placeholder="headwind-gcr";
+                    }
+
+                    div class="form-control" {
+                        label class="label" {
+                            span class="label-text" { "Messages per Pull" }
+                        }
+                        input type="number" id="pubsub-max-messages" class="input input-bordered" min="1" value="100";
+                    }
+                }
+            }
+
             // Helm Configuration
             div class="card bg-base-100 shadow-xl mb-6" {
                 div class="card-body" {
@@ -1283,12 +1645,16 @@ pub fn settings() -> Markup {
             // Load current settings from API
             async function loadSettings() {
                 try {
-                    const response = await fetch('/api/v1/settings');
+                    const response = await fetch(BASE_PATH + '/api/v1/settings');
                     const config = await response.json();
 
                     // Populate form fields
                     document.getElementById('polling-enabled').checked = config.polling.enabled;
                     document.getElementById('polling-interval').value = config.polling.interval;
+                    document.getElementById('pubsub-enabled').checked = config.pubsub.enabled;
+                    document.getElementById('pubsub-project').value = config.pubsub.project || '';
+                    document.getElementById('pubsub-subscription').value = config.pubsub.subscription || '';
+                    document.getElementById('pubsub-max-messages').value = config.pubsub.maxMessages;
                     document.getElementById('helm-auto-discovery').checked = config.helm.autoDiscovery;
                     document.getElementById('controllers-enabled').checked = config.controllers.enabled;
 
@@ -1332,6 +1698,12 @@ pub fn settings() -> Markup {
                         enabled: document.getElementById('polling-enabled').checked,
                         interval: parseInt(document.getElementById('polling-interval').value)
                     },
+                    pubsub: {
+                        enabled: document.getElementById('pubsub-enabled').checked,
+                        project: document.getElementById('pubsub-project').value || null,
+                        subscription: document.getElementById('pubsub-subscription').value || null,
+                        maxMessages: parseInt(document.getElementById('pubsub-max-messages').value)
+                    },
                     helm: {
                         autoDiscovery: document.getElementById('helm-auto-discovery').checked
                     },
@@ -1376,7 +1748,7 @@ pub fn settings() -> Markup {
                 };
 
                 try {
-                    const response = await fetch('/api/v1/settings', {
+                    const response = await fetch(BASE_PATH + '/api/v1/settings', {
                         method: 'PUT',
                         headers: {
                             'Content-Type': 'application/json'
@@ -1386,7 +1758,7 @@ pub fn settings() -> Markup {
 
                     if (response.ok) {
                         showToast('Settings saved successfully!', 'success');
-                        setTimeout(() => window.location.href = '/', 2000);
+                        setTimeout(() => window.location.href = BASE_PATH + '/', 2000);
                     } else {
                         const error = await response.json();
                         showToast('Failed to save settings: ' + (error.error || 'Unknown error'), 'error');
@@ -1400,7 +1772,7 @@ pub fn settings() -> Markup {
             // Test notification
             async function testNotification(type) {
                 try {
-                    const response = await fetch('/api/v1/settings/test-notification', {
+                    const response = await fetch(BASE_PATH + '/api/v1/settings/test-notification', {
                         method: 'POST',
                         headers: {
                             'Content-Type': 'application/json'
@@ -1490,7 +1862,7 @@ pub fn observability() -> Markup {
             div class="card bg-base-100 shadow-xl mb-6" {
                 div class="card-body" {
                     h2 class="card-title text-2xl mb-4" { "Resources Watched" }
-                    div class="grid grid-cols-2 md:grid-cols-4 gap-4" {
+                    div class="grid grid-cols-2 md:grid-cols-5 gap-4" {
                         div {
                             p class="text-sm text-base-content/70" { "Deployments" }
                             p class="text-2xl font-bold" id="metric-deployments-watched" { "0" }
@@ -1503,6 +1875,10 @@ pub fn observability() -> Markup {
                             p class="text-sm text-base-content/70" { "DaemonSets" }
                             p class="text-2xl font-bold" id="metric-daemonsets-watched" { "0" }
                         }
+                        div {
+                            p class="text-sm text-base-content/70" { "CronJobs" }
+                            p class="text-2xl font-bold" id="metric-cronjobs-watched" { "0" }
+                        }
                         div {
                             p class="text-sm text-base-content/70" { "Helm Releases" }
                             p class="text-2xl font-bold" id="metric-helm-releases-watched" { "0" }
@@ -1511,6 +1887,38 @@ pub fn observability() -> Markup {
                 }
             }
 
+            // Last registry poll cycle
+            div class="card bg-base-100 shadow-xl mb-6" {
+                div class="card-body" {
+                    h2 class="card-title text-2xl mb-4" { "Last Poll Cycle" }
+                    p id="poll-cycle-empty" class="text-base-content/70" { "No poll cycle has completed yet (polling may be disabled)." }
+                    div id="poll-cycle-details" class="hidden" {
+                        div class="grid grid-cols-2 md:grid-cols-4 gap-4 mb-4" {
+                            div {
+                                p class="text-sm text-base-content/70" { "Images Checked" }
+                                p class="text-2xl font-bold" id="poll-images-checked" { "0" }
+                            }
+                            div {
+                                p class="text-sm text-base-content/70" { "Charts Checked" }
+                                p class="text-2xl font-bold" id="poll-charts-checked" { "0" }
+                            }
+                            div {
+                                p class="text-sm text-base-content/70" { "New Versions" }
+                                p class="text-2xl font-bold text-success" id="poll-new-versions" { "0" }
+                            }
+                            div {
+                                p class="text-sm text-base-content/70" { "Errors" }
+                                p class="text-2xl font-bold text-error" id="poll-errors" { "0" }
+                            }
+                        }
+                        p class="text-sm text-base-content/70" {
+                            "Finished " span id="poll-finished-at" {} " in " span id="poll-duration" {} " · skipped (interval not elapsed): " span id="poll-skipped" {}
+                        }
+                        p class="text-sm text-base-content/70" id="poll-error-categories" {}
+                    }
+                }
+            }
+
             // Timeframe selector
             div class="card bg-base-100 shadow-xl mb-6" {
                 div class="card-body" {
@@ -1530,7 +1938,7 @@ pub fn observability() -> Markup {
                 // Updates over time chart
                 div class="card bg-base-100 shadow-xl" {
                     div class="card-body" {
-                        h2 class="card-title text-xl mb-4" { "Updates Over Time" }
+                        h2 class="card-title text-xl mb-4" { "Update Frequency" }
                         canvas id="updates-chart" {}
                     }
                 }
@@ -1548,7 +1956,7 @@ pub fn observability() -> Markup {
                 // Rollback metrics chart
                 div class="card bg-base-100 shadow-xl" {
                     div class="card-body" {
-                        h2 class="card-title text-xl mb-4" { "Rollback Operations" }
+                        h2 class="card-title text-xl mb-4" { "Rollback Rate" }
                         canvas id="rollback-chart" {}
                     }
                 }
@@ -1594,6 +2002,15 @@ pub fn observability() -> Markup {
                     }
                 }
             }
+
+            // Approval latency chart (from UpdateRequests, like the update counts)
+            div class="card bg-base-100 shadow-xl mb-6" {
+                div class="card-body" {
+                    h2 class="card-title text-xl mb-4" { "Approval Latency" }
+                    p class="text-sm text-base-content/70 mb-2" { "Minutes from an UpdateRequest's creation to its approval or rejection" }
+                    canvas id="approval-latency-chart" {}
+                }
+            }
         }
 
         // JavaScript for loading metrics
@@ -1605,6 +2022,11 @@ pub fn observability() -> Markup {
                 setInterval(loadMetrics, 30000);
             });
 
+            // Redraw the charts with the colors of the new theme
+            document.addEventListener('headwind:themechange', function() {
+                loadCharts();
+            });
+
             let updatesChart = null;
             let resourcesChart = null;
             let rollbackChart = null;
@@ -1613,6 +2035,8 @@ pub fn observability() -> Markup {
             let notificationsChart = null;
             let webhookChart = null;
             let errorsChart = null;
+            let approvalLatencyChart = null;
+            let updateRequests = [];
             let currentTimeRange = '6h';
 
             // Helper function to format timestamps for chart labels based on timeframe
@@ -1649,7 +2073,7 @@ pub fn observability() -> Markup {
 
             async function loadMetrics() {
                 try {
-                    const response = await fetch('/api/v1/metrics');
+                    const response = await fetch(BASE_PATH + '/api/v1/metrics');
                     const metrics = await response.json();
 
                     // Update backend type
@@ -1660,6 +2084,7 @@ pub fn observability() -> Markup {
                         'deployments_watched',
                         'statefulsets_watched',
                         'daemonsets_watched',
+                        'cronjobs_watched',
                         'helm_releases_watched'
                     ];
 
@@ -1673,6 +2098,9 @@ pub fn observability() -> Markup {
                     // Load UpdateRequest counts from API (these are persistent, not reset on restart)
                     await loadUpdateCounts();
 
+                    // Load summary of the last registry poll cycle
+                    await loadPollCycle();
+
                     // Show dashboard, hide loading
                     document.getElementById('metrics-loading').classList.add('hidden');
                     document.getElementById('metrics-dashboard').classList.remove('hidden');
@@ -1689,8 +2117,9 @@ pub fn observability() -> Markup {
             async function loadUpdateCounts() {
                 try {
                     // Fetch all UpdateRequests
-                    const response = await fetch('/api/v1/updates');
+                    const response = await fetch(BASE_PATH + '/api/v1/updates');
                     const updates = await response.json();
+                    updateRequests = updates;
 
                     // Count by phase
                     const counts = {
@@ -1731,66 +2160,131 @@ pub fn observability() -> Markup {
                 }
             }
 
+            async function loadPollCycle() {
+                try {
+                    const response = await fetch(BASE_PATH + '/api/v1/polling/last-cycle');
+                    if (!response.ok) {
+                        document.getElementById('poll-cycle-empty').classList.remove('hidden');
+                        document.getElementById('poll-cycle-details').classList.add('hidden');
+                        return;
+                    }
+                    const cycle = await response.json();
+
+                    const errorCounts = Object.entries(cycle.errors || {});
+                    const totalErrors = errorCounts.reduce((sum, [, count]) => sum + count, 0);
+
+                    document.getElementById('poll-images-checked').textContent = cycle.imagesChecked;
+                    document.getElementById('poll-charts-checked').textContent = cycle.chartsChecked;
+                    document.getElementById('poll-new-versions').textContent = cycle.newImageVersions + cycle.newChartVersions;
+                    document.getElementById('poll-errors').textContent = totalErrors;
+                    document.getElementById('poll-finished-at').textContent = new Date(cycle.finishedAt).toLocaleString();
+                    document.getElementById('poll-duration').textContent = (cycle.durationMs / 1000).toFixed(1) + 's';
+                    document.getElementById('poll-skipped').textContent = cycle.imagesSkipped + cycle.chartsSkipped;
+                    document.getElementById('poll-error-categories').textContent = errorCounts.length > 0
+                        ? 'Errors by category: ' + errorCounts.map(([category, count]) => `${category}: ${count}`).join(', ')
+                        : '';
+
+                    document.getElementById('poll-cycle-empty').classList.add('hidden');
+                    document.getElementById('poll-cycle-details').classList.remove('hidden');
+                } catch (error) {
+                    console.error('Failed to load poll cycle summary:', error);
+                }
+            }
+
+            // Fetch a metric's series for the selected range; counters are charted as their
+            // increase per step ('increase'), gauges as their value
+            async function fetchSeries(metric, series = 'value') {
+                const response = await fetch(`${BASE_PATH}/api/v1/metrics/timeseries/${metric}?range=${currentTimeRange}&series=${series}`);
+                return response.ok ? response.json() : [];
+            }
+
+            // Average and longest approval latency in minutes per interval of the selected range,
+            // by when the UpdateRequests were approved or rejected
+            function approvalLatencySeries(updates, range) {
+                const hour = 60 * 60 * 1000;
+                const rangeMs = { '1h': hour, '6h': 6 * hour, '24h': 24 * hour, '7d': 7 * 24 * hour, '30d': 30 * 24 * hour }[range] || 6 * hour;
+                const intervals = 12;
+                const size = rangeMs / intervals;
+                const end = Date.now();
+                const start = end - rangeMs;
+                const latencies = Array.from({ length: intervals }, () => []);
+
+                updates.forEach(update => {
+                    const decidedAt = Date.parse(update.status?.approvedAt || update.status?.rejectedAt);
+                    const createdAt = Date.parse(update.metadata?.creationTimestamp);
+                    if (isNaN(decidedAt) || isNaN(createdAt) || decidedAt < start || decidedAt > end) {
+                        return;
+                    }
+                    const index = Math.min(intervals - 1, Math.floor((decidedAt - start) / size));
+                    latencies[index].push((decidedAt - createdAt) / 60000);
+                });
+
+                return latencies.map((values, i) => ({
+                    timestamp: new Date(start + (i + 1) * size).toISOString(),
+                    average: values.length ? values.reduce((sum, v) => sum + v, 0) / values.length : null,
+                    max: values.length ? Math.max(...values) : null
+                }));
+            }
+
             async function loadCharts() {
                 try {
-                    const timeParam = `?range=${currentTimeRange}`;
 
                     // Load updates time series
                     const updatesData = await Promise.all([
-                        fetch('/api/v1/metrics/timeseries/headwind_updates_approved_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_updates_applied_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_updates_failed_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_updates_rejected_total' + timeParam).then(r => r.json())
+                        fetchSeries('headwind_updates_approved_total', 'increase'),
+                        fetchSeries('headwind_updates_applied_total', 'increase'),
+                        fetchSeries('headwind_updates_failed_total', 'increase'),
+                        fetchSeries('headwind_updates_rejected_total', 'increase')
                     ]);
 
                     // Load resources time series
                     const resourcesData = await Promise.all([
-                        fetch('/api/v1/metrics/timeseries/headwind_deployments_watched' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_statefulsets_watched' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_daemonsets_watched' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_helm_releases_watched' + timeParam).then(r => r.json())
+                        fetchSeries('headwind_deployments_watched'),
+                        fetchSeries('headwind_statefulsets_watched'),
+                        fetchSeries('headwind_daemonsets_watched'),
+                        fetchSeries('headwind_helm_releases_watched')
                     ]);
 
                     // Load rollback metrics
                     const rollbackData = await Promise.all([
-                        fetch('/api/v1/metrics/timeseries/headwind_rollbacks_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_rollbacks_automatic_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_rollbacks_manual_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_rollbacks_failed_total' + timeParam).then(r => r.json())
+                        fetchSeries('headwind_rollbacks_total', 'increase'),
+                        fetchSeries('headwind_rollbacks_automatic_total', 'increase'),
+                        fetchSeries('headwind_rollbacks_manual_total', 'increase'),
+                        fetchSeries('headwind_rollbacks_failed_total', 'increase')
                     ]);
 
                     // Load polling metrics
                     const pollingData = await Promise.all([
-                        fetch('/api/v1/metrics/timeseries/headwind_polling_cycles_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_polling_new_tags_found_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_polling_errors_total' + timeParam).then(r => r.json())
+                        fetchSeries('headwind_polling_cycles_total', 'increase'),
+                        fetchSeries('headwind_polling_new_tags_found_total', 'increase'),
+                        fetchSeries('headwind_polling_errors_total', 'increase')
                     ]);
 
                     // Load Helm metrics
                     const helmData = await Promise.all([
-                        fetch('/api/v1/metrics/timeseries/headwind_helm_updates_found_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_helm_updates_applied_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_helm_repository_queries_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_helm_repository_errors_total' + timeParam).then(r => r.json())
+                        fetchSeries('headwind_helm_updates_found_total', 'increase'),
+                        fetchSeries('headwind_helm_updates_applied_total', 'increase'),
+                        fetchSeries('headwind_helm_repository_queries_total', 'increase'),
+                        fetchSeries('headwind_helm_repository_errors_total', 'increase')
                     ]);
 
                     // Load notification metrics
                     const notificationData = await Promise.all([
-                        fetch('/api/v1/metrics/timeseries/headwind_notifications_sent_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_notifications_failed_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_notifications_slack_sent_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_notifications_teams_sent_total' + timeParam).then(r => r.json())
+                        fetchSeries('headwind_notifications_sent_total', 'increase'),
+                        fetchSeries('headwind_notifications_failed_total', 'increase'),
+                        fetchSeries('headwind_notifications_slack_sent_total', 'increase'),
+                        fetchSeries('headwind_notifications_teams_sent_total', 'increase')
                     ]);
 
                     // Load webhook metrics
                     const webhookData = await Promise.all([
-                        fetch('/api/v1/metrics/timeseries/headwind_webhook_events_total' + timeParam).then(r => r.json()),
-                        fetch('/api/v1/metrics/timeseries/headwind_webhook_events_processed' + timeParam).then(r => r.json())
+                        fetchSeries('headwind_webhook_events_total', 'increase'),
+                        fetchSeries('headwind_webhook_events_processed', 'increase')
                     ]);
 
                     // Load error metrics
                     const errorData = await Promise.all([
-                        fetch('/api/v1/metrics/timeseries/headwind_reconcile_errors_total' + timeParam).then(r => r.json())
+                        fetchSeries('headwind_reconcile_errors_total', 'increase')
                     ]);
 
                     // Create updates chart
@@ -2134,6 +2628,41 @@ pub fn observability() -> Markup {
                             scales: { y: { beginAtZero: true } }
                         }
                     });
+
+                    // Create approval latency chart
+                    const latencyData = approvalLatencySeries(updateRequests, currentTimeRange);
+                    if (approvalLatencyChart) approvalLatencyChart.destroy();
+                    const latencyCtx = document.getElementById('approval-latency-chart').getContext('2d');
+                    approvalLatencyChart = new Chart(latencyCtx, {
+                        type: 'line',
+                        data: {
+                            labels: latencyData.map(p => formatTimestamp(p.timestamp, currentTimeRange)),
+                            datasets: [
+                                {
+                                    label: 'Average',
+                                    data: latencyData.map(p => p.average),
+                                    borderColor: 'rgb(54, 162, 235)',
+                                    backgroundColor: 'rgba(54, 162, 235, 0.1)',
+                                    spanGaps: true,
+                                    tension: 0.4
+                                },
+                                {
+                                    label: 'Longest',
+                                    data: latencyData.map(p => p.max),
+                                    borderColor: 'rgb(255, 159, 64)',
+                                    backgroundColor: 'rgba(255, 159, 64, 0.1)',
+                                    spanGaps: true,
+                                    tension: 0.4
+                                }
+                            ]
+                        },
+                        options: {
+                            responsive: true,
+                            maintainAspectRatio: true,
+                            plugins: { legend: { position: 'bottom' } },
+                            scales: { y: { beginAtZero: true } }
+                        }
+                    });
                 } catch (error) {
                     console.error('Failed to load charts:', error);
                 }
@@ -2144,3 +2673,119 @@ pub fn observability() -> Markup {
 
     base_layout("Observability - Headwind", content)
 }
+
+/// Audit trail page template
+pub fn audit(
+    entries: &[AuditEntryView],
+    namespace: Option<&str>,
+    action: Option<&str>,
+    target: Option<&str>,
+) -> Markup {
+    const ACTIONS: [&str; 6] = [
+        "Detected",
+        "Approved",
+        "Rejected",
+        "Applied",
+        "Expired",
+        "RolledBack",
+    ];
+
+    let content = html! {
+        h1 class="text-3xl font-bold mb-6" { "Audit Trail" }
+
+        // Filters (submitted as query parameters)
+        form method="get" action=(url("/audit")) class="card bg-base-100 shadow-xl mb-6" {
+            div class="card-body flex flex-row flex-wrap gap-4 items-end" {
+                div class="form-control" {
+                    label class="label" { span class="label-text" { "Namespace" } }
+                    input type="text" name="namespace" class="input input-bordered input-sm"
+                        value=(namespace.unwrap_or_default());
+                }
+                div class="form-control" {
+                    label class="label" { span class="label-text" { "Action" } }
+                    select name="action" class="select select-bordered select-sm" {
+                        option value="" { "All" }
+                        @for a in ACTIONS {
+                            option value=(a) selected[action == Some(a)] { (a) }
+                        }
+                    }
+                }
+                div class="form-control" {
+                    label class="label" { span class="label-text" { "Resource" } }
+                    input type="text" name="target" class="input input-bordered input-sm"
+                        value=(target.unwrap_or_default());
+                }
+                button type="submit" class="btn btn-primary btn-sm" { "Filter" }
+            }
+        }
+
+        div class="card bg-base-100 shadow-xl" {
+            div class="card-body" {
+                @if entries.is_empty() {
+                    p class="text-gray-500 py-4" { "No audit records found." }
+                } @else {
+                    div class="overflow-x-auto" {
+                        table class="table table-sm" {
+                            thead {
+                                tr {
+                                    th { "Time" }
+                                    th { "Action" }
+                                    th { "Resource" }
+                                    th { "Namespace" }
+                                    th { "Change" }
+                                    th { "Actor" }
+                                    th { "Source" }
+                                    th { "Details" }
+                                }
+                            }
+                            tbody {
+                                @for entry in entries {
+                                    tr {
+                                        td class="text-sm opacity-70 whitespace-nowrap" { (entry.timestamp) }
+                                        td {
+                                            @if !entry.success {
+                                                span class="badge badge-error" { (entry.action) " failed" }
+                                            } @else if entry.action == "Rejected" || entry.action == "RolledBack" {
+                                                span class="badge badge-warning" { (entry.action) }
+                                            } @else if entry.action == "Expired" {
+                                                span class="badge badge-ghost" { (entry.action) }
+                                            } @else if entry.action == "Applied" {
+                                                span class="badge badge-success" { (entry.action) }
+                                            } @else {
+                                                span class="badge badge-info" { (entry.action) }
+                                            }
+                                        }
+                                        td {
+                                            span class="badge badge-outline badge-sm" { (entry.resource_kind) }
+                                            " "
+                                            (entry.resource_name)
+                                        }
+                                        td { (entry.namespace) }
+                                        td {
+                                            code class="text-xs" { (entry.current_image) }
+                                            " → "
+                                            code class="text-xs" { (entry.new_image) }
+                                        }
+                                        td { (entry.actor) }
+                                        td { span class="badge badge-ghost badge-sm" { (entry.source) } }
+                                        td class="text-sm" {
+                                            @if let Some(ref update_request) = entry.update_request {
+                                                a class="link" href=(url(&format!("/updates/{}/{}", entry.namespace, update_request))) { (update_request) }
+                                                @if entry.message.is_some() { br; }
+                                            }
+                                            @if let Some(ref message) = entry.message {
+                                                (message)
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    base_layout("Audit - Headwind", content)
+}
@@ -0,0 +1,222 @@
+//! Authentication of incoming registry webhooks.
+//!
+//! Each endpoint can be given a shared secret. Senders prove knowledge of it either by
+//! signing the request body (HMAC-SHA256, `X-Hub-Signature-256` or `X-Headwind-Signature`
+//! carrying `sha256=<hex>`) or, for registries that can only add static headers, by sending
+//! the secret itself (`Authorization: Bearer <secret>` or `X-Headwind-Token`).
+//!
+//! Registries that can do neither authenticate with a per-sender token in the URL path
+//! (`/webhook/<endpoint>/<token>`), checked against the `webhook-tokens` list so that each
+//! sender's token can be revoked on its own.
+
+use axum::http::HeaderMap;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+pub const SIGNATURE_HEADER: &str = "x-headwind-signature";
+pub const HUB_SIGNATURE_HEADER: &str = "x-hub-signature-256";
+pub const TOKEN_HEADER: &str = "x-headwind-token";
+
+const SHA256_BLOCK_SIZE: usize = 64;
+
+/// Why a webhook request was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// No signature or token was sent
+    MissingCredentials,
+    /// A signature was sent but doesn't match the body
+    InvalidSignature,
+    /// A token was sent but doesn't match the secret
+    InvalidToken,
+    /// The token in the URL path isn't in the `webhook-tokens` list
+    InvalidPathToken,
+}
+
+impl Rejection {
+    /// Value of the `reason` metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rejection::MissingCredentials => "missing_credentials",
+            Rejection::InvalidSignature => "invalid_signature",
+            Rejection::InvalidToken => "invalid_token",
+            Rejection::InvalidPathToken => "invalid_path_token",
+        }
+    }
+}
+
+/// Check a request against the endpoint secret. A signature takes precedence over a token.
+pub fn verify(secret: &str, headers: &HeaderMap, body: &[u8]) -> Result<(), Rejection> {
+    let signature = [SIGNATURE_HEADER, HUB_SIGNATURE_HEADER]
+        .iter()
+        .find_map(|name| header(headers, name));
+    if let Some(signature) = signature {
+        let expected = format!(
+            "sha256={}",
+            hex::encode(hmac_sha256(secret.as_bytes(), body))
+        );
+        return if constant_time_eq(signature.as_bytes(), expected.as_bytes()) {
+            Ok(())
+        } else {
+            Err(Rejection::InvalidSignature)
+        };
+    }
+
+    let token = header(headers, TOKEN_HEADER).or_else(|| {
+        header(headers, "authorization").map(|value| value.strip_prefix("Bearer ").unwrap_or(value))
+    });
+    match token {
+        Some(token) if constant_time_eq(token.trim().as_bytes(), secret.as_bytes()) => Ok(()),
+        Some(_) => Err(Rejection::InvalidToken),
+        None => Err(Rejection::MissingCredentials),
+    }
+}
+
+/// Check a URL path token against the senders' tokens and return the sender it belongs to.
+/// Every token is compared, so timing doesn't reveal which sender's token was close.
+pub fn verify_path_token<'a>(
+    tokens: &'a BTreeMap<String, String>,
+    token: &str,
+) -> Result<&'a str, Rejection> {
+    tokens
+        .iter()
+        .fold(None, |found, (sender, expected)| {
+            if constant_time_eq(token.as_bytes(), expected.as_bytes()) {
+                Some(sender.as_str())
+            } else {
+                found
+            }
+        })
+        .ok_or(Rejection::InvalidPathToken)
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut block = [0u8; SHA256_BLOCK_SIZE];
+    if key.len() > SHA256_BLOCK_SIZE {
+        let digest = Sha256::digest(key);
+        block[..digest.len()].copy_from_slice(&digest);
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let inner = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner);
+    outer.finalize().to_vec()
+}
+
+/// Compare without returning early, so timing doesn't reveal how much of a secret matched
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Test case 6: key longer than the block size
+        assert_eq!(
+            hex::encode(hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let body = br#"{"events":[]}"#;
+        let signature = format!("sha256={}", hex::encode(hmac_sha256(b"s3cret", body)));
+
+        assert_eq!(
+            verify("s3cret", &headers(&[(SIGNATURE_HEADER, &signature)]), body),
+            Ok(())
+        );
+        assert_eq!(
+            verify(
+                "s3cret",
+                &headers(&[(HUB_SIGNATURE_HEADER, &signature)]),
+                body
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verify("other", &headers(&[(SIGNATURE_HEADER, &signature)]), body),
+            Err(Rejection::InvalidSignature)
+        );
+        assert_eq!(
+            verify(
+                "s3cret",
+                &headers(&[(SIGNATURE_HEADER, &signature)]),
+                br#"{"events":[{}]}"#
+            ),
+            Err(Rejection::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_verify_token() {
+        assert_eq!(
+            verify(
+                "s3cret",
+                &headers(&[("authorization", "Bearer s3cret")]),
+                b""
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verify("s3cret", &headers(&[(TOKEN_HEADER, "s3cret")]), b""),
+            Ok(())
+        );
+        assert_eq!(
+            verify("s3cret", &headers(&[(TOKEN_HEADER, "wrong")]), b""),
+            Err(Rejection::InvalidToken)
+        );
+        assert_eq!(
+            verify("s3cret", &HeaderMap::new(), b""),
+            Err(Rejection::MissingCredentials)
+        );
+    }
+
+    #[test]
+    fn test_verify_path_token() {
+        let tokens = BTreeMap::from([
+            ("harbor-a".to_string(), "9f86d081".to_string()),
+            ("quay".to_string(), "2c26b46b".to_string()),
+        ]);
+        assert_eq!(verify_path_token(&tokens, "2c26b46b"), Ok("quay"));
+        assert_eq!(
+            verify_path_token(&tokens, "2c26b46"),
+            Err(Rejection::InvalidPathToken)
+        );
+        assert_eq!(
+            verify_path_token(&BTreeMap::new(), "2c26b46b"),
+            Err(Rejection::InvalidPathToken)
+        );
+    }
+}
@@ -1,21 +1,62 @@
-use crate::metrics::{WEBHOOK_EVENTS_PROCESSED, WEBHOOK_EVENTS_TOTAL};
-use crate::models::webhook::{ChartPushEvent, DockerHubWebhook, ImagePushEvent, RegistryWebhook};
-use crate::models::{EventSource, ResourcePolicy, annotations};
+pub mod auth;
+pub mod provenance;
+pub mod retry;
+
+use crate::config;
+use crate::controller::stores;
+use crate::controller::tracked::{DetectedImage, TrackedWorkload, handle_image_update};
+use crate::controller::workload::split_image;
+use crate::metrics::{
+    WEBHOOK_EVENT_QUEUE_CAPACITY, WEBHOOK_EVENT_QUEUE_DEPTH, WEBHOOK_EVENTS_PROCESSED,
+    WEBHOOK_EVENTS_TOTAL, WEBHOOK_REQUESTS_REJECTED,
+};
+use crate::models::UpdateTarget;
+use crate::models::webhook::{
+    ChartPushEvent, DockerHubWebhook, GhcrWebhook, ImagePushEvent, RegistryWebhook,
+};
+use crate::models::{
+    EventSource, PrereleasePolicy, ResourcePolicy, UpdateMode, UpdatePolicy, annotations,
+    container_annotations, has_policy, parse_approval_ttl, parse_channel_tag,
+    parse_container_overrides, parse_ignore_patterns, parse_max_version, parse_notify_detected,
+    parse_pin_digest, parse_require_approval, parse_required_approvals, parse_update_mode,
+};
+use crate::notifications::{self, DeploymentInfo};
 use crate::policy::PolicyEngine;
+use crate::policy::defaults::apply_policy_defaults;
+use crate::policy::flux::{FluxImagePolicy, apply_image_policies, list_image_policies};
+use crate::policy::targets::{apply_update_targets, list_update_targets};
 use anyhow::Result;
-use axum::{Json, Router, extract::State, http::StatusCode, response::IntoResponse, routing::post};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{Extensions, HeaderMap, StatusCode};
+use axum::{Router, response::IntoResponse, routing::post};
 use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, StatefulSet};
+use k8s_openapi::api::batch::v1::CronJob;
 use kube::{Api, Client, ResourceExt};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tower_http::trace::TraceLayer;
-use tracing::{debug, error, info, warn};
-
-pub type EventSender = mpsc::UnboundedSender<ImagePushEvent>;
-pub type EventReceiver = mpsc::UnboundedReceiver<ImagePushEvent>;
-pub type ChartEventSender = mpsc::UnboundedSender<ChartPushEvent>;
-pub type ChartEventReceiver = mpsc::UnboundedReceiver<ChartPushEvent>;
+use tracing::{Instrument, debug, error, info, info_span, warn};
+
+pub type EventSender = mpsc::Sender<ImagePushEvent>;
+pub type EventReceiver = mpsc::Receiver<ImagePushEvent>;
+pub type ChartEventSender = mpsc::Sender<ChartPushEvent>;
+pub type ChartEventReceiver = mpsc::Receiver<ChartPushEvent>;
+
+/// Default number of events each processor queue holds before webhooks are answered with 429
+const DEFAULT_EVENT_QUEUE_CAPACITY: usize = 1000;
+
+/// Capacity of the image and chart event queues (`HEADWIND_EVENT_QUEUE_CAPACITY`)
+fn event_queue_capacity() -> usize {
+    std::env::var("HEADWIND_EVENT_QUEUE_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_EVENT_QUEUE_CAPACITY)
+}
 
 #[derive(Clone)]
 struct WebhookState {
@@ -23,39 +64,83 @@ struct WebhookState {
     chart_event_tx: ChartEventSender,
 }
 
-pub async fn start_webhook_server() -> Result<(JoinHandle<()>, EventSender, ChartEventSender)> {
-    let (event_tx, event_rx) = mpsc::unbounded_channel();
-    let (chart_event_tx, chart_event_rx) = mpsc::unbounded_channel();
+/// Spawn the processors of image and chart push events and return their senders
+pub fn start_event_processors() -> (EventSender, ChartEventSender) {
+    let capacity = event_queue_capacity();
+    let (event_tx, event_rx) = mpsc::channel(capacity);
+    let (chart_event_tx, chart_event_rx) = mpsc::channel(capacity);
+    for queue in ["images", "charts"] {
+        WEBHOOK_EVENT_QUEUE_CAPACITY
+            .with_label_values(&[queue])
+            .set(capacity as i64);
+        WEBHOOK_EVENT_QUEUE_DEPTH.with_label_values(&[queue]).set(0);
+    }
 
-    // Clone senders to return them
-    let event_tx_clone = event_tx.clone();
-    let chart_event_tx_clone = chart_event_tx.clone();
+    // With an event bus, the processors may read the events consumed from it instead
+    let (event_rx, chart_event_rx) = crate::eventbus::attach(
+        capacity,
+        &event_tx,
+        &chart_event_tx,
+        event_rx,
+        chart_event_rx,
+    );
 
-    // Spawn processors for both event types
-    tokio::spawn(process_webhook_events(event_rx));
-    tokio::spawn(process_chart_events(chart_event_rx));
+    // Spawn processors for both event types, sharing the queue of failed events
+    let retry_tx = retry::start();
+    tokio::spawn(process_webhook_events(event_rx, retry_tx.clone()));
+    tokio::spawn(process_chart_events(chart_event_rx, retry_tx));
+    register_processor_health("events/images", event_tx.clone());
+    register_processor_health("events/charts", chart_event_tx.clone());
 
+    (event_tx, chart_event_tx)
+}
+
+/// Routes of the webhook server, without `/health`
+pub fn router(event_tx: EventSender, chart_event_tx: ChartEventSender) -> Router {
     let state = WebhookState {
         event_tx,
         chart_event_tx,
     };
 
-    let app = Router::new()
+    Router::new()
         .route("/webhook/registry", post(handle_registry_webhook))
+        .route("/webhook/registry/{token}", post(handle_registry_webhook))
         .route("/webhook/dockerhub", post(handle_dockerhub_webhook))
-        .route("/health", axum::routing::get(health_check))
+        .route("/webhook/dockerhub/{token}", post(handle_dockerhub_webhook))
+        .route("/webhook/ghcr", post(handle_ghcr_webhook))
+        .route("/webhook/ghcr/{token}", post(handle_ghcr_webhook))
+        .route(
+            "/admission/updaterequests",
+            post(crate::admission::review_update_request),
+        )
+        .route(
+            "/admission/workloads",
+            post(crate::admission::annotations::review_workload),
+        )
+        .route(
+            "/convert/updaterequests",
+            post(crate::admission::conversion::convert_update_requests),
+        )
         .layer(TraceLayer::new_for_http())
-        .with_state(state);
+        .with_state(state)
+}
+
+pub async fn start_webhook_server(
+    addr: SocketAddr,
+) -> Result<(JoinHandle<()>, EventSender, ChartEventSender)> {
+    let (event_tx, chart_event_tx) = start_event_processors();
+
+    // Clone senders to return them
+    let event_tx_clone = event_tx.clone();
+    let chart_event_tx_clone = chart_event_tx.clone();
+
+    let app =
+        router(event_tx, chart_event_tx).route("/health", axum::routing::get(crate::health::livez));
 
-    let addr = "0.0.0.0:8080";
     info!("Starting webhook server on {}", addr);
 
     let handle = tokio::spawn(async move {
-        let listener = tokio::net::TcpListener::bind(addr)
-            .await
-            .expect("Failed to bind webhook server");
-
-        axum::serve(listener, app)
+        crate::server::serve(addr, app)
             .await
             .expect("Webhook server failed");
     });
@@ -63,11 +148,91 @@ pub async fn start_webhook_server() -> Result<(JoinHandle<()>, EventSender, Char
     Ok((handle, event_tx_clone, chart_event_tx_clone))
 }
 
+/// Reject the request unless it carries a valid signature or token for `secret`, or, on the
+/// tokenized route, a `path_token` of one of the senders in `tokens`. Endpoints without a
+/// secret accept every request on the untokenized route.
+fn authorize(
+    endpoint: &str,
+    path_token: Option<&str>,
+    secret: Option<&str>,
+    tokens: &BTreeMap<String, String>,
+    extensions: &Extensions,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(), (StatusCode, &'static str)> {
+    let result = match (path_token, secret) {
+        (Some(token), _) => auth::verify_path_token(tokens, token).map(|sender| {
+            debug!(
+                "Webhook request on {} authenticated as sender {}",
+                endpoint, sender
+            );
+        }),
+        (None, Some(secret)) => auth::verify(secret, headers, body),
+        (None, None) => return Ok(()),
+    };
+
+    result.map_err(|rejection| {
+        WEBHOOK_REQUESTS_REJECTED
+            .with_label_values(&[endpoint, rejection.as_str()])
+            .inc();
+
+        let remote_addr = provenance::remote_addr(extensions).unwrap_or_default();
+        let header_value = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        warn!(
+            audit = true,
+            endpoint,
+            reason = rejection.as_str(),
+            remote_addr,
+            forwarded_for = header_value("x-forwarded-for"),
+            user_agent = header_value("user-agent"),
+            body_bytes = body.len(),
+            "Rejected unauthenticated webhook request"
+        );
+
+        match rejection {
+            auth::Rejection::InvalidPathToken => {
+                (StatusCode::UNAUTHORIZED, "Invalid webhook token")
+            },
+            _ => (StatusCode::UNAUTHORIZED, "Invalid webhook signature"),
+        }
+    })
+}
+
 async fn handle_registry_webhook(
     State(state): State<WebhookState>,
-    Json(payload): Json<RegistryWebhook>,
+    path_token: Option<Path<String>>,
+    extensions: Extensions,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> impl IntoResponse {
-    WEBHOOK_EVENTS_TOTAL.inc();
+    WEBHOOK_EVENTS_TOTAL.with_label_values(&["registry"]).inc();
+
+    let secrets = config::webhook_secrets();
+    if let Err(response) = authorize(
+        "registry",
+        path_token.as_ref().map(|Path(token)| token.as_str()),
+        secrets.registry.as_deref(),
+        &secrets.tokens,
+        &extensions,
+        &headers,
+        &body,
+    ) {
+        return response;
+    }
+
+    let payload: RegistryWebhook = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            warn!("Invalid registry webhook payload: {}", e);
+            return (StatusCode::BAD_REQUEST, "Invalid webhook payload");
+        },
+    };
 
     info!(
         "Received registry webhook with {} events",
@@ -92,6 +257,11 @@ async fn handle_registry_webhook(
                     registry: extract_registry(&event.target.repository),
                     repository: event.target.repository.clone(),
                     version: tag,
+                    source: Some(provenance::webhook(
+                        "registry",
+                        &extensions,
+                        Some(&event.target.digest),
+                    )),
                     digest: Some(event.target.digest),
                 };
 
@@ -101,9 +271,10 @@ async fn handle_registry_webhook(
                     chart_event.version
                 );
 
-                if let Err(e) = state.chart_event_tx.send(chart_event) {
-                    error!("Failed to send chart push event: {}", e);
-                    return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to process event");
+                if let Err(response) =
+                    enqueue("registry", "charts", &state.chart_event_tx, chart_event)
+                {
+                    return response;
                 }
             } else {
                 // This is a container image push
@@ -111,12 +282,16 @@ async fn handle_registry_webhook(
                     registry: extract_registry(&event.target.repository),
                     repository: event.target.repository.clone(),
                     tag,
+                    source: Some(provenance::webhook(
+                        "registry",
+                        &extensions,
+                        Some(&event.target.digest),
+                    )),
                     digest: Some(event.target.digest),
                 };
 
-                if let Err(e) = state.event_tx.send(push_event) {
-                    error!("Failed to send push event: {}", e);
-                    return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to process event");
+                if let Err(response) = enqueue("registry", "images", &state.event_tx, push_event) {
+                    return response;
                 }
             }
         }
@@ -127,8 +302,34 @@ async fn handle_registry_webhook(
 
 async fn handle_dockerhub_webhook(
     State(state): State<WebhookState>,
-    Json(payload): Json<DockerHubWebhook>,
+    path_token: Option<Path<String>>,
+    extensions: Extensions,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> impl IntoResponse {
+    WEBHOOK_EVENTS_TOTAL.with_label_values(&["dockerhub"]).inc();
+
+    let secrets = config::webhook_secrets();
+    if let Err(response) = authorize(
+        "dockerhub",
+        path_token.as_ref().map(|Path(token)| token.as_str()),
+        secrets.dockerhub.as_deref(),
+        &secrets.tokens,
+        &extensions,
+        &headers,
+        &body,
+    ) {
+        return response;
+    }
+
+    let payload: DockerHubWebhook = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            warn!("Invalid Docker Hub webhook payload: {}", e);
+            return (StatusCode::BAD_REQUEST, "Invalid webhook payload");
+        },
+    };
+
     info!(
         "Received Docker Hub webhook for {}",
         payload.repository.repo_name
@@ -139,175 +340,388 @@ async fn handle_dockerhub_webhook(
         repository: payload.repository.repo_name,
         tag: payload.push_data.tag,
         digest: None,
+        source: Some(provenance::webhook("dockerhub", &extensions, None)),
     };
 
-    if let Err(e) = state.event_tx.send(push_event) {
-        error!("Failed to send push event: {}", e);
-        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to process event");
+    if let Err(response) = enqueue("dockerhub", "images", &state.event_tx, push_event) {
+        return response;
     }
 
     (StatusCode::OK, "Webhook processed")
 }
 
-async fn health_check() -> impl IntoResponse {
-    (StatusCode::OK, "OK")
+/// Push parsed from a GitHub package webhook
+#[derive(Debug)]
+enum GhcrPush {
+    Image(ImagePushEvent),
+    Chart(ChartPushEvent),
 }
 
-/// Macro to generate process_* functions for different Kubernetes resource types.
-/// This eliminates ~200 lines of duplicated code across process_deployments,
-/// process_statefulsets, and process_daemonsets.
-///
-/// Each generated function:
-/// 1. Queries all resources of the specified type
-/// 2. Checks for headwind annotations
-/// 3. Extracts pod template spec
-/// 4. Iterates containers to find matching images
-/// 5. Calls the resource-specific update handler
-macro_rules! impl_process_resources {
-    ($fn_name:ident, $resource_type:ty, $resource_name:expr, $handler_path:path) => {
-        async fn $fn_name(
-            client: &Client,
-            policy_engine: &Arc<PolicyEngine>,
-            event: &ImagePushEvent,
-        ) -> Result<()> {
-            let resources: Api<$resource_type> = Api::all(client.clone());
-            let resource_list = resources.list(&Default::default()).await?;
+async fn handle_ghcr_webhook(
+    State(state): State<WebhookState>,
+    path_token: Option<Path<String>>,
+    extensions: Extensions,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    WEBHOOK_EVENTS_TOTAL.with_label_values(&["ghcr"]).inc();
+
+    let secrets = config::webhook_secrets();
+    if let Err(response) = authorize(
+        "ghcr",
+        path_token.as_ref().map(|Path(token)| token.as_str()),
+        secrets.ghcr.as_deref(),
+        &secrets.tokens,
+        &extensions,
+        &headers,
+        &body,
+    ) {
+        return response;
+    }
+
+    // GitHub sends a ping when the webhook is created
+    let event_type = headers
+        .get("x-github-event")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    match event_type {
+        "ping" => return (StatusCode::OK, "pong"),
+        "package" | "registry_package" | "" => {},
+        other => {
+            debug!("Ignoring GitHub {} event", other);
+            return (StatusCode::OK, "Event ignored");
+        },
+    }
+
+    let payload: GhcrWebhook = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            warn!("Invalid GHCR webhook payload: {}", e);
+            return (StatusCode::BAD_REQUEST, "Invalid webhook payload");
+        },
+    };
+
+    info!(
+        "Received GHCR webhook ({}) for {}/{}",
+        payload.action, payload.package.owner.login, payload.package.name
+    );
+
+    let queued = match ghcr_push_event(&payload) {
+        Some(GhcrPush::Image(mut event)) => {
+            event.source = Some(provenance::webhook(
+                "ghcr",
+                &extensions,
+                event.digest.as_deref(),
+            ));
+            enqueue("ghcr", "images", &state.event_tx, event)
+        },
+        Some(GhcrPush::Chart(mut event)) => {
+            event.source = Some(provenance::webhook(
+                "ghcr",
+                &extensions,
+                event.digest.as_deref(),
+            ));
+            info!(
+                "Detected Helm chart push: {} version {}",
+                event.base_oci_url(),
+                event.version
+            );
+            enqueue("ghcr", "charts", &state.chart_event_tx, event)
+        },
+        None => {
+            debug!("GHCR webhook is not a tagged push, ignoring");
+            Ok(())
+        },
+    };
+
+    if let Err(response) = queued {
+        return response;
+    }
+
+    (StatusCode::OK, "Webhook processed")
+}
+
+/// Convert a GitHub package webhook into an image or chart push. Returns None for other
+/// package ecosystems, other actions and untagged versions.
+fn ghcr_push_event(payload: &GhcrWebhook) -> Option<GhcrPush> {
+    if !matches!(payload.action.as_str(), "published" | "updated") {
+        return None;
+    }
+
+    let package = &payload.package;
+    let package_type = package.package_type.to_lowercase();
+    if !matches!(package_type.as_str(), "container" | "helm") {
+        return None;
+    }
+
+    let version = package.package_version.as_ref()?;
+    let metadata = version.container_metadata.as_ref()?;
+    let tag = metadata.tag.as_ref().filter(|tag| !tag.name.is_empty())?;
+
+    // GHCR image names are lowercase, package names may not be
+    let registry = package
+        .registry
+        .as_ref()
+        .and_then(|r| r.url.as_deref())
+        .map(|url| {
+            url.trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .trim_end_matches('/')
+                .to_string()
+        })
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "ghcr.io".to_string());
+    let repository = format!("{}/{}", package.owner.login, package.name).to_lowercase();
+    let digest = tag.digest.clone().or_else(|| version.version.clone());
+
+    let is_helm_chart = package_type == "helm"
+        || metadata.manifest.as_ref().is_some_and(|manifest| {
+            let config = &manifest["config"];
+            [&config["media_type"], &config["mediaType"]]
+                .iter()
+                .filter_map(|v| v.as_str())
+                .any(|media_type| media_type.contains("helm.config"))
+        });
+
+    if is_helm_chart {
+        Some(GhcrPush::Chart(ChartPushEvent {
+            registry,
+            repository,
+            version: tag.name.clone(),
+            digest,
+            source: None,
+        }))
+    } else {
+        Some(GhcrPush::Image(ImagePushEvent {
+            registry,
+            repository,
+            tag: tag.name.clone(),
+            digest,
+            source: None,
+        }))
+    }
+}
+
+/// Hand an event to its processor without waiting. A full queue is answered with 429 so the
+/// registry retries later instead of Headwind buffering a webhook storm in memory.
+fn enqueue<T>(
+    endpoint: &str,
+    queue: &str,
+    tx: &mpsc::Sender<T>,
+    event: T,
+) -> Result<(), (StatusCode, &'static str)> {
+    let result = match tx.try_send(event) {
+        Ok(()) => Ok(()),
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            WEBHOOK_REQUESTS_REJECTED
+                .with_label_values(&[endpoint, "queue_full"])
+                .inc();
+            warn!(
+                "Event queue {} is full ({} events), rejecting {} webhook",
+                queue,
+                tx.max_capacity(),
+                endpoint
+            );
+            Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                "Event queue full, retry later",
+            ))
+        },
+        Err(mpsc::error::TrySendError::Closed(_)) => {
+            error!("Failed to send push event: {} processor stopped", queue);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to process event"))
+        },
+    };
+
+    WEBHOOK_EVENT_QUEUE_DEPTH
+        .with_label_values(&[queue])
+        .set((tx.max_capacity() - tx.capacity()) as i64);
+    result
+}
+
+/// Fail liveness once the processor reading from `sender`'s channel has exited
+fn register_processor_health<T: Send + 'static>(name: &str, sender: mpsc::Sender<T>) {
+    crate::health::register(name, move || {
+        if sender.is_closed() {
+            crate::health::Status::Failed("event processor exited".to_string())
+        } else {
+            crate::health::Status::Ok
+        }
+    });
+}
+
+/// Match a push event against the containers of all workloads of kind `W`:
+/// 1. Lists the workloads (merged with matching UpdateTargets, ImagePolicies and defaults)
+/// 2. Checks for headwind annotations and parses the policy of each container
+/// 3. Iterates the containers of the pod template to find matching images
+/// 4. Handles the image update of each matching container
+async fn process_workloads<W: TrackedWorkload>(
+    client: &Client,
+    policy_engine: &Arc<PolicyEngine>,
+    event: &ImagePushEvent,
+    targets: &[UpdateTarget],
+    image_policies: &[FluxImagePolicy],
+) -> Result<()> {
+    let kind = W::kind(&());
+    let workloads = stores::list::<W>(client).await?;
+
+    debug!("Checking {} {}s for matching images", workloads.len(), kind);
+
+    for mut workload in workloads {
+        // Merge in policies from UpdateTargets selecting this workload
+        apply_update_targets(&mut workload, targets);
+        // Then in policies from Flux ImagePolicies tracking its images
+        apply_image_policies(&mut workload, W::pod_spec, image_policies);
+        // And the policy defaults of its namespace
+        apply_policy_defaults(&mut workload);
+
+        let namespace = workload.namespace().unwrap_or_default();
+        let name = workload.name_any();
+
+        // Check if workload has headwind annotations
+        let Some(annotations) = &workload.meta().annotations else {
+            continue;
+        };
+
+        // Skip if no policy annotation, for the workload or one of its containers
+        if !has_policy(annotations) {
+            continue;
+        }
+
+        // Parse policy, and the policies of containers with overrides
+        let (policy, container_policies) = match parse_policy_from_annotations(annotations)
+            .and_then(|p| Ok((p, parse_container_policies(annotations)?)))
+        {
+            Ok(policies) => policies,
+            Err(e) => {
+                warn!("Failed to parse policy for {} {}: {}", kind, name, e);
+                continue;
+            },
+        };
 
+        // Check event source - only process webhook events if event_source is "webhook" or "both"
+        if policy.event_source != EventSource::Webhook && policy.event_source != EventSource::Both {
             debug!(
-                "Checking {} {}s for matching images",
-                resource_list.items.len(),
-                $resource_name
+                "Skipping {} {} - event source is {:?}, not webhook",
+                kind, name, policy.event_source
             );
+            continue;
+        }
 
-            for resource in resource_list.items {
-                // Check if resource has headwind annotations
-                let annotations = match &resource.metadata.annotations {
-                    Some(ann) => ann,
-                    None => continue,
-                };
+        let notify_detected = parse_notify_detected(annotations);
 
-                // Skip if no policy annotation
-                if !annotations.contains_key(annotations::POLICY) {
-                    continue;
-                }
+        // Check each container
+        let Some(template_spec) = workload.pod_spec() else {
+            continue;
+        };
 
-                // Parse policy to check event source
-                let policy = match parse_policy_from_annotations(annotations) {
-                    Ok(p) => p,
-                    Err(e) => {
-                        warn!(
-                            "Failed to parse policy for {} {}: {}",
-                            $resource_name,
-                            resource.name_any(),
-                            e
-                        );
-                        continue;
-                    },
-                };
+        for container in &template_spec.containers {
+            // Skip containers not in the tracked images list (if specified)
+            if !policy.images.is_empty() && !policy.images.contains(&container.name) {
+                continue;
+            }
+            let policy = container_policies.get(&container.name).unwrap_or(&policy);
 
-                // Check event source - only process webhook events if event_source is "webhook" or "both"
-                if policy.event_source != EventSource::Webhook
-                    && policy.event_source != EventSource::Both
-                {
-                    debug!(
-                        "Skipping {} {} - event source is {:?}, not webhook",
-                        $resource_name,
-                        resource.name_any(),
-                        policy.event_source
-                    );
+            let current_image = match container.image.as_ref() {
+                Some(img) => img,
+                None => continue,
+            };
+
+            // Parse the current image to extract name and registry
+            let (image_name, current_tag) = match parse_image_full(current_image) {
+                Ok(parts) => parts,
+                Err(e) => {
+                    warn!("Failed to parse image {}: {}", current_image, e);
                     continue;
-                }
+                },
+            };
 
-                // Check each container in the resource
-                let spec = match resource.spec.as_ref() {
-                    Some(s) => s,
-                    None => continue,
-                };
+            // Check if this container uses the image from the webhook event
+            let matches = images_match(&event.registry, &event.repository, &image_name);
+            debug!(
+                "Image match check: event=({}, {}) {}={} => {}",
+                event.registry, event.repository, kind, image_name, matches
+            );
+            if !matches {
+                continue;
+            }
 
-                let template_spec = match spec.template.spec.as_ref() {
-                    Some(s) => s,
-                    None => continue,
-                };
+            info!(
+                "Found matching {} {}/{} container {} using {}",
+                kind, namespace, name, container.name, current_image
+            );
 
-                for container in &template_spec.containers {
-                    let current_image = match container.image.as_ref() {
-                        Some(img) => img,
-                        None => continue,
-                    };
-
-                    // Parse the current image
-                    let (image_name, current_tag) = match parse_image_full(current_image) {
-                        Ok(parts) => parts,
-                        Err(e) => {
-                            warn!("Failed to parse image {}: {}", current_image, e);
-                            continue;
-                        },
-                    };
-
-                    // Check if this container uses the image from the webhook event
-                    let matches = images_match(&event.registry, &event.repository, &image_name);
-                    if !matches {
-                        continue;
-                    }
+            let Some(new_version) =
+                event_version(client, policy, event, &image_name, &current_tag, &namespace).await
+            else {
+                continue;
+            };
 
-                    info!(
-                        "Found matching {} {}/{} container {} using {}",
-                        $resource_name,
-                        resource.namespace().unwrap_or_default(),
-                        resource.name_any(),
-                        container.name,
-                        current_image
-                    );
+            // Skip if it's the same version
+            if current_tag == new_version {
+                debug!(
+                    "Container {} already using tag {}, skipping",
+                    container.name, new_version
+                );
+                continue;
+            }
 
-                    // Skip if it's the same version
-                    if current_tag == event.tag {
-                        debug!(
-                            "Container {} already using tag {}, skipping",
-                            container.name, event.tag
-                        );
-                        continue;
-                    }
+            // Build the new image tag
+            let new_image = updated_image(&image_name, &new_version);
 
-                    // Call the update handler
-                    if let Err(e) =
-                        $handler_path(client, policy_engine, &resource, &image_name, &event.tag)
-                            .await
-                    {
-                        error!(
-                            "Failed to handle image update for {} {}/{}: {}",
-                            $resource_name,
-                            resource.namespace().unwrap_or_default(),
-                            resource.name_any(),
-                            e
-                        );
-                    }
+            // Containers with policy 'none' are never updated, but their owners may still want
+            // to hear about new releases (headwind.sh/notify-detected)
+            if policy.policy == UpdatePolicy::None {
+                if notify_detected
+                    && policy_engine.is_newer_release(policy, &current_tag, &new_version)
+                {
+                    info!(
+                        "New release for {} {}/{} container {} with policy 'none': {} -> {}",
+                        kind, namespace, name, container.name, current_tag, new_version
+                    );
+                    notifications::notify_update_detected_only(DeploymentInfo {
+                        name: name.clone(),
+                        namespace: namespace.clone(),
+                        current_image: current_image.clone(),
+                        new_image,
+                        container: Some(container.name.clone()),
+                        resource_kind: Some(kind.to_string()),
+                    });
                 }
+                continue;
             }
 
-            Ok(())
+            // Propose or apply the update
+            let detected = DetectedImage {
+                container: &container.name,
+                policy,
+                image_name: &image_name,
+                current_image,
+                new_version: &new_version,
+                new_image: &new_image,
+            };
+            if let Err(e) = handle_image_update(client, policy_engine, &workload, &detected).await {
+                error!(
+                    "Failed to handle image update for {} {}/{}: {}",
+                    kind, namespace, name, e
+                );
+            }
         }
-    };
+    }
+
+    Ok(())
 }
 
-// Generate process_statefulsets and process_daemonsets functions using the macro
-// Note: process_deployments is not generated here because it has a different signature
-// and additional logic (policy parsing, image filtering, etc.) that doesn't match
-// the StatefulSet/DaemonSet pattern
-impl_process_resources!(
-    process_statefulsets,
-    StatefulSet,
-    "statefulset",
-    crate::controller::handle_statefulset_image_update
-);
-
-impl_process_resources!(
-    process_daemonsets,
-    DaemonSet,
-    "daemonset",
-    crate::controller::handle_daemonset_image_update
-);
-
-async fn process_webhook_events(mut rx: EventReceiver) {
+/// Value of the `registry` metric label for an event's registry (Docker Hub when unset)
+fn registry_label(registry: &str) -> &str {
+    if registry.is_empty() {
+        "docker.io"
+    } else {
+        registry
+    }
+}
+
+async fn process_webhook_events(mut rx: EventReceiver, retry_tx: retry::RetrySender) {
     info!("Starting webhook event processor");
 
     // Create Kubernetes client
@@ -322,21 +736,35 @@ async fn process_webhook_events(mut rx: EventReceiver) {
     let policy_engine = Arc::new(PolicyEngine);
 
     while let Some(event) = rx.recv().await {
-        info!("Processing image push event: {}", event.full_image());
+        WEBHOOK_EVENT_QUEUE_DEPTH
+            .with_label_values(&["images"])
+            .set(rx.len() as i64);
+
+        // Root span of the update flow: policy evaluation, UpdateRequest creation and the
+        // rollout are traced beneath it
+        let span = info_span!("image_event", image = %event.full_image());
+        span.in_scope(|| info!("Processing image push event: {}", event.full_image()));
 
-        if let Err(e) = process_image_push_event(&client, &policy_engine, &event).await {
+        let processed = process_image_push_event(&client, &policy_engine, &event).instrument(span);
+        if let Err(e) = provenance::scope(event.source.clone(), processed).await {
             error!("Failed to process image push event: {}", e);
+            let _ = retry_tx.send(retry::Failure {
+                event: retry::QueuedEvent::Image(event),
+                error: e.to_string(),
+            });
             continue;
         }
 
-        WEBHOOK_EVENTS_PROCESSED.inc();
+        WEBHOOK_EVENTS_PROCESSED
+            .with_label_values(&[registry_label(&event.registry)])
+            .inc();
     }
 
     warn!("Webhook event processor stopped");
 }
 
 /// Process Helm chart push events
-async fn process_chart_events(mut rx: ChartEventReceiver) {
+async fn process_chart_events(mut rx: ChartEventReceiver, retry_tx: retry::RetrySender) {
     info!("Starting chart event processor");
 
     // Create Kubernetes client
@@ -351,18 +779,36 @@ async fn process_chart_events(mut rx: ChartEventReceiver) {
     let policy_engine = Arc::new(PolicyEngine);
 
     while let Some(event) = rx.recv().await {
-        info!(
-            "Processing Helm chart push event: {} version {}",
-            event.base_oci_url(),
-            event.version
+        WEBHOOK_EVENT_QUEUE_DEPTH
+            .with_label_values(&["charts"])
+            .set(rx.len() as i64);
+
+        let span = info_span!(
+            "chart_event",
+            chart = %event.base_oci_url(),
+            version = %event.version
         );
+        span.in_scope(|| {
+            info!(
+                "Processing Helm chart push event: {} version {}",
+                event.base_oci_url(),
+                event.version
+            )
+        });
 
-        if let Err(e) = process_chart_push_event(&client, &policy_engine, &event).await {
+        let processed = process_chart_push_event(&client, &policy_engine, &event).instrument(span);
+        if let Err(e) = provenance::scope(event.source.clone(), processed).await {
             error!("Failed to process chart push event: {}", e);
+            let _ = retry_tx.send(retry::Failure {
+                event: retry::QueuedEvent::Chart(event),
+                error: e.to_string(),
+            });
             continue;
         }
 
-        WEBHOOK_EVENTS_PROCESSED.inc();
+        WEBHOOK_EVENTS_PROCESSED
+            .with_label_values(&[registry_label(&event.registry)])
+            .inc();
     }
 
     warn!("Chart event processor stopped");
@@ -377,17 +823,16 @@ async fn process_chart_push_event(
     // Import HelmRelease and HelmRepository types
     use crate::models::{HelmRelease, HelmRepository};
 
-    // Query all HelmReleases
-    let helm_releases: Api<HelmRelease> = Api::all(client.clone());
-    let release_list = helm_releases.list(&Default::default()).await?;
+    // Query HelmReleases in watched namespaces
+    let release_list = stores::list::<HelmRelease>(client).await?;
 
     debug!(
         "Checking {} HelmReleases for matching charts",
-        release_list.items.len()
+        release_list.len()
     );
 
     // For each HelmRelease, check if it uses this chart
-    for helm_release in release_list.items {
+    for helm_release in release_list {
         // Check if release has headwind annotations
         let annotations = match &helm_release.metadata.annotations {
             Some(ann) => ann,
@@ -499,177 +944,250 @@ async fn process_image_push_event(
     policy_engine: &Arc<PolicyEngine>,
     event: &ImagePushEvent,
 ) -> Result<()> {
-    // Query all deployments
-    let deployments: Api<Deployment> = Api::all(client.clone());
-    let deployment_list = deployments.list(&Default::default()).await?;
+    // Label-selector based policies, merged into each workload's annotations
+    let targets = list_update_targets(client).await;
+    // Policies from Flux ImagePolicies, when the compatibility mode is enabled
+    let image_policies = list_image_policies(client).await;
 
-    debug!(
-        "Checking {} deployments for matching images",
-        deployment_list.items.len()
-    );
+    process_workloads::<Deployment>(client, policy_engine, event, &targets, &image_policies)
+        .await?;
+    process_workloads::<StatefulSet>(client, policy_engine, event, &targets, &image_policies)
+        .await?;
+    process_workloads::<DaemonSet>(client, policy_engine, event, &targets, &image_policies).await?;
+    process_workloads::<CronJob>(client, policy_engine, event, &targets, &image_policies).await?;
 
-    for deployment in deployment_list.items {
-        // Check if deployment has headwind annotations
-        let annotations = match &deployment.metadata.annotations {
-            Some(ann) => ann,
-            None => continue,
-        };
+    // Process images pinned in HelmRelease values
+    process_helmrelease_values(client, policy_engine, event).await?;
 
-        // Skip if no policy annotation
+    Ok(())
+}
+
+/// Match a push event against the images HelmReleases pin in their values
+/// (`headwind.sh/values-image-paths`)
+async fn process_helmrelease_values(
+    client: &Client,
+    policy_engine: &Arc<PolicyEngine>,
+    event: &ImagePushEvent,
+) -> Result<()> {
+    use crate::helm::values;
+    use crate::models::HelmRelease;
+
+    // HelmReleases are optional; without the Flux CRDs there is nothing to match
+    let release_list = match stores::list::<HelmRelease>(client).await {
+        Ok(releases) => releases,
+        Err(e) => {
+            debug!("Not checking HelmRelease values: {}", e);
+            return Ok(());
+        },
+    };
+
+    for helm_release in release_list {
+        let Some(annotations) = &helm_release.metadata.annotations else {
+            continue;
+        };
+        let Some(paths) = annotations.get(annotations::VALUES_IMAGE_PATHS) else {
+            continue;
+        };
         if !annotations.contains_key(annotations::POLICY) {
             continue;
         }
 
-        // Parse policy
         let policy = match parse_policy_from_annotations(annotations) {
             Ok(p) => p,
             Err(e) => {
                 warn!(
-                    "Failed to parse policy for deployment {}: {}",
-                    deployment.name_any(),
+                    "Failed to parse policy for HelmRelease {}: {}",
+                    helm_release.name_any(),
                     e
                 );
                 continue;
             },
         };
-
-        // Check event source - only process webhook events if event_source is "webhook" or "both"
         if policy.event_source != EventSource::Webhook && policy.event_source != EventSource::Both {
-            debug!(
-                "Skipping deployment {} - event source is {:?}, not webhook",
-                deployment.name_any(),
-                policy.event_source
-            );
             continue;
         }
 
-        // Check each container
-        let spec = match deployment.spec.as_ref() {
-            Some(s) => s,
-            None => continue,
-        };
-
-        let template_spec = match spec.template.spec.as_ref() {
-            Some(s) => s,
-            None => continue,
-        };
-
-        for container in &template_spec.containers {
-            // Skip containers not in the tracked images list (if specified)
-            if !policy.images.is_empty() && !policy.images.contains(&container.name) {
-                continue;
-            }
-
-            let current_image = match container.image.as_ref() {
-                Some(img) => img,
-                None => continue,
-            };
-
-            // Parse the current image to extract name and registry
-            let (image_name, current_tag) = match parse_image_full(current_image) {
-                Ok(parts) => parts,
-                Err(e) => {
-                    warn!("Failed to parse image {}: {}", current_image, e);
-                    continue;
-                },
-            };
-
-            // Check if this container uses the image from the webhook event
-            let matches = images_match(&event.registry, &event.repository, &image_name);
-            debug!(
-                "Image match check: event=({}, {}) deployment={} => {}",
-                event.registry, event.repository, image_name, matches
-            );
-            if !matches {
+        let images = values::find_images(
+            helm_release.spec.values.as_ref(),
+            &values::parse_paths(paths),
+        );
+        for values_image in images {
+            let (image_name, current_tag) = parse_image_full(&values_image.image)?;
+            if !images_match(&event.registry, &event.repository, &image_name)
+                || current_tag == event.tag
+            {
                 continue;
             }
 
             info!(
-                "Found matching deployment {}/{} container {} using {}",
-                deployment.namespace().unwrap_or_default(),
-                deployment.name_any(),
-                container.name,
-                current_image
+                "Found matching HelmRelease {}/{} values path .{} using {}",
+                helm_release.namespace().unwrap_or_default(),
+                helm_release.name_any(),
+                values_image.path,
+                values_image.image
             );
 
-            // Build the new image tag
-            let new_image = format_image(&event.registry, &event.repository, &event.tag);
-
-            // Skip if it's the same version
-            if current_tag == event.tag {
-                debug!(
-                    "Container {} already using tag {}, skipping",
-                    container.name, event.tag
-                );
-                continue;
-            }
-
-            // Call the update handler
-            if let Err(e) = crate::controller::handle_deployment_image_update(
-                client.clone(),
-                policy_engine.clone(),
-                &deployment,
-                &policy,
-                &container.name,
-                current_image,
-                &new_image,
+            if let Err(e) = crate::controller::handle_helm_values_image_update(
+                client,
+                policy_engine,
+                &helm_release,
+                &values_image,
+                &event.tag,
             )
             .await
             {
                 error!(
-                    "Failed to handle image update for {}/{}: {}",
-                    deployment.namespace().unwrap_or_default(),
-                    deployment.name_any(),
+                    "Failed to handle image update for HelmRelease {}/{}: {}",
+                    helm_release.namespace().unwrap_or_default(),
+                    helm_release.name_any(),
                     e
                 );
             }
         }
     }
 
-    // Process StatefulSets
-    process_statefulsets(client, policy_engine, event).await?;
+    Ok(())
+}
 
-    // Process DaemonSets
-    process_daemonsets(client, policy_engine, event).await?;
+/// Version a workload moves to for a push event: the tag, followed by the digest for
+/// workloads with `headwind.sh/pin-digest`, `headwind.sh/channel-tag` or the `digest`
+/// policy, and for restarted workloads (`headwind.sh/update-mode: restart`) when their own
+/// tag was pushed again. Events without a digest (Docker Hub) are resolved against the
+/// registry. Returns None when a pinned digest can't be resolved, or when the workload
+/// follows a channel tag other than the event's.
+async fn event_version(
+    client: &Client,
+    policy: &ResourcePolicy,
+    event: &ImagePushEvent,
+    image_name: &str,
+    current_tag: &str,
+    namespace: &str,
+) -> Option<String> {
+    let repushed = policy.update_mode == UpdateMode::Restart
+        && current_tag.split('@').next() == Some(event.tag.as_str());
+    match &policy.channel_tag {
+        Some(channel) if *channel != event.tag => return None,
+        Some(_) => {},
+        None if !policy.pin_digest && policy.policy != UpdatePolicy::Digest && !repushed => {
+            return Some(event.tag.clone());
+        },
+        None => {},
+    }
 
-    Ok(())
+    let digest = match &event.digest {
+        Some(digest) => digest.clone(),
+        None => {
+            let image = format!("{}:{}", image_name, event.tag);
+            match crate::polling::resolve_digest(client, &image, namespace).await {
+                Ok(digest) => digest,
+                Err(e) => {
+                    warn!("Failed to resolve digest of {} for pinning: {}", image, e);
+                    return None;
+                },
+            }
+        },
+    };
+
+    Some(format!("{}@{}", event.tag, digest))
 }
 
 /// Parse image into (full_name, tag)
 /// Examples:
 ///   "nginx:1.25.0" -> ("nginx", "1.25.0")
 ///   "gcr.io/project/image:v1.0" -> ("gcr.io/project/image", "v1.0")
+///   "nginx:1.25.0@sha256:..." -> ("nginx", "1.25.0@sha256:...")
 fn parse_image_full(image: &str) -> Result<(String, String)> {
-    match image.rsplit_once(':') {
-        Some((name, tag)) => {
-            // Check if the part after ':' looks like a port
-            if tag.contains('/') {
-                Ok((image.to_string(), "latest".to_string()))
-            } else {
-                Ok((name.to_string(), tag.to_string()))
+    match split_image(image) {
+        Some((name, tag)) => Ok((name.to_string(), tag.to_string())),
+        // No tag, or the part after ':' is a registry port
+        None => Ok((
+            image.split('@').next().unwrap_or(image).to_string(),
+            "latest".to_string(),
+        )),
+    }
+}
+
+/// Registry hosts that always refer to Docker Hub
+const DEFAULT_DOCKER_HUB_ALIASES: &[&str] = &[
+    "docker.io",
+    "index.docker.io",
+    "registry-1.docker.io",
+    "registry.hub.docker.com",
+];
+
+/// Registry hosts (or host/path prefixes) treated as equivalent to Docker Hub.
+///
+/// Defaults to the well-known Docker Hub hosts. Additional entries, such as pull-through
+/// cache mirrors (`mirror.example.com` or `harbor.example.com/dockerhub`), can be supplied
+/// as a comma-separated list in `HEADWIND_DOCKER_HUB_ALIASES`.
+static DOCKER_HUB_ALIASES: once_cell::sync::Lazy<Vec<String>> = once_cell::sync::Lazy::new(|| {
+    parse_docker_hub_aliases(std::env::var("HEADWIND_DOCKER_HUB_ALIASES").ok().as_deref())
+});
+
+/// Build the Docker Hub alias table from the defaults plus an optional comma-separated list.
+/// Longer entries are sorted first so path-prefixed mirrors win over bare hosts.
+fn parse_docker_hub_aliases(extra: Option<&str>) -> Vec<String> {
+    let mut aliases: Vec<String> = DEFAULT_DOCKER_HUB_ALIASES
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    if let Some(extra) = extra {
+        for alias in extra.split(',') {
+            let alias = alias.trim().trim_end_matches('/').to_lowercase();
+            if !alias.is_empty() && !aliases.contains(&alias) {
+                aliases.push(alias);
             }
-        },
-        None => Ok((image.to_string(), "latest".to_string())),
+        }
     }
+
+    aliases.sort_by_key(|a| std::cmp::Reverse(a.len()));
+    aliases
 }
 
-/// Check if two images match (handling registry prefixes)
-fn images_match(event_registry: &str, event_repository: &str, deployment_image: &str) -> bool {
-    use tracing::debug;
+/// Canonicalize an image name (without tag or digest) to `registry/repository` form.
+///
+/// Docker Hub images are rewritten to `docker.io/<namespace>/<name>`, adding the implicit
+/// `library/` namespace for official images and folding every configured alias into
+/// `docker.io`. Images from other registries are returned unchanged.
+///
+/// Examples:
+///   "nginx" -> "docker.io/library/nginx"
+///   "index.docker.io/bitnami/redis" -> "docker.io/bitnami/redis"
+///   "gcr.io/project/image" -> "gcr.io/project/image"
+fn canonical_image_name(image: &str, aliases: &[String]) -> String {
+    let docker_hub = |repository: &str| {
+        if repository.contains('/') {
+            format!("docker.io/{}", repository)
+        } else {
+            format!("docker.io/library/{}", repository)
+        }
+    };
 
-    debug!(
-        "images_match: event_registry={}, event_repository={}, deployment_image={}",
-        event_registry, event_repository, deployment_image
-    );
+    let lowered = image.to_lowercase();
+    for alias in aliases {
+        if let Some(rest) = lowered
+            .strip_prefix(alias.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+        {
+            return docker_hub(&image[image.len() - rest.len()..]);
+        }
+    }
 
-    // Strip tag/digest from deployment image to get just the image name
+    match image.split_once('/') {
+        Some((first, _)) if first.contains('.') || first.contains(':') || first == "localhost" => {
+            image.to_string()
+        },
+        _ => docker_hub(image),
+    }
+}
+
+/// Strip the tag and digest from an image reference, keeping any registry port
+fn strip_tag_and_digest(image: &str) -> &str {
     // We need to be careful: "registry.example.com:5000/image:tag" should become "registry.example.com:5000/image"
     // Split by @ first for digests, then find the last : that's after a / for tags
-    let without_digest = deployment_image
-        .split('@')
-        .next()
-        .unwrap_or(deployment_image);
-    let deployment_image_name = if let Some(slash_pos) = without_digest.rfind('/') {
+    let without_digest = image.split('@').next().unwrap_or(image);
+    if let Some(slash_pos) = without_digest.rfind('/') {
         // If there's a slash, only consider colons after it for tag splitting
         if let Some(colon_pos) = without_digest[slash_pos..].rfind(':') {
             &without_digest[..slash_pos + colon_pos]
@@ -686,83 +1204,63 @@ fn images_match(event_registry: &str, event_repository: &str, deployment_image:
         }
     } else {
         without_digest
-    };
+    }
+}
 
-    // Normalize deployment image (remove docker.io prefix if present)
-    let normalized_deployment = if let Some(rest) = deployment_image_name.strip_prefix("docker.io/")
-    {
-        rest.to_string()
-    } else {
-        deployment_image_name.to_string()
-    };
+/// Check if two images match (handling registry prefixes and Docker Hub aliases)
+fn images_match(event_registry: &str, event_repository: &str, deployment_image: &str) -> bool {
+    images_match_with_aliases(
+        event_registry,
+        event_repository,
+        deployment_image,
+        &DOCKER_HUB_ALIASES,
+    )
+}
 
-    // Build event image based on registry
-    let event_image = if event_registry == "docker.io" {
-        // Docker Hub with explicit registry
-        event_repository.to_string()
-    } else if event_registry == "library" {
-        // Docker Hub official images - registry is sent as "library"
-        // This should match deployment images like "nginx" or "library/nginx"
-        event_repository.to_string()
-    } else if !event_registry.contains('.') && !event_registry.contains(':') {
-        // Likely a Docker Hub user/org (no domain or port)
-        event_repository.to_string()
+fn images_match_with_aliases(
+    event_registry: &str,
+    event_repository: &str,
+    deployment_image: &str,
+    aliases: &[String],
+) -> bool {
+    debug!(
+        "images_match: event_registry={}, event_repository={}, deployment_image={}",
+        event_registry, event_repository, deployment_image
+    );
+
+    // Build the event image. Docker Hub webhooks send "library" as the registry for official
+    // images, and a registry without a domain or port is a Docker Hub user/org.
+    let event_image = if event_registry.is_empty()
+        || event_registry == "library"
+        || (!event_registry.contains('.')
+            && !event_registry.contains(':')
+            && event_registry != "localhost")
+    {
+        format!("docker.io/{}", event_repository)
     } else {
-        // External registry (contains . or :)
         format!("{}/{}", event_registry, event_repository)
     };
 
-    debug!("  deployment_image_name: {}", deployment_image_name);
-    debug!("  normalized_deployment: {}", normalized_deployment);
-    debug!("  event_image: {}", event_image);
-
-    // For Docker Hub official images, also check with library/ prefix
-    // This handles several cases:
-    // 1. registry="library" (Docker Hub webhook sends this for official images)
-    // 2. registry="docker.io", repository without "/" (e.g., "nginx")
-    // 3. registry="docker.io", repository="library/nginx" (explicit library namespace)
-    let is_library_image = event_registry == "library"
-        || (event_registry == "docker.io" && !event_repository.contains('/'))
-        || (event_registry == "docker.io" && event_repository.starts_with("library/"));
-
-    if is_library_image {
-        // Match both "nginx" and "library/nginx" in deployment
-        let deployment_without_library = normalized_deployment
-            .strip_prefix("library/")
-            .unwrap_or(&normalized_deployment);
-        let event_without_library = event_repository
-            .strip_prefix("library/")
-            .unwrap_or(event_repository);
-
-        debug!(
-            "  Library path comparison: {} == {} => {}",
-            event_without_library,
-            deployment_without_library,
-            event_without_library == deployment_without_library
-        );
-        event_without_library == deployment_without_library
-    } else {
-        // Standard comparison
-        debug!(
-            "  Standard comparison: {} == {} => {}",
-            event_image,
-            normalized_deployment,
-            event_image == normalized_deployment
-        );
-        event_image == normalized_deployment
-    }
+    let event_canonical = canonical_image_name(&event_image, aliases);
+    let deployment_canonical =
+        canonical_image_name(strip_tag_and_digest(deployment_image), aliases);
+
+    debug!(
+        "  Canonical comparison: {} == {} => {}",
+        event_canonical,
+        deployment_canonical,
+        event_canonical == deployment_canonical
+    );
+    event_canonical == deployment_canonical
 }
 
-/// Format image with registry and tag
-fn format_image(registry: &str, repository: &str, tag: &str) -> String {
-    if registry == "docker.io" {
-        format!("{}:{}", repository, tag)
-    } else {
-        format!("{}/{}:{}", registry, repository, tag)
-    }
+/// The image a container moves to: its own repository, which may be a mirror of the
+/// registry the event came from, with the new tag
+fn updated_image(image_name: &str, tag: &str) -> String {
+    format!("{}:{}", image_name, tag)
 }
 
-fn parse_policy_from_annotations(
+pub(crate) fn parse_policy_from_annotations(
     annotations: &std::collections::BTreeMap<String, String>,
 ) -> Result<ResourcePolicy> {
     let mut policy = ResourcePolicy::default();
@@ -775,10 +1273,20 @@ fn parse_policy_from_annotations(
         policy.pattern = Some(pattern.clone());
     }
 
-    if let Some(require_approval) = annotations.get(annotations::REQUIRE_APPROVAL) {
-        policy.require_approval = require_approval.parse().unwrap_or(true);
+    if let Some(ignore_patterns) = annotations.get(annotations::IGNORE_PATTERN) {
+        policy.ignore_patterns = parse_ignore_patterns(ignore_patterns);
     }
 
+    policy.prerelease = PrereleasePolicy::from_annotations(annotations);
+    policy.pin_digest = parse_pin_digest(annotations);
+    policy.channel_tag = parse_channel_tag(annotations);
+    policy.update_mode = parse_update_mode(annotations);
+    policy.approval_ttl = parse_approval_ttl(annotations);
+    policy.required_approvals = parse_required_approvals(annotations);
+    policy.max_version = parse_max_version(annotations);
+
+    policy.require_approval = parse_require_approval(annotations);
+
     if let Some(interval) = annotations.get(annotations::MIN_UPDATE_INTERVAL) {
         policy.min_update_interval = interval.parse().ok();
     }
@@ -794,6 +1302,21 @@ fn parse_policy_from_annotations(
     Ok(policy)
 }
 
+/// Policies of the containers with per-container overrides (`headwind.sh/container.<name>.*`),
+/// by container name. Other containers follow the workload's policy.
+pub(crate) fn parse_container_policies(
+    annotations: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, ResourcePolicy>> {
+    parse_container_overrides(annotations)
+        .into_keys()
+        .map(|container| {
+            let policy =
+                parse_policy_from_annotations(&container_annotations(annotations, &container))?;
+            Ok((container, policy))
+        })
+        .collect()
+}
+
 fn extract_registry(repository: &str) -> String {
     if repository.contains('/') {
         let parts: Vec<&str> = repository.splitn(2, '/').collect();
@@ -808,6 +1331,190 @@ fn extract_registry(repository: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_enqueue_rejects_when_full() {
+        let (tx, mut rx) = mpsc::channel(1);
+
+        assert!(enqueue("registry", "test", &tx, 1).is_ok());
+        assert_eq!(
+            enqueue("registry", "test", &tx, 2),
+            Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                "Event queue full, retry later"
+            ))
+        );
+
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert!(enqueue("registry", "test", &tx, 3).is_ok());
+
+        drop(rx);
+        assert_eq!(
+            enqueue("registry", "test", &tx, 4),
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to process event"))
+        );
+    }
+
+    fn ghcr_payload(action: &str, tag: &str, config_media_type: &str) -> GhcrWebhook {
+        serde_json::from_value(serde_json::json!({
+            "action": action,
+            "package": {
+                "name": "Web",
+                "package_type": "CONTAINER",
+                "owner": { "login": "Example" },
+                "package_version": {
+                    "version": "sha256:1111",
+                    "package_url": format!("ghcr.io/example/web:{}", tag),
+                    "container_metadata": {
+                        "tag": { "name": tag, "digest": "sha256:2222" },
+                        "manifest": {
+                            "config": { "media_type": config_media_type }
+                        }
+                    }
+                },
+                "registry": { "url": "https://ghcr.io" }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ghcr_image_push() {
+        let payload = ghcr_payload(
+            "published",
+            "1.5.0",
+            "application/vnd.oci.image.config.v1+json",
+        );
+        match ghcr_push_event(&payload) {
+            Some(GhcrPush::Image(event)) => {
+                assert_eq!(event.registry, "ghcr.io");
+                assert_eq!(event.repository, "example/web");
+                assert_eq!(event.tag, "1.5.0");
+                assert_eq!(event.digest.as_deref(), Some("sha256:2222"));
+            },
+            other => panic!("expected image push, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ghcr_chart_push() {
+        let payload = ghcr_payload(
+            "published",
+            "0.3.1",
+            "application/vnd.cncf.helm.config.v1+json",
+        );
+        match ghcr_push_event(&payload) {
+            Some(GhcrPush::Chart(event)) => {
+                assert_eq!(event.full_oci_url(), "oci://ghcr.io/example/web:0.3.1");
+            },
+            other => panic!("expected chart push, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ghcr_ignored_payloads() {
+        let untagged = ghcr_payload("published", "", "application/vnd.oci.image.config.v1+json");
+        assert!(ghcr_push_event(&untagged).is_none());
+
+        let mut npm = ghcr_payload("published", "1.0.0", "");
+        npm.package.package_type = "npm".to_string();
+        assert!(ghcr_push_event(&npm).is_none());
+
+        // Legacy registry_package events use the same shape
+        let legacy: GhcrWebhook = serde_json::from_value(serde_json::json!({
+            "action": "published",
+            "registry_package": {
+                "name": "web",
+                "package_type": "container",
+                "owner": { "login": "example" },
+                "package_version": {
+                    "container_metadata": { "tag": { "name": "2.0.0" } }
+                }
+            }
+        }))
+        .unwrap();
+        assert!(matches!(ghcr_push_event(&legacy), Some(GhcrPush::Image(_))));
+    }
+
+    #[test]
+    fn test_authorize() {
+        let extensions = Extensions::new();
+        let body = br#"{"events":[]}"#;
+
+        let tokens = BTreeMap::from([("harbor-a".to_string(), "9f86d081".to_string())]);
+
+        assert!(
+            authorize(
+                "registry",
+                None,
+                None,
+                &tokens,
+                &extensions,
+                &HeaderMap::new(),
+                body
+            )
+            .is_ok()
+        );
+
+        let before = WEBHOOK_REQUESTS_REJECTED
+            .with_label_values(&["registry", "missing_credentials"])
+            .get();
+        let rejected = authorize(
+            "registry",
+            None,
+            Some("s3cret"),
+            &tokens,
+            &extensions,
+            &HeaderMap::new(),
+            body,
+        );
+        assert_eq!(rejected.unwrap_err().0, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            WEBHOOK_REQUESTS_REJECTED
+                .with_label_values(&["registry", "missing_credentials"])
+                .get(),
+            before + 1
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            auth::SIGNATURE_HEADER,
+            format!("sha256={}", hex::encode(auth::hmac_sha256(b"s3cret", body)))
+                .parse()
+                .unwrap(),
+        );
+        assert!(
+            authorize(
+                "registry",
+                None,
+                Some("s3cret"),
+                &tokens,
+                &extensions,
+                &headers,
+                body
+            )
+            .is_ok()
+        );
+
+        // A path token replaces the endpoint secret
+        let empty = HeaderMap::new();
+        let path_authorize = |token| {
+            authorize(
+                "registry",
+                Some(token),
+                Some("s3cret"),
+                &tokens,
+                &extensions,
+                &empty,
+                body,
+            )
+        };
+        assert!(path_authorize("9f86d081").is_ok());
+        assert_eq!(
+            path_authorize("s3cret").unwrap_err(),
+            (StatusCode::UNAUTHORIZED, "Invalid webhook token")
+        );
+    }
+
     #[test]
     fn test_extract_registry() {
         assert_eq!(extract_registry("nginx"), "docker.io");
@@ -826,6 +1533,7 @@ mod tests {
             repository: "nginx".to_string(),
             tag: "latest".to_string(),
             digest: None,
+            source: None,
         };
         assert_eq!(event.full_image(), "nginx:latest");
 
@@ -834,6 +1542,7 @@ mod tests {
             repository: "project/image".to_string(),
             tag: "v1.0.0".to_string(),
             digest: None,
+            source: None,
         };
         assert_eq!(event2.full_image(), "gcr.io/project/image:v1.0.0");
     }
@@ -859,6 +1568,11 @@ mod tests {
         let (name, tag) = parse_image_full("localhost:5000/myimage").unwrap();
         assert_eq!(name, "localhost:5000/myimage");
         assert_eq!(tag, "latest");
+
+        // Pinned image keeps its digest with the tag
+        let (name, tag) = parse_image_full("ghcr.io/org/app:1.2.3@sha256:abc").unwrap();
+        assert_eq!(name, "ghcr.io/org/app");
+        assert_eq!(tag, "1.2.3@sha256:abc");
     }
 
     #[test]
@@ -903,21 +1617,145 @@ mod tests {
     }
 
     #[test]
-    fn test_format_image() {
-        // Docker Hub
-        assert_eq!(format_image("docker.io", "nginx", "1.25.0"), "nginx:1.25.0");
+    fn test_images_match_docker_hub_hosts() {
+        // Events from pull-through caches use different Docker Hub host strings
+        for host in ["index.docker.io", "registry-1.docker.io", "docker.io"] {
+            assert!(images_match(host, "library/nginx", "nginx:1.27.0"));
+            assert!(images_match(
+                host,
+                "nginx",
+                "docker.io/library/nginx:1.27.0"
+            ));
+            assert!(images_match(host, "bitnami/redis", "bitnami/redis:7.2"));
+            assert!(images_match(
+                host,
+                "bitnami/redis",
+                "index.docker.io/bitnami/redis"
+            ));
+            assert!(!images_match(host, "nginx", "gcr.io/project/nginx"));
+        }
+
+        // Workloads referencing an alias host match plain Docker Hub events
+        assert!(images_match(
+            "docker.io",
+            "nginx",
+            "registry-1.docker.io/library/nginx@sha256:abc"
+        ));
+        // User/org images never match official images of the same name
+        assert!(!images_match("docker.io", "bitnami/nginx", "nginx"));
+    }
+
+    #[test]
+    fn test_images_match_configured_mirror() {
+        let aliases =
+            parse_docker_hub_aliases(Some("mirror.example.com, harbor.example.com/dockerhub/"));
+
+        assert!(images_match_with_aliases(
+            "mirror.example.com",
+            "library/nginx",
+            "nginx:1.27.0",
+            &aliases
+        ));
+        assert!(images_match_with_aliases(
+            "harbor.example.com",
+            "dockerhub/library/nginx",
+            "docker.io/nginx",
+            &aliases
+        ));
+        assert!(images_match_with_aliases(
+            "docker.io",
+            "bitnami/redis",
+            "harbor.example.com/dockerhub/bitnami/redis:7.2",
+            &aliases
+        ));
+        // Other projects on the same mirror host are not Docker Hub
+        assert!(!images_match_with_aliases(
+            "harbor.example.com",
+            "internal/nginx",
+            "nginx",
+            &aliases
+        ));
+        // Mirrors are only honoured when configured
+        assert!(!images_match(
+            "mirror.example.com",
+            "library/nginx",
+            "nginx:1.27.0"
+        ));
+    }
+
+    #[test]
+    fn test_canonical_image_name() {
+        let aliases = parse_docker_hub_aliases(None);
+
+        assert_eq!(
+            canonical_image_name("nginx", &aliases),
+            "docker.io/library/nginx"
+        );
+        assert_eq!(
+            canonical_image_name("library/nginx", &aliases),
+            "docker.io/library/nginx"
+        );
+        assert_eq!(
+            canonical_image_name("INDEX.DOCKER.IO/bitnami/redis", &aliases),
+            "docker.io/bitnami/redis"
+        );
+        assert_eq!(
+            canonical_image_name("registry.hub.docker.com/library/nginx", &aliases),
+            "docker.io/library/nginx"
+        );
+        assert_eq!(
+            canonical_image_name("gcr.io/project/image", &aliases),
+            "gcr.io/project/image"
+        );
         assert_eq!(
-            format_image("docker.io", "library/nginx", "latest"),
-            "library/nginx:latest"
+            canonical_image_name("localhost:5000/myimage", &aliases),
+            "localhost:5000/myimage"
         );
+        assert_eq!(
+            canonical_image_name("localhost/myimage", &aliases),
+            "localhost/myimage"
+        );
+    }
 
-        // Private registry
+    #[test]
+    fn test_parse_docker_hub_aliases() {
+        let aliases = parse_docker_hub_aliases(Some(" Mirror.Example.com/ ,, docker.io"));
+        assert!(aliases.contains(&"mirror.example.com".to_string()));
+        assert!(aliases.contains(&"index.docker.io".to_string()));
+        assert_eq!(aliases.iter().filter(|a| *a == "docker.io").count(), 1);
+        // Longest entries first
+        assert!(aliases.windows(2).all(|w| w[0].len() >= w[1].len()));
+    }
+
+    #[test]
+    fn test_updated_image_keeps_repository() {
+        let aliases = parse_docker_hub_aliases(Some("harbor.example.com/dockerhub"));
+
+        // A Docker Hub push updates containers pulling through a mirror from the mirror
+        let current_image = "harbor.example.com/dockerhub/bitnami/redis:7.2";
+        assert!(images_match_with_aliases(
+            "docker.io",
+            "bitnami/redis",
+            current_image,
+            &aliases
+        ));
+        let (image_name, _) = parse_image_full(current_image).unwrap();
         assert_eq!(
-            format_image("gcr.io", "project/image", "v1.0"),
-            "gcr.io/project/image:v1.0"
+            updated_image(&image_name, "7.4"),
+            "harbor.example.com/dockerhub/bitnami/redis:7.4"
         );
+
+        // The container's spelling of the image is kept
+        let (image_name, _) = parse_image_full("nginx:1.25.0").unwrap();
+        assert_eq!(updated_image(&image_name, "1.26.0"), "nginx:1.26.0");
+        let (image_name, _) = parse_image_full("index.docker.io/library/nginx:1.25.0").unwrap();
         assert_eq!(
-            format_image("registry.example.com:5000", "myimage", "dev"),
+            updated_image(&image_name, "1.26.0"),
+            "index.docker.io/library/nginx:1.26.0"
+        );
+        let (image_name, _) = parse_image_full("registry.example.com:5000/myimage").unwrap();
+        assert_eq!(
+            updated_image(&image_name, "dev"),
             "registry.example.com:5000/myimage:dev"
         );
     }
@@ -940,4 +1778,44 @@ mod tests {
         assert!(!policy.require_approval);
         assert_eq!(policy.images, vec!["nginx", "redis"]);
     }
+
+    #[test]
+    fn test_parse_container_policies() {
+        use std::collections::BTreeMap;
+
+        let annotations: BTreeMap<String, String> = [
+            (annotations::POLICY, "minor"),
+            (annotations::REQUIRE_APPROVAL, "false"),
+            ("headwind.sh/container.nginx.policy", "patch"),
+            ("headwind.sh/container.nginx.require-approval", "true"),
+            ("headwind.sh/container.sidecar.policy", "none"),
+            // Not a per-container setting
+            ("headwind.sh/container.app.dry-run", "true"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        assert!(has_policy(&annotations));
+        let policies = parse_container_policies(&annotations).unwrap();
+        assert_eq!(policies.len(), 2);
+        assert_eq!(policies["nginx"].policy, UpdatePolicy::Patch);
+        assert!(policies["nginx"].require_approval);
+        assert_eq!(policies["sidecar"].policy, UpdatePolicy::None);
+        assert!(!policies["sidecar"].require_approval);
+
+        // A container override alone is enough to track the workload
+        let only_container: BTreeMap<String, String> = [(
+            "headwind.sh/container.nginx.policy".to_string(),
+            "patch".to_string(),
+        )]
+        .into();
+        assert!(has_policy(&only_container));
+        assert_eq!(
+            parse_policy_from_annotations(&only_container)
+                .unwrap()
+                .policy,
+            UpdatePolicy::None
+        );
+    }
 }
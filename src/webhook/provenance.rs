@@ -0,0 +1,119 @@
+//! Provenance of detected updates.
+//!
+//! Push events carry the [`SourceEvent`] they were received from. The event processors run
+//! the handling of each event inside [`scope`], and [`crate::controller::workload::submit_update_request`]
+//! records [`current`] in `spec.source` of the UpdateRequests it creates, so every proposed
+//! change can be traced back to the webhook, Pub/Sub message or poll cycle that found it.
+
+use crate::models::crd::SourceEvent;
+use axum::extract::ConnectInfo;
+use axum::http::Extensions;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::net::SocketAddr;
+
+tokio::task_local! {
+    static SOURCE: Option<SourceEvent>;
+}
+
+/// Run `future` with `source` as the provenance of the UpdateRequests it creates
+pub async fn scope<F: Future>(source: Option<SourceEvent>, future: F) -> F::Output {
+    SOURCE.scope(source, future).await
+}
+
+/// Provenance of the event being processed. Updates found outside of event processing were
+/// detected while reconciling their target.
+pub fn current() -> SourceEvent {
+    SOURCE
+        .try_with(Clone::clone)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| SourceEvent {
+            kind: "reconcile".to_string(),
+            received_at: Some(Utc::now()),
+            ..Default::default()
+        })
+}
+
+/// Remote address of a webhook request
+pub fn remote_addr(extensions: &Extensions) -> Option<String> {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.to_string())
+}
+
+/// Event received on the webhook `endpoint`
+pub fn webhook(endpoint: &str, extensions: &Extensions, digest: Option<&str>) -> SourceEvent {
+    SourceEvent {
+        kind: "webhook".to_string(),
+        origin: Some(endpoint.to_string()),
+        sender: remote_addr(extensions),
+        digest: digest.map(str::to_string),
+        received_at: Some(Utc::now()),
+        ..Default::default()
+    }
+}
+
+/// Message pulled from a Pub/Sub `subscription`
+pub fn pubsub(subscription: &str, digest: Option<&str>) -> SourceEvent {
+    SourceEvent {
+        kind: "pubsub".to_string(),
+        origin: Some(subscription.to_string()),
+        digest: digest.map(str::to_string),
+        received_at: Some(Utc::now()),
+        ..Default::default()
+    }
+}
+
+/// Event consumed from the event bus `subject`
+pub fn eventbus(subject: &str, digest: Option<&str>) -> SourceEvent {
+    SourceEvent {
+        kind: "eventbus".to_string(),
+        origin: Some(subject.to_string()),
+        digest: digest.map(str::to_string),
+        received_at: Some(Utc::now()),
+        ..Default::default()
+    }
+}
+
+/// Version found on `registry` by the poll cycle started at `cycle`
+pub fn polling(registry: &str, cycle: DateTime<Utc>, digest: Option<&str>) -> SourceEvent {
+    SourceEvent {
+        kind: "polling".to_string(),
+        origin: Some(registry.to_string()),
+        poll_cycle: Some(cycle),
+        digest: digest.map(str::to_string),
+        received_at: Some(Utc::now()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_current() {
+        assert_eq!(current().kind, "reconcile");
+
+        let source = pubsub("projects/p/subscriptions/headwind", Some("sha256:abc"));
+        let inner = scope(Some(source.clone()), async { current() }).await;
+        assert_eq!(inner, source);
+
+        let cycle = Utc::now();
+        let polled = polling("ghcr.io", cycle, None);
+        assert_eq!(polled.poll_cycle, Some(cycle));
+        assert_eq!(scope(None, async { current() }).await.kind, "reconcile");
+    }
+
+    #[test]
+    fn test_webhook() {
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 7], 51234))));
+        let source = webhook("dockerhub", &extensions, None);
+        assert_eq!(source.kind, "webhook");
+        assert_eq!(source.origin.as_deref(), Some("dockerhub"));
+        assert_eq!(source.sender.as_deref(), Some("10.0.0.7:51234"));
+        assert!(source.received_at.is_some());
+    }
+}
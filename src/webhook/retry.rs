@@ -0,0 +1,402 @@
+//! Retry queue of push events whose processing failed.
+//!
+//! A failed event is retried after `HEADWIND_EVENT_RETRY_BASE_DELAY` seconds (default 10),
+//! doubling the delay after every further failure up to 15 minutes. After
+//! `HEADWIND_EVENT_RETRY_MAX_ATTEMPTS` failed attempts (default 8, including the first) the
+//! event is dead-lettered.
+//!
+//! Every queued event is mirrored to a `FailedEvent` in the operator namespace: pending
+//! retries are picked up again after a restart, successfully retried events are deleted and
+//! dead letters stay behind for inspection. Without the CRD the queue is kept in memory only.
+
+use super::{process_chart_push_event, process_image_push_event, provenance, registry_label};
+use crate::config;
+use crate::metrics::{
+    WEBHOOK_EVENT_RETRIES, WEBHOOK_EVENT_RETRY_QUEUE, WEBHOOK_EVENTS_DEAD_LETTERED,
+    WEBHOOK_EVENTS_PROCESSED,
+};
+use crate::models::webhook::{ChartPushEvent, ImagePushEvent};
+use crate::models::{FailedEvent, FailedEventPhase, FailedEventSpec, FailedEventStatus};
+use crate::policy::PolicyEngine;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use kube::api::{DeleteParams, ListParams, ObjectMeta, Patch, PatchParams, PostParams};
+use kube::{Api, Client, ResourceExt};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{Instrument, debug, error, info, info_span, warn};
+
+/// Longest delay between two attempts
+const MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+
+/// A push event handed to the retry queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueuedEvent {
+    Image(ImagePushEvent),
+    Chart(ChartPushEvent),
+}
+
+impl QueuedEvent {
+    fn kind(&self) -> &'static str {
+        match self {
+            QueuedEvent::Image(_) => "image",
+            QueuedEvent::Chart(_) => "chart",
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            QueuedEvent::Image(event) => event.full_image(),
+            QueuedEvent::Chart(event) => event.full_oci_url(),
+        }
+    }
+
+    fn registry(&self) -> &str {
+        match self {
+            QueuedEvent::Image(event) => &event.registry,
+            QueuedEvent::Chart(event) => &event.registry,
+        }
+    }
+
+    fn to_spec(&self) -> FailedEventSpec {
+        match self {
+            QueuedEvent::Image(event) => FailedEventSpec {
+                image: Some(event.clone()),
+                chart: None,
+            },
+            QueuedEvent::Chart(event) => FailedEventSpec {
+                image: None,
+                chart: Some(event.clone()),
+            },
+        }
+    }
+
+    fn from_spec(spec: &FailedEventSpec) -> Option<Self> {
+        match (&spec.image, &spec.chart) {
+            (Some(event), _) => Some(QueuedEvent::Image(event.clone())),
+            (None, Some(event)) => Some(QueuedEvent::Chart(event.clone())),
+            (None, None) => None,
+        }
+    }
+
+    async fn process(&self, client: &Client, policy_engine: &Arc<PolicyEngine>) -> Result<()> {
+        match self {
+            QueuedEvent::Image(event) => {
+                let processed = process_image_push_event(client, policy_engine, event)
+                    .instrument(info_span!("image_event", image = %event.full_image()));
+                provenance::scope(event.source.clone(), processed).await
+            },
+            QueuedEvent::Chart(event) => {
+                let processed =
+                    process_chart_push_event(client, policy_engine, event).instrument(info_span!(
+                        "chart_event",
+                        chart = %event.base_oci_url(),
+                        version = %event.version
+                    ));
+                provenance::scope(event.source.clone(), processed).await
+            },
+        }
+    }
+}
+
+/// An event whose first processing attempt failed
+#[derive(Debug)]
+pub struct Failure {
+    pub event: QueuedEvent,
+    pub error: String,
+}
+
+pub type RetrySender = mpsc::UnboundedSender<Failure>;
+
+/// Backoff and attempt limit of the queue
+#[derive(Debug, Clone, Copy)]
+struct RetrySettings {
+    max_attempts: u32,
+    base_delay: Duration,
+}
+
+impl RetrySettings {
+    fn from_env() -> Self {
+        let env_u64 = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            max_attempts: env_u64("HEADWIND_EVENT_RETRY_MAX_ATTEMPTS", 8) as u32,
+            base_delay: Duration::from_secs(env_u64("HEADWIND_EVENT_RETRY_BASE_DELAY", 10)),
+        }
+    }
+
+    /// Delay before the next attempt after `attempts` failed ones
+    fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(MAX_DELAY)
+    }
+}
+
+struct Entry {
+    event: QueuedEvent,
+    attempts: u32,
+    next_attempt: DateTime<Utc>,
+    /// Name of the FailedEvent mirroring the entry, once stored
+    name: Option<String>,
+}
+
+/// Spawn the retry worker and return the sender failed events are queued with
+pub fn start() -> RetrySender {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(run(rx));
+    tx
+}
+
+async fn run(mut rx: mpsc::UnboundedReceiver<Failure>) {
+    let client = match Client::try_default().await {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Failed to create Kubernetes client: {}", e);
+            return;
+        },
+    };
+
+    let policy_engine = Arc::new(PolicyEngine);
+    let api: Api<FailedEvent> = Api::namespaced(client.clone(), &config::operator_namespace());
+    let settings = RetrySettings::from_env();
+
+    let mut queue = load(&api).await;
+    if !queue.is_empty() {
+        info!(
+            "Resuming {} failed push event(s) from before the restart",
+            queue.len()
+        );
+    }
+    WEBHOOK_EVENT_RETRY_QUEUE.set(queue.len() as i64);
+
+    loop {
+        let wait = queue
+            .iter()
+            .map(|entry| entry.next_attempt)
+            .min()
+            .map(|next| (next - Utc::now()).to_std().unwrap_or_default())
+            .unwrap_or(MAX_DELAY);
+
+        tokio::select! {
+            failure = rx.recv() => {
+                let Some(failure) = failure else {
+                    break;
+                };
+                let entry = Entry {
+                    event: failure.event,
+                    attempts: 1,
+                    next_attempt: Utc::now(),
+                    name: None,
+                };
+                queue.extend(failed(&api, &settings, entry, failure.error).await);
+            },
+            _ = tokio::time::sleep(wait) => {
+                let now = Utc::now();
+                let (due, waiting): (Vec<_>, Vec<_>) =
+                    queue.drain(..).partition(|entry| entry.next_attempt <= now);
+                queue = waiting;
+
+                for mut entry in due {
+                    let kind = entry.event.kind();
+                    match entry.event.process(&client, &policy_engine).await {
+                        Ok(()) => {
+                            info!(
+                                "Processed {} push event {} on attempt {}",
+                                kind,
+                                entry.event.describe(),
+                                entry.attempts + 1
+                            );
+                            WEBHOOK_EVENT_RETRIES.with_label_values(&[kind, "success"]).inc();
+                            WEBHOOK_EVENTS_PROCESSED
+                                .with_label_values(&[registry_label(entry.event.registry())])
+                                .inc();
+                            delete(&api, &entry).await;
+                        },
+                        Err(e) => {
+                            WEBHOOK_EVENT_RETRIES.with_label_values(&[kind, "failure"]).inc();
+                            entry.attempts += 1;
+                            queue.extend(failed(&api, &settings, entry, e.to_string()).await);
+                        },
+                    }
+                }
+            },
+        }
+
+        WEBHOOK_EVENT_RETRY_QUEUE.set(queue.len() as i64);
+    }
+
+    warn!("Push event retry queue stopped");
+}
+
+/// Schedule the next attempt of a failed entry, or dead-letter it when its attempts are used up
+async fn failed(
+    api: &Api<FailedEvent>,
+    settings: &RetrySettings,
+    mut entry: Entry,
+    error: String,
+) -> Option<Entry> {
+    let now = Utc::now();
+    let mut status = FailedEventStatus {
+        phase: FailedEventPhase::Retrying,
+        attempts: entry.attempts,
+        last_error: Some(error.clone()),
+        last_attempt_time: Some(now),
+        next_attempt_time: None,
+    };
+
+    if entry.attempts >= settings.max_attempts {
+        error!(
+            "Giving up on {} push event {} after {} attempts: {}",
+            entry.event.kind(),
+            entry.event.describe(),
+            entry.attempts,
+            error
+        );
+        WEBHOOK_EVENTS_DEAD_LETTERED
+            .with_label_values(&[entry.event.kind()])
+            .inc();
+        status.phase = FailedEventPhase::DeadLettered;
+        store(api, &mut entry, &status).await;
+        return None;
+    }
+
+    let delay = settings.backoff(entry.attempts);
+    entry.next_attempt = now + chrono::Duration::from_std(delay).unwrap_or_default();
+    status.next_attempt_time = Some(entry.next_attempt);
+    warn!(
+        "Processing {} push event {} failed (attempt {}/{}), retrying in {}s: {}",
+        entry.event.kind(),
+        entry.event.describe(),
+        entry.attempts,
+        settings.max_attempts,
+        delay.as_secs(),
+        error
+    );
+    store(api, &mut entry, &status).await;
+    Some(entry)
+}
+
+/// Create or update the FailedEvent of an entry. Failures only cost persistence; the entry
+/// stays queued in memory.
+async fn store(api: &Api<FailedEvent>, entry: &mut Entry, status: &FailedEventStatus) {
+    if entry.name.is_none() {
+        let failed_event = FailedEvent {
+            metadata: ObjectMeta {
+                generate_name: Some(format!("{}-", entry.event.kind())),
+                ..Default::default()
+            },
+            spec: entry.event.to_spec(),
+            status: None,
+        };
+        match api.create(&PostParams::default(), &failed_event).await {
+            Ok(created) => entry.name = Some(created.name_any()),
+            Err(kube::Error::Api(e)) if e.code == 404 => {
+                debug!("FailedEvent CRD not installed, retry queue not persisted");
+                return;
+            },
+            Err(e) => {
+                warn!("Failed to store FailedEvent: {}", e);
+                return;
+            },
+        }
+    }
+
+    let Some(name) = &entry.name else {
+        return;
+    };
+    let patch = serde_json::json!({ "status": status });
+    if let Err(e) = api
+        .patch_status(name, &PatchParams::default(), &Patch::Merge(&patch))
+        .await
+    {
+        warn!("Failed to update FailedEvent {}: {}", name, e);
+    }
+}
+
+async fn delete(api: &Api<FailedEvent>, entry: &Entry) {
+    let Some(name) = &entry.name else {
+        return;
+    };
+    match api.delete(name, &DeleteParams::default()).await {
+        Ok(_) => {},
+        Err(kube::Error::Api(e)) if e.code == 404 => {},
+        Err(e) => warn!("Failed to delete FailedEvent {}: {}", name, e),
+    }
+}
+
+/// Entries of the FailedEvents still being retried
+async fn load(api: &Api<FailedEvent>) -> Vec<Entry> {
+    let failed_events = match api.list(&ListParams::default()).await {
+        Ok(list) => list.items,
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            debug!("FailedEvent CRD not installed, no failed events to resume");
+            return Vec::new();
+        },
+        Err(e) => {
+            warn!("Failed to list FailedEvents: {}", e);
+            return Vec::new();
+        },
+    };
+
+    failed_events
+        .into_iter()
+        .filter_map(|failed_event| {
+            let status = failed_event.status.clone().unwrap_or_default();
+            if status.phase != FailedEventPhase::Retrying {
+                return None;
+            }
+            Some(Entry {
+                event: QueuedEvent::from_spec(&failed_event.spec)?,
+                attempts: status.attempts.max(1),
+                next_attempt: status.next_attempt_time.unwrap_or_else(Utc::now),
+                name: Some(failed_event.name_any()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let settings = RetrySettings {
+            max_attempts: 8,
+            base_delay: Duration::from_secs(10),
+        };
+        assert_eq!(settings.backoff(1), Duration::from_secs(10));
+        assert_eq!(settings.backoff(2), Duration::from_secs(20));
+        assert_eq!(settings.backoff(4), Duration::from_secs(80));
+        assert_eq!(settings.backoff(8), MAX_DELAY);
+        assert_eq!(settings.backoff(100), MAX_DELAY);
+    }
+
+    #[test]
+    fn test_spec_round_trip() {
+        let image = QueuedEvent::Image(ImagePushEvent {
+            registry: "ghcr.io".to_string(),
+            repository: "org/app".to_string(),
+            tag: "1.2.3".to_string(),
+            digest: None,
+            source: None,
+        });
+        let chart = QueuedEvent::Chart(ChartPushEvent {
+            registry: "ghcr.io".to_string(),
+            repository: "org/charts/app".to_string(),
+            version: "2.0.0".to_string(),
+            digest: Some("sha256:abc".to_string()),
+            source: None,
+        });
+
+        assert_eq!(QueuedEvent::from_spec(&image.to_spec()), Some(image));
+        assert_eq!(QueuedEvent::from_spec(&chart.to_spec()), Some(chart));
+        assert_eq!(QueuedEvent::from_spec(&FailedEventSpec::default()), None);
+    }
+}
@@ -122,6 +122,37 @@ fn test_glob_policy_integration() {
     ));
 }
 
+#[test]
+fn test_regex_policy_integration() {
+    let pattern = Some(r"^v1\.2[0-9]\.\d+$");
+    assert!(should_update(
+        "v1.20.0",
+        "v1.21.4",
+        UpdatePolicy::Regex,
+        pattern
+    ));
+    assert!(!should_update(
+        "v1.20.0",
+        "v1.3.0",
+        UpdatePolicy::Regex,
+        pattern
+    ));
+    assert!(!should_update(
+        "v1.20.0",
+        "v1.21.4-rc.1",
+        UpdatePolicy::Regex,
+        pattern
+    ));
+
+    // Invalid expressions never update
+    assert!(!should_update(
+        "v1.0.0",
+        "v1.1.0",
+        UpdatePolicy::Regex,
+        Some("v1.(")
+    ));
+}
+
 #[test]
 fn test_force_policy_integration() {
     // Should always update, even to same version
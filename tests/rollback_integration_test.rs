@@ -17,6 +17,7 @@ fn test_update_history_entry_serialization() {
         timestamp: Utc::now(),
         update_request_name: Some("req-123".to_string()),
         approved_by: Some("webhook".to_string()),
+        healthy: false,
     };
 
     let json = serde_json::to_string(&entry).expect("Failed to serialize");
@@ -40,6 +41,7 @@ fn test_update_history_entry_without_optional_fields() {
         timestamp: Utc::now(),
         update_request_name: None,
         approved_by: None,
+        healthy: false,
     };
 
     let json = serde_json::to_string(&entry).expect("Failed to serialize");
@@ -66,6 +68,7 @@ fn test_update_history_add_entry() {
         timestamp: Utc::now(),
         update_request_name: None,
         approved_by: None,
+        healthy: false,
     };
 
     history.add_entry(entry1.clone());
@@ -78,6 +81,7 @@ fn test_update_history_add_entry() {
         timestamp: Utc::now(),
         update_request_name: None,
         approved_by: None,
+        healthy: false,
     };
 
     history.add_entry(entry2.clone());
@@ -98,6 +102,7 @@ fn test_update_history_get_previous_image() {
         timestamp: Utc::now(),
         update_request_name: None,
         approved_by: None,
+        healthy: false,
     };
 
     let entry2 = UpdateHistoryEntry {
@@ -106,6 +111,7 @@ fn test_update_history_get_previous_image() {
         timestamp: Utc::now(),
         update_request_name: None,
         approved_by: None,
+        healthy: false,
     };
 
     history.add_entry(entry1);
@@ -129,6 +135,7 @@ fn test_update_history_max_entries() {
             timestamp: Utc::now(),
             update_request_name: None,
             approved_by: None,
+            healthy: false,
         };
         history.add_entry(entry);
     }
@@ -151,6 +158,7 @@ fn test_update_history_multiple_containers() {
         timestamp: Utc::now(),
         update_request_name: None,
         approved_by: None,
+        healthy: false,
     };
 
     let entry2 = UpdateHistoryEntry {
@@ -159,6 +167,7 @@ fn test_update_history_multiple_containers() {
         timestamp: Utc::now(),
         update_request_name: None,
         approved_by: None,
+        healthy: false,
     };
 
     history.add_entry(entry1);
@@ -204,6 +213,7 @@ fn test_auto_rollback_config_defaults() {
     assert!(!config.enabled); // Default is disabled
     assert_eq!(config.timeout, 300); // 5 minutes default
     assert_eq!(config.retries, 3); // 3 retries default
+    assert!(config.metric_gates.is_empty());
 }
 
 #[test]
@@ -283,6 +293,7 @@ fn test_update_history_to_json() {
         timestamp: Utc::now(),
         update_request_name: Some("req-123".to_string()),
         approved_by: Some("admin".to_string()),
+        healthy: false,
     };
 
     history.add_entry(entry);
@@ -311,6 +322,7 @@ fn test_update_history_entry_camel_case_serialization() {
         timestamp: Utc::now(),
         update_request_name: Some("req-123".to_string()),
         approved_by: Some("admin".to_string()),
+        healthy: false,
     };
 
     let json = serde_json::to_string(&entry).expect("Failed to serialize");
@@ -333,6 +345,7 @@ fn test_get_entry_by_index() {
             timestamp: Utc::now(),
             update_request_name: None,
             approved_by: None,
+            healthy: false,
         };
         history.add_entry(entry);
     }
@@ -356,3 +369,32 @@ fn test_get_entry_by_index() {
     let beyond = history.get_entry_by_index("app", 10);
     assert!(beyond.is_none());
 }
+
+#[test]
+fn test_auto_rollback_config_metric_gates() {
+    let mut annotations = BTreeMap::new();
+    annotations.insert("headwind.sh/auto-rollback".to_string(), "true".to_string());
+    annotations.insert(
+        "headwind.sh/error-rate-query".to_string(),
+        r#"sum(rate(http_requests_total{app="api",code=~"5.."}[2m])) / sum(rate(http_requests_total{app="api"}[2m]))"#.to_string(),
+    );
+    annotations.insert("headwind.sh/max-error-rate".to_string(), "0.05".to_string());
+    // A query without a threshold is ignored
+    annotations.insert(
+        "headwind.sh/latency-query".to_string(),
+        "histogram_quantile(0.99, sum by (le) (rate(http_request_duration_seconds_bucket[2m])))"
+            .to_string(),
+    );
+
+    let config = AutoRollbackConfig::from_annotations(&annotations);
+
+    assert_eq!(config.metric_gates.len(), 1);
+    assert_eq!(config.metric_gates[0].name, "error rate");
+    assert_eq!(config.metric_gates[0].max, 0.05);
+
+    annotations.insert("headwind.sh/max-latency".to_string(), "0.5".to_string());
+    let config = AutoRollbackConfig::from_annotations(&annotations);
+    assert_eq!(config.metric_gates.len(), 2);
+    assert_eq!(config.metric_gates[1].name, "latency");
+    assert_eq!(config.metric_gates[1].max, 0.5);
+}
@@ -107,6 +107,7 @@ fn test_image_push_event_full_image_name() {
         tag: "v1.2.3".to_string(),
         digest: Some("sha256:abc123".to_string()),
         registry: "docker.io".to_string(),
+        source: None,
     };
 
     // Docker Hub should omit registry in output
@@ -118,6 +119,7 @@ fn test_image_push_event_full_image_name() {
         tag: "v1.2.3".to_string(),
         digest: Some("sha256:abc123".to_string()),
         registry: "gcr.io".to_string(),
+        source: None,
     };
     assert_eq!(event2.full_image(), "gcr.io/myorg/myapp:v1.2.3");
 
@@ -127,6 +129,7 @@ fn test_image_push_event_full_image_name() {
         tag: "latest".to_string(),
         digest: None,
         registry: "".to_string(),
+        source: None,
     };
     assert_eq!(event3.full_image(), "library/nginx:latest");
 }